@@ -1,13 +1,9 @@
 use clap::{Parser, Subcommand};
 use hadoop::{
-    common::{
-        conf::Configuration,
-        fs::{common_configuration_keys_public, FileSystem, Path},
-    },
-    hdfs::fs::file_system,
+    common::{conf, fs::FsShell, util::GenericOptionsParser},
+    hdfs::hdfs::HdfsConfiguration,
 };
-use iref::{Iri, IriRefBuf};
-use std::str::FromStr;
+use std::{env, io, path, process};
 
 #[derive(Parser)]
 #[command(version)]
@@ -18,42 +14,52 @@ struct Cli {
 
 #[derive(Subcommand)]
 enum Commands {
-    #[command(subcommand)]
-    Dfs(DfsCommands),
-}
-
-#[derive(Subcommand)]
-enum DfsCommands {
-    #[command(name = "-mkdir")]
-    Mkdir {
-        #[arg(short, default_value_t = false)]
-        p: bool,
-        paths: Vec<String>,
+    /// run a filesystem command on the file systems supported in Hadoop.
+    Dfs {
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<String>,
     },
 }
 
 fn main() -> anyhow::Result<()> {
-    let cli = Cli::parse();
-    let fs = get_file_system()?;
-    match &cli.command {
-        Commands::Dfs(dfs_command) => match dfs_command {
-            DfsCommands::Mkdir { p: _, paths } => {
-                paths.iter().for_each(|path| _ = mkdir(&fs, path));
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn")).init();
+    let mut args: Vec<String> = env::args().collect();
+    if args.get(1).map(String::as_str) == Some("--config") {
+        let conf_dir = match args.get(2) {
+            Some(conf_dir) if path::Path::new(conf_dir).is_dir() => conf_dir.to_owned(),
+            Some(conf_dir) => {
+                eprintln!(
+                    "ERROR: Cannot find configuration directory \"{}\"",
+                    conf_dir
+                );
+                process::exit(1);
+            }
+            None => {
+                eprintln!("ERROR: No parameter provided for --config");
+                process::exit(1);
             }
-        },
+        };
+        env::set_var(conf::HADOOP_CONF_DIR, conf_dir);
+        args.drain(1..3);
     }
-    Ok(())
-}
 
-fn get_file_system() -> anyhow::Result<impl FileSystem> {
-    let conf = Configuration {};
-    let uri = Iri::new(conf.get_trimmed_with_default(
-        common_configuration_keys_public::FS_DEFAULT_NAME_KEY,
-        "hdfs://localhost:9000",
-    ))?;
-    file_system::get(&uri, &conf)
-}
+    // generic options follow the command name, as in `hdfs dfs -D k=v -ls /`
+    let mut conf = HdfsConfiguration::new();
+    if args.len() > 2 {
+        let parser = match GenericOptionsParser::new(&mut conf, &args[2..]) {
+            Ok(parser) => parser,
+            Err(e) => {
+                eprintln!("{}", e);
+                GenericOptionsParser::print_generic_command_usage(&mut io::stderr())?;
+                process::exit(255);
+            }
+        };
+        args.truncate(2);
+        args.extend_from_slice(parser.get_remaining_args());
+    }
 
-fn mkdir(fs: &impl FileSystem, path: &str) -> anyhow::Result<bool> {
-    fs.mkdirs(&Path::from(IriRefBuf::from_str(path)?), None)
+    let cli = Cli::parse_from(args);
+    match cli.command {
+        Commands::Dfs { args } => process::exit(FsShell::new(conf).run(&args)),
+    }
 }
@@ -1,7 +1,316 @@
-#[derive(Clone, Copy)]
-pub struct Configuration {}
+use super::{
+    deprecation::{DeprecationDelta, DEPRECATION_CONTEXT},
+    IntegerRanges, StorageSize, StorageUnit, TimeUnit,
+};
+use crate::common::{net::NetUtils, util::string_utils::TraditionalBinaryPrefix};
+use anyhow::Error;
+use once_cell::sync::{Lazy, OnceCell};
+use roxmltree::{Document, Node, ParsingOptions};
+use std::{
+    collections::{HashMap, HashSet},
+    env,
+    fmt::Display,
+    fs,
+    io::Read,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Mutex,
+};
+
+/// Environment variable naming the directory holding the site configuration.
+pub const HADOOP_CONF_DIR: &str = "HADOOP_CONF_DIR";
+/// Environment variable naming the Hadoop installation directory, whose
+/// `etc/hadoop` sub-directory is searched when [`HADOOP_CONF_DIR`] is unset.
+pub const HADOOP_HOME: &str = "HADOOP_HOME";
+
+const XINCLUDE_NS: &str = "http://www.w3.org/2001/XInclude";
+
+const MAX_SUBST: usize = 20;
+
+/// List of default Resources. Resources are loaded in the order of the list
+/// entries
+static DEFAULT_RESOURCES: Lazy<Mutex<Vec<String>>> = Lazy::new(|| {
+    Mutex::new(vec![
+        "core-default.xml".to_string(),
+        "core-site.xml".to_string(),
+    ])
+});
+
+/// A configuration resource, i.e. an XML document with `<property>` entries.
+#[derive(Clone)]
+enum Resource {
+    /// A resource looked up by name in the configuration directories.
+    Name(String),
+    /// A file on the local filesystem.
+    Path(PathBuf),
+    /// An XML document which has already been read from a stream.
+    Stream { name: String, content: String },
+}
+
+/// The properties loaded from the resources, along with the names of the
+/// parameters which were marked as final by a resource.
+#[derive(Clone, Default)]
+struct Properties {
+    values: HashMap<String, String>,
+    final_parameters: HashSet<String>,
+}
+
+/// Provides access to configuration parameters.
+///
+/// Configurations are specified by resources. A resource contains a set of
+/// name/value pairs as XML data. Each resource is named by either a `&str` or
+/// by a path. If named by a `&str`, then the configuration directories are
+/// examined for a file with that name. If named by a path, then the local
+/// filesystem is examined directly.
+///
+/// Unless explicitly turned off, Hadoop by default specifies two resources,
+/// loaded in-order from the configuration directory:
+/// 1. `core-default.xml`: Read-only defaults for hadoop.
+/// 2. `core-site.xml`: Site-specific configuration for a given hadoop
+///    installation.
+///
+/// The configuration directory is `$HADOOP_CONF_DIR`, falling back to
+/// `$HADOOP_HOME/etc/hadoop`. Applications may add additional resources,
+/// which are loaded subsequent to these resources in the order they are added.
+///
+/// Configuration parameters may be declared _final_. Once a resource declares
+/// a value final, no subsequently-loaded resource can alter that value.
+/// Values set at runtime through [`Configuration::set`] are kept across
+/// reloads and take precedence over every resource.
+///
+/// Value strings are first processed for _variable expansion_. The available
+/// properties are, in order of precedence:
+/// 1. Environment variables, referenced as `${env.NAME}`, optionally with a
+///    default as `${env.NAME:-default}` (used when unset or empty) or
+///    `${env.NAME-default}` (used when unset);
+/// 2. The system properties `user.name`, `user.home`, `user.dir`,
+///    `java.io.tmpdir`, `os.name`, `os.arch`, `file.separator`,
+///    `path.separator` and `line.separator`; and, if a name is undefined
+///    there,
+/// 3. Other properties defined in this Configuration.
+///
+/// A reference which cannot be resolved is left as is. Self-referential
+/// values are returned unexpanded.
+///
+/// Keys may be deprecated in favor of one or more new keys, see
+/// [`Configuration::add_deprecations`]. Reading or writing a deprecated key
+/// logs a warning once and is redirected to the new keys.
+#[derive(Clone)]
+pub struct Configuration {
+    resources: Vec<Resource>,
+    // Whether to load the default resources
+    load_defaults: bool,
+    // Lazily loaded from the resources
+    properties: OnceCell<Properties>,
+    // Stores the mapping of keys to values set at runtime
+    overlay: HashMap<String, String>,
+}
+
+impl Default for Configuration {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl Configuration {
+    /// A new configuration.
+    pub fn new() -> Self {
+        Self::with_load_defaults(true)
+    }
+
+    /// A new configuration where the behavior of reading from the default
+    /// resources can be turned off.
+    ///
+    /// If `load_defaults` is false, the new instance will not load resources
+    /// from the default files.
+    pub fn with_load_defaults(load_defaults: bool) -> Self {
+        Self {
+            resources: vec![],
+            load_defaults,
+            properties: OnceCell::new(),
+            overlay: HashMap::new(),
+        }
+    }
+
+    /// Add a default resource. Resources are loaded in the order of the
+    /// resources added.
+    pub fn add_default_resource(name: &str) {
+        let mut default_resources = DEFAULT_RESOURCES.lock().unwrap();
+        if !default_resources.iter().any(|r| r == name) {
+            default_resources.push(name.to_owned());
+        }
+    }
+
+    /// Add a configuration resource.
+    ///
+    /// The properties of this resource will override properties of previously
+    /// added resources, unless they were marked final.
+    ///
+    /// `name` is the resource to be added, the configuration directories are
+    /// examined for a file with that name.
+    pub fn add_resource(&mut self, name: &str) {
+        self.add_resource_object(Resource::Name(name.to_owned()));
+    }
+
+    /// Add a configuration resource.
+    ///
+    /// The properties of this file will override properties of previously
+    /// added resources, unless they were marked final.
+    ///
+    /// `file` is the file to be added, the local filesystem is examined
+    /// directly to find the resource, without referring to the configuration
+    /// directories. It fails if the file does not exist.
+    pub fn add_resource_path(&mut self, file: impl AsRef<Path>) -> anyhow::Result<()> {
+        let file = file.as_ref();
+        if !file.is_file() {
+            return Err(Error::msg(format!("{} not found", file.display())));
+        }
+        self.add_resource_object(Resource::Path(file.to_owned()));
+        Ok(())
+    }
+
+    /// Add a configuration resource.
+    ///
+    /// The properties of this resource will override properties of previously
+    /// added resources, unless they were marked final.
+    ///
+    /// `input` is read fully when added, `name` is only used in messages.
+    pub fn add_resource_stream(&mut self, mut input: impl Read, name: &str) -> anyhow::Result<()> {
+        let mut content = String::new();
+        input.read_to_string(&mut content)?;
+        self.add_resource_object(Resource::Stream {
+            name: name.to_owned(),
+            content,
+        });
+        Ok(())
+    }
+
+    /// Adds a set of deprecated keys to the global deprecations.
+    ///
+    /// It is a no-op for keys which are already deprecated.
+    pub fn add_deprecations(deltas: &[DeprecationDelta]) {
+        DEPRECATION_CONTEXT
+            .write()
+            .unwrap()
+            .add_deprecations(deltas);
+    }
+
+    /// Adds the deprecated key to the global deprecation map when no custom
+    /// message is provided.
+    pub fn add_deprecation(key: &str, new_key: &str) {
+        Self::add_deprecations(&[DeprecationDelta::new(key, new_key)]);
+    }
+
+    /// Checks whether the given `key` is deprecated.
+    pub fn is_deprecated(key: &str) -> bool {
+        DEPRECATION_CONTEXT
+            .read()
+            .unwrap()
+            .get_deprecated_key_info(key)
+            .is_some()
+    }
+
+    /// Checks for the presence of the property `name` in the deprecation map.
+    /// Returns the list of new keys if present in the deprecation map or the
+    /// `name` itself, logging a warning the first time a deprecated key is used.
+    fn handle_deprecation(name: &str) -> Vec<String> {
+        let name = name.trim();
+        let context = DEPRECATION_CONTEXT.read().unwrap();
+        match context.get_deprecated_key_info(name) {
+            Some(key_info) => {
+                key_info.log_deprecation_once(name);
+                key_info.new_keys.to_owned()
+            }
+            None => vec![name.to_owned()],
+        }
+    }
+
+    /// Returns the other keys superseding the same deprecated key as the
+    /// given non-deprecated `name`.
+    fn get_alternative_names(name: &str) -> Vec<String> {
+        let context = DEPRECATION_CONTEXT.read().unwrap();
+        context
+            .get_deprecated_key(name)
+            .and_then(|key| context.get_deprecated_key_info(key))
+            .map(|key_info| {
+                key_info
+                    .new_keys
+                    .iter()
+                    .filter(|n| *n != name)
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Attempts to repeatedly expand the value `expr` by replacing the
+    /// left-most substring of the form `${var}` in the following precedence
+    /// order:
+    /// 1. by the value of the environment variable `NAME` if `var` is of the
+    ///    form `env.NAME`
+    /// 2. by the value of the system property `var` if any
+    /// 3. by the value of the configuration key `var` if any
+    ///
+    /// If `var` is unbounded the current state of expansion "prefix${var}suffix"
+    /// is returned.
+    ///
+    /// If a cycle is detected: replacing `var1` requires replacing `var1`
+    /// possibly transitively, the original `expr` is returned, as it is when
+    /// the expansion is nested too deep.
+    fn substitute_vars(&self, expr: &str) -> String {
+        let mut eval = expr.to_owned();
+        for _ in 0..MAX_SUBST {
+            let Some((start, end)) = find_sub_variable(&eval) else {
+                return eval;
+            };
+            let var = &eval[start..end];
+            let val = match var.strip_prefix("env.").filter(|v| !v.is_empty()) {
+                Some(name) => get_env(name),
+                None => get_system_property(var),
+            }
+            .or_else(|| self.get_raw(var));
+            let Some(val) = val else {
+                // return literal ${var}: var is unbound
+                return eval;
+            };
+
+            let dollar = start - "${".len();
+            let after_right_brace = end + "}".len();
+            let ref_var = &eval[dollar..after_right_brace];
+
+            // detect self-referential values
+            if val.contains(ref_var) {
+                // return original expression if there is a loop
+                return expr.to_owned();
+            }
+
+            // substitute
+            eval = format!("{}{}{}", &eval[..dollar], val, &eval[after_right_brace..]);
+        }
+        log::error!(
+            "Variable substitution depth too large: {} {}",
+            MAX_SUBST,
+            expr
+        );
+        expr.to_owned()
+    }
+
+    fn add_resource_object(&mut self, resource: Resource) {
+        self.resources.push(resource);
+        self.reload_configuration();
+    }
+
+    /// Reload configuration from previously added resources.
+    ///
+    /// This method will clear all the configuration read from the added
+    /// resources, and final parameters. This will make the resources to
+    /// be read again before accessing the values. Values that are added
+    /// via set methods will overlay values read from the resources.
+    pub fn reload_configuration(&mut self) {
+        self.properties = OnceCell::new();
+    }
+
     /// Get the value of the `name` property, `None` if
     /// no such property exists. If the key is deprecated, it returns the value of
     /// the first key which replaces the deprecated key and is not `None`.
@@ -11,39 +320,55 @@ impl Configuration {
     ///
     /// As a side effect get loads the properties from the sources if called for
     /// the first time as a lazy init.
-    pub fn get<'a>(&self, _name: &str, default_value: Option<&'a str>) -> Option<&'a str> {
-        // TODO
-        None.or(default_value)
+    pub fn get(&self, name: &str, default_value: Option<&str>) -> Option<String> {
+        self.get_raw(name)
+            .or_else(|| default_value.map(str::to_owned))
+            .map(|value| self.substitute_vars(&value))
+    }
+
+    /// Get the value of the `name` property, without doing
+    /// variable expansion. If the key is deprecated,
+    /// it returns the value of the first key which replaces
+    /// the deprecated key and is not `None`.
+    pub fn get_raw(&self, name: &str) -> Option<String> {
+        let props = self.get_props();
+        Self::handle_deprecation(name)
+            .iter()
+            .find_map(|n| props.values.get(n).cloned())
     }
 
-    /// Get the value of the `name` property as a trimmed `&str`,
+    /// Get the value of the `name` property as a trimmed `String`,
     /// `None` if no such property exists.
     /// If the key is deprecated, it returns the value of
     /// the first key which replaces the deprecated key and is not `None`
     ///
     /// Values are processed for [`variable expansion`]
     /// before being returned.
-    pub fn get_trimmed<'a>(&self, name: &str) -> Option<&'a str> {
-        self.get(name, None).map(|v| v.trim())
+    pub fn get_trimmed(&self, name: &str) -> Option<String> {
+        self.get(name, None).map(|v| v.trim().to_owned())
     }
 
-    /// Get the value of the `name` property as a trimmed `&str`,
+    /// Get the value of the `name` property as a trimmed `String`,
     /// `default_value` if no such property exists.
     /// See [`Configuration::get_trimmed`] for more details.
-    pub fn get_trimmed_with_default<'a>(&self, name: &str, default_value: &'a str) -> &'a str {
-        self.get_trimmed(name).unwrap_or(default_value)
+    pub fn get_trimmed_with_default(&self, name: &str, default_value: &str) -> String {
+        self.get_trimmed(name)
+            .unwrap_or_else(|| default_value.to_owned())
     }
 
     /// Get the value of the `name` property as an `i32`.
     /// If no such property exists, the provided default value is returned,
     /// or if the specified value is not a valid `i32`,
     /// then an error is thrown.
+    ///
+    /// Values prefixed with `0x` or `0X` are parsed as hexadecimal.
     pub fn get_int(&self, name: &str, default_value: i32) -> anyhow::Result<i32> {
-        if let Some(value_string) = self.get_trimmed(name) {
-            Ok(i32::from_str_radix(value_string, 16)
-                .or_else(|_| i32::from_str_radix(value_string, 10))?)
-        } else {
-            Ok(default_value)
+        match self.get_trimmed(name) {
+            Some(value_string) => match get_hex_digits(&value_string) {
+                Some(hex_string) => Ok(i32::from_str_radix(&hex_string, 16)?),
+                None => Ok(value_string.parse()?),
+            },
+            None => Ok(default_value),
         }
     }
 
@@ -51,12 +376,50 @@ impl Configuration {
     /// If no such property exists, the provided default value is returned,
     /// or if the specified value is not a valid `i64`,
     /// then an error is thrown.
+    ///
+    /// Values prefixed with `0x` or `0X` are parsed as hexadecimal.
     pub fn get_long(&self, name: &str, default_value: i64) -> anyhow::Result<i64> {
-        if let Some(value_string) = self.get_trimmed(name) {
-            Ok(i64::from_str_radix(value_string, 16)
-                .or_else(|_| i64::from_str_radix(value_string, 10))?)
-        } else {
-            Ok(default_value)
+        match self.get_trimmed(name) {
+            Some(value_string) => match get_hex_digits(&value_string) {
+                Some(hex_string) => Ok(i64::from_str_radix(&hex_string, 16)?),
+                None => Ok(value_string.parse()?),
+            },
+            None => Ok(default_value),
+        }
+    }
+
+    /// Get the value of the `name` property as an `i64` or
+    /// human readable format. If no such property exists, the provided default
+    /// value is returned, or if the specified value is not a valid
+    /// `i64` or human readable format, then an error is thrown. You
+    /// can use the following suffix (case insensitive): k(kilo), m(mega), g(giga),
+    /// t(tera), p(peta), e(exa)
+    pub fn get_long_bytes(&self, name: &str, default_value: i64) -> anyhow::Result<i64> {
+        match self.get_trimmed(name) {
+            Some(value_string) => TraditionalBinaryPrefix::string2long(&value_string),
+            None => Ok(default_value),
+        }
+    }
+
+    /// Get the value of the `name` property as an `f32`.
+    /// If no such property exists, the provided default value is returned,
+    /// or if the specified value is not a valid `f32`,
+    /// then an error is thrown.
+    pub fn get_float(&self, name: &str, default_value: f32) -> anyhow::Result<f32> {
+        match self.get_trimmed(name) {
+            Some(value_string) => Ok(value_string.parse()?),
+            None => Ok(default_value),
+        }
+    }
+
+    /// Get the value of the `name` property as an `f64`.
+    /// If no such property exists, the provided default value is returned,
+    /// or if the specified value is not a valid `f64`,
+    /// then an error is thrown.
+    pub fn get_double(&self, name: &str, default_value: f64) -> anyhow::Result<f64> {
+        match self.get_trimmed(name) {
+            Some(value_string) => Ok(value_string.parse()?),
+            None => Ok(default_value),
         }
     }
 
@@ -70,4 +433,883 @@ impl Configuration {
             _ => default_value,
         }
     }
+
+    /// Return value matching this enumerated type.
+    /// Note that the returned value is trimmed by this method.
+    /// Returns `default_value` if the property is unset, or an error if the
+    /// value does not parse as `T`.
+    pub fn get_enum<T>(&self, name: &str, default_value: T) -> anyhow::Result<T>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        match self.get_trimmed(name) {
+            Some(val) => val
+                .parse()
+                .map_err(|e| Error::msg(format!("Invalid value for {}: '{}' ({})", name, val, e))),
+            None => Ok(default_value),
+        }
+    }
+
+    /// Return time duration in the given time unit. Valid units are encoded in
+    /// properties as suffixes: nanoseconds (ns), microseconds (us), milliseconds
+    /// (ms), seconds (s), minutes (m), hours (h), and days (d). If no unit is
+    /// provided, the default unit is applied.
+    pub fn get_time_duration(
+        &self,
+        name: &str,
+        default_value: i64,
+        unit: TimeUnit,
+    ) -> anyhow::Result<i64> {
+        self.get_time_duration_with_return_unit(name, default_value, unit, unit)
+    }
+
+    /// Return time duration in the given `return_unit`. The `default_value` and
+    /// values without a suffix are interpreted in `unit`.
+    /// See [`Configuration::get_time_duration`] for the accepted suffixes.
+    pub fn get_time_duration_with_return_unit(
+        &self,
+        name: &str,
+        default_value: i64,
+        unit: TimeUnit,
+        return_unit: TimeUnit,
+    ) -> anyhow::Result<i64> {
+        match self.get_trimmed(name) {
+            Some(v_str) => Self::get_time_duration_helper(name, &v_str, unit, return_unit),
+            None => Ok(return_unit.convert(default_value, unit)),
+        }
+    }
+
+    /// Return time duration in the given time unit, with `default_value`
+    /// following the same syntax as the property values.
+    pub fn get_time_duration_with_str_default(
+        &self,
+        name: &str,
+        default_value: &str,
+        unit: TimeUnit,
+    ) -> anyhow::Result<i64> {
+        let v_str = self.get_trimmed_with_default(name, default_value);
+        Self::get_time_duration_helper(name, &v_str, unit, unit)
+    }
+
+    fn get_time_duration_helper(
+        name: &str,
+        v_str: &str,
+        unit: TimeUnit,
+        return_unit: TimeUnit,
+    ) -> anyhow::Result<i64> {
+        let v_str = v_str.trim().to_lowercase();
+        let (raw, v_unit) = match TimeUnit::unit_for(&v_str) {
+            Some(v_unit) => (&v_str[..v_str.len() - v_unit.suffix().len()], v_unit),
+            None => {
+                log::info!("No unit for {}({}) assuming {}", name, v_str, unit);
+                (v_str.as_str(), unit)
+            }
+        };
+        let raw: i64 = raw
+            .trim()
+            .parse()
+            .map_err(|_| Error::msg(format!("Invalid time duration for {}: '{}'", name, v_str)))?;
+        let converted = return_unit.convert(raw, v_unit);
+        if v_unit.convert(converted, return_unit) < raw {
+            log::info!(
+                "Possible loss of precision converting {} to {} for {}",
+                v_str,
+                return_unit,
+                name
+            );
+        }
+        Ok(converted)
+    }
+
+    /// Set the value of `name` to the given time duration. This is equivalent
+    /// to `set(name, value + suffix)`.
+    pub fn set_time_duration(&mut self, name: &str, value: i64, unit: TimeUnit) {
+        self.set(name, &format!("{}{}", value, unit.suffix()));
+    }
+
+    /// Gets the Storage Size from the config, or returns the `default_value`.
+    /// The unit of return value is specified in `target_unit`.
+    ///
+    /// Values are written as a number followed by a unit, such as `100mb`,
+    /// `1g` or `10 kilobytes`; the unit is case insensitive.
+    pub fn get_storage_size(
+        &self,
+        name: &str,
+        default_value: &str,
+        target_unit: StorageUnit,
+    ) -> anyhow::Result<f64> {
+        let v_string = self
+            .get(name, None)
+            .filter(|v| !v.trim().is_empty())
+            .unwrap_or_else(|| default_value.to_owned());
+        // Please note: There is a bit of subtlety here. If the user specifies
+        // the default unit as "1GB", but the requested unit is MB, we will return
+        // the format in MB even though the default string is specified in GB.
+        let measure = StorageSize::parse(&v_string)?;
+        Ok(target_unit.from_bytes(measure.get_unit().to_bytes(measure.get_value())))
+    }
+
+    /// Sets Storage Size for the specified key.
+    pub fn set_storage_size(&mut self, name: &str, value: f64, unit: StorageUnit) {
+        self.set(name, &format!("{}{}", value, unit.get_short_name()));
+    }
+
+    /// Parse the given attribute as a set of integer ranges, such as
+    /// `2-3,5,7-`. See [`IntegerRanges`] for the accepted syntax.
+    pub fn get_range(&self, name: &str, default_value: &str) -> anyhow::Result<IntegerRanges> {
+        IntegerRanges::new(&self.get(name, Some(default_value)).unwrap_or_default())
+    }
+
+    /// Get the comma delimited values of the `name` property as
+    /// a collection of `String`s. Empty values are dropped and the values
+    /// are not trimmed. If no such property is specified then an empty
+    /// collection is returned.
+    pub fn get_string_collection(&self, name: &str) -> Vec<String> {
+        self.get(name, None)
+            .map(|value| {
+                value
+                    .split(',')
+                    .filter(|s| !s.is_empty())
+                    .map(str::to_owned)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Get the comma delimited values of the `name` property as
+    /// an array of `String`s. If no such property is specified, or the value
+    /// holds no values, then `None` is returned.
+    pub fn get_strings(&self, name: &str) -> Option<Vec<String>> {
+        Some(self.get_string_collection(name)).filter(|values| !values.is_empty())
+    }
+
+    /// Get the comma delimited values of the `name` property as
+    /// an array of `String`s. If no such property is specified then default
+    /// value is returned.
+    pub fn get_strings_with_default(&self, name: &str, default_value: &[&str]) -> Vec<String> {
+        match self.get(name, None) {
+            Some(_) => self.get_strings(name).unwrap_or_default(),
+            None => default_value.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    /// Get the comma delimited values of the `name` property as
+    /// an array of `String`s, trimmed of the leading and trailing whitespace.
+    /// Values may also be separated by newlines. If no such property is
+    /// specified then an empty array is returned.
+    pub fn get_trimmed_strings(&self, name: &str) -> Vec<String> {
+        self.get(name, None)
+            .map(|value| get_trimmed_strings(&value))
+            .unwrap_or_default()
+    }
+
+    /// Get the comma delimited values of the `name` property as
+    /// an array of `String`s, trimmed of the leading and trailing whitespace.
+    /// If no such property is specified then default value is returned.
+    pub fn get_trimmed_strings_with_default(
+        &self,
+        name: &str,
+        default_value: &[&str],
+    ) -> Vec<String> {
+        match self.get(name, None) {
+            Some(value) => get_trimmed_strings(&value),
+            None => default_value.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    /// Set the array of string values for the `name` property as
+    /// as comma delimited values.
+    pub fn set_strings(&mut self, name: &str, values: &[&str]) {
+        self.set(name, &values.join(","));
+    }
+
+    /// Get the socket address for `name` property as a [`SocketAddr`].
+    /// The address may be `host`, `host:port` or a URI with an authority;
+    /// `default_port` is used when the value has no port.
+    pub fn get_socket_addr(
+        &self,
+        name: &str,
+        default_address: &str,
+        default_port: i32,
+    ) -> anyhow::Result<SocketAddr> {
+        let address = self.get_trimmed_with_default(name, default_address);
+        NetUtils::create_socket_addr(&address, default_port, Some(name))
+    }
+
+    /// Set the `value` of the `name` property. If `name` is deprecated,
+    /// it also sets the `value` to the keys that replace the deprecated key.
+    ///
+    /// Values set at runtime take precedence over the resources, including
+    /// parameters marked final, and survive [`Configuration::reload_configuration`].
+    pub fn set(&mut self, name: &str, value: &str) {
+        let name = name.trim();
+        let mut names = vec![name.to_owned()];
+        if Self::is_deprecated(name) {
+            names.append(&mut Self::handle_deprecation(name));
+        } else {
+            names.append(&mut Self::get_alternative_names(name));
+        }
+        for n in names {
+            self.overlay.insert(n.to_owned(), value.to_owned());
+            self.get_props_mut().values.insert(n, value.to_owned());
+        }
+    }
+
+    /// Sets a property if it is currently unset.
+    pub fn set_if_unset(&mut self, name: &str, value: &str) {
+        if self.get(name, None).is_none() {
+            self.set(name, value);
+        }
+    }
+
+    /// Unset a previously set property.
+    pub fn unset(&mut self, name: &str) {
+        let name = name.trim();
+        let mut names = vec![name.to_owned()];
+        if Self::is_deprecated(name) {
+            names.append(&mut Self::handle_deprecation(name));
+        } else {
+            names.append(&mut Self::get_alternative_names(name));
+        }
+        for n in names {
+            self.overlay.remove(&n);
+            self.get_props_mut().values.remove(&n);
+        }
+    }
+
+    /// Set the value of the `name` property to an `i32`.
+    pub fn set_int(&mut self, name: &str, value: i32) {
+        self.set(name, &value.to_string());
+    }
+
+    /// Set the value of the `name` property to an `i64`.
+    pub fn set_long(&mut self, name: &str, value: i64) {
+        self.set(name, &value.to_string());
+    }
+
+    /// Set the value of the `name` property to a `bool`.
+    pub fn set_bool(&mut self, name: &str, value: bool) {
+        self.set(name, &value.to_string());
+    }
+
+    /// Return the number of keys in the configuration.
+    pub fn size(&self) -> usize {
+        self.get_props().values.len()
+    }
+
+    /// Get the path of the resource with the given `name` in the
+    /// configuration directories, `None` if it cannot be found.
+    pub fn get_resource(name: &str) -> Option<PathBuf> {
+        Self::get_conf_dirs()
+            .into_iter()
+            .map(|dir| dir.join(name))
+            .find(|path| path.is_file())
+    }
+
+    fn get_conf_dirs() -> Vec<PathBuf> {
+        let mut dirs = vec![];
+        if let Some(conf_dir) = env::var_os(HADOOP_CONF_DIR).filter(|d| !d.is_empty()) {
+            dirs.push(PathBuf::from(conf_dir));
+        }
+        if let Some(home) = env::var_os(HADOOP_HOME).filter(|h| !h.is_empty()) {
+            dirs.push(PathBuf::from(home).join("etc").join("hadoop"));
+        }
+        dirs
+    }
+
+    fn get_props(&self) -> &Properties {
+        self.properties.get_or_init(|| self.load_resources())
+    }
+
+    fn get_props_mut(&mut self) -> &mut Properties {
+        self.get_props();
+        self.properties.get_mut().unwrap()
+    }
+
+    fn load_resources(&self) -> Properties {
+        let mut properties = Properties::default();
+        if self.load_defaults {
+            let default_resources = DEFAULT_RESOURCES.lock().unwrap().clone();
+            for name in default_resources {
+                Self::load_resource(&mut properties, &Resource::Name(name));
+            }
+        }
+        for resource in &self.resources {
+            Self::load_resource(&mut properties, resource);
+        }
+        for (name, value) in &self.overlay {
+            properties.values.insert(name.to_owned(), value.to_owned());
+        }
+        properties
+    }
+
+    /// Load a single resource. Named resources missing from the configuration
+    /// directories are skipped, other resources which cannot be read or are
+    /// malformed are reported and skipped.
+    fn load_resource(properties: &mut Properties, resource: &Resource) {
+        let result = match resource {
+            Resource::Name(name) => match Self::get_resource(name) {
+                Some(path) => Self::load_file(properties, &path),
+                None => Ok(()),
+            },
+            Resource::Path(path) => Self::load_file(properties, path),
+            Resource::Stream { name, content } => Self::parse(properties, content, name, None),
+        };
+        if let Err(e) = result {
+            log::error!(
+                "error parsing conf {}: {}",
+                Self::resource_name(resource),
+                e
+            );
+        }
+    }
+
+    fn resource_name(resource: &Resource) -> String {
+        match resource {
+            Resource::Name(name) => name.to_owned(),
+            Resource::Path(path) => path.display().to_string(),
+            Resource::Stream { name, .. } => name.to_owned(),
+        }
+    }
+
+    fn load_file(properties: &mut Properties, path: &Path) -> anyhow::Result<()> {
+        let content = fs::read_to_string(path)?;
+        Self::parse(
+            properties,
+            &content,
+            &path.display().to_string(),
+            path.parent(),
+        )
+    }
+
+    fn parse(
+        properties: &mut Properties,
+        content: &str,
+        name: &str,
+        base_dir: Option<&Path>,
+    ) -> anyhow::Result<()> {
+        let options = ParsingOptions {
+            allow_dtd: true,
+            ..ParsingOptions::default()
+        };
+        let doc = Document::parse_with_options(content, options)?;
+        Self::parse_elements(properties, doc.root_element(), name, base_dir)
+    }
+
+    fn parse_elements(
+        properties: &mut Properties,
+        parent: Node,
+        name: &str,
+        base_dir: Option<&Path>,
+    ) -> anyhow::Result<()> {
+        for node in parent.children().filter(Node::is_element) {
+            match node.tag_name().name() {
+                "property" => Self::parse_property(properties, node, name),
+                "configuration" => Self::parse_elements(properties, node, name, base_dir)?,
+                "include" if node.tag_name().namespace() == Some(XINCLUDE_NS) => {
+                    Self::parse_include(properties, node, name, base_dir)?
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn parse_property(properties: &mut Properties, node: Node, resource: &str) {
+        let field = |field: &str| {
+            node.attribute(field).map(str::to_owned).or_else(|| {
+                node.children()
+                    .find(|c| c.is_element() && c.tag_name().name() == field)
+                    .map(|c| c.text().unwrap_or_default().to_owned())
+            })
+        };
+        let Some(attr) = field("name").map(|n| n.trim().to_owned()) else {
+            return;
+        };
+        let Some(value) = field("value") else {
+            return;
+        };
+        let final_parameter = field("final").is_some_and(|f| f.trim() == "true");
+        let new_keys = DEPRECATION_CONTEXT
+            .read()
+            .unwrap()
+            .get_deprecated_key_info(&attr)
+            .map(|key_info| key_info.new_keys.to_owned());
+        match new_keys {
+            // update new keys with deprecated key's value
+            Some(new_keys) => new_keys.iter().for_each(|key| {
+                Self::load_property(properties, resource, key, &value, final_parameter)
+            }),
+            None => Self::load_property(properties, resource, &attr, &value, final_parameter),
+        }
+    }
+
+    fn parse_include(
+        properties: &mut Properties,
+        node: Node,
+        name: &str,
+        base_dir: Option<&Path>,
+    ) -> anyhow::Result<()> {
+        let href = node
+            .attribute("href")
+            .ok_or(Error::msg(format!("include without href in '{}'", name)))?;
+        let path = base_dir.map_or(PathBuf::from(href), |dir| dir.join(href));
+        if path.is_file() {
+            return Self::load_file(properties, &path);
+        }
+        let has_fallback = node
+            .children()
+            .any(|c| c.is_element() && c.tag_name().name() == "fallback");
+        if has_fallback {
+            Ok(())
+        } else {
+            Err(Error::msg(format!(
+                "Fetch fail on include for '{}' with no fallback while loading '{}'",
+                href, name
+            )))
+        }
+    }
+
+    fn load_property(
+        properties: &mut Properties,
+        name: &str,
+        attr: &str,
+        value: &str,
+        final_parameter: bool,
+    ) {
+        if properties.final_parameters.contains(attr) {
+            if properties.values.get(attr).map(String::as_str) != Some(value) {
+                log::warn!(
+                    "{}:an attempt to override final parameter: {};  Ignoring.",
+                    name,
+                    attr
+                );
+            }
+            return;
+        }
+        properties.values.insert(attr.to_owned(), value.to_owned());
+        if final_parameter {
+            properties.final_parameters.insert(attr.to_owned());
+        }
+    }
+}
+
+/// Find the bounds of the left-most `${var}` reference in `eval`, as the
+/// byte range of `var`. A `var` never contains `$`, `}` or spaces.
+fn find_sub_variable(eval: &str) -> Option<(usize, usize)> {
+    let bytes = eval.as_bytes();
+    // scanning for a brace first because it's less frequent than $
+    // that can occur in nested class names
+    let mut match_start = 1;
+    'match_loop: while let Some(left_brace) = bytes
+        .get(match_start..)
+        .and_then(|b| b.iter().position(|c| *c == b'{'))
+        .map(|p| p + match_start)
+    {
+        // right brace of a smallest valid expression "${c}"
+        if left_brace + "{c".len() >= bytes.len() {
+            break;
+        }
+        if bytes[left_brace - 1] != b'$' {
+            // not a start of a variable
+            match_start = left_brace + 1;
+            continue;
+        }
+        // after '{'
+        let sub_start = left_brace + 1;
+        let mut matched_len = 0;
+        for (i, c) in bytes.iter().enumerate().skip(sub_start) {
+            match c {
+                b'}' if matched_len > 0 => return Some((sub_start, sub_start + matched_len)),
+                b'}' | b' ' | b'$' => {
+                    match_start = i + 1;
+                    continue 'match_loop;
+                }
+                _ => matched_len += 1,
+            }
+        }
+        // scanned from "${" to the end of eval, and no reset via ' ', '$':
+        // no match!
+        break;
+    }
+    None
+}
+
+/// Return the hexadecimal digits of `value` when it is written as `0x...`,
+/// keeping a leading minus sign.
+fn get_hex_digits(value: &str) -> Option<String> {
+    let (negative, s) = match value.strip_prefix('-') {
+        Some(s) => (true, s),
+        None => (false, value),
+    };
+    let hex_string = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X"))?;
+    Some(if negative {
+        format!("-{}", hex_string)
+    } else {
+        hex_string.to_owned()
+    })
+}
+
+/// Split `value` on commas and newlines, trimming the surrounding whitespace
+/// of each value, as with the pattern `\s*[,\n]\s*`. Trailing empty values
+/// are dropped.
+fn get_trimmed_strings(value: &str) -> Vec<String> {
+    let bytes = value.trim().as_bytes();
+    if bytes.is_empty() {
+        return vec![];
+    }
+    let skip_whitespace = |mut i: usize| {
+        while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+            i += 1;
+        }
+        i
+    };
+    let mut values = vec![];
+    let (mut token_start, mut i) = (0, 0);
+    while i < bytes.len() {
+        if !bytes[i].is_ascii_whitespace() && bytes[i] != b',' {
+            i += 1;
+            continue;
+        }
+        // a separator starting at i ends at the comma following the
+        // whitespace, or else at the last newline within the whitespace
+        let j = skip_whitespace(i);
+        let separator = if bytes.get(j) == Some(&b',') {
+            Some(j)
+        } else {
+            bytes[i..j].iter().rposition(|c| *c == b'\n').map(|p| i + p)
+        };
+        match separator {
+            Some(p) => {
+                values.push(String::from_utf8_lossy(&bytes[token_start..i]).into_owned());
+                i = skip_whitespace(p + 1);
+                token_start = i;
+            }
+            None => i = j,
+        }
+    }
+    values.push(String::from_utf8_lossy(&bytes[token_start..]).into_owned());
+    while values.last().is_some_and(String::is_empty) {
+        values.pop();
+    }
+    values
+}
+
+/// Resolve `NAME`, `NAME:-default` or `NAME-default` against the environment.
+fn get_env(var: &str) -> Option<String> {
+    for (i, c) in var.char_indices() {
+        if c == ':' && var[i + 1..].starts_with('-') {
+            let value = env::var(&var[..i]).ok().filter(|v| !v.is_empty());
+            return value.or_else(|| Some(var[i + 2..].to_owned()));
+        } else if c == '-' {
+            return env::var(&var[..i])
+                .ok()
+                .or_else(|| Some(var[i + 1..].to_owned()));
+        }
+    }
+    env::var(var).ok()
+}
+
+/// The system properties which are commonly referenced from Hadoop
+/// configuration files.
+fn get_system_property(name: &str) -> Option<String> {
+    match name {
+        "user.name" => Some(whoami::username()),
+        "user.home" => env::var("HOME").ok(),
+        "user.dir" => env::current_dir().ok().map(|d| d.display().to_string()),
+        "java.io.tmpdir" => Some(env::temp_dir().display().to_string()),
+        "os.name" => Some(env::consts::OS.to_owned()),
+        "os.arch" => Some(env::consts::ARCH.to_owned()),
+        "file.separator" => Some(std::path::MAIN_SEPARATOR.to_string()),
+        "path.separator" => Some(if cfg!(windows) { ";" } else { ":" }.to_owned()),
+        "line.separator" => Some(if cfg!(windows) { "\r\n" } else { "\n" }.to_owned()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn conf_with(resources: &[&str]) -> Configuration {
+        let mut conf = Configuration::with_load_defaults(false);
+        for (i, resource) in resources.iter().enumerate() {
+            conf.add_resource_stream(resource.as_bytes(), &format!("test-{}.xml", i))
+                .unwrap();
+        }
+        conf
+    }
+
+    #[test]
+    fn test_parse_properties() {
+        let conf = conf_with(&[r#"<?xml version="1.0"?>
+            <?xml-stylesheet type="text/xsl" href="configuration.xsl"?>
+            <configuration>
+              <property><name> fs.defaultFS </name><value>hdfs://nn:8020</value></property>
+              <property name="ipc.client.tcpnodelay" value="false"/>
+              <property><name>empty.value</name><value></value></property>
+            </configuration>"#]);
+        assert_eq!(conf.get("fs.defaultFS", None).unwrap(), "hdfs://nn:8020");
+        assert!(!conf.get_bool("ipc.client.tcpnodelay", true));
+        assert_eq!(conf.get("empty.value", None).unwrap(), "");
+        assert_eq!(conf.get("missing", Some("default")).unwrap(), "default");
+        assert_eq!(conf.size(), 3);
+    }
+
+    #[test]
+    fn test_resource_precedence_and_final() {
+        let mut conf = conf_with(&[
+            r#"<configuration>
+              <property><name>a</name><value>1</value></property>
+              <property><name>b</name><value>1</value><final>true</final></property>
+            </configuration>"#,
+            r#"<configuration>
+              <property><name>a</name><value>2</value></property>
+              <property><name>b</name><value>2</value></property>
+            </configuration>"#,
+        ]);
+        assert_eq!(conf.get("a", None).unwrap(), "2");
+        assert_eq!(conf.get("b", None).unwrap(), "1");
+
+        // runtime values win over final parameters and survive reloads
+        conf.set("b", "3");
+        conf.add_resource_stream(
+            "<configuration><property><name>a</name><value>4</value></property></configuration>"
+                .as_bytes(),
+            "test-2.xml",
+        )
+        .unwrap();
+        assert_eq!(conf.get("a", None).unwrap(), "4");
+        assert_eq!(conf.get("b", None).unwrap(), "3");
+
+        conf.unset("b");
+        assert_eq!(conf.get("b", None), None);
+        conf.reload_configuration();
+        assert_eq!(conf.get("b", None).unwrap(), "1");
+
+        conf.set_if_unset("a", "5");
+        conf.set_if_unset("c", "5");
+        assert_eq!(conf.get("a", None).unwrap(), "4");
+        assert_eq!(conf.get_int("c", 0).unwrap(), 5);
+    }
+
+    #[test]
+    fn test_include() {
+        let dir = env::temp_dir().join(format!("hadoop-conf-{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("included.xml"),
+            "<configuration><property><name>x</name><value>y</value></property></configuration>",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("site.xml"),
+            r#"<configuration xmlns:xi="http://www.w3.org/2001/XInclude">
+              <xi:include href="included.xml"/>
+              <xi:include href="missing.xml"><xi:fallback/></xi:include>
+            </configuration>"#,
+        )
+        .unwrap();
+        let mut conf = Configuration::with_load_defaults(false);
+        conf.add_resource_path(dir.join("site.xml")).unwrap();
+        assert_eq!(conf.get("x", None).unwrap(), "y");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_missing_resource_path() {
+        let mut conf = Configuration::with_load_defaults(false);
+        let path = env::temp_dir().join(format!("hadoop-conf-{}.xml", Uuid::new_v4()));
+        let err = conf.add_resource_path(&path).unwrap_err();
+        assert_eq!(err.to_string(), format!("{} not found", path.display()));
+    }
+
+    #[test]
+    fn test_variable_expansion() {
+        // a variable no other test reads, removed once the test is done
+        let var = format!("HADOOP_CONF_TEST_{}", Uuid::new_v4().simple());
+        env::set_var(&var, "from-env");
+        let resource = r#"<configuration>
+              <property><name>base</name><value>/data</value></property>
+              <property><name>dir</name><value>${base}/${user.name}</value></property>
+              <property><name>env</name><value>${env.HADOOP_CONF_TEST_VAR}</value></property>
+              <property><name>env.default</name><value>${env.HADOOP_CONF_TEST_UNSET:-x}</value></property>
+              <property><name>unbound</name><value>a${not.defined}b</value></property>
+              <property><name>cycle</name><value>${cycle}!</value></property>
+            </configuration>"#
+            .replace("HADOOP_CONF_TEST_VAR", &var);
+        let mut conf = conf_with(&[&resource]);
+        assert_eq!(
+            conf.get("dir", None).unwrap(),
+            format!("/data/{}", whoami::username())
+        );
+        assert_eq!(conf.get_raw("dir").unwrap(), "${base}/${user.name}");
+        assert_eq!(conf.get("env", None).unwrap(), "from-env");
+        assert_eq!(conf.get("env.default", None).unwrap(), "x");
+        assert_eq!(conf.get("unbound", None).unwrap(), "a${not.defined}b");
+        assert_eq!(conf.get("cycle", None).unwrap(), "${cycle}!");
+        assert_eq!(conf.get("missing", Some("${base}")).unwrap(), "/data");
+
+        for i in 0..=MAX_SUBST {
+            conf.set(&format!("depth{}", i), &format!("${{depth{}}}", i + 1));
+        }
+        assert_eq!(conf.get("depth0", None).unwrap(), "${depth1}");
+        env::remove_var(&var);
+    }
+
+    #[test]
+    fn test_deprecated_keys() {
+        // the deprecations are global, so deprecate keys no other test uses
+        let id = Uuid::new_v4().simple().to_string();
+        let (old_key, new_key) = (format!("test.old.{}", id), format!("test.new.{}", id));
+        Configuration::add_deprecation(&old_key, &new_key);
+        assert!(Configuration::is_deprecated("fs.default.name"));
+
+        let resource = r#"<configuration>
+              <property><name>fs.default.name</name><value>hdfs://old:8020</value></property>
+              <property><name>test.old.key</name><value>1</value></property>
+            </configuration>"#
+            .replace("test.old.key", &old_key);
+        let mut conf = conf_with(&[&resource]);
+        assert_eq!(conf.get("fs.defaultFS", None).unwrap(), "hdfs://old:8020");
+        assert_eq!(
+            conf.get("fs.default.name", None).unwrap(),
+            "hdfs://old:8020"
+        );
+        assert_eq!(conf.get(&new_key, None).unwrap(), "1");
+
+        conf.set(&old_key, "2");
+        assert_eq!(conf.get(&new_key, None).unwrap(), "2");
+        conf.set("fs.defaultFS", "hdfs://new:8020");
+        assert_eq!(
+            conf.get("fs.default.name", None).unwrap(),
+            "hdfs://new:8020"
+        );
+        conf.unset(&old_key);
+        assert_eq!(conf.get(&new_key, None), None);
+    }
+
+    #[test]
+    fn test_typed_getters() {
+        let mut conf = Configuration::with_load_defaults(false);
+        conf.set("int", "20");
+        conf.set("hex", "0x20");
+        conf.set("neg.hex", "-0X1f");
+        conf.set("bytes", " 128m ");
+        conf.set("float", "0.75");
+        assert_eq!(conf.get_int("int", 0).unwrap(), 20);
+        assert_eq!(conf.get_int("hex", 0).unwrap(), 32);
+        assert_eq!(conf.get_long("neg.hex", 0).unwrap(), -31);
+        assert!(conf.get_int("float", 0).is_err());
+        assert_eq!(conf.get_long_bytes("bytes", 0).unwrap(), 128 << 20);
+        assert_eq!(conf.get_long_bytes("int", 0).unwrap(), 20);
+        assert!(conf.get_long_bytes("float", 0).is_err());
+        assert_eq!(conf.get_float("float", 0.0).unwrap(), 0.75);
+        assert_eq!(conf.get_double("missing", 1.5).unwrap(), 1.5);
+
+        conf.set("bool.enum", " true ");
+        assert!(conf.get_enum("bool.enum", false).unwrap());
+        assert!(conf.get_enum::<bool>("float", false).is_err());
+    }
+
+    #[test]
+    fn test_time_duration() {
+        let mut conf = Configuration::with_load_defaults(false);
+        conf.set("ms", "1500ms");
+        conf.set("s", "30s");
+        conf.set("m", "2m");
+        conf.set("d", "1d");
+        conf.set("plain", "7");
+        let ms = TimeUnit::Milliseconds;
+        assert_eq!(conf.get_time_duration("ms", 0, ms).unwrap(), 1500);
+        assert_eq!(
+            conf.get_time_duration("ms", 0, TimeUnit::Seconds).unwrap(),
+            1
+        );
+        assert_eq!(conf.get_time_duration("s", 0, ms).unwrap(), 30_000);
+        assert_eq!(conf.get_time_duration("m", 0, ms).unwrap(), 120_000);
+        assert_eq!(conf.get_time_duration("d", 0, TimeUnit::Hours).unwrap(), 24);
+        assert_eq!(
+            conf.get_time_duration("plain", 0, TimeUnit::Seconds)
+                .unwrap(),
+            7
+        );
+        assert_eq!(
+            conf.get_time_duration_with_return_unit("missing", 3, TimeUnit::Seconds, ms)
+                .unwrap(),
+            3000
+        );
+        assert_eq!(
+            conf.get_time_duration_with_str_default("missing", "1h", TimeUnit::Minutes)
+                .unwrap(),
+            60
+        );
+        conf.set_time_duration("set", 5, TimeUnit::Minutes);
+        assert_eq!(conf.get("set", None).unwrap(), "5m");
+        conf.set("bad", "1x");
+        assert!(conf.get_time_duration("bad", 0, ms).is_err());
+    }
+
+    #[test]
+    fn test_storage_size() {
+        let mut conf = Configuration::with_load_defaults(false);
+        conf.set("k", "512k");
+        conf.set("gb", "1GB");
+        conf.set("long", "2 megabytes");
+        let size = |name, default, unit| conf.get_storage_size(name, default, unit).unwrap();
+        assert_eq!(size("k", "0b", StorageUnit::Bytes), 512.0 * 1024.0);
+        assert_eq!(size("gb", "0b", StorageUnit::Mb), 1024.0);
+        assert_eq!(size("long", "0b", StorageUnit::Kb), 2048.0);
+        assert_eq!(size("missing", "1t", StorageUnit::Gb), 1024.0);
+        conf.set("bad", "10");
+        assert!(conf
+            .get_storage_size("bad", "0b", StorageUnit::Bytes)
+            .is_err());
+        conf.set_storage_size("set", 1.5, StorageUnit::Gb);
+        assert_eq!(conf.get("set", None).unwrap(), "1.5gb");
+    }
+
+    #[test]
+    fn test_strings_and_ranges() {
+        let mut conf = Configuration::with_load_defaults(false);
+        conf.set("list", " a, b ,,c,, ");
+        conf.set("lines", "a,\n b\n,c");
+        assert_eq!(conf.get_strings("list").unwrap(), [" a", " b ", "c", " "]);
+        assert_eq!(conf.get_strings("missing"), None);
+        assert_eq!(conf.get_strings_with_default("missing", &["x"]), ["x"]);
+        assert_eq!(conf.get_trimmed_strings("list"), ["a", "b", "", "c"]);
+        assert_eq!(conf.get_trimmed_strings("lines"), ["a", "b", "c"]);
+        assert!(conf.get_trimmed_strings("missing").is_empty());
+        conf.set_strings("set", &["x", "y"]);
+        assert_eq!(
+            conf.get_trimmed_strings_with_default("set", &[]),
+            ["x", "y"]
+        );
+
+        let ranges = conf.get_range("missing", "2-3,5,7-").unwrap();
+        assert!(ranges.is_included(2) && ranges.is_included(5) && ranges.is_included(100));
+        assert!(!ranges.is_included(4) && !ranges.is_included(6));
+        assert_eq!(ranges.get_range_start(), 2);
+        assert_eq!(ranges.iter().take(4).collect::<Vec<_>>(), [2, 3, 5, 7]);
+        assert_eq!(ranges.to_string(), "2-3,5-5,7-2147483647");
+        assert!(conf.get_range("missing", "").unwrap().is_empty());
+        assert!(conf.get_range("missing", "5-2").is_err());
+        assert!(conf.get_range("missing", "1-2-3").is_err());
+    }
+
+    #[test]
+    fn test_socket_addr() {
+        let mut conf = Configuration::with_load_defaults(false);
+        conf.set("host.port", "127.0.0.1:9000");
+        conf.set("uri", "hdfs://127.0.0.1:9001");
+        conf.set("with.path", "127.0.0.1:9000/path");
+        let addr = |name| conf.get_socket_addr(name, "127.0.0.1", 8020);
+        assert_eq!(addr("host.port").unwrap().port(), 9000);
+        assert_eq!(addr("uri").unwrap().port(), 9001);
+        assert_eq!(addr("missing").unwrap().port(), 8020);
+        let err = addr("with.path").unwrap_err().to_string();
+        assert!(err.contains("(configuration property 'with.path')"));
+    }
 }
@@ -0,0 +1,166 @@
+use crate::common::fs::common_configuration_keys;
+use once_cell::sync::Lazy;
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        RwLock,
+    },
+};
+
+/// The default deprecated keys of the core configuration.
+static DEFAULT_DEPRECATIONS: Lazy<Vec<DeprecationDelta>> = Lazy::new(|| {
+    vec![
+        DeprecationDelta::new(
+            "topology.script.file.name",
+            common_configuration_keys::NET_TOPOLOGY_SCRIPT_FILE_NAME_KEY,
+        ),
+        DeprecationDelta::new(
+            "topology.script.number.args",
+            common_configuration_keys::NET_TOPOLOGY_SCRIPT_NUMBER_ARGS_KEY,
+        ),
+        DeprecationDelta::new(
+            "hadoop.configured.node.mapping",
+            common_configuration_keys::NET_TOPOLOGY_CONFIGURED_NODE_MAPPING_KEY,
+        ),
+        DeprecationDelta::new(
+            "topology.node.switch.mapping.impl",
+            common_configuration_keys::NET_TOPOLOGY_NODE_SWITCH_MAPPING_IMPL_KEY,
+        ),
+        DeprecationDelta::new(
+            "dfs.df.interval",
+            common_configuration_keys::FS_DF_INTERVAL_KEY,
+        ),
+        DeprecationDelta::new(
+            "fs.default.name",
+            common_configuration_keys::FS_DEFAULT_NAME_KEY,
+        ),
+        DeprecationDelta::new(
+            "dfs.umaskmode",
+            common_configuration_keys::FS_PERMISSIONS_UMASK_KEY,
+        ),
+        DeprecationDelta::new(
+            "dfs.nfs.exports.allowed.hosts",
+            common_configuration_keys::NFS_EXPORTS_ALLOWED_HOSTS_KEY,
+        ),
+    ]
+});
+
+/// The global deprecation context, which is shared by all configurations.
+pub(super) static DEPRECATION_CONTEXT: Lazy<RwLock<DeprecationContext>> = Lazy::new(|| {
+    let mut context = DeprecationContext::default();
+    context.add_deprecations(&DEFAULT_DEPRECATIONS);
+    RwLock::new(context)
+});
+
+/// A pending addition to the global set of deprecated keys.
+#[derive(Clone)]
+pub struct DeprecationDelta {
+    key: String,
+    new_keys: Vec<String>,
+    custom_message: Option<String>,
+}
+
+impl DeprecationDelta {
+    pub fn new(key: &str, new_key: &str) -> Self {
+        Self::with_new_keys(key, &[new_key], None)
+    }
+
+    pub fn with_new_keys(key: &str, new_keys: &[&str], custom_message: Option<&str>) -> Self {
+        Self {
+            key: key.to_owned(),
+            new_keys: new_keys.iter().map(|k| k.to_string()).collect(),
+            custom_message: custom_message.map(str::to_owned),
+        }
+    }
+
+    pub fn get_key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn get_new_keys(&self) -> &[String] {
+        &self.new_keys
+    }
+
+    pub fn get_custom_message(&self) -> Option<&str> {
+        self.custom_message.as_deref()
+    }
+}
+
+/// Class to keep the information about the keys which replace the deprecated
+/// ones.
+///
+/// This class stores the new keys which replace the deprecated keys and also
+/// gives a provision to have a custom message for each of the deprecated key
+/// that is being replaced. It also provides method to get the appropriate
+/// warning message which can be logged whenever the deprecated key is used.
+pub(super) struct DeprecatedKeyInfo {
+    pub new_keys: Vec<String>,
+    custom_message: Option<String>,
+    access_warning_logged: AtomicBool,
+}
+
+impl DeprecatedKeyInfo {
+    /// Method to provide the warning message. It gives the custom message if
+    /// non-null, and default message otherwise.
+    fn get_warning_message(&self, key: &str) -> String {
+        match &self.custom_message {
+            Some(message) => message.to_owned(),
+            None => format!(
+                "{} is deprecated. Instead, use {}",
+                key,
+                self.new_keys.join(", ")
+            ),
+        }
+    }
+
+    /// Log the warning message for the deprecated `key` the first time it is
+    /// accessed.
+    pub fn log_deprecation_once(&self, key: &str) {
+        if !self.access_warning_logged.swap(true, Ordering::SeqCst) {
+            log::warn!("{}", self.get_warning_message(key));
+        }
+    }
+}
+
+/// The set of all keys which are deprecated.
+#[derive(Default)]
+pub(super) struct DeprecationContext {
+    // Stores the deprecated keys, the new keys which replace the deprecated keys
+    // and custom message(if any provided).
+    deprecated_key_map: HashMap<String, DeprecatedKeyInfo>,
+    // Stores a mapping from superseding keys to the keys which they deprecate.
+    reverse_deprecated_key_map: HashMap<String, String>,
+}
+
+impl DeprecationContext {
+    pub fn add_deprecations(&mut self, deltas: &[DeprecationDelta]) {
+        for delta in deltas {
+            if self.deprecated_key_map.contains_key(&delta.key) {
+                continue;
+            }
+            self.deprecated_key_map.insert(
+                delta.key.to_owned(),
+                DeprecatedKeyInfo {
+                    new_keys: delta.new_keys.to_owned(),
+                    custom_message: delta.custom_message.to_owned(),
+                    access_warning_logged: AtomicBool::new(false),
+                },
+            );
+            for new_key in &delta.new_keys {
+                self.reverse_deprecated_key_map
+                    .insert(new_key.to_owned(), delta.key.to_owned());
+            }
+        }
+    }
+
+    pub fn get_deprecated_key_info(&self, key: &str) -> Option<&DeprecatedKeyInfo> {
+        self.deprecated_key_map.get(key)
+    }
+
+    pub fn get_deprecated_key(&self, new_key: &str) -> Option<&str> {
+        self.reverse_deprecated_key_map
+            .get(new_key)
+            .map(String::as_str)
+    }
+}
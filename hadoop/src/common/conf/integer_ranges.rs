@@ -0,0 +1,87 @@
+use anyhow::Error;
+use std::fmt;
+
+#[derive(Clone, Copy, Debug)]
+struct Range {
+    start: i32,
+    end: i32,
+}
+
+/// A class that represents a set of positive integer ranges. It parses
+/// strings of the form: "2-3,5,7-" where ranges are separated by comma and
+/// the lower/upper bounds are separated by dash. Either the lower or upper
+/// bound may be omitted meaning all values up to or over. So the string
+/// above means 2, 3, 5, and 7, 8, 9, ...
+#[derive(Clone, Debug, Default)]
+pub struct IntegerRanges {
+    ranges: Vec<Range>,
+}
+
+impl IntegerRanges {
+    pub fn new(new_value: &str) -> anyhow::Result<Self> {
+        let mut ranges = vec![];
+        for rng in new_value.split(',').filter(|r| !r.is_empty()) {
+            let rng = rng.trim();
+            let parts: Vec<&str> = rng.splitn(3, '-').collect();
+            if parts.len() > 2 {
+                return Err(Error::msg(format!("integer range badly formed: {}", rng)));
+            }
+            let start = Self::convert_to_int(parts[0], 0)?;
+            let end = match parts.get(1) {
+                Some(end) => Self::convert_to_int(end, i32::MAX)?,
+                None => start,
+            };
+            if start > end {
+                return Err(Error::msg(format!(
+                    "IntegerRange from {} to {} is invalid",
+                    start, end
+                )));
+            }
+            ranges.push(Range { start, end });
+        }
+        Ok(Self { ranges })
+    }
+
+    /// Convert a string to an int treating empty strings as the default value.
+    fn convert_to_int(value: &str, default_value: i32) -> anyhow::Result<i32> {
+        let trim = value.trim();
+        if trim.is_empty() {
+            return Ok(default_value);
+        }
+        Ok(trim.parse()?)
+    }
+
+    /// Is the given value in the set of ranges
+    pub fn is_included(&self, value: i32) -> bool {
+        self.ranges
+            .iter()
+            .any(|r| r.start <= value && value <= r.end)
+    }
+
+    /// Returns true if there are no values in this range, else false.
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// Get range start for the first integer range, `-1` if there is no
+    /// range.
+    pub fn get_range_start(&self) -> i32 {
+        self.ranges.first().map_or(-1, |r| r.start)
+    }
+
+    /// Iterate over all the values in the ranges, in order.
+    pub fn iter(&self) -> impl Iterator<Item = i32> + '_ {
+        self.ranges.iter().flat_map(|r| r.start..=r.end)
+    }
+}
+
+impl fmt::Display for IntegerRanges {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ranges: Vec<String> = self
+            .ranges
+            .iter()
+            .map(|r| format!("{}-{}", r.start, r.end))
+            .collect();
+        write!(f, "{}", ranges.join(","))
+    }
+}
@@ -1,3 +1,13 @@
 mod configuration;
+mod deprecation;
+mod integer_ranges;
+mod storage_size;
+mod storage_unit;
+mod time_unit;
 
-pub use configuration::Configuration;
+pub use configuration::{Configuration, HADOOP_CONF_DIR, HADOOP_HOME};
+pub use deprecation::DeprecationDelta;
+pub use integer_ranges::IntegerRanges;
+pub use storage_size::StorageSize;
+pub use storage_unit::StorageUnit;
+pub use time_unit::TimeUnit;
@@ -0,0 +1,54 @@
+use super::StorageUnit;
+use anyhow::Error;
+
+/// A class that contains the numeric value and the unit of measure.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StorageSize {
+    unit: StorageUnit,
+    value: f64,
+}
+
+impl StorageSize {
+    /// Constructs a Storage Measure, which contains the value and the unit of
+    /// measure.
+    pub fn new(unit: StorageUnit, value: f64) -> Self {
+        Self { unit, value }
+    }
+
+    /// Parse a string like `1000MB`, `1g` or `10 kilobytes` into a
+    /// StorageSize.
+    pub fn parse(value: &str) -> anyhow::Result<Self> {
+        let sanitized_value = value.trim().to_lowercase();
+        if sanitized_value.is_empty() {
+            return Err(Error::msg("value cannot be blank"));
+        }
+        // We are trying to get the longest match first, so the order of
+        // matching is get_long_name, get_short_name and then get_suffix_char.
+        let (unit, suffix) = StorageUnit::values()
+            .into_iter()
+            .find_map(|unit| {
+                [
+                    unit.get_long_name(),
+                    unit.get_short_name(),
+                    unit.get_suffix_char(),
+                ]
+                .into_iter()
+                .find(|suffix| sanitized_value.ends_with(suffix))
+                .map(|suffix| (unit, suffix))
+            })
+            .ok_or(Error::msg(format!(
+                "{} is not in expected format.Expected format is <number><unit>. e.g. 1000MB",
+                value
+            )))?;
+        let val_string = sanitized_value[..sanitized_value.len() - suffix.len()].trim();
+        Ok(Self::new(unit, val_string.parse()?))
+    }
+
+    pub fn get_unit(&self) -> StorageUnit {
+        self.unit
+    }
+
+    pub fn get_value(&self) -> f64 {
+        self.value
+    }
+}
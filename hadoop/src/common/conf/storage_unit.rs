@@ -0,0 +1,96 @@
+use std::fmt;
+
+const BYTE: f64 = 1.0;
+const KILOBYTE: f64 = 1024.0;
+
+/// Class that maintains different forms of Storage Units.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StorageUnit {
+    Eb,
+    Pb,
+    Tb,
+    Gb,
+    Mb,
+    Kb,
+    Bytes,
+}
+
+impl StorageUnit {
+    /// All the units, from the largest to the smallest.
+    pub fn values() -> [Self; 7] {
+        [
+            Self::Eb,
+            Self::Pb,
+            Self::Tb,
+            Self::Gb,
+            Self::Mb,
+            Self::Kb,
+            Self::Bytes,
+        ]
+    }
+
+    fn bytes_per_unit(&self) -> f64 {
+        let exponent = match self {
+            Self::Eb => 6,
+            Self::Pb => 5,
+            Self::Tb => 4,
+            Self::Gb => 3,
+            Self::Mb => 2,
+            Self::Kb => 1,
+            Self::Bytes => 0,
+        };
+        BYTE * KILOBYTE.powi(exponent)
+    }
+
+    pub fn get_long_name(&self) -> &'static str {
+        match self {
+            Self::Eb => "exabytes",
+            Self::Pb => "petabytes",
+            Self::Tb => "terabytes",
+            Self::Gb => "gigabytes",
+            Self::Mb => "megabytes",
+            Self::Kb => "kilobytes",
+            Self::Bytes => "bytes",
+        }
+    }
+
+    pub fn get_short_name(&self) -> &'static str {
+        match self {
+            Self::Eb => "eb",
+            Self::Pb => "pb",
+            Self::Tb => "tb",
+            Self::Gb => "gb",
+            Self::Mb => "mb",
+            Self::Kb => "kb",
+            Self::Bytes => "b",
+        }
+    }
+
+    pub fn get_suffix_char(&self) -> &'static str {
+        match self {
+            Self::Eb => "e",
+            Self::Pb => "p",
+            Self::Tb => "t",
+            Self::Gb => "g",
+            Self::Mb => "m",
+            Self::Kb => "k",
+            Self::Bytes => "b",
+        }
+    }
+
+    /// Convert `value` in this unit to bytes.
+    pub fn to_bytes(&self, value: f64) -> f64 {
+        value * self.bytes_per_unit()
+    }
+
+    /// Convert `value` in bytes to this unit.
+    pub fn from_bytes(&self, value: f64) -> f64 {
+        value / self.bytes_per_unit()
+    }
+}
+
+impl fmt::Display for StorageUnit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.get_long_name())
+    }
+}
@@ -0,0 +1,86 @@
+use std::fmt;
+
+/// A unit of time, used to interpret and convert time durations.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimeUnit {
+    Nanoseconds,
+    Microseconds,
+    Milliseconds,
+    Seconds,
+    Minutes,
+    Hours,
+    Days,
+}
+
+impl TimeUnit {
+    fn values() -> [Self; 7] {
+        [
+            Self::Nanoseconds,
+            Self::Microseconds,
+            Self::Milliseconds,
+            Self::Seconds,
+            Self::Minutes,
+            Self::Hours,
+            Self::Days,
+        ]
+    }
+
+    fn nanos(&self) -> i64 {
+        match self {
+            Self::Nanoseconds => 1,
+            Self::Microseconds => 1_000,
+            Self::Milliseconds => 1_000_000,
+            Self::Seconds => 1_000_000_000,
+            Self::Minutes => 60_000_000_000,
+            Self::Hours => 3_600_000_000_000,
+            Self::Days => 86_400_000_000_000,
+        }
+    }
+
+    /// The suffix of a time duration expressed in this unit.
+    pub fn suffix(&self) -> &'static str {
+        match self {
+            Self::Nanoseconds => "ns",
+            Self::Microseconds => "us",
+            Self::Milliseconds => "ms",
+            Self::Seconds => "s",
+            Self::Minutes => "m",
+            Self::Hours => "h",
+            Self::Days => "d",
+        }
+    }
+
+    /// Find the unit whose suffix ends the given duration string.
+    pub fn unit_for(s: &str) -> Option<Self> {
+        Self::values().into_iter().find(|u| s.ends_with(u.suffix()))
+    }
+
+    /// Converts the given time `duration` in the given `unit` to this unit.
+    /// Conversions from finer to coarser granularities truncate, so lose
+    /// precision. Conversions from coarser to finer granularities with
+    /// arguments that would numerically overflow saturate to `i64::MIN` if
+    /// negative or `i64::MAX` if positive.
+    pub fn convert(&self, duration: i64, unit: TimeUnit) -> i64 {
+        let (from, to) = (unit.nanos(), self.nanos());
+        if from >= to {
+            duration.saturating_mul(from / to)
+        } else {
+            duration / (to / from)
+        }
+    }
+}
+
+impl fmt::Display for TimeUnit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Nanoseconds => "NANOSECONDS",
+            Self::Microseconds => "MICROSECONDS",
+            Self::Milliseconds => "MILLISECONDS",
+            Self::Seconds => "SECONDS",
+            Self::Minutes => "MINUTES",
+            Self::Hours => "HOURS",
+            Self::Days => "DAYS",
+        };
+        write!(f, "{}", name)
+    }
+}
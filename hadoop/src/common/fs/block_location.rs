@@ -0,0 +1,118 @@
+use super::StorageType;
+use std::fmt;
+
+/// Represents the network location of a block, information about the hosts
+/// that contain block replicas, and other block metadata (E.g. the file
+/// offset associated with the block, length, whether it is corrupt, etc).
+///
+/// For a single BlockLocation, it will have different meanings for
+/// replicated and erasure coded files.
+///
+/// If the file is 3-replicated, offset and length of a BlockLocation
+/// represent the absolute value in the file and the hosts are the 3 datanodes
+/// that holding the replicas.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BlockLocation {
+    // Datanode hostnames
+    hosts: Vec<String>,
+    // Datanodes with a cached replica
+    cached_hosts: Vec<String>,
+    // Datanode IP:xferPort for accessing the block
+    names: Vec<String>,
+    // Full path name in network topology
+    topology_paths: Vec<String>,
+    storage_ids: Vec<String>,
+    storage_types: Vec<StorageType>,
+    // Offset of the block in the file
+    offset: i64,
+    length: i64,
+    corrupt: bool,
+}
+
+impl BlockLocation {
+    /// Constructor with host, name, network topology, offset, length
+    /// and corrupt flag.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        names: Vec<String>,
+        hosts: Vec<String>,
+        cached_hosts: Vec<String>,
+        topology_paths: Vec<String>,
+        storage_ids: Vec<String>,
+        storage_types: Vec<StorageType>,
+        offset: i64,
+        length: i64,
+        corrupt: bool,
+    ) -> Self {
+        Self {
+            hosts,
+            cached_hosts,
+            names,
+            topology_paths,
+            storage_ids,
+            storage_types,
+            offset,
+            length,
+            corrupt,
+        }
+    }
+
+    /// Get the list of hosts (hostname) hosting this block.
+    pub fn get_hosts(&self) -> &[String] {
+        &self.hosts
+    }
+
+    /// Get the list of hosts (hostname) hosting a cached replica of the block.
+    pub fn get_cached_hosts(&self) -> &[String] {
+        &self.cached_hosts
+    }
+
+    /// Get the list of names (IP:xferPort) hosting this block.
+    pub fn get_names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// Get the list of network topology paths for each of the hosts.
+    /// The last component of the path is the "name" (IP:xferPort).
+    pub fn get_topology_paths(&self) -> &[String] {
+        &self.topology_paths
+    }
+
+    /// Get the storageID of each replica of the block.
+    pub fn get_storage_ids(&self) -> &[String] {
+        &self.storage_ids
+    }
+
+    /// Get the storage type of each replica of the block.
+    pub fn get_storage_types(&self) -> &[StorageType] {
+        &self.storage_types
+    }
+
+    /// Get the start offset of file associated with this block.
+    pub fn get_offset(&self) -> i64 {
+        self.offset
+    }
+
+    /// Get the length of the block.
+    pub fn get_length(&self) -> i64 {
+        self.length
+    }
+
+    /// Get the corrupt flag.
+    pub fn is_corrupt(&self) -> bool {
+        self.corrupt
+    }
+}
+
+impl fmt::Display for BlockLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{},{}", self.offset, self.length)?;
+        if self.corrupt {
+            write!(f, "(corrupt)")?;
+        }
+        for h in &self.hosts {
+            write!(f, ",{}", h)?;
+        }
+        Ok(())
+    }
+}
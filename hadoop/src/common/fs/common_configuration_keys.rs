@@ -10,6 +10,11 @@ pub const IPC_PING_INTERVAL_KEY: &str = "ipc.ping.interval";
 /// Default value for [`IPC_PING_INTERVAL_KEY`]
 pub const IPC_PING_INTERVAL_DEFAULT: i32 = 60000; // 1min
 
+/// Timeout value for RPC client on waiting for response
+pub const IPC_CLIENT_RPC_TIMEOUT_KEY: &str = "ipc.client.rpc-timeout.ms";
+/// Default value for [`IPC_CLIENT_RPC_TIMEOUT_KEY`]
+pub const IPC_CLIENT_RPC_TIMEOUT_DEFAULT: i32 = 120000;
+
 /// Enables pings from RPC client to the server
 pub const IPC_CLIENT_PING_KEY: &str = "ipc.client.ping";
 /// Default value of [`IPC_CLIENT_PING_KEY`]
@@ -33,3 +38,6 @@ pub const IPC_CLIENT_BIND_WILDCARD_ADDR_DEFAULT: bool = false;
 pub const IPC_CLIENT_CONNECT_MAX_RETRIES_ON_SASL_KEY: &str =
     "ipc.client.connect.max.retries.on.sasl";
 pub const IPC_CLIENT_CONNECT_MAX_RETRIES_ON_SASL_DEFAULT: i32 = 5;
+
+/// Allowed hosts for nfs exports
+pub const NFS_EXPORTS_ALLOWED_HOSTS_KEY: &str = "nfs.exports.allowed.hosts";
@@ -3,6 +3,19 @@ pub const FS_DEFAULT_NAME_KEY: &str = "fs.defaultFS";
 /// Default value for [`FS_DEFAULT_NAME_KEY`]
 pub const FS_DEFAULT_NAME_DEFAULT: &str = "file:///";
 
+pub const NET_TOPOLOGY_SCRIPT_NUMBER_ARGS_KEY: &str = "net.topology.script.number.args";
+pub const NET_TOPOLOGY_NODE_SWITCH_MAPPING_IMPL_KEY: &str = "net.topology.node.switch.mapping.impl";
+pub const NET_TOPOLOGY_SCRIPT_FILE_NAME_KEY: &str = "net.topology.script.file.name";
+pub const NET_TOPOLOGY_CONFIGURED_NODE_MAPPING_KEY: &str = "net.topology.configured.node.mapping";
+
+pub const FS_DF_INTERVAL_KEY: &str = "fs.df.interval";
+/// Default value for [`FS_DF_INTERVAL_KEY`]
+pub const FS_DF_INTERVAL_DEFAULT: i64 = 60000;
+
+pub const FS_TRASH_INTERVAL_KEY: &str = "fs.trash.interval";
+/// Default value for [`FS_TRASH_INTERVAL_KEY`]
+pub const FS_TRASH_INTERVAL_DEFAULT: f32 = 0.0;
+
 pub const FS_CLIENT_RESOLVE_REMOTE_SYMLINKS_KEY: &str = "fs.client.resolve.remote.symlinks";
 /// Default value for [`FS_CLIENT_RESOLVE_REMOTE_SYMLINKS_KEY`]
 pub const FS_CLIENT_RESOLVE_REMOTE_SYMLINKS_DEFAULT: bool = true;
@@ -15,6 +28,14 @@ pub const IPC_CLIENT_CONNECT_TIMEOUT_KEY: &str = "ipc.client.connect.timeout";
 /// Default value for [`IPC_CLIENT_CONNECT_TIMEOUT_KEY`]
 pub const IPC_CLIENT_CONNECT_TIMEOUT_DEFAULT: i32 = 20000;
 
+pub const IPC_CLIENT_CONNECT_MAX_RETRIES_KEY: &str = "ipc.client.connect.max.retries";
+/// Default value for [`IPC_CLIENT_CONNECT_MAX_RETRIES_KEY`]
+pub const IPC_CLIENT_CONNECT_MAX_RETRIES_DEFAULT: i32 = 10;
+
+pub const IPC_CLIENT_CONNECT_RETRY_INTERVAL_KEY: &str = "ipc.client.connect.retry.interval";
+/// Default value for [`IPC_CLIENT_CONNECT_RETRY_INTERVAL_KEY`]
+pub const IPC_CLIENT_CONNECT_RETRY_INTERVAL_DEFAULT: i32 = 1000;
+
 pub const IPC_CLIENT_CONNECT_MAX_RETRIES_ON_SOCKET_TIMEOUTS_KEY: &str =
     "ipc.client.connect.max.retries.on.timeouts";
 /// Default value for [`IPC_CLIENT_CONNECT_MAX_RETRIES_ON_SOCKET_TIMEOUTS_KEY`]
@@ -27,3 +48,12 @@ pub const IPC_CLIENT_TCPNODELAY_DEFAULT: bool = true;
 pub const IPC_CLIENT_LOW_LATENCY: &str = "ipc.client.low-latency";
 /// Default value of [`IPC_CLIENT_LOW_LATENCY`]
 pub const IPC_CLIENT_LOW_LATENCY_DEFAULT: bool = false;
+
+pub const HADOOP_SHELL_SAFELY_DELETE_LIMIT_NUM_FILES: &str =
+    "hadoop.shell.safely.delete.limit.num.files";
+/// Default value for [`HADOOP_SHELL_SAFELY_DELETE_LIMIT_NUM_FILES`]
+pub const HADOOP_SHELL_SAFELY_DELETE_LIMIT_NUM_FILES_DEFAULT: i64 = 100;
+
+pub const HADOOP_SECURITY_AUTHENTICATION: &str = "hadoop.security.authentication";
+
+pub const HADOOP_RPC_PROTECTION: &str = "hadoop.rpc.protection";
@@ -0,0 +1,36 @@
+use super::FileChecksum;
+use crate::common::util::DataChecksumType;
+
+/// Composite CRC.
+pub struct CompositeCrcFileChecksum {
+    crc: u32,
+    crc_type: DataChecksumType,
+    _bytes_per_crc: i32,
+}
+
+impl CompositeCrcFileChecksum {
+    pub const LENGTH: usize = 4;
+
+    /// Create a CompositeCrcFileChecksum
+    pub fn new(crc: u32, crc_type: DataChecksumType, bytes_per_crc: i32) -> Self {
+        Self {
+            crc,
+            crc_type,
+            _bytes_per_crc: bytes_per_crc,
+        }
+    }
+}
+
+impl FileChecksum for CompositeCrcFileChecksum {
+    fn get_algorithm_name(&self) -> String {
+        format!("COMPOSITE-{}", self.crc_type)
+    }
+
+    fn get_length(&self) -> usize {
+        Self::LENGTH
+    }
+
+    fn get_bytes(&self) -> Vec<u8> {
+        self.crc.to_be_bytes().to_vec()
+    }
+}
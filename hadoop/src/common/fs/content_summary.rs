@@ -0,0 +1,149 @@
+use crate::{common::util::string_utils::TraditionalBinaryPrefix, hdfs::ContentSummaryProto};
+
+const QUOTA_NONE: &str = "none";
+const QUOTA_INF: &str = "inf";
+
+/// Store the summary of a content (a directory or a file).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ContentSummary {
+    length: i64,
+    file_count: i64,
+    directory_count: i64,
+    quota: i64,
+    space_consumed: i64,
+    space_quota: i64,
+}
+
+impl ContentSummary {
+    pub fn new(
+        length: i64,
+        file_count: i64,
+        directory_count: i64,
+        quota: i64,
+        space_consumed: i64,
+        space_quota: i64,
+    ) -> Self {
+        Self {
+            length,
+            file_count,
+            directory_count,
+            quota,
+            space_consumed,
+            space_quota,
+        }
+    }
+
+    pub fn get_length(&self) -> i64 {
+        self.length
+    }
+
+    pub fn get_directory_count(&self) -> i64 {
+        self.directory_count
+    }
+
+    pub fn get_file_count(&self) -> i64 {
+        self.file_count
+    }
+
+    /// Return the directory quota.
+    pub fn get_quota(&self) -> i64 {
+        self.quota
+    }
+
+    /// Return (disk) space consumed.
+    pub fn get_space_consumed(&self) -> i64 {
+        self.space_consumed
+    }
+
+    /// Return (disk) space quota.
+    pub fn get_space_quota(&self) -> i64 {
+        self.space_quota
+    }
+
+    /// Return the header of the output.
+    /// if `q_option` is false, output directory count, file count, and content size;
+    /// if `q_option` is true, output quota and remaining quota as well.
+    pub fn get_header(q_option: bool) -> String {
+        let summary_header = format!(
+            "{:>12} {:>12} {:>18} ",
+            "DIR_COUNT", "FILE_COUNT", "CONTENT_SIZE"
+        );
+        if q_option {
+            Self::get_quota_header() + &summary_header
+        } else {
+            summary_header
+        }
+    }
+
+    /// Return the header of the quota part of the output.
+    pub fn get_quota_header() -> String {
+        format!(
+            "{:>12} {:>15} {:>15} {:>15} ",
+            "QUOTA", "REM_QUOTA", "SPACE_QUOTA", "REM_SPACE_QUOTA"
+        )
+    }
+
+    /// Return the string representation of the object in the output format.
+    /// if `q_option` is false, output directory count, file count, and content size;
+    /// if `q_option` is true, output quota and remaining quota as well.
+    /// if `h_option` is true, sizes are output in human readable format.
+    pub fn to_string(&self, q_option: bool, h_option: bool) -> String {
+        let prefix = if q_option {
+            self.get_quota_usage(h_option)
+        } else {
+            String::new()
+        };
+        format!(
+            "{}{:>12} {:>12} {:>18} ",
+            prefix,
+            format_size(self.directory_count, h_option),
+            format_size(self.file_count, h_option),
+            format_size(self.length, h_option)
+        )
+    }
+
+    /// Return the quota and remaining quota part of the output.
+    pub fn get_quota_usage(&self, h_option: bool) -> String {
+        let (mut quota_str, mut quota_rem) = (QUOTA_NONE.to_owned(), QUOTA_INF.to_owned());
+        let (mut space_quota_str, mut space_quota_rem) =
+            (QUOTA_NONE.to_owned(), QUOTA_INF.to_owned());
+
+        if self.quota > 0 {
+            quota_str = format_size(self.quota, h_option);
+            quota_rem = format_size(
+                self.quota - (self.directory_count + self.file_count),
+                h_option,
+            );
+        }
+        if self.space_quota >= 0 {
+            space_quota_str = format_size(self.space_quota, h_option);
+            space_quota_rem = format_size(self.space_quota - self.space_consumed, h_option);
+        }
+        format!(
+            "{:>12} {:>15} {:>15} {:>15} ",
+            quota_str, quota_rem, space_quota_str, space_quota_rem
+        )
+    }
+}
+
+impl From<&ContentSummaryProto> for ContentSummary {
+    fn from(cs: &ContentSummaryProto) -> Self {
+        Self::new(
+            cs.length as i64,
+            cs.file_count as i64,
+            cs.directory_count as i64,
+            cs.quota as i64,
+            cs.space_consumed as i64,
+            cs.space_quota as i64,
+        )
+    }
+}
+
+/// Formats a size to be human readable or in bytes.
+fn format_size(size: i64, human_readable: bool) -> String {
+    if human_readable {
+        TraditionalBinaryPrefix::long2string(size, "", 1)
+    } else {
+        size.to_string()
+    }
+}
@@ -0,0 +1,121 @@
+use anyhow::Error;
+
+/// CreateFlag specifies the file create semantic. Users can combine flags like:
+/// `&[CreateFlag::Create, CreateFlag::Append]`
+///
+/// Use the CreateFlag as follows:
+/// - CREATE - to create a file if it does not exist,
+///   else throw FileAlreadyExists.
+/// - APPEND - to append to a file if it exists,
+///   else throw FileNotFound.
+/// - OVERWRITE - to truncate a file if it exists,
+///   else throw FileNotFound.
+/// - CREATE|APPEND - to create a file if it does not exist,
+///   else append to an existing file.
+/// - CREATE|OVERWRITE - to create a file if it does not exist,
+///   else overwrite an existing file.
+/// - SYNC_BLOCK - to force closed blocks to the disk device.
+///   In addition `hsync` should be called after each write,
+///   if true synchronous behavior is required.
+///
+/// Following combinations are not valid and will result in
+/// an error:
+/// - APPEND|OVERWRITE
+/// - CREATE|APPEND|OVERWRITE
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CreateFlag {
+    /// Create a file. See the type documentation for more description.
+    Create,
+    /// Truncate/overwrite a file. Same as POSIX O_TRUNC.
+    Overwrite,
+    /// Append to a file. See the type documentation for more description.
+    Append,
+    /// Force closed blocks to disk. Similar to POSIX O_SYNC.
+    SyncBlock,
+    /// Create the block on transient storage (RAM) if available. If
+    /// transient storage is unavailable then the block will be created
+    /// on disk.
+    ///
+    /// HDFS will make a best effort to lazily write these files to persistent
+    /// storage, however file contents may be lost at any time due to process/
+    /// node restarts, hence there is no guarantee of data durability.
+    ///
+    /// This flag must only be used for intermediate data whose loss can be
+    /// tolerated by the application.
+    LazyPersist,
+    /// Append data to a new block instead of the end of the last partial block.
+    /// This is only useful for APPEND.
+    NewBlock,
+    /// Advise that a block replica NOT be written to the local DataNode where
+    /// 'local' means the same host as the client is being run on.
+    NoLocalWrite,
+    /// Enforce the file to be a replicated file, no matter what its parent
+    /// directory's replication or erasure coding policy is.
+    ShouldReplicate,
+    /// Advise that the first block replica NOT take into account DataNode
+    /// locality. The first block replica should be placed randomly within the
+    /// cluster. Subsequent block replicas should follow DataNode locality rules.
+    IgnoreClientLocality,
+}
+
+impl CreateFlag {
+    pub fn mode(&self) -> i16 {
+        match self {
+            Self::Create => 0x01,
+            Self::Overwrite => 0x02,
+            Self::Append => 0x04,
+            Self::SyncBlock => 0x08,
+            Self::LazyPersist => 0x10,
+            Self::NewBlock => 0x20,
+            Self::NoLocalWrite => 0x40,
+            Self::ShouldReplicate => 0x80,
+            Self::IgnoreClientLocality => 0x100,
+        }
+    }
+
+    /// Validate the CreateFlag and throw exception if it is invalid
+    pub fn validate(flag: &[CreateFlag]) -> anyhow::Result<()> {
+        if flag.is_empty() {
+            return Err(Error::msg("create flag must not be empty"));
+        }
+        let append = flag.contains(&Self::Append);
+        let overwrite = flag.contains(&Self::Overwrite);
+
+        // Both append and overwrite is an error
+        if append && overwrite {
+            return Err(Error::msg(format!(
+                "Both append and overwrite options cannot be enabled: {:?}",
+                flag
+            )));
+        }
+        Ok(())
+    }
+
+    /// Validate the CreateFlag for the append operation. The flag must contain
+    /// APPEND, and optionally NEW_BLOCK.
+    pub fn validate_for_append(flag: &[CreateFlag]) -> anyhow::Result<()> {
+        Self::validate(flag)?;
+        if !flag.contains(&Self::Append) {
+            return Err(Error::msg(format!("{:?} does not contain APPEND", flag)));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_for_append() {
+        assert!(CreateFlag::validate_for_append(&[CreateFlag::Append]).is_ok());
+        assert!(
+            CreateFlag::validate_for_append(&[CreateFlag::Append, CreateFlag::NewBlock]).is_ok()
+        );
+        assert!(CreateFlag::validate_for_append(&[CreateFlag::Create]).is_err());
+        assert!(CreateFlag::validate_for_append(&[]).is_err());
+        assert!(
+            CreateFlag::validate_for_append(&[CreateFlag::Append, CreateFlag::Overwrite]).is_err()
+        );
+    }
+}
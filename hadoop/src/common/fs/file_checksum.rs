@@ -0,0 +1,25 @@
+use crate::common::util::string_utils;
+use std::fmt;
+
+/// An abstract class representing file checksums for files.
+pub trait FileChecksum {
+    /// The checksum algorithm name
+    fn get_algorithm_name(&self) -> String;
+
+    /// The length of the checksum in bytes
+    fn get_length(&self) -> usize;
+
+    /// The value of the checksum in bytes
+    fn get_bytes(&self) -> Vec<u8>;
+}
+
+impl fmt::Display for dyn FileChecksum {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}",
+            self.get_algorithm_name(),
+            string_utils::byte_to_hex_string(&self.get_bytes())
+        )
+    }
+}
@@ -0,0 +1,219 @@
+use super::{permission::FsPermission, Path};
+use std::{collections::HashSet, fmt};
+
+/// Flags for entity attributes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AttrFlags {
+    /// ACL bit on a file.
+    HasAcl,
+    /// Encryption bit on a file.
+    HasCrypt,
+    /// Erasure coded bit on a file.
+    HasEc,
+    /// Snapshot capability enabled.
+    SnapshotEnabled,
+}
+
+/// Interface that represents the client side information for a file.
+#[derive(Clone, Debug)]
+pub struct FileStatus {
+    path: Path,
+    length: i64,
+    isdir: bool,
+    block_replication: i16,
+    blocksize: i64,
+    modification_time: i64,
+    access_time: i64,
+    permission: FsPermission,
+    owner: String,
+    group: String,
+    symlink: Option<Path>,
+    attr: HashSet<AttrFlags>,
+}
+
+impl FileStatus {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        length: i64,
+        isdir: bool,
+        block_replication: i16,
+        blocksize: i64,
+        modification_time: i64,
+        access_time: i64,
+        permission: Option<FsPermission>,
+        owner: &str,
+        group: &str,
+        symlink: Option<Path>,
+        path: Path,
+        attr: HashSet<AttrFlags>,
+    ) -> Self {
+        let permission = permission.unwrap_or_else(|| {
+            if isdir {
+                FsPermission::get_dir_default()
+            } else if symlink.is_some() {
+                FsPermission::from(0o777)
+            } else {
+                FsPermission::get_file_default()
+            }
+        });
+        Self {
+            path,
+            length,
+            isdir,
+            block_replication,
+            blocksize,
+            modification_time,
+            access_time,
+            permission,
+            owner: owner.to_owned(),
+            group: group.to_owned(),
+            symlink,
+            attr,
+        }
+    }
+
+    /// Convert boolean attributes to a set of flags.
+    pub fn attributes(acl: bool, crypt: bool, ec: bool, sn: bool) -> HashSet<AttrFlags> {
+        [
+            (acl, AttrFlags::HasAcl),
+            (crypt, AttrFlags::HasCrypt),
+            (ec, AttrFlags::HasEc),
+            (sn, AttrFlags::SnapshotEnabled),
+        ]
+        .into_iter()
+        .filter_map(|(set, flag)| set.then_some(flag))
+        .collect()
+    }
+
+    /// Get the length of this file, in bytes.
+    pub fn get_len(&self) -> i64 {
+        self.length
+    }
+
+    /// Is this a file?
+    pub fn is_file(&self) -> bool {
+        !self.isdir && !self.is_symlink()
+    }
+
+    /// Is this a directory?
+    pub fn is_directory(&self) -> bool {
+        self.isdir
+    }
+
+    /// Is this a symbolic link?
+    pub fn is_symlink(&self) -> bool {
+        self.symlink.is_some()
+    }
+
+    /// Get the block size of the file.
+    pub fn get_block_size(&self) -> i64 {
+        self.blocksize
+    }
+
+    /// Get the replication factor of a file.
+    pub fn get_replication(&self) -> i16 {
+        self.block_replication
+    }
+
+    /// Get the modification time of the file, in milliseconds since January 1, 1970 UTC.
+    pub fn get_modification_time(&self) -> i64 {
+        self.modification_time
+    }
+
+    /// Get the access time of the file, in milliseconds since January 1, 1970 UTC.
+    pub fn get_access_time(&self) -> i64 {
+        self.access_time
+    }
+
+    /// Get FsPermission associated with the file.
+    pub fn get_permission(&self) -> &FsPermission {
+        &self.permission
+    }
+
+    /// Tell whether the underlying file or directory has ACLs set.
+    pub fn has_acl(&self) -> bool {
+        self.attr.contains(&AttrFlags::HasAcl)
+    }
+
+    /// Tell whether the underlying file or directory is encrypted or not.
+    pub fn is_encrypted(&self) -> bool {
+        self.attr.contains(&AttrFlags::HasCrypt)
+    }
+
+    /// Tell whether the underlying file or directory is erasure coded or not.
+    pub fn is_erasure_coded(&self) -> bool {
+        self.attr.contains(&AttrFlags::HasEc)
+    }
+
+    /// Check if directory is Snapshot enabled or not.
+    pub fn is_snapshot_enabled(&self) -> bool {
+        self.attr.contains(&AttrFlags::SnapshotEnabled)
+    }
+
+    /// Get the owner of the file.
+    pub fn get_owner(&self) -> &str {
+        &self.owner
+    }
+
+    /// Get the group associated with the file.
+    pub fn get_group(&self) -> &str {
+        &self.group
+    }
+
+    pub fn get_path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn set_path(&mut self, path: Path) {
+        self.path = path;
+    }
+
+    /// The contents of the symbolic link, if this is one.
+    pub fn get_symlink(&self) -> Option<&Path> {
+        self.symlink.as_ref()
+    }
+}
+
+impl PartialEq for FileStatus {
+    /// Compare if this object is equal to another object by path.
+    fn eq(&self, other: &Self) -> bool {
+        self.path == other.path
+    }
+}
+
+impl fmt::Display for FileStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "FileStatus{{path={}; isDirectory={}",
+            self.path, self.isdir
+        )?;
+        if !self.isdir {
+            write!(
+                f,
+                "; length={}; replication={}; blocksize={}",
+                self.length, self.block_replication, self.blocksize
+            )?;
+        }
+        write!(
+            f,
+            "; modification_time={}; access_time={}; owner={}; group={}; permission={}; isSymlink={}",
+            self.modification_time,
+            self.access_time,
+            self.owner,
+            self.group,
+            self.permission,
+            self.is_symlink()
+        )?;
+        if let Some(symlink) = &self.symlink {
+            write!(f, "; symlink={}", symlink)?;
+        }
+        write!(
+            f,
+            "; hasAcl={}; isEncrypted={}; isErasureCoded={}}}",
+            self.has_acl(),
+            self.is_encrypted(),
+            self.is_erasure_coded()
+        )
+    }
+}
@@ -1,9 +1,14 @@
 use super::{
-    common_configuration_keys, common_configuration_keys_public, permission::FsPermission, Path,
+    common_configuration_keys, common_configuration_keys_public,
+    options::Rename,
+    path_io_exception,
+    permission::{FsAction, FsPermission},
+    BlockLocation, ContentSummary, FSDataInputStream, FSDataOutputStream, FileChecksum, FileStatus,
+    FsStatus, Globber, LocatedFileStatus, PartialListing, Path,
 };
-use crate::common::conf::Configuration;
+use crate::common::{conf::Configuration, io::IOException, security::UserGroupInformation};
 use anyhow::Error;
-use iref::Iri;
+use iref::{Iri, IriBuf};
 use std::any::type_name;
 
 pub const FS_DEFAULT_NAME_KEY: &str = common_configuration_keys::FS_DEFAULT_NAME_KEY;
@@ -12,16 +17,55 @@ pub const DEFAULT_FS: &str = common_configuration_keys::FS_DEFAULT_NAME_DEFAULT;
 pub const TRASH_PREFIX: &str = ".Trash";
 pub const USER_HOME_PREFIX: &str = "/user";
 
+/// An iterator over a collection whose elements need to be fetched remotely.
+pub type RemoteIterator<'a, T> = Box<dyn Iterator<Item = anyhow::Result<T>> + 'a>;
+
+/// Get the default FileSystem URI from a configuration.
+pub fn get_default_uri(conf: &Configuration) -> anyhow::Result<IriBuf> {
+    Ok(IriBuf::new(conf.get_trimmed_with_default(
+        common_configuration_keys::FS_DEFAULT_NAME_KEY,
+        common_configuration_keys::FS_DEFAULT_NAME_DEFAULT,
+    ))?)
+}
+
+/// Set the default FileSystem URI in a configuration.
+///
+/// Old-style names are updated: `local` becomes `file:///` and a bare
+/// `host:port` is taken as `hdfs://host:port/`.
+pub fn set_default_uri(conf: &mut Configuration, uri: &str) {
+    conf.set(FS_DEFAULT_NAME_KEY, &fix_name(uri));
+}
+
+/// Update old-format filesystem names, for back-compatibility.
+fn fix_name(name: &str) -> String {
+    if name == "local" {
+        log::warn!("\"local\" is a deprecated filesystem name. Use \"file:///\" instead.");
+        "file:///".to_owned()
+    } else if !name.contains('/') {
+        log::warn!(
+            "\"{}\" is a deprecated filesystem name. Use \"hdfs://{}/\" instead.",
+            name,
+            name
+        );
+        format!("hdfs://{}", name)
+    } else {
+        name.to_owned()
+    }
+}
+
 /// An abstract base class for a fairly generic filesystem.  It
 /// may be implemented as a distributed filesystem, or as a "local"
 /// one that reflects the locally-connected disk.  The local version
 /// exists for small Hadoop instances and for testing.
 pub trait FileSystem {
-    type FileSystemImpl: FileSystem;
-
-    fn new(uri: &Iri, conf: &Configuration) -> anyhow::Result<Self::FileSystemImpl>;
+    fn new(uri: &Iri, conf: &Configuration) -> anyhow::Result<Self>
+    where
+        Self: Sized;
 
-    fn get_resolve_symlinks(conf: &Configuration) -> bool {
+    fn get_resolve_symlinks(conf: &Configuration) -> bool
+    where
+        Self: Sized,
+    {
         conf.get_bool(
             common_configuration_keys_public::FS_CLIENT_RESOLVE_REMOTE_SYMLINKS_KEY,
             common_configuration_keys_public::FS_CLIENT_RESOLVE_REMOTE_SYMLINKS_DEFAULT,
@@ -29,11 +73,11 @@ pub trait FileSystem {
     }
 
     /// Get the default FileSystem URI from a configuration.
-    fn get_default_uri(conf: &Configuration) -> anyhow::Result<&Iri> {
-        Ok(Iri::new(conf.get_trimmed_with_default(
-            common_configuration_keys::FS_DEFAULT_NAME_KEY,
-            common_configuration_keys::FS_DEFAULT_NAME_DEFAULT,
-        ))?)
+    fn get_default_uri(conf: &Configuration) -> anyhow::Result<IriBuf>
+    where
+        Self: Sized,
+    {
+        get_default_uri(conf)
     }
 
     /// Should symbolic links be resolved by `FileSystemLinkResolver`.
@@ -41,10 +85,7 @@ pub trait FileSystem {
 
     /// Return the protocol scheme for this FileSystem.
     fn get_scheme(&self) -> anyhow::Result<&str> {
-        Err(Error::msg(format!(
-            "Not implemented by the {} FileSystem implementation",
-            type_name::<Self>().split("::").last().unwrap_or_default()
-        )))
+        Err(not_implemented::<Self>())
     }
 
     /// Returns a URI which identifies this FileSystem.
@@ -57,11 +98,444 @@ pub trait FileSystem {
     /// Get the current working directory for the given FileSystem
     fn get_working_directory(&self) -> &Path;
 
+    /// Qualify a path to one which uses this FileSystem and, if relative,
+    /// made absolute.
+    fn make_qualified(&self, path: &Path) -> anyhow::Result<Path> {
+        path.make_qualified(self.get_uri(), self.get_working_directory())
+    }
+
     /// Make the given file and all non-existent parents into
     /// directories. Has roughly the semantics of Unix `mkdir -p`.
     /// Existence of the directory hierarchy is not an error.
     fn mkdirs(&self, f: &Path, permission: Option<&FsPermission>) -> anyhow::Result<bool>;
 
+    /// Opens an [`FSDataInputStream`] at the indicated Path.
+    fn open(&self, _f: &Path) -> anyhow::Result<FSDataInputStream> {
+        Err(not_implemented::<Self>())
+    }
+
+    /// Create an [`FSDataOutputStream`] at the indicated Path with write-progress
+    /// reporting. Files are overwritten by default, parent directories are
+    /// created as needed, and default replication and block size of the
+    /// filesystem are used when not given.
+    fn create(
+        &self,
+        _f: &Path,
+        _permission: Option<&FsPermission>,
+        _overwrite: bool,
+        _replication: Option<i16>,
+        _block_size: Option<i64>,
+    ) -> anyhow::Result<FSDataOutputStream> {
+        Err(not_implemented::<Self>())
+    }
+
+    /// Append to an existing file (optional operation).
+    fn append(&self, _f: &Path) -> anyhow::Result<FSDataOutputStream> {
+        Err(not_implemented::<Self>())
+    }
+
+    /// Append to an existing file (optional operation).
+    ///
+    /// If `append_to_new_block` is set, append data to a new block instead of
+    /// the end of the last partial block. File systems which do not support
+    /// it ignore the flag.
+    fn append_with_new_block(
+        &self,
+        f: &Path,
+        _append_to_new_block: bool,
+    ) -> anyhow::Result<FSDataOutputStream> {
+        self.append(f)
+    }
+
+    /// Concat existing files together.
+    ///
+    /// `trg` is the path to the target destination, `psrcs` the paths to the
+    /// sources to use for the concatenation.
+    fn concat(&self, _trg: &Path, _psrcs: &[Path]) -> anyhow::Result<()> {
+        Err(not_implemented::<Self>())
+    }
+
+    /// Return a file status object that represents the path.
+    fn get_file_status(&self, _f: &Path) -> anyhow::Result<FileStatus> {
+        Err(not_implemented::<Self>())
+    }
+
+    /// Return a file status object that represents the path, together with
+    /// the block locations of the whole file if the path is a file.
+    fn get_located_file_status(&self, f: &Path) -> anyhow::Result<LocatedFileStatus> {
+        let stat = self.get_file_status(f)?;
+        let locs = if stat.is_file() {
+            self.get_file_block_locations(&stat, 0, stat.get_len())?
+        } else {
+            vec![]
+        };
+        Ok(LocatedFileStatus::new(stat, locs))
+    }
+
+    /// List the statuses of the files/directories in the given path if the path is
+    /// a directory.
+    ///
+    /// Does not guarantee to return the List of files/directories status in a
+    /// sorted order.
+    fn list_status(&self, _f: &Path) -> anyhow::Result<Vec<FileStatus>> {
+        Err(not_implemented::<Self>())
+    }
+
+    /// Returns a remote iterator so that followup calls are made on demand
+    /// while consuming the entries. Each FileSystem implementation should
+    /// override this method and provide a more efficient implementation, if
+    /// possible.
+    ///
+    /// Does not guarantee to return the iterator that traverses statuses
+    /// of the files in a sorted order.
+    fn list_status_iterator(&self, p: &Path) -> anyhow::Result<RemoteIterator<'_, FileStatus>> {
+        Ok(Box::new(self.list_status(p)?.into_iter().map(Ok)))
+    }
+
+    /// List the statuses of the files/directories in the given path if the path is
+    /// a directory. Return the file's status and block locations if the path is a
+    /// file.
+    ///
+    /// If a returned status is a file, it contains the file's block locations.
+    fn list_located_status(
+        &self,
+        f: &Path,
+    ) -> anyhow::Result<RemoteIterator<'_, LocatedFileStatus>> {
+        let statuses = self.list_status(f)?;
+        Ok(Box::new(statuses.into_iter().map(move |stat| {
+            // get the block locations of a file
+            let locs = if stat.is_file() {
+                self.get_file_block_locations(&stat, 0, stat.get_len())?
+            } else {
+                vec![]
+            };
+            Ok(LocatedFileStatus::new(stat, locs))
+        })))
+    }
+
+    /// Batched listing API that returns [`PartialListing`]s for the
+    /// passed Paths.
+    fn batched_list_status_iterator(
+        &self,
+        _paths: &[Path],
+    ) -> anyhow::Result<RemoteIterator<'_, PartialListing<FileStatus>>> {
+        Err(not_implemented::<Self>())
+    }
+
+    /// Batched listing API that returns [`PartialListing`]s for the passed
+    /// Paths. The PartialListing will contain [`LocatedFileStatus`] entries
+    /// with locations.
+    fn batched_list_located_status_iterator(
+        &self,
+        _paths: &[Path],
+    ) -> anyhow::Result<RemoteIterator<'_, PartialListing<LocatedFileStatus>>> {
+        Err(not_implemented::<Self>())
+    }
+
+    /// Return all the files that match `path_pattern` and are not checksum
+    /// files. Results are sorted by their names.
+    ///
+    /// Returns `None` if `path_pattern` has no glob and the path does not exist,
+    /// and an empty vector if `path_pattern` has a glob and no path matches it.
+    fn glob_status(&self, path_pattern: &Path) -> anyhow::Result<Option<Vec<FileStatus>>> {
+        Globber::new(self, path_pattern).glob()
+    }
+
+    /// Check if a path exists.
+    fn exists(&self, f: &Path) -> anyhow::Result<bool> {
+        match self.get_file_status(f) {
+            Ok(_) => Ok(true),
+            Err(e) if path_io_exception::is_file_not_found(&e) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Delete a file.
+    ///
+    /// If `f` is a directory and `recursive` is not set, an error is returned
+    /// unless the directory is empty.
+    fn delete(&self, _f: &Path, _recursive: bool) -> anyhow::Result<bool> {
+        Err(not_implemented::<Self>())
+    }
+
+    /// Renames Path src to Path dst.
+    fn rename(&self, _src: &Path, _dst: &Path) -> anyhow::Result<bool> {
+        Err(not_implemented::<Self>())
+    }
+
+    /// Renames Path src to Path dst.
+    ///
+    /// - Fails if src is a file and dst is a directory.
+    /// - Fails if src is a directory and dst is a file.
+    /// - Fails if the parent of dst does not exist or is a file.
+    ///
+    /// If OVERWRITE option is not passed as an argument, rename fails
+    /// if the dst already exists.
+    ///
+    /// If OVERWRITE option is passed as an argument, rename overwrites
+    /// the dst if it is a file or an empty directory. Rename fails if dst is
+    /// a non-empty directory.
+    ///
+    /// Note that atomicity of rename is dependent on the file system
+    /// implementation. The default implementation is not atomic.
+    fn rename2(&self, src: &Path, dst: &Path, options: &[Rename]) -> anyhow::Result<()> {
+        // Default implementation
+        let src_status = match self.get_file_status(src) {
+            Ok(status) => status,
+            Err(e) if path_io_exception::is_file_not_found(&e) => {
+                return Err(
+                    IOException::FileNotFound(format!("rename source {} not found.", src)).into(),
+                )
+            }
+            Err(e) => return Err(e),
+        };
+        let overwrite = options.contains(&Rename::Overwrite);
+
+        match self.get_file_status(dst) {
+            Ok(dst_status) => {
+                if src_status.is_directory() != dst_status.is_directory() {
+                    return Err(Error::msg(format!(
+                        "Source {} Destination {} both should be either file or directory",
+                        src, dst
+                    )));
+                }
+                if !overwrite {
+                    return Err(IOException::FileAlreadyExists(format!(
+                        "rename destination {} already exists.",
+                        dst
+                    ))
+                    .into());
+                }
+                // Delete the destination that is a file or an empty directory
+                if dst_status.is_directory() && !self.list_status(dst)?.is_empty() {
+                    return Err(Error::msg(format!(
+                        "rename cannot overwrite non empty destination directory {}",
+                        dst
+                    )));
+                }
+                self.delete(dst, false)?;
+            }
+            Err(e) if path_io_exception::is_file_not_found(&e) => {
+                if let Some(parent) = dst.get_parent() {
+                    let parent_status = match self.get_file_status(&parent) {
+                        Ok(status) => status,
+                        Err(e) if path_io_exception::is_file_not_found(&e) => {
+                            return Err(IOException::FileNotFound(format!(
+                                "rename destination parent {} not found.",
+                                parent
+                            ))
+                            .into())
+                        }
+                        Err(e) => return Err(e),
+                    };
+                    if !parent_status.is_directory() {
+                        return Err(IOException::ParentNotDirectory(format!(
+                            "rename destination parent {} is a file.",
+                            parent
+                        ))
+                        .into());
+                    }
+                }
+            }
+            Err(e) => return Err(e),
+        }
+        if !self.rename(src, dst)? {
+            return Err(Error::msg(format!(
+                "rename from {} to {} failed.",
+                src, dst
+            )));
+        }
+        Ok(())
+    }
+
+    /// Truncate the file in the indicated path to the indicated size.
+    /// - Fails if path is a directory.
+    /// - Fails if path does not exist.
+    /// - Fails if path is not closed.
+    /// - Fails if new size is greater than current size.
+    ///
+    /// Returns true if the file has been truncated to the desired `new_length`
+    /// and is immediately available to be reused for write operations such as
+    /// append, or false if a background process of adjusting the length of
+    /// the last block has been started, and clients should wait for it to
+    /// complete before proceeding with further file updates.
+    fn truncate(&self, _f: &Path, _new_length: i64) -> anyhow::Result<bool> {
+        Err(not_implemented::<Self>())
+    }
+
+    /// Set permission of a path.
+    fn set_permission(&self, _p: &Path, _permission: &FsPermission) -> anyhow::Result<()> {
+        Err(not_implemented::<Self>())
+    }
+
+    /// Set owner of a path (i.e. a file or a directory).
+    /// The parameters username and groupname cannot both be `None`.
+    fn set_owner(
+        &self,
+        _p: &Path,
+        _username: Option<&str>,
+        _groupname: Option<&str>,
+    ) -> anyhow::Result<()> {
+        Err(not_implemented::<Self>())
+    }
+
+    /// Set the replication for an existing file.
+    /// If a filesystem does not support replication, it will always
+    /// return true: the check for a file existing may be bypassed.
+    fn set_replication(&self, _src: &Path, _replication: i16) -> anyhow::Result<bool> {
+        Ok(true)
+    }
+
+    /// Set the modification and access time of a file.
+    /// A value of -1 means that this call should not set the time.
+    fn set_times(&self, _p: &Path, _mtime: i64, _atime: i64) -> anyhow::Result<()> {
+        Err(not_implemented::<Self>())
+    }
+
+    /// Return the [`ContentSummary`] of a given Path.
+    fn get_content_summary(&self, f: &Path) -> anyhow::Result<ContentSummary> {
+        let status = self.get_file_status(f)?;
+        if status.is_file() {
+            // f is a file
+            let length = status.get_len();
+            return Ok(ContentSummary::new(length, 1, 0, -1, length, -1));
+        }
+        // f is a directory
+        let (mut length, mut file_count, mut directory_count) = (0, 0, 1);
+        for s in self.list_status(f)? {
+            if s.is_directory() {
+                let c = self.get_content_summary(s.get_path())?;
+                length += c.get_length();
+                file_count += c.get_file_count();
+                directory_count += c.get_directory_count();
+            } else {
+                length += s.get_len();
+                file_count += 1;
+            }
+        }
+        Ok(ContentSummary::new(
+            length,
+            file_count,
+            directory_count,
+            -1,
+            length,
+            -1,
+        ))
+    }
+
+    /// Returns a status object describing the use and capacity of the
+    /// filesystem. If the filesystem has multiple partitions, the
+    /// use and capacity of the partition pointed to by the specified
+    /// path is reflected.
+    fn get_status(&self, _p: Option<&Path>) -> anyhow::Result<FsStatus> {
+        Ok(FsStatus::new(i64::MAX, 0, i64::MAX))
+    }
+
+    /// Get the checksum of a file, if the FS supports checksums.
+    fn get_file_checksum(&self, f: &Path) -> anyhow::Result<Option<Box<dyn FileChecksum>>> {
+        self.get_file_checksum_with_length(f, i64::MAX)
+    }
+
+    /// Get the checksum of a file, from the beginning of the file till the
+    /// specific length, if the FS supports checksums.
+    fn get_file_checksum_with_length(
+        &self,
+        _f: &Path,
+        _length: i64,
+    ) -> anyhow::Result<Option<Box<dyn FileChecksum>>> {
+        Ok(None)
+    }
+
+    /// Return an array containing hostnames, offset and size of
+    /// portions of the given file.  For nonexistent
+    /// file or regions, an empty vector will be returned.
+    ///
+    /// This call is most helpful with location-aware distributed
+    /// filesystems, where it returns hostnames of machines that
+    /// contain the given file. A FileSystem will normally return the
+    /// equivalent result of passing the [`FileStatus`] of the path to
+    /// this method.
+    ///
+    /// The default implementation returns an array containing one element:
+    /// `BlockLocation { names: ["localhost:9866"], hosts: ["localhost"], offset: 0, length: file.get_len() }`
+    fn get_file_block_locations(
+        &self,
+        file: &FileStatus,
+        start: i64,
+        len: i64,
+    ) -> anyhow::Result<Vec<BlockLocation>> {
+        if start < 0 || len < 0 {
+            return Err(Error::msg("Invalid start or len parameter"));
+        }
+        if file.get_len() <= start {
+            return Ok(vec![]);
+        }
+        let name = vec!["localhost:9866".to_owned()];
+        let host = vec!["localhost".to_owned()];
+        Ok(vec![BlockLocation::new(
+            name,
+            host,
+            vec![],
+            vec![],
+            vec![],
+            vec![],
+            0,
+            file.get_len(),
+            false,
+        )])
+    }
+
+    /// Return the number of bytes that large input files should be optimally
+    /// be split into to minimize I/O time.
+    fn get_default_block_size(&self, _f: &Path) -> i64 {
+        32 * 1024 * 1024
+    }
+
+    /// Get the default replication for a path.
+    fn get_default_replication(&self, _path: &Path) -> i16 {
+        1
+    }
+
+    /// Checks if the user can access a path.  The mode specifies which access
+    /// checks to perform.  If the requested permissions are granted, then the
+    /// method returns normally.  If access is denied, then the method returns
+    /// an error.
+    fn access(&self, path: &Path, mode: FsAction) -> anyhow::Result<()> {
+        let stat = self.get_file_status(path)?;
+        let perm = stat.get_permission();
+        let ugi = UserGroupInformation::get_current_user()?;
+        let user = ugi.get_short_user_name();
+        let granted = if user == stat.get_owner() {
+            perm.get_user_action()
+        } else if ugi.get_group_names().iter().any(|g| g == stat.get_group()) {
+            perm.get_group_action()
+        } else {
+            perm.get_other_action()
+        };
+        if granted.implies(Some(&mode)) {
+            Ok(())
+        } else {
+            Err(Error::msg(format!(
+                "Permission denied: user={}, path=\"{}\":{}:{}:{}{}",
+                user,
+                path,
+                stat.get_owner(),
+                stat.get_group(),
+                if stat.is_directory() { "d" } else { "-" },
+                perm
+            )))
+        }
+    }
+
+    /// Get the root directory of Trash for current user when the path specified
+    /// is deleted.
+    fn get_trash_root(&self, _path: &Path) -> anyhow::Result<Path> {
+        self.make_qualified(&Path::from_parent_str(
+            &self.get_home_directory()?,
+            TRASH_PREFIX,
+        )?)
+    }
+
     /// See [`FileContext#fix_relative_part`]
     fn fix_relative_part(&self, p: &Path) -> anyhow::Result<Path> {
         if p.is_uri_path_absolute() {
@@ -71,3 +545,104 @@ pub trait FileSystem {
         }
     }
 }
+
+/// The error of an operation the FileSystem `F` does not support.
+fn not_implemented<F: FileSystem + ?Sized>() -> Error {
+    Error::msg(format!(
+        "Not implemented by the {} FileSystem implementation",
+        type_name::<F>().split("::").last().unwrap_or_default()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::fs::RawLocalFileSystem;
+    use std::{env, fs};
+    use uuid::Uuid;
+
+    #[test]
+    fn test_rename2() -> anyhow::Result<()> {
+        let uri = IriBuf::new("file:///".to_owned())?;
+        let local = RawLocalFileSystem::new(uri.as_iri(), &Configuration::new())?;
+        let dir = env::temp_dir().join(format!("rename2-{}", Uuid::new_v4()));
+        fs::create_dir(&dir)?;
+        fs::write(dir.join("a"), "a")?;
+        fs::write(dir.join("b"), "b")?;
+        let path = |name: &str| Path::new(dir.join(name).to_str().unwrap());
+        let io_exception = |e: Error| e.downcast::<IOException>().unwrap();
+
+        let e = local.rename2(&path("a")?, &path("b")?, &[Rename::None]);
+        assert!(matches!(
+            io_exception(e.unwrap_err()),
+            IOException::FileAlreadyExists(_)
+        ));
+        let e = local.rename2(&path("c")?, &path("d")?, &[]);
+        assert!(matches!(
+            io_exception(e.unwrap_err()),
+            IOException::FileNotFound(_)
+        ));
+        let e = local.rename2(&path("a")?, &path("b/c")?, &[]);
+        assert!(matches!(
+            io_exception(e.unwrap_err()),
+            IOException::ParentNotDirectory(_)
+        ));
+
+        local.rename2(&path("a")?, &path("b")?, &[Rename::Overwrite])?;
+        assert!(!dir.join("a").exists());
+        assert_eq!(fs::read_to_string(dir.join("b"))?, "a");
+
+        // only a missing destination is renamed to, other errors are
+        // returned
+        let denied = DeniedFileSystem(local);
+        let e = denied.rename2(&path("b")?, &path("denied")?, &[]);
+        assert!(matches!(
+            io_exception(e.unwrap_err()),
+            IOException::AccessControl(_)
+        ));
+        assert!(dir.join("b").exists());
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    /// A local file system which denies access to the status of the files
+    /// named "denied".
+    struct DeniedFileSystem(RawLocalFileSystem);
+
+    impl FileSystem for DeniedFileSystem {
+        fn new(uri: &Iri, conf: &Configuration) -> anyhow::Result<Self> {
+            Ok(Self(RawLocalFileSystem::new(uri, conf)?))
+        }
+
+        fn resolve_symlinks(&self) -> bool {
+            self.0.resolve_symlinks()
+        }
+
+        fn get_uri(&self) -> &Iri {
+            self.0.get_uri()
+        }
+
+        fn get_home_directory(&self) -> anyhow::Result<Path> {
+            self.0.get_home_directory()
+        }
+
+        fn get_working_directory(&self) -> &Path {
+            self.0.get_working_directory()
+        }
+
+        fn mkdirs(&self, f: &Path, permission: Option<&FsPermission>) -> anyhow::Result<bool> {
+            self.0.mkdirs(f, permission)
+        }
+
+        fn get_file_status(&self, f: &Path) -> anyhow::Result<FileStatus> {
+            if f.get_name() == "denied" {
+                return Err(IOException::AccessControl(format!("Permission denied: {}", f)).into());
+            }
+            self.0.get_file_status(f)
+        }
+
+        fn rename(&self, src: &Path, dst: &Path) -> anyhow::Result<bool> {
+            self.0.rename(src, dst)
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use std::{
+    fs::File,
+    io::{self, Read, Seek, SeekFrom},
+    os::unix::fs::FileExt,
+};
+
+/// FSInputStream is a generic old InputStream with a little bit
+/// of RAF-style seek ability.
+///
+/// Positional reads do not change the current offset of the stream and
+/// may be made concurrently from several threads.
+pub trait FSInputStream: Read + Seek + Send + Sync {
+    /// Read up to `buf.len()` bytes from a given position within the stream,
+    /// returning the number of bytes read. This does not change the current
+    /// offset of the stream.
+    fn read_at(&self, position: u64, buf: &mut [u8]) -> io::Result<usize>;
+
+    /// Read exactly `buf.len()` bytes from a given position within the stream.
+    /// This does not change the current offset of the stream.
+    fn read_fully(&self, position: u64, buf: &mut [u8]) -> io::Result<()> {
+        let mut n_read = 0;
+        while n_read < buf.len() {
+            let nbytes = self.read_at(position + n_read as u64, &mut buf[n_read..])?;
+            if nbytes == 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "End of file reached before reading fully.",
+                ));
+            }
+            n_read += nbytes;
+        }
+        Ok(())
+    }
+}
+
+impl FSInputStream for File {
+    fn read_at(&self, position: u64, buf: &mut [u8]) -> io::Result<usize> {
+        FileExt::read_at(self, buf, position)
+    }
+}
+
+/// Utility that wraps a [`FSInputStream`] in a [`Read`]
+/// and buffers input through a [`Seek`].
+pub struct FSDataInputStream {
+    input: Box<dyn FSInputStream>,
+}
+
+impl FSDataInputStream {
+    pub fn new(input: Box<dyn FSInputStream>) -> Self {
+        Self { input }
+    }
+
+    /// Get the current position in the input stream.
+    pub fn get_pos(&mut self) -> io::Result<u64> {
+        self.input.stream_position()
+    }
+
+    /// Read bytes from the given position in the stream to the given buffer.
+    ///
+    /// Returns the number of bytes read, 0 at the end of the stream.
+    pub fn read_at(&self, position: u64, buf: &mut [u8]) -> io::Result<usize> {
+        self.input.read_at(position, buf)
+    }
+
+    /// Read bytes from the given position in the stream to the given buffer.
+    /// Continues to read until `buf` is filled.
+    pub fn read_fully(&self, position: u64, buf: &mut [u8]) -> io::Result<()> {
+        self.input.read_fully(position, buf)
+    }
+}
+
+impl Read for FSDataInputStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.input.read(buf)
+    }
+}
+
+impl Seek for FSDataInputStream {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.input.seek(pos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{env, fs};
+    use uuid::Uuid;
+
+    #[test]
+    fn test_positioned_read() -> io::Result<()> {
+        let path = env::temp_dir().join(format!("pread-{}", Uuid::new_v4()));
+        fs::write(&path, "0123456789")?;
+        let mut input = FSDataInputStream::new(Box::new(File::open(&path)?));
+
+        input.seek(SeekFrom::Start(2))?;
+        let mut buf = [0; 4];
+        assert_eq!(4, input.read_at(5, &mut buf)?);
+        assert_eq!(b"5678", &buf);
+        input.read_fully(0, &mut buf)?;
+        assert_eq!(b"0123", &buf);
+        assert_eq!(2, input.get_pos()?);
+
+        let e = input.read_fully(8, &mut buf).unwrap_err();
+        assert_eq!(io::ErrorKind::UnexpectedEof, e.kind());
+
+        // positional reads may be made from several threads at once
+        let input = &input;
+        std::thread::scope(|scope| {
+            for i in 0..5 {
+                scope.spawn(move || {
+                    let mut buf = [0; 2];
+                    input.read_fully(i * 2, &mut buf).unwrap();
+                    assert_eq!(format!("{}{}", i * 2, i * 2 + 1).as_bytes(), &buf);
+                });
+            }
+        });
+        fs::remove_file(&path)?;
+        Ok(())
+    }
+}
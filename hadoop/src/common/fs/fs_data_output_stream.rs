@@ -0,0 +1,96 @@
+use crate::hdfs::hdfs::SyncFlag;
+use std::{
+    fs::File,
+    io::{self, Write},
+};
+
+/// A [`Write`] which must be closed explicitly, so that failures to
+/// persist the written data are reported to the caller.
+pub trait OutputStream: Write {
+    /// Closes this output stream and releases any system resources
+    /// associated with this stream.
+    fn close(&mut self) -> io::Result<()>;
+
+    /// Flush out the data in client's user buffer. After the return of
+    /// this call, new readers will see the data.
+    fn hflush(&mut self) -> io::Result<()> {
+        self.flush()
+    }
+
+    /// Similar to posix fsync, flush out the data in client's user buffer
+    /// all the way to the disk device (but the disk may have it in its cache).
+    fn hsync(&mut self) -> io::Result<()> {
+        self.hflush()
+    }
+
+    /// Similar to [`hsync`](Self::hsync), with `sync_flags` indicating
+    /// what else to do besides syncing the data.
+    fn hsync_with_flags(&mut self, _sync_flags: &[SyncFlag]) -> io::Result<()> {
+        self.hsync()
+    }
+}
+
+impl OutputStream for File {
+    fn close(&mut self) -> io::Result<()> {
+        self.flush()
+    }
+
+    fn hsync(&mut self) -> io::Result<()> {
+        self.sync_all()
+    }
+}
+
+/// Utility that wraps a [`OutputStream`] in a [`Write`].
+pub struct FSDataOutputStream {
+    out: Box<dyn OutputStream>,
+    position: u64,
+}
+
+impl FSDataOutputStream {
+    pub fn new(out: Box<dyn OutputStream>, start_position: u64) -> Self {
+        Self {
+            out,
+            position: start_position,
+        }
+    }
+
+    /// Get the current position in the output stream.
+    pub fn get_pos(&self) -> u64 {
+        self.position
+    }
+
+    /// Flush out the data in client's user buffer, so that new readers
+    /// will see the data.
+    pub fn hflush(&mut self) -> io::Result<()> {
+        self.out.hflush()
+    }
+
+    /// Flush out the data in client's user buffer all the way to the disk
+    /// device.
+    pub fn hsync(&mut self) -> io::Result<()> {
+        self.out.hsync()
+    }
+
+    /// Sync the data like [`hsync`](Self::hsync), also doing what the
+    /// `sync_flags` ask for.
+    pub fn hsync_with_flags(&mut self, sync_flags: &[SyncFlag]) -> io::Result<()> {
+        self.out.hsync_with_flags(sync_flags)
+    }
+
+    /// Close the underlying output stream.
+    pub fn close(&mut self) -> io::Result<()> {
+        self.out.close()
+    }
+}
+
+impl Write for FSDataOutputStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.out.write(buf)?;
+        self.position += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
@@ -0,0 +1,511 @@
+use super::shell::{self, Command, CommandContext, IllegalArgumentException};
+use crate::common::{conf::Configuration, util::GenericOptionsParser};
+use std::io::{self, Write};
+
+const USAGE_PREFIX: &str = "Usage: hadoop fs [generic options]";
+
+/// Provide command line access to a FileSystem.
+pub struct FsShell {
+    conf: Configuration,
+}
+
+impl FsShell {
+    /// Construct a FsShell with the given configuration.  Commands can be
+    /// executed via [`FsShell::run`].
+    pub fn new(conf: Configuration) -> Self {
+        Self { conf }
+    }
+
+    pub fn get_conf(&self) -> &Configuration {
+        &self.conf
+    }
+
+    /// run, writing the output of the commands to stdout and their errors to
+    /// stderr.
+    pub fn run(&self, argv: &[String]) -> i32 {
+        self.run_with(argv, &mut io::stdout().lock(), &mut io::stderr().lock())
+    }
+
+    /// run the command `argv[0]` with the arguments `argv[1..]`, returning its
+    /// exit code.
+    pub fn run_with(&self, argv: &[String], out: &mut dyn Write, err: &mut dyn Write) -> i32 {
+        let mut exit_code = -1;
+        let Some(cmd) = argv.first() else {
+            let _ = print_usage(err, None);
+            return exit_code;
+        };
+        match cmd.as_str() {
+            "-help" => return self.print_info(out, &argv[1..], true),
+            "-usage" => return self.print_info(out, &argv[1..], false),
+            _ => {}
+        }
+
+        let name = cmd.strip_prefix('-').unwrap_or_default();
+        let instance = cmd
+            .starts_with('-')
+            .then(|| shell::get_instance(name))
+            .flatten();
+        let Some(mut instance) = instance else {
+            display_error(err, cmd, "Unknown command");
+            let _ = print_usage(err, None);
+            return exit_code;
+        };
+
+        let mut ctx = CommandContext::new(&self.conf, name, out, err);
+        match shell::run(instance.as_mut(), &mut ctx, argv[1..].to_vec()) {
+            Ok(code) => exit_code = code,
+            Err(e) => {
+                drop(ctx);
+                if e.is::<IllegalArgumentException>() {
+                    display_error(err, cmd, &e.to_string());
+                    let _ = print_usage(err, Some((name, instance.as_ref())));
+                } else {
+                    // run catches the path errors, so something is REALLY wrong if here
+                    log::debug!("Error: {:?}", e);
+                    display_error(err, cmd, "Fatal internal error");
+                    let _ = writeln!(err, "{:?}", e);
+                }
+            }
+        }
+        exit_code
+    }
+
+    /// Displays the usage, or the help with the descriptions, of the given
+    /// commands or of all the commands if none are given.
+    fn print_info(&self, out: &mut dyn Write, cmds: &[String], show_help: bool) -> i32 {
+        let mut exit_code = 0;
+        let names: Vec<&str> = if cmds.is_empty() {
+            if !show_help {
+                let _ = print_usage(out, None);
+                return exit_code;
+            }
+            let _ = writeln!(out, "{}", USAGE_PREFIX);
+            let _ = print_command_list(out);
+            let _ = writeln!(out);
+            shell::get_names().collect()
+        } else {
+            cmds.iter()
+                .map(|cmd| cmd.strip_prefix('-').unwrap_or(cmd))
+                .collect()
+        };
+        for name in names {
+            match shell::get_instance(name) {
+                Some(instance) if show_help => {
+                    let _ = print_instance_help(out, name, instance.as_ref());
+                }
+                Some(instance) => {
+                    let _ = print_instance_usage(out, name, instance.as_ref());
+                }
+                None => {
+                    let _ = writeln!(out, "{}: Unknown command", name);
+                    exit_code = 1;
+                }
+            }
+        }
+        if cmds.is_empty() {
+            let _ = GenericOptionsParser::print_generic_command_usage(&mut &mut *out);
+        }
+        exit_code
+    }
+}
+
+/// The usage of a command, with its name.
+fn get_usage(name: &str, instance: &dyn Command) -> String {
+    match instance.get_usage() {
+        "" => format!("-{}", name),
+        usage => format!("-{} {}", name, usage),
+    }
+}
+
+/// Display the usage of all the commands, or of the given command.
+fn print_usage(out: &mut dyn Write, instance: Option<(&str, &dyn Command)>) -> io::Result<()> {
+    if let Some((name, instance)) = instance {
+        return print_instance_usage(out, name, instance);
+    }
+    writeln!(out, "{}", USAGE_PREFIX)?;
+    print_command_list(out)?;
+    writeln!(out)?;
+    GenericOptionsParser::print_generic_command_usage(&mut &mut *out)
+}
+
+fn print_command_list(out: &mut dyn Write) -> io::Result<()> {
+    for name in shell::get_names() {
+        if let Some(instance) = shell::get_instance(name) {
+            writeln!(out, "\t[{}]", get_usage(name, instance.as_ref()))?;
+        }
+    }
+    Ok(())
+}
+
+fn print_instance_usage(out: &mut dyn Write, name: &str, instance: &dyn Command) -> io::Result<()> {
+    writeln!(out, "{} {}", USAGE_PREFIX, get_usage(name, instance))
+}
+
+fn print_instance_help(out: &mut dyn Write, name: &str, instance: &dyn Command) -> io::Result<()> {
+    writeln!(out, "{} :", get_usage(name, instance))?;
+    for line in instance.get_description().split('\n') {
+        writeln!(out, "  {}", line)?;
+    }
+    writeln!(out)
+}
+
+fn display_error(err: &mut dyn Write, cmd: &str, message: &str) {
+    for line in message.split('\n') {
+        let _ = writeln!(err, "{}: {}", cmd, line);
+        if !cmd.starts_with('-') && shell::get_instance(cmd).is_some() {
+            let _ = writeln!(
+                err,
+                "Did you mean -{}?  This command begins with a dash.",
+                cmd
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::fs::file_system;
+    use std::{env, fs, path::PathBuf};
+    use uuid::Uuid;
+
+    /// A directory of the local file system, removed with its content when
+    /// dropped so that a failed assertion does not leave it behind.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new() -> Self {
+            let dir = env::temp_dir().join(format!("fs-shell-{}", Uuid::new_v4()));
+            fs::create_dir(&dir).unwrap();
+            Self(dir)
+        }
+
+        fn path(&self) -> &str {
+            self.0.to_str().unwrap()
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn local_shell() -> FsShell {
+        let mut conf = Configuration::new();
+        file_system::set_default_uri(&mut conf, "file:///");
+        FsShell::new(conf)
+    }
+
+    fn run(shell: &FsShell, args: &[&str]) -> (i32, String, String) {
+        let args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+        let (mut out, mut err) = (vec![], vec![]);
+        let exit_code = shell.run_with(&args, &mut out, &mut err);
+        (
+            exit_code,
+            String::from_utf8(out).unwrap(),
+            String::from_utf8(err).unwrap(),
+        )
+    }
+
+    /// The columns of the lines of a listing, without the modification time.
+    fn ls_columns(out: &str) -> Vec<Vec<&str>> {
+        out.lines()
+            .map(|line| {
+                let columns: Vec<&str> = line.split_whitespace().collect();
+                [&columns[..5], &columns[7..]].concat()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_shell_commands() {
+        let shell = local_shell();
+        let tmp = TempDir::new();
+        let dir = tmp.path();
+        let local = format!("{}/local.txt", dir);
+        fs::write(&local, "hello\n").unwrap();
+
+        let (code, _, _) = run(&shell, &["-mkdir", "-p", &format!("{}/a/b", dir)]);
+        assert_eq!(code, 0);
+        let (code, _, err) = run(&shell, &["-mkdir", &format!("{}/a", dir)]);
+        assert_eq!(code, 1);
+        assert_eq!(err, format!("mkdir: `{}/a': File exists\n", dir));
+
+        let (code, _, _) = run(&shell, &["-put", &local, &format!("{}/a", dir)]);
+        assert_eq!(code, 0);
+        let (code, out, _) = run(&shell, &["-cat", &format!("{}/a/*.txt", dir)]);
+        assert_eq!((code, out.as_str()), (0, "hello\n"));
+
+        let (code, out, _) = run(&shell, &["-ls", &format!("{}/a", dir)]);
+        assert_eq!(code, 0);
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines[0], "Found 2 items");
+        assert!(lines[1].starts_with('d') && lines[1].ends_with(&format!("{}/a/b", dir)));
+        assert!(lines[2].starts_with("-rw") && lines[2].contains(" 6 "));
+
+        let (code, out, _) = run(&shell, &["-stat", "%F %n", &format!("{}/a", dir)]);
+        assert_eq!((code, out.as_str()), (0, "directory a\n"));
+        let (code, _, _) = run(&shell, &["-test", "-d", &format!("{}/a", dir)]);
+        assert_eq!(code, 0);
+        let (code, _, _) = run(&shell, &["-test", "-e", &format!("{}/none", dir)]);
+        assert_eq!(code, 1);
+
+        let (code, out, _) = run(&shell, &["-rm", "-r", "-skipTrash", &format!("{}/a", dir)]);
+        assert_eq!((code, out), (0, format!("Deleted {}/a\n", dir)));
+        let (code, _, err) = run(&shell, &["-rm", &format!("{}/a", dir)]);
+        assert_eq!(code, 1);
+        assert_eq!(err, format!("rm: `{}/a': No such file or directory\n", dir));
+
+        let (code, _, err) = run(&shell, &["-ls", "-z"]);
+        assert_eq!(code, -1);
+        assert!(err.starts_with("-ls: Illegal option -z\nUsage: hadoop fs [generic options] -ls"));
+        let (code, _, err) = run(&shell, &["-foo"]);
+        assert_eq!(code, -1);
+        assert!(err.starts_with("-foo: Unknown command\n"));
+    }
+
+    #[test]
+    fn test_usage_commands() {
+        let shell = local_shell();
+        let tmp = TempDir::new();
+        let dir = tmp.path();
+        fs::create_dir_all(format!("{}/a/b", dir)).unwrap();
+        fs::write(format!("{}/a/f", dir), [0; 2048]).unwrap();
+        fs::write(format!("{}/a/b/g", dir), "world\n").unwrap();
+
+        let (code, out, _) = run(&shell, &["-du", &format!("{}/a", dir)]);
+        assert_eq!(code, 0);
+        assert_eq!(
+            out,
+            format!("6     6     {dir}/a/b\n2048  2048  {dir}/a/f\n", dir = dir)
+        );
+        let (code, out, _) = run(&shell, &["-du", "-s", "-h", &format!("{}/a", dir)]);
+        assert_eq!((code, out), (0, format!("2.0 K  2.0 K  {}/a\n", dir)));
+        let (code, _, err) = run(&shell, &["-du", &format!("{}/none", dir)]);
+        assert_eq!(code, 1);
+        assert_eq!(
+            err,
+            format!("du: `{}/none': No such file or directory\n", dir)
+        );
+
+        let (code, out, _) = run(&shell, &["-df", &format!("{}/a", dir)]);
+        assert_eq!(code, 0);
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(
+            lines[0].split_whitespace().collect::<Vec<_>>(),
+            [
+                "Filesystem",
+                "Size",
+                "Used",
+                "Available",
+                "Use%",
+                "Mounted",
+                "on"
+            ]
+        );
+        let columns: Vec<&str> = lines[1].split_whitespace().collect();
+        assert_eq!(columns[0], "file:///");
+        assert!(columns[1..4].iter().all(|c| c.parse::<u64>().is_ok()));
+        assert!(columns[4].ends_with('%'));
+
+        let (code, out, _) = run(&shell, &["-count", &format!("{}/a", dir)]);
+        assert_eq!(code, 0);
+        assert_eq!(out, format!("{:>12} {:>12} {:>18} {}/a\n", 2, 2, 2054, dir));
+        let (code, out, _) = run(&shell, &["-count", "-q", "-h", "-v", &format!("{}/a", dir)]);
+        assert_eq!(code, 0);
+        let lines: Vec<Vec<&str>> = out
+            .lines()
+            .map(|line| line.split_whitespace().collect())
+            .collect();
+        assert_eq!(
+            lines[0],
+            [
+                "QUOTA",
+                "REM_QUOTA",
+                "SPACE_QUOTA",
+                "REM_SPACE_QUOTA",
+                "DIR_COUNT",
+                "FILE_COUNT",
+                "CONTENT_SIZE",
+                "PATHNAME"
+            ]
+        );
+        let path = format!("{}/a", dir);
+        assert_eq!(
+            lines[1],
+            ["none", "inf", "none", "inf", "2", "2", "2.0", "K", &path]
+        );
+    }
+
+    #[test]
+    fn test_ls_options() {
+        let shell = local_shell();
+        let tmp = TempDir::new();
+        let dir = tmp.path();
+        fs::create_dir_all(format!("{}/a/b", dir)).unwrap();
+        fs::write(format!("{}/a/f", dir), [0; 2048]).unwrap();
+        fs::write(format!("{}/a/b/g", dir), "world\n").unwrap();
+        let (b, f, g) = (
+            format!("{}/a/b", dir),
+            format!("{}/a/f", dir),
+            format!("{}/a/b/g", dir),
+        );
+
+        // recursive listings have no "Found n items" header
+        let (code, out, _) = run(&shell, &["-ls", "-R", &format!("{}/a", dir)]);
+        assert_eq!(code, 0);
+        let columns = ls_columns(&out);
+        assert_eq!(columns.len(), 3);
+        assert_eq!((columns[0][1], columns[0][5]), ("-", b.as_str()));
+        assert_eq!((columns[1][1], columns[1][4]), ("1", "6"));
+        assert_eq!(columns[1][5], g);
+        assert_eq!((columns[2][4], columns[2][5]), ("2048", f.as_str()));
+
+        // a directory is listed as a plain file
+        let (code, out, _) = run(&shell, &["-ls", "-d", &format!("{}/a", dir)]);
+        assert_eq!(code, 0);
+        let columns = ls_columns(&out);
+        assert_eq!(columns.len(), 1);
+        assert!(columns[0][0].starts_with('d'));
+        assert_eq!(columns[0][5], format!("{}/a", dir));
+
+        // sizes are human readable
+        let (code, out, _) = run(&shell, &["-ls", "-h", &f]);
+        assert_eq!(code, 0);
+        let columns: Vec<&str> = out.split_whitespace().collect();
+        assert_eq!(&columns[4..6], ["2", "K"]);
+        assert_eq!(columns[8], f);
+
+        let (code, _, err) = run(&shell, &["-ls", &format!("{}/none", dir)]);
+        assert_eq!(code, 1);
+        assert_eq!(
+            err,
+            format!("ls: `{}/none': No such file or directory\n", dir)
+        );
+    }
+
+    #[test]
+    fn test_permission_commands() {
+        let shell = local_shell();
+        let tmp = TempDir::new();
+        let dir = tmp.path();
+        fs::create_dir_all(format!("{}/a/b", dir)).unwrap();
+        fs::write(format!("{}/a/b/g", dir), "world\n").unwrap();
+
+        let (code, out, err) = run(&shell, &["-chmod", "-R", "700", &format!("{}/a", dir)]);
+        assert_eq!((code, out.as_str(), err.as_str()), (0, "", ""));
+        let (_, out, _) = run(&shell, &["-ls", "-R", &format!("{}/a", dir)]);
+        let perms: Vec<&str> = ls_columns(&out).iter().map(|c| c[0]).collect();
+        assert_eq!(perms, ["drwx------", "-rwx------"]);
+        let (code, _, _) = run(&shell, &["-chmod", "go+r", &format!("{}/a/b/g", dir)]);
+        assert_eq!(code, 0);
+        let (_, out, _) = run(&shell, &["-stat", "%A", &format!("{}/a/b/g", dir)]);
+        assert_eq!(out, "rwxr--r--\n");
+
+        let (code, _, err) = run(&shell, &["-chmod", "xyz", &format!("{}/a", dir)]);
+        assert_eq!(code, -1);
+        assert!(err.starts_with("-chmod: chmod : mode 'xyz' does not match the expected pattern."));
+
+        // changing the owner to the current one is allowed to anyone
+        let (_, owner, _) = run(&shell, &["-stat", "%u:%g", &format!("{}/a", dir)]);
+        let (code, out, err) = run(
+            &shell,
+            &["-chown", "-R", owner.trim(), &format!("{}/a", dir)],
+        );
+        assert_eq!((code, out.as_str(), err.as_str()), (0, "", ""));
+        let (code, _, err) = run(&shell, &["-chown", "-R", "owner", &format!("{}/none", dir)]);
+        assert_eq!(code, 1);
+        assert_eq!(
+            err,
+            format!("chown: `{}/none': No such file or directory\n", dir)
+        );
+    }
+
+    #[test]
+    fn test_copy_commands() {
+        let shell = local_shell();
+        let tmp = TempDir::new();
+        let dir = tmp.path();
+        fs::create_dir_all(format!("{}/a/b", dir)).unwrap();
+        fs::write(format!("{}/a/f", dir), "hello\n").unwrap();
+        fs::write(format!("{}/a/b/g", dir), "world\n").unwrap();
+        let path = |name: &str| format!("{}/{}", dir, name);
+
+        let (code, out, err) = run(&shell, &["-cp", &path("a/f"), &path("a/c")]);
+        assert_eq!((code, out.as_str(), err.as_str()), (0, "", ""));
+        assert_eq!(fs::read_to_string(path("a/c")).unwrap(), "hello\n");
+        let (code, _, err) = run(&shell, &["-cp", &path("a/f"), &path("a/c")]);
+        assert_eq!(code, 1);
+        assert_eq!(err, format!("cp: `{}': File exists\n", path("a/c")));
+
+        let (code, out, err) = run(&shell, &["-mv", &path("a/c"), &path("a/b")]);
+        assert_eq!((code, out.as_str(), err.as_str()), (0, "", ""));
+        assert!(!fs::exists(path("a/c")).unwrap());
+        assert_eq!(fs::read_to_string(path("a/b/c")).unwrap(), "hello\n");
+        let (code, _, err) = run(&shell, &["-mv", &path("a/none"), &path("a/b")]);
+        assert_eq!(code, 1);
+        assert_eq!(
+            err,
+            format!("mv: `{}': No such file or directory\n", path("a/none"))
+        );
+
+        // the files of the directory are concatenated in order
+        let (code, _, _) = run(&shell, &["-getmerge", &path("a/b"), &path("merged")]);
+        assert_eq!(code, 0);
+        assert_eq!(
+            fs::read_to_string(path("merged")).unwrap(),
+            "hello\nworld\n"
+        );
+
+        let (code, _, _) = run(&shell, &["-appendToFile", &path("a/f"), &path("a/b/g")]);
+        assert_eq!(code, 0);
+        let (code, out, _) = run(&shell, &["-cat", &path("a/b/g")]);
+        assert_eq!((code, out.as_str()), (0, "world\nhello\n"));
+    }
+
+    #[test]
+    fn test_file_commands() {
+        let shell = local_shell();
+        let tmp = TempDir::new();
+        let dir = tmp.path();
+        fs::create_dir_all(format!("{}/a/b", dir)).unwrap();
+        fs::write(format!("{}/a/f.txt", dir), "hello\n").unwrap();
+        fs::write(format!("{}/a/b/g.txt", dir), "world\n").unwrap();
+        fs::write(format!("{}/a/b/h.log", dir), "log\n").unwrap();
+        let path = |name: &str| format!("{}/{}", dir, name);
+
+        let (code, out, _) = run(&shell, &["-find", &path("a"), "-name", "*.txt", "-print"]);
+        assert_eq!(code, 0);
+        assert_eq!(out, format!("{}\n{}\n", path("a/b/g.txt"), path("a/f.txt")));
+
+        // the local file system has no checksums
+        let (code, out, _) = run(&shell, &["-checksum", &path("a/f.txt")]);
+        assert_eq!((code, out), (0, format!("{}\tNONE\t\n", path("a/f.txt"))));
+
+        let (code, out, _) = run(&shell, &["-setrep", "2", &path("a/f.txt")]);
+        assert_eq!(
+            (code, out),
+            (0, format!("Replication 2 set: {}\n", path("a/f.txt")))
+        );
+        let (code, _, err) = run(&shell, &["-setrep", "-w", "2", &path("a/f.txt")]);
+        assert_eq!(code, -1);
+        assert!(err.starts_with("-setrep: Illegal option -w\n"));
+
+        let (code, out, err) = run(&shell, &["-touchz", &path("a/z")]);
+        assert_eq!((code, out.as_str(), err.as_str()), (0, "", ""));
+        assert_eq!(fs::read(path("a/z")).unwrap(), b"");
+        let (code, _, err) = run(&shell, &["-touchz", &path("a/f.txt")]);
+        assert_eq!(code, 1);
+        assert_eq!(
+            err,
+            format!("touchz: `{}': Not a zero-length file\n", path("a/f.txt"))
+        );
+
+        let (code, out, _) = run(&shell, &["-tail", &path("a/b/g.txt")]);
+        assert_eq!((code, out.as_str()), (0, "world\n"));
+        let (code, _, err) = run(&shell, &["-tail", &path("a")]);
+        assert_eq!(code, 1);
+        assert_eq!(err, format!("tail: `{}': Is a directory\n", path("a")));
+    }
+}
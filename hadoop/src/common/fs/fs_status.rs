@@ -0,0 +1,34 @@
+/// This class is used to represent the capacity, free and used space on a
+/// [`FileSystem`](super::FileSystem).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FsStatus {
+    capacity: i64,
+    used: i64,
+    remaining: i64,
+}
+
+impl FsStatus {
+    /// Construct a FsStatus object, using the specified statistics
+    pub fn new(capacity: i64, used: i64, remaining: i64) -> Self {
+        Self {
+            capacity,
+            used,
+            remaining,
+        }
+    }
+
+    /// Return the capacity in bytes of the file system
+    pub fn get_capacity(&self) -> i64 {
+        self.capacity
+    }
+
+    /// Return the number of bytes used on the file system
+    pub fn get_used(&self) -> i64 {
+        self.used
+    }
+
+    /// Return the number of remaining bytes on the file system
+    pub fn get_remaining(&self) -> i64 {
+        self.remaining
+    }
+}
@@ -0,0 +1,139 @@
+use anyhow::Error;
+use regex::Regex;
+
+/// A class for POSIX glob pattern with brace expansions.
+pub struct GlobPattern {
+    compiled: Regex,
+    has_wildcard: bool,
+}
+
+impl GlobPattern {
+    /// Construct the glob pattern object with a glob pattern string
+    pub fn new(glob: &str) -> anyhow::Result<Self> {
+        let chars: Vec<char> = glob.chars().collect();
+        let len = chars.len();
+        let mut regex = String::from("^(?s:");
+        let mut set_open = 0;
+        let mut curly_open = 0;
+        let mut has_wildcard = false;
+        let error = |message: &str, pos: usize| {
+            Error::msg(format!("{} at {} in glob pattern: {}", message, pos, glob))
+        };
+
+        let mut i = 0;
+        while i < len {
+            let c = chars[i];
+            i += 1;
+            match c {
+                '\\' => {
+                    let escaped = chars
+                        .get(i)
+                        .ok_or_else(|| error("Missing escaped character", i))?;
+                    regex.push_str(&regex::escape(&escaped.to_string()));
+                    i += 1;
+                }
+                // escape regex special chars that are not glob special chars
+                '.' | '$' | '(' | ')' | '|' | '+' => {
+                    regex.push('\\');
+                    regex.push(c);
+                }
+                '*' => {
+                    regex.push_str(".*");
+                    has_wildcard = true;
+                }
+                '?' => {
+                    regex.push('.');
+                    has_wildcard = true;
+                }
+                '{' => {
+                    // start of a group, non-capturing
+                    regex.push_str("(?:");
+                    curly_open += 1;
+                    has_wildcard = true;
+                }
+                ',' => regex.push(if curly_open > 0 { '|' } else { c }),
+                '}' if curly_open > 0 => {
+                    // end of a group
+                    curly_open -= 1;
+                    regex.push(')');
+                }
+                '[' => {
+                    if set_open > 0 {
+                        return Err(error("Unclosed character class", i - 1));
+                    }
+                    set_open += 1;
+                    has_wildcard = true;
+                    regex.push(c);
+                }
+                // ^ inside [...] can be unescaped
+                '^' => {
+                    if set_open == 0 {
+                        regex.push('\\');
+                    }
+                    regex.push(c);
+                }
+                // [! needs to be translated to [^
+                '!' => regex.push(if set_open > 0 && chars[i - 2] == '[' {
+                    '^'
+                } else {
+                    '!'
+                }),
+                ']' => {
+                    // Many set errors like [][] could not be easily detected here,
+                    // we'll just let the regex compiler do the real work.
+                    set_open = 0;
+                    regex.push(c);
+                }
+                '}' | '&' | '~' | '-' if set_open == 0 => {
+                    regex.push_str(&regex::escape(&c.to_string()));
+                }
+                _ => regex.push(c),
+            }
+        }
+
+        if set_open > 0 {
+            return Err(error("Unclosed character class", len));
+        }
+        if curly_open > 0 {
+            return Err(error("Unclosed group", len));
+        }
+        regex.push_str(")$");
+        Ok(Self {
+            compiled: Regex::new(&regex).map_err(|e| error(&e.to_string(), 0))?,
+            has_wildcard,
+        })
+    }
+
+    /// Match input against the compiled glob pattern
+    pub fn matches(&self, s: &str) -> bool {
+        self.compiled.is_match(s)
+    }
+
+    /// Whether the glob pattern has wildcards
+    pub fn has_wildcard(&self) -> bool {
+        self.has_wildcard
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glob_pattern() {
+        let matches = |glob: &str, s: &str| GlobPattern::new(glob).unwrap().matches(s);
+        assert!(matches("*.txt", "a.txt"));
+        assert!(!matches("*.txt", "atxt"));
+        assert!(matches("a?c", "abc"));
+        assert!(matches("{a,b}c", "bc"));
+        assert!(matches("[a-c]x", "bx"));
+        assert!(matches("[!a-c]x", "dx"));
+        assert!(!matches("[!a-c]x", "ax"));
+        assert!(matches("a\\*", "a*"));
+        assert!(!matches("a\\*", "ab"));
+        assert!(matches("a+b(1)", "a+b(1)"));
+        assert!(!GlobPattern::new("abc").unwrap().has_wildcard());
+        assert!(GlobPattern::new("[abc").is_err());
+        assert!(GlobPattern::new("{a,b").is_err());
+    }
+}
@@ -0,0 +1,236 @@
+use super::{path_io_exception, FileStatus, FileSystem, GlobPattern, Path};
+
+/// Implementation of [`FileSystem::glob_status`].
+pub(crate) struct Globber<'a, F: FileSystem + ?Sized> {
+    fs: &'a F,
+    path_pattern: &'a Path,
+}
+
+impl<'a, F: FileSystem + ?Sized> Globber<'a, F> {
+    pub fn new(fs: &'a F, path_pattern: &'a Path) -> Self {
+        Self { fs, path_pattern }
+    }
+
+    pub fn glob(&self) -> anyhow::Result<Option<Vec<FileStatus>>> {
+        // First we get the scheme and authority of the pattern that was passed
+        // in.
+        let fs_uri = self.fs.get_uri();
+        let scheme = self
+            .path_pattern
+            .get_scheme()
+            .unwrap_or(fs_uri.scheme().as_str());
+        let authority = self
+            .path_pattern
+            .get_authority()
+            .or(fs_uri.authority().map(|a| a.as_str()))
+            .unwrap_or("");
+
+        // Next we strip off everything except the pathname itself, and expand all
+        // globs.  Expansion is a process which turns "grouping" clauses,
+        // expressed as brackets, into separate path patterns.
+        let path_pattern_string = self.path_pattern.get_uri_path();
+        let flattened_patterns = expand_groups(&path_pattern_string);
+
+        // Now loop over all flattened patterns.  In every case, we'll be trying to
+        // match them to entries in the filesystem.
+        let mut results = vec![];
+        let mut saw_wildcard = false;
+        for flat_pattern in &flattened_patterns {
+            // Get the absolute path for this flattened pattern.  We couldn't do
+            // this prior to flattening because of patterns like {/,a}, where which
+            // path you go down influences how the path must be made absolute.
+            let abs_pattern =
+                self.fs
+                    .fix_relative_part(&Path::new(if flat_pattern.is_empty() {
+                        "."
+                    } else {
+                        flat_pattern
+                    })?)?;
+
+            // Now we break the flattened, absolute pattern into path components.
+            // For example, /a/*/c would be broken into the list [a, *, c]
+            let abs_pattern_path = abs_pattern.get_uri_path();
+            let components: Vec<&str> = abs_pattern_path
+                .split('/')
+                .filter(|c| !c.is_empty())
+                .collect();
+
+            // Starting out at the root of the filesystem, we try to match
+            // filesystem entries against pattern components.  Each candidate is
+            // paired with whether its status has been fetched from the
+            // filesystem, rather than assumed.
+            let root = FileStatus::new(
+                0,
+                true,
+                0,
+                0,
+                0,
+                0,
+                None,
+                "",
+                "",
+                None,
+                Path::from_components(Some(scheme), Some(authority), "/")?,
+                Default::default(),
+            );
+            let mut candidates = vec![(root, false)];
+
+            for (idx, component) in components.iter().enumerate() {
+                if candidates.is_empty() {
+                    break;
+                }
+                let glob_filter = GlobPattern::new(component)?;
+                if !glob_filter.has_wildcard() {
+                    // Optimization: if this is not the terminal path component, and we
+                    // are not matching against a glob, assume that it exists.  If it
+                    // doesn't exist, we'll find out later when resolving a later glob
+                    // or the terminal path component.
+                    let component = unescape_path_component(component);
+                    for (candidate, verified) in candidates.iter_mut() {
+                        candidate.set_path(candidate.get_path().child(&component)?);
+                        *verified = false;
+                    }
+                    continue;
+                }
+                saw_wildcard = true;
+                let mut new_candidates = vec![];
+                for (candidate, _) in &candidates {
+                    let children = match self.fs.list_status(candidate.get_path()) {
+                        Ok(children) => children,
+                        Err(e) if path_io_exception::is_file_not_found(&e) => continue,
+                        Err(e) => return Err(e),
+                    };
+                    if children.len() == 1 {
+                        // If we get back only one result, this could be either a listing
+                        // of a directory with one entry, or it could reflect the fact
+                        // that what we listed resolved to a file.
+                        match self.fs.get_file_status(candidate.get_path()) {
+                            Ok(status) if status.is_directory() => {}
+                            _ => continue,
+                        }
+                    }
+                    for mut child in children {
+                        if idx < components.len() - 1 && !child.is_directory() {
+                            // Don't try to recurse into non-directories.  See HADOOP-10957.
+                            continue;
+                        }
+                        // Set the child path based on the parent path.
+                        let name = child.get_path().get_name();
+                        if glob_filter.matches(&name) {
+                            child.set_path(candidate.get_path().child(&name)?);
+                            new_candidates.push((child, true));
+                        }
+                    }
+                }
+                candidates = new_candidates;
+            }
+            for (mut status, verified) in candidates {
+                // If we didn't fetch the status of this candidate from the
+                // filesystem, we have to do so now, and skip it if it doesn't exist.
+                if !verified {
+                    match self.fs.get_file_status(status.get_path()) {
+                        Ok(mut real) => {
+                            real.set_path(status.get_path().clone());
+                            status = real;
+                        }
+                        Err(e) if path_io_exception::is_file_not_found(&e) => continue,
+                        Err(e) => return Err(e),
+                    }
+                }
+                results.push(status);
+            }
+        }
+
+        // When the input pattern "looks" like just a simple filename, and we
+        // can't find it, we return None rather than an empty vector.
+        // This is a special case which the shell relies on.
+        //
+        // To be more precise: if there were no results, AND there were no
+        // groupings (aka brackets), and no wildcards in the input (aka stars),
+        // we return None.
+        if !saw_wildcard && results.is_empty() && flattened_patterns.len() <= 1 {
+            return Ok(None);
+        }
+        results.sort_by_key(|status| status.get_path().to_string());
+        Ok(Some(results))
+    }
+}
+
+/// Remove the backslash quoting of the characters in a path component.
+fn unescape_path_component(name: &str) -> String {
+    let mut unescaped = String::with_capacity(name.len());
+    let mut chars = name.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => unescaped.extend(chars.next()),
+            _ => unescaped.push(c),
+        }
+    }
+    unescaped
+}
+
+/// Expand the leftmost outer curly bracket pairs of the pattern into separate
+/// patterns, recursively, so that `{a,b}/c` gives `a/c` and `b/c`.
+/// Escaped brackets and brackets within a character class are kept.
+fn expand_groups(pattern: &str) -> Vec<String> {
+    let chars: Vec<char> = pattern.chars().collect();
+    let mut open = None;
+    let mut depth = 0;
+    let mut in_set = false;
+    let mut alternatives = vec![];
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 1,
+            '[' if !in_set => in_set = true,
+            ']' if in_set => in_set = false,
+            '{' if !in_set => {
+                if depth == 0 {
+                    open = Some(i);
+                    alternatives.clear();
+                }
+                depth += 1;
+            }
+            ',' if !in_set && depth == 1 => alternatives.push(i),
+            '}' if !in_set && depth > 0 => {
+                depth -= 1;
+                if depth == 0 {
+                    let open = open.unwrap_or_default();
+                    let prefix: String = chars[..open].iter().collect();
+                    let suffix: String = chars[i + 1..].iter().collect();
+                    let mut bounds = vec![open];
+                    bounds.extend(&alternatives);
+                    bounds.push(i);
+                    return bounds
+                        .windows(2)
+                        .flat_map(|w| {
+                            let alternative: String = chars[w[0] + 1..w[1]].iter().collect();
+                            expand_groups(&format!("{}{}{}", prefix, alternative, suffix))
+                        })
+                        .collect();
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    vec![pattern.to_owned()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_groups() {
+        assert_eq!(expand_groups("/a/b"), vec!["/a/b"]);
+        assert_eq!(expand_groups("{a,b}/c"), vec!["a/c", "b/c"]);
+        assert_eq!(
+            expand_groups("/{x,y{1,2}}/z"),
+            vec!["/x/z", "/y1/z", "/y2/z"]
+        );
+        assert_eq!(expand_groups("a\\{b,c}"), vec!["a\\{b,c}"]);
+        assert_eq!(expand_groups("[{]x"), vec!["[{]x"]);
+        assert_eq!(unescape_path_component("a\\*b"), "a*b");
+    }
+}
@@ -0,0 +1,44 @@
+use super::{BlockLocation, FileStatus};
+use std::ops::Deref;
+
+/// This class defines a FileStatus that includes a file's block locations.
+#[derive(Clone, Debug)]
+pub struct LocatedFileStatus {
+    status: FileStatus,
+    locations: Vec<BlockLocation>,
+}
+
+impl LocatedFileStatus {
+    /// Constructor with the file status and the file's block locations.
+    pub fn new(status: FileStatus, locations: Vec<BlockLocation>) -> Self {
+        Self { status, locations }
+    }
+
+    /// Get the file's block locations.
+    ///
+    /// In HDFS, the returned BlockLocation will have different formats for
+    /// replicated and erasure coded file.
+    pub fn get_block_locations(&self) -> &[BlockLocation] {
+        &self.locations
+    }
+
+    /// Get the status of the file without its block locations.
+    pub fn into_file_status(self) -> FileStatus {
+        self.status
+    }
+}
+
+impl Deref for LocatedFileStatus {
+    type Target = FileStatus;
+
+    fn deref(&self) -> &Self::Target {
+        &self.status
+    }
+}
+
+impl PartialEq for LocatedFileStatus {
+    /// Compare if this object is equal to another object by path.
+    fn eq(&self, other: &Self) -> bool {
+        self.status == other.status
+    }
+}
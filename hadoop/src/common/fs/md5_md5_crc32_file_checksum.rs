@@ -0,0 +1,78 @@
+use super::FileChecksum;
+use crate::common::util::DataChecksumType;
+
+/// The length of an MD5 digest in bytes.
+const MD5_LEN: usize = 16;
+
+/// MD5 of MD5 of CRC32.
+///
+/// The CRC type is CRC32 (gzip) or CRC32C (Castagnoli), depending on the
+/// checksums of the blocks.
+pub struct MD5MD5CRC32FileChecksum {
+    bytes_per_crc: i32,
+    crc_per_block: i64,
+    md5: [u8; MD5_LEN],
+    crc_type: DataChecksumType,
+}
+
+impl MD5MD5CRC32FileChecksum {
+    pub const LENGTH: usize = MD5_LEN + 4 + 8;
+
+    /// Create a MD5FileChecksum
+    pub fn new(
+        bytes_per_crc: i32,
+        crc_per_block: i64,
+        md5: [u8; MD5_LEN],
+        crc_type: DataChecksumType,
+    ) -> Self {
+        Self {
+            bytes_per_crc,
+            crc_per_block,
+            md5,
+            crc_type,
+        }
+    }
+
+    /// returns the CRC type
+    pub fn get_crc_type(&self) -> DataChecksumType {
+        self.crc_type
+    }
+}
+
+impl FileChecksum for MD5MD5CRC32FileChecksum {
+    fn get_algorithm_name(&self) -> String {
+        format!(
+            "MD5-of-{}MD5-of-{}{}",
+            self.crc_per_block, self.bytes_per_crc, self.crc_type
+        )
+    }
+
+    fn get_length(&self) -> usize {
+        Self::LENGTH
+    }
+
+    fn get_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(Self::LENGTH);
+        bytes.extend_from_slice(&self.bytes_per_crc.to_be_bytes());
+        bytes.extend_from_slice(&self.crc_per_block.to_be_bytes());
+        bytes.extend_from_slice(&self.md5);
+        bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_bytes() {
+        let checksum =
+            MD5MD5CRC32FileChecksum::new(512, 256, [0xAB; MD5_LEN], DataChecksumType::Crc32c);
+        assert_eq!("MD5-of-256MD5-of-512CRC32C", checksum.get_algorithm_name());
+        let bytes = checksum.get_bytes();
+        assert_eq!(checksum.get_length(), bytes.len());
+        assert_eq!([0, 0, 2, 0], bytes[..4]);
+        assert_eq!([0, 0, 0, 0, 0, 0, 1, 0], bytes[4..12]);
+        assert_eq!([0xAB; MD5_LEN], bytes[12..]);
+    }
+}
@@ -1,10 +1,50 @@
+mod block_location;
 pub mod common_configuration_keys;
 pub mod common_configuration_keys_public;
+mod composite_crc_file_checksum;
 mod configurable;
+mod content_summary;
+mod create_flag;
+mod file_checksum;
+mod file_status;
 pub mod file_system;
+mod fs_data_input_stream;
+mod fs_data_output_stream;
+mod fs_shell;
+mod fs_status;
+mod glob_pattern;
+mod globber;
+mod located_file_status;
+mod md5_md5_crc32_file_checksum;
+pub mod options;
+mod partial_listing;
 pub mod path;
+pub mod path_io_exception;
 pub mod permission;
+mod raw_local_file_system;
+mod shell;
+mod storage_type;
+mod trash;
 
+pub use block_location::BlockLocation;
+pub use composite_crc_file_checksum::CompositeCrcFileChecksum;
 pub use configurable::Configurable;
-pub use file_system::FileSystem;
+pub use content_summary::ContentSummary;
+pub use create_flag::CreateFlag;
+pub use file_checksum::FileChecksum;
+pub use file_status::{AttrFlags, FileStatus};
+pub use file_system::{FileSystem, RemoteIterator};
+pub use fs_data_input_stream::{FSDataInputStream, FSInputStream};
+pub use fs_data_output_stream::{FSDataOutputStream, OutputStream};
+pub use fs_shell::FsShell;
+pub use fs_status::FsStatus;
+pub use glob_pattern::GlobPattern;
+pub(crate) use globber::Globber;
+pub use located_file_status::LocatedFileStatus;
+pub use md5_md5_crc32_file_checksum::MD5MD5CRC32FileChecksum;
+pub use partial_listing::PartialListing;
 pub use path::Path;
+pub use path_io_exception::PathIOException;
+pub use raw_local_file_system::{RawLocalFileSystem, LOCAL_FS_SCHEME};
+pub use storage_type::StorageType;
+pub use trash::Trash;
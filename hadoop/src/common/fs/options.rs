@@ -0,0 +1,48 @@
+use anyhow::Error;
+use std::str::FromStr;
+
+/// Enum to support the varargs for rename() options
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Rename {
+    /// No options
+    None,
+    /// Overwrite the rename destination
+    Overwrite,
+    /// Rename to Trash
+    ToTrash,
+}
+
+impl Rename {
+    pub fn value(&self) -> u8 {
+        match self {
+            Self::None => 0,
+            Self::Overwrite => 1,
+            Self::ToTrash => 2,
+        }
+    }
+}
+
+/// Enum for indicating what mode to use when combining chunk and block
+/// checksums to define an aggregate FileChecksum. This should be considered
+/// a client-side runtime option rather than a persistent property of any
+/// stored metadata, which is why this is not part of ChecksumOpt, which
+/// deals with properties of files at rest.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ChecksumCombineMode {
+    /// MD5 of block checksums, which are MD5 over chunk CRCs
+    Md5md5crc,
+    /// Block/chunk-independent composite CRC
+    CompositeCrc,
+}
+
+impl FromStr for ChecksumCombineMode {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "MD5MD5CRC" => Ok(Self::Md5md5crc),
+            "COMPOSITE_CRC" => Ok(Self::CompositeCrc),
+            _ => Err(Error::msg(format!("Unknown checksum combine mode: {}", s))),
+        }
+    }
+}
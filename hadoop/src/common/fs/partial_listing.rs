@@ -0,0 +1,50 @@
+use super::Path;
+use anyhow::Error;
+
+/// A partial listing of the children of a parent directory. Since it is a
+/// partial listing, multiple PartialListing may need to be combined to obtain
+/// the full listing of a parent directory.
+///
+/// ListingBatch behaves similar to a Future, in that getting the result via
+/// [`PartialListing::get`] will return the listing or the error that was
+/// encountered while listing the parent.
+#[derive(Debug)]
+pub struct PartialListing<T> {
+    listed_path: Path,
+    partial_listing: Option<Vec<T>>,
+    exception: Option<Error>,
+}
+
+impl<T> PartialListing<T> {
+    pub fn new(listed_path: Path, partial_listing: Vec<T>) -> Self {
+        Self {
+            listed_path,
+            partial_listing: Some(partial_listing),
+            exception: None,
+        }
+    }
+
+    pub fn with_exception(listed_path: Path, exception: Error) -> Self {
+        Self {
+            listed_path,
+            partial_listing: None,
+            exception: Some(exception),
+        }
+    }
+
+    /// Partial listing of the path being listed. In the case where the path
+    /// is a file, the list will be a singleton with the file itself.
+    ///
+    /// Returns the error that was encountered while listing the path, if any.
+    pub fn get(self) -> anyhow::Result<Vec<T>> {
+        match self.exception {
+            Some(e) => Err(e),
+            None => Ok(self.partial_listing.unwrap_or_default()),
+        }
+    }
+
+    /// Path being listed.
+    pub fn get_listed_path(&self) -> &Path {
+        &self.listed_path
+    }
+}
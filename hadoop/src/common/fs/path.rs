@@ -1,35 +1,242 @@
-use iref::{IriRef, IriRefBuf};
+use anyhow::Error;
+use iref::{Iri, IriRef, IriRefBuf};
+use std::{fmt, str::FromStr};
 
 /// The directory separator, a slash.
 pub const SEPARATOR: &str = "/";
+pub const SEPARATOR_CHAR: char = '/';
+pub const CUR_DIR: &str = ".";
 
+/// Names a file or directory in a FileSystem.
+/// Path strings use slash as the directory separator.
+///
+/// Path strings are URIs, but with unescaped elements and some additional
+/// normalization: redundant slashes are removed, as well as a trailing slash
+/// and `.` / `..` segments.
 // TODO: review and refactor `Path`
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Path {
     uri: IriRefBuf,
 }
 
 impl Path {
+    /// Construct a path from a String.  Path strings are URIs, but with
+    /// unescaped elements and some additional normalization.
+    pub fn new(path_string: &str) -> anyhow::Result<Self> {
+        if path_string.is_empty() {
+            return Err(Error::msg("Can not create a Path from an empty string"));
+        }
+
+        // parse uri components
+        let mut scheme = None;
+        let mut authority = None;
+        let mut start = 0;
+
+        // parse uri scheme, if any
+        let colon = path_string.find(':');
+        let slash = path_string.find(SEPARATOR_CHAR);
+        if let Some(colon) = colon {
+            if slash.is_none_or(|slash| colon < slash) {
+                scheme = Some(&path_string[..colon]);
+                start = colon + 1;
+            }
+        }
+
+        // parse uri authority, if any
+        if path_string[start..].starts_with("//") && path_string.len() - start > 2 {
+            let next_slash = path_string[start + 2..]
+                .find(SEPARATOR_CHAR)
+                .map_or(path_string.len(), |i| start + 2 + i);
+            authority = Some(&path_string[start + 2..next_slash]);
+            start = next_slash;
+        }
+
+        // uri path is the rest of the string -- query & fragment not supported
+        let path = &path_string[start..];
+
+        Self::from_components(scheme, authority, path)
+    }
+
+    /// Construct a Path from components.
+    pub fn from_components(
+        scheme: Option<&str>,
+        authority: Option<&str>,
+        path: &str,
+    ) -> anyhow::Result<Self> {
+        let path = normalize_path(path);
+        let mut uri = String::new();
+        if let Some(scheme) = scheme {
+            uri.push_str(scheme);
+            uri.push(':');
+        }
+        if let Some(authority) = authority {
+            uri.push_str("//");
+            uri.push_str(authority);
+        }
+        uri.push_str(&encode_path(&path));
+        Ok(Self {
+            uri: IriRefBuf::new(uri).map_err(|e| Error::msg(format!("Invalid path: {}", e.0)))?,
+        })
+    }
+
     /// Create a new Path based on the child path resolved against the parent path.
     pub fn from_parent(parent: &Self, child: &Self) -> anyhow::Result<Self> {
-        // TODO: review and refactor
-        let parent_uri = parent
-            .uri
-            .as_iri()
-            .ok_or(anyhow::Error::msg("Parent path is not absolute"))?;
-        let uri = child.uri.resolved(parent_uri).into();
-        Ok(Self { uri })
+        let child_scheme = child.get_scheme();
+        let child_authority = child.get_authority();
+        if child_scheme.is_some() {
+            return Ok(child.clone());
+        }
+        let scheme = parent.get_scheme();
+        if child_authority.is_some() {
+            return Self::from_components(scheme, child_authority, &child.get_uri_path());
+        }
+        let authority = parent.get_authority();
+        let child_path = child.get_uri_path();
+        let path = if child.is_uri_path_absolute() {
+            child_path
+        } else if child_path.is_empty() || child_path == CUR_DIR {
+            parent.get_uri_path()
+        } else {
+            // Add a slash to parent's path so resolution is compatible with URI's
+            let parent_path = parent.get_uri_path();
+            if parent_path.is_empty() || parent_path == CUR_DIR {
+                child_path
+            } else {
+                format!(
+                    "{}{}{}",
+                    parent_path.trim_end_matches(SEPARATOR_CHAR),
+                    SEPARATOR,
+                    child_path
+                )
+            }
+        };
+        Self::from_components(scheme, authority, &path)
+    }
+
+    /// Create a new Path for the entry `name` of this directory, taking
+    /// `name` literally rather than parsing it as a path string.
+    pub fn child(&self, name: &str) -> anyhow::Result<Self> {
+        let path = self.get_uri_path();
+        let path = match path.as_str() {
+            "" | CUR_DIR => name.to_owned(),
+            _ => format!(
+                "{}{}{}",
+                path.trim_end_matches(SEPARATOR_CHAR),
+                SEPARATOR,
+                name
+            ),
+        };
+        Self::from_components(
+            self.get_scheme(),
+            self.uri.authority().map(|a| a.as_str()),
+            &path,
+        )
+    }
+
+    /// Create a new Path based on the child path string resolved against the
+    /// parent path.
+    pub fn from_parent_str(parent: &Self, child: &str) -> anyhow::Result<Self> {
+        Self::from_parent(parent, &Self::new(child)?)
     }
 
     pub fn to_uri(&self) -> &IriRef {
         self.uri.as_iri_ref()
     }
 
+    pub fn get_scheme(&self) -> Option<&str> {
+        self.uri.scheme().map(|s| s.as_str())
+    }
+
+    /// The authority of this path, `None` if it has none or it is empty.
+    pub fn get_authority(&self) -> Option<&str> {
+        self.uri
+            .authority()
+            .map(|a| a.as_str())
+            .filter(|a| !a.is_empty())
+    }
+
+    /// The unescaped path component of the URI of this path.
+    pub fn get_uri_path(&self) -> String {
+        decode_path(self.uri.path().as_str())
+    }
+
     /// Returns true if the path component (i.e. directory) of this URI is
     /// absolute.
     pub fn is_uri_path_absolute(&self) -> bool {
         self.uri.path().as_str().starts_with(SEPARATOR)
     }
+
+    /// There is some ambiguity here. An absolute path is a slash
+    /// relative name without a scheme or an authority.
+    /// So either this method was incorrectly named or its
+    /// implementation is incorrect. This method returns true
+    /// even if there is a scheme and authority.
+    pub fn is_absolute(&self) -> bool {
+        self.is_uri_path_absolute()
+    }
+
+    /// Returns true if and only if this path represents the root of a file
+    /// system.
+    pub fn is_root(&self) -> bool {
+        self.uri.path().as_str() == SEPARATOR
+    }
+
+    /// Returns the final component of this path.
+    pub fn get_name(&self) -> String {
+        let path = self.get_uri_path();
+        match path.rfind(SEPARATOR_CHAR) {
+            Some(slash) => path[slash + 1..].to_owned(),
+            None => path,
+        }
+    }
+
+    /// Returns the parent of a path or `None` if at root.
+    pub fn get_parent(&self) -> Option<Self> {
+        let path = self.get_uri_path();
+        let last_slash = path.rfind(SEPARATOR_CHAR);
+        if path.is_empty() || path == SEPARATOR || path == CUR_DIR {
+            // at root
+            return None;
+        }
+        let parent = match last_slash {
+            // no slash: the parent is the current directory
+            None => CUR_DIR.to_owned(),
+            Some(0) => SEPARATOR.to_owned(),
+            Some(last_slash) => path[..last_slash].to_owned(),
+        };
+        Self::from_components(self.get_scheme(), self.get_authority(), &parent).ok()
+    }
+
+    /// Adds a suffix to the final name in the path.
+    pub fn suffix(&self, suffix: &str) -> anyhow::Result<Self> {
+        Self::from_components(
+            self.get_scheme(),
+            self.get_authority(),
+            &format!("{}{}", self.get_uri_path(), suffix),
+        )
+    }
+
+    /// Returns a qualified path object, using the scheme and authority of
+    /// `default_uri` and resolving a relative path against `working_dir`.
+    pub fn make_qualified(&self, default_uri: &Iri, working_dir: &Path) -> anyhow::Result<Self> {
+        let path = if self.is_absolute() {
+            self.clone()
+        } else {
+            Self::from_parent(working_dir, self)?
+        };
+        let default_authority = default_uri
+            .authority()
+            .map(|a| a.as_str())
+            .filter(|a| !a.is_empty());
+        if path.get_scheme().is_some()
+            && (path.get_authority().is_some() || default_authority.is_none())
+        {
+            return Ok(path);
+        }
+        let scheme = path.get_scheme().unwrap_or(default_uri.scheme().as_str());
+        let authority = path.get_authority().or(default_authority).unwrap_or("");
+        Self::from_components(Some(scheme), Some(authority), &path.get_uri_path())
+    }
 }
 
 impl From<IriRefBuf> for Path {
@@ -40,6 +247,91 @@ impl From<IriRefBuf> for Path {
     }
 }
 
+impl FromStr for Path {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::new(s)
+    }
+}
+
+impl fmt::Display for Path {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // we can't use uri.to_string(), which escapes everything, because we
+        // want illegal characters unescaped in the string, for glob
+        // processing, etc.
+        if let Some(scheme) = self.get_scheme() {
+            write!(f, "{}:", scheme)?;
+        }
+        if let Some(authority) = self.get_authority() {
+            write!(f, "//{}", authority)?;
+        }
+        write!(f, "{}", self.get_uri_path())
+    }
+}
+
+/// Remove redundant slashes, a trailing slash and `.` / `..` segments.
+fn normalize_path(path: &str) -> String {
+    let absolute = path.starts_with(SEPARATOR_CHAR);
+    let mut segments: Vec<&str> = vec![];
+    for segment in path.split(SEPARATOR_CHAR) {
+        match segment {
+            "" | "." => {}
+            ".." if segments.last().is_some_and(|s| *s != "..") => {
+                segments.pop();
+            }
+            // no parent of the root
+            ".." if absolute => {}
+            _ => segments.push(segment),
+        }
+    }
+    let joined = segments.join(SEPARATOR);
+    if absolute {
+        format!("{}{}", SEPARATOR, joined)
+    } else if joined.is_empty() && !path.is_empty() {
+        CUR_DIR.to_owned()
+    } else {
+        joined
+    }
+}
+
+/// Escape the characters which may not appear in the path of an IRI.
+fn encode_path(path: &str) -> String {
+    let mut encoded = String::with_capacity(path.len());
+    for c in path.chars() {
+        if c.is_ascii_control() || " \"#%<>?[\\]^`{|}".contains(c) {
+            encoded.push_str(&format!("%{:02X}", c as u8));
+        } else {
+            encoded.push(c);
+        }
+    }
+    encoded
+}
+
+/// Unescape the `%XX` sequences of an IRI path.
+fn decode_path(path: &str) -> String {
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(b)) => {
+                decoded.push(b);
+                i += 3;
+            }
+            (b, _) => {
+                decoded.push(b);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -57,6 +349,48 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_normalize() {
+        let path = |s: &str| Path::new(s).unwrap().to_string();
+        assert_eq!(path("/a//b/"), "/a/b");
+        assert_eq!(path("/a/./b/../c"), "/a/c");
+        assert_eq!(path("a/../.."), "..");
+        assert_eq!(path("."), ".");
+        assert_eq!(path("hdfs://nn:8020/a b/c"), "hdfs://nn:8020/a b/c");
+        assert_eq!(path("file:///tmp/x"), "file:/tmp/x");
+        assert!(Path::new("").is_err());
+    }
+
+    #[test]
+    fn test_parent_and_name() {
+        let path = Path::new("hdfs://nn/user/a b").unwrap();
+        assert_eq!(path.get_name(), "a b");
+        assert_eq!(path.get_uri_path(), "/user/a b");
+        assert_eq!(path.get_parent().unwrap().to_string(), "hdfs://nn/user");
+        assert!(Path::new("/").unwrap().get_parent().is_none());
+        assert_eq!(
+            Path::new("x").unwrap().get_parent().unwrap().to_string(),
+            "."
+        );
+        assert_eq!(
+            path.suffix("._COPYING_").unwrap().get_name(),
+            "a b._COPYING_"
+        );
+
+        let cwd = Path::new("/user/alex").unwrap();
+        let default_uri = IriBuf::new("hdfs://nn:8020".to_owned()).unwrap();
+        let qualified = Path::new("data/../logs")
+            .unwrap()
+            .make_qualified(default_uri.as_iri(), &cwd)
+            .unwrap();
+        assert_eq!(qualified.to_string(), "hdfs://nn:8020/user/alex/logs");
+        let parent = Path::new("hdfs://nn/user/alex").unwrap();
+        assert_eq!(
+            Path::from_parent_str(&parent, ".").unwrap().to_string(),
+            "hdfs://nn/user/alex"
+        );
+    }
+
     #[test]
     fn test_is_uri_path_absolute() {
         let iri = IriRefBuf::new("/dev/../hello".to_owned()).unwrap();
@@ -0,0 +1,182 @@
+use crate::common::io::IOException;
+use anyhow::Error;
+use std::{fmt, io};
+
+/// The reason a [`PathIOException`] was raised.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PathErrorKind {
+    /// Generic i/o error.
+    Other,
+    /// Exception corresponding to File Exists - EEXISTS.
+    Exists,
+    /// Exception corresponding to path not found: ENOENT/ENOFILE.
+    NotFound,
+    /// EISDIR.
+    IsDirectory,
+    /// ENOTDIR.
+    IsNotDirectory,
+    /// Generated by rm commands.
+    IsNotEmptyDirectory,
+    /// EACCES.
+    AccessDenied,
+    /// ENOTSUP.
+    Operation,
+}
+
+impl PathErrorKind {
+    fn default_message(&self) -> &'static str {
+        match self {
+            Self::Other => "Input/output error",
+            Self::Exists => "File exists",
+            Self::NotFound => "No such file or directory",
+            Self::IsDirectory => "Is a directory",
+            Self::IsNotDirectory => "Is not a directory",
+            Self::IsNotEmptyDirectory => "Directory is not empty",
+            Self::AccessDenied => "Permission denied",
+            Self::Operation => "Operation not supported",
+        }
+    }
+}
+
+/// Exceptions based on standard posix/linux style exceptions for path related
+/// errors. Returns an exception with the format "path: standard error string".
+///
+/// This exception corresponds to Error Input/output(EIO)
+#[derive(Clone, Debug)]
+pub struct PathIOException {
+    kind: PathErrorKind,
+    path: String,
+    error: Option<String>,
+    // NOTE: this really should be a Path, but a Path is buggy and won't
+    // return the exact string used to construct the path, and it mangles
+    // uris with no authority
+    operation: Option<String>,
+    target_path: Option<String>,
+    cause: Option<String>,
+}
+
+impl PathIOException {
+    /// Constructor a generic I/O error exception
+    pub fn new(path: &str) -> Self {
+        Self::with_kind(PathErrorKind::Other, path)
+    }
+
+    /// Exception with the given kind and its standard error string.
+    pub fn with_kind(kind: PathErrorKind, path: &str) -> Self {
+        Self {
+            kind,
+            path: path.to_owned(),
+            error: None,
+            operation: None,
+            target_path: None,
+            cause: None,
+        }
+    }
+
+    /// Avoid using this method.  Use a subclass of PathIOException if
+    /// possible.
+    pub fn with_error(path: &str, error: &str) -> Self {
+        Self {
+            error: Some(error.to_owned()),
+            ..Self::new(path)
+        }
+    }
+
+    pub fn exists(path: &str) -> Self {
+        Self::with_kind(PathErrorKind::Exists, path)
+    }
+
+    pub fn not_found(path: &str) -> Self {
+        Self::with_kind(PathErrorKind::NotFound, path)
+    }
+
+    pub fn is_directory(path: &str) -> Self {
+        Self::with_kind(PathErrorKind::IsDirectory, path)
+    }
+
+    pub fn is_not_directory(path: &str) -> Self {
+        Self::with_kind(PathErrorKind::IsNotDirectory, path)
+    }
+
+    pub fn is_not_empty_directory(path: &str) -> Self {
+        Self::with_kind(PathErrorKind::IsNotEmptyDirectory, path)
+    }
+
+    pub fn access_denied(path: &str) -> Self {
+        Self::with_kind(PathErrorKind::AccessDenied, path)
+    }
+
+    pub fn operation_not_supported(path: &str) -> Self {
+        Self::with_kind(PathErrorKind::Operation, path)
+    }
+
+    pub fn get_kind(&self) -> &PathErrorKind {
+        &self.kind
+    }
+
+    /// Optional operation that will preface the path
+    pub fn set_operation(mut self, operation: &str) -> Self {
+        self.operation = Some(operation.to_owned());
+        self
+    }
+
+    /// Optional path if the exception involved two paths, ex. a copy operation
+    pub fn set_target_path(mut self, target_path: &str) -> Self {
+        self.target_path = Some(target_path.to_owned());
+        self
+    }
+
+    /// The underlying error, appended to the message.
+    pub fn set_cause(mut self, cause: &Error) -> Self {
+        self.cause = Some(cause.to_string());
+        self
+    }
+
+    /// Returns the path which caused the exception
+    pub fn get_path(&self) -> &str {
+        &self.path
+    }
+
+    /// Returns the target path of a two path operation
+    pub fn get_target_path(&self) -> Option<&str> {
+        self.target_path.as_deref()
+    }
+}
+
+impl fmt::Display for PathIOException {
+    /// Format permission denied messages as "`path': error"
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(operation) = &self.operation {
+            write!(f, "{} ", operation)?;
+        }
+        write!(f, "`{}'", self.path)?;
+        if let Some(target_path) = &self.target_path {
+            write!(f, " to `{}'", target_path)?;
+        }
+        write!(
+            f,
+            ": {}",
+            self.error.as_deref().unwrap_or(self.kind.default_message())
+        )?;
+        if let Some(cause) = &self.cause {
+            write!(f, ": {}", cause)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for PathIOException {}
+
+/// Whether the error reports a missing file or directory.
+pub fn is_file_not_found(e: &Error) -> bool {
+    if let Some(e) = e.downcast_ref::<PathIOException>() {
+        return e.kind == PathErrorKind::NotFound;
+    }
+    if let Some(e) = e.downcast_ref::<io::Error>() {
+        return e.kind() == io::ErrorKind::NotFound;
+    }
+    if let Some(e) = e.downcast_ref::<IOException>() {
+        return matches!(e, IOException::FileNotFound(_));
+    }
+    false
+}
@@ -0,0 +1,32 @@
+use super::permission_parser::{PermissionParser, PermissionPattern};
+use crate::common::fs::FileStatus;
+
+/// Symbolic form allows X and t, with at least one permission character,
+/// and octal form allows a leading 1 for the sticky bit.
+static CHMOD_PATTERN: PermissionPattern = PermissionPattern {
+    symbolic_perms: "rwxXt",
+    symbolic_perms_required: true,
+    octal_sticky_bit: true,
+};
+
+/// Parse a permission mode passed in from a chmod command and apply that
+/// mode against an existing file.
+pub struct ChmodParser {
+    parser: PermissionParser,
+}
+
+impl ChmodParser {
+    pub fn new(mode_str: &str) -> anyhow::Result<Self> {
+        Ok(Self {
+            parser: PermissionParser::new(mode_str, &CHMOD_PATTERN)?,
+        })
+    }
+
+    /// Apply permission against specified file and determine what the
+    /// new mode would be
+    pub fn apply_new_permission(&self, file: &FileStatus) -> i16 {
+        let existing = file.get_permission().to_short() as i32;
+        let exe_ok = file.is_directory() || (existing & 0o111) != 0;
+        self.parser.combine_modes(existing, exe_ok) as i16
+    }
+}
@@ -5,7 +5,7 @@ use once_cell::sync::Lazy;
 pub static FSACTION_VALUES: Lazy<Vec<FsAction>> = Lazy::new(FsAction::values);
 
 /// File system actions, e.g. read, write, etc.
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 #[repr(u8)]
 pub enum FsAction {
     // POSIX style
@@ -69,8 +69,7 @@ impl FsAction {
     pub fn get_fs_action(permission: &str) -> Option<FsAction> {
         FSACTION_VALUES
             .iter()
-            .filter(|a| a.symbol() == permission)
-            .next()
+            .find(|a| a.symbol() == permission)
             .cloned()
     }
 }
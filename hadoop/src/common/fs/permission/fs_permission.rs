@@ -6,7 +6,9 @@ use crate::{
     },
     hdfs::FsPermissionProto,
 };
+use anyhow::Error;
 use once_cell::sync::Lazy;
+use std::fmt;
 
 const UMASK_LABEL: &str = FS_PERMISSIONS_UMASK_KEY;
 const DEFAULT_UMASK: i32 = FS_PERMISSIONS_UMASK_DEFAULT;
@@ -16,7 +18,7 @@ pub static DIR_DEFAULT_PERM: Lazy<FsPermission> = Lazy::new(FsPermission::get_di
 /// Default permission for file
 pub static FILE_DEFAULT_PERM: Lazy<FsPermission> = Lazy::new(FsPermission::get_file_default);
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct FsPermission {
     useraction: FsAction,
     groupaction: FsAction,
@@ -42,6 +44,25 @@ impl FsPermission {
         self.sticky_bit = sb;
     }
 
+    /// Return user [`FsAction`].
+    pub fn get_user_action(&self) -> FsAction {
+        self.useraction
+    }
+
+    /// Return group [`FsAction`].
+    pub fn get_group_action(&self) -> FsAction {
+        self.groupaction
+    }
+
+    /// Return other [`FsAction`].
+    pub fn get_other_action(&self) -> FsAction {
+        self.otheraction
+    }
+
+    pub fn get_sticky_bit(&self) -> bool {
+        self.sticky_bit
+    }
+
     /// Encode the object to a short.
     pub fn to_short(&self) -> i16 {
         (if self.sticky_bit { 1 << 9 } else { 0 })
@@ -81,7 +102,16 @@ impl FsPermission {
         // If the deprecated key is not present then check for the new key
         if let Some(conf) = conf {
             if let Some(conf_umask) = conf.get(UMASK_LABEL, None) {
-                umask = UmaskParser::new(conf_umask)?.get_umask();
+                umask = UmaskParser::new(&conf_umask)
+                    .map_err(|_| {
+                        let error = format!(
+                            "Unable to parse configuration {} with value {} as octal or symbolic umask.",
+                            UMASK_LABEL, conf_umask
+                        );
+                        log::warn!("{}", error);
+                        Error::msg(error)
+                    })?
+                    .get_umask();
             }
         }
         Ok(Self::from(umask))
@@ -111,10 +141,33 @@ impl From<i16> for FsPermission {
     }
 }
 
-impl Into<FsPermissionProto> for &FsPermission {
-    fn into(self) -> FsPermissionProto {
+impl fmt::Display for FsPermission {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let str = format!("{}{}", self.useraction, self.groupaction);
+        let other = self.otheraction.to_string();
+        if self.sticky_bit {
+            let sticky = if self.otheraction.implies(Some(&FsAction::Execute)) {
+                "t"
+            } else {
+                "T"
+            };
+            write!(f, "{}{}{}", str, &other[..other.len() - 1], sticky)
+        } else {
+            write!(f, "{}{}", str, other)
+        }
+    }
+}
+
+impl From<&FsPermissionProto> for FsPermission {
+    fn from(proto: &FsPermissionProto) -> Self {
+        Self::from(proto.perm as i16)
+    }
+}
+
+impl From<&FsPermission> for FsPermissionProto {
+    fn from(permission: &FsPermission) -> Self {
         FsPermissionProto {
-            perm: self.to_short() as u32,
+            perm: permission.to_short() as u32,
         }
     }
 }
@@ -1,8 +1,11 @@
+mod chmod_parser;
 mod fs_action;
 mod fs_create_modes;
 pub mod fs_permission;
+mod permission_parser;
 mod umask_parser;
 
+pub use chmod_parser::ChmodParser;
 pub use fs_action::FsAction;
 pub use fs_create_modes::FsCreateModes;
 pub use fs_permission::FsPermission;
@@ -0,0 +1,231 @@
+use anyhow::Error;
+
+/// The symbolic and octal forms accepted by a [`PermissionParser`].
+pub(crate) struct PermissionPattern {
+    // permission characters allowed in the symbolic form
+    pub symbolic_perms: &'static str,
+    // whether the symbolic form requires at least one permission character
+    pub symbolic_perms_required: bool,
+    // whether the octal form accepts a leading sticky bit digit,
+    // otherwise only leading zeros are allowed
+    pub octal_sticky_bit: bool,
+}
+
+/// Base class for parsing either chmod permissions or umask permissions.
+/// Includes common code needed by either operation as implemented in
+/// UmaskParser and ChmodParser classes.
+pub(crate) struct PermissionParser {
+    pub symbolic: bool,
+    user_mode: i32,
+    group_mode: i32,
+    others_mode: i32,
+    sticky_mode: i32,
+    user_type: char,
+    group_type: char,
+    others_type: char,
+    sticky_bit_type: char,
+}
+
+/// One `[ugoa]*[+=-]+[perms]*[,\s]*` clause of a symbolic mode.
+struct SymbolicClause {
+    who: String,
+    op: char,
+    perms: String,
+    comma_separated: bool,
+    end: usize,
+}
+
+impl PermissionParser {
+    /// Begin parsing permission stored in `mode_str`
+    pub fn new(mode_str: &str, pattern: &PermissionPattern) -> anyhow::Result<Self> {
+        let mut parser = Self {
+            symbolic: false,
+            user_mode: 0,
+            group_mode: 0,
+            others_mode: 0,
+            sticky_mode: 0,
+            user_type: '+',
+            group_type: '+',
+            others_type: '+',
+            sticky_bit_type: '+',
+        };
+        if parser.apply_normal_pattern(mode_str, pattern)?
+            || parser.apply_octal_pattern(mode_str, pattern)
+        {
+            Ok(parser)
+        } else {
+            Err(Error::msg(mode_str.to_owned()))
+        }
+    }
+
+    fn apply_normal_pattern(
+        &mut self,
+        mode_str: &str,
+        pattern: &PermissionPattern,
+    ) -> anyhow::Result<bool> {
+        let chars: Vec<char> = mode_str.chars().collect();
+        let mut clause = match Self::match_symbolic(&chars, 0, pattern) {
+            Some(clause) => clause,
+            None => return Ok(false),
+        };
+        // Are there multiple permissions stored in one chmod?
+        loop {
+            let (mut user, mut group, mut others) = (false, false, false);
+            for c in clause.who.chars() {
+                match c {
+                    'u' => user = true,
+                    'g' => group = true,
+                    'o' => others = true,
+                    _ => {}
+                }
+            }
+            if !(user || group || others) {
+                // same as specifying 'a'
+                (user, group, others) = (true, true, true);
+            }
+
+            let mut mode = 0;
+            let mut sticky_bit = false;
+            for c in clause.perms.chars() {
+                match c {
+                    'r' => mode |= 4,
+                    'w' => mode |= 2,
+                    'x' => mode |= 1,
+                    'X' => mode |= 8,
+                    't' => sticky_bit = true,
+                    _ => {}
+                }
+            }
+
+            if user {
+                self.user_mode = mode;
+                self.user_type = clause.op;
+            }
+            if group {
+                self.group_mode = mode;
+                self.group_type = clause.op;
+            }
+            if others {
+                self.others_mode = mode;
+                self.others_type = clause.op;
+                self.sticky_mode = sticky_bit as i32;
+                self.sticky_bit_type = clause.op;
+            }
+
+            if clause.end >= chars.len() {
+                break;
+            }
+            clause = match Self::match_symbolic(&chars, clause.end, pattern) {
+                Some(next) if clause.comma_separated => next,
+                _ => return Err(Error::msg(mode_str.to_owned())),
+            };
+        }
+        self.symbolic = true;
+        Ok(true)
+    }
+
+    /// Match `\s*([ugoa]*)([+=-]+)([perms]*)([,\s]*)\s*` starting at `start`.
+    fn match_symbolic(
+        chars: &[char],
+        start: usize,
+        pattern: &PermissionPattern,
+    ) -> Option<SymbolicClause> {
+        let take_while = |from: usize, pred: &dyn Fn(char) -> bool| {
+            let end = chars[from..]
+                .iter()
+                .position(|c| !pred(*c))
+                .map_or(chars.len(), |p| from + p);
+            (chars[from..end].iter().collect::<String>(), end)
+        };
+        let (_, pos) = take_while(start, &|c| c.is_ascii_whitespace());
+        let (who, pos) = take_while(pos, &|c| "ugoa".contains(c));
+        let (op, pos) = take_while(pos, &|c| "+=-".contains(c));
+        let (perms, pos) = take_while(pos, &|c| pattern.symbolic_perms.contains(c));
+        let (separator, pos) = take_while(pos, &|c| c == ',' || c.is_ascii_whitespace());
+        if op.is_empty() || (pattern.symbolic_perms_required && perms.is_empty()) {
+            return None;
+        }
+        Some(SymbolicClause {
+            who,
+            op: op.chars().last()?,
+            perms,
+            comma_separated: separator.contains(','),
+            end: pos,
+        })
+    }
+
+    fn apply_octal_pattern(&mut self, mode_str: &str, pattern: &PermissionPattern) -> bool {
+        let trimmed = mode_str.trim();
+        let digits = trimmed.strip_prefix('+').unwrap_or(trimmed);
+        if digits.len() < 3 || !digits.chars().all(|c| ('0'..='7').contains(&c)) {
+            return false;
+        }
+        let (sticky, mode) = digits.split_at(digits.len() - 3);
+        let valid_sticky = if pattern.octal_sticky_bit {
+            sticky.is_empty() || sticky == "0" || sticky == "1"
+        } else {
+            sticky.chars().all(|c| c == '0')
+        };
+        if !valid_sticky {
+            return false;
+        }
+
+        let type_apply = '=';
+        self.sticky_bit_type = type_apply;
+        self.user_type = type_apply;
+        self.group_type = type_apply;
+        self.others_type = type_apply;
+
+        // If sticky bit is specified get the bit and process it
+        self.sticky_mode = (sticky == "1") as i32;
+
+        // Process the other octal modes
+        let mode: Vec<i32> = mode.chars().map(|c| c as i32 - '0' as i32).collect();
+        self.user_mode = mode[0];
+        self.group_mode = mode[1];
+        self.others_mode = mode[2];
+        self.symbolic = false;
+        true
+    }
+
+    pub fn combine_modes(&self, existing: i32, exe_ok: bool) -> i32 {
+        Self::combine_mode_segments(self.sticky_bit_type, self.sticky_mode, existing >> 9, false)
+            << 9
+            | Self::combine_mode_segments(
+                self.user_type,
+                self.user_mode,
+                (existing >> 6) & 7,
+                exe_ok,
+            ) << 6
+            | Self::combine_mode_segments(
+                self.group_type,
+                self.group_mode,
+                (existing >> 3) & 7,
+                exe_ok,
+            ) << 3
+            | Self::combine_mode_segments(self.others_type, self.others_mode, existing & 7, exe_ok)
+    }
+
+    fn combine_mode_segments(op: char, mut mode: i32, existing: i32, exe_ok: bool) -> i32 {
+        let mut cap_x = false;
+        if (mode & 8) != 0 {
+            // convert X to x;
+            cap_x = true;
+            mode &= !8;
+            mode |= 1;
+        }
+
+        match op {
+            '+' => mode |= existing,
+            '-' => mode = !mode & existing,
+            _ => {}
+        }
+
+        // if X is specified add 'x' only if exe_ok or x was already set.
+        if cap_x && !exe_ok && (mode & 1) != 0 && (existing & 1) == 0 {
+            // remove x
+            mode &= !1;
+        }
+        mode
+    }
+}
@@ -1,13 +1,60 @@
+use super::permission_parser::{PermissionParser, PermissionPattern};
+
+/// Octal form with no leading 1 for sticky bit, and symbolic form which
+/// does not allow X or t.
+static UMASK_PATTERN: PermissionPattern = PermissionPattern {
+    symbolic_perms: "rwx",
+    symbolic_perms_required: false,
+    octal_sticky_bit: false,
+};
+
+/// Parse umask value provided as a string, either in octal or symbolic
+/// format and return it as a short value. Umask values are slightly
+/// different from standard modes as they cannot specify sticky bit
+/// or X.
 pub struct UmaskParser {
+    parser: PermissionParser,
     umask_mode: i16,
 }
 
 impl UmaskParser {
-    pub fn new(_mode_str: &str) -> anyhow::Result<Self> {
-        Ok(Self { umask_mode: 0o22 })
+    pub fn new(mode_str: &str) -> anyhow::Result<Self> {
+        let parser = PermissionParser::new(mode_str, &UMASK_PATTERN)?;
+        let umask_mode = parser.combine_modes(0, false) as i16;
+        Ok(Self { parser, umask_mode })
     }
 
+    /// To be used for file/directory creation only. Symbolic umask is applied
+    /// relative to file mode creation mask; the permission op characters '+'
+    /// results in clearing the corresponding bit in the mask, '-' results in bits
+    /// for indicated permission to be set in the mask.
+    ///
+    /// For octal umask, the specified bits are set in the file mode creation mask.
     pub fn get_umask(&self) -> i16 {
-        self.umask_mode
+        if self.parser.symbolic {
+            // Return the complement of effective permissions
+            !self.umask_mode
+        } else {
+            self.umask_mode
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_umask_parser() {
+        assert_eq!(UmaskParser::new("022").unwrap().get_umask(), 0o022);
+        assert_eq!(UmaskParser::new(" 0077 ").unwrap().get_umask(), 0o077);
+        assert_eq!(
+            UmaskParser::new("u=rwx,g=rx,o=").unwrap().get_umask() & 0o777,
+            0o027
+        );
+        assert_eq!(UmaskParser::new("a=rx").unwrap().get_umask() & 0o777, 0o222);
+        assert!(UmaskParser::new("1022").is_err());
+        assert!(UmaskParser::new("u=rwt").is_err());
+        assert!(UmaskParser::new("u=rwx g=rx").is_err());
     }
 }
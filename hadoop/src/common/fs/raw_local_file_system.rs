@@ -0,0 +1,343 @@
+use super::{
+    permission::FsPermission, FSDataInputStream, FSDataOutputStream, FileStatus, FileSystem,
+    FsStatus, Path,
+};
+use crate::common::conf::Configuration;
+use anyhow::Error;
+use iref::{Iri, IriBuf};
+use nix::{
+    sys::statvfs,
+    unistd::{self, Gid, Group, Uid, User},
+};
+use std::{
+    env,
+    fs::{self, File, FileTimes, Metadata, OpenOptions, Permissions},
+    io,
+    os::unix::fs::{MetadataExt, PermissionsExt},
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
+
+/// The scheme of the local file system.
+pub const LOCAL_FS_SCHEME: &str = "file";
+
+/// Implement the FileSystem API for the raw local filesystem.
+pub struct RawLocalFileSystem {
+    conf: Configuration,
+    uri: IriBuf,
+    working_dir: Path,
+    resolve_symlinks: bool,
+}
+
+impl RawLocalFileSystem {
+    /// Convert a path to a File.
+    pub fn path_to_file(&self, path: &Path) -> anyhow::Result<PathBuf> {
+        self.check_path(path)?;
+        let path = self.fix_relative_part(path)?;
+        Ok(PathBuf::from(path.get_uri_path()))
+    }
+
+    /// Check that a Path belongs to this FileSystem.
+    fn check_path(&self, path: &Path) -> anyhow::Result<()> {
+        match path.get_scheme() {
+            Some(scheme) if !scheme.eq_ignore_ascii_case(LOCAL_FS_SCHEME) => Err(Error::msg(
+                format!("Wrong FS: {}, expected: {}", path, self.uri),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    fn file_not_found(f: &Path) -> Error {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("File {} does not exist", f),
+        )
+        .into()
+    }
+
+    fn metadata(&self, f: &Path) -> anyhow::Result<Metadata> {
+        fs::metadata(self.path_to_file(f)?).map_err(|e| match e.kind() {
+            // a path under a file does not exist either
+            io::ErrorKind::NotFound | io::ErrorKind::NotADirectory => Self::file_not_found(f),
+            _ => e.into(),
+        })
+    }
+
+    fn to_file_status(&self, path: Path, metadata: &Metadata) -> FileStatus {
+        let owner = User::from_uid(Uid::from_raw(metadata.uid()))
+            .ok()
+            .flatten()
+            .map_or_else(|| metadata.uid().to_string(), |u| u.name);
+        let group = Group::from_gid(Gid::from_raw(metadata.gid()))
+            .ok()
+            .flatten()
+            .map_or_else(|| metadata.gid().to_string(), |g| g.name);
+        FileStatus::new(
+            metadata.len() as i64,
+            metadata.is_dir(),
+            1,
+            self.get_default_block_size(&path),
+            metadata.mtime() * 1000 + metadata.mtime_nsec() / 1_000_000,
+            metadata.atime() * 1000 + metadata.atime_nsec() / 1_000_000,
+            Some(FsPermission::from((metadata.mode() & 0o1777) as i16)),
+            &owner,
+            &group,
+            None,
+            path,
+            Default::default(),
+        )
+    }
+
+    /// Apply the umask of the configuration to the permission of a new file
+    /// or directory.
+    fn set_masked_permission(&self, f: &Path, permission: &FsPermission) -> anyhow::Result<()> {
+        let umask = FsPermission::get_umask(Some(&self.conf))?;
+        self.set_permission(f, &permission.apply_umask(&umask))
+    }
+
+    fn mkdirs_with_optional_permission(
+        &self,
+        f: &Path,
+        permission: Option<&FsPermission>,
+    ) -> anyhow::Result<bool> {
+        let p2f = self.path_to_file(f)?;
+        if let Ok(metadata) = fs::metadata(&p2f) {
+            if !metadata.is_dir() {
+                return Err(Error::msg(format!(
+                    "Destination exists and is not a directory: {}",
+                    p2f.display()
+                )));
+            }
+            return Ok(true);
+        }
+        if let Some(parent) = self.fix_relative_part(f)?.get_parent() {
+            let parent2f = self.path_to_file(&parent)?;
+            match fs::metadata(&parent2f) {
+                Ok(metadata) if !metadata.is_dir() => {
+                    return Err(Error::msg(format!(
+                        "Parent path is not a directory: {}",
+                        parent
+                    )))
+                }
+                Ok(_) => {}
+                Err(_) => {
+                    self.mkdirs_with_optional_permission(&parent, None)?;
+                }
+            }
+        }
+        match fs::create_dir(&p2f) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists && p2f.is_dir() => {}
+            Err(e) => return Err(e.into()),
+        }
+        if let Some(permission) = permission {
+            self.set_masked_permission(f, permission)?;
+        }
+        Ok(true)
+    }
+}
+
+impl FileSystem for RawLocalFileSystem {
+    fn new(uri: &Iri, conf: &Configuration) -> anyhow::Result<Self> {
+        let cwd = env::current_dir()?;
+        Ok(Self {
+            conf: conf.to_owned(),
+            uri: IriBuf::new(format!("{}:///", uri.scheme().as_str()))?,
+            working_dir: Path::from_components(
+                Some(LOCAL_FS_SCHEME),
+                Some(""),
+                &cwd.to_string_lossy(),
+            )?,
+            resolve_symlinks: Self::get_resolve_symlinks(conf),
+        })
+    }
+
+    fn resolve_symlinks(&self) -> bool {
+        self.resolve_symlinks
+    }
+
+    fn get_scheme(&self) -> anyhow::Result<&str> {
+        Ok(LOCAL_FS_SCHEME)
+    }
+
+    fn get_uri(&self) -> &Iri {
+        self.uri.as_iri()
+    }
+
+    fn get_home_directory(&self) -> anyhow::Result<Path> {
+        let home = env::var("HOME").unwrap_or_else(|_| "/".to_owned());
+        self.make_qualified(&Path::new(&home)?)
+    }
+
+    fn get_working_directory(&self) -> &Path {
+        &self.working_dir
+    }
+
+    fn mkdirs(&self, f: &Path, permission: Option<&FsPermission>) -> anyhow::Result<bool> {
+        self.mkdirs_with_optional_permission(f, permission)
+    }
+
+    fn open(&self, f: &Path) -> anyhow::Result<FSDataInputStream> {
+        if self.metadata(f)?.is_dir() {
+            return Err(Error::msg(format!("{} (Is a directory)", f)));
+        }
+        Ok(FSDataInputStream::new(Box::new(File::open(
+            self.path_to_file(f)?,
+        )?)))
+    }
+
+    fn create(
+        &self,
+        f: &Path,
+        permission: Option<&FsPermission>,
+        overwrite: bool,
+        _replication: Option<i16>,
+        _block_size: Option<i64>,
+    ) -> anyhow::Result<FSDataOutputStream> {
+        if self.exists(f)? && !overwrite {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("File already exists: {}", f),
+            )
+            .into());
+        }
+        if let Some(parent) = self.fix_relative_part(f)?.get_parent() {
+            if !self.mkdirs(&parent, None)? {
+                return Err(Error::msg(format!("Mkdirs failed to create {}", parent)));
+            }
+        }
+        let file = File::create(self.path_to_file(f)?)?;
+        if let Some(permission) = permission {
+            self.set_masked_permission(f, permission)?;
+        }
+        Ok(FSDataOutputStream::new(Box::new(file), 0))
+    }
+
+    fn append(&self, f: &Path) -> anyhow::Result<FSDataOutputStream> {
+        let metadata = self.metadata(f)?;
+        if metadata.is_dir() {
+            return Err(Error::msg(format!("Cannot append to a diretory (={} )", f)));
+        }
+        let file = OpenOptions::new()
+            .append(true)
+            .open(self.path_to_file(f)?)?;
+        Ok(FSDataOutputStream::new(Box::new(file), metadata.len()))
+    }
+
+    fn get_file_status(&self, f: &Path) -> anyhow::Result<FileStatus> {
+        let metadata = self.metadata(f)?;
+        Ok(self.to_file_status(self.make_qualified(f)?, &metadata))
+    }
+
+    fn list_status(&self, f: &Path) -> anyhow::Result<Vec<FileStatus>> {
+        let metadata = self.metadata(f)?;
+        let path = self.make_qualified(f)?;
+        if !metadata.is_dir() {
+            return Ok(vec![self.to_file_status(path, &metadata)]);
+        }
+        let mut results = vec![];
+        for entry in fs::read_dir(self.path_to_file(f)?)? {
+            let entry = entry?;
+            // the file may have been deleted since the listing
+            if let Ok(metadata) = fs::metadata(entry.path()) {
+                let child = path.child(&entry.file_name().to_string_lossy())?;
+                results.push(self.to_file_status(child, &metadata));
+            }
+        }
+        Ok(results)
+    }
+
+    /// Delete the given path to a file or directory.
+    fn delete(&self, p: &Path, recursive: bool) -> anyhow::Result<bool> {
+        let f = self.path_to_file(p)?;
+        let metadata = match fs::symlink_metadata(&f) {
+            Ok(metadata) => metadata,
+            // no path, return false "nothing to delete"
+            Err(_) => return Ok(false),
+        };
+        if !metadata.is_dir() {
+            fs::remove_file(&f)?;
+        } else if recursive {
+            fs::remove_dir_all(&f)?;
+        } else if fs::read_dir(&f)?.next().is_some() {
+            return Err(Error::msg(format!(
+                "Directory {} is not empty",
+                f.display()
+            )));
+        } else {
+            fs::remove_dir(&f)?;
+        }
+        Ok(true)
+    }
+
+    fn rename(&self, src: &Path, dst: &Path) -> anyhow::Result<bool> {
+        match fs::rename(self.path_to_file(src)?, self.path_to_file(dst)?) {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn set_permission(&self, p: &Path, permission: &FsPermission) -> anyhow::Result<()> {
+        Ok(fs::set_permissions(
+            self.path_to_file(p)?,
+            Permissions::from_mode(permission.to_short() as u32 & 0o1777),
+        )?)
+    }
+
+    /// Use the command chown to set owner.
+    fn set_owner(
+        &self,
+        p: &Path,
+        username: Option<&str>,
+        groupname: Option<&str>,
+    ) -> anyhow::Result<()> {
+        if username.is_none() && groupname.is_none() {
+            return Err(Error::msg("username == null && groupname == null"));
+        }
+        let uid = username
+            .map(|u| match User::from_name(u) {
+                Ok(Some(user)) => Ok(user.uid),
+                _ => Err(Error::msg(format!("chown: invalid user: '{}'", u))),
+            })
+            .transpose()?;
+        let gid = groupname
+            .map(|g| match Group::from_name(g) {
+                Ok(Some(group)) => Ok(group.gid),
+                _ => Err(Error::msg(format!("chown: invalid group: '{}'", g))),
+            })
+            .transpose()?;
+        Ok(unistd::chown(&self.path_to_file(p)?, uid, gid)?)
+    }
+
+    /// Sets the Path's last modified time and last access time to
+    /// the given valid times.
+    fn set_times(&self, p: &Path, mtime: i64, atime: i64) -> anyhow::Result<()> {
+        let to_system_time =
+            |ms: i64| SystemTime::UNIX_EPOCH + Duration::from_millis(ms.max(0) as u64);
+        let mut times = FileTimes::new();
+        if mtime >= 0 {
+            times = times.set_modified(to_system_time(mtime));
+        }
+        if atime >= 0 {
+            times = times.set_accessed(to_system_time(atime));
+        }
+        let f = self.path_to_file(p)?;
+        if !f.exists() {
+            return Err(Self::file_not_found(p));
+        }
+        Ok(File::open(f)?.set_times(times)?)
+    }
+
+    fn get_status(&self, p: Option<&Path>) -> anyhow::Result<FsStatus> {
+        let partition = match p {
+            Some(p) => self.path_to_file(p)?,
+            None => PathBuf::from("/"),
+        };
+        let stat = statvfs::statvfs(&partition)?;
+        let fragment_size = stat.fragment_size() as i64;
+        let total = stat.blocks() as i64 * fragment_size;
+        let free = stat.blocks_free() as i64 * fragment_size;
+        Ok(FsStatus::new(total, total - free, free))
+    }
+}
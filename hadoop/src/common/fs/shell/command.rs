@@ -0,0 +1,367 @@
+use super::{command_format::IllegalArgumentException, PathData};
+use crate::{
+    common::{
+        conf::Configuration,
+        fs::{file_system, FileSystem, Path, PathIOException},
+    },
+    hdfs::fs::file_system as fs_factory,
+};
+use anyhow::Error;
+use iref::IriBuf;
+use std::{collections::HashMap, io::Write, rc::Rc};
+
+/// The state shared by a [`Command`] while it runs: the configuration, the
+/// output streams, the filesystems touched so far and the error counters.
+pub(crate) struct CommandContext<'a> {
+    conf: &'a Configuration,
+    pub out: &'a mut dyn Write,
+    pub err: &'a mut dyn Write,
+    name: String,
+    recursive: bool,
+    depth: usize,
+    num_errors: usize,
+    pub exit_code: i32,
+    file_systems: HashMap<String, Rc<dyn FileSystem>>,
+}
+
+impl<'a> CommandContext<'a> {
+    pub fn new(
+        conf: &'a Configuration,
+        name: &str,
+        out: &'a mut dyn Write,
+        err: &'a mut dyn Write,
+    ) -> Self {
+        Self {
+            conf,
+            out,
+            err,
+            name: name.to_owned(),
+            recursive: false,
+            depth: 0,
+            num_errors: 0,
+            exit_code: 0,
+            file_systems: HashMap::new(),
+        }
+    }
+
+    pub fn get_conf(&self) -> &Configuration {
+        self.conf
+    }
+
+    pub fn set_recursive(&mut self, flag: bool) {
+        self.recursive = flag;
+    }
+
+    pub fn is_recursive(&self) -> bool {
+        self.recursive
+    }
+
+    /// The depth of the path currently being processed, the arguments being at
+    /// depth 0.
+    pub fn get_depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Get the FileSystem a path belongs to. Paths without a scheme, or with
+    /// the default scheme and no authority, are resolved against the default
+    /// filesystem.
+    pub fn get_file_system(&mut self, path: &Path) -> anyhow::Result<Rc<dyn FileSystem>> {
+        let default_uri = file_system::get_default_uri(self.conf)?;
+        let default_scheme = default_uri.scheme().as_str();
+        let default_authority = default_uri
+            .authority()
+            .map(|a| a.as_str())
+            .unwrap_or_default();
+        let (scheme, authority) = match path.get_scheme() {
+            None => (default_scheme, default_authority),
+            Some(scheme) => match path.get_authority() {
+                None if scheme == default_scheme => (scheme, default_authority),
+                authority => (scheme, authority.unwrap_or_default()),
+            },
+        };
+        let uri = format!("{}://{}/", scheme, authority);
+        if let Some(fs) = self.file_systems.get(&uri) {
+            return Ok(fs.clone());
+        }
+        let fs: Rc<dyn FileSystem> =
+            Rc::from(fs_factory::get(&IriBuf::new(uri.clone())?, self.conf)?);
+        self.file_systems.insert(uri, fs.clone());
+        Ok(fs)
+    }
+
+    /// Get the local FileSystem.
+    pub fn get_local_file_system(&mut self) -> anyhow::Result<Rc<dyn FileSystem>> {
+        self.get_file_system(&Path::new("file:///")?)
+    }
+
+    /// Display an error string prefaced with the command name, and count it.
+    pub fn display_error(&mut self, e: &Error) {
+        self.num_errors += 1;
+        self.display_warning(&e.to_string());
+    }
+
+    /// Display a warning string prefaced with the command name.
+    pub fn display_warning(&mut self, message: &str) {
+        let _ = writeln!(self.err, "{}: {}", self.name, message);
+    }
+}
+
+/// An abstract class for the execution of a file system command.
+///
+/// The default methods implement the standard processing of arguments: the
+/// options are parsed, each argument is expanded as a glob, and every
+/// resulting path is handed to [`Command::process_path`], recursing into
+/// directories when the command is recursive. Most methods have a free
+/// function counterpart in this module so overriding implementations can
+/// call the default behaviour.
+pub(crate) trait Command {
+    /// The usage of the command, excluding its name.
+    fn get_usage(&self) -> &'static str;
+
+    /// The description of the command.
+    fn get_description(&self) -> &'static str;
+
+    /// Must be implemented by commands to process the command line flags and
+    /// check the bounds of the remaining arguments.  If an
+    /// [`IllegalArgumentException`] is returned, the FsShell object will print
+    /// the short usage of the command.
+    fn process_options(
+        &mut self,
+        ctx: &mut CommandContext,
+        args: &mut Vec<String>,
+    ) -> anyhow::Result<()>;
+
+    /// Allows commands that don't use paths to handle the raw arguments.
+    /// Default behavior is to expand the arguments via
+    /// [`Command::expand_arguments`] and pass the resulting list to
+    /// [`Command::process_arguments`].
+    fn process_raw_arguments(
+        &mut self,
+        ctx: &mut CommandContext,
+        args: Vec<String>,
+    ) -> anyhow::Result<()> {
+        process_raw_arguments(self, ctx, args)
+    }
+
+    /// Expands a list of arguments into [`PathData`] objects.  The default
+    /// behavior is to call [`Command::expand_argument`] on each element
+    /// which by default globs the argument.
+    fn expand_arguments(
+        &mut self,
+        ctx: &mut CommandContext,
+        args: Vec<String>,
+    ) -> anyhow::Result<Vec<PathData>> {
+        let mut expanded_args = vec![];
+        for arg in args {
+            match self.expand_argument(ctx, &arg) {
+                Ok(items) => expanded_args.extend(items),
+                Err(e) => ctx.display_error(&e),
+            }
+        }
+        Ok(expanded_args)
+    }
+
+    /// Expand the given argument into a list of [`PathData`] objects.
+    /// The default behavior is to expand globs.  Commands may override to
+    /// perform other expansions on an argument.
+    fn expand_argument(
+        &mut self,
+        ctx: &mut CommandContext,
+        arg: &str,
+    ) -> anyhow::Result<Vec<PathData>> {
+        expand_argument(ctx, arg)
+    }
+
+    /// Processes the command's list of expanded arguments.
+    /// [`Command::process_argument`] will be invoked with each item in the
+    /// list.  The default action for the method is to do nothing.
+    fn process_arguments(
+        &mut self,
+        ctx: &mut CommandContext,
+        args: Vec<PathData>,
+    ) -> anyhow::Result<()> {
+        process_arguments(self, ctx, args)
+    }
+
+    /// Processes a [`PathData`] item, calling
+    /// [`Command::process_path_argument`] or
+    /// [`Command::process_nonexistent_path`] if the item does not exist.
+    fn process_argument(&mut self, ctx: &mut CommandContext, item: PathData) -> anyhow::Result<()> {
+        if item.exists() {
+            self.process_path_argument(ctx, item)
+        } else {
+            self.process_nonexistent_path(ctx, item)
+        }
+    }
+
+    /// This is the last chance to modify an argument before going into the
+    /// (possibly) recursive [`Command::process_paths`] that calls
+    /// [`Command::process_path`] on each item.
+    fn process_path_argument(
+        &mut self,
+        ctx: &mut CommandContext,
+        item: PathData,
+    ) -> anyhow::Result<()> {
+        process_path_argument(self, ctx, item)
+    }
+
+    /// Provides a hook for handling paths that don't exist.  By default it
+    /// will return an error.  Primarily overriden by commands that create
+    /// paths such as mkdir or touch.
+    fn process_nonexistent_path(
+        &mut self,
+        _ctx: &mut CommandContext,
+        item: PathData,
+    ) -> anyhow::Result<()> {
+        Err(PathIOException::not_found(&item.to_string()).into())
+    }
+
+    /// Iterates over the given expanded paths and invokes
+    /// [`Command::process_path`] on each element.  If "recursive" is true,
+    /// will do a post-visit DFS on directories.
+    fn process_paths(
+        &mut self,
+        ctx: &mut CommandContext,
+        parent: Option<&PathData>,
+        items: Vec<PathData>,
+    ) -> anyhow::Result<()> {
+        process_paths(self, ctx, parent, items)
+    }
+
+    /// Hook for commands to implement an operation to be applied on each
+    /// path for the command.  Note implementation of this method is optional
+    /// if earlier methods in the chain handle the operation.
+    fn process_path(&mut self, ctx: &mut CommandContext, item: &mut PathData)
+        -> anyhow::Result<()>;
+
+    /// Hook for commands to implement an operation to be applied on each
+    /// path for the command after being processed successfully.
+    fn post_process_path(
+        &mut self,
+        _ctx: &mut CommandContext,
+        _item: &PathData,
+    ) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// Determines whether a [`PathData`] item is recursable. Default
+    /// implementation is to recurse directories but can be overridden to
+    /// recurse through symbolic links.
+    fn is_path_recursable(&self, item: &PathData) -> bool {
+        item.stat.as_ref().is_some_and(|stat| stat.is_directory())
+    }
+
+    /// Gets the directory listing for a path and invokes
+    /// [`Command::process_paths`].
+    fn recurse_path(&mut self, ctx: &mut CommandContext, item: &PathData) -> anyhow::Result<()> {
+        recurse_path(self, ctx, item)
+    }
+}
+
+/// Invokes the command handler.  The default behavior is to process options,
+/// expand arguments, and then process each argument.
+///
+/// Returns the exit code of the command, 0 when all the paths were processed
+/// successfully and 1 otherwise.  Malformed command lines are returned as an
+/// [`IllegalArgumentException`] error.
+pub(crate) fn run(
+    cmd: &mut dyn Command,
+    ctx: &mut CommandContext,
+    mut args: Vec<String>,
+) -> anyhow::Result<i32> {
+    let result = cmd
+        .process_options(ctx, &mut args)
+        .and_then(|_| cmd.process_raw_arguments(ctx, args));
+    if let Err(e) = result {
+        if e.is::<IllegalArgumentException>() {
+            return Err(e);
+        }
+        ctx.display_error(&e);
+    }
+    Ok(if ctx.num_errors == 0 {
+        ctx.exit_code
+    } else {
+        1
+    })
+}
+
+pub(crate) fn process_raw_arguments<C: Command + ?Sized>(
+    cmd: &mut C,
+    ctx: &mut CommandContext,
+    args: Vec<String>,
+) -> anyhow::Result<()> {
+    let items = cmd.expand_arguments(ctx, args)?;
+    cmd.process_arguments(ctx, items)
+}
+
+pub(crate) fn expand_argument(
+    ctx: &mut CommandContext,
+    arg: &str,
+) -> anyhow::Result<Vec<PathData>> {
+    let items = PathData::expand_as_glob(ctx, arg)?;
+    if items.is_empty() {
+        // it's a glob that failed to match
+        return Err(PathIOException::not_found(arg).into());
+    }
+    Ok(items)
+}
+
+pub(crate) fn process_arguments<C: Command + ?Sized>(
+    cmd: &mut C,
+    ctx: &mut CommandContext,
+    args: Vec<PathData>,
+) -> anyhow::Result<()> {
+    for item in args {
+        if let Err(e) = cmd.process_argument(ctx, item) {
+            if e.is::<IllegalArgumentException>() {
+                return Err(e);
+            }
+            ctx.display_error(&e);
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn process_path_argument<C: Command + ?Sized>(
+    cmd: &mut C,
+    ctx: &mut CommandContext,
+    item: PathData,
+) -> anyhow::Result<()> {
+    // null indicates that the call is not via recursion, ie. there is
+    // no parent directory that was expanded
+    ctx.depth = 0;
+    cmd.process_paths(ctx, None, vec![item])
+}
+
+pub(crate) fn process_paths<C: Command + ?Sized>(
+    cmd: &mut C,
+    ctx: &mut CommandContext,
+    _parent: Option<&PathData>,
+    items: Vec<PathData>,
+) -> anyhow::Result<()> {
+    for mut item in items {
+        let result = cmd.process_path(ctx, &mut item).and_then(|_| {
+            if ctx.recursive && cmd.is_path_recursable(&item) {
+                cmd.recurse_path(ctx, &item)?;
+            }
+            cmd.post_process_path(ctx, &item)
+        });
+        if let Err(e) = result {
+            ctx.display_error(&e);
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn recurse_path<C: Command + ?Sized>(
+    cmd: &mut C,
+    ctx: &mut CommandContext,
+    item: &PathData,
+) -> anyhow::Result<()> {
+    ctx.depth += 1;
+    let result = item
+        .get_directory_contents()
+        .and_then(|items| cmd.process_paths(ctx, Some(item), items));
+    ctx.depth -= 1;
+    result
+}
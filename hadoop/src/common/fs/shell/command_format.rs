@@ -0,0 +1,127 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+};
+
+/// Thrown when the command line of a command is malformed, so the shell
+/// reports the usage of the command instead of a path error.
+#[derive(Debug)]
+pub(crate) struct IllegalArgumentException(pub String);
+
+impl fmt::Display for IllegalArgumentException {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for IllegalArgumentException {}
+
+/// Parse the args of a command and check the format of args.
+pub(crate) struct CommandFormat {
+    min_par: usize,
+    max_par: usize,
+    options: BTreeMap<String, bool>,
+}
+
+impl CommandFormat {
+    /// Simple parsing of command line arguments
+    pub fn new(min: usize, max: usize, possible_opt: &[&str]) -> Self {
+        Self {
+            min_par: min,
+            max_par: max,
+            options: possible_opt
+                .iter()
+                .map(|opt| (opt.to_string(), false))
+                .collect(),
+        }
+    }
+
+    /// Parse parameters from the given list of args.  The list is
+    /// destructively modified to remove the options.
+    pub fn parse(&mut self, args: &mut Vec<String>) -> Result<(), IllegalArgumentException> {
+        while let Some(arg) = args.first() {
+            // stop if not an opt, or the stdin arg "-" is found
+            if !arg.starts_with('-') || arg == "-" {
+                break;
+            } else if arg == "--" {
+                // force end of option processing
+                args.remove(0);
+                break;
+            }
+
+            match self.options.get_mut(&arg[1..]) {
+                Some(value) => {
+                    args.remove(0);
+                    *value = true;
+                }
+                None => {
+                    return Err(IllegalArgumentException(format!("Illegal option {}", arg)));
+                }
+            }
+        }
+        let psize = args.len();
+        if psize < self.min_par {
+            return Err(IllegalArgumentException(format!(
+                "Not enough arguments: expected {} but got {}",
+                self.min_par, psize
+            )));
+        }
+        if psize > self.max_par {
+            return Err(IllegalArgumentException(format!(
+                "Too many arguments: expected {} but got {}",
+                self.max_par, psize
+            )));
+        }
+        Ok(())
+    }
+
+    /// Return if the option is set or not
+    pub fn get_opt(&self, option: &str) -> bool {
+        self.options.get(option).copied().unwrap_or_default()
+    }
+
+    /// Returns all the options that are set
+    pub fn get_opts(&self) -> BTreeSet<&str> {
+        self.options
+            .iter()
+            .filter(|(_, set)| **set)
+            .map(|(opt, _)| opt.as_str())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse() {
+        let mut cf = CommandFormat::new(1, usize::MAX, &["R", "h"]);
+        let mut a = args(&["-R", "-h", "/a", "-b"]);
+        cf.parse(&mut a).unwrap();
+        assert_eq!(a, args(&["/a", "-b"]));
+        assert!(cf.get_opt("R") && cf.get_opt("h"));
+        assert_eq!(cf.get_opts().len(), 2);
+
+        let mut cf = CommandFormat::new(0, 1, &["f"]);
+        let mut a = args(&["--", "-f"]);
+        cf.parse(&mut a).unwrap();
+        assert!(!cf.get_opt("f"));
+
+        let mut cf = CommandFormat::new(1, 2, &["f"]);
+        let message = |cf: &mut CommandFormat, a: &[&str]| cf.parse(&mut args(a)).unwrap_err().0;
+        assert_eq!(message(&mut cf, &["-x", "a"]), "Illegal option -x");
+        assert_eq!(
+            message(&mut cf, &["-f"]),
+            "Not enough arguments: expected 1 but got 0"
+        );
+        assert_eq!(
+            message(&mut cf, &["a", "b", "c"]),
+            "Too many arguments: expected 2 but got 3"
+        );
+    }
+}
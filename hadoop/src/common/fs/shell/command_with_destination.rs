@@ -0,0 +1,317 @@
+use super::{CommandContext, PathData};
+use crate::common::fs::{path, PathIOException};
+use anyhow::Error;
+use std::io::{self, Read};
+
+/// The suffix of the temporary file a copy is written to before being renamed
+/// to its target.
+const COPYING_SUFFIX: &str = "._COPYING_";
+
+/// Provides the state and the shared steps of the commands that operate on a
+/// source and a destination, as in cp, mv, put and get.  The destination is
+/// the last argument and may be a directory, in which case the sources are
+/// copied into it.
+#[derive(Default)]
+pub(crate) struct CommandWithDestination {
+    dst: Option<PathData>,
+    overwrite: bool,
+    preserve: bool,
+    direct: bool,
+}
+
+impl CommandWithDestination {
+    /// Flag to indicate whether to overwrite an existing file while copying.
+    pub fn set_overwrite(&mut self, flag: bool) {
+        self.overwrite = flag;
+    }
+
+    /// If true, the last modified time, last access time,
+    /// owner, group and permission information of the source
+    /// file will be preserved as far as target `FileSystem`
+    /// implementation allows.
+    pub fn set_preserve(&mut self, preserve: bool) {
+        self.preserve = preserve;
+    }
+
+    /// If true, the file is written directly to the target instead of a
+    /// temporary `._COPYING_` file renamed once the copy completes.
+    pub fn set_direct_write(&mut self, flag: bool) {
+        self.direct = flag;
+    }
+
+    pub fn is_overwrite(&self) -> bool {
+        self.overwrite
+    }
+
+    pub fn get_dst(&self) -> &PathData {
+        self.dst
+            .as_ref()
+            .expect("the destination is set while processing the options")
+    }
+
+    /// The last arg is expected to be a local path, if only one argument is
+    /// given then the destination will be the current directory
+    pub fn get_local_destination(
+        &mut self,
+        ctx: &mut CommandContext,
+        args: &mut Vec<String>,
+    ) -> anyhow::Result<()> {
+        let path_string = if args.len() < 2 {
+            path::CUR_DIR.to_owned()
+        } else {
+            args.pop().unwrap_or_default()
+        };
+        self.dst = Some(PathData::new_local(ctx, &path_string)?);
+        Ok(())
+    }
+
+    /// The last arg is expected to be a remote path, if only one argument is
+    /// given then the destination will be the remote user's directory
+    pub fn get_remote_destination(
+        &mut self,
+        ctx: &mut CommandContext,
+        args: &mut Vec<String>,
+    ) -> anyhow::Result<()> {
+        if args.len() < 2 {
+            self.dst = Some(PathData::new(ctx, path::CUR_DIR)?);
+            return Ok(());
+        }
+        let path_string = args.pop().unwrap_or_default();
+        // if the path is a glob, then it must match one and only one path
+        let mut items = PathData::expand_as_glob(ctx, &path_string)?;
+        match items.len() {
+            0 => Err(PathIOException::not_found(&path_string).into()),
+            1 => {
+                self.dst = items.pop();
+                Ok(())
+            }
+            _ => Err(PathIOException::with_error(&path_string, "Too many matches").into()),
+        }
+    }
+
+    /// Checks the destination against the expanded sources: if more than one
+    /// arg, the destination must be a directory; if one arg, the dst must not
+    /// exist or must be a directory.
+    pub fn check_arguments(&self, args: &[PathData]) -> anyhow::Result<()> {
+        let dst = self.get_dst();
+        match &dst.stat {
+            Some(stat) if !stat.is_directory() => {
+                if args.len() > 1 {
+                    return Err(PathIOException::is_not_directory(&dst.to_string()).into());
+                }
+                if !self.overwrite {
+                    return Err(PathIOException::exists(&dst.to_string()).into());
+                }
+            }
+            Some(_) => {}
+            None if !dst.parent_exists()? => {
+                return Err(PathIOException::not_found(&dst.to_string()).into());
+            }
+            None => {}
+        }
+        Ok(())
+    }
+
+    /// Checks that a source directory is neither copied onto itself nor into
+    /// one of its subdirectories.
+    pub fn check_path_argument(&self, ctx: &CommandContext, src: &PathData) -> anyhow::Result<()> {
+        let dst = self.get_dst();
+        if !src.stat.as_ref().is_some_and(|s| s.is_directory())
+            || src.fs.get_uri() != dst.fs.get_uri()
+        {
+            return Ok(());
+        }
+        let target = self.get_target_path(ctx, src)?;
+        let mut src_path = src.fs.make_qualified(&src.path)?.to_string();
+        let dst_path = dst.fs.make_qualified(&target.path)?.to_string();
+        if dst_path == src_path {
+            return Err(
+                PathIOException::with_error(&src.to_string(), "are identical")
+                    .set_target_path(&dst_path)
+                    .into(),
+            );
+        }
+        // When a path is normalized, all trailing slashes are removed
+        // except for the root
+        if !src_path.ends_with(path::SEPARATOR) {
+            src_path.push_str(path::SEPARATOR);
+        }
+        if dst_path.starts_with(&src_path) {
+            return Err(PathIOException::with_error(
+                &src.to_string(),
+                "is a subdirectory of itself",
+            )
+            .set_target_path(&target.to_string())
+            .into());
+        }
+        Ok(())
+    }
+
+    /// Copies a source file to the target, a source directory is only
+    /// accepted if the command is recursive.
+    pub fn process_path(
+        &self,
+        ctx: &CommandContext,
+        src: &PathData,
+        target: &PathData,
+    ) -> anyhow::Result<()> {
+        let Some(stat) = &src.stat else {
+            return Err(PathIOException::not_found(&src.to_string()).into());
+        };
+        if stat.is_symlink() {
+            // TODO: remove when FileContext is supported, this needs to either
+            // copy the symlink or deref the symlink
+            Err(PathIOException::operation_not_supported(&src.to_string()).into())
+        } else if stat.is_file() {
+            self.copy_file_to_target(src, target)
+        } else if stat.is_directory() && !ctx.is_recursive() {
+            Err(PathIOException::is_directory(&src.to_string()).into())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Descends into the source directory `src`: the destination becomes the
+    /// target directory of `src`, created if needed.  Returns the previous
+    /// destination, to be restored once the directory is processed.
+    pub fn enter_directory(
+        &mut self,
+        ctx: &CommandContext,
+        src: &PathData,
+    ) -> anyhow::Result<PathData> {
+        // modify dst as we descend to append the basename of the
+        // current directory being processed
+        let mut dst = self.get_target_path(ctx, src)?;
+        match &dst.stat {
+            Some(stat) if !stat.is_directory() => {
+                return Err(PathIOException::is_not_directory(&dst.to_string()).into());
+            }
+            Some(_) => {}
+            None => {
+                if !dst.fs.mkdirs(&dst.path, None)? {
+                    return Err(PathIOException::new(&dst.to_string())
+                        .set_operation("mkdir")
+                        .into());
+                }
+                // need to update stat to know it exists now
+                dst.refresh_status()?;
+            }
+        }
+        Ok(self.dst.replace(dst).expect("the destination is set"))
+    }
+
+    /// Restores the destination saved by [`Self::enter_directory`] once the
+    /// source directory `src` has been processed, preserving the attributes
+    /// of the directory if requested.
+    pub fn leave_directory(&mut self, src: &PathData, saved_dst: PathData) -> anyhow::Result<()> {
+        let dst = self.dst.replace(saved_dst).expect("the destination is set");
+        if dst.stat.as_ref().is_some_and(|s| s.is_directory()) {
+            self.preserve_attributes(src, &dst)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the target of the copy of `src`, the destination itself or
+    /// the child named after `src` in the destination directory.
+    pub fn get_target_path(
+        &self,
+        ctx: &CommandContext,
+        src: &PathData,
+    ) -> anyhow::Result<PathData> {
+        let dst = self.get_dst();
+        // on the first loop, the dst may be directory or a file, so only create
+        // a child path if dst is a dir; after recursion, it's always a dir
+        if ctx.get_depth() > 0
+            || dst.stat.as_ref().is_some_and(|s| s.is_directory())
+            || dst.represents_directory()
+        {
+            dst.get_path_data_for_child(src)
+        } else {
+            Ok(dst.clone())
+        }
+    }
+
+    /// Copies the source file to the target.
+    pub fn copy_file_to_target(&self, src: &PathData, target: &PathData) -> anyhow::Result<()> {
+        let mut input = src.fs.open(&src.path)?;
+        self.copy_stream_to_target(&mut input, target)?;
+        self.preserve_attributes(src, target)
+    }
+
+    /// Copies the stream contents to a temporary file.  If the copy is
+    /// successful, the temporary file will be renamed to the real path,
+    /// else the temporary file will be deleted.
+    pub fn copy_stream_to_target(
+        &self,
+        input: &mut dyn Read,
+        target: &PathData,
+    ) -> anyhow::Result<()> {
+        if target
+            .stat
+            .as_ref()
+            .is_some_and(|s| s.is_directory() || !self.overwrite)
+        {
+            return Err(PathIOException::exists(&target.to_string()).into());
+        }
+        let temp_target = if self.direct {
+            target.clone()
+        } else {
+            target.suffix(COPYING_SUFFIX)?
+        };
+        if let Err(e) = write_stream_to_file(input, &temp_target) {
+            // last ditch effort to ensure temp file is removed
+            let _ = temp_target.fs.delete(&temp_target.path, false);
+            return Err(e);
+        }
+        if !self.direct {
+            rename(&temp_target, target).inspect_err(|_| {
+                let _ = temp_target.fs.delete(&temp_target.path, false);
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Preserve the attributes of the source to the target.
+    pub fn preserve_attributes(&self, src: &PathData, target: &PathData) -> anyhow::Result<()> {
+        if !self.preserve {
+            return Ok(());
+        }
+        let Some(stat) = &src.stat else {
+            return Ok(());
+        };
+        target.fs.set_times(
+            &target.path,
+            stat.get_modification_time(),
+            stat.get_access_time(),
+        )?;
+        target
+            .fs
+            .set_owner(&target.path, Some(stat.get_owner()), Some(stat.get_group()))?;
+        target
+            .fs
+            .set_permission(&target.path, stat.get_permission())
+    }
+}
+
+fn write_stream_to_file(input: &mut dyn Read, target: &PathData) -> anyhow::Result<()> {
+    let mut out = target.fs.create(&target.path, None, true, None, None)?;
+    io::copy(input, &mut out)?;
+    out.close()?;
+    Ok(())
+}
+
+fn rename(src: &PathData, target: &PathData) -> anyhow::Result<()> {
+    // the rename method with an option to delete the target is deprecated
+    if target.exists() && !target.fs.delete(&target.path, false)? {
+        // too bad we don't know why it failed
+        return Err(Error::msg(format!("delete {}", target)));
+    }
+    if !target.fs.rename(&src.path, &target.path)? {
+        // too bad we don't know why it failed
+        return Err(PathIOException::new(&src.to_string())
+            .set_operation("rename")
+            .set_target_path(&target.to_string())
+            .into());
+    }
+    Ok(())
+}
@@ -0,0 +1,388 @@
+use super::{command, Command, CommandContext, CommandFormat, CommandWithDestination, PathData};
+use crate::common::fs::{path, PathIOException};
+use anyhow::Error;
+use std::io::{self, Write};
+
+/// The standard input, read when `-` is given as the source.
+const STDIN: &str = "-";
+
+/// The commands copying files between filesystems.
+#[derive(Clone, Copy)]
+pub(crate) enum CopyKind {
+    /// Copy files within a filesystem or between filesystems.
+    Cp,
+    /// Copy local files to a remote filesystem.
+    Put,
+    /// Same as [`CopyKind::Put`], restricted to local sources.
+    CopyFromLocal,
+    /// Copy remote files to the local filesystem.
+    Get,
+    /// Same as [`CopyKind::Get`], restricted to a local destination.
+    CopyToLocal,
+}
+
+/// Copy files that match the file pattern <src> to a destination.
+pub(crate) struct CopyCommand {
+    kind: CopyKind,
+    dest: CommandWithDestination,
+}
+
+impl CopyCommand {
+    pub fn new(kind: CopyKind) -> Self {
+        Self {
+            kind,
+            dest: CommandWithDestination::default(),
+        }
+    }
+}
+
+impl Command for CopyCommand {
+    fn get_usage(&self) -> &'static str {
+        match self.kind {
+            CopyKind::Cp => "[-f] [-p] [-d] <src> ... <dst>",
+            CopyKind::Put | CopyKind::CopyFromLocal => "[-f] [-p] [-l] [-d] <localsrc> ... <dst>",
+            CopyKind::Get | CopyKind::CopyToLocal => {
+                "[-f] [-p] [-ignoreCrc] [-crc] <src> ... <localdst>"
+            }
+        }
+    }
+
+    fn get_description(&self) -> &'static str {
+        match self.kind {
+            CopyKind::Cp => {
+                "Copy files that match the file pattern <src> to a destination.  When copying \
+                 multiple files, the destination must be a directory.\n\
+                 Flags :\n\
+                 \x20 -p  Preserves timestamps, ownership and the mode.\n\
+                 \x20 -f  Overwrites the destination if it already exists.\n\
+                 \x20 -d  Skip creation of temporary file(<dst>._COPYING_)."
+            }
+            CopyKind::Put => {
+                "Copy files from the local file system into fs. Copying fails if the file \
+                 already exists, unless the -f flag is given.\n\
+                 Flags:\n\
+                 \x20 -p  Preserves timestamps, ownership and the mode.\n\
+                 \x20 -f  Overwrites the destination if it already exists.\n\
+                 \x20 -l  Allow DataNode to lazily persist the file to disk. Forces \
+                 replication factor of 1.\n\
+                 \x20 -d  Skip creation of temporary file(<dst>._COPYING_)."
+            }
+            CopyKind::CopyFromLocal => "Identical to the -put command.",
+            CopyKind::Get => {
+                "Copy files that match the file pattern <src> to the local name.  <src> is \
+                 kept.  When copying multiple files, the destination must be a directory.\n\
+                 Flags:\n\
+                 \x20 -p          Preserves timestamps, ownership and the mode.\n\
+                 \x20 -f          Overwrites the destination if it already exists.\n\
+                 \x20 -ignoreCrc  write CRC checksums for the files downloaded.\n\
+                 \x20 -crc        write CRC checksums for the files downloaded."
+            }
+            CopyKind::CopyToLocal => "Identical to the -get command.",
+        }
+    }
+
+    fn process_options(
+        &mut self,
+        ctx: &mut CommandContext,
+        args: &mut Vec<String>,
+    ) -> anyhow::Result<()> {
+        match self.kind {
+            CopyKind::Cp => {
+                let mut cf = CommandFormat::new(2, usize::MAX, &["f", "p", "d"]);
+                cf.parse(args)?;
+                self.dest.set_direct_write(cf.get_opt("d"));
+                self.dest.set_overwrite(cf.get_opt("f"));
+                self.dest.set_preserve(cf.get_opt("p"));
+                // should have a -r option
+                ctx.set_recursive(true);
+                self.dest.get_remote_destination(ctx, args)
+            }
+            CopyKind::Put | CopyKind::CopyFromLocal => {
+                let mut cf = CommandFormat::new(1, usize::MAX, &["f", "p", "l", "d"]);
+                cf.parse(args)?;
+                self.dest.set_overwrite(cf.get_opt("f"));
+                self.dest.set_preserve(cf.get_opt("p"));
+                self.dest.set_direct_write(cf.get_opt("d"));
+                self.dest.get_remote_destination(ctx, args)?;
+                // should have a -r option
+                ctx.set_recursive(true);
+                Ok(())
+            }
+            CopyKind::Get | CopyKind::CopyToLocal => {
+                let mut cf = CommandFormat::new(1, usize::MAX, &["crc", "ignoreCrc", "p", "f"]);
+                cf.parse(args)?;
+                self.dest.set_preserve(cf.get_opt("p"));
+                self.dest.set_overwrite(cf.get_opt("f"));
+                // should have a -r option
+                ctx.set_recursive(true);
+                self.dest.get_local_destination(ctx, args)
+            }
+        }
+    }
+
+    fn expand_argument(
+        &mut self,
+        ctx: &mut CommandContext,
+        arg: &str,
+    ) -> anyhow::Result<Vec<PathData>> {
+        match self.kind {
+            CopyKind::Put | CopyKind::CopyFromLocal => {
+                // the sources are local files, taken literally
+                Ok(vec![new_local_path_data(ctx, arg)?])
+            }
+            _ => command::expand_argument(ctx, arg),
+        }
+    }
+
+    fn process_arguments(
+        &mut self,
+        ctx: &mut CommandContext,
+        args: Vec<PathData>,
+    ) -> anyhow::Result<()> {
+        if matches!(self.kind, CopyKind::Put | CopyKind::CopyFromLocal)
+            && args.len() == 1
+            && args[0].to_string() == STDIN
+        {
+            // NOTE: this logic should be better, mimics previous implementation
+            let target = self.dest.get_target_path(ctx, &args[0])?;
+            return self
+                .dest
+                .copy_stream_to_target(&mut io::stdin().lock(), &target);
+        }
+        self.dest.check_arguments(&args)?;
+        command::process_arguments(self, ctx, args)
+    }
+
+    fn process_path_argument(
+        &mut self,
+        ctx: &mut CommandContext,
+        item: PathData,
+    ) -> anyhow::Result<()> {
+        self.dest.check_path_argument(ctx, &item)?;
+        command::process_path_argument(self, ctx, item)
+    }
+
+    fn process_path(
+        &mut self,
+        ctx: &mut CommandContext,
+        item: &mut PathData,
+    ) -> anyhow::Result<()> {
+        let target = self.dest.get_target_path(ctx, item)?;
+        self.dest.process_path(ctx, item, &target)
+    }
+
+    fn recurse_path(&mut self, ctx: &mut CommandContext, item: &PathData) -> anyhow::Result<()> {
+        let saved_dst = self.dest.enter_directory(ctx, item)?;
+        let result = command::recurse_path(self, ctx, item);
+        let restored = self.dest.leave_directory(item, saved_dst);
+        result.and(restored)
+    }
+}
+
+/// Merge files from a filesystem into a single local file
+#[derive(Default)]
+pub(crate) struct Merge {
+    dst: Option<PathData>,
+    srcs: Vec<PathData>,
+    delimiter: Option<&'static str>,
+    skip_empty_file_delimiter: bool,
+}
+
+impl Merge {
+    pub const NAME: &'static str = "getmerge";
+}
+
+impl Command for Merge {
+    fn get_usage(&self) -> &'static str {
+        "[-nl] [-skip-empty-file] <src> <localdst>"
+    }
+
+    fn get_description(&self) -> &'static str {
+        "Get all the files in the directories that match the source file pattern and \
+         merge and sort them to only one file on local fs. <src> is kept.\n\
+         \x20 -nl   Add a newline character at the end of each file.\n\
+         \x20 -skip-empty-file   Do not add new line character for empty file."
+    }
+
+    fn process_options(
+        &mut self,
+        ctx: &mut CommandContext,
+        args: &mut Vec<String>,
+    ) -> anyhow::Result<()> {
+        let mut cf = CommandFormat::new(2, usize::MAX, &["nl", "skip-empty-file"]);
+        cf.parse(args)?;
+        self.delimiter = cf.get_opt("nl").then_some("\n");
+        self.skip_empty_file_delimiter = cf.get_opt("skip-empty-file");
+
+        let dst = new_local_path_data(ctx, &args.pop().unwrap_or_default())?;
+        if dst.stat.as_ref().is_some_and(|s| s.is_directory()) {
+            return Err(PathIOException::is_directory(&dst.to_string()).into());
+        }
+        self.dst = Some(dst);
+        Ok(())
+    }
+
+    fn process_arguments(
+        &mut self,
+        ctx: &mut CommandContext,
+        args: Vec<PathData>,
+    ) -> anyhow::Result<()> {
+        command::process_arguments(self, ctx, args)?;
+        // check for error collecting paths
+        if ctx.exit_code != 0 {
+            return Ok(());
+        }
+        let Some(dst) = &self.dst else {
+            return Ok(());
+        };
+        let mut out = dst.fs.create(&dst.path, None, true, None, None)?;
+        for src in &self.srcs {
+            let len = src.stat.as_ref().map_or(0, |s| s.get_len());
+            if len != 0 {
+                let mut input = src.fs.open(&src.path)?;
+                io::copy(&mut input, &mut out)?;
+            }
+            if let Some(delimiter) = self.delimiter {
+                if !self.skip_empty_file_delimiter || len != 0 {
+                    out.write_all(delimiter.as_bytes())?;
+                }
+            }
+        }
+        out.close()?;
+        Ok(())
+    }
+
+    fn process_nonexistent_path(
+        &mut self,
+        ctx: &mut CommandContext,
+        item: PathData,
+    ) -> anyhow::Result<()> {
+        // flag that a path is bad
+        ctx.exit_code = 1;
+        Err(PathIOException::not_found(&item.to_string()).into())
+    }
+
+    fn process_path(
+        &mut self,
+        ctx: &mut CommandContext,
+        item: &mut PathData,
+    ) -> anyhow::Result<()> {
+        // for directories, recurse one level to get its files, else skip it
+        if item.stat.as_ref().is_some_and(|s| s.is_directory()) {
+            if ctx.get_depth() == 0 {
+                self.recurse_path(ctx, item)?;
+            }
+            // skip subdirs
+        } else {
+            self.srcs.push(item.clone());
+        }
+        Ok(())
+    }
+}
+
+/// Appends the contents of all the given local files to the
+/// given dst file. The dst file will be created if it does
+/// not exist. If a source file is `-` the input is read from stdin.
+#[derive(Default)]
+pub(crate) struct AppendToFile {
+    dst: Option<PathData>,
+    read_stdin: bool,
+}
+
+impl AppendToFile {
+    pub const NAME: &'static str = "appendToFile";
+}
+
+impl Command for AppendToFile {
+    fn get_usage(&self) -> &'static str {
+        "<localsrc> ... <dst>"
+    }
+
+    fn get_description(&self) -> &'static str {
+        "Appends the contents of all the given local files to the given dst file. The dst \
+         file will be created if it does not exist. If <localSrc> is -, then the input is \
+         read from stdin."
+    }
+
+    fn process_options(
+        &mut self,
+        ctx: &mut CommandContext,
+        args: &mut Vec<String>,
+    ) -> anyhow::Result<()> {
+        if args.len() < 2 {
+            return Err(Error::msg("missing destination argument"));
+        }
+        let mut dest = CommandWithDestination::default();
+        dest.get_remote_destination(ctx, args)?;
+        self.dst = Some(dest.get_dst().clone());
+        CommandFormat::new(1, usize::MAX, &[]).parse(args)?;
+        Ok(())
+    }
+
+    fn expand_arguments(
+        &mut self,
+        ctx: &mut CommandContext,
+        args: Vec<String>,
+    ) -> anyhow::Result<Vec<PathData>> {
+        let mut items = vec![];
+        for arg in args {
+            if arg == STDIN {
+                self.read_stdin = true;
+                continue;
+            }
+            let item = new_local_path_data(ctx, &arg)?;
+            if !item.exists() {
+                return Err(PathIOException::not_found(&arg).into());
+            }
+            items.push(item);
+        }
+        Ok(items)
+    }
+
+    fn process_arguments(
+        &mut self,
+        _ctx: &mut CommandContext,
+        args: Vec<PathData>,
+    ) -> anyhow::Result<()> {
+        let Some(dst) = &self.dst else {
+            return Ok(());
+        };
+        if !dst.exists() {
+            dst.fs.create(&dst.path, None, false, None, None)?.close()?;
+        }
+        let mut out = dst.fs.append(&dst.path)?;
+        if self.read_stdin {
+            if !args.is_empty() {
+                return Err(Error::msg(
+                    "stdin (-) must be the sole input argument when present",
+                ));
+            }
+            io::copy(&mut io::stdin().lock(), &mut out)?;
+        }
+        // Read in each input file and write to the target.
+        for source in &args {
+            let mut input = source.fs.open(&source.path)?;
+            io::copy(&mut input, &mut out)?;
+        }
+        out.close()?;
+        Ok(())
+    }
+
+    fn process_path(
+        &mut self,
+        _ctx: &mut CommandContext,
+        _item: &mut PathData,
+    ) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+/// A source path on the local filesystem, unless it names another
+/// filesystem with a scheme.
+fn new_local_path_data(ctx: &mut CommandContext, arg: &str) -> anyhow::Result<PathData> {
+    if path::Path::new(arg)?.get_scheme().is_some() {
+        PathData::new(ctx, arg)
+    } else {
+        PathData::new_local(ctx, arg)
+    }
+}
@@ -0,0 +1,70 @@
+use super::{Command, CommandContext, CommandFormat, PathData};
+use crate::common::fs::{path, ContentSummary};
+
+/// Count the number of directories, files, bytes, quota, and remaining quota.
+#[derive(Default)]
+pub(crate) struct Count {
+    show_quotas: bool,
+    human_readable: bool,
+    show_quotas_and_usage_only: bool,
+}
+
+impl Count {
+    pub const NAME: &'static str = "count";
+}
+
+impl Command for Count {
+    fn get_usage(&self) -> &'static str {
+        "[-q] [-h] [-v] [-u] <path> ..."
+    }
+
+    fn get_description(&self) -> &'static str {
+        "Count the number of directories, files and bytes under the paths\n\
+         that match the specified file pattern.  The output columns are:\n\
+         DIR_COUNT FILE_COUNT CONTENT_SIZE PATHNAME\n\
+         or, with the -q option:\n\
+         QUOTA REM_QUOTA SPACE_QUOTA REM_SPACE_QUOTA\n\
+         \x20     DIR_COUNT FILE_COUNT CONTENT_SIZE PATHNAME\n\
+         The -h option shows file sizes in human readable format.\n\
+         The -v option displays a header line.\n\
+         The -u option shows the quota and the usage against the quota without the \
+         detailed content summary."
+    }
+
+    fn process_options(
+        &mut self,
+        ctx: &mut CommandContext,
+        args: &mut Vec<String>,
+    ) -> anyhow::Result<()> {
+        let mut cf = CommandFormat::new(1, usize::MAX, &["q", "h", "v", "u"]);
+        cf.parse(args)?;
+        if args.is_empty() {
+            // default path is the current working directory
+            args.push(path::CUR_DIR.to_owned());
+        }
+        self.show_quotas = cf.get_opt("q");
+        self.human_readable = cf.get_opt("h");
+        self.show_quotas_and_usage_only = cf.get_opt("u");
+
+        if cf.get_opt("v") {
+            let head_string = if self.show_quotas_and_usage_only {
+                ContentSummary::get_quota_header()
+            } else {
+                ContentSummary::get_header(self.show_quotas)
+            };
+            writeln!(ctx.out, "{}PATHNAME", head_string)?;
+        }
+        Ok(())
+    }
+
+    fn process_path(&mut self, ctx: &mut CommandContext, src: &mut PathData) -> anyhow::Result<()> {
+        let summary = src.fs.get_content_summary(&src.path)?;
+        let output_string = if self.show_quotas_and_usage_only {
+            summary.get_quota_usage(self.human_readable)
+        } else {
+            summary.to_string(self.show_quotas, self.human_readable)
+        };
+        writeln!(ctx.out, "{}{}", output_string, src)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,164 @@
+use super::{command, Command, CommandContext, CommandFormat, PathData};
+use crate::common::fs::{
+    common_configuration_keys_public::{
+        HADOOP_SHELL_SAFELY_DELETE_LIMIT_NUM_FILES,
+        HADOOP_SHELL_SAFELY_DELETE_LIMIT_NUM_FILES_DEFAULT,
+    },
+    path_io_exception, PathIOException, Trash,
+};
+use anyhow::Error;
+use std::io::{self, BufRead};
+
+/// remove non-directory paths
+#[derive(Default)]
+pub(crate) struct Rm {
+    skip_trash: bool,
+    delete_dirs: bool,
+    ignore_fnf: bool,
+    safe_delete: bool,
+}
+
+impl Rm {
+    pub const NAME: &'static str = "rm";
+
+    fn can_be_safely_deleted(
+        &self,
+        ctx: &mut CommandContext,
+        item: &PathData,
+    ) -> anyhow::Result<bool> {
+        if !self.safe_delete {
+            return Ok(true);
+        }
+        let delete_limit = ctx.get_conf().get_long(
+            HADOOP_SHELL_SAFELY_DELETE_LIMIT_NUM_FILES,
+            HADOOP_SHELL_SAFELY_DELETE_LIMIT_NUM_FILES_DEFAULT,
+        )?;
+        if delete_limit > 0 {
+            let num_files = item.fs.get_content_summary(&item.path)?.get_file_count();
+            if num_files > delete_limit
+                && !confirm_prompt(ctx, &format!("Proceed deleting {} files?", num_files))?
+            {
+                writeln!(ctx.err, "Delete aborted at user request.\n")?;
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    fn move_to_trash(&self, ctx: &CommandContext, item: &PathData) -> anyhow::Result<bool> {
+        if self.skip_trash {
+            return Ok(false);
+        }
+        Trash::move_to_appropriate_trash(item.fs.as_ref(), &item.path, ctx.get_conf()).map_err(
+            |e| {
+                if path_io_exception::is_file_not_found(&e) {
+                    e
+                } else {
+                    Error::msg(format!("{}. Consider using -skipTrash option", e))
+                }
+            },
+        )
+    }
+}
+
+impl Command for Rm {
+    fn get_usage(&self) -> &'static str {
+        "[-f] [-r|-R] [-skipTrash] [-safely] <src> ..."
+    }
+
+    fn get_description(&self) -> &'static str {
+        "Delete all files that match the specified file pattern. Equivalent to the Unix \
+         command \"rm <src>\"\n\
+         \x20 -f          If the file does not exist, do not display a diagnostic message or \
+         modify the exit status to reflect an error.\n\
+         \x20 -[rR]       Recursively deletes directories.\n\
+         \x20 -skipTrash  option bypasses trash, if enabled, and immediately deletes <src>.\n\
+         \x20 -safely     option requires safety confirmation, if enabled, requires \
+         confirmation before deleting large directory with more than \
+         <hadoop.shell.delete.limit.num.files> files. Delay is expected when walking over \
+         large directory recursively to count the number of files to be deleted before the \
+         confirmation."
+    }
+
+    fn process_options(
+        &mut self,
+        _ctx: &mut CommandContext,
+        args: &mut Vec<String>,
+    ) -> anyhow::Result<()> {
+        let mut cf = CommandFormat::new(1, usize::MAX, &["f", "r", "R", "skipTrash", "safely"]);
+        cf.parse(args)?;
+        self.ignore_fnf = cf.get_opt("f");
+        self.delete_dirs = cf.get_opt("r") || cf.get_opt("R");
+        self.skip_trash = cf.get_opt("skipTrash");
+        self.safe_delete = cf.get_opt("safely");
+        Ok(())
+    }
+
+    fn expand_argument(
+        &mut self,
+        ctx: &mut CommandContext,
+        arg: &str,
+    ) -> anyhow::Result<Vec<PathData>> {
+        match command::expand_argument(ctx, arg) {
+            // prevent -f on a non-existent glob from failing
+            Err(e) if self.ignore_fnf && path_io_exception::is_file_not_found(&e) => Ok(vec![]),
+            result => result,
+        }
+    }
+
+    fn process_nonexistent_path(
+        &mut self,
+        _ctx: &mut CommandContext,
+        item: PathData,
+    ) -> anyhow::Result<()> {
+        if self.ignore_fnf {
+            return Ok(());
+        }
+        Err(PathIOException::not_found(&item.to_string()).into())
+    }
+
+    fn process_path(
+        &mut self,
+        ctx: &mut CommandContext,
+        item: &mut PathData,
+    ) -> anyhow::Result<()> {
+        if item.stat.as_ref().is_some_and(|s| s.is_directory()) && !self.delete_dirs {
+            return Err(PathIOException::is_directory(&item.to_string()).into());
+        }
+
+        // TODO: if the user wants the trash to be used but there is any
+        // problem (ie. creating the trash dir, moving the item to be deleted,
+        // etc), then the path will just be deleted because moveToTrash returns
+        // false and it falls thru to fs.delete.  this doesn't seem right
+        if self.move_to_trash(ctx, item)? || !self.can_be_safely_deleted(ctx, item)? {
+            return Ok(());
+        }
+        if !item.fs.delete(&item.path, self.delete_dirs)? {
+            return Err(PathIOException::new(&item.to_string()).into());
+        }
+        writeln!(ctx.out, "Deleted {}", item)?;
+        Ok(())
+    }
+}
+
+/// Print out a prompt to the user, and return true if the user
+/// responds with "y" or "yes". (case insensitive)
+fn confirm_prompt(ctx: &mut CommandContext, prompt: &str) -> anyhow::Result<bool> {
+    let stdin = io::stdin();
+    loop {
+        write!(ctx.err, "{} (Y or N) ", prompt)?;
+        ctx.err.flush()?;
+        let mut response = String::new();
+        if stdin.lock().read_line(&mut response)? == 0 {
+            return Ok(false);
+        }
+        let response = response.trim();
+        if response.eq_ignore_ascii_case("y") || response.eq_ignore_ascii_case("yes") {
+            return Ok(true);
+        } else if response.eq_ignore_ascii_case("n") || response.eq_ignore_ascii_case("no") {
+            return Ok(false);
+        }
+        writeln!(ctx.err, "Invalid input: {}", response)?;
+        // else ask them again
+    }
+}
@@ -0,0 +1,157 @@
+use super::{Command, CommandContext, CommandFormat, PathData};
+use crate::common::{fs::PathIOException, util::string_utils};
+use flate2::read::MultiGzDecoder;
+use std::io::{self, Read, Seek, SeekFrom};
+
+/// The magic number of the gzip format.
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Displays file content to stdout
+#[derive(Default)]
+pub(crate) struct Cat;
+
+impl Cat {
+    pub const NAME: &'static str = "cat";
+}
+
+impl Command for Cat {
+    fn get_usage(&self) -> &'static str {
+        "[-ignoreCrc] <src> ..."
+    }
+
+    fn get_description(&self) -> &'static str {
+        "Fetch all files that match the file pattern <src> and display their content on \
+         stdout."
+    }
+
+    fn process_options(
+        &mut self,
+        ctx: &mut CommandContext,
+        args: &mut Vec<String>,
+    ) -> anyhow::Result<()> {
+        CommandFormat::new(1, usize::MAX, &["ignoreCrc"]).parse(args)?;
+        ctx.set_recursive(false);
+        Ok(())
+    }
+
+    fn process_path(
+        &mut self,
+        ctx: &mut CommandContext,
+        item: &mut PathData,
+    ) -> anyhow::Result<()> {
+        check_not_directory(item)?;
+        let mut input = item.fs.open(&item.path)?;
+        io::copy(&mut input, ctx.out)?;
+        Ok(())
+    }
+}
+
+/// Same behavior as "-cat", but handles zip and TextRecordInputStream
+/// encodings.
+#[derive(Default)]
+pub(crate) struct Text;
+
+impl Text {
+    pub const NAME: &'static str = "text";
+}
+
+impl Command for Text {
+    fn get_usage(&self) -> &'static str {
+        "[-ignoreCrc] <src> ..."
+    }
+
+    fn get_description(&self) -> &'static str {
+        "Takes a source file and outputs the file in text format.\n\
+         The allowed formats are zip and TextRecordInputStream."
+    }
+
+    fn process_options(
+        &mut self,
+        ctx: &mut CommandContext,
+        args: &mut Vec<String>,
+    ) -> anyhow::Result<()> {
+        CommandFormat::new(1, usize::MAX, &["ignoreCrc"]).parse(args)?;
+        ctx.set_recursive(false);
+        Ok(())
+    }
+
+    fn process_path(
+        &mut self,
+        ctx: &mut CommandContext,
+        item: &mut PathData,
+    ) -> anyhow::Result<()> {
+        check_not_directory(item)?;
+        let mut input = item.fs.open(&item.path)?;
+        let mut magic = [0; 2];
+        let len = input.read(&mut magic)?;
+        input.seek(SeekFrom::Start(0))?;
+        if len == magic.len() && magic == GZIP_MAGIC {
+            io::copy(&mut MultiGzDecoder::new(input), ctx.out)?;
+        } else {
+            io::copy(&mut input, ctx.out)?;
+        }
+        Ok(())
+    }
+}
+
+/// Display md5 checksum of a file.
+#[derive(Default)]
+pub(crate) struct Checksum;
+
+impl Checksum {
+    pub const NAME: &'static str = "checksum";
+}
+
+impl Command for Checksum {
+    fn get_usage(&self) -> &'static str {
+        "<src> ..."
+    }
+
+    fn get_description(&self) -> &'static str {
+        "Dump checksum information for files that match the file pattern <src> to stdout. \
+         Note that this requires a round-trip to a datanode storing each block of the \
+         file, and thus is not efficient to run on a large number of files. The checksum \
+         of a file depends on its content, block size and the checksum algorithm and \
+         parameters used for creating the file."
+    }
+
+    fn process_options(
+        &mut self,
+        _ctx: &mut CommandContext,
+        args: &mut Vec<String>,
+    ) -> anyhow::Result<()> {
+        CommandFormat::new(1, usize::MAX, &[]).parse(args)?;
+        Ok(())
+    }
+
+    fn process_path(
+        &mut self,
+        ctx: &mut CommandContext,
+        item: &mut PathData,
+    ) -> anyhow::Result<()> {
+        check_not_directory(item)?;
+        match item.fs.get_file_checksum(&item.path)? {
+            None => writeln!(ctx.out, "{}\tNONE\t", item)?,
+            Some(checksum) => {
+                let bytes = checksum.get_bytes();
+                writeln!(
+                    ctx.out,
+                    "{}\t{}\t{}",
+                    item,
+                    checksum.get_algorithm_name(),
+                    string_utils::byte_to_hex_string(
+                        &bytes[..checksum.get_length().min(bytes.len())]
+                    )
+                )?
+            }
+        }
+        Ok(())
+    }
+}
+
+fn check_not_directory(item: &PathData) -> anyhow::Result<()> {
+    if item.stat.as_ref().is_some_and(|s| s.is_directory()) {
+        return Err(PathIOException::is_directory(&item.to_string()).into());
+    }
+    Ok(())
+}
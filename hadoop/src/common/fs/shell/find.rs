@@ -0,0 +1,152 @@
+use super::{command_format::IllegalArgumentException, Command, CommandContext, PathData};
+use crate::common::fs::{path, GlobPattern};
+
+/// A test or an action of a find expression.
+enum Expression {
+    /// Evaluates as true if the basename of the file matches the pattern,
+    /// ignoring the case if `case_insensitive` is set.
+    Name {
+        pattern: GlobPattern,
+        case_insensitive: bool,
+    },
+    /// Always evaluates to true, printing the path followed by the
+    /// terminator.
+    Print { terminator: &'static str },
+}
+
+impl Expression {
+    fn is_action(&self) -> bool {
+        matches!(self, Self::Print { .. })
+    }
+
+    fn apply(&self, ctx: &mut CommandContext, item: &PathData) -> anyhow::Result<bool> {
+        match self {
+            Self::Name {
+                pattern,
+                case_insensitive,
+            } => {
+                let mut name = item.path.get_name();
+                if *case_insensitive {
+                    name = name.to_lowercase();
+                }
+                Ok(pattern.matches(&name))
+            }
+            Self::Print { terminator } => {
+                write!(ctx.out, "{}{}", item, terminator)?;
+                Ok(true)
+            }
+        }
+    }
+}
+
+/// Implements a Hadoop find command.
+#[derive(Default)]
+pub(crate) struct Find {
+    /// The expressions of the command, all of which must be satisfied in turn.
+    root_expression: Vec<Expression>,
+}
+
+impl Find {
+    pub const NAME: &'static str = "find";
+
+    /// Parse a list of arguments to to extract the expression.
+    fn parse_expression(&mut self, args: Vec<String>) -> anyhow::Result<()> {
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let expression = match arg.as_str() {
+                "-a" | "-and" => continue,
+                "-name" | "-iname" => {
+                    let case_insensitive = arg == "-iname";
+                    let pattern = args.next().ok_or_else(|| {
+                        IllegalArgumentException(format!("{}: Missing argument", arg))
+                    })?;
+                    let pattern = if case_insensitive {
+                        pattern.to_lowercase()
+                    } else {
+                        pattern
+                    };
+                    Expression::Name {
+                        pattern: GlobPattern::new(&pattern)?,
+                        case_insensitive,
+                    }
+                }
+                "-print" => Expression::Print { terminator: "\n" },
+                "-print0" => Expression::Print { terminator: "\0" },
+                _ => {
+                    return Err(
+                        IllegalArgumentException(format!("Unexpected argument: {}", arg)).into(),
+                    )
+                }
+            };
+            self.root_expression.push(expression);
+        }
+        if !self.root_expression.iter().any(Expression::is_action) {
+            self.root_expression
+                .push(Expression::Print { terminator: "\n" });
+        }
+        Ok(())
+    }
+}
+
+impl Command for Find {
+    fn get_usage(&self) -> &'static str {
+        "<path> ... <expression> ..."
+    }
+
+    fn get_description(&self) -> &'static str {
+        "Finds all files that match the specified expression and applies selected actions to \
+         them. If no <path> is specified then defaults to the current working directory. If \
+         no expression is specified then defaults to -print.\n\n\
+         The following primary expressions are recognised:\n\
+         \x20 -name pattern\n\
+         \x20 -iname pattern\n\
+         \x20   Evaluates as true if the basename of the file matches the pattern using \
+         standard file system globbing. If -iname is used then the match is case \
+         insensitive.\n\n\
+         \x20 -print\n\
+         \x20 -print0\n\
+         \x20   Always evaluates to true. Causes the current pathname to be written to \
+         standard output followed by a newline. If the -print0 expression is used then an \
+         ASCII NULL character is appended rather than a newline.\n\n\
+         The following operators are recognised:\n\
+         \x20 expression -a expression\n\
+         \x20 expression -and expression\n\
+         \x20 expression expression\n\
+         \x20   Logical AND operator for joining two expressions. Returns true if both \
+         child expressions return true. Implied by the juxtaposition of two expressions and \
+         so does not need to be explicitly specified. The second expression will not be \
+         applied if the first fails."
+    }
+
+    fn process_options(
+        &mut self,
+        ctx: &mut CommandContext,
+        args: &mut Vec<String>,
+    ) -> anyhow::Result<()> {
+        // the paths are the arguments before the first expression
+        let first_expression = args
+            .iter()
+            .position(|arg| arg.starts_with('-') && arg.len() > 1)
+            .unwrap_or(args.len());
+        let expression = args.split_off(first_expression);
+        self.parse_expression(expression)?;
+        if args.is_empty() {
+            args.push(path::CUR_DIR.to_owned());
+        }
+        ctx.set_recursive(true);
+        Ok(())
+    }
+
+    fn process_path(
+        &mut self,
+        ctx: &mut CommandContext,
+        item: &mut PathData,
+    ) -> anyhow::Result<()> {
+        for expression in &self.root_expression {
+            if !expression.apply(ctx, item)? {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,218 @@
+use super::{
+    command_format::IllegalArgumentException, Command, CommandContext, CommandFormat, PathData,
+};
+use crate::common::fs::permission::{ChmodParser, FsPermission};
+use anyhow::Error;
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+/// The characters allowed in owner and group names.
+const ALLOWED_CHARS: &str = "[-_./@a-zA-Z0-9]";
+
+static CHOWN_PATTERN: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(&format!(r"^\s*({0}+)?([:]({0}*))?\s*$", ALLOWED_CHARS))
+        .expect("valid chown pattern")
+});
+
+static CHGRP_PATTERN: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(&format!(r"^\s*({}+)\s*$", ALLOWED_CHARS)).expect("valid chgrp pattern")
+});
+
+/// The pattern is almost as flexible as mode allowed by chmod shell command.
+/// The main restriction is that we recognize only rwxXt. To reduce errors we
+/// also enforce octal mode specifications of either 3 digits without a sticky
+/// bit setting or four digits with a sticky bit setting.
+#[derive(Default)]
+pub(crate) struct Chmod {
+    pp: Option<ChmodParser>,
+}
+
+impl Chmod {
+    pub const NAME: &'static str = "chmod";
+}
+
+impl Command for Chmod {
+    fn get_usage(&self) -> &'static str {
+        "[-R] <MODE[,MODE]... | OCTALMODE> PATH..."
+    }
+
+    fn get_description(&self) -> &'static str {
+        "Changes permissions of a file. This works similar to the shell's chmod command with \
+         a few exceptions.\n\
+         -R           modifies the files recursively. This is the only option currently \
+         supported.\n\
+         <MODE>       Mode is the same as mode used for the shell's command. The only letters \
+         recognized are 'rwxXt', e.g. +t,a+r,g-w,+rwx,o=r.\n\
+         <OCTALMODE>  Mode specifed in 3 or 4 digits. If 4 digits, the first may be 1 or 0 to \
+         turn the sticky bit on or off, respectively.  Unlike the shell command, it is not \
+         possible to specify only part of the mode, e.g. 754 is same as u=rwx,g=rx,o=r.\n\n\
+         If none of 'augo' is specified, 'a' is assumed and unlike the shell command, no \
+         umask is applied."
+    }
+
+    fn process_options(
+        &mut self,
+        ctx: &mut CommandContext,
+        args: &mut Vec<String>,
+    ) -> anyhow::Result<()> {
+        let mut cf = CommandFormat::new(2, usize::MAX, &["R"]);
+        cf.parse(args)?;
+        ctx.set_recursive(cf.get_opt("R"));
+
+        let mode_str = args.remove(0);
+        // TODO: remove "chmod : " so it's not doubled up in output, but it's
+        // here for backwards compatibility...
+        self.pp = Some(ChmodParser::new(&mode_str).map_err(|_| {
+            IllegalArgumentException(format!(
+                "chmod : mode '{}' does not match the expected pattern.",
+                mode_str
+            ))
+        })?);
+        Ok(())
+    }
+
+    fn process_path(
+        &mut self,
+        _ctx: &mut CommandContext,
+        item: &mut PathData,
+    ) -> anyhow::Result<()> {
+        let (Some(pp), Some(stat)) = (&self.pp, &item.stat) else {
+            return Ok(());
+        };
+        let new_perms = pp.apply_new_permission(stat);
+        if stat.get_permission().to_short() != new_perms {
+            item.fs
+                .set_permission(&item.path, &FsPermission::from(new_perms))
+                .map_err(|e| {
+                    log::debug!("Error changing permissions of {}: {}", item, e);
+                    Error::msg(format!("changing permissions of '{}': {}", item, e))
+                })?;
+        }
+        Ok(())
+    }
+}
+
+/// Used to change owner and/or group of files, or only the group of files
+/// with chgrp.
+#[derive(Default)]
+pub(crate) struct Chown {
+    chgrp: bool,
+    owner: Option<String>,
+    group: Option<String>,
+}
+
+impl Chown {
+    pub const NAME: &'static str = "chown";
+    pub const CHGRP_NAME: &'static str = "chgrp";
+
+    /// The chgrp command, which only changes the group of files.
+    pub fn chgrp() -> Self {
+        Self {
+            chgrp: true,
+            ..Default::default()
+        }
+    }
+
+    /// Parse the first argument into an owner and group
+    fn parse_owner_group(&mut self, owner_str: &str) -> Result<(), IllegalArgumentException> {
+        if self.chgrp {
+            let captures = CHGRP_PATTERN.captures(owner_str).ok_or_else(|| {
+                IllegalArgumentException(format!(
+                    "'{}' does not match expected pattern for group",
+                    owner_str
+                ))
+            })?;
+            self.owner = None;
+            self.group = captures.get(1).map(|m| m.as_str().to_owned());
+            return Ok(());
+        }
+
+        let captures = CHOWN_PATTERN.captures(owner_str).ok_or_else(|| {
+            IllegalArgumentException(format!(
+                "'{}' does not match expected pattern for [owner][:group].",
+                owner_str
+            ))
+        })?;
+        self.owner = captures.get(1).map(|m| m.as_str().to_owned());
+        self.group = captures
+            .get(3)
+            .map(|m| m.as_str().to_owned())
+            .filter(|g| !g.is_empty());
+        if self.owner.is_none() && self.group.is_none() {
+            return Err(IllegalArgumentException(format!(
+                "'{}' does not specify owner or group.",
+                owner_str
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl Command for Chown {
+    fn get_usage(&self) -> &'static str {
+        if self.chgrp {
+            "[-R] GROUP PATH..."
+        } else {
+            "[-R] [OWNER][:[GROUP]] PATH..."
+        }
+    }
+
+    fn get_description(&self) -> &'static str {
+        if self.chgrp {
+            "This is equivalent to -chown ... :GROUP ..."
+        } else {
+            "Changes owner and group of a file. This is similar to the shell's chown command \
+             with a few exceptions.\n\
+             -R  modifies the files recursively. This is the only option currently \
+             supported.\n\n\
+             If only the owner or group is specified, then only the owner or group is \
+             modified. The owner and group names may only consist of digits, alphabet, and \
+             any of -_./@. The names are case sensitive.\n\n\
+             WARNING: Avoid using '.' to separate user name and group though Linux allows \
+             it. If user names have dots in them and you are using local file system, you \
+             might see surprising results since the shell command 'chown' is used for local \
+             files."
+        }
+    }
+
+    fn process_options(
+        &mut self,
+        ctx: &mut CommandContext,
+        args: &mut Vec<String>,
+    ) -> anyhow::Result<()> {
+        let mut cf = CommandFormat::new(2, usize::MAX, &["R"]);
+        cf.parse(args)?;
+        ctx.set_recursive(cf.get_opt("R"));
+        let owner_str = args.remove(0);
+        self.parse_owner_group(&owner_str)?;
+        Ok(())
+    }
+
+    fn process_path(
+        &mut self,
+        _ctx: &mut CommandContext,
+        item: &mut PathData,
+    ) -> anyhow::Result<()> {
+        let Some(stat) = &item.stat else {
+            return Ok(());
+        };
+        //Should we do case insensitive match?
+        let new_owner = self
+            .owner
+            .as_deref()
+            .filter(|owner| *owner != stat.get_owner());
+        let new_group = self
+            .group
+            .as_deref()
+            .filter(|group| *group != stat.get_group());
+        if new_owner.is_some() || new_group.is_some() {
+            item.fs
+                .set_owner(&item.path, new_owner, new_group)
+                .map_err(|e| {
+                    log::debug!("Error changing ownership of {}: {}", item, e);
+                    Error::msg(format!("changing ownership of '{}': {}", item, e))
+                })?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,263 @@
+use super::{command, Command, CommandContext, CommandFormat, PathData};
+use crate::common::{
+    fs::path,
+    util::string_utils::{self, TraditionalBinaryPrefix},
+};
+use std::io::{self, Write};
+
+/// Format a size for the usage commands, with a binary prefix when
+/// `human_readable` is set.
+fn format_size(size: i64, human_readable: bool) -> String {
+    if human_readable {
+        TraditionalBinaryPrefix::long2string(size, "", 1)
+    } else {
+        size.to_string()
+    }
+}
+
+/// Show the size of a partition in the filesystem
+#[derive(Default)]
+pub(crate) struct Df {
+    human_readable: bool,
+    usages_table: TableBuilder,
+}
+
+impl Df {
+    pub const NAME: &'static str = "df";
+}
+
+impl Command for Df {
+    fn get_usage(&self) -> &'static str {
+        "[-h] [<path> ...]"
+    }
+
+    fn get_description(&self) -> &'static str {
+        "Shows the capacity, free and used space of the filesystem. If the filesystem has \
+         multiple partitions, and no path to a particular partition is specified, then the \
+         status of the root partitions will be shown.\n\
+         \x20 -h  Formats the sizes of files in a human-readable fashion rather than a number \
+         of bytes."
+    }
+
+    fn process_options(
+        &mut self,
+        _ctx: &mut CommandContext,
+        args: &mut Vec<String>,
+    ) -> anyhow::Result<()> {
+        let mut cf = CommandFormat::new(0, usize::MAX, &["h"]);
+        cf.parse(args)?;
+        self.human_readable = cf.get_opt("h");
+        if args.is_empty() {
+            args.push(path::SEPARATOR.to_owned());
+        }
+        Ok(())
+    }
+
+    fn process_arguments(
+        &mut self,
+        ctx: &mut CommandContext,
+        args: Vec<PathData>,
+    ) -> anyhow::Result<()> {
+        self.usages_table = TableBuilder::with_header(&[
+            "Filesystem",
+            "Size",
+            "Used",
+            "Available",
+            "Use%",
+            "Mounted on",
+        ]);
+        self.usages_table.set_right_align(&[1, 2, 3, 4]);
+
+        command::process_arguments(self, ctx, args)?;
+        self.usages_table.print_to_stream(ctx.out)?;
+        Ok(())
+    }
+
+    fn process_path(
+        &mut self,
+        _ctx: &mut CommandContext,
+        item: &mut PathData,
+    ) -> anyhow::Result<()> {
+        let fs_stats = item.fs.get_status(Some(&item.path))?;
+        let size = fs_stats.get_capacity();
+        let used = fs_stats.get_used();
+        let free = fs_stats.get_remaining();
+        self.usages_table.add_row(vec![
+            item.fs.get_uri().to_string(),
+            format_size(size, self.human_readable),
+            format_size(used, self.human_readable),
+            format_size(free, self.human_readable),
+            string_utils::format_percent(used as f64 / size as f64, 0),
+            path::SEPARATOR.to_owned(),
+        ]);
+        Ok(())
+    }
+}
+
+/// show disk usage
+#[derive(Default)]
+pub(crate) struct Du {
+    human_readable: bool,
+    summary: bool,
+    show_headers: bool,
+    usages_table: TableBuilder,
+}
+
+impl Du {
+    pub const NAME: &'static str = "du";
+}
+
+impl Command for Du {
+    fn get_usage(&self) -> &'static str {
+        "[-s] [-h] [-v] [-x] <path> ..."
+    }
+
+    fn get_description(&self) -> &'static str {
+        "Show the amount of space, in bytes, used by the files that match the specified file \
+         pattern. The following flags are optional:\n\
+         \x20 -s  Rather than showing the size of each individual file that matches the \
+         pattern, shows the total (summary) size.\n\
+         \x20 -h  Formats the sizes of files in a human-readable fashion rather than a number \
+         of bytes.\n\
+         \x20 -v  option displays a header line.\n\
+         \x20 -x  Excludes snapshots from being counted.\n\n\
+         Note that, even without the -s option, this only shows size summaries one level \
+         deep into a directory.\n\n\
+         The output is in the form \n\
+         \tsize\tdisk space consumed\tname(full path)"
+    }
+
+    fn process_options(
+        &mut self,
+        _ctx: &mut CommandContext,
+        args: &mut Vec<String>,
+    ) -> anyhow::Result<()> {
+        let mut cf = CommandFormat::new(0, usize::MAX, &["h", "s", "v", "x"]);
+        cf.parse(args)?;
+        self.human_readable = cf.get_opt("h");
+        self.summary = cf.get_opt("s");
+        self.show_headers = cf.get_opt("v");
+        if args.is_empty() {
+            args.push(path::CUR_DIR.to_owned());
+        }
+        Ok(())
+    }
+
+    fn process_arguments(
+        &mut self,
+        ctx: &mut CommandContext,
+        args: Vec<PathData>,
+    ) -> anyhow::Result<()> {
+        self.usages_table = if self.show_headers {
+            TableBuilder::with_header(&[
+                "SIZE",
+                "DISK_SPACE_CONSUMED_WITH_ALL_REPLICAS",
+                "FULL_PATH_NAME",
+            ])
+        } else {
+            TableBuilder::default()
+        };
+        command::process_arguments(self, ctx, args)?;
+        self.usages_table.print_to_stream(ctx.out)?;
+        Ok(())
+    }
+
+    fn process_path_argument(
+        &mut self,
+        ctx: &mut CommandContext,
+        item: PathData,
+    ) -> anyhow::Result<()> {
+        // go one level deep on dirs from cmdline unless in summary mode
+        if !self.summary && self.is_path_recursable(&item) {
+            self.recurse_path(ctx, &item)
+        } else {
+            command::process_path_argument(self, ctx, item)
+        }
+    }
+
+    fn process_path(
+        &mut self,
+        _ctx: &mut CommandContext,
+        item: &mut PathData,
+    ) -> anyhow::Result<()> {
+        let content_summary = item.fs.get_content_summary(&item.path)?;
+        let length = content_summary.get_length();
+        let space_consumed = content_summary.get_space_consumed();
+        self.usages_table.add_row(vec![
+            format_size(length, self.human_readable),
+            format_size(space_consumed, self.human_readable),
+            item.to_string(),
+        ]);
+        Ok(())
+    }
+}
+
+/// Creates a table of aligned values based on the maximum width of each
+/// column as a string
+#[derive(Default)]
+struct TableBuilder {
+    has_header: bool,
+    rows: Vec<Vec<String>>,
+    right_align: Vec<usize>,
+}
+
+impl TableBuilder {
+    fn with_header(headers: &[&str]) -> Self {
+        Self {
+            has_header: true,
+            rows: vec![headers.iter().map(|h| h.to_string()).collect()],
+            right_align: vec![],
+        }
+    }
+
+    /// Change the default left-align of columns to right-align
+    fn set_right_align(&mut self, indexes: &[usize]) {
+        self.right_align = indexes.to_vec();
+    }
+
+    /// Add a row of objects to the table
+    fn add_row(&mut self, row: Vec<String>) {
+        self.rows.push(row);
+    }
+
+    /// Does table have any rows
+    fn is_empty(&self) -> bool {
+        self.rows.len() <= usize::from(self.has_header)
+    }
+
+    /// Render the table to a stream
+    fn print_to_stream(&self, out: &mut dyn Write) -> io::Result<()> {
+        if self.is_empty() {
+            return Ok(());
+        }
+        let columns = self.rows.iter().map(Vec::len).max().unwrap_or_default();
+        let max_widths: Vec<usize> = (0..columns)
+            .map(|i| {
+                self.rows
+                    .iter()
+                    .filter_map(|row| row.get(i))
+                    .map(|value| value.chars().count())
+                    .max()
+                    .unwrap_or_default()
+            })
+            .collect();
+        for row in &self.rows {
+            let mut line = String::new();
+            for (i, value) in row.iter().enumerate() {
+                if i != 0 {
+                    line.push_str("  ");
+                }
+                let width = max_widths[i];
+                if self.right_align.contains(&i) {
+                    line.push_str(&format!("{:>width$}", value));
+                } else if i != columns - 1 {
+                    line.push_str(&format!("{:<width$}", value));
+                } else {
+                    line.push_str(value);
+                }
+            }
+            writeln!(out, "{}", line)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,191 @@
+use super::{command, Command, CommandContext, CommandFormat, PathData};
+use crate::common::{fs::path, util::string_utils::TraditionalBinaryPrefix};
+use chrono::{Local, TimeZone};
+use std::cmp::Ordering;
+
+/// Get a listing of all files in that match the file patterns.
+#[derive(Default)]
+pub(crate) struct Ls {
+    dir_recurse: bool,
+    path_only: bool,
+    human_readable: bool,
+    order_reverse: bool,
+    order_time: bool,
+    order_size: bool,
+    use_atime: bool,
+    max_repl: usize,
+    max_len: usize,
+    max_owner: usize,
+    max_group: usize,
+}
+
+impl Ls {
+    pub const NAME: &'static str = "ls";
+
+    fn format_size(&self, size: i64) -> String {
+        if self.human_readable {
+            TraditionalBinaryPrefix::long2string(size, "", 1)
+        } else {
+            size.to_string()
+        }
+    }
+
+    fn adjust_column_widths(&mut self, items: &[PathData]) {
+        for stat in items.iter().filter_map(|item| item.stat.as_ref()) {
+            self.max_repl = self.max_repl.max(stat.get_replication().to_string().len());
+            self.max_len = self.max_len.max(self.format_size(stat.get_len()).len());
+            self.max_owner = self.max_owner.max(stat.get_owner().len());
+            self.max_group = self.max_group.max(stat.get_group().len());
+        }
+    }
+
+    /// Compare the items in the order requested by the options: by path by
+    /// default, newest first for -t, largest first for -S, reversed for -r.
+    fn compare(&self, a: &PathData, b: &PathData) -> Ordering {
+        let ordering = match (&a.stat, &b.stat) {
+            (Some(sa), Some(sb)) if self.order_time => {
+                let (ta, tb) = if self.use_atime {
+                    (sa.get_access_time(), sb.get_access_time())
+                } else {
+                    (sa.get_modification_time(), sb.get_modification_time())
+                };
+                tb.cmp(&ta).then_with(|| a.cmp(b))
+            }
+            (Some(sa), Some(sb)) if self.order_size => {
+                sb.get_len().cmp(&sa.get_len()).then_with(|| a.cmp(b))
+            }
+            _ => a.cmp(b),
+        };
+        if self.order_reverse {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    }
+}
+
+impl Command for Ls {
+    fn get_usage(&self) -> &'static str {
+        "[-C] [-d] [-h] [-R] [-t] [-S] [-r] [-u] [<path> ...]"
+    }
+
+    fn get_description(&self) -> &'static str {
+        "List the contents that match the specified file pattern. If path is not \
+         specified, the contents of /user/<currentUser> will be listed. For a directory a \
+         list of its direct children is returned (unless -d option is specified).\n\n\
+         Directory entries are of the form:\n\
+         \tpermissions - userId groupId sizeOfDirectory(in bytes) \
+         modificationDate(yyyy-MM-dd HH:mm) directoryName\n\n\
+         and file entries are of the form:\n\
+         \tpermissions numberOfReplicas userId groupId sizeOfFile(in bytes) \
+         modificationDate(yyyy-MM-dd HH:mm) fileName\n\n\
+         \x20 -C  Display the paths of files and directories only.\n\
+         \x20 -d  Directories are listed as plain files.\n\
+         \x20 -h  Formats the sizes of files in a human-readable fashion rather than a \
+         number of bytes.\n\
+         \x20 -R  Recursively list the contents of directories.\n\
+         \x20 -t  Sort files by modification time (most recent first).\n\
+         \x20 -S  Sort files by size.\n\
+         \x20 -r  Reverse the order of the sort.\n\
+         \x20 -u  Use time of last access instead of modification for display and sorting."
+    }
+
+    fn process_options(
+        &mut self,
+        ctx: &mut CommandContext,
+        args: &mut Vec<String>,
+    ) -> anyhow::Result<()> {
+        let mut cf = CommandFormat::new(0, usize::MAX, &["C", "d", "h", "R", "t", "S", "r", "u"]);
+        cf.parse(args)?;
+        self.path_only = cf.get_opt("C");
+        self.dir_recurse = !cf.get_opt("d");
+        ctx.set_recursive(cf.get_opt("R") && self.dir_recurse);
+        self.human_readable = cf.get_opt("h");
+        self.order_reverse = cf.get_opt("r");
+        self.order_time = cf.get_opt("t");
+        self.order_size = !self.order_time && cf.get_opt("S");
+        self.use_atime = cf.get_opt("u");
+        self.max_repl = 3;
+        self.max_len = 10;
+        if args.is_empty() {
+            args.push(path::CUR_DIR.to_owned());
+        }
+        Ok(())
+    }
+
+    fn process_path_argument(
+        &mut self,
+        ctx: &mut CommandContext,
+        item: PathData,
+    ) -> anyhow::Result<()> {
+        // implicitly recurse once for cmdline directories
+        if self.dir_recurse && self.is_path_recursable(&item) {
+            self.recurse_path(ctx, &item)
+        } else {
+            command::process_path_argument(self, ctx, item)
+        }
+    }
+
+    fn process_paths(
+        &mut self,
+        ctx: &mut CommandContext,
+        parent: Option<&PathData>,
+        mut items: Vec<PathData>,
+    ) -> anyhow::Result<()> {
+        if parent.is_some() && !ctx.is_recursive() && !items.is_empty() && !self.path_only {
+            writeln!(ctx.out, "Found {} items", items.len())?;
+        }
+        items.sort_by(|a, b| self.compare(a, b));
+        if !self.path_only {
+            self.adjust_column_widths(&items);
+        }
+        command::process_paths(self, ctx, parent, items)
+    }
+
+    fn process_path(
+        &mut self,
+        ctx: &mut CommandContext,
+        item: &mut PathData,
+    ) -> anyhow::Result<()> {
+        let Some(stat) = &item.stat else {
+            return Ok(());
+        };
+        if self.path_only {
+            writeln!(ctx.out, "{}", item)?;
+            return Ok(());
+        }
+        let time = if self.use_atime {
+            stat.get_access_time()
+        } else {
+            stat.get_modification_time()
+        };
+        let date = Local
+            .timestamp_millis_opt(time)
+            .single()
+            .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_default();
+        let repl = if stat.is_file() {
+            stat.get_replication().to_string()
+        } else {
+            "-".to_owned()
+        };
+        writeln!(
+            ctx.out,
+            "{}{}{}{:>repl_width$} {:<owner_width$} {:<group_width$} {:>len_width$} {} {}",
+            if stat.is_directory() { "d" } else { "-" },
+            stat.get_permission(),
+            if stat.has_acl() { "+" } else { " " },
+            repl,
+            stat.get_owner(),
+            stat.get_group(),
+            self.format_size(stat.get_len()),
+            date,
+            item,
+            repl_width = self.max_repl,
+            owner_width = self.max_owner,
+            group_width = self.max_group,
+            len_width = self.max_len,
+        )?;
+        Ok(())
+    }
+}
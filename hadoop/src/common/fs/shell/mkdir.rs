@@ -0,0 +1,68 @@
+use super::{Command, CommandContext, CommandFormat, PathData};
+use crate::common::fs::PathIOException;
+
+/// Create the given dir
+#[derive(Default)]
+pub(crate) struct Mkdir {
+    create_parents: bool,
+}
+
+impl Mkdir {
+    pub const NAME: &'static str = "mkdir";
+}
+
+impl Command for Mkdir {
+    fn get_usage(&self) -> &'static str {
+        "[-p] <path> ..."
+    }
+
+    fn get_description(&self) -> &'static str {
+        "Create a directory in specified location.\n\
+         \x20 -p  Do not fail if the directory already exists"
+    }
+
+    fn process_options(
+        &mut self,
+        _ctx: &mut CommandContext,
+        args: &mut Vec<String>,
+    ) -> anyhow::Result<()> {
+        let mut cf = CommandFormat::new(1, usize::MAX, &["p"]);
+        cf.parse(args)?;
+        self.create_parents = cf.get_opt("p");
+        Ok(())
+    }
+
+    fn process_path(
+        &mut self,
+        _ctx: &mut CommandContext,
+        item: &mut PathData,
+    ) -> anyhow::Result<()> {
+        if item.stat.as_ref().is_some_and(|s| s.is_directory()) {
+            if !self.create_parents {
+                return Err(PathIOException::exists(&item.to_string()).into());
+            }
+            Ok(())
+        } else {
+            Err(PathIOException::is_not_directory(&item.to_string()).into())
+        }
+    }
+
+    fn process_nonexistent_path(
+        &mut self,
+        _ctx: &mut CommandContext,
+        item: PathData,
+    ) -> anyhow::Result<()> {
+        if !self.create_parents {
+            // check if parent exists
+            if let Some(parent) = item.path.get_parent() {
+                if !item.fs.exists(&parent)? {
+                    return Err(PathIOException::not_found(&parent.to_string()).into());
+                }
+            }
+        }
+        if !item.fs.mkdirs(&item.path, None)? {
+            return Err(PathIOException::new(&item.to_string()).into());
+        }
+        Ok(())
+    }
+}
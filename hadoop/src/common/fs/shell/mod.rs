@@ -0,0 +1,91 @@
+mod command;
+mod command_format;
+mod command_with_destination;
+mod copy_commands;
+mod count;
+mod delete;
+mod display;
+mod find;
+mod fs_shell_permissions;
+mod fs_usage;
+mod ls;
+mod mkdir;
+mod move_commands;
+mod path_data;
+mod set_replication;
+mod stat;
+mod tail;
+mod test;
+mod touch;
+
+pub(crate) use command::{run, Command, CommandContext};
+pub(crate) use command_format::{CommandFormat, IllegalArgumentException};
+pub(crate) use command_with_destination::CommandWithDestination;
+pub(crate) use path_data::PathData;
+
+use copy_commands::{AppendToFile, CopyCommand, CopyKind, Merge};
+use count::Count;
+use delete::Rm;
+use display::{Cat, Checksum, Text};
+use find::Find;
+use fs_shell_permissions::{Chmod, Chown};
+use fs_usage::{Df, Du};
+use ls::Ls;
+use mkdir::Mkdir;
+use move_commands::Rename;
+use set_replication::SetReplication;
+use stat::Stat;
+use tail::Tail;
+use test::Test;
+use touch::Touchz;
+
+/// Creates a new instance of a command.
+type CommandConstructor = fn() -> Box<dyn Command>;
+
+/// The commands of the shell, by name, sorted.
+static COMMANDS: &[(&str, CommandConstructor)] = &[
+    (AppendToFile::NAME, || Box::<AppendToFile>::default()),
+    (Cat::NAME, || Box::<Cat>::default()),
+    (Checksum::NAME, || Box::<Checksum>::default()),
+    (Chown::CHGRP_NAME, || Box::new(Chown::chgrp())),
+    (Chmod::NAME, || Box::<Chmod>::default()),
+    (Chown::NAME, || Box::<Chown>::default()),
+    ("copyFromLocal", || {
+        Box::new(CopyCommand::new(CopyKind::CopyFromLocal))
+    }),
+    ("copyToLocal", || {
+        Box::new(CopyCommand::new(CopyKind::CopyToLocal))
+    }),
+    (Count::NAME, || Box::<Count>::default()),
+    ("cp", || Box::new(CopyCommand::new(CopyKind::Cp))),
+    (Df::NAME, || Box::<Df>::default()),
+    (Du::NAME, || Box::<Du>::default()),
+    (Find::NAME, || Box::<Find>::default()),
+    ("get", || Box::new(CopyCommand::new(CopyKind::Get))),
+    (Merge::NAME, || Box::<Merge>::default()),
+    (Ls::NAME, || Box::<Ls>::default()),
+    (Mkdir::NAME, || Box::<Mkdir>::default()),
+    (Rename::NAME, || Box::<Rename>::default()),
+    ("put", || Box::new(CopyCommand::new(CopyKind::Put))),
+    (Rm::NAME, || Box::<Rm>::default()),
+    (SetReplication::NAME, || Box::<SetReplication>::default()),
+    (Stat::NAME, || Box::<Stat>::default()),
+    (Tail::NAME, || Box::<Tail>::default()),
+    (Test::NAME, || Box::<Test>::default()),
+    (Text::NAME, || Box::<Text>::default()),
+    (Touchz::NAME, || Box::<Touchz>::default()),
+];
+
+/// Returns the names of the registered commands, without the leading dash.
+pub(crate) fn get_names() -> impl Iterator<Item = &'static str> {
+    COMMANDS.iter().map(|(name, _)| *name)
+}
+
+/// Get an instance of the requested command, `None` if the name is not
+/// registered.
+pub(crate) fn get_instance(name: &str) -> Option<Box<dyn Command>> {
+    COMMANDS
+        .iter()
+        .find(|(n, _)| *n == name)
+        .map(|(_, new_command)| new_command())
+}
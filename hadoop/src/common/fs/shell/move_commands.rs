@@ -0,0 +1,84 @@
+use super::{command, Command, CommandContext, CommandFormat, CommandWithDestination, PathData};
+use crate::common::fs::PathIOException;
+
+/// Move/rename paths on the same fileystem
+#[derive(Default)]
+pub(crate) struct Rename {
+    dest: CommandWithDestination,
+}
+
+impl Rename {
+    pub const NAME: &'static str = "mv";
+}
+
+impl Command for Rename {
+    fn get_usage(&self) -> &'static str {
+        "[-f] <src> ... <dst>"
+    }
+
+    fn get_description(&self) -> &'static str {
+        "Move files that match the specified file pattern <src> to a destination <dst>.  \
+         When moving multiple files, the destination must be a directory.\n\
+         \x20 -f  Overwrites the destination if it already exists."
+    }
+
+    fn process_options(
+        &mut self,
+        ctx: &mut CommandContext,
+        args: &mut Vec<String>,
+    ) -> anyhow::Result<()> {
+        let mut cf = CommandFormat::new(2, usize::MAX, &["f"]);
+        cf.parse(args)?;
+        self.dest.set_overwrite(cf.get_opt("f"));
+        self.dest.get_remote_destination(ctx, args)
+    }
+
+    fn process_arguments(
+        &mut self,
+        ctx: &mut CommandContext,
+        args: Vec<PathData>,
+    ) -> anyhow::Result<()> {
+        self.dest.check_arguments(&args)?;
+        command::process_arguments(self, ctx, args)
+    }
+
+    fn process_path_argument(
+        &mut self,
+        ctx: &mut CommandContext,
+        item: PathData,
+    ) -> anyhow::Result<()> {
+        self.dest.check_path_argument(ctx, &item)?;
+        command::process_path_argument(self, ctx, item)
+    }
+
+    fn process_path(&mut self, ctx: &mut CommandContext, src: &mut PathData) -> anyhow::Result<()> {
+        let target = self.dest.get_target_path(ctx, src)?;
+        let src_uri = src.fs.get_uri();
+        let dst_uri = target.fs.get_uri();
+        if src_uri.scheme() != dst_uri.scheme()
+            || src_uri.authority().map(|a| a.host()) != dst_uri.authority().map(|a| a.host())
+        {
+            return Err(PathIOException::with_error(
+                &src.to_string(),
+                "Does not match target filesystem",
+            )
+            .into());
+        }
+        if target.exists() {
+            if !self.dest.is_overwrite() {
+                return Err(PathIOException::exists(&target.to_string()).into());
+            }
+            if !target.fs.delete(&target.path, false)? {
+                // too bad we don't know why it failed
+                return Err(PathIOException::new(&target.to_string())
+                    .set_operation("delete")
+                    .into());
+            }
+        }
+        if !target.fs.rename(&src.path, &target.path)? {
+            // we have no way to know the actual error...
+            return Err(PathIOException::new(&src.to_string()).into());
+        }
+        Ok(())
+    }
+}
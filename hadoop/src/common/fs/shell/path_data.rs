@@ -0,0 +1,302 @@
+use super::CommandContext;
+use crate::common::fs::{path, path_io_exception, FileStatus, FileSystem, Path, PathIOException};
+use std::{cmp::Ordering, fmt, rc::Rc};
+
+/// Encapsulates a Path (path), its FileStatus (stat), and its FileSystem (fs).
+/// PathData ensures that the returned path string will be the same as the
+/// one passed in during initialization (unlike Path objects which can
+/// modify the path string).
+/// The stat field will be `None` if the path does not exist.
+#[derive(Clone)]
+pub(crate) struct PathData {
+    pub fs: Rc<dyn FileSystem>,
+    pub path: Path,
+    pub stat: Option<FileStatus>,
+    string: String,
+}
+
+/// The type of the path pattern a glob was expanded from, so the expanded
+/// paths can be displayed the same way.
+enum PathType {
+    HasScheme,
+    SchemelessAbsolute,
+    Relative,
+}
+
+impl PathData {
+    /// Creates an object to wrap the given parameters as fields.  The string
+    /// used to create the path will be recorded since the Path object does not
+    /// return exactly the same string used to initialize it
+    pub fn new(ctx: &mut CommandContext, path_string: &str) -> anyhow::Result<Self> {
+        let fs = ctx.get_file_system(&Path::new(path_string)?)?;
+        Self::with_fs(fs, path_string)
+    }
+
+    /// Creates an object to wrap the given parameters as fields.  The string
+    /// used to create the path is resolved against the local filesystem.
+    pub fn new_local(ctx: &mut CommandContext, path_string: &str) -> anyhow::Result<Self> {
+        let fs = ctx.get_local_file_system()?;
+        Self::with_fs(fs, path_string)
+    }
+
+    /// Looks up the file status of the path in the given filesystem.
+    pub fn with_fs(fs: Rc<dyn FileSystem>, path_string: &str) -> anyhow::Result<Self> {
+        let path = fs.make_qualified(&Path::new(path_string)?)?;
+        let stat = lookup_stat(fs.as_ref(), &path)?;
+        Ok(Self::with_stat(fs, path_string, path, stat))
+    }
+
+    fn with_stat(
+        fs: Rc<dyn FileSystem>,
+        path_string: &str,
+        path: Path,
+        stat: Option<FileStatus>,
+    ) -> Self {
+        Self {
+            fs,
+            path,
+            stat,
+            string: path_string.to_owned(),
+        }
+    }
+
+    /// Whether the path exists.
+    pub fn exists(&self) -> bool {
+        self.stat.is_some()
+    }
+
+    /// Updates the paths's file status
+    pub fn refresh_status(&mut self) -> anyhow::Result<&FileStatus> {
+        self.stat = lookup_stat(self.fs.as_ref(), &self.path)?;
+        self.stat
+            .as_ref()
+            .ok_or_else(|| PathIOException::not_found(&self.string).into())
+    }
+
+    /// Returns a new PathData with the given extension.
+    pub fn suffix(&self, extension: &str) -> anyhow::Result<Self> {
+        Self::with_fs(self.fs.clone(), &format!("{}{}", self.string, extension))
+    }
+
+    /// Test if the parent directory exists
+    pub fn parent_exists(&self) -> anyhow::Result<bool> {
+        Ok(self.represents_directory()
+            || match self.path.get_parent() {
+                Some(parent) => self.fs.exists(&parent)?,
+                None => true,
+            })
+    }
+
+    /// Check if the path represents a directory as determined by the basename
+    /// being "." or "..", or the path ending with a directory separator
+    pub fn represents_directory(&self) -> bool {
+        let uri_path = self.path.get_uri_path();
+        let name = uri_path
+            .rsplit(path::SEPARATOR_CHAR)
+            .next()
+            .unwrap_or_default();
+        // Path will munch off the chars that indicate a dir, so there's no way
+        // to perform this test except to look at raw uri.  Empty name or "."
+        // or ".." must be a directory.
+        self.string.ends_with(path::SEPARATOR_CHAR)
+            || name.is_empty()
+            || name == "."
+            || name == ".."
+    }
+
+    /// Returns a list of PathData objects of the items contained in the given
+    /// directory.
+    pub fn get_directory_contents(&self) -> anyhow::Result<Vec<Self>> {
+        if !self.stat.as_ref().is_some_and(|s| s.is_directory()) {
+            return Err(PathIOException::is_not_directory(&self.string).into());
+        }
+        let mut items = self
+            .fs
+            .list_status(&self.path)?
+            .into_iter()
+            .map(|stat| {
+                let child = self.get_string_for_child_path(stat.get_path());
+                let path = stat.get_path().clone();
+                Self::with_stat(self.fs.clone(), &child, path, Some(stat))
+            })
+            .collect::<Vec<_>>();
+        items.sort();
+        Ok(items)
+    }
+
+    /// Creates a new object for a child entry in this directory
+    pub fn get_path_data_for_child(&self, child: &Self) -> anyhow::Result<Self> {
+        if !self.stat.as_ref().is_some_and(|s| s.is_directory()) {
+            return Err(PathIOException::is_not_directory(&self.string).into());
+        }
+        Self::with_fs(
+            self.fs.clone(),
+            &self.get_string_for_child_path(&child.path),
+        )
+    }
+
+    /// Given a child of this directory, use the directory's path and the
+    /// child's basename to construct the string to the child.  This preserves
+    /// relative paths since Path will fully qualify.
+    fn get_string_for_child_path(&self, child_path: &Path) -> String {
+        let basename = child_path.get_name();
+        if self.string == path::CUR_DIR {
+            return basename;
+        }
+        // check getPath() so scheme slashes aren't considered part of the path
+        let separator = if self.path.get_uri_path().ends_with(path::SEPARATOR_CHAR) {
+            ""
+        } else {
+            path::SEPARATOR
+        };
+        format!(
+            "{}{}{}",
+            self.string.trim_end_matches(path::SEPARATOR_CHAR),
+            separator,
+            basename
+        )
+    }
+
+    /// Expand the given path as a glob pattern.  Non-existent paths do not
+    /// throw an exception because creation commands like touch and mkdir need
+    /// to create them.  The "stat" field will be `None` if the path does not
+    /// exist.
+    pub fn expand_as_glob(ctx: &mut CommandContext, pattern: &str) -> anyhow::Result<Vec<Self>> {
+        let glob_path = Path::new(pattern)?;
+        let fs = ctx.get_file_system(&glob_path)?;
+        let stats = fs.glob_status(&glob_path)?;
+        let Some(stats) = stats else {
+            // not a glob & file not found, so add the path with a null stat
+            let unquoted = unquote(pattern);
+            let path = fs.make_qualified(&Path::new(&unquoted)?)?;
+            return Ok(vec![Self::with_stat(fs, &unquoted, path, None)]);
+        };
+
+        // figure out what type of glob path was given, will convert globbed
+        // paths to match the type to preserve relativity
+        let path_type = if glob_path.get_scheme().is_some() {
+            PathType::HasScheme
+        } else if glob_path.is_uri_path_absolute() {
+            PathType::SchemelessAbsolute
+        } else {
+            PathType::Relative
+        };
+
+        let mut items = stats
+            .into_iter()
+            .map(|stat| {
+                let match_path = stat.get_path().clone();
+                let glob_match = match path_type {
+                    PathType::HasScheme => {
+                        // use as-is, but remove authority if necessary
+                        if glob_path.get_authority().is_none() {
+                            Path::from_components(
+                                match_path.get_scheme(),
+                                None,
+                                &match_path.get_uri_path(),
+                            )?
+                            .to_string()
+                        } else {
+                            match_path.to_string()
+                        }
+                    }
+                    PathType::SchemelessAbsolute => match_path.get_uri_path(),
+                    PathType::Relative => relativize(
+                        &fs.get_working_directory().get_uri_path(),
+                        &match_path.get_uri_path(),
+                    ),
+                };
+                Ok(Self::with_stat(
+                    fs.clone(),
+                    &glob_match,
+                    match_path,
+                    Some(stat),
+                ))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        items.sort();
+        Ok(items)
+    }
+}
+
+impl fmt::Display for PathData {
+    /// Returns the printable version of the path that is either the path
+    /// as given on the command line, or the full path
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.string)
+    }
+}
+
+impl PartialEq for PathData {
+    fn eq(&self, other: &Self) -> bool {
+        self.path == other.path
+    }
+}
+
+impl Eq for PathData {}
+
+impl PartialOrd for PathData {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for PathData {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.path.to_string().cmp(&other.path.to_string())
+    }
+}
+
+/// Get the file status of a path, `None` if it doesn't exist.
+fn lookup_stat(fs: &dyn FileSystem, path: &Path) -> anyhow::Result<Option<FileStatus>> {
+    match fs.get_file_status(path) {
+        Ok(stat) => Ok(Some(stat)),
+        Err(e) if path_io_exception::is_file_not_found(&e) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Remove the backslashes quoting the glob characters of a pattern.
+fn unquote(pattern: &str) -> String {
+    let mut unquoted = String::with_capacity(pattern.len());
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => unquoted.extend(chars.next()),
+            c => unquoted.push(c),
+        }
+    }
+    unquoted
+}
+
+/// Returns the path `src_path` relative to the directory `cwd_path`.
+fn relativize(cwd_path: &str, src_path: &str) -> String {
+    let cwd: Vec<&str> = cwd_path.split('/').filter(|c| !c.is_empty()).collect();
+    let src: Vec<&str> = src_path.split('/').filter(|c| !c.is_empty()).collect();
+    let common = cwd
+        .iter()
+        .zip(src.iter())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let mut relative = vec![".."; cwd.len() - common];
+    relative.extend_from_slice(&src[common..]);
+    if relative.is_empty() {
+        path::CUR_DIR.to_owned()
+    } else {
+        relative.join(path::SEPARATOR)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_relativize() {
+        assert_eq!(relativize("/user/me", "/user/me/a/b"), "a/b");
+        assert_eq!(relativize("/user/me", "/user/other"), "../other");
+        assert_eq!(relativize("/user/me", "/user/me"), ".");
+        assert_eq!(relativize("/", "/tmp"), "tmp");
+        assert_eq!(unquote("a\\*b\\\\"), "a*b\\");
+    }
+}
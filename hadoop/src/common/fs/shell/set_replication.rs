@@ -0,0 +1,88 @@
+use super::{
+    command_format::IllegalArgumentException, Command, CommandContext, CommandFormat, PathData,
+};
+use crate::common::fs::PathIOException;
+use anyhow::Error;
+
+/// Modifies the replication factor
+#[derive(Default)]
+pub(crate) struct SetReplication {
+    new_rep: i16,
+}
+
+impl SetReplication {
+    pub const NAME: &'static str = "setrep";
+}
+
+impl Command for SetReplication {
+    fn get_usage(&self) -> &'static str {
+        "[-R] <rep> <path> ..."
+    }
+
+    fn get_description(&self) -> &'static str {
+        "Set the replication level of a file. If <path> is a directory then the command \
+         recursively changes the replication factor of all files under the directory tree \
+         rooted at <path>. The EC files will be ignored here.\n\
+         -R  It is accepted for backwards compatibility. It has no effect."
+    }
+
+    fn process_options(
+        &mut self,
+        ctx: &mut CommandContext,
+        args: &mut Vec<String>,
+    ) -> anyhow::Result<()> {
+        let mut cf = CommandFormat::new(2, usize::MAX, &["R"]);
+        cf.parse(args)?;
+        ctx.set_recursive(true);
+
+        let rep = args.remove(0);
+        self.new_rep = match rep.parse() {
+            Ok(new_rep) => new_rep,
+            Err(_) => {
+                ctx.display_warning("Illegal replication, a positive integer expected");
+                return Err(
+                    IllegalArgumentException(format!("For input string: \"{}\"", rep)).into(),
+                );
+            }
+        };
+        if self.new_rep < 1 {
+            return Err(IllegalArgumentException("replication must be >= 1".to_owned()).into());
+        }
+        Ok(())
+    }
+
+    fn process_path(
+        &mut self,
+        ctx: &mut CommandContext,
+        item: &mut PathData,
+    ) -> anyhow::Result<()> {
+        let Some(stat) = &item.stat else {
+            return Ok(());
+        };
+        if stat.is_symlink() {
+            return Err(
+                PathIOException::with_error(&item.to_string(), "Symlinks unsupported").into(),
+            );
+        }
+        if stat.is_file() {
+            // Do the checking if the file is erasure coded since
+            // replication factor for an EC file is meaningless.
+            if stat.is_erasure_coded() {
+                writeln!(
+                    ctx.out,
+                    "Did not set replication for: {}, because it's an erasure coded file.",
+                    item
+                )?;
+            } else {
+                if !item.fs.set_replication(&item.path, self.new_rep)? {
+                    return Err(Error::msg(format!(
+                        "Could not set replication for: {}",
+                        item
+                    )));
+                }
+                writeln!(ctx.out, "Replication {} set: {}", self.new_rep, item)?;
+            }
+        }
+        Ok(())
+    }
+}
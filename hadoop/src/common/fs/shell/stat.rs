@@ -0,0 +1,125 @@
+use super::{Command, CommandContext, CommandFormat, PathData};
+use chrono::{TimeZone, Utc};
+
+/// Print statistics about path in specified format.
+/// Format sequences:
+///   %a: Permissions in octal
+///   %A: Permissions in symbolic style
+///   %b: Size of file in bytes
+///   %F: Type
+///   %g: Group name of owner
+///   %n: Filename
+///   %o: Block size
+///   %r: replication
+///   %u: User name of owner
+///   %x: atime UTC date as "yyyy-MM-dd HH:mm:ss"
+///   %X: atime Milliseconds since January 1, 1970 UTC
+///   %y: mtime UTC date as "yyyy-MM-dd HH:mm:ss"
+///   %Y: mtime Milliseconds since January 1, 1970 UTC
+/// If the format is not specified, %y is used by default.
+pub(crate) struct Stat {
+    format: String,
+}
+
+impl Default for Stat {
+    fn default() -> Self {
+        Self {
+            format: "%y".to_owned(),
+        }
+    }
+}
+
+impl Stat {
+    pub const NAME: &'static str = "stat";
+}
+
+impl Command for Stat {
+    fn get_usage(&self) -> &'static str {
+        "[format] <path> ..."
+    }
+
+    fn get_description(&self) -> &'static str {
+        "Print statistics about the file/directory at <path>\n\
+         in the specified format. Format accepts permissions in\n\
+         octal (%a) and symbolic (%A), filesize in\n\
+         bytes (%b), type (%F), group name of owner (%g),\n\
+         name (%n), block size (%o), replication (%r), user name\n\
+         of owner (%u), access date (%x, %X).\n\
+         modification date (%y, %Y).\n\
+         %x and %y show UTC date as \"yyyy-MM-dd HH:mm:ss\" and\n\
+         %X and %Y show milliseconds since January 1, 1970 UTC.\n\
+         If the format is not specified, %y is used by default.\n"
+    }
+
+    fn process_options(
+        &mut self,
+        ctx: &mut CommandContext,
+        args: &mut Vec<String>,
+    ) -> anyhow::Result<()> {
+        let mut cf = CommandFormat::new(1, usize::MAX, &["R"]);
+        cf.parse(args)?;
+        ctx.set_recursive(cf.get_opt("R"));
+        if args[0].contains('%') {
+            self.format = args.remove(0);
+        }
+        // make sure there's still at least one arg
+        cf.parse(args)?;
+        Ok(())
+    }
+
+    fn process_path(
+        &mut self,
+        ctx: &mut CommandContext,
+        item: &mut PathData,
+    ) -> anyhow::Result<()> {
+        let Some(stat) = &item.stat else {
+            return Ok(());
+        };
+        let mut buf = String::new();
+        let mut fmt = self.format.chars();
+        while let Some(c) = fmt.next() {
+            if c != '%' {
+                buf.push(c);
+                continue;
+            }
+            // this silently drops a trailing %?
+            let Some(c) = fmt.next() else {
+                break;
+            };
+            match c {
+                'a' => buf.push_str(&format!("{:o}", stat.get_permission().to_short() & 0o1777)),
+                'A' => buf.push_str(&stat.get_permission().to_string()),
+                'b' => buf.push_str(&stat.get_len().to_string()),
+                'F' => buf.push_str(if stat.is_directory() {
+                    "directory"
+                } else if stat.is_file() {
+                    "regular file"
+                } else {
+                    "symlink"
+                }),
+                'g' => buf.push_str(stat.get_group()),
+                'n' => buf.push_str(&item.path.get_name()),
+                'o' => buf.push_str(&stat.get_block_size().to_string()),
+                'r' => buf.push_str(&stat.get_replication().to_string()),
+                'u' => buf.push_str(stat.get_owner()),
+                'x' => buf.push_str(&format_time(stat.get_access_time())),
+                'X' => buf.push_str(&stat.get_access_time().to_string()),
+                'y' => buf.push_str(&format_time(stat.get_modification_time())),
+                'Y' => buf.push_str(&stat.get_modification_time().to_string()),
+                // this leaves %<unknown> alone, which causes the potential for
+                // future format options to break strings; should use %% to
+                // escape percents
+                c => buf.push(c),
+            }
+        }
+        writeln!(ctx.out, "{}", buf)?;
+        Ok(())
+    }
+}
+
+fn format_time(millis: i64) -> String {
+    Utc.timestamp_millis_opt(millis)
+        .single()
+        .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_default()
+}
@@ -0,0 +1,106 @@
+use super::{command, Command, CommandContext, CommandFormat, PathData};
+use crate::common::fs::PathIOException;
+use std::{
+    io::{self, Seek, SeekFrom},
+    thread,
+    time::Duration,
+};
+
+/// Show the last 1KB of a file, and optionally follow the data appended to
+/// it.
+pub(crate) struct Tail {
+    starting_offset: i64,
+    follow: bool,
+    follow_delay: Duration,
+}
+
+impl Default for Tail {
+    fn default() -> Self {
+        Self {
+            starting_offset: -1024,
+            follow: false,
+            // milliseconds
+            follow_delay: Duration::from_millis(5000),
+        }
+    }
+}
+
+impl Tail {
+    pub const NAME: &'static str = "tail";
+
+    fn dump_from_offset(
+        &self,
+        ctx: &mut CommandContext,
+        item: &mut PathData,
+        mut offset: i64,
+    ) -> anyhow::Result<i64> {
+        let file_size = item.refresh_status()?.get_len();
+        if offset > file_size {
+            return Ok(file_size);
+        }
+        // treat a negative offset as relative to end of the file, floor of 0
+        if offset < 0 {
+            offset = (file_size + offset).max(0);
+        }
+
+        let mut input = item.fs.open(&item.path)?;
+        input.seek(SeekFrom::Start(offset as u64))?;
+        io::copy(&mut input, ctx.out)?;
+        ctx.out.flush()?;
+        Ok(input.get_pos()? as i64)
+    }
+}
+
+impl Command for Tail {
+    fn get_usage(&self) -> &'static str {
+        "[-f] <file>"
+    }
+
+    fn get_description(&self) -> &'static str {
+        "Show the last 1KB of the file.\n\
+         -f  Shows appended data as the file grows."
+    }
+
+    fn process_options(
+        &mut self,
+        _ctx: &mut CommandContext,
+        args: &mut Vec<String>,
+    ) -> anyhow::Result<()> {
+        let mut cf = CommandFormat::new(1, 1, &["f"]);
+        cf.parse(args)?;
+        self.follow = cf.get_opt("f");
+        Ok(())
+    }
+
+    // TODO: HADOOP-7234 will add glob support; for now, be backwards compat
+    fn expand_argument(
+        &mut self,
+        ctx: &mut CommandContext,
+        arg: &str,
+    ) -> anyhow::Result<Vec<PathData>> {
+        let items = command::expand_argument(ctx, arg)?;
+        if items.len() > 1 {
+            return Err(PathIOException::with_error(arg, "Too many items").into());
+        }
+        Ok(items)
+    }
+
+    fn process_path(
+        &mut self,
+        ctx: &mut CommandContext,
+        item: &mut PathData,
+    ) -> anyhow::Result<()> {
+        if item.stat.as_ref().is_some_and(|s| s.is_directory()) {
+            return Err(PathIOException::is_directory(&item.to_string()).into());
+        }
+
+        let mut offset = self.dump_from_offset(ctx, item, self.starting_offset)?;
+        if self.follow {
+            loop {
+                thread::sleep(self.follow_delay);
+                offset = self.dump_from_offset(ctx, item, offset)?;
+            }
+        }
+        Ok(())
+    }
+}
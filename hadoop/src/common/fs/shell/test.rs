@@ -0,0 +1,83 @@
+use super::{
+    command_format::IllegalArgumentException, Command, CommandContext, CommandFormat, PathData,
+};
+use crate::common::fs::permission::FsAction;
+
+/// Perform shell-like file tests
+#[derive(Default)]
+pub(crate) struct Test {
+    flag: char,
+}
+
+impl Test {
+    pub const NAME: &'static str = "test";
+}
+
+impl Command for Test {
+    fn get_usage(&self) -> &'static str {
+        "-[defswrz] <path>"
+    }
+
+    fn get_description(&self) -> &'static str {
+        "Answer various questions about <path>, with result via exit status.\n\
+         \x20 -d  return 0 if <path> is a directory.\n\
+         \x20 -e  return 0 if <path> exists.\n\
+         \x20 -f  return 0 if <path> is a file.\n\
+         \x20 -s  return 0 if file <path> is greater than zero bytes in size.\n\
+         \x20 -w  return 0 if file <path> exists and write permission is granted.\n\
+         \x20 -r  return 0 if file <path> exists and read permission is granted.\n\
+         \x20 -z  return 0 if file <path> is zero bytes in size, else return 1."
+    }
+
+    fn process_options(
+        &mut self,
+        _ctx: &mut CommandContext,
+        args: &mut Vec<String>,
+    ) -> anyhow::Result<()> {
+        let mut cf = CommandFormat::new(1, 1, &["e", "d", "f", "s", "z", "w", "r"]);
+        cf.parse(args)?;
+        let opts = cf.get_opts();
+        let mut opts = opts.iter();
+        match (opts.next(), opts.next()) {
+            (None, _) => Err(IllegalArgumentException("No test flag given".to_owned()).into()),
+            (Some(opt), None) => {
+                self.flag = opt.chars().next().unwrap_or_default();
+                Ok(())
+            }
+            _ => Err(IllegalArgumentException("Only one test flag is allowed".to_owned()).into()),
+        }
+    }
+
+    fn process_path(
+        &mut self,
+        ctx: &mut CommandContext,
+        item: &mut PathData,
+    ) -> anyhow::Result<()> {
+        let Some(stat) = &item.stat else {
+            return Ok(());
+        };
+        let test = match self.flag {
+            'e' => true,
+            'd' => stat.is_directory(),
+            'f' => stat.is_file(),
+            's' => stat.get_len() > 0,
+            'z' => stat.get_len() == 0,
+            'w' => item.fs.access(&item.path, FsAction::Write).is_ok(),
+            'r' => item.fs.access(&item.path, FsAction::Read).is_ok(),
+            _ => false,
+        };
+        if !test {
+            ctx.exit_code = 1;
+        }
+        Ok(())
+    }
+
+    fn process_nonexistent_path(
+        &mut self,
+        ctx: &mut CommandContext,
+        _item: PathData,
+    ) -> anyhow::Result<()> {
+        ctx.exit_code = 1;
+        Ok(())
+    }
+}
@@ -0,0 +1,70 @@
+use super::{Command, CommandContext, CommandFormat, PathData};
+use crate::common::fs::PathIOException;
+
+/// (Re)create zero-length file at the specified path.
+/// This will be replaced by a more UNIX-like touch when files may be
+/// modified.
+#[derive(Default)]
+pub(crate) struct Touchz;
+
+impl Touchz {
+    pub const NAME: &'static str = "touchz";
+
+    fn touchz(&self, item: &PathData) -> anyhow::Result<()> {
+        item.fs
+            .create(&item.path, None, true, None, None)?
+            .close()?;
+        Ok(())
+    }
+}
+
+impl Command for Touchz {
+    fn get_usage(&self) -> &'static str {
+        "<path> ..."
+    }
+
+    fn get_description(&self) -> &'static str {
+        "Creates a file of zero length at <path> with current time as the timestamp of that \
+         <path>. An error is returned if the file exists with non-zero length\n"
+    }
+
+    fn process_options(
+        &mut self,
+        _ctx: &mut CommandContext,
+        args: &mut Vec<String>,
+    ) -> anyhow::Result<()> {
+        CommandFormat::new(1, usize::MAX, &[]).parse(args)?;
+        Ok(())
+    }
+
+    fn process_path(
+        &mut self,
+        _ctx: &mut CommandContext,
+        item: &mut PathData,
+    ) -> anyhow::Result<()> {
+        let Some(stat) = &item.stat else {
+            return Ok(());
+        };
+        if stat.is_directory() {
+            // TODO: handle this
+            return Err(PathIOException::is_directory(&item.to_string()).into());
+        }
+        if stat.get_len() != 0 {
+            return Err(
+                PathIOException::with_error(&item.to_string(), "Not a zero-length file").into(),
+            );
+        }
+        self.touchz(item)
+    }
+
+    fn process_nonexistent_path(
+        &mut self,
+        _ctx: &mut CommandContext,
+        item: PathData,
+    ) -> anyhow::Result<()> {
+        if !item.parent_exists()? {
+            return Err(PathIOException::not_found(&item.to_string()).into());
+        }
+        self.touchz(&item)
+    }
+}
@@ -0,0 +1,69 @@
+use crate::hdfs::StorageTypeProto;
+use std::fmt;
+
+/// Defines the types of supported storage media. The default storage
+/// medium is assumed to be DISK.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum StorageType {
+    RamDisk,
+    Ssd,
+    #[default]
+    Disk,
+    Archive,
+    Provided,
+    Nvdimm,
+}
+
+impl StorageType {
+    pub const DEFAULT: StorageType = StorageType::Disk;
+
+    /// Is this storage type volatile, i.e. its data does not survive a restart?
+    pub fn is_transient(&self) -> bool {
+        *self == Self::RamDisk
+    }
+
+    /// Is this storage type backed by memory?
+    pub fn is_ram(&self) -> bool {
+        matches!(self, Self::RamDisk | Self::Nvdimm)
+    }
+}
+
+impl fmt::Display for StorageType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::RamDisk => "RAM_DISK",
+            Self::Ssd => "SSD",
+            Self::Disk => "DISK",
+            Self::Archive => "ARCHIVE",
+            Self::Provided => "PROVIDED",
+            Self::Nvdimm => "NVDIMM",
+        };
+        f.write_str(name)
+    }
+}
+
+impl From<StorageTypeProto> for StorageType {
+    fn from(proto: StorageTypeProto) -> Self {
+        match proto {
+            StorageTypeProto::Disk => Self::Disk,
+            StorageTypeProto::Ssd => Self::Ssd,
+            StorageTypeProto::Archive => Self::Archive,
+            StorageTypeProto::RamDisk => Self::RamDisk,
+            StorageTypeProto::Provided => Self::Provided,
+            StorageTypeProto::Nvdimm => Self::Nvdimm,
+        }
+    }
+}
+
+impl From<StorageType> for StorageTypeProto {
+    fn from(storage_type: StorageType) -> Self {
+        match storage_type {
+            StorageType::Disk => Self::Disk,
+            StorageType::Ssd => Self::Ssd,
+            StorageType::Archive => Self::Archive,
+            StorageType::RamDisk => Self::RamDisk,
+            StorageType::Provided => Self::Provided,
+            StorageType::Nvdimm => Self::Nvdimm,
+        }
+    }
+}
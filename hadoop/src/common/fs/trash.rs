@@ -0,0 +1,154 @@
+use super::{
+    common_configuration_keys_public::{FS_TRASH_INTERVAL_DEFAULT, FS_TRASH_INTERVAL_KEY},
+    options::Rename,
+    path_io_exception,
+    permission::FsPermission,
+    FileSystem, Path,
+};
+use crate::common::conf::Configuration;
+use anyhow::Error;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The directory of the current checkpoint within the trash root.
+const CURRENT: &str = "Current";
+/// Permission of the trash directories.
+const PERMISSION: i16 = 0o700;
+const MSECS_PER_MINUTE: f32 = 60.0 * 1000.0;
+
+/// Provides a trash facility which supports pluggable Trash policies.
+///
+/// See the implementation of the configured TrashPolicy for more
+/// details.
+pub struct Trash<'a, F: FileSystem + ?Sized> {
+    fs: &'a F,
+    deletion_interval: i64,
+}
+
+impl<'a, F: FileSystem + ?Sized> Trash<'a, F> {
+    /// Construct a trash can accessor for the FileSystem provided.
+    pub fn new(fs: &'a F, conf: &Configuration) -> anyhow::Result<Self> {
+        let interval = conf.get_float(FS_TRASH_INTERVAL_KEY, FS_TRASH_INTERVAL_DEFAULT)?;
+        Ok(Self {
+            fs,
+            deletion_interval: (interval * MSECS_PER_MINUTE) as i64,
+        })
+    }
+
+    /// In case of the symlinks or mount points, one has to move the appropriate
+    /// trashbin in the actual volume of the path p being deleted.
+    ///
+    /// Hence we get the file system of the fully-qualified resolved-path and
+    /// then move the path p to the trashbin in that volume,
+    pub fn move_to_appropriate_trash(
+        fs: &'a F,
+        p: &Path,
+        conf: &Configuration,
+    ) -> anyhow::Result<bool> {
+        Self::new(fs, conf)?.move_to_trash(p)
+    }
+
+    /// Returns whether the trash is enabled for this filesystem
+    pub fn is_enabled(&self) -> bool {
+        self.deletion_interval > 0
+    }
+
+    /// Move a file or directory to the current trash directory.
+    ///
+    /// Returns false if the item is already in the trash or trash is disabled
+    pub fn move_to_trash(&self, path: &Path) -> anyhow::Result<bool> {
+        if !self.is_enabled() {
+            return Ok(false);
+        }
+
+        let path = self.fs.fix_relative_part(path)?;
+        // check that path exists
+        self.fs.get_file_status(&path)?;
+        let qpath = self.fs.make_qualified(&path)?.to_string();
+
+        let trash_root = self.fs.get_trash_root(&path)?;
+        let trash_current = Path::from_parent_str(&trash_root, CURRENT)?;
+        if qpath.starts_with(&trash_root.to_string()) {
+            // already in trash
+            return Ok(false);
+        }
+
+        if let Some(trash_root_parent) = trash_root.get_parent() {
+            if trash_root_parent.to_string().starts_with(&qpath) {
+                return Err(Error::msg(format!(
+                    "Cannot move \"{}\" to the trash, as it contains the trash",
+                    path
+                )));
+            }
+        }
+
+        let mut trash_path = make_trash_relative_path(&trash_current, &path)?;
+        let base_trash_path = match path.get_parent() {
+            Some(parent) => make_trash_relative_path(&trash_current, &parent)?,
+            None => trash_current,
+        };
+
+        let mut cause = None;
+        // try twice, in case checkpoint between the mkdirs() & rename()
+        for _ in 0..2 {
+            match self
+                .fs
+                .mkdirs(&base_trash_path, Some(&FsPermission::from(PERMISSION)))
+            {
+                Ok(true) => {}
+                Ok(false) => {
+                    log::warn!("Can't create(mkdir) trash directory: {}", base_trash_path);
+                    return Ok(false);
+                }
+                Err(e) => {
+                    log::warn!("Can't create trash directory: {}: {}", base_trash_path, e);
+                    cause = Some(e);
+                    break;
+                }
+            }
+
+            // if the target path in Trash already exists, then append with
+            // a current time in millisecs.
+            let orig = trash_path.clone();
+            loop {
+                match self.fs.exists(&trash_path) {
+                    Ok(false) => break,
+                    Ok(true) => trash_path = orig.suffix(&now().to_string())?,
+                    Err(e) if path_io_exception::is_file_not_found(&e) => break,
+                    Err(e) => return Err(e),
+                }
+            }
+
+            // move to current trash
+            match self.fs.rename2(&path, &trash_path, &[Rename::ToTrash]) {
+                Ok(()) => {
+                    log::info!("Moved: '{}' to trash at: {}", path, trash_path);
+                    return Ok(true);
+                }
+                Err(e) => cause = Some(e),
+            }
+        }
+        Err(Error::msg(match cause {
+            Some(cause) => format!("Failed to move to trash: {}: {}", path, cause),
+            None => format!("Failed to move to trash: {}", path),
+        }))
+    }
+}
+
+fn make_trash_relative_path(base_path: &Path, rm_file_path: &Path) -> anyhow::Result<Path> {
+    Path::from_components(
+        base_path.get_scheme(),
+        base_path.get_authority(),
+        &format!(
+            "{}{}",
+            base_path.get_uri_path(),
+            rm_file_path.get_uri_path()
+        ),
+    )
+}
+
+fn now() -> u128 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or_default()
+}
@@ -0,0 +1,141 @@
+use std::fmt;
+
+macro_rules! io_exceptions {
+    ($($(#[$meta:meta])* $variant:ident => $class_name:literal,)*) => {
+        /// Signals that an I/O exception of some sort has occurred.
+        ///
+        /// Each variant stands for a Java exception class a server or the
+        /// client raises, so that callers can tell the failures apart. The
+        /// variants carry the detail message of the exception.
+        #[derive(Clone, Debug, PartialEq, Eq)]
+        pub enum IOException {
+            $($(#[$meta])* $variant(String),)*
+        }
+
+        impl IOException {
+            /// Map a Java exception class name onto its variant, if it is one
+            /// of the known exceptions.
+            pub fn from_class_name(class_name: &str, message: &str) -> Option<Self> {
+                match class_name {
+                    $($class_name => Some(Self::$variant(message.to_owned())),)*
+                    _ => None,
+                }
+            }
+
+            /// The name of the Java exception class this variant stands for.
+            pub fn get_class_name(&self) -> &'static str {
+                match self {
+                    $(Self::$variant(_) => $class_name,)*
+                }
+            }
+
+            /// Returns the detail message of this exception.
+            pub fn get_message(&self) -> &str {
+                match self {
+                    $(Self::$variant(message) => message,)*
+                }
+            }
+        }
+    };
+}
+
+io_exceptions! {
+    /// Signals that an attempt to open the file denoted by a specified
+    /// pathname has failed.
+    FileNotFound => "java.io.FileNotFoundException",
+    /// Indicates that the parent of specified Path is not a directory
+    /// as expected.
+    ParentNotDirectory => "org.apache.hadoop.fs.ParentNotDirectoryException",
+    /// Used when target file already exists for any operation and is not
+    /// configured to be overwritten.
+    FileAlreadyExists => "org.apache.hadoop.fs.FileAlreadyExistsException",
+    /// Generated by rm commands when a non-empty directory is deleted
+    /// without recursion.
+    PathIsNotEmptyDirectory => "org.apache.hadoop.fs.PathIsNotEmptyDirectoryException",
+    /// Path string is invalid either because it has invalid characters or due
+    /// to other file system specific reasons.
+    InvalidPath => "org.apache.hadoop.fs.InvalidPathException",
+    /// Thrown to indicate that the requested operation is not supported.
+    UnsupportedOperation => "java.lang.UnsupportedOperationException",
+    /// An exception class for access control related issues.
+    AccessControl => "org.apache.hadoop.security.AccessControlException",
+    /// Snapshot access related exception.
+    SnapshotAccessControl => "org.apache.hadoop.hdfs.protocol.SnapshotAccessControlException",
+    /// Thrown when the name node is in safe mode.
+    /// Client cannot modified namespace until the safe mode is off.
+    SafeMode => "org.apache.hadoop.hdfs.server.namenode.SafeModeException",
+    /// Thrown by a server typically to indicate that server is in a state
+    /// where request cannot be processed temporarily (such as still starting
+    /// up). Client may retry the request. If the service is up, the server
+    /// may be able to process a retried request.
+    Retriable => "org.apache.hadoop.ipc.RetriableException",
+    /// Thrown by a server that is in a standby state, to tell the client to
+    /// try another server.
+    Standby => "org.apache.hadoop.ipc.StandbyException",
+    /// Signals that a timeout has occurred on a socket read or accept.
+    SocketTimeout => "java.net.SocketTimeoutException",
+    /// Thrown by NetUtils.connect if it times out while connecting to the
+    /// remote host.
+    ConnectTimeout => "org.apache.hadoop.net.ConnectTimeoutException",
+    /// Signals that an AsyncCallLimitExceededException has occurred. This
+    /// exception is used to limit the maximum number of concurrent
+    /// asynchronous calls.
+    AsyncCallLimitExceeded => "org.apache.hadoop.ipc.AsyncCallLimitExceededException",
+    /// This exception is thrown when modification to HDFS results in violation
+    /// of a directory quota.
+    QuotaExceeded => "org.apache.hadoop.hdfs.protocol.QuotaExceededException",
+    /// The namespace quota of a directory is exceeded.
+    NSQuotaExceeded => "org.apache.hadoop.hdfs.protocol.NSQuotaExceededException",
+    /// The diskspace quota of a directory is exceeded.
+    DSQuotaExceeded => "org.apache.hadoop.hdfs.protocol.DSQuotaExceededException",
+    /// The quota of a storage type of a directory is exceeded.
+    QuotaByStorageTypeExceeded =>
+        "org.apache.hadoop.hdfs.protocol.QuotaByStorageTypeExceededException",
+    /// Thrown when a symbolic link is encountered in a path.
+    UnresolvedPath => "org.apache.hadoop.hdfs.protocol.UnresolvedPathException",
+    /// The lease that was being used to create a file has expired.
+    LeaseExpired => "org.apache.hadoop.hdfs.server.namenode.LeaseExpiredException",
+    /// The exception that happens when you ask to create a file that already
+    /// is being created, but is not closed yet.
+    AlreadyBeingCreated => "org.apache.hadoop.hdfs.protocol.AlreadyBeingCreatedException",
+    /// The file has not finished being written to enough datanodes yet.
+    NotReplicatedYet => "org.apache.hadoop.hdfs.server.namenode.NotReplicatedYetException",
+    /// Exception indicating that a replica is already being recovery.
+    RecoveryInProgress => "org.apache.hadoop.hdfs.protocol.RecoveryInProgressException",
+    /// This exception is thrown when a read encounters a block that has no
+    /// locations associated with it.
+    BlockMissing => "org.apache.hadoop.hdfs.BlockMissingException",
+    /// This exception is thrown when the length of a LocatedBlock instance
+    /// can not be obtained.
+    CannotObtainBlockLength => "org.apache.hadoop.hdfs.CannotObtainBlockLengthException",
+    /// Exception indicating that DataNode does not have a replica
+    /// that matches the target block.
+    ReplicaNotFound => "org.apache.hadoop.hdfs.server.datanode.ReplicaNotFoundException",
+    /// Thrown for checksum errors.
+    Checksum => "org.apache.hadoop.fs.ChecksumException",
+    /// This class represents an error that has occurred when using SASL.
+    Sasl => "javax.security.sasl.SaslException",
+    /// Access token verification failed.
+    InvalidBlockToken => "org.apache.hadoop.security.token.block.InvalidBlockTokenException",
+}
+
+impl IOException {
+    /// Whether this exception reports a violated quota of any kind.
+    pub fn is_quota_exceeded(&self) -> bool {
+        matches!(
+            self,
+            Self::QuotaExceeded(_)
+                | Self::NSQuotaExceeded(_)
+                | Self::DSQuotaExceeded(_)
+                | Self::QuotaByStorageTypeExceeded(_)
+        )
+    }
+}
+
+impl fmt::Display for IOException {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.get_message())
+    }
+}
+
+impl std::error::Error for IOException {}
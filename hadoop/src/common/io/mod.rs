@@ -1,4 +1,8 @@
+mod io_exception;
 pub mod retry;
 mod text;
+mod writable_utils;
 
+pub use io_exception::IOException;
 pub use text::Text;
+pub use writable_utils::WritableUtils;
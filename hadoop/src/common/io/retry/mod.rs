@@ -1,3 +1,9 @@
+mod retry_invocation_handler;
+mod retry_policies;
 mod retry_policy;
+mod retry_utils;
 
-pub use retry_policy::RetryPolicy;
+pub use retry_invocation_handler::RetryInvocationHandler;
+pub use retry_policies::{MultipleLinearRandomRetry, RetryPolicies};
+pub use retry_policy::{RetryAction, RetryDecision, RetryPolicy};
+pub use retry_utils::RetryUtils;
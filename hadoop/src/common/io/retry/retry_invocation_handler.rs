@@ -0,0 +1,211 @@
+use super::{RetryDecision, RetryPolicy};
+use crate::common::ipc::Client;
+use anyhow::Error;
+#[cfg(feature = "async")]
+use std::future::Future;
+use std::{collections::HashMap, sync::Arc, thread, time::Duration};
+
+/// Invokes the methods of a proxy, retrying the failed invocations as
+/// decided by the retry policy of each method.
+///
+/// A retried invocation reuses the call id of the original one with an
+/// incremented retry count, so that the server can tell the retries apart
+/// from new calls.
+pub struct RetryInvocationHandler<T> {
+    proxy: T,
+    default_policy: Arc<dyn RetryPolicy>,
+    method_name_to_policy_map: HashMap<String, Arc<dyn RetryPolicy>>,
+}
+
+impl<T> RetryInvocationHandler<T> {
+    /// Create a handler invoking the methods of `proxy`, using the policies
+    /// in `method_name_to_policy_map` for the methods in it, and
+    /// `default_policy` for any other method.
+    pub fn new(
+        proxy: T,
+        default_policy: Arc<dyn RetryPolicy>,
+        method_name_to_policy_map: HashMap<String, Arc<dyn RetryPolicy>>,
+    ) -> Self {
+        Self {
+            proxy,
+            default_policy,
+            method_name_to_policy_map,
+        }
+    }
+
+    fn get_retry_policy(&self, method_name: &str) -> &Arc<dyn RetryPolicy> {
+        self.method_name_to_policy_map
+            .get(method_name)
+            .unwrap_or(&self.default_policy)
+    }
+
+    /// Invoke the method `method_name` of the proxy with `invoke`, retrying
+    /// it as long as its retry policy allows to.
+    ///
+    /// `is_idempotent_or_at_most_once` tells whether the method is safe to
+    /// retry even if the server may have applied it already.
+    pub fn invoke<R>(
+        &self,
+        method_name: &str,
+        is_idempotent_or_at_most_once: bool,
+        invoke: impl Fn(&T) -> anyhow::Result<R>,
+    ) -> anyhow::Result<R> {
+        let call_id = Client::next_call_id();
+        let mut retries = 0;
+        let mut failovers = 0;
+        loop {
+            Client::set_call_id_and_retry_count(call_id, retries)?;
+            let e = match invoke(&self.proxy) {
+                Ok(result) => return Ok(result),
+                Err(e) => e,
+            };
+            let delay = self.get_retry_delay(
+                method_name,
+                call_id,
+                e,
+                retries,
+                &mut failovers,
+                is_idempotent_or_at_most_once,
+            )?;
+            if !delay.is_zero() {
+                thread::sleep(delay);
+            }
+            retries += 1;
+        }
+    }
+
+    /// Invoke the asynchronous method `method_name` of the proxy with
+    /// `invoke`, retrying it as long as its retry policy allows to, see
+    /// [`Self::invoke`]. The delays between the retries do not block the
+    /// thread.
+    #[cfg(feature = "async")]
+    pub async fn invoke_async<'a, R, F>(
+        &'a self,
+        method_name: &str,
+        is_idempotent_or_at_most_once: bool,
+        invoke: impl Fn(&'a T) -> F,
+    ) -> anyhow::Result<R>
+    where
+        F: Future<Output = anyhow::Result<R>>,
+    {
+        let call_id = Client::next_call_id();
+        let mut retries = 0;
+        let mut failovers = 0;
+        loop {
+            // the call takes its id when first polled, before yielding, so
+            // on the thread which set it
+            Client::set_call_id_and_retry_count(call_id, retries)?;
+            let e = match invoke(&self.proxy).await {
+                Ok(result) => return Ok(result),
+                Err(e) => e,
+            };
+            let delay = self.get_retry_delay(
+                method_name,
+                call_id,
+                e,
+                retries,
+                &mut failovers,
+                is_idempotent_or_at_most_once,
+            )?;
+            if !delay.is_zero() {
+                tokio::time::sleep(delay).await;
+            }
+            retries += 1;
+        }
+    }
+
+    /// Decide, with the retry policy of the method, whether the invocation
+    /// which failed with `e` is retried, returning the delay before the retry
+    /// or the error to fail with.
+    fn get_retry_delay(
+        &self,
+        method_name: &str,
+        call_id: i32,
+        e: Error,
+        retries: i32,
+        failovers: &mut i32,
+        is_idempotent_or_at_most_once: bool,
+    ) -> anyhow::Result<Duration> {
+        let policy = self.get_retry_policy(method_name);
+        let action = policy.should_retry(&e, retries, *failovers, is_idempotent_or_at_most_once)?;
+        match action.action {
+            RetryDecision::Fail => {
+                if let Some(reason) = &action.reason {
+                    log::debug!(
+                        "Exception while invoking call #{} {}. Not retrying because {}",
+                        call_id,
+                        method_name,
+                        reason
+                    );
+                }
+                return Err(e);
+            }
+            RetryDecision::FailoverAndRetry => {
+                log::info!(
+                    "Exception while invoking call #{} {} after {} failover attempts. \
+                    Trying to failover {}: {:#}",
+                    call_id,
+                    method_name,
+                    failovers,
+                    format_sleep_message(action.delay_millis),
+                    e
+                );
+                *failovers += 1;
+            }
+            RetryDecision::Retry => {
+                log::info!(
+                    "Exception while invoking call #{} {}. Retrying {}: {:#}",
+                    call_id,
+                    method_name,
+                    format_sleep_message(action.delay_millis),
+                    e
+                );
+            }
+        }
+        Ok(Duration::from_millis(action.delay_millis.max(0) as u64))
+    }
+}
+
+fn format_sleep_message(millis: i64) -> String {
+    if millis > 0 {
+        format!("after sleeping for {}ms.", millis)
+    } else {
+        "immediately.".to_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::io::{retry::RetryPolicies, IOException};
+    use std::cell::Cell;
+
+    fn failing(attempts: &Cell<i32>, failures: i32) -> anyhow::Result<i32> {
+        attempts.set(attempts.get() + 1);
+        if attempts.get() <= failures {
+            return Err(Error::new(IOException::Retriable("try again".to_owned())));
+        }
+        Ok(attempts.get())
+    }
+
+    #[test]
+    fn test_invoke() -> anyhow::Result<()> {
+        let handler = RetryInvocationHandler::new(
+            Cell::new(0),
+            RetryPolicies::retry_up_to_maximum_count_with_fixed_sleep(2, 0),
+            HashMap::from([("once".to_owned(), RetryPolicies::try_once_then_fail())]),
+        );
+
+        // retried up to the maximum count
+        assert_eq!(3, handler.invoke("method", true, |p| failing(p, 2))?);
+        handler.proxy.set(0);
+        assert!(handler.invoke("method", true, |p| failing(p, 3)).is_err());
+        assert_eq!(3, handler.proxy.get());
+
+        // the policy of the method is used
+        handler.proxy.set(0);
+        assert!(handler.invoke("once", true, |p| failing(p, 1)).is_err());
+        assert_eq!(1, handler.proxy.get());
+        Ok(())
+    }
+}
@@ -0,0 +1,708 @@
+use super::{RetryAction, RetryDecision, RetryPolicy};
+use crate::common::{io::IOException, ipc::RemoteException};
+use anyhow::Error;
+use std::{
+    collections::HashMap,
+    fmt, io,
+    sync::{Arc, LazyLock},
+};
+
+/// The class name of the exception a server raises when a client presents an
+/// invalid token.
+const INVALID_TOKEN_CLASS_NAME: &str =
+    "org.apache.hadoop.security.token.SecretManager$InvalidToken";
+
+static TRY_ONCE_THEN_FAIL: LazyLock<Arc<dyn RetryPolicy>> =
+    LazyLock::new(|| Arc::new(TryOnceThenFail));
+static RETRY_FOREVER: LazyLock<Arc<dyn RetryPolicy>> = LazyLock::new(|| Arc::new(RetryForever));
+
+/// A collection of useful implementations of [`RetryPolicy`].
+pub struct RetryPolicies;
+
+impl RetryPolicies {
+    /// Try once, and fail by re-throwing the exception.
+    /// This corresponds to having no retry mechanism in place.
+    pub fn try_once_then_fail() -> Arc<dyn RetryPolicy> {
+        Arc::clone(&TRY_ONCE_THEN_FAIL)
+    }
+
+    /// Keep trying forever.
+    pub fn retry_forever() -> Arc<dyn RetryPolicy> {
+        Arc::clone(&RETRY_FOREVER)
+    }
+
+    /// Keep trying a limited number of times, waiting a fixed time between
+    /// attempts, and then fail by re-throwing the exception.
+    pub fn retry_up_to_maximum_count_with_fixed_sleep(
+        max_retries: i32,
+        sleep_time: i64,
+    ) -> Arc<dyn RetryPolicy> {
+        Arc::new(RetryUpToMaximumCountWithFixedSleep::new(
+            max_retries,
+            sleep_time,
+        ))
+    }
+
+    /// Keep trying a limited number of times, waiting a growing amount of
+    /// time between attempts, and then fail by re-throwing the exception.
+    /// The time between attempts is `sleep_time` multiplied by a random
+    /// number in the range of [0, 2 to the number of retries).
+    pub fn exponential_backoff_retry(
+        max_retries: i32,
+        sleep_time: i64,
+    ) -> anyhow::Result<Arc<dyn RetryPolicy>> {
+        Ok(Arc::new(ExponentialBackoffRetry::new(
+            max_retries,
+            sleep_time,
+        )?))
+    }
+
+    /// Set a default policy with some explicit handlers for specific
+    /// exceptions, keyed by their class names.
+    pub fn retry_by_exception(
+        default_policy: Arc<dyn RetryPolicy>,
+        exception_to_policy_map: HashMap<String, Arc<dyn RetryPolicy>>,
+    ) -> Arc<dyn RetryPolicy> {
+        Arc::new(ExceptionDependentRetry {
+            default_policy,
+            exception_to_policy_map,
+        })
+    }
+
+    /// A retry policy for RemoteException, set a default policy with some
+    /// explicit handlers for the class names of the exceptions raised on the
+    /// server side.
+    pub fn retry_by_remote_exception(
+        default_policy: Arc<dyn RetryPolicy>,
+        exception_to_policy_map: HashMap<String, Arc<dyn RetryPolicy>>,
+    ) -> Arc<dyn RetryPolicy> {
+        Arc::new(RemoteExceptionDependentRetry {
+            default_policy,
+            exception_to_policy_map,
+        })
+    }
+
+    /// Fail over to another server on network exceptions, falling back to
+    /// `fallback_policy` for any other exception.
+    pub fn failover_on_network_exception(
+        fallback_policy: Arc<dyn RetryPolicy>,
+        max_failovers: i32,
+        max_retries: i32,
+        delay_millis: i64,
+        max_delay_base: i64,
+    ) -> Arc<dyn RetryPolicy> {
+        Arc::new(FailoverOnNetworkExceptionRetry {
+            fallback_policy,
+            max_failovers,
+            max_retries,
+            delay_millis,
+            max_delay_base,
+        })
+    }
+}
+
+/// Retry up to `max_retries`.
+/// The actual sleep time of the n-th retry is `f(n, sleep_time)`,
+/// where f is a function provided by the subclass implementation.
+trait RetryLimited {
+    fn get_max_retries(&self) -> i32;
+
+    fn calculate_sleep_time(&self, retries: i32) -> i64;
+
+    fn get_reason(&self) -> String {
+        format!(
+            "retries get failed due to exceeded maximum allowed retries number: {}",
+            self.get_max_retries()
+        )
+    }
+
+    fn should_retry_limited(&self, retries: i32) -> RetryAction {
+        if retries >= self.get_max_retries() {
+            return RetryAction::fail(&self.get_reason());
+        }
+        RetryAction::with_delay(
+            RetryDecision::Retry,
+            self.calculate_sleep_time(retries),
+            None,
+        )
+    }
+}
+
+struct TryOnceThenFail;
+
+impl RetryPolicy for TryOnceThenFail {
+    fn should_retry(
+        &self,
+        _e: &Error,
+        _retries: i32,
+        _failovers: i32,
+        _is_idempotent_or_at_most_once: bool,
+    ) -> anyhow::Result<RetryAction> {
+        Ok(RetryAction::fail("try once and fail."))
+    }
+}
+
+struct RetryForever;
+
+impl RetryPolicy for RetryForever {
+    fn should_retry(
+        &self,
+        _e: &Error,
+        _retries: i32,
+        _failovers: i32,
+        _is_idempotent_or_at_most_once: bool,
+    ) -> anyhow::Result<RetryAction> {
+        Ok(RetryAction::RETRY)
+    }
+}
+
+struct RetryUpToMaximumCountWithFixedSleep {
+    max_retries: i32,
+    sleep_time: i64,
+}
+
+impl RetryUpToMaximumCountWithFixedSleep {
+    fn new(max_retries: i32, sleep_time: i64) -> Self {
+        Self {
+            max_retries,
+            sleep_time,
+        }
+    }
+}
+
+impl RetryLimited for RetryUpToMaximumCountWithFixedSleep {
+    fn get_max_retries(&self) -> i32 {
+        self.max_retries
+    }
+
+    fn calculate_sleep_time(&self, _retries: i32) -> i64 {
+        self.sleep_time
+    }
+}
+
+impl RetryPolicy for RetryUpToMaximumCountWithFixedSleep {
+    fn should_retry(
+        &self,
+        _e: &Error,
+        retries: i32,
+        _failovers: i32,
+        _is_idempotent_or_at_most_once: bool,
+    ) -> anyhow::Result<RetryAction> {
+        Ok(self.should_retry_limited(retries))
+    }
+}
+
+struct ExponentialBackoffRetry {
+    max_retries: i32,
+    sleep_time: i64,
+}
+
+impl ExponentialBackoffRetry {
+    fn new(max_retries: i32, sleep_time: i64) -> anyhow::Result<Self> {
+        if max_retries < 0 {
+            return Err(Error::msg(format!("maxRetries = {} < 0", max_retries)));
+        }
+        if max_retries >= i64::BITS as i32 - 1 {
+            // calculate_sleep_time may overflow.
+            return Err(Error::msg(format!(
+                "maxRetries = {} >= {}",
+                max_retries,
+                i64::BITS - 1
+            )));
+        }
+        Ok(Self {
+            max_retries,
+            sleep_time,
+        })
+    }
+}
+
+impl RetryLimited for ExponentialBackoffRetry {
+    fn get_max_retries(&self) -> i32 {
+        self.max_retries
+    }
+
+    fn calculate_sleep_time(&self, retries: i32) -> i64 {
+        calculate_exponential_time(self.sleep_time, retries + 1, i64::MAX)
+    }
+}
+
+impl RetryPolicy for ExponentialBackoffRetry {
+    fn should_retry(
+        &self,
+        _e: &Error,
+        retries: i32,
+        _failovers: i32,
+        _is_idempotent_or_at_most_once: bool,
+    ) -> anyhow::Result<RetryAction> {
+        Ok(self.should_retry_limited(retries))
+    }
+}
+
+/// Given pairs of number of retries and sleep time (n0, t0), (n1, t1), ...,
+/// the first n0 retries sleep t0 milliseconds on average,
+/// the following n1 retries sleep t1 milliseconds on average, and so on.
+///
+/// For all the sleep, the actual sleep time is randomly uniform distributed
+/// in the close interval [0.5t, 1.5t], where t is the sleep time specified.
+///
+/// The objects of this class are immutable.
+#[derive(Debug, PartialEq, Eq)]
+pub struct MultipleLinearRandomRetry {
+    pairs: Vec<Pair>,
+}
+
+/// Pairs of numRetries and sleepSeconds
+#[derive(Debug, PartialEq, Eq)]
+struct Pair {
+    num_retries: i32,
+    sleep_millis: i32,
+}
+
+impl MultipleLinearRandomRetry {
+    fn new(pairs: Vec<Pair>) -> anyhow::Result<Self> {
+        if pairs.is_empty() {
+            return Err(Error::msg("pairs must be neither null nor empty."));
+        }
+        Ok(Self { pairs })
+    }
+
+    /// Given the current number of retry, search the corresponding pair.
+    /// Returns the corresponding pair, or `None` if the current number of
+    /// retry > maximum number of retry.
+    fn search_pair(&self, mut cur_retry: i32) -> Option<&Pair> {
+        let mut i = 0;
+        while i < self.pairs.len() && cur_retry > self.pairs[i].num_retries {
+            cur_retry -= self.pairs[i].num_retries;
+            i += 1;
+        }
+        self.pairs.get(i)
+    }
+
+    /// Parse the given string as a MultipleLinearRandomRetry object.
+    /// The format of the string is "t_1, n_1, t_2, n_2, ...",
+    /// where t_i and n_i are the i-th pair of sleep time and number of retries.
+    /// Note that the white spaces in the string are ignored.
+    ///
+    /// Returns the parsed object, or `None` if the parsing fails.
+    pub fn parse_comma_separated_string(s: &str) -> Option<Self> {
+        let elements: Vec<_> = s.split(',').collect();
+        if elements.len() % 2 != 0 {
+            log::warn!(
+                "Illegal value: the number of elements in \"{}\" is {} but an even number of \
+                elements is expected.",
+                s,
+                elements.len()
+            );
+            return None;
+        }
+        let mut pairs = vec![];
+        for pair in elements.chunks(2) {
+            // parse the i-th sleep-time and number-of-retries
+            let sleep_millis = parse_positive_int(pair[0], s)?;
+            let num_retries = parse_positive_int(pair[1], s)?;
+            pairs.push(Pair {
+                num_retries,
+                sleep_millis,
+            });
+        }
+        Self::new(pairs).ok()
+    }
+}
+
+impl RetryPolicy for MultipleLinearRandomRetry {
+    fn should_retry(
+        &self,
+        _e: &Error,
+        cur_retry: i32,
+        _failovers: i32,
+        _is_idempotent_or_at_most_once: bool,
+    ) -> anyhow::Result<RetryAction> {
+        let p = match self.search_pair(cur_retry) {
+            // no more retries.
+            None => return Ok(RetryAction::fail("retries >= maxRetries")),
+            Some(p) => p,
+        };
+
+        // calculate sleep time and return.
+        // ensure 0.5 <= ratio <=1.5
+        let ratio = rand::random::<f64>() + 0.5;
+        let sleep_time = (p.sleep_millis as f64 * ratio).round() as i64;
+        Ok(RetryAction::with_delay(
+            RetryDecision::Retry,
+            sleep_time,
+            None,
+        ))
+    }
+}
+
+impl fmt::Display for MultipleLinearRandomRetry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let pairs = self
+            .pairs
+            .iter()
+            .map(|p| format!("{}x{}ms", p.num_retries, p.sleep_millis))
+            .collect::<Vec<_>>();
+        write!(f, "MultipleLinearRandomRetry[{}]", pairs.join(", "))
+    }
+}
+
+/// Parse the i-th element as an integer.
+/// Returns `None` if the parsing fails or the parsed value <= 0.
+fn parse_positive_int(element: &str, original_string: &str) -> Option<i32> {
+    match element.trim().parse::<i32>() {
+        Ok(n) if n > 0 => Some(n),
+        Ok(n) => {
+            log::warn!(
+                "Illegal value: the element \"{}\" in \"{}\" is {} <= 0.",
+                element,
+                original_string,
+                n
+            );
+            None
+        }
+        Err(e) => {
+            log::warn!(
+                "Failed to parse \"{}\", which is the element \"{}\" in \"{}\": {}",
+                element,
+                element,
+                original_string,
+                e
+            );
+            None
+        }
+    }
+}
+
+struct ExceptionDependentRetry {
+    default_policy: Arc<dyn RetryPolicy>,
+    exception_to_policy_map: HashMap<String, Arc<dyn RetryPolicy>>,
+}
+
+impl RetryPolicy for ExceptionDependentRetry {
+    fn should_retry(
+        &self,
+        e: &Error,
+        retries: i32,
+        failovers: i32,
+        is_idempotent_or_at_most_once: bool,
+    ) -> anyhow::Result<RetryAction> {
+        let policy = get_class_name(e)
+            .and_then(|class_name| self.exception_to_policy_map.get(class_name))
+            .unwrap_or(&self.default_policy);
+        policy.should_retry(e, retries, failovers, is_idempotent_or_at_most_once)
+    }
+}
+
+struct RemoteExceptionDependentRetry {
+    default_policy: Arc<dyn RetryPolicy>,
+    exception_to_policy_map: HashMap<String, Arc<dyn RetryPolicy>>,
+}
+
+impl RetryPolicy for RemoteExceptionDependentRetry {
+    fn should_retry(
+        &self,
+        e: &Error,
+        retries: i32,
+        failovers: i32,
+        is_idempotent_or_at_most_once: bool,
+    ) -> anyhow::Result<RetryAction> {
+        let policy = e
+            .downcast_ref::<RemoteException>()
+            .and_then(|re| self.exception_to_policy_map.get(re.get_class_name()))
+            .unwrap_or(&self.default_policy);
+        policy.should_retry(e, retries, failovers, is_idempotent_or_at_most_once)
+    }
+}
+
+/// Fail over and retry in the case of:
+///   Remote StandbyException (server is up, but is not the active server)
+///   Immediate socket exceptions (e.g. no route to host, econnrefused)
+///   Socket exceptions after initial connection when operation is idempotent
+///
+/// The first failover is immediate, while all subsequent failovers wait an
+/// exponentially-increasing random amount of time.
+///
+/// Fail immediately in the case of:
+///   Socket exceptions after initial connection when operation is not idempotent
+///
+/// Fall back on underlying retry policy otherwise.
+struct FailoverOnNetworkExceptionRetry {
+    fallback_policy: Arc<dyn RetryPolicy>,
+    max_failovers: i32,
+    max_retries: i32,
+    delay_millis: i64,
+    max_delay_base: i64,
+}
+
+impl FailoverOnNetworkExceptionRetry {
+    /// Returns 0 for the first failover; otherwise an exponential amount of
+    /// time.
+    fn get_failover_or_retry_sleep_time(&self, times: i32) -> i64 {
+        if times > 0 {
+            calculate_exponential_time(self.delay_millis, times, self.max_delay_base)
+        } else {
+            0
+        }
+    }
+}
+
+impl RetryPolicy for FailoverOnNetworkExceptionRetry {
+    fn should_retry(
+        &self,
+        e: &Error,
+        retries: i32,
+        failovers: i32,
+        is_idempotent_or_at_most_once: bool,
+    ) -> anyhow::Result<RetryAction> {
+        if failovers >= self.max_failovers {
+            return Ok(RetryAction::fail(&format!(
+                "failovers ({}) exceeded maximum allowed ({})",
+                failovers, self.max_failovers
+            )));
+        }
+        if retries - failovers > self.max_retries {
+            return Ok(RetryAction::fail(&format!(
+                "retries ({}) exceeded maximum allowed ({})",
+                retries, self.max_retries
+            )));
+        }
+
+        if is_connect_exception(e) || is_standby_exception(e) {
+            Ok(RetryAction::with_delay(
+                RetryDecision::FailoverAndRetry,
+                self.get_failover_or_retry_sleep_time(failovers),
+                None,
+            ))
+        } else if is_retriable_exception(e) {
+            Ok(RetryAction::with_delay(
+                RetryDecision::Retry,
+                calculate_exponential_time(self.delay_millis, retries, self.max_delay_base),
+                None,
+            ))
+        } else if e
+            .downcast_ref::<RemoteException>()
+            .is_some_and(|re| re.get_class_name() == INVALID_TOKEN_CLASS_NAME)
+        {
+            Ok(RetryAction::fail("Invalid or Cancelled Token"))
+        } else if e.downcast_ref::<io::Error>().is_some()
+            || e.downcast_ref::<IOException>().is_some()
+        {
+            if is_idempotent_or_at_most_once {
+                Ok(RetryAction::with_delay(
+                    RetryDecision::FailoverAndRetry,
+                    self.get_failover_or_retry_sleep_time(retries),
+                    None,
+                ))
+            } else {
+                Ok(RetryAction::fail(
+                    "the invoked method is not idempotent, and unable to determine \
+                    whether it was invoked",
+                ))
+            }
+        } else {
+            self.fallback_policy
+                .should_retry(e, retries, failovers, is_idempotent_or_at_most_once)
+        }
+    }
+}
+
+/// Return a value which is `time` increasing exponentially as a function of
+/// `retries`, +/- 0%-50% of that value, chosen randomly.
+fn calculate_exponential_time(time: i64, retries: i32, cap: i64) -> i64 {
+    let base_time = time
+        .checked_mul(1i64.checked_shl(retries as u32).unwrap_or(i64::MAX))
+        .unwrap_or(i64::MAX)
+        .min(cap);
+    (base_time as f64 * (rand::random::<f64>() + 0.5)) as i64
+}
+
+/// The class name of the exception, that is the class name of its
+/// [`IOException`] variant, or `RemoteException` for the exceptions raised on
+/// the server side.
+fn get_class_name(e: &Error) -> Option<&str> {
+    if e.downcast_ref::<RemoteException>().is_some() {
+        Some("org.apache.hadoop.ipc.RemoteException")
+    } else {
+        e.downcast_ref::<IOException>()
+            .map(IOException::get_class_name)
+    }
+}
+
+/// Whether the exception is raised when connecting to the server failed
+/// immediately, so that the call was not sent at all.
+fn is_connect_exception(e: &Error) -> bool {
+    if let Some(IOException::ConnectTimeout(_)) = e.downcast_ref::<IOException>() {
+        return true;
+    }
+    e.downcast_ref::<io::Error>().is_some_and(|e| {
+        matches!(
+            e.kind(),
+            io::ErrorKind::ConnectionRefused
+                | io::ErrorKind::UnexpectedEof
+                | io::ErrorKind::HostUnreachable
+                | io::ErrorKind::NetworkUnreachable
+                | io::ErrorKind::AddrNotAvailable
+        )
+    })
+}
+
+/// The exception as one of the known [`IOException`]s, unwrapping the
+/// exceptions raised on the server side.
+fn unwrap_io_exception(e: &Error) -> Option<IOException> {
+    match e.downcast_ref::<RemoteException>() {
+        Some(re) => IOException::from_class_name(re.get_class_name(), re.get_message()),
+        None => e.downcast_ref::<IOException>().cloned(),
+    }
+}
+
+/// Whether the exception is a StandbyException, possibly raised on the
+/// server side.
+fn is_standby_exception(e: &Error) -> bool {
+    matches!(unwrap_io_exception(e), Some(IOException::Standby(_)))
+}
+
+/// Whether the exception is a RetriableException, possibly raised on the
+/// server side.
+pub(super) fn is_retriable_exception(e: &Error) -> bool {
+    matches!(unwrap_io_exception(e), Some(IOException::Retriable(_)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_comma_separated_string() {
+        let policy =
+            MultipleLinearRandomRetry::parse_comma_separated_string("10000, 6,60000,10").unwrap();
+        assert_eq!(
+            "MultipleLinearRandomRetry[6x10000ms, 10x60000ms]",
+            policy.to_string()
+        );
+        assert!(MultipleLinearRandomRetry::parse_comma_separated_string("").is_none());
+        assert!(MultipleLinearRandomRetry::parse_comma_separated_string("10000").is_none());
+        assert!(MultipleLinearRandomRetry::parse_comma_separated_string("10000,0").is_none());
+        assert!(MultipleLinearRandomRetry::parse_comma_separated_string("a,6").is_none());
+    }
+
+    #[test]
+    fn test_multiple_linear_random_retry() -> anyhow::Result<()> {
+        let policy =
+            MultipleLinearRandomRetry::parse_comma_separated_string("100,2,1000,1").unwrap();
+        let e = Error::msg("error");
+        for (retries, sleep) in [(0, 100), (1, 100), (2, 100), (3, 1000)] {
+            let action = policy.should_retry(&e, retries, 0, false)?;
+            assert_eq!(RetryDecision::Retry, action.action);
+            assert!((sleep / 2..=sleep * 3 / 2).contains(&action.delay_millis));
+        }
+        assert_eq!(
+            RetryDecision::Fail,
+            policy.should_retry(&e, 4, 0, false)?.action
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_retry_limited() -> anyhow::Result<()> {
+        let e = Error::msg("error");
+        let fixed = RetryPolicies::retry_up_to_maximum_count_with_fixed_sleep(2, 10);
+        assert_eq!(
+            RetryAction::with_delay(RetryDecision::Retry, 10, None),
+            fixed.should_retry(&e, 1, 0, false)?
+        );
+        assert_eq!(
+            RetryAction::fail(&fixed_reason(2)),
+            fixed.should_retry(&e, 2, 0, false)?
+        );
+
+        let backoff = RetryPolicies::exponential_backoff_retry(3, 10)?;
+        let action = backoff.should_retry(&e, 2, 0, false)?;
+        assert_eq!(RetryDecision::Retry, action.action);
+        assert!((40..=120).contains(&action.delay_millis));
+        assert_eq!(
+            RetryDecision::Fail,
+            backoff.should_retry(&e, 3, 0, false)?.action
+        );
+        assert!(RetryPolicies::exponential_backoff_retry(63, 10).is_err());
+        Ok(())
+    }
+
+    fn fixed_reason(max_retries: i32) -> String {
+        format!(
+            "retries get failed due to exceeded maximum allowed retries number: {}",
+            max_retries
+        )
+    }
+
+    #[test]
+    fn test_exception_dependent_retry() -> anyhow::Result<()> {
+        let policy = RetryPolicies::retry_by_exception(
+            RetryPolicies::try_once_then_fail(),
+            HashMap::from([(
+                IOException::SafeMode(String::new())
+                    .get_class_name()
+                    .to_owned(),
+                RetryPolicies::retry_forever(),
+            )]),
+        );
+        let safe_mode = Error::new(IOException::SafeMode("safe mode".to_owned()));
+        assert_eq!(
+            RetryDecision::Retry,
+            policy.should_retry(&safe_mode, 10, 0, false)?.action
+        );
+        let other = Error::new(IOException::FileNotFound("not found".to_owned()));
+        assert_eq!(
+            RetryDecision::Fail,
+            policy.should_retry(&other, 0, 0, false)?.action
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_failover_on_network_exception() -> anyhow::Result<()> {
+        let policy = RetryPolicies::failover_on_network_exception(
+            RetryPolicies::try_once_then_fail(),
+            2,
+            2,
+            10,
+            100,
+        );
+        let refused = Error::new(io::Error::from(io::ErrorKind::ConnectionRefused));
+        assert_eq!(
+            RetryAction::FAILOVER_AND_RETRY,
+            policy.should_retry(&refused, 0, 0, false)?
+        );
+        assert_eq!(
+            RetryDecision::Fail,
+            policy.should_retry(&refused, 2, 2, false)?.action
+        );
+
+        let standby = Error::new(RemoteException::new(
+            "org.apache.hadoop.ipc.StandbyException",
+            "standby",
+        ));
+        assert_eq!(
+            RetryDecision::FailoverAndRetry,
+            policy.should_retry(&standby, 1, 1, false)?.action
+        );
+
+        // a broken connection is only retried for idempotent methods
+        let reset = Error::new(io::Error::from(io::ErrorKind::ConnectionReset));
+        assert_eq!(
+            RetryDecision::FailoverAndRetry,
+            policy.should_retry(&reset, 0, 0, true)?.action
+        );
+        assert_eq!(
+            RetryDecision::Fail,
+            policy.should_retry(&reset, 0, 0, false)?.action
+        );
+
+        let remote = Error::new(RemoteException::new("java.lang.Exception", "remote"));
+        assert_eq!(
+            RetryDecision::Fail,
+            policy.should_retry(&remote, 0, 0, true)?.action
+        );
+        Ok(())
+    }
+}
@@ -1,5 +1,68 @@
+use anyhow::Error;
+
 /// Specifies a policy for retrying method failures.
 /// Implementations of this interface should be immutable.
-pub trait RetryPolicy {
-    // TODO
+pub trait RetryPolicy: Send + Sync {
+    /// Determines whether the framework should retry a method for the given
+    /// exception, and the number of retries that have been made for that
+    /// operation so far.
+    ///
+    /// `retries` is the number of times the method has been retried,
+    /// `failovers` the number of times the method has failed over to a
+    /// different backend implementation, and `is_idempotent_or_at_most_once`
+    /// whether the method is idempotent or at most once, so that it is safe
+    /// to retry it even if it may have been applied by the server.
+    ///
+    /// Returns an error if the exception is not to be retried and the
+    /// policy wants to raise a different one.
+    fn should_retry(
+        &self,
+        e: &Error,
+        retries: i32,
+        failovers: i32,
+        is_idempotent_or_at_most_once: bool,
+    ) -> anyhow::Result<RetryAction>;
+}
+
+/// The decision of a [`RetryPolicy`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RetryDecision {
+    Fail,
+    Retry,
+    FailoverAndRetry,
+}
+
+/// Returned by [`RetryPolicy::should_retry`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RetryAction {
+    pub action: RetryDecision,
+    pub delay_millis: i64,
+    pub reason: Option<String>,
+}
+
+impl RetryAction {
+    pub const FAIL: RetryAction = RetryAction::new(RetryDecision::Fail);
+    pub const RETRY: RetryAction = RetryAction::new(RetryDecision::Retry);
+    pub const FAILOVER_AND_RETRY: RetryAction = RetryAction::new(RetryDecision::FailoverAndRetry);
+
+    pub const fn new(action: RetryDecision) -> Self {
+        Self::with_delay(action, 0, None)
+    }
+
+    pub const fn with_delay(
+        action: RetryDecision,
+        delay_millis: i64,
+        reason: Option<String>,
+    ) -> Self {
+        Self {
+            action,
+            delay_millis,
+            reason,
+        }
+    }
+
+    /// A decision to fail for the given reason.
+    pub fn fail(reason: &str) -> Self {
+        Self::with_delay(RetryDecision::Fail, 0, Some(reason.to_owned()))
+    }
 }
@@ -0,0 +1,103 @@
+use super::{
+    retry_policies::is_retriable_exception, MultipleLinearRandomRetry, RetryAction, RetryPolicies,
+    RetryPolicy,
+};
+use crate::common::{conf::Configuration, io::IOException, ipc::RemoteException};
+use anyhow::Error;
+use std::{io, sync::Arc};
+
+pub struct RetryUtils;
+
+impl RetryUtils {
+    /// Return the default retry policy set in conf.
+    ///
+    /// If the value `retry_policy_enabled_key` is set to false in conf,
+    /// use TRY_ONCE_THEN_FAIL.
+    ///
+    /// Otherwise, get the MultipleLinearRandomRetry policy specified in the
+    /// conf and then
+    /// 1. use `multiple_linear_random_retry` for
+    ///    - `remote_exception_to_retry`, or
+    ///    - IOException other than RemoteException, or
+    ///    - ServiceException; and
+    /// 2. use TRY_ONCE_THEN_FAIL for
+    ///    - non-`remote_exception_to_retry` RemoteException, or
+    ///    - non-IOException.
+    pub fn get_default_retry_policy(
+        conf: &Configuration,
+        retry_policy_enabled_key: &str,
+        default_retry_policy_enabled: bool,
+        retry_policy_spec_key: &str,
+        default_retry_policy_spec: &str,
+        remote_exception_to_retry: &str,
+    ) -> Arc<dyn RetryPolicy> {
+        match Self::get_multiple_linear_random_retry(
+            conf,
+            retry_policy_enabled_key,
+            default_retry_policy_enabled,
+            retry_policy_spec_key,
+            default_retry_policy_spec,
+        ) {
+            None => RetryPolicies::try_once_then_fail(),
+            Some(multiple_linear_random_retry) => Arc::new(WrapperRetryPolicy {
+                multiple_linear_random_retry,
+                remote_exception_to_retry: remote_exception_to_retry.to_owned(),
+            }),
+        }
+    }
+
+    /// Return the MultipleLinearRandomRetry policy specified in the conf,
+    /// or `None` if the feature is disabled or the spec is illegal.
+    pub fn get_multiple_linear_random_retry(
+        conf: &Configuration,
+        retry_policy_enabled_key: &str,
+        default_retry_policy_enabled: bool,
+        retry_policy_spec_key: &str,
+        default_retry_policy_spec: &str,
+    ) -> Option<MultipleLinearRandomRetry> {
+        let enabled = conf.get_bool(retry_policy_enabled_key, default_retry_policy_enabled);
+        if !enabled {
+            return None;
+        }
+        let policy = conf
+            .get(retry_policy_spec_key, Some(default_retry_policy_spec))
+            .unwrap_or_default();
+        MultipleLinearRandomRetry::parse_comma_separated_string(&policy)
+    }
+}
+
+/// Retries the exceptions chosen by [`RetryUtils::get_default_retry_policy`]
+/// with a MultipleLinearRandomRetry policy, and fails on any other.
+struct WrapperRetryPolicy {
+    multiple_linear_random_retry: MultipleLinearRandomRetry,
+    remote_exception_to_retry: String,
+}
+
+impl RetryPolicy for WrapperRetryPolicy {
+    fn should_retry(
+        &self,
+        e: &Error,
+        retries: i32,
+        failovers: i32,
+        is_method_idempotent: bool,
+    ) -> anyhow::Result<RetryAction> {
+        let retry = if is_retriable_exception(e) {
+            // RetriableException or RetriableException wrapped
+            true
+        } else if let Some(re) = e.downcast_ref::<RemoteException>() {
+            re.get_class_name() == self.remote_exception_to_retry
+        } else {
+            e.downcast_ref::<IOException>().is_some() || e.downcast_ref::<io::Error>().is_some()
+        };
+        if retry {
+            self.multiple_linear_random_retry.should_retry(
+                e,
+                retries,
+                failovers,
+                is_method_idempotent,
+            )
+        } else {
+            Ok(RetryAction::fail("try once and fail."))
+        }
+    }
+}
@@ -1,7 +1,9 @@
+use std::fmt;
+
 #[derive(Debug)]
 pub struct Text {
-    _bytes: Vec<u8>,
-    _length: i32,
+    bytes: Vec<u8>,
+    length: i32,
 }
 
 impl Text {
@@ -21,8 +23,16 @@ impl From<String> for Text {
     fn from(s: String) -> Self {
         let bb = Self::encode(&s, true);
         Self {
-            _bytes: bb.into(),
-            _length: bb.len() as i32,
+            bytes: bb.into(),
+            length: bb.len() as i32,
         }
     }
 }
+
+impl fmt::Display for Text {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&String::from_utf8_lossy(
+            &self.bytes[..self.length as usize],
+        ))
+    }
+}
@@ -0,0 +1,84 @@
+use std::io::Read;
+
+/// Utilities for reading the Writable serialization of primitive values.
+pub struct WritableUtils;
+
+impl WritableUtils {
+    /// Reads a zero-compressed encoded long from input stream and returns it.
+    pub fn read_vlong(stream: &mut impl Read) -> anyhow::Result<i64> {
+        let first_byte = Self::read_byte(stream)?;
+        let len = Self::decode_vint_size(first_byte);
+        if len == 1 {
+            return Ok(first_byte as i64);
+        }
+        let mut i = 0i64;
+        for _ in 0..len - 1 {
+            let b = Self::read_byte(stream)?;
+            i = (i << 8) | (b as u8 as i64);
+        }
+        Ok(if Self::is_negative_vint(first_byte) {
+            i ^ -1
+        } else {
+            i
+        })
+    }
+
+    /// Reads a zero-compressed encoded integer from input stream and returns it.
+    pub fn read_vint(stream: &mut impl Read) -> anyhow::Result<i32> {
+        let n = Self::read_vlong(stream)?;
+        i32::try_from(n).map_err(|_| anyhow::Error::msg("value too long to fit in integer"))
+    }
+
+    /// Read a String as a VInt n, followed by n Bytes in Text format.
+    /// Returns `None` for a length of -1.
+    pub fn read_string(stream: &mut impl Read) -> anyhow::Result<Option<String>> {
+        let length = Self::read_vint(stream)?;
+        if length == -1 {
+            return Ok(None);
+        }
+        let mut buffer = vec![0; length as usize];
+        stream.read_exact(&mut buffer)?;
+        Ok(Some(String::from_utf8_lossy(&buffer).into_owned()))
+    }
+
+    /// Given the first byte of a vint/vlong, determine the sign
+    pub fn is_negative_vint(value: i8) -> bool {
+        value < -120 || (-112..0).contains(&value)
+    }
+
+    /// Parse the first byte of a vint/vlong to determine the number of bytes
+    pub fn decode_vint_size(value: i8) -> i32 {
+        if value >= -112 {
+            1
+        } else if value < -120 {
+            -119 - value as i32
+        } else {
+            -111 - value as i32
+        }
+    }
+
+    fn read_byte(stream: &mut impl Read) -> anyhow::Result<i8> {
+        let mut buf = [0; 1];
+        stream.read_exact(&mut buf)?;
+        Ok(buf[0] as i8)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_vlong() {
+        let read = |bytes: &[u8]| WritableUtils::read_vlong(&mut &bytes[..]).unwrap();
+        assert_eq!(read(&[0x05]), 5);
+        assert_eq!(read(&[0xff]), -1);
+        assert_eq!(read(&[0x8f, 0x80]), 128);
+        assert_eq!(read(&[0x8e, 0x01, 0x00]), 256);
+        assert_eq!(read(&[0x87, 0x80]), -129);
+        assert_eq!(
+            WritableUtils::read_string(&mut &[0x03, b'a', b'b', b'c'][..]).unwrap(),
+            Some("abc".to_owned())
+        );
+    }
+}
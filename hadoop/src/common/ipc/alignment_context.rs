@@ -4,6 +4,6 @@
 /// This should be implemented separately on the client side and server side
 /// and can be used to pass state information on RPC responses from server
 /// to client.
-pub trait AlignmentContext {
+pub trait AlignmentContext: Send + Sync {
     // TODO
 }
@@ -1,6 +1,9 @@
 use super::Client;
 use crate::common::ipc::{AlignmentContext, RpcKind};
-use std::rc::Rc;
+use anyhow::Error;
+use std::sync::{Arc, Condvar, Mutex};
+#[cfg(feature = "async")]
+use tokio::sync::oneshot;
 
 /// Class that represents an RPC call
 pub struct Call {
@@ -9,36 +12,89 @@ pub struct Call {
     // retry count
     pub retry: i32,
     // the serialized rpc request
-    pub rpc_request: Rc<Vec<u8>>,
-    // `None` if rpc has error
-    _rpc_response: Option<String>,
-    // exception, `None` if success
-    _error: Option<String>,
+    pub rpc_request: Arc<Vec<u8>>,
+    // the rpc response or the exception of the call, `None` until the call
+    // is done
+    result: Mutex<Option<anyhow::Result<Vec<u8>>>>,
+    // notified when the call is done
+    done: Condvar,
+    // receives the outcome of an asynchronous call instead of `result`
+    #[cfg(feature = "async")]
+    async_response: Mutex<Option<oneshot::Sender<anyhow::Result<Vec<u8>>>>>,
     // Rpc EngineKind
     pub rpc_kind: RpcKind,
-    // true when call is done
-    _done: bool,
     _external_handler: Option<String>,
-    pub alignment_context: Option<Rc<dyn AlignmentContext>>,
+    pub alignment_context: Option<Arc<dyn AlignmentContext>>,
 }
 
 impl Call {
-    pub(super) fn new(rpc_kind: &RpcKind, param: Rc<Vec<u8>>) -> anyhow::Result<Self> {
+    pub(super) fn new(rpc_kind: &RpcKind, param: Arc<Vec<u8>>) -> anyhow::Result<Self> {
         Ok(Self {
             id: Client::take_call_id()?,
-            retry: Client::get_retry_count()?,
+            retry: Client::take_retry_count()?,
             rpc_request: param,
-            _rpc_response: None,
-            _error: None,
+            result: Mutex::new(None),
+            done: Condvar::new(),
+            #[cfg(feature = "async")]
+            async_response: Mutex::new(None),
             rpc_kind: rpc_kind.to_owned(),
-            _done: false,
             _external_handler: Client::get_external_handler()?,
             alignment_context: None,
         })
     }
 
+    /// Indicate when the call is complete and the
+    /// value or error are available.  Notifies by default.
+    fn call_complete(&self, result: anyhow::Result<Vec<u8>>) {
+        #[cfg(feature = "async")]
+        if let Some(sender) = self.async_response.lock().unwrap().take() {
+            // the caller may have stopped waiting
+            let _ = sender.send(result);
+            return;
+        }
+        let mut current = self.result.lock().unwrap();
+        // the first outcome of the call wins
+        if current.is_none() {
+            *current = Some(result);
+        }
+        // notify caller
+        self.done.notify_all();
+    }
+
+    /// Set the exception when there is an error.
+    /// Notify the caller the call is done.
+    pub fn set_exception(&self, error: Error) {
+        self.call_complete(Err(error));
+    }
+
+    /// Set the return value when there is no error.
+    /// Notify the caller the call is done.
+    pub fn set_rpc_response(&self, rpc_response: Vec<u8>) {
+        self.call_complete(Ok(rpc_response));
+    }
+
+    /// Wait until the call is done, returning its rpc response.
+    pub fn get_rpc_response(&self) -> anyhow::Result<Vec<u8>> {
+        let mut result = self.result.lock().unwrap();
+        loop {
+            if let Some(result) = result.take() {
+                return result;
+            }
+            result = self.done.wait(result).unwrap();
+        }
+    }
+
+    /// Make the call asynchronous, its outcome is sent to the returned
+    /// receiver when the call is done.
+    #[cfg(feature = "async")]
+    pub fn set_async_response(&self) -> oneshot::Receiver<anyhow::Result<Vec<u8>>> {
+        let (sender, receiver) = oneshot::channel();
+        *self.async_response.lock().unwrap() = Some(sender);
+        receiver
+    }
+
     /// Set an AlignmentContext for the call to update when call is done.
-    pub fn set_alignment_context(&mut self, ac: Option<Rc<dyn AlignmentContext>>) {
+    pub fn set_alignment_context(&mut self, ac: Option<Arc<dyn AlignmentContext>>) {
         self.alignment_context = ac;
     }
 }
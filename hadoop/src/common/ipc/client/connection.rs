@@ -1,114 +1,507 @@
-use super::{call::Call, Client, ConnectionId, IpcStreams};
+use super::{call::Call, prepend_buf_size, ConnectionId, ConnectionPool, IpcStreams};
 use crate::common::{
     fs::common_configuration_keys::IPC_MAXIMUM_RESPONSE_LENGTH_DEFAULT,
-    io::retry::RetryPolicy,
-    ipc::{server::AuthProtocol, RpcConstants, RpcKind, RpcProtocol, RPC},
+    io::{
+        retry::{RetryDecision, RetryPolicy},
+        IOException,
+    },
+    ipc::{client_id::BYTE_LENGTH, AuthProtocol, RemoteException, RpcConstants, RpcKind},
+    net::NetUtils,
     rpc_request_header_proto::OperationProto,
+    rpc_response_header_proto::RpcStatusProto,
+    security::{AuthMethod, SaslRpcClient, SecurityUtil, UserGroupInformation},
     util::ProtoUtil,
+    RpcResponseHeaderProto,
 };
+use anyhow::Error;
 use atomic::Atomic;
 use prost::Message;
+use rand::Rng;
 use std::{
     collections::HashMap,
-    marker::PhantomData,
-    net::{SocketAddr, TcpStream},
-    rc::Rc,
-    sync::Arc,
+    io::{self, Read},
+    net::{Shutdown, SocketAddr, TcpStream},
+    sync::{atomic::Ordering, Arc, Condvar, Mutex, OnceLock, Weak},
+    thread,
+    time::{Duration, Instant},
 };
 
+/// The maximum backoff in msecs before retrying a failed SASL negotiation.
+const MAX_SASL_BACKOFF_MILLIS: u64 = 5000;
+
 /// Thread that reads responses and notifies callers.  Each connection owns a
 /// socket connected to a remote address.  Calls are multiplexed through this
 /// socket: responses may be delivered out of order.
-pub(super) struct Connection<'a, T: RpcProtocol> {
-    client: &'a Client,
-    _server: SocketAddr,
-    remote_id: Rc<ConnectionId>,
-    auth_method: String,
-    auth_protocol: AuthProtocol,
+pub(super) struct Connection {
+    client_id: [u8; BYTE_LENGTH],
+    server: SocketAddr,
+    remote_id: Arc<ConnectionId>,
     service_class: u8,
-    pub ipc_streams: IpcStreams,
-    _max_response_length: i32,
-    _rpc_timeout: i32,
-    _max_idle_time: i32,
-    _connection_retry_policy: Option<Rc<dyn RetryPolicy>>,
-    _max_retries_on_sasl: i32,
-    _max_retries_on_socket_timeouts: i32,
-    _tcp_no_delay: bool,
-    _tcp_low_latency: bool,
-    _do_ping: bool,
-    _ping_interval: i32,
-    _so_timeout: i32,
-    _ping_request: Vec<u8>,
+    // the streams requests are written to, set once the connection is set up
+    ipc_streams: Mutex<Option<IpcStreams>>,
+    // the connected socket, used to interrupt the response reader
+    socket: OnceLock<TcpStream>,
+    max_response_length: i32,
+    rpc_timeout: i32,
+    // connections will be culled if it was idle for maxIdleTime msecs
+    max_idle_time: i32,
+    connection_retry_policy: Arc<dyn RetryPolicy>,
+    max_retries_on_sasl: i32,
+    // the max. no. of retries for socket connections on time out exceptions
+    max_retries_on_socket_timeouts: i32,
+    // if T then disable Nagle's Algorithm
+    tcp_no_delay: bool,
+    // if T then use low-delay QoS
+    tcp_low_latency: bool,
+    // do we need to send ping message
+    do_ping: bool,
+    // how often sends ping to the server in msecs
+    ping_interval: i32,
+    // the timeout of the socket reads in msecs
+    so_timeout: i32,
+    // the timeout of connecting to the server in msecs
+    connection_timeout: i32,
+    // if T then the socket is not bound to the local address of the client
+    bind_to_wild_card_address: bool,
+    // if T then falling back to SIMPLE auth is allowed when security is
+    // enabled
+    fallback_allowed: bool,
+    // the serialized ping request, sent when the connection is idle
+    ping_request: Vec<u8>,
+    state: Mutex<ConnectionState>,
+    // notified when a call is added or the connection is marked closed
+    work: Condvar,
+    // the pool of the client, the connection removes itself when closed
+    connections: Weak<ConnectionPool>,
+}
+
+struct ConnectionState {
     // currently active calls
-    calls: HashMap<i32, Rc<Call>>,
-    phantom: PhantomData<T>,
+    calls: HashMap<i32, Arc<Call>>,
+    // last I/O activity time
+    last_activity: Instant,
+    // indicate if the connection is closed
+    should_close_connection: bool,
+    // close reason
+    close_exception: Option<Error>,
 }
 
-impl<'a, T: RpcProtocol> Connection<'a, T> {
+impl Connection {
     pub fn new(
-        client: &'a Client,
-        remote_id: Rc<ConnectionId>,
+        client_id: &[u8; BYTE_LENGTH],
+        remote_id: Arc<ConnectionId>,
         service_class: u8,
-    ) -> anyhow::Result<Self> {
-        let tcp_stream = TcpStream::connect(remote_id.get_address())?;
-        let ipc_streams = IpcStreams::new(tcp_stream, IPC_MAXIMUM_RESPONSE_LENGTH_DEFAULT);
-        // try SASL if security is enabled or if the ugi contains tokens.
-        // this causes a SIMPLE client with tokens to attempt SASL
-        // TODO: get value of try_sasl from UGI
-        let try_sasl = false;
-        let auth_protocol = if try_sasl {
-            AuthProtocol::Sasl
+        connection_timeout: i32,
+        bind_to_wild_card_address: bool,
+        fallback_allowed: bool,
+        connections: Weak<ConnectionPool>,
+    ) -> Self {
+        let rpc_timeout = remote_id.get_rpc_timeout();
+        let do_ping = remote_id.get_do_ping();
+        let ping_interval = remote_id.get_ping_interval();
+        let so_timeout = if rpc_timeout > 0 {
+            // effective rpc timeout is rounded up to multiple of pingInterval
+            // if pingInterval < rpcTimeout.
+            if do_ping && ping_interval < rpc_timeout {
+                ping_interval
+            } else {
+                rpc_timeout
+            }
         } else {
-            AuthProtocol::None
+            ping_interval
+        };
+        let ping_request = if do_ping {
+            let ping_header = ProtoUtil::make_rpc_request_header(
+                &RpcKind::RpcProtocolBuffer,
+                OperationProto::RpcFinalPacket,
+                RpcConstants::PING_CALL_ID,
+                RpcConstants::INVALID_RETRY_COUNT,
+                client_id,
+                None,
+            );
+            prepend_buf_size(ping_header.encode_length_delimited_to_vec())
+        } else {
+            vec![]
         };
-        Ok(Self {
-            client,
-            _server: remote_id.get_address().to_owned(),
-            remote_id: Rc::clone(&remote_id),
-            auth_method: "".to_string(),
-            auth_protocol,
+        Self {
+            client_id: client_id.to_owned(),
+            server: remote_id.get_address().to_owned(),
+            remote_id: Arc::clone(&remote_id),
             service_class,
-            ipc_streams,
-            _max_response_length: IPC_MAXIMUM_RESPONSE_LENGTH_DEFAULT,
-            _rpc_timeout: remote_id.get_rpc_timeout(),
-            _max_idle_time: remote_id.get_max_idle_time(),
-            _connection_retry_policy: remote_id.get_retry_policy(),
-            _max_retries_on_sasl: remote_id.get_max_retries_on_sasl(),
-            _max_retries_on_socket_timeouts: remote_id.get_max_retries_on_socket_timeouts(),
-            _tcp_no_delay: remote_id.get_tcp_no_delay(),
-            _tcp_low_latency: remote_id.get_tcp_low_latency(),
-            _do_ping: remote_id.get_do_ping(),
-            _ping_interval: remote_id.get_ping_interval(),
-            _so_timeout: remote_id.get_rpc_timeout(),
-            _ping_request: vec![],
-            calls: HashMap::new(),
-            phantom: PhantomData,
-        })
+            ipc_streams: Mutex::new(None),
+            socket: OnceLock::new(),
+            max_response_length: IPC_MAXIMUM_RESPONSE_LENGTH_DEFAULT,
+            rpc_timeout,
+            max_idle_time: remote_id.get_max_idle_time(),
+            connection_retry_policy: remote_id.get_retry_policy(),
+            max_retries_on_sasl: remote_id.get_max_retries_on_sasl(),
+            max_retries_on_socket_timeouts: remote_id.get_max_retries_on_socket_timeouts(),
+            tcp_no_delay: remote_id.get_tcp_no_delay(),
+            tcp_low_latency: remote_id.get_tcp_low_latency(),
+            do_ping,
+            ping_interval,
+            so_timeout,
+            connection_timeout,
+            bind_to_wild_card_address,
+            fallback_allowed,
+            ping_request,
+            state: Mutex::new(ConnectionState {
+                calls: HashMap::new(),
+                last_activity: Instant::now(),
+                should_close_connection: false,
+                close_exception: None,
+            }),
+            work: Condvar::new(),
+            connections,
+        }
+    }
+
+    fn get_name(&self) -> String {
+        format!(
+            "IPC Client connection to {} from {}",
+            self.server,
+            self.remote_id.get_ticket().get_user_name()
+        )
+    }
+
+    /// Update lastActivity with the current time.
+    fn touch(&self) {
+        self.state.lock().unwrap().last_activity = Instant::now();
+    }
+
+    /// Process timeout exception: if the connection is not going to be
+    /// closed or the RPC is not timed out yet, send a ping.
+    fn handle_timeout(&self, waiting: i32) -> io::Result<()> {
+        if self.should_close() || (0 < self.rpc_timeout && self.rpc_timeout <= waiting) {
+            let ch = self
+                .socket
+                .get()
+                .and_then(|socket| socket.local_addr().ok())
+                .map(|local| format!("{} remote={}", local, self.server))
+                .unwrap_or_else(|| self.server.to_string());
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                IOException::SocketTimeout(format!(
+                    "{} millis timeout while waiting for channel to be ready for read. ch : {}",
+                    waiting, ch
+                )),
+            ));
+        }
+        self.send_ping()
+    }
+
+    /// Send a ping to the server if the time elapsed
+    /// since last I/O activity is equal to or greater than the ping interval.
+    fn send_ping(&self) -> io::Result<()> {
+        if !self.do_ping {
+            return Ok(());
+        }
+        let mut state = self.state.lock().unwrap();
+        if state.last_activity.elapsed() < Duration::from_millis(self.ping_interval as u64) {
+            return Ok(());
+        }
+        state.last_activity = Instant::now();
+        drop(state);
+        log::debug!("{} sending ping", self.get_name());
+        if let Some(ipc_streams) = self.ipc_streams.lock().unwrap().as_mut() {
+            ipc_streams
+                .send_request(&self.ping_request)
+                .map_err(io::Error::other)?;
+        }
+        Ok(())
     }
 
     /// Add a call to this connection's call queue and notify
     /// a listener; synchronized.
-    pub(super) fn add_call(&mut self, call: Rc<Call>) {
-        self.calls.insert(call.id, call);
+    /// Returns false if called during shutdown.
+    pub(super) fn add_call(&self, call: Arc<Call>) -> bool {
+        let mut state = self.state.lock().unwrap();
+        if state.should_close_connection {
+            return false;
+        }
+        state.calls.insert(call.id, call);
+        self.work.notify_all();
+        true
+    }
+
+    fn should_close(&self) -> bool {
+        self.state.lock().unwrap().should_close_connection
     }
 
-    pub fn setup_iostreams(
-        &mut self,
-        _fallback_to_simple_auth: Option<Arc<Atomic<bool>>>,
+    fn setup_connection(&self) -> anyhow::Result<TcpStream> {
+        let mut io_failures = 0;
+        let mut timeout_failures = 0;
+        loop {
+            match self.connect() {
+                Ok(socket) => return Ok(socket),
+                Err(e) => {
+                    // TODO: check for an address change and reset the
+                    // failure counters if the address was changed
+                    if let Some(IOException::ConnectTimeout(_)) = e.downcast_ref::<IOException>() {
+                        self.handle_connection_timeout(
+                            timeout_failures,
+                            self.max_retries_on_socket_timeouts,
+                            e,
+                        )?;
+                        timeout_failures += 1;
+                    } else {
+                        self.handle_connection_failure(io_failures, e)?;
+                        io_failures += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Make a single attempt to connect to the server.
+    fn connect(&self) -> anyhow::Result<TcpStream> {
+        // Bind the socket to the host specified in the principal name of the
+        // client, to ensure Server matching address of the client connection
+        // to host name in principal passed.
+        let ticket = self.remote_id.get_ticket();
+        let bind_addr = if ticket.has_kerberos_credentials() {
+            SecurityUtil::get_host_from_principal(&ticket.get_user_name())
+                // If host name is a valid local address then bind socket to it
+                .and_then(|host| NetUtils::get_local_inet_address(&host))
+                .and_then(|local_addr| {
+                    NetUtils::bind_to_local_address(local_addr, self.bind_to_wild_card_address)
+                })
+                .map(|local_addr| SocketAddr::new(local_addr, 0))
+        } else {
+            None
+        };
+        let socket = NetUtils::connect(&self.server, bind_addr.as_ref(), self.connection_timeout)?;
+        socket.set_nodelay(self.tcp_no_delay)?;
+        NetUtils::set_keep_alive(&socket, true)?;
+        if self.tcp_low_latency {
+            // This allows intermediate switches to shape IPC traffic
+            // differently from Shuffle/HDFS DataStreamer traffic.
+            //
+            // IPTOS_RELIABILITY (0x04) | IPTOS_LOWDELAY (0x10)
+            NetUtils::set_traffic_class(&socket, 0x04 | 0x10)?;
+        }
+        if self.so_timeout > 0 {
+            socket.set_read_timeout(Some(Duration::from_millis(self.so_timeout as u64)))?;
+        }
+        Ok(socket)
+    }
+
+    /// Handle connection failures due to timeout on connect.
+    ///
+    /// If the current number of retries is equal to the max number of retries,
+    /// stop retrying and return the exception; otherwise backoff and retry.
+    fn handle_connection_timeout(
+        &self,
+        cur_retries: i32,
+        max_retries: i32,
+        e: Error,
     ) -> anyhow::Result<()> {
-        // TODO: implement missing details
+        // throw the exception if the maximum number of retries is reached
+        if cur_retries >= max_retries {
+            return Err(e);
+        }
+        log::info!(
+            "Retrying connect to server: {}. Already tried {} time(s); maxRetries={}",
+            self.server,
+            cur_retries,
+            max_retries
+        );
+        Ok(())
+    }
 
-        let remote_id = Rc::clone(&self.remote_id);
-        let auth_method = &self.auth_method.clone();
-        self.write_connection_header()?;
-        if self.auth_protocol == AuthProtocol::Sasl {
-            // TODO
-            unimplemented!("SASL Auth is not implemented yet");
+    /// Handle connection failures, asking the connection retry policy
+    /// whether to retry, and sleeping before the next attempt if so.
+    fn handle_connection_failure(&self, cur_retries: i32, e: Error) -> anyhow::Result<()> {
+        let action = self
+            .connection_retry_policy
+            .should_retry(&e, cur_retries, 0, true)?;
+        if action.action == RetryDecision::Fail {
+            if let Some(reason) = &action.reason {
+                log::warn!(
+                    "Failed to connect to server: {}: {} {}",
+                    self.server,
+                    reason,
+                    e
+                );
+            }
+            return Err(e);
+        }
+        if self.should_close() {
+            return Err(e);
         }
-        self.write_connection_context(remote_id, auth_method)?;
+        thread::sleep(Duration::from_millis(action.delay_millis.max(0) as u64));
+        log::info!(
+            "Retrying connect to server: {}. Already tried {} time(s); {}",
+            self.server,
+            cur_retries,
+            e
+        );
         Ok(())
     }
 
+    /// Connect to the server and set up the I/O streams. It then sends
+    /// a header to the server and starts the connection thread that waits
+    /// for responses.
+    pub fn setup_iostreams(self: &Arc<Self>, fallback_to_simple_auth: Option<Arc<Atomic<bool>>>) {
+        let mut ipc_streams = self.ipc_streams.lock().unwrap();
+        if ipc_streams.is_some() || self.should_close() {
+            return;
+        }
+        log::debug!("Connecting to {}", self.server);
+        let result = self
+            .setup_iostreams_internal(fallback_to_simple_auth)
+            .and_then(|mut streams| {
+                // start the receiver thread after the socket connection
+                // has been set up
+                let mut reader = streams.split_reader()?;
+                let input = reader.take_input_stream();
+                reader.set_input_stream(Box::new(PingInputStream {
+                    inner: input,
+                    connection: Arc::clone(self),
+                }));
+                let connection = Arc::clone(self);
+                thread::Builder::new()
+                    .name(self.get_name())
+                    .spawn(move || connection.run(reader))?;
+                Ok(streams)
+            });
+        match result {
+            Ok(streams) => *ipc_streams = Some(streams),
+            Err(e) => {
+                drop(ipc_streams);
+                self.mark_closed(e);
+                self.close();
+            }
+        }
+    }
+
+    fn setup_iostreams_internal(
+        &self,
+        fallback_to_simple_auth: Option<Arc<Atomic<bool>>>,
+    ) -> anyhow::Result<IpcStreams> {
+        let ticket = self.remote_id.get_ticket();
+        // try SASL if security is enabled or if the ugi contains tokens.
+        // this causes a SIMPLE client with tokens to attempt SASL
+        let try_sasl =
+            UserGroupInformation::is_security_enabled()? || !ticket.get_tokens().is_empty();
+        let auth_protocol = if try_sasl {
+            AuthProtocol::Sasl
+        } else {
+            AuthProtocol::None
+        };
+
+        let mut num_retries = 0;
+        loop {
+            let mut ipc_streams =
+                IpcStreams::new(self.setup_connection()?, self.max_response_length)?;
+            self.write_connection_header(&mut ipc_streams, &auth_protocol)?;
+            let mut auth_method = AuthMethod::Simple;
+            if auth_protocol == AuthProtocol::Sasl {
+                // Do not use Client.conf here! We must use ConnectionId.conf,
+                // since the Client object is cached and shared between all RPC
+                // clients, even those for separate services.
+                let mut sasl_rpc_client = SaslRpcClient::new(
+                    ticket,
+                    self.remote_id.get_protocol(),
+                    &self.server,
+                    self.remote_id.get_conf(),
+                );
+                match sasl_rpc_client.sasl_connect(&mut ipc_streams) {
+                    Ok(method) => auth_method = method,
+                    Err(e) => {
+                        let _ = ipc_streams.socket.shutdown(Shutdown::Both);
+                        self.handle_sasl_connection_failure(
+                            num_retries,
+                            self.max_retries_on_sasl,
+                            &sasl_rpc_client.get_auth_method(),
+                            e,
+                        )?;
+                        num_retries += 1;
+                        continue;
+                    }
+                }
+                if auth_method != AuthMethod::Simple {
+                    // Sasl connect is successful. Let's set up Sasl i/o streams.
+                    ipc_streams.set_sasl_client(&sasl_rpc_client)?;
+                    if let Some(fallback_to_simple_auth) = &fallback_to_simple_auth {
+                        fallback_to_simple_auth.store(false, Ordering::SeqCst);
+                    }
+                } else if UserGroupInformation::is_security_enabled()? {
+                    if !self.fallback_allowed {
+                        return Err(Error::new(IOException::AccessControl(
+                            "Server asks us to fall back to SIMPLE auth, but this client is \
+                            configured to only allow secure connections."
+                                .to_owned(),
+                        )));
+                    }
+                    if let Some(fallback_to_simple_auth) = &fallback_to_simple_auth {
+                        fallback_to_simple_auth.store(true, Ordering::SeqCst);
+                    }
+                }
+            }
+            self.write_connection_context(&mut ipc_streams, &auth_method)?;
+            let _ = self.socket.set(ipc_streams.socket.try_clone()?);
+            // update last activity time
+            self.touch();
+            return Ok(ipc_streams);
+        }
+    }
+
+    /// Whether the connection authenticates with the Kerberos credentials
+    /// of the login user, which can be renewed.
+    fn should_authenticate_over_krb(&self, auth_method: &AuthMethod) -> anyhow::Result<bool> {
+        if *auth_method != AuthMethod::Kerberos {
+            return Ok(false);
+        }
+        let login_user = UserGroupInformation::get_login_user()?;
+        // TODO: compare with the real user of proxy users
+        Ok(login_user.has_kerberos_credentials() && login_user == *self.remote_id.get_ticket())
+    }
+
+    /// If multiple clients with the same principal try to connect to the same
+    /// server at the same time, the server assumes a replay attack is in
+    /// progress. This is a feature of kerberos. In order to work around this,
+    /// what is done is that the client backs off randomly and tries to initiate
+    /// the connection again. The other problem is to do with ticket expiry. To
+    /// handle that, a relogin is attempted.
+    ///
+    /// Returns `Ok` if the connection should be retried, else the error to
+    /// report.
+    fn handle_sasl_connection_failure(
+        &self,
+        cur_retries: i32,
+        max_retries: i32,
+        auth_method: &AuthMethod,
+        e: Error,
+    ) -> anyhow::Result<()> {
+        if self.should_authenticate_over_krb(auth_method)? {
+            if cur_retries < max_retries {
+                log::debug!(
+                    "Exception encountered while connecting to the server: {:#}",
+                    e
+                );
+                // try re-login
+                UserGroupInformation::get_login_user()?.relogin_from_keytab()?;
+                // have granularity of milliseconds
+                // we are sleeping with the Connection lock held but since this
+                // connection instance is being used for connecting to the server
+                // in question, it is okay
+                let backoff = rand::thread_rng().gen_range(1..=MAX_SASL_BACKOFF_MILLIS);
+                thread::sleep(Duration::from_millis(backoff));
+                return Ok(());
+            }
+        } else if e.downcast_ref::<RemoteException>().is_some() {
+            return Err(e);
+        }
+        let msg = format!(
+            "Couldn't setup connection for {} to {}",
+            self.remote_id.get_ticket().get_user_name(),
+            self.server
+        );
+        log::warn!("{}: {:#}", msg, e);
+        Err(e.context(msg))
+    }
+
     /// Write the connection header - this is sent when connection is established
     /// +----------------------------------+
     /// |  "hrpc" 4 bytes                  |
@@ -119,25 +512,29 @@ impl<'a, T: RpcProtocol> Connection<'a, T> {
     /// +----------------------------------+
     /// |  AuthProtocol (1 byte)           |
     /// +----------------------------------+
-    fn write_connection_header(&mut self) -> anyhow::Result<()> {
-        self.ipc_streams.send_request(RpcConstants::HEADER)?;
-        self.ipc_streams.send_request(&[
+    fn write_connection_header(
+        &self,
+        ipc_streams: &mut IpcStreams,
+        auth_protocol: &AuthProtocol,
+    ) -> anyhow::Result<()> {
+        ipc_streams.send_request(RpcConstants::HEADER)?;
+        ipc_streams.send_request(&[
             RpcConstants::CURRENT_VERSION,
             self.service_class,
-            self.auth_protocol.call_id() as u8,
+            auth_protocol.call_id() as u8,
         ])?;
         Ok(())
     }
 
     /// Write the connection context header for each connection
     fn write_connection_context(
-        &mut self,
-        remote_id: Rc<ConnectionId>,
-        auth_method: &str,
+        &self,
+        ipc_streams: &mut IpcStreams,
+        auth_method: &AuthMethod,
     ) -> anyhow::Result<()> {
         let message = ProtoUtil::make_ipc_connection_context(
-            Some(RPC::get_protocol_name::<T>()),
-            Some(remote_id.get_ticket()),
+            Some(self.remote_id.get_protocol().protocol_name),
+            Some(self.remote_id.get_ticket()),
             auth_method,
         );
         let connection_context_header = ProtoUtil::make_rpc_request_header(
@@ -145,41 +542,255 @@ impl<'a, T: RpcProtocol> Connection<'a, T> {
             OperationProto::RpcFinalPacket,
             RpcConstants::CONNECTION_CONTEXT_CALL_ID,
             RpcConstants::INVALID_RETRY_COUNT,
-            &self.client.client_id,
+            &self.client_id,
             None,
         );
         let mut buf = connection_context_header.encode_length_delimited_to_vec();
         buf.append(&mut message.encode_length_delimited_to_vec());
         let out = prepend_buf_size(buf);
-        self.ipc_streams.send_request(&out)?;
+        ipc_streams.send_request(&out)?;
         Ok(())
     }
 
+    /// Wait till someone signals us to start reading RPC response or it is
+    /// idle too long, it is marked as to be closed, or the client is marked
+    /// as not running.
+    ///
+    /// Return true if it is time to read a response; false otherwise.
+    fn wait_for_work(&self) -> bool {
+        let mut state = self.state.lock().unwrap();
+        loop {
+            if state.should_close_connection {
+                return false;
+            }
+            if !state.calls.is_empty() {
+                return true;
+            }
+            let Some(idle_time_left) = self.get_idle_time_left(&state) else {
+                // idle connection closed
+                state.should_close_connection = true;
+                return false;
+            };
+            state = self.work.wait_timeout(state, idle_time_left).unwrap().0;
+        }
+    }
+
+    /// Return the time left before the connection has been idle for
+    /// maxIdleTime, or `None` if it has been already.
+    fn get_idle_time_left(&self, state: &ConnectionState) -> Option<Duration> {
+        let max_idle_time = Duration::from_millis(self.max_idle_time.max(0) as u64);
+        max_idle_time
+            .checked_sub(state.last_activity.elapsed())
+            .filter(|left| !left.is_zero())
+    }
+
+    /// Move the last I/O activity `elapsed` back in time, closing the
+    /// connection at once if it has no calls and has now been idle for
+    /// maxIdleTime.
+    #[cfg(test)]
+    pub fn advance_idle_time(&self, elapsed: Duration) {
+        let mut state = self.state.lock().unwrap();
+        state.last_activity -= elapsed;
+        if state.calls.is_empty() && self.get_idle_time_left(&state).is_none() {
+            state.should_close_connection = true;
+        }
+        self.work.notify_all();
+    }
+
+    fn run(self: Arc<Self>, mut ipc_streams: IpcStreams) {
+        log::debug!("{}: starting", self.get_name());
+        while self.wait_for_work() {
+            // wait here for work - read or close connection
+            if let Err(e) = self.receive_rpc_response(&mut ipc_streams) {
+                self.mark_closed(e);
+            }
+        }
+        self.close();
+        log::debug!("{}: stopped", self.get_name());
+    }
+
     /// Initiates a rpc call by sending the rpc request to the remote server.
-    /// Note: this is not called from the current thread, but by another
-    /// thread, so that if the current thread is interrupted that the socket
-    /// state isn't corrupted with a partially written message.
-    pub fn send_rpc_request(&mut self, call: Rc<Call>) -> anyhow::Result<()> {
+    /// Requests of concurrent calls are written one at a time, the responses
+    /// are read by the connection thread. If the request cannot be sent, the
+    /// connection is closed and the call fails with the cause.
+    pub fn send_rpc_request(&self, call: &Call) {
         let header = ProtoUtil::make_rpc_request_header(
             &call.rpc_kind,
             OperationProto::RpcFinalPacket,
             call.id,
             call.retry,
-            &self.client.client_id,
-            call.alignment_context.as_ref().map(Rc::clone),
+            &self.client_id,
+            call.alignment_context.as_ref().map(Arc::clone),
         );
         let mut buf = header.encode_length_delimited_to_vec();
         buf.append(&mut call.rpc_request.as_ref().to_owned());
         let out = prepend_buf_size(buf);
-        // TODO: rpc request queue
-        self.ipc_streams.send_request(&out)?;
+
+        let mut ipc_streams = self.ipc_streams.lock().unwrap();
+        if self.should_close() {
+            // the call is failed when the connection is closed
+            return;
+        }
+        log::debug!("{} sending #{}", self.get_name(), call.id);
+        let result = match ipc_streams.as_mut() {
+            Some(ipc_streams) => ipc_streams.send_request(&out).map(|_| ()),
+            None => Err(Error::msg(format!(
+                "Connection to {} is not set up",
+                self.server
+            ))),
+        };
+        drop(ipc_streams);
+        if let Err(e) = result {
+            self.mark_closed(e);
+        }
+    }
+
+    /// Receive a response.
+    /// Because only one receiver, so no synchronization on in.
+    fn receive_rpc_response(&self, ipc_streams: &mut IpcStreams) -> anyhow::Result<()> {
+        let response = ipc_streams.read_response()?;
+        self.touch();
+        let mut value = &response[..];
+        let header = RpcResponseHeaderProto::decode_length_delimited(&mut value)?;
+        // get call id
+        let call_id = header.call_id as i32;
+        log::debug!("{} got value #{}", self.get_name(), call_id);
+
+        let status = header.status();
+        if status == RpcStatusProto::Success {
+            if let Some(call) = self.remove_call(call_id) {
+                call.set_rpc_response(value.to_vec());
+            }
+        } else {
+            // Rpc Request failed
+            let re = RemoteException::from(&header);
+            if status == RpcStatusProto::Error {
+                if let Some(call) = self.remove_call(call_id) {
+                    call.set_exception(re.into());
+                }
+            } else if status == RpcStatusProto::Fatal {
+                // Close the connection
+                return Err(re.into());
+            }
+        }
         Ok(())
     }
+
+    fn remove_call(&self, call_id: i32) -> Option<Arc<Call>> {
+        let call = self.state.lock().unwrap().calls.remove(&call_id);
+        if call.is_none() {
+            log::debug!(
+                "{} got a response for unknown call #{}",
+                self.get_name(),
+                call_id
+            );
+        }
+        call
+    }
+
+    /// Mark the connection to be closed, failing the active calls with `e`.
+    pub fn mark_closed(&self, e: Error) {
+        let mut state = self.state.lock().unwrap();
+        if !state.should_close_connection {
+            state.should_close_connection = true;
+            state.close_exception = Some(e);
+        }
+        self.work.notify_all();
+        drop(state);
+        // wake up the connection thread blocked on reading the socket
+        if let Some(socket) = self.socket.get() {
+            let _ = socket.shutdown(Shutdown::Both);
+        }
+    }
+
+    /// Close the connection.
+    fn close(&self) {
+        if !self.should_close() {
+            log::error!("The connection is not in the closed state");
+            return;
+        }
+
+        // release the resources
+        // first thing to do;take the connection out of the connection list
+        if let Some(connections) = self.connections.upgrade() {
+            let mut connections = connections.lock().unwrap();
+            if connections
+                .get(&self.remote_id)
+                .is_some_and(|c| std::ptr::eq(c.as_ref(), self))
+            {
+                connections.remove(&self.remote_id);
+            }
+        }
+        if let Some(ipc_streams) = self.ipc_streams.lock().unwrap().take() {
+            log::debug!("closing ipc connection to {}", self.server);
+            ipc_streams.close();
+        }
+
+        // clean up all calls
+        let mut state = self.state.lock().unwrap();
+        let calls = std::mem::take(&mut state.calls);
+        match &state.close_exception {
+            None => {
+                if !calls.is_empty() {
+                    log::warn!("A connection is closed for no cause and calls are not empty");
+                }
+                for call in calls.into_values() {
+                    call.set_exception(Error::msg("Unexpected closed connection"));
+                }
+            }
+            Some(e) => {
+                // log the info
+                log::debug!("closing ipc connection to {}: {}", self.server, e);
+                for call in calls.into_values() {
+                    call.set_exception(copy_exception(e));
+                }
+            }
+        }
+    }
 }
 
-// TODO: consider implementing ResponseBuffer
-fn prepend_buf_size(mut buf: Vec<u8>) -> Vec<u8> {
-    let mut out = (buf.len() as i32).to_be_bytes().to_vec();
-    out.append(&mut buf);
-    out
+/// This class sends a ping to the remote side when timeout on
+/// reading. If no failure is detected, it retries until at least
+/// a byte is read.
+struct PingInputStream {
+    inner: Box<dyn Read + Send>,
+    connection: Arc<Connection>,
+}
+
+impl Read for PingInputStream {
+    /// Read bytes into a buffer starting from offset off
+    /// Send a ping if timeout on read. Retries if no failure is detected
+    /// until a byte is read.
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut waiting = 0;
+        loop {
+            match self.inner.read(buf) {
+                Err(e)
+                    if matches!(
+                        e.kind(),
+                        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                    ) =>
+                {
+                    waiting += self.connection.so_timeout;
+                    self.connection.handle_timeout(waiting)?;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+/// Copy the reason a connection is closed for each of its calls, keeping the
+/// exceptions callers tell apart.
+fn copy_exception(e: &Error) -> Error {
+    if let Some(re) = e.downcast_ref::<RemoteException>() {
+        re.to_owned().into()
+    } else if let Some(ioe) = e.downcast_ref::<IOException>() {
+        Error::new(ioe.to_owned())
+    } else if let Some(ioe) = e.downcast_ref::<io::Error>() {
+        // keep the kind of the error, retry policies tell network errors apart
+        Error::new(io::Error::new(ioe.kind(), format!("{:#}", e)))
+    } else {
+        Error::msg(format!("{:#}", e))
+    }
 }
@@ -2,21 +2,34 @@ use super::Client;
 use crate::common::{
     conf::Configuration,
     fs::{common_configuration_keys, common_configuration_keys_public},
-    io::retry::RetryPolicy,
+    io::retry::{RetryPolicies, RetryPolicy},
+    ipc::ProtocolInfo,
     security::UserGroupInformation,
 };
 use anyhow::Error;
-use std::{net::SocketAddr, rc::Rc};
+use std::{
+    collections::BTreeMap,
+    hash::{Hash, Hasher},
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+};
+
+/// The default connection retry policies by maximum retries and retry
+/// interval. Retry policies are compared by identity, so the ids of the
+/// connections using the same default policy must share it to be equal.
+static DEFAULT_RETRY_POLICIES: Mutex<BTreeMap<(i32, i32), Arc<dyn RetryPolicy>>> =
+    Mutex::new(BTreeMap::new());
 
 /// This class holds the address and the user ticket. The client connections
 /// to servers are uniquely identified by `<remoteAddress, protocol, ticket>`
 pub struct ConnectionId {
     address: SocketAddr,
+    protocol: &'static ProtocolInfo,
     ticket: UserGroupInformation,
     rpc_timeout: i32,
     // connections will be culled if it was idle for maxIdleTime msecs
     max_idle_time: i32,
-    connection_retry_policy: Option<Rc<dyn RetryPolicy>>,
+    connection_retry_policy: Arc<dyn RetryPolicy>,
     max_retries_on_sasl: i32,
     // the max. no. of retries for socket connections on time out exceptions
     max_retries_on_socket_timeouts: i32,
@@ -29,15 +42,16 @@ pub struct ConnectionId {
     // how often sends ping to the server in msecs
     ping_interval: i32,
     // used to get the expected kerberos principal name
-    _conf: Configuration,
+    conf: Configuration,
 }
 
 impl ConnectionId {
     fn new(
         address: &SocketAddr,
+        protocol: &'static ProtocolInfo,
         ticket: &UserGroupInformation,
         rpc_timeout: i32,
-        connection_retry_policy: Option<Rc<dyn RetryPolicy>>,
+        connection_retry_policy: Arc<dyn RetryPolicy>,
         conf: &Configuration,
     ) -> anyhow::Result<Self> {
         let max_idle_time = conf.get_int(
@@ -66,6 +80,7 @@ impl ConnectionId {
         );
         Ok(Self {
             address: address.to_owned(),
+            protocol,
             ticket: ticket.to_owned(),
             rpc_timeout,
             max_idle_time,
@@ -80,7 +95,7 @@ impl ConnectionId {
             } else {
                 0
             },
-            _conf: conf.to_owned(),
+            conf: conf.to_owned(),
         })
     }
 
@@ -101,6 +116,10 @@ impl ConnectionId {
         Ok(())
     }
 
+    pub fn get_protocol(&self) -> &'static ProtocolInfo {
+        self.protocol
+    }
+
     pub fn get_ticket(&self) -> &UserGroupInformation {
         &self.ticket
     }
@@ -137,20 +156,89 @@ impl ConnectionId {
         self.ping_interval
     }
 
-    pub fn get_retry_policy(&self) -> Option<Rc<dyn RetryPolicy>> {
-        self.connection_retry_policy.as_ref().map(Rc::clone)
+    pub fn get_conf(&self) -> &Configuration {
+        &self.conf
+    }
+
+    pub fn get_retry_policy(&self) -> Arc<dyn RetryPolicy> {
+        Arc::clone(&self.connection_retry_policy)
     }
 
     /// Returns a ConnectionId object.
     pub fn get_connection_id(
         addr: &SocketAddr,
+        protocol: &'static ProtocolInfo,
         ticket: &UserGroupInformation,
         rpc_timeout: i32,
-        connection_retry_policy: Option<Rc<dyn RetryPolicy>>,
+        connection_retry_policy: Option<Arc<dyn RetryPolicy>>,
         conf: &Configuration,
     ) -> anyhow::Result<Self> {
-        // TODO: set connection_retry_policy if not yet
+        let connection_retry_policy = match connection_retry_policy {
+            Some(policy) => policy,
+            None => {
+                let max = conf.get_int(
+                    common_configuration_keys_public::IPC_CLIENT_CONNECT_MAX_RETRIES_KEY,
+                    common_configuration_keys_public::IPC_CLIENT_CONNECT_MAX_RETRIES_DEFAULT,
+                )?;
+                let retry_interval = conf.get_int(
+                    common_configuration_keys_public::IPC_CLIENT_CONNECT_RETRY_INTERVAL_KEY,
+                    common_configuration_keys_public::IPC_CLIENT_CONNECT_RETRY_INTERVAL_DEFAULT,
+                )?;
+                let mut policies = DEFAULT_RETRY_POLICIES.lock().unwrap();
+                Arc::clone(policies.entry((max, retry_interval)).or_insert_with(|| {
+                    RetryPolicies::retry_up_to_maximum_count_with_fixed_sleep(
+                        max,
+                        retry_interval as i64,
+                    )
+                }))
+            }
+        };
+        Self::new(
+            addr,
+            protocol,
+            ticket,
+            rpc_timeout,
+            connection_retry_policy,
+            conf,
+        )
+    }
+
+    /// The address of the retry policy, retry policies are compared by
+    /// identity.
+    fn retry_policy_ptr(&self) -> *const () {
+        Arc::as_ptr(&self.connection_retry_policy) as *const ()
+    }
+}
+
+impl PartialEq for ConnectionId {
+    fn eq(&self, other: &Self) -> bool {
+        self.address == other.address
+            && self.protocol.protocol_name == other.protocol.protocol_name
+            && self.ticket == other.ticket
+            && self.rpc_timeout == other.rpc_timeout
+            && self.max_idle_time == other.max_idle_time
+            && self.retry_policy_ptr() == other.retry_policy_ptr()
+            && self.max_retries_on_sasl == other.max_retries_on_sasl
+            && self.max_retries_on_socket_timeouts == other.max_retries_on_socket_timeouts
+            && self.tcp_no_delay == other.tcp_no_delay
+            && self.tcp_low_latency == other.tcp_low_latency
+            && self.do_ping == other.do_ping
+            && self.ping_interval == other.ping_interval
+    }
+}
 
-        Self::new(addr, ticket, rpc_timeout, connection_retry_policy, conf)
+impl Eq for ConnectionId {}
+
+impl Hash for ConnectionId {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.retry_policy_ptr().hash(state);
+        self.address.hash(state);
+        self.do_ping.hash(state);
+        self.max_idle_time.hash(state);
+        self.ping_interval.hash(state);
+        self.protocol.protocol_name.hash(state);
+        self.rpc_timeout.hash(state);
+        self.ticket.hash(state);
+        self.tcp_no_delay.hash(state);
     }
 }
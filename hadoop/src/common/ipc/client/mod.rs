@@ -2,63 +2,125 @@ mod call;
 mod connection;
 mod connection_id;
 
-use super::{client_id::BYTE_LENGTH, AlignmentContext, ClientId, RpcKind, RpcProtocol};
-use crate::common::{conf::Configuration, fs::common_configuration_keys};
+use super::{
+    client_id::BYTE_LENGTH, AlignmentContext, ClientId, RemoteException, RpcConstants, RpcKind,
+};
+use crate::common::{
+    conf::Configuration,
+    fs::common_configuration_keys,
+    io::{IOException, WritableUtils},
+    security::SaslRpcClient,
+};
 use anyhow::Error;
 use atomic::Atomic;
 use call::Call;
 use connection::Connection;
 pub use connection_id::ConnectionId;
+#[cfg(feature = "async")]
+use std::sync::atomic::AtomicI32;
 use std::{
     cell::RefCell,
-    io::{Read, Write},
-    net::TcpStream,
-    rc::Rc,
-    sync::{atomic::Ordering, Arc},
+    collections::HashMap,
+    io::{self, Read, Write},
+    mem,
+    net::{Shutdown, TcpStream},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
 };
 
 /// A counter for generating call IDs.
 static CALL_ID_COUNTER: Atomic<i32> = Atomic::new(0);
 
 thread_local! {
-    static CALL_ID: RefCell<Option<i32>> = RefCell::new(None);
-    static RETRY_COUNT: RefCell<Option<i32>> = RefCell::new(None);
-    static EXTERNAL_CALL_HANDLER: RefCell<Option<String>> = RefCell::new(None);
+    static CALL_ID: RefCell<Option<i32>> = const { RefCell::new(None) };
+    static RETRY_COUNT: RefCell<Option<i32>> = const { RefCell::new(None) };
+    static EXTERNAL_CALL_HANDLER: RefCell<Option<String>> = const { RefCell::new(None) };
 }
 
+/// The connections of a client, keyed by the id of their remote.
+type ConnectionPool = Mutex<HashMap<Arc<ConnectionId>, Arc<Connection>>>;
+
 /// A client for an IPC service.  IPC calls take a single [`Writable`] as a
 /// parameter, and return a [`Writable`] as their value.  A service runs on
 /// a port and is defined by a parameter class and a value class.
 pub struct Client {
+    // the connections to the servers, connections to a given ConnectionId
+    // are reused
+    connections: Arc<ConnectionPool>,
     _value_class: String,
     _conf: Configuration,
-    _connection_timeout: i32,
-    _fallback_allowed: bool,
-    _bind_to_wild_card_address: bool,
+    connection_timeout: i32,
+    fallback_allowed: bool,
+    bind_to_wild_card_address: bool,
     client_id: [u8; BYTE_LENGTH],
-    _max_async_calls: i32,
+    // false once the client is stopped, no calls are accepted then
+    running: AtomicBool,
+    // the maximum number of outstanding asynchronous calls
+    #[cfg(feature = "async")]
+    max_async_calls: i32,
+    #[cfg(feature = "async")]
+    async_call_counter: AtomicI32,
 }
 
 impl Client {
-    pub fn get_timeout(_conf: &Configuration) -> i32 {
-        // TODO
-        3000
+    /// The time after which a RPC will timeout.
+    /// If ping is not enabled (via ipc.client.ping), then the timeout value
+    /// is the same as the pingInterval.
+    /// If ping is enabled, then there is no timeout value, -1 is returned,
+    /// unless a positive rpc timeout is configured.
+    pub fn get_timeout(conf: &Configuration) -> anyhow::Result<i32> {
+        let timeout = Self::get_rpc_timeout(conf)?;
+        if timeout > 0 {
+            return Ok(timeout);
+        }
+        if !conf.get_bool(
+            common_configuration_keys::IPC_CLIENT_PING_KEY,
+            common_configuration_keys::IPC_CLIENT_PING_DEFAULT,
+        ) {
+            return Self::get_ping_interval(conf);
+        }
+        Ok(-1)
+    }
+
+    /// The time after which a RPC will timeout, 0 if there is no timeout.
+    pub fn get_rpc_timeout(conf: &Configuration) -> anyhow::Result<i32> {
+        let timeout = conf.get_int(
+            common_configuration_keys::IPC_CLIENT_RPC_TIMEOUT_KEY,
+            common_configuration_keys::IPC_CLIENT_RPC_TIMEOUT_DEFAULT,
+        )?;
+        Ok(timeout.max(0))
     }
 
     fn _get_call_id() -> anyhow::Result<i32> {
         Ok(CALL_ID
             .try_with(|x| *x.borrow())?
-            .unwrap_or_else(|| Self::next_call_id()))
+            .unwrap_or_else(Self::next_call_id))
     }
 
     fn take_call_id() -> anyhow::Result<i32> {
         Ok(CALL_ID
             .try_with(|x| x.take())?
-            .unwrap_or_else(|| Self::next_call_id()))
+            .unwrap_or_else(Self::next_call_id))
     }
 
-    fn get_retry_count() -> anyhow::Result<i32> {
-        Ok(RETRY_COUNT.try_with(|x| *x.borrow())?.unwrap_or_default())
+    fn take_retry_count() -> anyhow::Result<i32> {
+        Ok(RETRY_COUNT.try_with(|x| x.take())?.unwrap_or_default())
+    }
+
+    /// Set call id and retry count for the next call made by this thread, so
+    /// that a retried call reuses the id of the original one.
+    pub fn set_call_id_and_retry_count(cid: i32, rc: i32) -> anyhow::Result<()> {
+        if cid < 0 {
+            return Err(Error::msg(format!("Invalid call id: {}", cid)));
+        }
+        if rc == RpcConstants::INVALID_RETRY_COUNT {
+            return Err(Error::msg(format!("Invalid retry count: {}", rc)));
+        }
+        CALL_ID.try_with(|x| x.replace(Some(cid)))?;
+        RETRY_COUNT.try_with(|x| x.replace(Some(rc)))?;
+        Ok(())
     }
 
     fn get_external_handler() -> anyhow::Result<Option<String>> {
@@ -74,7 +136,7 @@ impl Client {
         )
     }
 
-    fn create_call(&self, rpc_kind: &RpcKind, rpc_request: Rc<Vec<u8>>) -> anyhow::Result<Call> {
+    fn create_call(&self, rpc_kind: &RpcKind, rpc_request: Arc<Vec<u8>>) -> anyhow::Result<Call> {
         Call::new(rpc_kind, rpc_request)
     }
 
@@ -91,81 +153,194 @@ impl Client {
             common_configuration_keys::IPC_CLIENT_BIND_WILDCARD_ADDR_KEY,
             common_configuration_keys::IPC_CLIENT_BIND_WILDCARD_ADDR_DEFAULT,
         );
+        #[cfg(feature = "async")]
         let max_async_calls = conf.get_int(
             common_configuration_keys::IPC_CLIENT_ASYNC_CALLS_MAX_KEY,
             common_configuration_keys::IPC_CLIENT_ASYNC_CALLS_MAX_DEFAULT,
         )?;
         Ok(Self {
+            connections: Default::default(),
             _value_class: value_class.to_owned(),
             _conf: conf.to_owned(),
-            _connection_timeout: connection_timeout,
-            _fallback_allowed: fallback_allowed,
-            _bind_to_wild_card_address: bind_to_wild_card_address,
+            connection_timeout,
+            fallback_allowed,
+            bind_to_wild_card_address,
             client_id: ClientId::get_client_id(),
-            _max_async_calls: max_async_calls,
+            running: AtomicBool::new(true),
+            #[cfg(feature = "async")]
+            max_async_calls,
+            #[cfg(feature = "async")]
+            async_call_counter: AtomicI32::new(0),
         })
     }
 
+    /// Stop all threads related to this client.  No further calls may be made
+    /// using this client.
+    pub fn stop(&self) {
+        log::debug!("Stopping client");
+        let connections: Vec<_> = {
+            let mut connections = self.connections.lock().unwrap();
+            self.running.store(false, Ordering::SeqCst);
+            connections.drain().collect()
+        };
+        // wake up all connections
+        for (_, connection) in connections {
+            connection.mark_closed(Error::msg("The client is stopped"));
+        }
+    }
+
     /// Make a call, passing `rpc_request`, to the IPC server defined by
     /// `remote_id`, returning the rpc response.
-    pub fn call<T: RpcProtocol>(
+    ///
+    /// Calls from different threads to the same `remote_id` share one
+    /// connection, their responses are dispatched by call id.  Calls fail
+    /// once the client is stopped.
+    pub fn call(
         &self,
         rpc_kind: &RpcKind,
-        rpc_request: Rc<Vec<u8>>,
-        remote_id: Rc<ConnectionId>,
+        rpc_request: Arc<Vec<u8>>,
+        remote_id: Arc<ConnectionId>,
         service_class: u8,
         fallback_to_simple_auth: Option<Arc<Atomic<bool>>>,
-        alignment_context: Option<Rc<dyn AlignmentContext>>,
+        alignment_context: Option<Arc<dyn AlignmentContext>>,
     ) -> anyhow::Result<Vec<u8>> {
         // TODO: return Writable
 
         let mut call = self.create_call(rpc_kind, rpc_request)?;
         call.set_alignment_context(alignment_context);
-        let call = Rc::new(call);
+        let call = Arc::new(call);
 
-        let mut connection = self.get_connection::<T>(
+        let connection = self.get_connection(
             remote_id,
-            Rc::clone(&call),
+            Arc::clone(&call),
             service_class,
             fallback_to_simple_auth,
         )?;
 
-        connection.send_rpc_request(Rc::clone(&call))?;
+        connection.send_rpc_request(&call);
+
+        call.get_rpc_response()
+    }
+
+    /// Make an asynchronous call, passing `rpc_request`, to the IPC server
+    /// defined by `remote_id`, the returned future resolves to the rpc
+    /// response.
+    ///
+    /// At most `ipc.client.async.calls.max` asynchronous calls may be
+    /// outstanding at any time, further calls fail with an
+    /// AsyncCallLimitExceededException.  A new connection is set up on the
+    /// blocking thread pool of the runtime, which must be a tokio runtime.
+    #[cfg(feature = "async")]
+    pub async fn call_async(
+        &self,
+        rpc_kind: &RpcKind,
+        rpc_request: Arc<Vec<u8>>,
+        remote_id: Arc<ConnectionId>,
+        service_class: u8,
+        fallback_to_simple_auth: Option<Arc<Atomic<bool>>>,
+        alignment_context: Option<Arc<dyn AlignmentContext>>,
+    ) -> anyhow::Result<Vec<u8>> {
+        let _async_call = self.check_async_call()?;
+
+        let mut call = self.create_call(rpc_kind, rpc_request)?;
+        call.set_alignment_context(alignment_context);
+        let response = call.set_async_response();
+        let call = Arc::new(call);
+
+        // connecting retries with sleeps and may negotiate SASL, so keep it
+        // off the threads driving the futures
+        let connection =
+            self.add_call_to_connection(remote_id, Arc::clone(&call), service_class)?;
+        let connection = tokio::task::spawn_blocking(move || {
+            connection.setup_iostreams(fallback_to_simple_auth);
+            connection
+        })
+        .await?;
 
-        // TODO: support asynchronous mode
+        connection.send_rpc_request(&call);
 
-        let res: Vec<u8> = self.get_rpc_response(Rc::clone(&call), &mut connection, 0)?;
-        Ok(res)
+        response
+            .await
+            .map_err(|_| Error::msg(format!("Call {} was dropped before it was done", call.id)))?
+    }
+
+    /// Check if the number of outstanding asynchronous calls is below the
+    /// limit, the returned guard releases the call when it is dropped.
+    #[cfg(feature = "async")]
+    fn check_async_call(&self) -> anyhow::Result<AsyncCallGuard<'_>> {
+        let count = self.async_call_counter.fetch_add(1, Ordering::SeqCst) + 1;
+        let guard = AsyncCallGuard(&self.async_call_counter);
+        if count > self.max_async_calls {
+            return Err(Error::new(IOException::AsyncCallLimitExceeded(format!(
+                "Exceeded limit of max asynchronous calls: {}, please configure {} to adjust it.",
+                self.max_async_calls,
+                common_configuration_keys::IPC_CLIENT_ASYNC_CALLS_MAX_KEY
+            ))));
+        }
+        Ok(guard)
     }
 
     /// Get a connection from the pool, or create a new one and add it to the
     /// pool.  Connections to a given ConnectionId are reused.
-    fn get_connection<'a, T: RpcProtocol>(
-        &'a self,
-        remote_id: Rc<ConnectionId>,
-        call: Rc<Call>,
+    fn get_connection(
+        &self,
+        remote_id: Arc<ConnectionId>,
+        call: Arc<Call>,
         service_class: u8,
         fallback_to_simple_auth: Option<Arc<Atomic<bool>>>,
-    ) -> anyhow::Result<Connection<'a, T>> {
-        // TODO: connection pool
-        let mut connection = Connection::new(self, remote_id, service_class)?;
-
-        connection.add_call(call);
+    ) -> anyhow::Result<Arc<Connection>> {
+        let connection = self.add_call_to_connection(remote_id, call, service_class)?;
 
         // If the server happens to be slow, the method below will take longer to
         // establish a connection.
-        connection.setup_iostreams(fallback_to_simple_auth)?;
+        connection.setup_iostreams(fallback_to_simple_auth);
         Ok(connection)
     }
 
-    fn get_rpc_response<T: RpcProtocol>(
+    /// Get the connection to `remote_id` from the pool, or create a new one
+    /// and add it to the pool, and add `call` to it.  The returned connection
+    /// may not be set up yet.
+    fn add_call_to_connection(
         &self,
-        _call: Rc<Call>,
-        connection: &mut Connection<T>,
-        _timeout: i64,
-    ) -> anyhow::Result<Vec<u8>> {
-        // TODO: get rpc response from call
-        Ok(connection.ipc_streams.read_response()?)
+        remote_id: Arc<ConnectionId>,
+        call: Arc<Call>,
+        service_class: u8,
+    ) -> anyhow::Result<Arc<Connection>> {
+        Ok(loop {
+            let mut connections = self.connections.lock().unwrap();
+            if !self.running.load(Ordering::SeqCst) {
+                // the client is stopped
+                return Err(Error::msg("The client is stopped"));
+            }
+            let connection = Arc::clone(connections.entry(Arc::clone(&remote_id)).or_insert_with(
+                || {
+                    Arc::new(Connection::new(
+                        &self.client_id,
+                        Arc::clone(&remote_id),
+                        service_class,
+                        self.connection_timeout,
+                        self.bind_to_wild_card_address,
+                        self.fallback_allowed,
+                        Arc::downgrade(&self.connections),
+                    ))
+                },
+            ));
+            drop(connections);
+            if connection.add_call(Arc::clone(&call)) {
+                break connection;
+            }
+            // This connection is closed, should be removed. But other thread
+            // could have already known this closedConnection, and replace it
+            // with a new connection. So we should call conditional remove to
+            // make sure we only remove this closedConnection.
+            let mut connections = self.connections.lock().unwrap();
+            if connections
+                .get(&remote_id)
+                .is_some_and(|c| Arc::ptr_eq(c, &connection))
+            {
+                connections.remove(&remote_id);
+            }
+        })
     }
 
     /// Returns the next valid sequential call ID by incrementing an atomic counter
@@ -174,39 +349,103 @@ impl Client {
     /// purposes.  The values can overflow back to 0 and be reused.  Note that prior
     /// versions of the client did not mask off the sign bit, so a server may still
     /// see a negative call ID if it receives connections from an old client.
-    fn next_call_id() -> i32 {
+    pub fn next_call_id() -> i32 {
         CALL_ID_COUNTER.fetch_add(1, Ordering::SeqCst) & 0x7FFFFFFF
     }
 }
 
-struct IpcStreams {
-    inner: TcpStream,
+/// Releases an outstanding asynchronous call when dropped.
+#[cfg(feature = "async")]
+struct AsyncCallGuard<'a>(&'a AtomicI32);
+
+#[cfg(feature = "async")]
+impl Drop for AsyncCallGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Manages the input and output streams for an IPC connection.
+pub(crate) struct IpcStreams {
+    socket: TcpStream,
+    input: Box<dyn Read + Send>,
+    output: Box<dyn Write + Send>,
     max_response_length: i32,
     first_response: bool,
 }
 
+impl Drop for Client {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
 impl IpcStreams {
-    fn new(inner: TcpStream, max_response_length: i32) -> Self {
-        IpcStreams {
-            inner,
+    fn new(socket: TcpStream, max_response_length: i32) -> anyhow::Result<Self> {
+        Ok(IpcStreams {
+            input: Box::new(socket.try_clone()?),
+            output: Box::new(socket.try_clone()?),
+            socket,
             max_response_length,
             first_response: true,
-        }
+        })
+    }
+
+    /// Create the streams of the reader of the responses over the same
+    /// socket, which take over the input stream.
+    fn split_reader(&mut self) -> anyhow::Result<Self> {
+        let socket = self.socket.try_clone()?;
+        Ok(IpcStreams {
+            input: self.take_input_stream(),
+            output: Box::new(socket.try_clone()?),
+            socket,
+            max_response_length: self.max_response_length,
+            first_response: self.first_response,
+        })
+    }
+
+    /// Wrap the streams with the security layer negotiated by the SASL
+    /// client, if any.
+    pub(crate) fn set_sasl_client(
+        &mut self,
+        sasl_rpc_client: &SaslRpcClient,
+    ) -> anyhow::Result<()> {
+        let input = self.take_input_stream();
+        self.set_input_stream(sasl_rpc_client.get_input_stream(input));
+        let output = mem::replace(&mut self.output, Box::new(io::sink()));
+        self.output = sasl_rpc_client.get_output_stream(output)?;
+        Ok(())
+    }
+
+    fn take_input_stream(&mut self) -> Box<dyn Read + Send> {
+        mem::replace(&mut self.input, Box::new(io::empty()))
+    }
+
+    fn set_input_stream(&mut self, input: Box<dyn Read + Send>) {
+        self.input = input;
     }
 
     fn read_i32(&mut self) -> anyhow::Result<i32> {
         let mut buf = [0; 4];
-        self.inner.read_exact(&mut buf)?;
+        self.input.read_exact(&mut buf).map_err(unwrap_io_error)?;
         Ok(i32::from_be_bytes(buf))
     }
 
-    fn read_response(&mut self) -> anyhow::Result<Vec<u8>> {
+    pub(crate) fn read_response(&mut self) -> anyhow::Result<Vec<u8>> {
         let length = self.read_i32()?;
         if self.first_response {
             self.first_response = false;
+            // pre-rpcv9 exception, almost certainly a version mismatch.
             if length == -1 {
+                // ignore fatal/error status, it's fatal for certain.
                 self.read_i32()?;
-                return Err(Error::msg("Remote Exception"));
+                let class_name = WritableUtils::read_string(&mut self.input)?;
+                let msg = WritableUtils::read_string(&mut self.input)?;
+                return Err(RemoteException::new(
+                    class_name.as_deref().unwrap_or_default(),
+                    msg.as_deref().unwrap_or_default(),
+                )
+                .into());
             }
         }
         if length <= 0 {
@@ -216,15 +455,560 @@ impl IpcStreams {
             return Err(Error::msg("RPC response exceeds maximum data length"));
         }
         let mut buf = vec![0; length as usize];
-        self.inner.read_exact(&mut buf)?;
+        self.input.read_exact(&mut buf).map_err(unwrap_io_error)?;
         Ok(buf)
     }
 
-    fn send_request(&mut self, buf: &[u8]) -> anyhow::Result<usize> {
-        Ok(self.inner.write(buf)?)
+    pub(crate) fn send_request(&mut self, buf: &[u8]) -> anyhow::Result<usize> {
+        self.output.write_all(buf).map_err(unwrap_io_error)?;
+        self.output.flush().map_err(unwrap_io_error)?;
+        Ok(buf.len())
     }
 
     fn _flush(&mut self) -> anyhow::Result<()> {
-        Ok(self.inner.flush()?)
+        Ok(self.output.flush()?)
+    }
+
+    fn close(self) {
+        // the socket may already be closed by the server
+        let _ = self.socket.shutdown(Shutdown::Both);
+    }
+}
+
+// TODO: consider implementing ResponseBuffer
+pub(crate) fn prepend_buf_size(mut buf: Vec<u8>) -> Vec<u8> {
+    let mut out = (buf.len() as i32).to_be_bytes().to_vec();
+    out.append(&mut buf);
+    out
+}
+
+/// Unwrap the [`IOException`] a stream of the connection failed with, if any.
+fn unwrap_io_error(e: io::Error) -> Error {
+    match e.get_ref().and_then(|e| e.downcast_ref::<IOException>()) {
+        Some(ioe) => Error::new(ioe.to_owned()),
+        None => Error::new(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::{
+        fs::common_configuration_keys_public::{
+            IPC_CLIENT_CONNECTION_MAXIDLETIME_KEY, IPC_CLIENT_CONNECT_MAX_RETRIES_KEY,
+            IPC_CLIENT_CONNECT_RETRY_INTERVAL_KEY,
+        },
+        ipc::{AuthProtocol, ProtocolInfo},
+        rpc_response_header_proto::RpcStatusProto,
+        rpc_sasl_proto::{SaslAuth, SaslState},
+        security::{token::Token, SaslRpcServer, SecurityUtil, UserGroupInformation},
+        RpcRequestHeaderProto, RpcResponseHeaderProto, RpcSaslProto,
+    };
+    use prost::Message;
+    use std::{
+        net::{SocketAddr, TcpListener},
+        sync::atomic::AtomicUsize,
+        thread,
+        time::{Duration, Instant},
+    };
+
+    static TEST_PROTOCOL: ProtocolInfo = ProtocolInfo {
+        protocol_name: "protocol",
+        protocol_version: 1,
+        token_kind: None,
+        server_principal: None,
+    };
+
+    static TOKEN_PROTOCOL: ProtocolInfo = ProtocolInfo {
+        protocol_name: "protocol",
+        protocol_version: 1,
+        token_kind: Some("TEST_TOKEN"),
+        server_principal: None,
+    };
+
+    fn read_frame(stream: &mut TcpStream) -> anyhow::Result<Vec<u8>> {
+        let mut len = [0; 4];
+        stream.read_exact(&mut len)?;
+        let mut frame = vec![0; i32::from_be_bytes(len) as usize];
+        stream.read_exact(&mut frame)?;
+        Ok(frame)
+    }
+
+    /// Start a server which echoes the payload of the requests, answering
+    /// every `batch` requests in the reverse order. Returns its address and
+    /// the number of accepted connections.
+    fn start_server(batch: usize) -> anyhow::Result<(SocketAddr, Arc<AtomicUsize>)> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let addr = listener.local_addr()?;
+        let accepted = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&accepted);
+        thread::spawn(move || {
+            for mut stream in listener.incoming().map_while(Result::ok) {
+                counter.fetch_add(1, Ordering::SeqCst);
+                thread::spawn(move || -> anyhow::Result<()> {
+                    // "hrpc", version, service class and auth protocol
+                    stream.read_exact(&mut [0; 7])?;
+                    // the connection context
+                    read_frame(&mut stream)?;
+                    loop {
+                        let mut requests = vec![];
+                        for _ in 0..batch {
+                            let frame = read_frame(&mut stream)?;
+                            let mut payload = &frame[..];
+                            let header =
+                                RpcRequestHeaderProto::decode_length_delimited(&mut payload)?;
+                            requests.push((header.call_id, payload.to_vec()));
+                        }
+                        for (call_id, payload) in requests.into_iter().rev() {
+                            let header = RpcResponseHeaderProto {
+                                call_id: call_id as u32,
+                                status: RpcStatusProto::Success as i32,
+                                ..Default::default()
+                            };
+                            let mut buf = header.encode_length_delimited_to_vec();
+                            buf.extend_from_slice(&payload);
+                            stream.write_all(&(buf.len() as i32).to_be_bytes())?;
+                            stream.write_all(&buf)?;
+                        }
+                    }
+                });
+            }
+        });
+        Ok((addr, accepted))
+    }
+
+    fn new_remote_id(addr: &SocketAddr, conf: &Configuration) -> anyhow::Result<Arc<ConnectionId>> {
+        let ticket = UserGroupInformation::get_current_user()?;
+        Ok(Arc::new(ConnectionId::get_connection_id(
+            addr,
+            &TEST_PROTOCOL,
+            &ticket,
+            0,
+            None,
+            conf,
+        )?))
+    }
+
+    fn call(
+        client: &Client,
+        remote_id: &Arc<ConnectionId>,
+        request: &[u8],
+    ) -> anyhow::Result<Vec<u8>> {
+        client.call(
+            &RpcKind::RpcProtocolBuffer,
+            Arc::new(request.to_vec()),
+            Arc::clone(remote_id),
+            0,
+            None,
+            None,
+        )
+    }
+
+    #[test]
+    fn test_connection_reuse() -> anyhow::Result<()> {
+        let (addr, accepted) = start_server(1)?;
+        let mut conf = Configuration::new();
+        conf.set_int(IPC_CLIENT_CONNECTION_MAXIDLETIME_KEY, 10000);
+        let client = Client::new("value_class", &conf)?;
+        let get_connection = || client.connections.lock().unwrap().values().next().cloned();
+
+        // calls to equal connection ids share one connection
+        let remote_id = new_remote_id(&addr, &conf)?;
+        assert_eq!(b"request", &call(&client, &remote_id, b"request")?[..]);
+        call(&client, &remote_id, b"request")?;
+        call(&client, &new_remote_id(&addr, &conf)?, b"request")?;
+        assert_eq!(1, accepted.load(Ordering::SeqCst));
+        assert_eq!(1, client.connections.lock().unwrap().len());
+
+        // a connection idle for less than maxIdleTime is kept
+        let connection = get_connection().unwrap();
+        connection.advance_idle_time(Duration::from_secs(5));
+        call(&client, &remote_id, b"request")?;
+        assert_eq!(1, accepted.load(Ordering::SeqCst));
+
+        // the idle connection is closed and replaced by a new one
+        connection.advance_idle_time(Duration::from_secs(10));
+        call(&client, &remote_id, b"request")?;
+        assert_eq!(2, accepted.load(Ordering::SeqCst));
+        assert!(!Arc::ptr_eq(&connection, &get_connection().unwrap()));
+
+        // no calls are made once the client is stopped
+        client.stop();
+        assert!(client.connections.lock().unwrap().is_empty());
+        let err = call(&client, &remote_id, b"request").unwrap_err();
+        assert_eq!("The client is stopped", err.to_string());
+        assert!(client.connections.lock().unwrap().is_empty());
+        assert_eq!(2, accepted.load(Ordering::SeqCst));
+        Ok(())
+    }
+
+    #[test]
+    fn test_concurrent_calls() -> anyhow::Result<()> {
+        let (addr, accepted) = start_server(2)?;
+        let conf = Configuration::new();
+        let client = Arc::new(Client::new("value_class", &conf)?);
+        let remote_id = new_remote_id(&addr, &conf)?;
+
+        // the responses of the two calls are sent in the reverse order
+        let handles: Vec<_> = [b"first", b"other"]
+            .into_iter()
+            .map(|request| {
+                let client = Arc::clone(&client);
+                let remote_id = Arc::clone(&remote_id);
+                thread::spawn(move || call(&client, &remote_id, request))
+            })
+            .collect();
+        let responses = handles
+            .into_iter()
+            .map(|h| h.join().unwrap())
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        assert_eq!(b"first", &responses[0][..]);
+        assert_eq!(b"other", &responses[1][..]);
+        assert_eq!(1, accepted.load(Ordering::SeqCst));
+        Ok(())
+    }
+
+    #[test]
+    fn test_connect_retries() -> anyhow::Result<()> {
+        // an address nothing listens on
+        let addr = TcpListener::bind("127.0.0.1:0")?.local_addr()?;
+        let mut conf = Configuration::new();
+        conf.set_int(IPC_CLIENT_CONNECT_MAX_RETRIES_KEY, 2);
+        conf.set_int(IPC_CLIENT_CONNECT_RETRY_INTERVAL_KEY, 100);
+        let client = Client::new("value_class", &conf)?;
+        let remote_id = new_remote_id(&addr, &conf)?;
+
+        let start = Instant::now();
+        let err = call(&client, &remote_id, b"request").unwrap_err();
+        assert_eq!(
+            Some(io::ErrorKind::ConnectionRefused),
+            err.downcast_ref::<io::Error>().map(io::Error::kind)
+        );
+        // two retries after the first attempt
+        assert!(start.elapsed() >= Duration::from_millis(200));
+        Ok(())
+    }
+
+    #[test]
+    fn test_rpc_timeout() -> anyhow::Result<()> {
+        // a server which never answers, recording the ids of the calls
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let addr = listener.local_addr()?;
+        let call_ids = Arc::new(Mutex::new(vec![]));
+        let received = Arc::clone(&call_ids);
+        thread::spawn(move || -> anyhow::Result<()> {
+            let (mut stream, _) = listener.accept()?;
+            stream.read_exact(&mut [0; 7])?;
+            read_frame(&mut stream)?;
+            loop {
+                let frame = read_frame(&mut stream)?;
+                let header = RpcRequestHeaderProto::decode_length_delimited(&frame[..])?;
+                received.lock().unwrap().push(header.call_id);
+            }
+        });
+        let mut conf = Configuration::new();
+        conf.set_int(common_configuration_keys::IPC_CLIENT_RPC_TIMEOUT_KEY, 300);
+        conf.set_int(common_configuration_keys::IPC_PING_INTERVAL_KEY, 100);
+        let client = Client::new("value_class", &conf)?;
+        let ticket = UserGroupInformation::get_current_user()?;
+        let remote_id = Arc::new(ConnectionId::get_connection_id(
+            &addr,
+            &TEST_PROTOCOL,
+            &ticket,
+            Client::get_timeout(&conf)?,
+            None,
+            &conf,
+        )?);
+
+        let err = call(&client, &remote_id, b"request").unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<IOException>(),
+            Some(IOException::SocketTimeout(_))
+        ));
+        // the connection was kept alive with pings until the call timed out
+        assert!(call_ids
+            .lock()
+            .unwrap()
+            .contains(&RpcConstants::PING_CALL_ID));
+        Ok(())
+    }
+
+    fn read_sasl_message(stream: &mut TcpStream) -> anyhow::Result<RpcSaslProto> {
+        let frame = read_frame(stream)?;
+        let mut payload = &frame[..];
+        let header = RpcRequestHeaderProto::decode_length_delimited(&mut payload)?;
+        assert_eq!(AuthProtocol::Sasl.call_id() as i32, header.call_id);
+        Ok(RpcSaslProto::decode_length_delimited(payload)?)
+    }
+
+    fn write_sasl_message(stream: &mut TcpStream, message: &RpcSaslProto) -> anyhow::Result<()> {
+        let header = RpcResponseHeaderProto {
+            call_id: AuthProtocol::Sasl.call_id() as i32 as u32,
+            status: RpcStatusProto::Success as i32,
+            ..Default::default()
+        };
+        let mut buf = header.encode_length_delimited_to_vec();
+        buf.append(&mut message.encode_length_delimited_to_vec());
+        stream.write_all(&(buf.len() as i32).to_be_bytes())?;
+        stream.write_all(&buf)?;
+        Ok(())
+    }
+
+    /// The DIGEST-MD5 response value of the server side, for the realm
+    /// "default", the nonce "nonce" and the digest uri "/default".
+    fn digest_response(token: &Token, cnonce: &str, method: &str) -> String {
+        let a1 = format!(
+            "{}:default:{}",
+            SaslRpcServer::encode_identifier(token.get_identifier()),
+            SaslRpcServer::encode_password(token.get_password())
+        );
+        let mut a1 = md5::compute(a1).0.to_vec();
+        a1.extend_from_slice(format!(":nonce:{}", cnonce).as_bytes());
+        let kd = format!(
+            "{:x}:nonce:00000001:{}:auth:{:x}",
+            md5::compute(a1),
+            cnonce,
+            md5::compute(format!("{}:/default", method))
+        );
+        format!("{:x}", md5::compute(kd))
+    }
+
+    #[test]
+    fn test_sasl_token_authentication() -> anyhow::Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let addr = listener.local_addr()?;
+        let token = Token::new(
+            b"identifier",
+            b"password",
+            "TEST_TOKEN",
+            &SecurityUtil::build_token_service(&addr).to_string(),
+        );
+        let server_token = token.to_owned();
+        let server = thread::spawn(move || -> anyhow::Result<Vec<u8>> {
+            let (mut stream, _) = listener.accept()?;
+            let mut header = [0; 7];
+            stream.read_exact(&mut header)?;
+            assert_eq!(AuthProtocol::Sasl.call_id() as u8, header[6]);
+
+            let negotiate = read_sasl_message(&mut stream)?;
+            assert_eq!(SaslState::Negotiate, negotiate.state());
+            let auth = SaslAuth {
+                method: "TOKEN".to_owned(),
+                mechanism: "DIGEST-MD5".to_owned(),
+                protocol: Some("".to_owned()),
+                server_id: Some("default".to_owned()),
+                challenge: Some(
+                    b"realm=\"default\",nonce=\"nonce\",qop=\"auth\",charset=utf-8,\
+                    algorithm=md5-sess"
+                        .to_vec(),
+                ),
+            };
+            write_sasl_message(
+                &mut stream,
+                &RpcSaslProto {
+                    state: SaslState::Negotiate.into(),
+                    auths: vec![
+                        SaslAuth {
+                            method: "KERBEROS".to_owned(),
+                            mechanism: "GSSAPI".to_owned(),
+                            ..Default::default()
+                        },
+                        auth,
+                    ],
+                    ..Default::default()
+                },
+            )?;
+
+            let initiate = read_sasl_message(&mut stream)?;
+            assert_eq!(SaslState::Initiate, initiate.state());
+            assert_eq!("TOKEN", initiate.auths[0].method);
+            let response = String::from_utf8(initiate.token.unwrap_or_default())?;
+            let directive = |name: &str| {
+                response
+                    .split(',')
+                    .filter_map(|d| d.split_once('='))
+                    .find(|(n, _)| *n == name)
+                    .map(|(_, v)| v.trim_matches('"').to_owned())
+                    .unwrap_or_default()
+            };
+            let cnonce = directive("cnonce");
+            assert_eq!(
+                digest_response(&server_token, &cnonce, "AUTHENTICATE"),
+                directive("response")
+            );
+            write_sasl_message(
+                &mut stream,
+                &RpcSaslProto {
+                    state: SaslState::Success.into(),
+                    token: Some(
+                        format!("rspauth={}", digest_response(&server_token, &cnonce, ""))
+                            .into_bytes(),
+                    ),
+                    ..Default::default()
+                },
+            )?;
+
+            // the connection context and the call, which is echoed
+            read_frame(&mut stream)?;
+            let frame = read_frame(&mut stream)?;
+            let mut payload = &frame[..];
+            let header = RpcRequestHeaderProto::decode_length_delimited(&mut payload)?;
+            let response_header = RpcResponseHeaderProto {
+                call_id: header.call_id as u32,
+                status: RpcStatusProto::Success as i32,
+                ..Default::default()
+            };
+            let mut buf = response_header.encode_length_delimited_to_vec();
+            buf.extend_from_slice(payload);
+            stream.write_all(&(buf.len() as i32).to_be_bytes())?;
+            stream.write_all(&buf)?;
+            Ok(payload.to_vec())
+        });
+
+        let conf = Configuration::new();
+        let client = Client::new("value_class", &conf)?;
+        let ticket = UserGroupInformation::create_remote_user("user")?;
+        ticket.add_token(token);
+        let remote_id = Arc::new(ConnectionId::get_connection_id(
+            &addr,
+            &TOKEN_PROTOCOL,
+            &ticket,
+            0,
+            None,
+            &conf,
+        )?);
+
+        assert_eq!(b"request", &call(&client, &remote_id, b"request")?[..]);
+        assert_eq!(b"request", &server.join().unwrap()?[..]);
+        Ok(())
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_async_calls() -> anyhow::Result<()> {
+        let (addr, accepted) = start_server(2)?;
+        let conf = Configuration::new();
+        let client = Client::new("value_class", &conf)?;
+        let remote_id = new_remote_id(&addr, &conf)?;
+
+        // both calls are outstanding before the server answers them
+        let call_async = |request: &[u8]| {
+            client.call_async(
+                &RpcKind::RpcProtocolBuffer,
+                Arc::new(request.to_vec()),
+                Arc::clone(&remote_id),
+                0,
+                None,
+                None,
+            )
+        };
+        let (first, other) = tokio::join!(call_async(b"first"), call_async(b"other"));
+        assert_eq!(b"first", &first?[..]);
+        assert_eq!(b"other", &other?[..]);
+        assert_eq!(1, accepted.load(Ordering::SeqCst));
+        assert_eq!(0, client.async_call_counter.load(Ordering::SeqCst));
+        Ok(())
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_async_call_connects_off_the_runtime() -> anyhow::Result<()> {
+        // nothing listens on the port, so connecting retries with sleeps
+        let addr = TcpListener::bind("127.0.0.1:0")?.local_addr()?;
+        let mut conf = Configuration::new();
+        conf.set_int(IPC_CLIENT_CONNECT_MAX_RETRIES_KEY, 2);
+        conf.set_int(IPC_CLIENT_CONNECT_RETRY_INTERVAL_KEY, 100);
+        let client = Client::new("value_class", &conf)?;
+        let remote_id = new_remote_id(&addr, &conf)?;
+
+        // the only runtime thread keeps running other tasks meanwhile
+        let ticks = Arc::new(AtomicUsize::new(0));
+        let ticker = tokio::spawn({
+            let ticks = Arc::clone(&ticks);
+            async move {
+                loop {
+                    ticks.fetch_add(1, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                }
+            }
+        });
+        let err = client
+            .call_async(
+                &RpcKind::RpcProtocolBuffer,
+                Arc::new(b"request".to_vec()),
+                remote_id,
+                0,
+                None,
+                None,
+            )
+            .await
+            .unwrap_err();
+        ticker.abort();
+        assert_eq!(
+            Some(io::ErrorKind::ConnectionRefused),
+            err.downcast_ref::<io::Error>().map(io::Error::kind)
+        );
+        assert!(ticks.load(Ordering::SeqCst) > 10);
+        Ok(())
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_async_call_limit() -> anyhow::Result<()> {
+        let (addr, _) = start_server(2)?;
+        let mut conf = Configuration::new();
+        conf.set_int(common_configuration_keys::IPC_CLIENT_ASYNC_CALLS_MAX_KEY, 1);
+        let client = Arc::new(Client::new("value_class", &conf)?);
+        let remote_id = new_remote_id(&addr, &conf)?;
+
+        // the server answers in batches of two, so the first call stays
+        // outstanding
+        let first = {
+            let client = Arc::clone(&client);
+            let remote_id = Arc::clone(&remote_id);
+            tokio::spawn(async move {
+                client
+                    .call_async(
+                        &RpcKind::RpcProtocolBuffer,
+                        Arc::new(b"first".to_vec()),
+                        remote_id,
+                        0,
+                        None,
+                        None,
+                    )
+                    .await
+            })
+        };
+        while client.async_call_counter.load(Ordering::SeqCst) == 0 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        // another asynchronous call exceeds the limit
+        let err = client
+            .call_async(
+                &RpcKind::RpcProtocolBuffer,
+                Arc::new(b"other".to_vec()),
+                Arc::clone(&remote_id),
+                0,
+                None,
+                None,
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<IOException>(),
+            Some(IOException::AsyncCallLimitExceeded(_))
+        ));
+        assert_eq!(1, client.async_call_counter.load(Ordering::SeqCst));
+
+        // a synchronous call is not limited, it completes the batch
+        let other = {
+            let client = Arc::clone(&client);
+            tokio::task::spawn_blocking(move || call(&client, &remote_id, b"other"))
+        };
+        assert_eq!(b"other", &other.await??[..]);
+        assert_eq!(b"first", &first.await??[..]);
+        assert_eq!(0, client.async_call_counter.load(Ordering::SeqCst));
+        Ok(())
     }
 }
@@ -0,0 +1,24 @@
+use super::Client;
+use crate::common::conf::Configuration;
+use std::sync::{Arc, Mutex, Weak};
+
+/// The client shared by the proxies, dropped once no proxy uses it.
+static CLIENT: Mutex<Weak<Client>> = Mutex::new(Weak::new());
+
+/// Cache a client so that the proxies share its connections.
+pub(crate) struct ClientCache;
+
+impl ClientCache {
+    /// Construct & cache an IPC client if no cached client exists. The client
+    /// is stopped once the last proxy using it is dropped.
+    pub fn get_client(conf: &Configuration) -> anyhow::Result<Arc<Client>> {
+        let mut cached = CLIENT.lock().unwrap();
+        if let Some(client) = cached.upgrade() {
+            return Ok(client);
+        }
+        // TODO: value_class
+        let client = Arc::new(Client::new("value_class", conf)?);
+        *cached = Arc::downgrade(&client);
+        Ok(client)
+    }
+}
@@ -1,18 +1,24 @@
 mod alignment_context;
 mod client;
+mod client_cache;
 pub(crate) mod client_id;
 mod protobuf_rpc_engine2;
 mod protocol_info;
+pub mod remote_exception;
 mod rpc;
 mod rpc_constants;
 mod rpc_engine;
 mod server;
 
 pub use alignment_context::AlignmentContext;
+pub(crate) use client::{prepend_buf_size, IpcStreams};
 pub use client::{Client, ConnectionId};
+pub(crate) use client_cache::ClientCache;
 pub(crate) use client_id::ClientId;
 pub use protobuf_rpc_engine2::{Invoker, ProtobufRpcEngine2};
 pub use protocol_info::ProtocolInfo;
+pub use remote_exception::RemoteException;
 pub use rpc::{RpcKind, RpcProtocol, RPC};
 pub(crate) use rpc_constants::RpcConstants;
 pub use rpc_engine::RpcEngine;
+pub(crate) use server::AuthProtocol;
@@ -1,13 +1,11 @@
-use super::{AlignmentContext, Client, ConnectionId, RpcEngine, RpcProtocol, RPC};
+use super::{AlignmentContext, Client, ClientCache, ConnectionId, RpcEngine, RpcProtocol, RPC};
 use crate::common::{
-    conf::Configuration, io::retry::RetryPolicy, ipc::RpcKind,
-    rpc_response_header_proto::RpcStatusProto, security::UserGroupInformation, RequestHeaderProto,
-    RpcResponseHeaderProto,
+    conf::Configuration, io::retry::RetryPolicy, ipc::RpcKind, security::UserGroupInformation,
+    RequestHeaderProto,
 };
-use anyhow::Error;
 use atomic::Atomic;
 use prost::Message;
-use std::{marker::PhantomData, net::SocketAddr, rc::Rc, sync::Arc};
+use std::{marker::PhantomData, net::SocketAddr, sync::Arc};
 
 pub struct ProtobufRpcEngine2;
 
@@ -18,9 +16,9 @@ impl RpcEngine for ProtobufRpcEngine2 {
         ticket: &UserGroupInformation,
         conf: &Configuration,
         rpc_timeout: i32,
-        connection_retry_policy: Option<Rc<dyn RetryPolicy>>,
+        connection_retry_policy: Option<Arc<dyn RetryPolicy>>,
         fallback_to_simple_auth: Option<Arc<Atomic<bool>>>,
-        alignment_context: Option<Rc<dyn AlignmentContext>>,
+        alignment_context: Option<Arc<dyn AlignmentContext>>,
     ) -> anyhow::Result<T> {
         Ok(T::from(Invoker::from_socket_addr(
             addr,
@@ -35,12 +33,12 @@ impl RpcEngine for ProtobufRpcEngine2 {
 }
 
 pub struct Invoker<T: RpcProtocol> {
-    remote_id: Rc<ConnectionId>,
-    client: Client,
+    remote_id: Arc<ConnectionId>,
+    client: Arc<Client>,
     client_protocol_version: u64,
     protocol_name: String,
     fallback_to_simple_auth: Option<Arc<Atomic<bool>>>,
-    alignment_context: Option<Rc<dyn AlignmentContext>>,
+    alignment_context: Option<Arc<dyn AlignmentContext>>,
     phantom: PhantomData<T>,
 }
 
@@ -50,39 +48,36 @@ impl<T: RpcProtocol> Invoker<T> {
         ticket: &UserGroupInformation,
         conf: &Configuration,
         rpc_timeout: i32,
-        connection_retry_policy: Option<Rc<dyn RetryPolicy>>,
+        connection_retry_policy: Option<Arc<dyn RetryPolicy>>,
         fallback_to_simple_auth: Option<Arc<Atomic<bool>>>,
-        alignment_context: Option<Rc<dyn AlignmentContext>>,
+        alignment_context: Option<Arc<dyn AlignmentContext>>,
     ) -> anyhow::Result<Self> {
-        let connection_id = Rc::new(ConnectionId::get_connection_id(
+        let connection_id = Arc::new(ConnectionId::get_connection_id(
             addr,
+            T::get_protocol_info(),
             ticket,
             rpc_timeout,
             connection_retry_policy,
             conf,
         )?);
-        Ok(Self::from_connection_id(
+        Self::from_connection_id(
             connection_id,
             conf,
             fallback_to_simple_auth,
             alignment_context,
-        )?)
+        )
     }
 
     /// This constructor takes a connection_id, instead of creating a new one.
     pub fn from_connection_id(
-        conn_id: Rc<ConnectionId>,
+        conn_id: Arc<ConnectionId>,
         conf: &Configuration,
         fallback_to_simple_auth: Option<Arc<Atomic<bool>>>,
-        alignment_context: Option<Rc<dyn AlignmentContext>>,
+        alignment_context: Option<Arc<dyn AlignmentContext>>,
     ) -> anyhow::Result<Self> {
-        // TODO: construct & cache client (or consider client singleton)
-
-        // TODO: value_class
-
         Ok(Self {
             remote_id: conn_id,
-            client: Client::new("value_class", conf)?,
+            client: ClientCache::get_client(conf)?,
             client_protocol_version: RPC::get_protocol_version::<T>(),
             protocol_name: RPC::get_protocol_name::<T>().to_owned(),
             fallback_to_simple_auth,
@@ -105,16 +100,37 @@ impl<T: RpcProtocol> Invoker<T> {
         method: &str,
         the_request: &impl Message,
     ) -> anyhow::Result<M> {
-        let val = self.client.call::<T>(
+        let val = self.client.call(
             &RpcKind::RpcProtocolBuffer,
-            Rc::new(self.construct_rpc_request(method, the_request)),
-            Rc::clone(&self.remote_id),
+            Arc::new(self.construct_rpc_request(method, the_request)),
+            Arc::clone(&self.remote_id),
             RPC::RPC_SERVICE_CLASS_DEFAULT,
             self.fallback_to_simple_auth.as_ref().map(Arc::clone),
-            self.alignment_context.as_ref().map(Rc::clone),
+            self.alignment_context.as_ref().map(Arc::clone),
         )?;
 
-        // TODO: support asynchronous mode
+        self.get_return_message(method, &val)
+    }
+
+    /// This is the client side invoker of RPC method, the returned future
+    /// resolves to the response of the method.
+    #[cfg(feature = "async")]
+    pub async fn invoke_async<M: Default + Message>(
+        &self,
+        method: &str,
+        the_request: &impl Message,
+    ) -> anyhow::Result<M> {
+        let val = self
+            .client
+            .call_async(
+                &RpcKind::RpcProtocolBuffer,
+                Arc::new(self.construct_rpc_request(method, the_request)),
+                Arc::clone(&self.remote_id),
+                RPC::RPC_SERVICE_CLASS_DEFAULT,
+                self.fallback_to_simple_auth.as_ref().map(Arc::clone),
+                self.alignment_context.as_ref().map(Arc::clone),
+            )
+            .await?;
 
         self.get_return_message(method, &val)
     }
@@ -130,19 +146,10 @@ impl<T: RpcProtocol> Invoker<T> {
     fn get_return_message<M: Default + Message>(
         &self,
         _method: &str,
-        buf: &Vec<u8>,
+        buf: &[u8],
     ) -> anyhow::Result<M> {
         // TODO: use Writable
 
-        let mut buffer = &buf[..];
-        let header: RpcResponseHeaderProto = Message::decode_length_delimited(buffer)?;
-        let status = header.status();
-        if status == RpcStatusProto::Success {
-            let header_len = header.encode_length_delimited_to_vec().len();
-            buffer = &buf[header_len..];
-            let res = M::decode_length_delimited(buffer)?;
-            return Ok(res);
-        }
-        Err(Error::msg(format!("{:#?}", header)))
+        Ok(M::decode_length_delimited(buf)?)
     }
 }
@@ -3,4 +3,9 @@ pub struct ProtocolInfo {
     // the name of the protocol (i.e. rpc service)
     pub protocol_name: &'static str,
     pub protocol_version: u64,
+    // the kind of the tokens used to authenticate to the protocol, if any
+    pub token_kind: Option<&'static str>,
+    // the configuration key of the Kerberos principal of the server, if the
+    // protocol supports Kerberos authentication
+    pub server_principal: Option<&'static str>,
 }
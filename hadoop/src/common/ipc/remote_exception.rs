@@ -0,0 +1,145 @@
+use crate::common::{
+    io::IOException,
+    rpc_response_header_proto::{RpcErrorCodeProto, RpcStatusProto},
+    RpcResponseHeaderProto,
+};
+use anyhow::Error;
+use std::fmt;
+
+/// An exception raised by the remote server, as reported in the response
+/// header of a failed call.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RemoteException {
+    // the class name of the exception raised on the server side
+    class_name: String,
+    error_msg: String,
+    // the error code of the failed call, if the server set it
+    error_code: Option<RpcErrorCodeProto>,
+    status: RpcStatusProto,
+}
+
+impl RemoteException {
+    pub fn new(class_name: &str, msg: &str) -> Self {
+        Self::with_error_code(class_name, msg, None)
+    }
+
+    pub fn with_error_code(
+        class_name: &str,
+        msg: &str,
+        error_code: Option<RpcErrorCodeProto>,
+    ) -> Self {
+        Self {
+            class_name: class_name.to_owned(),
+            error_msg: msg.to_owned(),
+            error_code,
+            status: RpcStatusProto::Error,
+        }
+    }
+
+    /// Returns the class name of the exception raised on the server side.
+    pub fn get_class_name(&self) -> &str {
+        &self.class_name
+    }
+
+    /// Returns the error message of the exception raised on the server side.
+    pub fn get_message(&self) -> &str {
+        &self.error_msg
+    }
+
+    /// Returns error code of the failed call, if any.
+    pub fn get_error_code(&self) -> Option<RpcErrorCodeProto> {
+        self.error_code
+    }
+
+    /// Returns the status of the failed call. A fatal status means the
+    /// server closed the connection.
+    pub fn get_status(&self) -> RpcStatusProto {
+        self.status
+    }
+
+    /// If this remote exception wraps one of the known [`IOException`]s,
+    /// return it; otherwise return this remote exception.
+    pub fn unwrap_remote_exception(self) -> Error {
+        match IOException::from_class_name(&self.class_name, &self.error_msg) {
+            Some(e) => Error::new(e),
+            None => Error::new(self),
+        }
+    }
+}
+
+impl fmt::Display for RemoteException {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.error_msg)
+    }
+}
+
+impl std::error::Error for RemoteException {}
+
+impl From<&RpcResponseHeaderProto> for RemoteException {
+    fn from(header: &RpcResponseHeaderProto) -> Self {
+        let exception_class_name = header
+            .exception_class_name
+            .as_deref()
+            .unwrap_or("ServerDidNotSetExceptionClassName");
+        let error_msg = header
+            .error_msg
+            .as_deref()
+            .unwrap_or("ServerDidNotSetErrorMsg");
+        let er_code = header
+            .error_detail
+            .and_then(|code| RpcErrorCodeProto::try_from(code).ok());
+        if er_code.is_none() {
+            log::warn!("Detailed error code not set by server on rpc error");
+        }
+        Self {
+            status: header.status(),
+            ..Self::with_error_code(exception_class_name, error_msg, er_code)
+        }
+    }
+}
+
+/// Unwrap `e` if it is a [`RemoteException`], see
+/// [`RemoteException::unwrap_remote_exception`]; other errors are returned as
+/// they are.
+pub fn unwrap_remote_exception(e: Error) -> Error {
+    match e.downcast::<RemoteException>() {
+        Ok(re) => re.unwrap_remote_exception(),
+        Err(e) => e,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unwrap_remote_exception() {
+        let header = RpcResponseHeaderProto {
+            status: RpcStatusProto::Error as i32,
+            exception_class_name: Some(
+                "org.apache.hadoop.hdfs.server.namenode.SafeModeException".to_owned(),
+            ),
+            error_msg: Some("Cannot delete /a. Name node is in safe mode.".to_owned()),
+            error_detail: Some(RpcErrorCodeProto::ErrorApplication as i32),
+            ..Default::default()
+        };
+        let re = RemoteException::from(&header);
+        assert_eq!(
+            re.get_error_code(),
+            Some(RpcErrorCodeProto::ErrorApplication)
+        );
+        assert_eq!(re.get_status(), RpcStatusProto::Error);
+        let e = re
+            .unwrap_remote_exception()
+            .downcast::<IOException>()
+            .unwrap();
+        assert_eq!(
+            e,
+            IOException::SafeMode("Cannot delete /a. Name node is in safe mode.".to_owned())
+        );
+
+        let re = RemoteException::new("org.example.UnknownException", "boom");
+        let e = unwrap_remote_exception(Error::new(re.clone()));
+        assert_eq!(e.downcast::<RemoteException>().unwrap(), re);
+    }
+}
@@ -1,7 +1,7 @@
 use super::{AlignmentContext, Invoker, ProtobufRpcEngine2, ProtocolInfo, RpcEngine};
 use crate::common::{conf::Configuration, io::retry::RetryPolicy, security::UserGroupInformation};
 use atomic::Atomic;
-use std::{net::SocketAddr, rc::Rc, sync::Arc};
+use std::{net::SocketAddr, sync::Arc};
 
 #[derive(Clone)]
 #[repr(u8)]
@@ -37,6 +37,7 @@ impl RPC {
     }
 
     /// return the RpcEngine configured to handle a protocol
+    #[allow(clippy::extra_unused_type_parameters)]
     fn get_protocol_engine<T: RpcProtocol>(_conf: &Configuration) -> impl RpcEngine {
         // TODO: get or create an RPC Engine in cache for Protocol `T`
 
@@ -50,9 +51,9 @@ impl RPC {
         ticket: &UserGroupInformation,
         conf: &Configuration,
         rpc_timeout: i32,
-        connection_retry_policy: Option<Rc<dyn RetryPolicy>>,
+        connection_retry_policy: Option<Arc<dyn RetryPolicy>>,
         fallback_to_simple_auth: Option<Arc<Atomic<bool>>>,
-        alignment_context: Option<Rc<dyn AlignmentContext>>,
+        alignment_context: Option<Arc<dyn AlignmentContext>>,
     ) -> anyhow::Result<T> {
         // TODO: init SaslRpcServer if needed
 
@@ -3,8 +3,12 @@ pub(crate) struct RpcConstants;
 impl RpcConstants {
     pub const CONNECTION_CONTEXT_CALL_ID: i32 = -3;
 
+    pub const PING_CALL_ID: i32 = -4;
+
     pub const INVALID_RETRY_COUNT: i32 = -1;
 
+    pub const DUMMY_CLIENT_ID: &'static [u8] = &[];
+
     /// The Rpc-connection header is as follows
     /// +----------------------------------+
     /// |  "hrpc" 4 bytes                  |
@@ -15,7 +19,7 @@ impl RpcConstants {
     /// +----------------------------------+
     /// |  AuthProtocol (1 byte)           |
     /// +----------------------------------+
-
+    ///
     /// The first four bytes of Hadoop RPC connections
     pub const HEADER: &'static [u8] = "hrpc".as_bytes();
 
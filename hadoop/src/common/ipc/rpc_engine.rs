@@ -1,19 +1,20 @@
 use super::{AlignmentContext, RpcProtocol};
 use crate::common::{conf::Configuration, io::retry::RetryPolicy, security::UserGroupInformation};
 use atomic::Atomic;
-use std::{net::SocketAddr, rc::Rc, sync::Arc};
+use std::{net::SocketAddr, sync::Arc};
 
 /// An RPC implementation.
 pub trait RpcEngine {
     /// Construct a client-side proxy object.
+    #[allow(clippy::too_many_arguments)]
     fn get_proxy<T: RpcProtocol>(
         &self,
         addr: &SocketAddr,
         ticket: &UserGroupInformation,
         conf: &Configuration,
         rpc_timeout: i32,
-        connection_retry_policy: Option<Rc<dyn RetryPolicy>>,
+        connection_retry_policy: Option<Arc<dyn RetryPolicy>>,
         fallback_to_simple_auth: Option<Arc<Atomic<bool>>>,
-        alignment_context: Option<Rc<dyn AlignmentContext>>,
+        alignment_context: Option<Arc<dyn AlignmentContext>>,
     ) -> anyhow::Result<T>;
 }
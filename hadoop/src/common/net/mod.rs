@@ -1,3 +1,7 @@
 mod net_utils;
+mod network_topology;
+mod node_base;
 
 pub use net_utils::NetUtils;
+pub use network_topology::NetworkTopology;
+pub use node_base::NodeBase;
@@ -1,19 +1,203 @@
+use crate::common::io::IOException;
 use anyhow::Error;
-use std::net::{SocketAddr, ToSocketAddrs};
+use iref::IriBuf;
+use socket2::{Domain, SockAddr, SockRef, Socket, Type};
+use std::{
+    ffi::CStr,
+    io,
+    net::{IpAddr, SocketAddr, TcpStream, ToSocketAddrs, UdpSocket},
+    time::Duration,
+};
 
 pub struct NetUtils;
 
 impl NetUtils {
+    /// Create a [`SocketAddr`] from the given target string and
+    /// default port. If the string cannot be parsed correctly, the
+    /// `config_name` parameter is used as part of the error message
+    /// to help users find the misconfigured property.
+    ///
+    /// Accepts `<host>`, `<host>:<port>` and `<scheme>://<host>:<port>/<path>`.
+    pub fn create_socket_addr(
+        target: &str,
+        default_port: i32,
+        config_name: Option<&str>,
+    ) -> anyhow::Result<SocketAddr> {
+        let help_text = config_name
+            .map(|name| format!(" (configuration property '{}')", name))
+            .unwrap_or_default();
+        let target = target.trim();
+        let has_scheme = target.contains("://");
+        let uri = if has_scheme {
+            target.to_owned()
+        } else {
+            format!("dummyscheme://{}", target)
+        };
+        let invalid = || {
+            Error::msg(format!(
+                "Does not contain a valid host:port authority: {}{}",
+                target, help_text
+            ))
+        };
+        let uri = IriBuf::new(uri).map_err(|_| invalid())?;
+        let authority = uri.authority().ok_or_else(invalid)?;
+        let host = authority.host().as_str();
+        let port = match authority.port() {
+            Some(port) => port.as_str().parse().map_err(|_| invalid())?,
+            None => default_port,
+        };
+        if host.is_empty() || port < 0 || (!has_scheme && !uri.path().is_empty()) {
+            return Err(invalid());
+        }
+        Self::create_socket_addr_for_host(host, port)
+    }
+
     pub fn create_socket_addr_for_host(host: &str, port: i32) -> anyhow::Result<SocketAddr> {
         // TODO: resolve host
 
-        Ok(format!("{}:{}", host, port)
+        format!("{}:{}", host, port)
             .to_socket_addrs()?
-            .filter(|s| s.is_ipv4())
-            .last()
+            .rfind(|s| s.is_ipv4())
             .ok_or(Error::msg(format!(
                 "Does not contain a valid host:port authority: {}:{}",
                 host, port
-            )))?)
+            )))
+    }
+
+    /// Connect to `endpoint`, waiting at most `timeout` milliseconds if it
+    /// is positive. The socket is bound to `local_addr` first if given.
+    pub fn connect(
+        endpoint: &SocketAddr,
+        local_addr: Option<&SocketAddr>,
+        timeout: i32,
+    ) -> anyhow::Result<TcpStream> {
+        let result = match local_addr {
+            Some(local_addr) => connect_from(endpoint, local_addr, timeout),
+            None if timeout <= 0 => TcpStream::connect(endpoint),
+            None => TcpStream::connect_timeout(endpoint, Duration::from_millis(timeout as u64)),
+        };
+        result.map_err(|e| {
+            if e.kind() == io::ErrorKind::TimedOut {
+                Error::new(IOException::ConnectTimeout(format!(
+                    "{} millis timeout while waiting for channel to be ready for connect. \
+                    ch : {}",
+                    timeout, endpoint
+                )))
+            } else {
+                Error::new(e)
+            }
+        })
+    }
+
+    /// Checks if `host` is a local host name and return the address it
+    /// resolves to, `None` if it is not an address of this host.
+    pub fn get_local_inet_address(host: &str) -> Option<IpAddr> {
+        (host, 0)
+            .to_socket_addrs()
+            .ok()?
+            .map(|addr| addr.ip())
+            // only the addresses of the local interfaces can be bound to
+            .find(|ip| UdpSocket::bind(SocketAddr::new(*ip, 0)).is_ok())
+    }
+
+    /// Return the address a client socket should be bound to, `None` to
+    /// bind it to the wildcard address.
+    pub fn bind_to_local_address(local_addr: IpAddr, bind_wildcard_addr: bool) -> Option<IpAddr> {
+        if bind_wildcard_addr {
+            None
+        } else {
+            Some(local_addr)
+        }
+    }
+
+    /// Get the fully qualified domain name of the address by a reverse
+    /// lookup, or its textual representation if the lookup fails.
+    pub fn get_canonical_host_name(addr: &IpAddr) -> String {
+        let sockaddr = SockAddr::from(SocketAddr::new(*addr, 0));
+        let mut host = [0 as libc::c_char; libc::NI_MAXHOST as usize];
+        // SAFETY: `sockaddr` holds a socket address of the length passed,
+        // and `host` is a buffer of the length passed.
+        let ret = unsafe {
+            libc::getnameinfo(
+                sockaddr.as_ptr(),
+                sockaddr.len(),
+                host.as_mut_ptr(),
+                host.len() as libc::socklen_t,
+                std::ptr::null_mut(),
+                0,
+                libc::NI_NAMEREQD,
+            )
+        };
+        if ret != 0 {
+            return addr.to_string();
+        }
+        // SAFETY: getnameinfo NUL-terminates the host name on success.
+        unsafe { CStr::from_ptr(host.as_ptr()) }
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    /// Enable or disable SO_KEEPALIVE of the socket.
+    pub fn set_keep_alive(socket: &TcpStream, on: bool) -> anyhow::Result<()> {
+        Ok(SockRef::from(socket).set_keepalive(on)?)
+    }
+
+    /// Set the type-of-service of the IP packets sent from the socket.
+    pub fn set_traffic_class(socket: &TcpStream, tc: i32) -> anyhow::Result<()> {
+        let socket = SockRef::from(socket);
+        match socket.local_addr()?.as_socket() {
+            Some(SocketAddr::V6(_)) => socket.set_tclass_v6(tc as u32)?,
+            _ => socket.set_tos(tc as u32)?,
+        }
+        Ok(())
+    }
+}
+
+/// Connect a new socket bound to `local_addr` to `endpoint`, waiting at most
+/// `timeout` milliseconds if it is positive.
+fn connect_from(
+    endpoint: &SocketAddr,
+    local_addr: &SocketAddr,
+    timeout: i32,
+) -> io::Result<TcpStream> {
+    let socket = Socket::new(Domain::for_address(*endpoint), Type::STREAM, None)?;
+    socket.set_reuse_address(true)?;
+    socket.bind(&SockAddr::from(*local_addr))?;
+    let endpoint = SockAddr::from(*endpoint);
+    if timeout > 0 {
+        socket.connect_timeout(&endpoint, Duration::from_millis(timeout as u64))?;
+    } else {
+        socket.connect(&endpoint)?;
+    }
+    Ok(socket.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    #[test]
+    fn test_connect_from_local_addr() -> anyhow::Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let endpoint = listener.local_addr()?;
+        let local_addr = SocketAddr::new(endpoint.ip(), 0);
+        for timeout in [0, 1000] {
+            let socket = NetUtils::connect(&endpoint, Some(&local_addr), timeout)?;
+            let (_, peer) = listener.accept()?;
+            assert_eq!(socket.local_addr()?, peer);
+            assert_eq!(endpoint, socket.peer_addr()?);
+            NetUtils::set_keep_alive(&socket, true)?;
+            NetUtils::set_traffic_class(&socket, 0x08)?;
+        }
+
+        // nothing listens on the endpoint once the listener is closed
+        drop(listener);
+        let err = NetUtils::connect(&endpoint, Some(&local_addr), 1000).unwrap_err();
+        assert_eq!(
+            Some(io::ErrorKind::ConnectionRefused),
+            err.downcast_ref::<io::Error>().map(io::Error::kind)
+        );
+        Ok(())
     }
 }
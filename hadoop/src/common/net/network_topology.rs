@@ -0,0 +1,8 @@
+/// The class represents a cluster of computer with a tree hierarchical
+/// network topology. For example, a cluster may be consists of many data
+/// centers filled with racks of computers.
+pub struct NetworkTopology;
+
+impl NetworkTopology {
+    pub const DEFAULT_RACK: &'static str = "/default-rack";
+}
@@ -0,0 +1,59 @@
+use std::fmt;
+
+/// A base class that implements the Node interface representing a leaf
+/// (e.g. a datanode) in the network topology.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NodeBase {
+    // host:port#
+    name: String,
+    // string representation of this node's location
+    location: String,
+}
+
+impl NodeBase {
+    /// Path separator
+    pub const PATH_SEPARATOR: char = '/';
+    /// Path separator as a string
+    pub const PATH_SEPARATOR_STR: &'static str = "/";
+    /// String representation of root
+    pub const ROOT: &'static str = "";
+
+    /// Construct a node from its name and its location.
+    pub fn new(name: &str, location: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            location: Self::normalize(location),
+        }
+    }
+
+    /// Get the name of this node.
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get the string representation of this node's network location.
+    pub fn get_network_location(&self) -> &str {
+        &self.location
+    }
+
+    /// Normalize a path by stripping off any trailing [`Self::PATH_SEPARATOR`].
+    pub fn normalize(path: &str) -> String {
+        if path.is_empty() {
+            return Self::ROOT.to_owned();
+        }
+        path.trim_end_matches(Self::PATH_SEPARATOR).to_owned()
+    }
+}
+
+impl fmt::Display for NodeBase {
+    /// Return this node's path as its string representation
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}{}{}",
+            self.location,
+            Self::PATH_SEPARATOR_STR,
+            self.name
+        )
+    }
+}
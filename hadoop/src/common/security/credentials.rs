@@ -0,0 +1,247 @@
+use super::token::Token;
+use crate::common::{io::WritableUtils, CredentialsKvProto, CredentialsProto, TokenProto};
+use anyhow::Error;
+use prost::Message;
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufReader, Read},
+    path::Path,
+};
+
+/// The magic bytes a token storage file starts with.
+const TOKEN_STORAGE_MAGIC: &[u8] = b"HDTS";
+
+/// The serialization formats of a token storage file.
+const WRITABLE_FORMAT: u8 = 0;
+const PROTOBUF_FORMAT: u8 = 1;
+
+/// A class that provides the facilities of reading and writing
+/// secret keys and Tokens.
+#[derive(Clone, Debug, Default)]
+pub struct Credentials {
+    secret_keys_map: HashMap<String, Vec<u8>>,
+    token_map: HashMap<String, Token>,
+}
+
+impl Credentials {
+    /// Create an empty credentials instance.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the Token object for the alias.
+    pub fn get_token(&self, alias: &str) -> Option<&Token> {
+        self.token_map.get(alias)
+    }
+
+    /// Add a token in the storage (in memory).
+    pub fn add_token(&mut self, alias: &str, t: Token) {
+        self.token_map.insert(alias.to_owned(), t);
+    }
+
+    /// Return all the tokens in the in-memory map.
+    pub fn get_all_tokens(&self) -> Vec<&Token> {
+        self.token_map.values().collect()
+    }
+
+    /// Returns the number of tokens in the in-memory map.
+    pub fn number_of_tokens(&self) -> usize {
+        self.token_map.len()
+    }
+
+    /// Returns the key bytes for the alias.
+    pub fn get_secret_key(&self, alias: &str) -> Option<&[u8]> {
+        self.secret_keys_map.get(alias).map(Vec::as_slice)
+    }
+
+    /// Returns the number of keys in the in-memory map.
+    pub fn number_of_secret_keys(&self) -> usize {
+        self.secret_keys_map.len()
+    }
+
+    /// Set the key for an alias.
+    pub fn add_secret_key(&mut self, alias: &str, key: &[u8]) {
+        self.secret_keys_map.insert(alias.to_owned(), key.to_vec());
+    }
+
+    /// Copy all of the credentials from one credential object into another.
+    /// Existing secrets and tokens are overwritten.
+    pub fn add_all(&mut self, other: &Credentials) {
+        self.secret_keys_map.extend(
+            other
+                .secret_keys_map
+                .iter()
+                .map(|(alias, key)| (alias.to_owned(), key.to_owned())),
+        );
+        self.token_map.extend(
+            other
+                .token_map
+                .iter()
+                .map(|(alias, token)| (alias.to_owned(), token.to_owned())),
+        );
+    }
+
+    /// Convenience method for reading a token storage file and loading its
+    /// Tokens.
+    pub fn read_token_storage_file(filename: &Path) -> anyhow::Result<Self> {
+        let mut credentials = Self::new();
+        let mut stream = BufReader::new(File::open(filename)?);
+        credentials
+            .read_token_storage_stream(&mut stream)
+            .map_err(|e| {
+                Error::msg(format!(
+                    "Exception reading {}: {}",
+                    filename.to_string_lossy(),
+                    e
+                ))
+            })?;
+        Ok(credentials)
+    }
+
+    /// Convenience method for reading a token from a stream.
+    pub fn read_token_storage_stream(&mut self, stream: &mut impl Read) -> anyhow::Result<()> {
+        let mut magic = [0; 4];
+        stream.read_exact(&mut magic)?;
+        if magic != TOKEN_STORAGE_MAGIC {
+            return Err(Error::msg("Bad header found in token storage."));
+        }
+        let mut version = [0; 1];
+        stream.read_exact(&mut version)?;
+        match version[0] {
+            WRITABLE_FORMAT => self.read_fields(stream),
+            PROTOBUF_FORMAT => self.read_proto(stream),
+            v => Err(Error::msg(format!(
+                "Unknown version {} in token storage.",
+                v
+            ))),
+        }
+    }
+
+    /// Loads all the keys in the Writable serialization.
+    fn read_fields(&mut self, stream: &mut impl Read) -> anyhow::Result<()> {
+        let size = WritableUtils::read_vint(stream)?;
+        for _ in 0..size {
+            let alias = WritableUtils::read_string(stream)?.unwrap_or_default();
+            let t = Token::read_fields(stream)?;
+            self.token_map.insert(alias, t);
+        }
+
+        let size = WritableUtils::read_vint(stream)?;
+        for _ in 0..size {
+            let alias = WritableUtils::read_string(stream)?.unwrap_or_default();
+            let len = WritableUtils::read_vint(stream)?;
+            let mut value = vec![0; len.max(0) as usize];
+            stream.read_exact(&mut value)?;
+            self.secret_keys_map.insert(alias, value);
+        }
+        Ok(())
+    }
+
+    /// Populates keys/values from proto buffer storage.
+    fn read_proto(&mut self, stream: &mut impl Read) -> anyhow::Result<()> {
+        let mut buf = vec![];
+        stream.read_to_end(&mut buf)?;
+        let storage = CredentialsProto::decode_length_delimited(&buf[..])?;
+        for kv in storage.tokens {
+            if let Some(token) = &kv.token {
+                self.token_map.insert(kv.alias, Token::from(token));
+            }
+        }
+        for kv in storage.secrets {
+            if let Some(secret) = kv.secret {
+                self.secret_keys_map.insert(kv.alias, secret);
+            }
+        }
+        Ok(())
+    }
+
+    /// Convert the credentials to the protobuf storage format.
+    pub fn to_proto(&self) -> CredentialsProto {
+        CredentialsProto {
+            tokens: self
+                .token_map
+                .iter()
+                .map(|(alias, token)| CredentialsKvProto {
+                    alias: alias.to_owned(),
+                    token: Some(TokenProto::from(token)),
+                    secret: None,
+                })
+                .collect(),
+            secrets: self
+                .secret_keys_map
+                .iter()
+                .map(|(alias, secret)| CredentialsKvProto {
+                    alias: alias.to_owned(),
+                    token: None,
+                    secret: Some(secret.to_owned()),
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Append a Text in the Writable serialization, for short values.
+    fn write_text(buf: &mut Vec<u8>, value: &[u8]) {
+        buf.push(value.len() as u8);
+        buf.extend_from_slice(value);
+    }
+
+    #[test]
+    fn test_read_writable_token_storage() -> anyhow::Result<()> {
+        let mut buf = b"HDTS".to_vec();
+        buf.push(WRITABLE_FORMAT);
+        // one token
+        buf.push(1);
+        write_text(&mut buf, b"alias");
+        write_text(&mut buf, b"identifier");
+        write_text(&mut buf, b"password");
+        write_text(&mut buf, b"HDFS_DELEGATION_TOKEN");
+        write_text(&mut buf, b"127.0.0.1:8020");
+        // one secret
+        buf.push(1);
+        write_text(&mut buf, b"key");
+        write_text(&mut buf, b"secret");
+
+        let mut credentials = Credentials::new();
+        credentials.read_token_storage_stream(&mut &buf[..])?;
+        assert_eq!(
+            Some(&Token::new(
+                b"identifier",
+                b"password",
+                "HDFS_DELEGATION_TOKEN",
+                "127.0.0.1:8020"
+            )),
+            credentials.get_token("alias")
+        );
+        assert_eq!(Some(&b"secret"[..]), credentials.get_secret_key("key"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_protobuf_token_storage() -> anyhow::Result<()> {
+        let mut credentials = Credentials::new();
+        let token = Token::new(b"id", b"pw", "kind", "service");
+        credentials.add_token("alias", token.to_owned());
+        credentials.add_secret_key("key", b"secret");
+        let mut buf = b"HDTS".to_vec();
+        buf.push(PROTOBUF_FORMAT);
+        buf.append(&mut credentials.to_proto().encode_length_delimited_to_vec());
+
+        let mut read = Credentials::new();
+        read.read_token_storage_stream(&mut &buf[..])?;
+        assert_eq!(1, read.number_of_tokens());
+        assert_eq!(Some(&token), read.get_token("alias"));
+        assert_eq!(Some(&b"secret"[..]), read.get_secret_key("key"));
+
+        buf[0] = b'X';
+        assert!(Credentials::new()
+            .read_token_storage_stream(&mut &buf[..])
+            .is_err());
+        Ok(())
+    }
+}
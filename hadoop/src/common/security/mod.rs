@@ -1,7 +1,17 @@
+mod credentials;
+pub mod sasl;
+mod sasl_properties_resolver;
+mod sasl_rpc_client;
+mod sasl_rpc_server;
 mod security_util;
+pub mod token;
 mod user;
 mod user_group_information;
 
+pub use credentials::Credentials;
+pub use sasl_properties_resolver::SaslPropertiesResolver;
+pub use sasl_rpc_client::SaslRpcClient;
+pub use sasl_rpc_server::{AuthMethod, QualityOfProtection, SaslRpcServer};
 pub use security_util::SecurityUtil;
 pub(crate) use user::User;
-pub use user_group_information::UserGroupInformation;
+pub use user_group_information::{AuthenticationMethod, UserGroupInformation};
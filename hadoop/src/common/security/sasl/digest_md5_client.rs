@@ -0,0 +1,567 @@
+use super::SaslClient;
+use crate::common::io::IOException;
+use anyhow::Error;
+use rand::RngCore;
+
+/// The nonce count of the only response sent, the client does not support
+/// subsequent authentication.
+const NONCE_COUNT: &str = "00000001";
+
+/// The digest of the empty entity body, appended to A2 when the quality of
+/// protection is integrity or privacy.
+const EMPTY_ENTITY_DIGEST: &str = ":00000000000000000000000000000000";
+
+/// The default maximum size of the wrapped messages a peer accepts.
+const DEFAULT_MAXBUF: usize = 65536;
+
+/// The ciphers supported for privacy, in order of preference, with the
+/// number of bytes of H(A1) the sealing keys are derived from.
+const CIPHERS: [(&str, usize); 3] = [("rc4", 16), ("rc4-56", 7), ("rc4-40", 5)];
+
+const CLIENT_INT_MAGIC: &[u8] =
+    b"Digest session key to client-to-server signing key magic constant";
+const SVR_INT_MAGIC: &[u8] = b"Digest session key to server-to-client signing key magic constant";
+const CLIENT_CONF_MAGIC: &[u8] = b"Digest H(A1) to client-to-server sealing key magic constant";
+const SVR_CONF_MAGIC: &[u8] = b"Digest H(A1) to server-to-client sealing key magic constant";
+
+/// The message type of the wrapped messages.
+const MESSAGE_TYPE: [u8; 2] = [0, 1];
+
+enum State {
+    // waiting for the digest challenge
+    Challenge,
+    // the digest response was sent, waiting for the response auth
+    ResponseAuth { expected: String, h_a1: [u8; 16] },
+    Complete,
+}
+
+/// An implementation of the DIGEST-MD5 SASL mechanism client, as defined in
+/// RFC 2831, used to authenticate with tokens.
+pub struct DigestMd5Client {
+    digest_uri: String,
+    username: String,
+    password: String,
+    // the qualities of protection the client accepts, in order of preference
+    qop: Vec<String>,
+    cnonce: String,
+    negotiated_qop: Option<String>,
+    // the cipher negotiated for privacy, with its sealing key length
+    negotiated_cipher: Option<(&'static str, usize)>,
+    // the maximum size of the wrapped messages the server accepts
+    send_max_buffer_size: usize,
+    security_layer: Option<SecurityLayer>,
+    state: State,
+}
+
+impl DigestMd5Client {
+    /// Create a client authenticating to the `server_name` host of the
+    /// `protocol` service with the given credentials.
+    pub fn new(
+        protocol: &str,
+        server_name: &str,
+        username: &str,
+        password: &str,
+        qop: &[&str],
+    ) -> Self {
+        let mut cnonce = [0; 16];
+        rand::thread_rng().fill_bytes(&mut cnonce);
+        Self {
+            digest_uri: format!("{}/{}", protocol, server_name),
+            username: username.to_owned(),
+            password: password.to_owned(),
+            qop: qop.iter().map(|q| q.to_string()).collect(),
+            cnonce: cnonce.iter().map(|b| format!("{:02x}", b)).collect(),
+            negotiated_qop: None,
+            negotiated_cipher: None,
+            send_max_buffer_size: DEFAULT_MAXBUF,
+            security_layer: None,
+            state: State::Challenge,
+        }
+    }
+
+    /// Process the digest challenge sent by the server and generate the
+    /// digest response.
+    fn process_challenge(&mut self, challenge: &[u8]) -> anyhow::Result<Vec<u8>> {
+        let directives = parse_directives(challenge)?;
+        let get = |name: &str| {
+            directives
+                .iter()
+                .find(|(n, _)| n.eq_ignore_ascii_case(name))
+                .map(|(_, v)| v.as_str())
+        };
+        let nonce = get("nonce")
+            .ok_or_else(|| sasl_exception("Digest-challenge format violation: missing nonce"))?;
+        match get("algorithm") {
+            Some(algorithm) if algorithm.eq_ignore_ascii_case("md5-sess") => {}
+            Some(_) => return Err(sasl_exception("Invalid value for 'algorithm' directive")),
+            None => {
+                return Err(sasl_exception(
+                    "Digest-challenge format violation: algorithm directive missing",
+                ))
+            }
+        }
+        // the first realm offered is the default one
+        let realm = get("realm").unwrap_or_default();
+        let server_qop = get("qop").unwrap_or("auth");
+        let server_qop = server_qop.split(',').map(str::trim).collect::<Vec<_>>();
+        let qop = self
+            .qop
+            .iter()
+            .find(|q| server_qop.contains(&q.as_str()))
+            .ok_or_else(|| sasl_exception("No common protection layer between client and server"))?
+            .to_owned();
+        let utf8 = get("charset").is_some_and(|c| c.eq_ignore_ascii_case("utf-8"));
+        if let Some(maxbuf) = get("maxbuf") {
+            self.send_max_buffer_size = maxbuf
+                .parse()
+                .ok()
+                .filter(|size| *size > 16)
+                .ok_or_else(|| sasl_exception("Invalid value for 'maxbuf' directive"))?;
+        }
+        let cipher = if qop == "auth-conf" {
+            let server_ciphers = get("cipher").unwrap_or_default();
+            let server_ciphers = server_ciphers.split(',').map(str::trim).collect::<Vec<_>>();
+            let cipher = CIPHERS
+                .iter()
+                .find(|(name, _)| server_ciphers.contains(name))
+                .ok_or_else(|| sasl_exception("Client supports no common cipher"))?;
+            Some(*cipher)
+        } else {
+            None
+        };
+
+        let h_a1 = self.h_a1(realm, nonce);
+        let ha1 = format!("{:x}", md5::Digest(h_a1));
+        let response = self.response_value(&ha1, nonce, &qop, "AUTHENTICATE");
+        let expected = self.response_value(&ha1, nonce, &qop, "");
+
+        let mut digest_response = String::new();
+        if utf8 {
+            digest_response.push_str("charset=utf-8,");
+        }
+        digest_response.push_str(&format!("username=\"{}\",", quote(&self.username)));
+        if !realm.is_empty() {
+            digest_response.push_str(&format!("realm=\"{}\",", quote(realm)));
+        }
+        digest_response.push_str(&format!(
+            "nonce=\"{}\",nc={},cnonce=\"{}\",digest-uri=\"{}\",response={},qop={}",
+            quote(nonce),
+            NONCE_COUNT,
+            self.cnonce,
+            quote(&self.digest_uri),
+            response,
+            qop
+        ));
+        if let Some((name, _)) = cipher {
+            digest_response.push_str(&format!(",cipher={}", name));
+        }
+        self.negotiated_qop = Some(qop);
+        self.negotiated_cipher = cipher;
+        self.state = State::ResponseAuth { expected, h_a1 };
+        Ok(digest_response.into_bytes())
+    }
+
+    /// H(A1), where A1 is `{ H({ username, ":", realm, ":", passwd }), ":",
+    /// nonce, ":", cnonce }`.
+    fn h_a1(&self, realm: &str, nonce: &str) -> [u8; 16] {
+        let mut a1 = md5::compute(format!("{}:{}:{}", self.username, realm, self.password))
+            .0
+            .to_vec();
+        a1.extend_from_slice(format!(":{}:{}", nonce, self.cnonce).as_bytes());
+        md5::compute(a1).0
+    }
+
+    fn security_layer(&mut self) -> anyhow::Result<&mut SecurityLayer> {
+        self.security_layer
+            .as_mut()
+            .ok_or_else(|| sasl_exception("No security layer negotiated"))
+    }
+
+    /// The digest of the response, `method` is `AUTHENTICATE` for the value
+    /// sent by the client, and empty for the one the server responds with.
+    fn response_value(&self, ha1: &str, nonce: &str, qop: &str, method: &str) -> String {
+        let mut a2 = format!("{}:{}", method, self.digest_uri);
+        if qop != "auth" {
+            a2.push_str(EMPTY_ENTITY_DIGEST);
+        }
+        let kd = format!(
+            "{}:{}:{}:{}:{}:{:x}",
+            ha1,
+            nonce,
+            NONCE_COUNT,
+            self.cnonce,
+            qop,
+            md5::compute(a2)
+        );
+        format!("{:x}", md5::compute(kd))
+    }
+}
+
+impl SaslClient for DigestMd5Client {
+    fn get_mechanism_name(&self) -> &str {
+        "DIGEST-MD5"
+    }
+
+    fn has_initial_response(&self) -> bool {
+        false
+    }
+
+    fn evaluate_challenge(&mut self, challenge: &[u8]) -> anyhow::Result<Option<Vec<u8>>> {
+        match &self.state {
+            State::Challenge => Ok(Some(self.process_challenge(challenge)?)),
+            State::ResponseAuth { expected, h_a1 } => {
+                let directives = parse_directives(challenge)?;
+                let rspauth = directives
+                    .iter()
+                    .find(|(name, _)| name == "rspauth")
+                    .map(|(_, value)| value);
+                if rspauth != Some(expected) {
+                    return Err(sasl_exception(
+                        "Server's rspauth value does not match what client expects",
+                    ));
+                }
+                if self.negotiated_qop.as_deref() != Some("auth") {
+                    self.security_layer = Some(SecurityLayer::new(
+                        h_a1,
+                        self.negotiated_cipher.map(|(_, n)| n),
+                    ));
+                }
+                self.state = State::Complete;
+                Ok(None)
+            }
+            State::Complete => Err(sasl_exception("DIGEST-MD5 authentication already complete")),
+        }
+    }
+
+    fn is_complete(&self) -> bool {
+        matches!(self.state, State::Complete)
+    }
+
+    fn get_negotiated_qop(&self) -> Option<&str> {
+        self.negotiated_qop.as_deref()
+    }
+
+    fn get_raw_send_size(&self) -> anyhow::Result<usize> {
+        // the wrapped messages are followed by the MAC, message type and
+        // sequence number
+        Ok(self.send_max_buffer_size - 16)
+    }
+
+    fn wrap(&mut self, outgoing: &[u8]) -> anyhow::Result<Vec<u8>> {
+        Ok(self.security_layer()?.wrap(outgoing))
+    }
+
+    fn unwrap(&mut self, incoming: &[u8]) -> anyhow::Result<Vec<u8>> {
+        self.security_layer()?.unwrap(incoming)
+    }
+}
+
+/// The integrity protection of the messages exchanged once authenticated,
+/// with the privacy protection of the RC4 cipher if negotiated.
+struct SecurityLayer {
+    // the integrity keys of the messages sent and received
+    my_ki: [u8; 16],
+    peer_ki: [u8; 16],
+    my_seq_num: u32,
+    peer_seq_num: u32,
+    // the ciphers encrypting the messages sent and decrypting the received
+    // ones
+    ciphers: Option<(Rc4, Rc4)>,
+}
+
+impl SecurityLayer {
+    /// Derive the keys of the client from H(A1), the sealing keys from its
+    /// first `cipher_key_length` bytes if privacy was negotiated.
+    fn new(h_a1: &[u8; 16], cipher_key_length: Option<usize>) -> Self {
+        let key = |h_a1: &[u8], magic: &[u8]| md5::compute([h_a1, magic].concat()).0;
+        let ciphers = cipher_key_length.map(|n| {
+            (
+                Rc4::new(&key(&h_a1[..n], CLIENT_CONF_MAGIC)),
+                Rc4::new(&key(&h_a1[..n], SVR_CONF_MAGIC)),
+            )
+        });
+        Self {
+            my_ki: key(h_a1, CLIENT_INT_MAGIC),
+            peer_ki: key(h_a1, SVR_INT_MAGIC),
+            my_seq_num: 0,
+            peer_seq_num: 0,
+            ciphers,
+        }
+    }
+
+    /// `{ message, HMAC(Ki, { SeqNum, message })[0..9], MsgType, SeqNum }`,
+    /// where the message and MAC are encrypted with privacy.
+    fn wrap(&mut self, outgoing: &[u8]) -> Vec<u8> {
+        let seq_num = self.my_seq_num.to_be_bytes();
+        let mac = hmac_md5(&self.my_ki, &[&seq_num, outgoing]);
+        let mut wrapped = outgoing.to_vec();
+        wrapped.extend_from_slice(&mac[..10]);
+        if let Some((encryption, _)) = self.ciphers.as_mut() {
+            encryption.apply(&mut wrapped);
+        }
+        wrapped.extend_from_slice(&MESSAGE_TYPE);
+        wrapped.extend_from_slice(&seq_num);
+        self.my_seq_num = self.my_seq_num.wrapping_add(1);
+        wrapped
+    }
+
+    fn unwrap(&mut self, incoming: &[u8]) -> anyhow::Result<Vec<u8>> {
+        if incoming.len() < 16 {
+            return Err(sasl_exception("Invalid wrapped message length"));
+        }
+        let (message, trailer) = incoming.split_at(incoming.len() - 6);
+        let mut message = message.to_vec();
+        if let Some((_, decryption)) = self.ciphers.as_mut() {
+            decryption.apply(&mut message);
+        }
+        let (message, mac) = message.split_at(message.len() - 10);
+        let (message_type, seq_num) = trailer.split_at(2);
+        if mac != &hmac_md5(&self.peer_ki, &[seq_num, message])[..10] {
+            return Err(sasl_exception("Invalid MAC of the wrapped message"));
+        }
+        if message_type != MESSAGE_TYPE {
+            return Err(sasl_exception(
+                "Invalid message type of the wrapped message",
+            ));
+        }
+        let seq_num = u32::from_be_bytes(seq_num.try_into()?);
+        if seq_num != self.peer_seq_num {
+            return Err(sasl_exception(&format!(
+                "Out of order sequencing of messages from server. Got: {} Expected: {}",
+                seq_num, self.peer_seq_num
+            )));
+        }
+        self.peer_seq_num = self.peer_seq_num.wrapping_add(1);
+        Ok(message.to_vec())
+    }
+}
+
+/// The RC4 stream cipher.
+struct Rc4 {
+    state: [u8; 256],
+    i: u8,
+    j: u8,
+}
+
+impl Rc4 {
+    fn new(key: &[u8]) -> Self {
+        let mut state = [0; 256];
+        for (i, s) in state.iter_mut().enumerate() {
+            *s = i as u8;
+        }
+        let mut j = 0u8;
+        for i in 0..256 {
+            j = j.wrapping_add(state[i]).wrapping_add(key[i % key.len()]);
+            state.swap(i, j as usize);
+        }
+        Self { state, i: 0, j: 0 }
+    }
+
+    /// Encrypt or decrypt the data in place, continuing the key stream.
+    fn apply(&mut self, data: &mut [u8]) {
+        for byte in data {
+            self.i = self.i.wrapping_add(1);
+            self.j = self.j.wrapping_add(self.state[self.i as usize]);
+            self.state.swap(self.i as usize, self.j as usize);
+            let k = self.state[self.i as usize].wrapping_add(self.state[self.j as usize]);
+            *byte ^= self.state[k as usize];
+        }
+    }
+}
+
+/// HMAC-MD5 of the concatenated parts, as defined in RFC 2104.
+fn hmac_md5(key: &[u8; 16], parts: &[&[u8]]) -> [u8; 16] {
+    let mut ipad = [0x36; 64];
+    let mut opad = [0x5c; 64];
+    for (i, k) in key.iter().enumerate() {
+        ipad[i] ^= k;
+        opad[i] ^= k;
+    }
+    let mut inner = md5::Context::new();
+    inner.consume(ipad);
+    for part in parts {
+        inner.consume(part);
+    }
+    let mut outer = md5::Context::new();
+    outer.consume(opad);
+    outer.consume(inner.compute().0);
+    outer.compute().0
+}
+
+fn sasl_exception(msg: &str) -> Error {
+    Error::new(IOException::Sasl(format!("DIGEST-MD5: {}", msg)))
+}
+
+/// Escape the quotes and backslashes of a quoted string value.
+fn quote(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Parse the comma separated `name=value` directives of a challenge, where
+/// values may be quoted strings.
+fn parse_directives(challenge: &[u8]) -> anyhow::Result<Vec<(String, String)>> {
+    let challenge = String::from_utf8_lossy(challenge);
+    let mut chars = challenge.chars().peekable();
+    let mut directives = vec![];
+    loop {
+        while chars.next_if(|c| c.is_whitespace() || *c == ',').is_some() {}
+        if chars.peek().is_none() {
+            return Ok(directives);
+        }
+        let mut name = String::new();
+        loop {
+            match chars.next() {
+                Some('=') => break,
+                Some(c) => name.push(c),
+                None => return Err(sasl_exception("Invalid digest challenge")),
+            }
+        }
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        let mut value = String::new();
+        if chars.next_if_eq(&'"').is_some() {
+            loop {
+                match chars.next() {
+                    Some('"') => break,
+                    Some('\\') => value.extend(chars.next()),
+                    Some(c) => value.push(c),
+                    None => return Err(sasl_exception("Unmatched quote in digest challenge")),
+                }
+            }
+        } else {
+            while let Some(c) = chars.next_if(|c| *c != ',') {
+                value.push(c);
+            }
+        }
+        directives.push((name.trim().to_owned(), value.trim().to_owned()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_digest_authentication() -> anyhow::Result<()> {
+        // the example of RFC 2831
+        let mut client =
+            DigestMd5Client::new("imap", "elwood.innosoft.com", "chris", "secret", &["auth"]);
+        client.cnonce = "OA6MHXh6VqTrRk".to_owned();
+        let response = client
+            .evaluate_challenge(
+                b"realm=\"elwood.innosoft.com\",nonce=\"OA6MG9tEQGm2hh\",qop=\"auth\",\
+                algorithm=md5-sess,charset=utf-8",
+            )?
+            .unwrap();
+        let response = String::from_utf8(response)?;
+        assert!(response.contains("username=\"chris\""));
+        assert!(response.contains("digest-uri=\"imap/elwood.innosoft.com\""));
+        assert!(response.contains("response=d388dad90d4bbd760a152321f2143af7"));
+        assert!(!client.is_complete());
+
+        assert!(client
+            .evaluate_challenge(b"rspauth=00000000000000000000000000000000")
+            .is_err());
+        assert_eq!(
+            None,
+            client.evaluate_challenge(b"rspauth=ea40f60335c427b5527b84dbabcdfffd")?
+        );
+        assert!(client.is_complete());
+        assert_eq!(Some("auth"), client.get_negotiated_qop());
+        Ok(())
+    }
+
+    #[test]
+    fn test_no_common_qop() {
+        let mut client = DigestMd5Client::new("", "default", "user", "password", &["auth"]);
+        let err = client
+            .evaluate_challenge(
+                b"realm=\"default\",nonce=\"n\",qop=\"auth-conf\",algorithm=md5-sess",
+            )
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<IOException>(),
+            Some(IOException::Sasl(_))
+        ));
+    }
+
+    #[test]
+    fn test_rc4_and_hmac_md5() {
+        let mut data = b"Plaintext".to_vec();
+        Rc4::new(b"Key").apply(&mut data);
+        assert_eq!(
+            vec![0xbb, 0xf3, 0x16, 0xe8, 0xd9, 0x40, 0xaf, 0x0a, 0xd3],
+            data
+        );
+        // the first test case of RFC 2104
+        assert_eq!(
+            "9294727a3638bb1c13f48ef8158bfc9d",
+            format!(
+                "{:x}",
+                md5::Digest(hmac_md5(&[0x0b; 16], &[b"Hi ", b"There"]))
+            )
+        );
+    }
+
+    #[test]
+    fn test_security_layer() -> anyhow::Result<()> {
+        for qop in ["auth-int", "auth-conf"] {
+            let mut client = DigestMd5Client::new("", "default", "user", "password", &[qop]);
+            let response = client
+                .evaluate_challenge(
+                    format!(
+                        "realm=\"default\",nonce=\"n\",qop=\"{}\",cipher=\"rc4-40,rc4\",\
+                        maxbuf=1024,algorithm=md5-sess",
+                        qop
+                    )
+                    .as_bytes(),
+                )?
+                .unwrap();
+            let response = String::from_utf8(response)?;
+            assert_eq!(qop == "auth-conf", response.ends_with(",cipher=rc4"));
+            let State::ResponseAuth { expected, h_a1 } = &client.state else {
+                panic!("digest response not sent");
+            };
+            let (rspauth, h_a1) = (format!("rspauth={}", expected), h_a1.to_owned());
+            assert!(client.wrap(b"message").is_err());
+            client.evaluate_challenge(rspauth.as_bytes())?;
+            assert_eq!(1008, client.get_raw_send_size()?);
+
+            // the server side swaps the keys of the client
+            let mut server = SecurityLayer::new(&h_a1, (qop == "auth-conf").then_some(16));
+            std::mem::swap(&mut server.my_ki, &mut server.peer_ki);
+            server.ciphers = server
+                .ciphers
+                .map(|(encryption, decryption)| (decryption, encryption));
+            for message in [&b"first"[..], b"other"] {
+                let wrapped = client.wrap(message)?;
+                assert_eq!(message.len() + 16, wrapped.len());
+                assert_eq!(qop == "auth-int", wrapped.starts_with(message));
+                assert_eq!(message, &server.unwrap(&wrapped)?[..]);
+                assert_eq!(message, &client.unwrap(&server.wrap(message))?[..]);
+            }
+
+            // tampered and replayed messages are rejected
+            let wrapped = server.wrap(b"message");
+            client.unwrap(&wrapped)?;
+            assert!(client.unwrap(&wrapped).is_err());
+            let mut wrapped = server.wrap(b"message");
+            wrapped[0] ^= 1;
+            assert!(client.unwrap(&wrapped).is_err());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_directives() -> anyhow::Result<()> {
+        let directives = parse_directives(b" a=\"x\\\"y\", b = 1 ,c=\"p,q\"")?;
+        assert_eq!(
+            vec![
+                ("a".to_owned(), "x\"y".to_owned()),
+                ("b".to_owned(), "1".to_owned()),
+                ("c".to_owned(), "p,q".to_owned()),
+            ],
+            directives
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,605 @@
+//! Bindings to the GSS-API of the system Kerberos library, as defined in
+//! RFC 2744, and safe wrappers of the credentials and security contexts
+//! used by the GSSAPI SASL mechanism.
+
+use crate::common::io::IOException;
+use anyhow::Error;
+use libc::{c_char, c_int, c_void};
+use std::{ffi::CString, path::Path, ptr, slice};
+
+#[allow(non_camel_case_types)]
+type OM_uint32 = u32;
+
+#[repr(C)]
+struct GssBufferDesc {
+    length: usize,
+    value: *mut c_void,
+}
+
+#[repr(C)]
+struct GssOidDesc {
+    length: OM_uint32,
+    elements: *mut c_void,
+}
+
+type GssOid = *mut GssOidDesc;
+type GssName = *mut c_void;
+type GssCred = *mut c_void;
+type GssCtx = *mut c_void;
+
+#[repr(C)]
+struct GssKeyValueElement {
+    key: *const c_char,
+    value: *const c_char,
+}
+
+#[repr(C)]
+struct GssKeyValueSet {
+    count: OM_uint32,
+    elements: *const GssKeyValueElement,
+}
+
+const GSS_S_CONTINUE_NEEDED: OM_uint32 = 1;
+const GSS_C_INDEFINITE: OM_uint32 = 0xffffffff;
+const GSS_C_INITIATE: c_int = 1;
+const GSS_C_GSS_CODE: c_int = 1;
+const GSS_C_MECH_CODE: c_int = 2;
+const GSS_C_MUTUAL_FLAG: OM_uint32 = 2;
+const GSS_C_CONF_FLAG: OM_uint32 = 16;
+const GSS_C_INTEG_FLAG: OM_uint32 = 32;
+
+#[link(name = "gssapi_krb5")]
+extern "C" {
+    static GSS_C_NT_HOSTBASED_SERVICE: GssOid;
+    static GSS_KRB5_NT_PRINCIPAL_NAME: GssOid;
+    static gss_mech_krb5: GssOid;
+
+    fn gss_import_name(
+        minor_status: *mut OM_uint32,
+        input_name_buffer: *const GssBufferDesc,
+        input_name_type: GssOid,
+        output_name: *mut GssName,
+    ) -> OM_uint32;
+
+    fn gss_display_name(
+        minor_status: *mut OM_uint32,
+        input_name: GssName,
+        output_name_buffer: *mut GssBufferDesc,
+        output_name_type: *mut GssOid,
+    ) -> OM_uint32;
+
+    fn gss_release_name(minor_status: *mut OM_uint32, name: *mut GssName) -> OM_uint32;
+
+    fn gss_acquire_cred_from(
+        minor_status: *mut OM_uint32,
+        desired_name: GssName,
+        time_req: OM_uint32,
+        desired_mechs: *mut c_void,
+        cred_usage: c_int,
+        cred_store: *const GssKeyValueSet,
+        output_cred_handle: *mut GssCred,
+        actual_mechs: *mut c_void,
+        time_rec: *mut OM_uint32,
+    ) -> OM_uint32;
+
+    fn gss_inquire_cred(
+        minor_status: *mut OM_uint32,
+        cred_handle: GssCred,
+        name: *mut GssName,
+        lifetime: *mut OM_uint32,
+        cred_usage: *mut c_int,
+        mechanisms: *mut c_void,
+    ) -> OM_uint32;
+
+    fn gss_release_cred(minor_status: *mut OM_uint32, cred_handle: *mut GssCred) -> OM_uint32;
+
+    fn gss_init_sec_context(
+        minor_status: *mut OM_uint32,
+        initiator_cred_handle: GssCred,
+        context_handle: *mut GssCtx,
+        target_name: GssName,
+        mech_type: GssOid,
+        req_flags: OM_uint32,
+        time_req: OM_uint32,
+        input_chan_bindings: *mut c_void,
+        input_token: *const GssBufferDesc,
+        actual_mech_type: *mut GssOid,
+        output_token: *mut GssBufferDesc,
+        ret_flags: *mut OM_uint32,
+        time_rec: *mut OM_uint32,
+    ) -> OM_uint32;
+
+    #[cfg(test)]
+    fn gss_accept_sec_context(
+        minor_status: *mut OM_uint32,
+        context_handle: *mut GssCtx,
+        acceptor_cred_handle: GssCred,
+        input_token_buffer: *const GssBufferDesc,
+        input_chan_bindings: *mut c_void,
+        src_name: *mut GssName,
+        mech_type: *mut GssOid,
+        output_token: *mut GssBufferDesc,
+        ret_flags: *mut OM_uint32,
+        time_rec: *mut OM_uint32,
+        delegated_cred_handle: *mut GssCred,
+    ) -> OM_uint32;
+
+    fn gss_delete_sec_context(
+        minor_status: *mut OM_uint32,
+        context_handle: *mut GssCtx,
+        output_token: *mut GssBufferDesc,
+    ) -> OM_uint32;
+
+    fn gss_wrap(
+        minor_status: *mut OM_uint32,
+        context_handle: GssCtx,
+        conf_req_flag: c_int,
+        qop_req: OM_uint32,
+        input_message_buffer: *const GssBufferDesc,
+        conf_state: *mut c_int,
+        output_message_buffer: *mut GssBufferDesc,
+    ) -> OM_uint32;
+
+    fn gss_wrap_size_limit(
+        minor_status: *mut OM_uint32,
+        context_handle: GssCtx,
+        conf_req_flag: c_int,
+        qop_req: OM_uint32,
+        req_output_size: OM_uint32,
+        max_input_size: *mut OM_uint32,
+    ) -> OM_uint32;
+
+    fn gss_unwrap(
+        minor_status: *mut OM_uint32,
+        context_handle: GssCtx,
+        input_message_buffer: *const GssBufferDesc,
+        output_message_buffer: *mut GssBufferDesc,
+        conf_state: *mut c_int,
+        qop_state: *mut OM_uint32,
+    ) -> OM_uint32;
+
+    fn gss_release_buffer(minor_status: *mut OM_uint32, buffer: *mut GssBufferDesc) -> OM_uint32;
+
+    fn gss_display_status(
+        minor_status: *mut OM_uint32,
+        status_value: OM_uint32,
+        status_type: c_int,
+        mech_type: GssOid,
+        message_context: *mut OM_uint32,
+        status_string: *mut GssBufferDesc,
+    ) -> OM_uint32;
+}
+
+/// Whether the major status reports a calling or routine error.
+fn gss_error(major: OM_uint32) -> bool {
+    major & 0xffff0000 != 0
+}
+
+/// A buffer borrowing the given bytes as the input of a GSS-API call.
+fn input_buffer(bytes: &[u8]) -> GssBufferDesc {
+    GssBufferDesc {
+        length: bytes.len(),
+        value: bytes.as_ptr() as *mut c_void,
+    }
+}
+
+fn empty_buffer() -> GssBufferDesc {
+    GssBufferDesc {
+        length: 0,
+        value: ptr::null_mut(),
+    }
+}
+
+/// Copy the bytes of a buffer allocated by the GSS-API and release it.
+fn take_buffer(mut buffer: GssBufferDesc) -> Vec<u8> {
+    let bytes = if buffer.value.is_null() {
+        vec![]
+    } else {
+        // SAFETY: the buffer holds `length` bytes allocated by the GSS-API.
+        unsafe { slice::from_raw_parts(buffer.value as *const u8, buffer.length) }.to_vec()
+    };
+    let mut minor = 0;
+    // SAFETY: the buffer was allocated by the GSS-API and is released once.
+    unsafe { gss_release_buffer(&mut minor, &mut buffer) };
+    bytes
+}
+
+/// The messages of a GSS-API status code.
+fn display_status(status: OM_uint32, status_type: c_int) -> Vec<String> {
+    let mut messages = vec![];
+    let mut message_context = 0;
+    loop {
+        let mut minor = 0;
+        let mut buffer = empty_buffer();
+        // SAFETY: all the pointers passed are valid for the call.
+        let major = unsafe {
+            gss_display_status(
+                &mut minor,
+                status,
+                status_type,
+                ptr::null_mut(),
+                &mut message_context,
+                &mut buffer,
+            )
+        };
+        if gss_error(major) {
+            break;
+        }
+        messages.push(String::from_utf8_lossy(&take_buffer(buffer)).into_owned());
+        if message_context == 0 {
+            break;
+        }
+    }
+    messages
+}
+
+/// An error of a GSS-API call, with the messages of its major and minor
+/// status codes.
+fn gss_exception(call: &str, major: OM_uint32, minor: OM_uint32) -> Error {
+    let mut messages = display_status(major, GSS_C_GSS_CODE);
+    if minor != 0 {
+        messages.append(&mut display_status(minor, GSS_C_MECH_CODE));
+    }
+    Error::new(IOException::Sasl(format!(
+        "GSSException: {} failed: {}",
+        call,
+        messages.join(": ")
+    )))
+}
+
+/// An internal GSS-API name.
+struct Name {
+    handle: GssName,
+}
+
+impl Name {
+    fn import(name: &str, name_type: GssOid) -> anyhow::Result<Self> {
+        let buffer = input_buffer(name.as_bytes());
+        let mut handle = ptr::null_mut();
+        let mut minor = 0;
+        // SAFETY: `buffer` borrows `name`, which outlives the call.
+        let major = unsafe { gss_import_name(&mut minor, &buffer, name_type, &mut handle) };
+        if gss_error(major) {
+            return Err(gss_exception("gss_import_name", major, minor));
+        }
+        Ok(Self { handle })
+    }
+
+    fn display(&self) -> anyhow::Result<String> {
+        let mut buffer = empty_buffer();
+        let mut minor = 0;
+        // SAFETY: the name handle is valid until dropped.
+        let major =
+            unsafe { gss_display_name(&mut minor, self.handle, &mut buffer, ptr::null_mut()) };
+        if gss_error(major) {
+            return Err(gss_exception("gss_display_name", major, minor));
+        }
+        Ok(String::from_utf8_lossy(&take_buffer(buffer)).into_owned())
+    }
+}
+
+impl Drop for Name {
+    fn drop(&mut self) {
+        let mut minor = 0;
+        // SAFETY: the handle is owned by this name and released once.
+        unsafe { gss_release_name(&mut minor, &mut self.handle) };
+    }
+}
+
+/// The Kerberos credentials of a client principal.
+pub(crate) struct Credential {
+    handle: GssCred,
+}
+
+// SAFETY: MIT credential handles may be moved between threads, the
+// credential is only used by one thread at a time
+unsafe impl Send for Credential {}
+
+impl Credential {
+    /// Acquire the initiator credentials of `principal`. The tickets are
+    /// obtained with the keys of `keytab` if given, else they are taken
+    /// from the default credentials cache.
+    pub fn acquire(principal: Option<&str>, keytab: Option<&Path>) -> anyhow::Result<Self> {
+        let name = principal
+            .map(|principal| Name::import(principal, unsafe { GSS_KRB5_NT_PRINCIPAL_NAME }))
+            .transpose()?;
+        let mut store = vec![];
+        if let Some(keytab) = keytab {
+            store.push((
+                CString::new("client_keytab")?,
+                CString::new(keytab.to_string_lossy().as_bytes())?,
+            ));
+            // keep the tickets obtained with the keytab out of the default
+            // credentials cache
+            store.push((
+                CString::new("ccache")?,
+                CString::new(format!("MEMORY:hadoop-{}", principal.unwrap_or_default()))?,
+            ));
+        }
+        let elements = store
+            .iter()
+            .map(|(key, value)| GssKeyValueElement {
+                key: key.as_ptr(),
+                value: value.as_ptr(),
+            })
+            .collect::<Vec<_>>();
+        let cred_store = GssKeyValueSet {
+            count: elements.len() as OM_uint32,
+            elements: elements.as_ptr(),
+        };
+        let mut handle = ptr::null_mut();
+        let mut minor = 0;
+        // SAFETY: the name and the key-value elements borrowing `store`
+        // outlive the call.
+        let major = unsafe {
+            gss_acquire_cred_from(
+                &mut minor,
+                name.as_ref().map_or(ptr::null_mut(), |name| name.handle),
+                GSS_C_INDEFINITE,
+                ptr::null_mut(),
+                GSS_C_INITIATE,
+                &cred_store,
+                &mut handle,
+                ptr::null_mut(),
+                ptr::null_mut(),
+            )
+        };
+        if gss_error(major) {
+            return Err(gss_exception("gss_acquire_cred_from", major, minor));
+        }
+        Ok(Self { handle })
+    }
+
+    /// The name of the principal the credentials belong to.
+    pub fn get_name(&self) -> anyhow::Result<String> {
+        let mut name = Name {
+            handle: ptr::null_mut(),
+        };
+        let mut minor = 0;
+        // SAFETY: the credential handle is valid until dropped, and the
+        // returned name is released by `name`.
+        let major = unsafe {
+            gss_inquire_cred(
+                &mut minor,
+                self.handle,
+                &mut name.handle,
+                ptr::null_mut(),
+                ptr::null_mut(),
+                ptr::null_mut(),
+            )
+        };
+        if gss_error(major) {
+            return Err(gss_exception("gss_inquire_cred", major, minor));
+        }
+        name.display()
+    }
+}
+
+impl Drop for Credential {
+    fn drop(&mut self) {
+        let mut minor = 0;
+        // SAFETY: the handle is owned by this credential and released once.
+        unsafe { gss_release_cred(&mut minor, &mut self.handle) };
+    }
+}
+
+/// A Kerberos security context established with a service.
+pub(crate) struct SecurityContext {
+    credential: Credential,
+    target: Name,
+    handle: GssCtx,
+    established: bool,
+}
+
+// SAFETY: MIT security contexts may be moved between threads, the context
+// is only used by one thread at a time
+unsafe impl Send for SecurityContext {}
+
+impl SecurityContext {
+    /// Create a context to establish with the `service@host` service.
+    pub fn new(credential: Credential, service: &str, host: &str) -> anyhow::Result<Self> {
+        let target = Name::import(&format!("{}@{}", service, host), unsafe {
+            GSS_C_NT_HOSTBASED_SERVICE
+        })?;
+        Ok(Self {
+            credential,
+            target,
+            handle: ptr::null_mut(),
+            established: false,
+        })
+    }
+
+    /// Process the token sent by the service, returns the token to send to
+    /// it, if any.
+    pub fn init_sec_context(&mut self, input_token: &[u8]) -> anyhow::Result<Option<Vec<u8>>> {
+        let input = input_buffer(input_token);
+        let mut output = empty_buffer();
+        let mut minor = 0;
+        // SAFETY: the handles are valid until dropped, and `input` borrows
+        // `input_token`, which outlives the call.
+        let major = unsafe {
+            gss_init_sec_context(
+                &mut minor,
+                self.credential.handle,
+                &mut self.handle,
+                self.target.handle,
+                gss_mech_krb5,
+                GSS_C_MUTUAL_FLAG | GSS_C_CONF_FLAG | GSS_C_INTEG_FLAG,
+                GSS_C_INDEFINITE,
+                ptr::null_mut(),
+                &input,
+                ptr::null_mut(),
+                &mut output,
+                ptr::null_mut(),
+                ptr::null_mut(),
+            )
+        };
+        let token = take_buffer(output);
+        if gss_error(major) {
+            return Err(gss_exception("gss_init_sec_context", major, minor));
+        }
+        self.established = major & GSS_S_CONTINUE_NEEDED == 0;
+        Ok(if token.is_empty() { None } else { Some(token) })
+    }
+
+    /// Whether the context is established.
+    pub fn is_established(&self) -> bool {
+        self.established
+    }
+
+    /// Protect the message for the service, encrypting it if `conf` is set.
+    pub fn wrap(&self, message: &[u8], conf: bool) -> anyhow::Result<Vec<u8>> {
+        wrap(self.handle, message, conf)
+    }
+
+    /// The maximum size of the messages that wrap into at most
+    /// `max_output_size` bytes.
+    pub fn wrap_size_limit(&self, conf: bool, max_output_size: u32) -> anyhow::Result<u32> {
+        let mut max_input_size = 0;
+        let mut minor = 0;
+        // SAFETY: the context handle is valid until dropped.
+        let major = unsafe {
+            gss_wrap_size_limit(
+                &mut minor,
+                self.handle,
+                conf as c_int,
+                0,
+                max_output_size,
+                &mut max_input_size,
+            )
+        };
+        if gss_error(major) {
+            return Err(gss_exception("gss_wrap_size_limit", major, minor));
+        }
+        Ok(max_input_size)
+    }
+
+    /// Verify and decrypt a message protected by the service.
+    pub fn unwrap(&self, message: &[u8]) -> anyhow::Result<Vec<u8>> {
+        unwrap(self.handle, message)
+    }
+}
+
+impl Drop for SecurityContext {
+    fn drop(&mut self) {
+        delete_sec_context(&mut self.handle);
+    }
+}
+
+/// The service side of a security context, established with the keys of
+/// the default keytab, to test the client against.
+#[cfg(test)]
+pub(crate) struct AcceptorContext {
+    handle: GssCtx,
+}
+
+#[cfg(test)]
+impl AcceptorContext {
+    pub fn new() -> Self {
+        Self {
+            handle: ptr::null_mut(),
+        }
+    }
+
+    /// Process the token sent by the client, returns the token to send to
+    /// it, if any.
+    pub fn accept_sec_context(&mut self, input_token: &[u8]) -> anyhow::Result<Option<Vec<u8>>> {
+        let input = input_buffer(input_token);
+        let mut output = empty_buffer();
+        let mut minor = 0;
+        // SAFETY: the context handle is valid until dropped, and `input`
+        // borrows `input_token`, which outlives the call.
+        let major = unsafe {
+            gss_accept_sec_context(
+                &mut minor,
+                &mut self.handle,
+                ptr::null_mut(),
+                &input,
+                ptr::null_mut(),
+                ptr::null_mut(),
+                ptr::null_mut(),
+                &mut output,
+                ptr::null_mut(),
+                ptr::null_mut(),
+                ptr::null_mut(),
+            )
+        };
+        let token = take_buffer(output);
+        if gss_error(major) {
+            return Err(gss_exception("gss_accept_sec_context", major, minor));
+        }
+        Ok(if token.is_empty() { None } else { Some(token) })
+    }
+
+    /// Protect the message for the client, encrypting it if `conf` is set.
+    pub fn wrap(&self, message: &[u8], conf: bool) -> anyhow::Result<Vec<u8>> {
+        wrap(self.handle, message, conf)
+    }
+
+    /// Verify and decrypt a message protected by the client.
+    pub fn unwrap(&self, message: &[u8]) -> anyhow::Result<Vec<u8>> {
+        unwrap(self.handle, message)
+    }
+}
+
+#[cfg(test)]
+impl Drop for AcceptorContext {
+    fn drop(&mut self) {
+        delete_sec_context(&mut self.handle);
+    }
+}
+
+fn wrap(handle: GssCtx, message: &[u8], conf: bool) -> anyhow::Result<Vec<u8>> {
+    let input = input_buffer(message);
+    let mut output = empty_buffer();
+    let mut minor = 0;
+    // SAFETY: the context handle is valid while its owner is alive, and
+    // `input` borrows `message`, which outlives the call.
+    let major = unsafe {
+        gss_wrap(
+            &mut minor,
+            handle,
+            conf as c_int,
+            0,
+            &input,
+            ptr::null_mut(),
+            &mut output,
+        )
+    };
+    let wrapped = take_buffer(output);
+    if gss_error(major) {
+        return Err(gss_exception("gss_wrap", major, minor));
+    }
+    Ok(wrapped)
+}
+
+fn unwrap(handle: GssCtx, message: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let input = input_buffer(message);
+    let mut output = empty_buffer();
+    let mut minor = 0;
+    // SAFETY: the context handle is valid while its owner is alive, and
+    // `input` borrows `message`, which outlives the call.
+    let major = unsafe {
+        gss_unwrap(
+            &mut minor,
+            handle,
+            &input,
+            &mut output,
+            ptr::null_mut(),
+            ptr::null_mut(),
+        )
+    };
+    let unwrapped = take_buffer(output);
+    if gss_error(major) {
+        return Err(gss_exception("gss_unwrap", major, minor));
+    }
+    Ok(unwrapped)
+}
+
+fn delete_sec_context(handle: &mut GssCtx) {
+    if !handle.is_null() {
+        let mut minor = 0;
+        // SAFETY: the handle is owned by the caller and deleted once.
+        unsafe { gss_delete_sec_context(&mut minor, handle, ptr::null_mut()) };
+    }
+}
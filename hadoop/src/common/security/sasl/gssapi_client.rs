@@ -0,0 +1,353 @@
+use super::{
+    gss::{Credential, SecurityContext},
+    SaslClient,
+};
+use crate::common::io::IOException;
+use anyhow::Error;
+
+/// The security layers of the GSSAPI mechanism, in the order of the bits
+/// of the security layer bitmask.
+const QOP_LAYERS: [(&str, u8); 3] = [("auth", 1), ("auth-int", 2), ("auth-conf", 4)];
+
+/// The maximum size of the wrapped messages the client accepts.
+const MAX_RECEIVE_BUFFER_SIZE: u32 = 65536;
+
+enum State {
+    // the security context is being established
+    Context,
+    // waiting for the security layers offered by the server
+    SecurityLayer,
+    Complete,
+}
+
+/// An implementation of the GSSAPI SASL mechanism client, as defined in
+/// RFC 4752, used to authenticate with Kerberos credentials.
+pub struct GssapiClient {
+    context: SecurityContext,
+    // the qualities of protection the client accepts, in order of preference
+    qop: Vec<String>,
+    negotiated_qop: Option<String>,
+    // the maximum size of the wrapped messages the server accepts
+    send_max_buffer_size: u32,
+    state: State,
+}
+
+impl GssapiClient {
+    /// Create a client authenticating to the `server_name` host of the
+    /// `protocol` service with the given credentials.
+    pub(crate) fn new(
+        credential: Credential,
+        protocol: &str,
+        server_name: &str,
+        qop: &[&str],
+    ) -> anyhow::Result<Self> {
+        Ok(Self {
+            context: SecurityContext::new(credential, protocol, server_name)?,
+            qop: qop.iter().map(|q| q.to_string()).collect(),
+            negotiated_qop: None,
+            send_max_buffer_size: 0,
+            state: State::Context,
+        })
+    }
+
+    /// Select the security layer among the ones offered by the server, and
+    /// generate the wrapped response.
+    fn do_final_handshake(&mut self, challenge: &[u8]) -> anyhow::Result<Vec<u8>> {
+        let offer = self.context.unwrap(challenge)?;
+        if offer.len() != 4 {
+            return Err(sasl_exception(&format!(
+                "Invalid security layer offer length: {}",
+                offer.len()
+            )));
+        }
+        let server_layers = offer[0];
+        let (qop, layer) = self
+            .qop
+            .iter()
+            .filter_map(|q| QOP_LAYERS.iter().find(|(name, _)| name == q))
+            .find(|(_, layer)| server_layers & layer != 0)
+            .ok_or_else(|| sasl_exception("No common protection layer between client and server"))?
+            .to_owned();
+        // the maximum buffer size must be 0 without a security layer
+        let max_buffer_size = if layer == 1 {
+            0
+        } else {
+            MAX_RECEIVE_BUFFER_SIZE
+        };
+        let mut response = max_buffer_size.to_be_bytes();
+        response[0] = layer;
+        let wrapped = self.context.wrap(&response, false)?;
+        self.send_max_buffer_size = u32::from_be_bytes([0, offer[1], offer[2], offer[3]]);
+        self.negotiated_qop = Some(qop.to_owned());
+        self.state = State::Complete;
+        Ok(wrapped)
+    }
+
+    /// Whether the negotiated security layer encrypts the messages, failing
+    /// if no security layer was negotiated.
+    fn security_layer(&self) -> anyhow::Result<bool> {
+        match self.negotiated_qop.as_deref() {
+            Some("auth-int") => Ok(false),
+            Some("auth-conf") => Ok(true),
+            _ => Err(sasl_exception("No security layer negotiated")),
+        }
+    }
+}
+
+impl SaslClient for GssapiClient {
+    fn get_mechanism_name(&self) -> &str {
+        "GSSAPI"
+    }
+
+    fn has_initial_response(&self) -> bool {
+        true
+    }
+
+    fn evaluate_challenge(&mut self, challenge: &[u8]) -> anyhow::Result<Option<Vec<u8>>> {
+        match self.state {
+            State::Context => {
+                let token = self.context.init_sec_context(challenge)?;
+                if self.context.is_established() {
+                    self.state = State::SecurityLayer;
+                }
+                Ok(Some(token.unwrap_or_default()))
+            }
+            State::SecurityLayer => Ok(Some(self.do_final_handshake(challenge)?)),
+            State::Complete => Err(sasl_exception("GSSAPI authentication already complete")),
+        }
+    }
+
+    fn is_complete(&self) -> bool {
+        matches!(self.state, State::Complete)
+    }
+
+    fn get_negotiated_qop(&self) -> Option<&str> {
+        self.negotiated_qop.as_deref()
+    }
+
+    fn get_raw_send_size(&self) -> anyhow::Result<usize> {
+        let conf = self.security_layer()?;
+        let raw_send_size = self
+            .context
+            .wrap_size_limit(conf, self.send_max_buffer_size)?;
+        if raw_send_size == 0 {
+            return Err(sasl_exception(&format!(
+                "Server max buffer size {} is too small to wrap messages",
+                self.send_max_buffer_size
+            )));
+        }
+        Ok(raw_send_size as usize)
+    }
+
+    fn wrap(&mut self, outgoing: &[u8]) -> anyhow::Result<Vec<u8>> {
+        let conf = self.security_layer()?;
+        self.context.wrap(outgoing, conf)
+    }
+
+    fn unwrap(&mut self, incoming: &[u8]) -> anyhow::Result<Vec<u8>> {
+        self.security_layer()?;
+        self.context.unwrap(incoming)
+    }
+}
+
+fn sasl_exception(msg: &str) -> Error {
+    Error::new(IOException::Sasl(format!("GSSAPI: {}", msg)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::security::sasl::gss::AcceptorContext;
+    use std::{
+        env, fs,
+        net::{TcpListener, TcpStream},
+        path::{Path, PathBuf},
+        process::{Child, Command},
+        thread,
+        time::Duration,
+    };
+    use uuid::Uuid;
+
+    const REALM: &str = "EXAMPLE.COM";
+
+    /// The MIT Kerberos commands run by [`MiniKdc`].
+    const KDC_COMMANDS: [&str; 3] = ["krb5kdc", "kdb5_util", "kadmin.local"];
+
+    /// A MIT Kerberos KDC running in a temporary directory, with the keytabs
+    /// of a user and of the `nn/localhost` service.
+    struct MiniKdc {
+        dir: PathBuf,
+        kdc: Child,
+    }
+
+    impl MiniKdc {
+        /// Start the KDC, or return `None` if the MIT krb5kdc, kdb5_util and
+        /// kadmin.local commands are not all on the PATH.  The Kerberos
+        /// library of the test process is pointed at the KDC through the
+        /// environment.
+        fn start() -> anyhow::Result<Option<Self>> {
+            let path = env::var_os("PATH").unwrap_or_default();
+            let missing: Vec<&str> = KDC_COMMANDS
+                .into_iter()
+                .filter(|command| !env::split_paths(&path).any(|dir| dir.join(command).is_file()))
+                .collect();
+            if !missing.is_empty() {
+                eprintln!(
+                    "skipping, the KDC commands {:?} are not on the PATH",
+                    missing
+                );
+                return Ok(None);
+            }
+            let dir = env::temp_dir().join(format!("minikdc-{}", Uuid::new_v4()));
+            fs::create_dir_all(&dir)?;
+            let port = TcpListener::bind("127.0.0.1:0")?.local_addr()?.port();
+            fs::write(
+                dir.join("krb5.conf"),
+                format!(
+                    "[libdefaults]\n default_realm = {REALM}\n dns_lookup_kdc = false\n \
+                     dns_canonicalize_hostname = false\n rdns = false\n \
+                     udp_preference_limit = 1\n\
+                     [realms]\n {REALM} = {{\n  kdc = 127.0.0.1:{port}\n }}\n\
+                     [domain_realm]\n localhost = {REALM}\n"
+                ),
+            )?;
+            fs::write(
+                dir.join("kdc.conf"),
+                format!(
+                    "[kdcdefaults]\n kdc_ports = {port}\n kdc_tcp_ports = {port}\n\
+                     [realms]\n {REALM} = {{\n  database_name = {0}/principal\n  \
+                     key_stash_file = {0}/stash\n }}\n",
+                    dir.display()
+                ),
+            )?;
+            env::set_var("KRB5_CONFIG", dir.join("krb5.conf"));
+            env::set_var("KRB5_KDC_PROFILE", dir.join("kdc.conf"));
+            env::set_var("KRB5_KTNAME", dir.join("nn.keytab"));
+            env::set_var("KRB5RCACHETYPE", "none");
+
+            run(Command::new("kdb5_util").args(["-r", REALM, "create", "-s", "-P", "master"]))?;
+            for (principal, keytab) in [("user", "user.keytab"), ("nn/localhost", "nn.keytab")] {
+                let principal = format!("{}@{}", principal, REALM);
+                for query in [
+                    format!("addprinc -randkey {}", principal),
+                    format!("ktadd -k {} {}", dir.join(keytab).display(), principal),
+                ] {
+                    run(Command::new("kadmin.local").args(["-r", REALM, "-q", &query]))?;
+                }
+            }
+            let kdc = Command::new("krb5kdc").args(["-n", "-r", REALM]).spawn()?;
+            let mut kdc = Self { dir, kdc };
+            while TcpStream::connect(("127.0.0.1", port)).is_err() {
+                if let Some(status) = kdc.kdc.try_wait()? {
+                    return Err(Error::msg(format!("krb5kdc exited: {}", status)));
+                }
+                thread::sleep(Duration::from_millis(10));
+            }
+            Ok(Some(kdc))
+        }
+
+        fn user_keytab(&self) -> PathBuf {
+            self.dir.join("user.keytab")
+        }
+    }
+
+    impl Drop for MiniKdc {
+        fn drop(&mut self) {
+            let _ = self.kdc.kill();
+            let _ = self.kdc.wait();
+            let _ = fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    fn run(command: &mut Command) -> anyhow::Result<()> {
+        let output = command.output()?;
+        if !output.status.success() {
+            return Err(Error::msg(format!(
+                "{:?} failed: {}",
+                command,
+                String::from_utf8_lossy(&output.stderr)
+            )));
+        }
+        Ok(())
+    }
+
+    /// Authenticate the client with the service, which offers the given
+    /// security layers and maximum buffer size.
+    fn authenticate(
+        client: &mut GssapiClient,
+        server: &mut AcceptorContext,
+        layers: u8,
+        max_buffer_size: u32,
+    ) -> anyhow::Result<()> {
+        let mut token = client.evaluate_challenge(&[])?.unwrap_or_default();
+        while !matches!(client.state, State::SecurityLayer) {
+            let challenge = server.accept_sec_context(&token)?.unwrap_or_default();
+            token = client.evaluate_challenge(&challenge)?.unwrap_or_default();
+        }
+        let mut offer = max_buffer_size.to_be_bytes();
+        offer[0] = layers;
+        let response = client.evaluate_challenge(&server.wrap(&offer, false)?)?;
+        assert_eq!(4, server.unwrap(&response.unwrap_or_default())?.len());
+        assert!(client.is_complete());
+        Ok(())
+    }
+
+    #[test]
+    fn test_missing_credentials() {
+        let err = Credential::acquire(
+            Some("user@EXAMPLE.COM"),
+            Some(Path::new("/nonexistent/user.keytab")),
+        )
+        .and_then(|credential| {
+            GssapiClient::new(credential, "nn", "localhost", &["auth"])?.evaluate_challenge(&[])
+        })
+        .err()
+        .unwrap();
+        assert!(matches!(
+            err.downcast_ref::<IOException>(),
+            Some(IOException::Sasl(_))
+        ));
+    }
+
+    #[test]
+    fn test_security_layer() -> anyhow::Result<()> {
+        let Some(kdc) = MiniKdc::start()? else {
+            return Ok(());
+        };
+        let new_client = |qop| -> anyhow::Result<GssapiClient> {
+            let credential =
+                Credential::acquire(Some(&format!("user@{}", REALM)), Some(&kdc.user_keytab()))?;
+            GssapiClient::new(credential, "nn", "localhost", &[qop])
+        };
+
+        // the messages are wrapped within the buffer size of the server
+        for qop in ["auth-int", "auth-conf"] {
+            let mut client = new_client(qop)?;
+            let mut server = AcceptorContext::new();
+            authenticate(&mut client, &mut server, 7, 1024)?;
+            assert_eq!(Some(qop), client.get_negotiated_qop());
+            let raw_send_size = client.get_raw_send_size()?;
+            assert!(raw_send_size > 0 && raw_send_size < 1024);
+            let message = vec![b'x'; raw_send_size];
+            let wrapped = client.wrap(&message)?;
+            assert!(wrapped.len() <= 1024);
+            assert_eq!(message, server.unwrap(&wrapped)?);
+            assert_eq!(
+                b"reply",
+                &client.unwrap(&server.wrap(b"reply", qop == "auth-conf")?)?[..]
+            );
+        }
+
+        // no message fits in a buffer of size 0
+        let mut client = new_client("auth-int")?;
+        authenticate(&mut client, &mut AcceptorContext::new(), 2, 0)?;
+        let err = client.get_raw_send_size().unwrap_err();
+        assert!(err.to_string().contains("too small to wrap messages"));
+
+        // there is nothing to wrap without a security layer
+        let mut client = new_client("auth")?;
+        authenticate(&mut client, &mut AcceptorContext::new(), 1, 0)?;
+        assert!(client.get_raw_send_size().is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,13 @@
+mod digest_md5_client;
+#[cfg(feature = "gssapi")]
+mod gss;
+#[cfg(feature = "gssapi")]
+mod gssapi_client;
+mod sasl_client;
+
+pub use digest_md5_client::DigestMd5Client;
+#[cfg(feature = "gssapi")]
+pub(crate) use gss::Credential;
+#[cfg(feature = "gssapi")]
+pub use gssapi_client::GssapiClient;
+pub use sasl_client::SaslClient;
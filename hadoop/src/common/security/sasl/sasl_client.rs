@@ -0,0 +1,35 @@
+/// Performs SASL authentication as a client.
+///
+/// A protocol library uses a mechanism specific client to exchange
+/// challenges and responses with the server until the authentication has
+/// completed.
+pub trait SaslClient: Send {
+    /// Returns the IANA-registered mechanism name of this SASL client.
+    fn get_mechanism_name(&self) -> &str;
+
+    /// Determines whether this mechanism has an optional initial response.
+    fn has_initial_response(&self) -> bool;
+
+    /// Evaluates the challenge data and generates a response, `None` if there
+    /// is no response to send to the server.
+    fn evaluate_challenge(&mut self, challenge: &[u8]) -> anyhow::Result<Option<Vec<u8>>>;
+
+    /// Determines whether the authentication exchange has completed.
+    fn is_complete(&self) -> bool;
+
+    /// The quality of protection negotiated, once the authentication
+    /// exchange has completed.
+    fn get_negotiated_qop(&self) -> Option<&str>;
+
+    /// The maximum size of the messages that may be wrapped at once, given
+    /// the buffer size negotiated with the server.
+    fn get_raw_send_size(&self) -> anyhow::Result<usize>;
+
+    /// Wraps a message to be sent to the server, with the integrity and
+    /// optionally privacy protection of the negotiated security layer.
+    fn wrap(&mut self, outgoing: &[u8]) -> anyhow::Result<Vec<u8>>;
+
+    /// Unwraps a message received from the server, with the negotiated
+    /// security layer.
+    fn unwrap(&mut self, incoming: &[u8]) -> anyhow::Result<Vec<u8>>;
+}
@@ -0,0 +1,71 @@
+use super::QualityOfProtection;
+use crate::common::{
+    conf::Configuration, fs::common_configuration_keys_public::HADOOP_RPC_PROTECTION,
+};
+use anyhow::Error;
+use std::net::IpAddr;
+
+/// Provides the SASL properties of the connections, i.e. the qualities of
+/// protection to negotiate, from the `hadoop.rpc.protection` configuration.
+pub struct SaslPropertiesResolver {
+    // the SASL QOP values, in order of preference
+    qop: Vec<&'static str>,
+}
+
+impl SaslPropertiesResolver {
+    /// Returns an instance of SaslPropertiesResolver.
+    // TODO: support hadoop.security.saslproperties.resolver.class
+    pub fn get_instance(conf: &Configuration) -> anyhow::Result<Self> {
+        let qop = conf
+            .get_trimmed_strings_with_default(HADOOP_RPC_PROTECTION, &["authentication"])
+            .iter()
+            .map(|name| {
+                QualityOfProtection::from_str_name(name)
+                    .map(|qop| qop.get_sasl_qop())
+                    .ok_or_else(|| {
+                        Error::msg(format!(
+                            "Invalid value for {}: {}",
+                            HADOOP_RPC_PROTECTION, name
+                        ))
+                    })
+            })
+            .collect::<anyhow::Result<_>>()?;
+        Ok(Self { qop })
+    }
+
+    /// The default qualities of protection, in order of preference.
+    pub fn get_default_properties(&self) -> &[&'static str] {
+        &self.qop
+    }
+
+    /// The qualities of protection of the connections to the server, in order
+    /// of preference.
+    pub fn get_client_properties(&self, _server_address: &IpAddr) -> &[&'static str] {
+        self.get_default_properties()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_qop_properties() -> anyhow::Result<()> {
+        let mut conf = Configuration::new();
+        let addr = "127.0.0.1".parse()?;
+        assert_eq!(
+            ["auth"],
+            SaslPropertiesResolver::get_instance(&conf)?.get_client_properties(&addr)
+        );
+
+        conf.set("hadoop.rpc.protection", "Privacy, integrity");
+        assert_eq!(
+            ["auth-conf", "auth-int"],
+            SaslPropertiesResolver::get_instance(&conf)?.get_client_properties(&addr)
+        );
+
+        conf.set("hadoop.rpc.protection", "secret");
+        assert!(SaslPropertiesResolver::get_instance(&conf).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,877 @@
+#[cfg(feature = "gssapi")]
+use super::sasl::{Credential, GssapiClient};
+use super::{
+    sasl::{DigestMd5Client, SaslClient},
+    token::Token,
+    AuthMethod, SaslPropertiesResolver, SaslRpcServer, SecurityUtil, UserGroupInformation,
+};
+use crate::common::{
+    conf::Configuration,
+    io::IOException,
+    ipc::{
+        prepend_buf_size, AuthProtocol, IpcStreams, ProtocolInfo, RemoteException, RpcConstants,
+        RpcKind,
+    },
+    rpc_request_header_proto::OperationProto,
+    rpc_response_header_proto::RpcStatusProto,
+    rpc_sasl_proto::{SaslAuth, SaslState},
+    util::ProtoUtil,
+    RpcResponseHeaderProto, RpcSaslProto,
+};
+use anyhow::Error;
+use prost::Message;
+use std::{
+    io::{self, Read, Write},
+    mem,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+};
+
+/// The SASL client of a connection, shared with the streams wrapping the
+/// messages once the negotiation has completed.
+type SharedSaslClient = Arc<Mutex<Box<dyn SaslClient>>>;
+
+/// A utility class that encapsulates SASL logic for RPC client
+pub struct SaslRpcClient {
+    ugi: UserGroupInformation,
+    protocol: &'static ProtocolInfo,
+    server_addr: SocketAddr,
+    conf: Configuration,
+    sasl_client: Option<SharedSaslClient>,
+    auth_method: AuthMethod,
+}
+
+impl SaslRpcClient {
+    /// Create a SaslRpcClient that can be used by a RPC client to negotiate
+    /// SASL authentication with a RPC server
+    pub fn new(
+        ugi: &UserGroupInformation,
+        protocol: &'static ProtocolInfo,
+        server_addr: &SocketAddr,
+        conf: &Configuration,
+    ) -> Self {
+        Self {
+            ugi: ugi.to_owned(),
+            protocol,
+            server_addr: server_addr.to_owned(),
+            conf: conf.to_owned(),
+            sasl_client: None,
+            auth_method: AuthMethod::Simple,
+        }
+    }
+
+    pub fn get_auth_method(&self) -> AuthMethod {
+        self.auth_method
+    }
+
+    /// The quality of protection negotiated with the server, if the SASL
+    /// negotiation has completed.
+    pub fn get_negotiated_qop(&self) -> Option<String> {
+        let sasl_client = self.sasl_client.as_ref()?.lock().unwrap();
+        if !sasl_client.is_complete() {
+            return None;
+        }
+        sasl_client.get_negotiated_qop().map(str::to_owned)
+    }
+
+    /// Whether the messages are wrapped with a security layer, i.e. the
+    /// negotiated quality of protection is integrity or privacy.
+    pub fn use_wrap(&self) -> bool {
+        // SASL wrapping is only used if the connection has a QOP, and
+        // the value is not auth.  ex. auth-int & auth-priv
+        self.get_negotiated_qop()
+            .is_some_and(|qop| !qop.eq_ignore_ascii_case("auth"))
+    }
+
+    /// Get SASL wrapped input stream if SASL QoP requires unwrapping,
+    /// otherwise return original stream.
+    pub(crate) fn get_input_stream(&self, input: Box<dyn Read + Send>) -> Box<dyn Read + Send> {
+        match &self.sasl_client {
+            Some(sasl_client) if self.use_wrap() => {
+                Box::new(WrappedInputStream::new(Arc::clone(sasl_client), input))
+            }
+            _ => input,
+        }
+    }
+
+    /// Get SASL wrapped output stream if SASL QoP requires wrapping,
+    /// otherwise return original stream.
+    pub(crate) fn get_output_stream(
+        &self,
+        output: Box<dyn Write + Send>,
+    ) -> anyhow::Result<Box<dyn Write + Send>> {
+        Ok(match &self.sasl_client {
+            Some(sasl_client) if self.use_wrap() => {
+                Box::new(WrappedOutputStream::new(Arc::clone(sasl_client), output)?)
+            }
+            _ => output,
+        })
+    }
+
+    /// Instantiate a sasl client for the first supported auth type in the
+    /// given list.  The auth type must be defined, enabled, and the user
+    /// must possess the required credentials, else the next auth is tried.
+    fn select_sasl_client(&mut self, auth_types: &[SaslAuth]) -> anyhow::Result<SaslAuth> {
+        let mut selected_auth_type = None;
+        let mut switch_to_simple = false;
+        for auth_type in auth_types {
+            let auth_method = match Self::get_valid_auth_method(auth_type) {
+                Some(auth_method) => auth_method,
+                // unknown auth
+                None => continue,
+            };
+            if auth_method == AuthMethod::Simple {
+                switch_to_simple = true;
+            } else {
+                self.sasl_client = self
+                    .create_sasl_client(auth_method, auth_type)?
+                    .map(|sasl_client| Arc::new(Mutex::new(sasl_client)));
+                if self.sasl_client.is_none() {
+                    // client lacks credentials, try next
+                    continue;
+                }
+            }
+            selected_auth_type = Some(auth_type.to_owned());
+            break;
+        }
+        match selected_auth_type {
+            Some(auth_type) if self.sasl_client.is_some() || switch_to_simple => {
+                log::debug!(
+                    "Use {} authentication for protocol {}",
+                    auth_type.method,
+                    self.protocol.protocol_name
+                );
+                Ok(auth_type)
+            }
+            _ => {
+                let server_auth_methods = auth_types
+                    .iter()
+                    .map(|auth_type| auth_type.method.as_str())
+                    .collect::<Vec<_>>();
+                Err(Error::new(IOException::AccessControl(format!(
+                    "Client cannot authenticate via:[{}]",
+                    server_auth_methods.join(", ")
+                ))))
+            }
+        }
+    }
+
+    /// The authentication method of the auth type, if it is known and uses
+    /// the mechanism of the method.
+    fn get_valid_auth_method(auth_type: &SaslAuth) -> Option<AuthMethod> {
+        AuthMethod::from_str_name(&auth_type.method)
+            .filter(|auth_method| auth_method.get_mechanism_name() == auth_type.mechanism)
+    }
+
+    /// Try to create a SaslClient for an authentication type.  May return
+    /// `None` if the type isn't supported or the client lacks the required
+    /// credentials.
+    fn create_sasl_client(
+        &self,
+        method: AuthMethod,
+        auth_type: &SaslAuth,
+    ) -> anyhow::Result<Option<Box<dyn SaslClient>>> {
+        let sasl_properties_resolver = SaslPropertiesResolver::get_instance(&self.conf)?;
+        let sasl_properties =
+            sasl_properties_resolver.get_client_properties(&self.server_addr.ip());
+        // SASL requires the client and server to use the same proto and serverId
+        // if necessary, auth types below will verify they are valid
+        let sasl_protocol = auth_type.protocol.as_deref().unwrap_or_default();
+        let sasl_server_name = auth_type.server_id.as_deref().unwrap_or_default();
+        match method {
+            AuthMethod::Token => {
+                let token = match self.get_server_token() {
+                    Some(token) => token,
+                    None => {
+                        log::debug!(
+                            "tokens aren't supported for this protocol or user doesn't have one"
+                        );
+                        return Ok(None);
+                    }
+                };
+                Ok(Some(Box::new(DigestMd5Client::new(
+                    sasl_protocol,
+                    sasl_server_name,
+                    &SaslRpcServer::encode_identifier(token.get_identifier()),
+                    &SaslRpcServer::encode_password(token.get_password()),
+                    sasl_properties,
+                ))))
+            }
+            AuthMethod::Kerberos => {
+                if !self.ugi.has_kerberos_credentials() {
+                    log::debug!("client isn't using kerberos");
+                    return Ok(None);
+                }
+                let server_principal = match self.get_server_principal(auth_type)? {
+                    Some(server_principal) => server_principal,
+                    None => {
+                        log::debug!("protocol doesn't use kerberos");
+                        return Ok(None);
+                    }
+                };
+                log::debug!(
+                    "RPC Server's Kerberos principal name for protocol={} is {}",
+                    self.protocol.protocol_name,
+                    server_principal
+                );
+                self.create_gssapi_client(sasl_protocol, sasl_server_name, sasl_properties)
+            }
+            method => Err(Error::msg(format!(
+                "Unknown authentication method {}",
+                method
+            ))),
+        }
+    }
+
+    /// Create a SaslClient authenticating with the Kerberos credentials of
+    /// the user.
+    #[cfg(feature = "gssapi")]
+    fn create_gssapi_client(
+        &self,
+        sasl_protocol: &str,
+        sasl_server_name: &str,
+        sasl_properties: &[&str],
+    ) -> anyhow::Result<Option<Box<dyn SaslClient>>> {
+        // the tickets of users logged in from a keytab are not in the ticket
+        // cache
+        let credential = match self.ugi.get_keytab() {
+            Some(keytab) => Credential::acquire(Some(&self.ugi.get_user_name()), Some(keytab))?,
+            None => Credential::acquire(None, None)?,
+        };
+        Ok(Some(Box::new(GssapiClient::new(
+            credential,
+            sasl_protocol,
+            sasl_server_name,
+            sasl_properties,
+        )?)))
+    }
+
+    #[cfg(not(feature = "gssapi"))]
+    fn create_gssapi_client(
+        &self,
+        _sasl_protocol: &str,
+        _sasl_server_name: &str,
+        _sasl_properties: &[&str],
+    ) -> anyhow::Result<Option<Box<dyn SaslClient>>> {
+        log::debug!("kerberos authentication requires the gssapi feature");
+        Ok(None)
+    }
+
+    /// Get the remote server's principal.  The value will be obtained from
+    /// the config and cross-checked against the server's advertised principal.
+    fn get_server_principal(&self, auth_type: &SaslAuth) -> anyhow::Result<Option<String>> {
+        let server_key = match self.protocol.server_principal {
+            Some(server_key) => server_key,
+            None => return Ok(None),
+        };
+        let (protocol, server_id) = match (&auth_type.protocol, &auth_type.server_id) {
+            (Some(protocol), Some(server_id)) => (protocol, server_id),
+            _ => {
+                return Err(Error::msg(
+                    "Server didn't advertise the Kerberos principal it uses",
+                ))
+            }
+        };
+        // construct server advertised principal for comparison
+        let server_principal = format!("{}/{}", protocol, server_id);
+        // TODO: support the server principal pattern of `<key>.pattern`
+        // check that the server advertised principal matches our conf
+        let conf_principal = self
+            .conf
+            .get(server_key, None)
+            .map(|principal| {
+                SecurityUtil::get_server_principal_for_addr(&principal, &self.server_addr.ip())
+            })
+            .unwrap_or_default();
+        log::debug!(
+            "getting serverKey: {} conf value: {}",
+            server_key,
+            conf_principal
+        );
+        if conf_principal.is_empty() {
+            return Err(Error::msg(
+                "Failed to specify server's Kerberos principal name",
+            ));
+        }
+        if SecurityUtil::get_host_from_principal(&conf_principal).is_none() {
+            return Err(Error::msg(format!(
+                "Kerberos principal name does NOT have the expected hostname part: {}",
+                conf_principal
+            )));
+        }
+        // the server advertises its principal without the realm
+        let conf_service_principal = conf_principal.split('@').next().unwrap_or_default();
+        if server_principal != conf_service_principal {
+            return Err(Error::msg(format!(
+                "Server has invalid Kerberos principal: {}, expecting: {}",
+                server_principal, conf_principal
+            )));
+        }
+        Ok(Some(server_principal))
+    }
+
+    /// Try to locate the required token for the server.
+    fn get_server_token(&self) -> Option<Token> {
+        // the protocol has no support for tokens without a token kind
+        let kind = self.protocol.token_kind?;
+        let service = SecurityUtil::build_token_service(&self.server_addr).to_string();
+        self.ugi
+            .get_tokens()
+            .into_iter()
+            .find(|token| token.get_kind() == kind && token.get_service() == service)
+    }
+
+    /// Do client side SASL authentication with server via the given
+    /// IpcStreams.
+    ///
+    /// Returns the [`AuthMethod`] used to authenticate, which is
+    /// [`AuthMethod::Simple`] if the server switched the client to simple
+    /// authentication.
+    pub(crate) fn sasl_connect(
+        &mut self,
+        ipc_streams: &mut IpcStreams,
+    ) -> anyhow::Result<AuthMethod> {
+        // redefined if/when a SASL negotiation starts, can be queried if the
+        // negotiation fails
+        self.auth_method = AuthMethod::Simple;
+
+        Self::send_sasl_message(
+            ipc_streams,
+            &RpcSaslProto {
+                state: SaslState::Negotiate.into(),
+                ..Default::default()
+            },
+        )?;
+
+        // loop until sasl is complete or a rpc error occurs
+        let mut done = false;
+        while !done {
+            let response_message = ipc_streams.read_response()?;
+            let mut value = &response_message[..];
+            let header = RpcResponseHeaderProto::decode_length_delimited(&mut value)?;
+            match header.status() {
+                // might get a RPC error during
+                RpcStatusProto::Error | RpcStatusProto::Fatal => {
+                    return Err(RemoteException::from(&header).into());
+                }
+                _ => {}
+            }
+            if header.call_id as i32 != AuthProtocol::Sasl.call_id() as i32 {
+                return Err(sasl_exception("Non-SASL response during negotiation"));
+            }
+            let sasl_message = RpcSaslProto::decode_length_delimited(&mut value)?;
+            if !value.is_empty() {
+                return Err(sasl_exception("Received malformed response length"));
+            }
+            log::debug!(
+                "Received SASL message state: {}",
+                sasl_message.state().as_str_name()
+            );
+            // handle sasl negotiation process
+            let response = match sasl_message.state() {
+                SaslState::Negotiate => {
+                    // create a compatible SASL client, throws if no supported auths
+                    let mut sasl_auth_type = self.select_sasl_client(&sasl_message.auths)?;
+                    // define auth being attempted for the caller
+                    self.auth_method =
+                        Self::get_valid_auth_method(&sasl_auth_type).unwrap_or(AuthMethod::Simple);
+
+                    let sasl_client = self.sasl_client.as_ref().map(|c| c.lock().unwrap());
+                    let response_token = match sasl_client {
+                        Some(mut sasl_client) if self.auth_method != AuthMethod::Simple => {
+                            let challenge_token = if sasl_auth_type.challenge.is_some() {
+                                // server provided the first challenge
+                                sasl_auth_type.challenge.take()
+                            } else if sasl_client.has_initial_response() {
+                                Some(vec![])
+                            } else {
+                                None
+                            };
+                            match challenge_token {
+                                Some(challenge_token) => {
+                                    sasl_client.evaluate_challenge(&challenge_token)?
+                                }
+                                None => Some(vec![]),
+                            }
+                        }
+                        _ => {
+                            // switching to SIMPLE
+                            // not going to wait for success ack
+                            done = true;
+                            None
+                        }
+                    };
+                    let mut response = Self::create_sasl_reply(SaslState::Initiate, response_token);
+                    response.auths.push(sasl_auth_type);
+                    Some(response)
+                }
+                SaslState::Challenge => {
+                    let mut sasl_client = self
+                        .sasl_client
+                        .as_ref()
+                        .ok_or_else(|| {
+                            // should probably instantiate a client to allow a server to
+                            // demand a specific negotiation
+                            sasl_exception("Server sent unsolicited challenge")
+                        })?
+                        .lock()
+                        .unwrap();
+                    let response_token =
+                        Self::sasl_evaluate_token(&mut **sasl_client, &sasl_message, false)?;
+                    Some(Self::create_sasl_reply(SaslState::Response, response_token))
+                }
+                SaslState::Success => {
+                    // simple server sends immediate success to a SASL client for
+                    // switch to simple
+                    match &self.sasl_client {
+                        None => self.auth_method = AuthMethod::Simple,
+                        Some(sasl_client) => {
+                            let mut sasl_client = sasl_client.lock().unwrap();
+                            Self::sasl_evaluate_token(&mut **sasl_client, &sasl_message, true)?;
+                        }
+                    }
+                    done = true;
+                    None
+                }
+                state => {
+                    return Err(sasl_exception(&format!(
+                        "RPC client doesn't support SASL {}",
+                        state.as_str_name()
+                    )));
+                }
+            };
+            if let Some(response) = response {
+                Self::send_sasl_message(ipc_streams, &response)?;
+            }
+        }
+        Ok(self.auth_method)
+    }
+
+    fn send_sasl_message(
+        ipc_streams: &mut IpcStreams,
+        message: &RpcSaslProto,
+    ) -> anyhow::Result<()> {
+        log::debug!(
+            "Sending sasl message state: {}",
+            message.state().as_str_name()
+        );
+        ipc_streams.send_request(&Self::make_sasl_request(message))?;
+        Ok(())
+    }
+
+    /// The request of the SASL message, prefixed with its length.
+    fn make_sasl_request(message: &RpcSaslProto) -> Vec<u8> {
+        let sasl_header = ProtoUtil::make_rpc_request_header(
+            &RpcKind::RpcProtocolBuffer,
+            OperationProto::RpcFinalPacket,
+            AuthProtocol::Sasl.call_id() as i32,
+            RpcConstants::INVALID_RETRY_COUNT,
+            RpcConstants::DUMMY_CLIENT_ID,
+            None,
+        );
+        let mut buf = sasl_header.encode_length_delimited_to_vec();
+        buf.append(&mut message.encode_length_delimited_to_vec());
+        prepend_buf_size(buf)
+    }
+
+    /// Evaluate the server provided challenge.  The server must send a token
+    /// if it's not done.  If the server is done, the challenge token is
+    /// optional because not all mechanisms send a final token for the client
+    /// to update its internal state.  The client must also be done after
+    /// evaluating the optional token.
+    fn sasl_evaluate_token(
+        sasl_client: &mut dyn SaslClient,
+        sasl_response: &RpcSaslProto,
+        server_is_done: bool,
+    ) -> anyhow::Result<Option<Vec<u8>>> {
+        let sasl_token = match &sasl_response.token {
+            Some(token) => sasl_client.evaluate_challenge(token)?,
+            // the server may only omit a token when it's done
+            None if !server_is_done => {
+                return Err(sasl_exception("Server challenge contains no token"));
+            }
+            None => None,
+        };
+        if server_is_done {
+            // server tried to report success before our client completed
+            if !sasl_client.is_complete() {
+                return Err(sasl_exception("Client is out of sync with server"));
+            }
+            // a client cannot generate a response to a success message
+            if sasl_token.is_some() {
+                return Err(sasl_exception("Client generated spurious response"));
+            }
+        }
+        Ok(sasl_token)
+    }
+
+    fn create_sasl_reply(state: SaslState, response_token: Option<Vec<u8>>) -> RpcSaslProto {
+        RpcSaslProto {
+            state: state.into(),
+            token: response_token,
+            ..Default::default()
+        }
+    }
+}
+
+/// Reads the responses of the server, unwrapping the SASL wrapped RPC
+/// packets.
+struct WrappedInputStream {
+    sasl_client: SharedSaslClient,
+    input: Box<dyn Read + Send>,
+    // the bytes of the packet being read, kept if the read is interrupted
+    packet: Vec<u8>,
+    unwrapped_rpc_buffer: io::Cursor<Vec<u8>>,
+}
+
+impl WrappedInputStream {
+    fn new(sasl_client: SharedSaslClient, input: Box<dyn Read + Send>) -> Self {
+        Self {
+            sasl_client,
+            input,
+            packet: vec![],
+            unwrapped_rpc_buffer: io::Cursor::new(vec![]),
+        }
+    }
+
+    /// Read the next RPC packet and unwrap its token into the buffer.
+    fn read_next_rpc_packet(&mut self) -> io::Result<()> {
+        log::debug!("reading next wrapped RPC packet");
+        self.fill_packet(4)?;
+        let rpc_len = i32::from_be_bytes([
+            self.packet[0],
+            self.packet[1],
+            self.packet[2],
+            self.packet[3],
+        ]);
+        if rpc_len < 0 {
+            return Err(io_error(sasl_exception(&format!(
+                "Invalid wrapped RPC packet length: {}",
+                rpc_len
+            ))));
+        }
+        self.fill_packet(4 + rpc_len as usize)?;
+        let packet = mem::take(&mut self.packet);
+        let token = Self::decode_wrapped_token(&packet[4..]).map_err(io_error)?;
+        log::debug!("unwrapping token of length:{}", token.len());
+        let unwrapped = self
+            .sasl_client
+            .lock()
+            .unwrap()
+            .unwrap(&token)
+            .map_err(io_error)?;
+        self.unwrapped_rpc_buffer = io::Cursor::new(unwrapped);
+        Ok(())
+    }
+
+    /// Read from the input until the packet has `len` bytes.
+    fn fill_packet(&mut self, len: usize) -> io::Result<()> {
+        while self.packet.len() < len {
+            let start = self.packet.len();
+            self.packet.resize(len, 0);
+            let result = self.input.read(&mut self.packet[start..]);
+            self.packet.truncate(start + *result.as_ref().unwrap_or(&0));
+            if result? == 0 {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+        }
+        Ok(())
+    }
+
+    /// The token of the SASL wrapped RPC packet.
+    fn decode_wrapped_token(mut rpc_buf: &[u8]) -> anyhow::Result<Vec<u8>> {
+        // decode the RPC header
+        let header = RpcResponseHeaderProto::decode_length_delimited(&mut rpc_buf)?;
+        // Must be SASL wrapped, verify and decode.
+        if header.call_id as i32 == AuthProtocol::Sasl.call_id() as i32 {
+            let sasl_message = RpcSaslProto::decode_length_delimited(rpc_buf)?;
+            if sasl_message.state() == SaslState::Wrap {
+                return Ok(sasl_message.token.unwrap_or_default());
+            }
+        }
+        Err(sasl_exception("Server sent non-wrapped response"))
+    }
+}
+
+impl Read for WrappedInputStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        // fill the buffer with the next RPC message
+        while self.unwrapped_rpc_buffer.position() as usize
+            == self.unwrapped_rpc_buffer.get_ref().len()
+        {
+            self.read_next_rpc_packet()?;
+        }
+        self.unwrapped_rpc_buffer.read(buf)
+    }
+}
+
+/// Writes the requests to the server as SASL wrapped RPC packets, at most
+/// the negotiated raw send size at once.
+struct WrappedOutputStream {
+    sasl_client: SharedSaslClient,
+    output: Box<dyn Write + Send>,
+    max_send_buf_size: usize,
+}
+
+impl WrappedOutputStream {
+    fn new(sasl_client: SharedSaslClient, output: Box<dyn Write + Send>) -> anyhow::Result<Self> {
+        // the client and server negotiate a maximum buffer size that can be
+        // wrapped
+        let max_send_buf_size = sasl_client.lock().unwrap().get_raw_send_size()?;
+        Ok(Self {
+            sasl_client,
+            output,
+            max_send_buf_size,
+        })
+    }
+}
+
+impl Write for WrappedOutputStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let len = buf.len().min(self.max_send_buf_size);
+        log::debug!("wrapping token of length:{}", len);
+        let token = self
+            .sasl_client
+            .lock()
+            .unwrap()
+            .wrap(&buf[..len])
+            .map_err(io_error)?;
+        let sasl_message = RpcSaslProto {
+            state: SaslState::Wrap.into(),
+            token: Some(token),
+            ..Default::default()
+        };
+        self.output
+            .write_all(&SaslRpcClient::make_sasl_request(&sasl_message))?;
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.output.flush()
+    }
+}
+
+fn sasl_exception(msg: &str) -> Error {
+    Error::new(IOException::Sasl(msg.to_owned()))
+}
+
+/// The error of a wrapped stream, keeping the [`IOException`] it failed
+/// with.
+fn io_error(e: Error) -> io::Error {
+    match e.downcast::<IOException>() {
+        Ok(ioe) => io::Error::other(ioe),
+        Err(e) => io::Error::other(format!("{:#}", e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::RpcRequestHeaderProto;
+
+    static KERBEROS_PROTOCOL: ProtocolInfo = ProtocolInfo {
+        protocol_name: "protocol",
+        protocol_version: 1,
+        token_kind: None,
+        server_principal: Some("test.kerberos.principal"),
+    };
+
+    static SIMPLE_PROTOCOL: ProtocolInfo = ProtocolInfo {
+        protocol_name: "protocol",
+        protocol_version: 1,
+        token_kind: None,
+        server_principal: None,
+    };
+
+    fn kerberos_auth(server_id: &str) -> SaslAuth {
+        SaslAuth {
+            method: AuthMethod::Kerberos.to_string(),
+            mechanism: AuthMethod::Kerberos.get_mechanism_name().to_owned(),
+            protocol: Some("nn".to_owned()),
+            server_id: Some(server_id.to_owned()),
+            challenge: None,
+        }
+    }
+
+    /// A SASL client whose security layer inverts the bits of the messages.
+    struct InvertingSaslClient;
+
+    impl SaslClient for InvertingSaslClient {
+        fn get_mechanism_name(&self) -> &str {
+            "INVERTING"
+        }
+
+        fn has_initial_response(&self) -> bool {
+            false
+        }
+
+        fn evaluate_challenge(&mut self, _: &[u8]) -> anyhow::Result<Option<Vec<u8>>> {
+            Ok(None)
+        }
+
+        fn is_complete(&self) -> bool {
+            true
+        }
+
+        fn get_negotiated_qop(&self) -> Option<&str> {
+            Some("auth-int")
+        }
+
+        fn get_raw_send_size(&self) -> anyhow::Result<usize> {
+            Ok(4)
+        }
+
+        fn wrap(&mut self, outgoing: &[u8]) -> anyhow::Result<Vec<u8>> {
+            Ok(outgoing.iter().map(|b| !b).collect())
+        }
+
+        fn unwrap(&mut self, incoming: &[u8]) -> anyhow::Result<Vec<u8>> {
+            Ok(incoming.iter().map(|b| !b).collect())
+        }
+    }
+
+    /// An output stream whose written bytes are shared.
+    struct SharedOutput(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedOutput {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// An input stream reading 3 bytes at a time, timing out between reads.
+    struct SlowInput {
+        input: io::Cursor<Vec<u8>>,
+        timed_out: bool,
+    }
+
+    impl Read for SlowInput {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.timed_out = !self.timed_out;
+            if self.timed_out {
+                return Err(io::ErrorKind::TimedOut.into());
+            }
+            let len = buf.len().min(3);
+            self.input.read(&mut buf[..len])
+        }
+    }
+
+    fn sasl_response(state: SaslState, token: &[u8]) -> Vec<u8> {
+        let header = RpcResponseHeaderProto {
+            call_id: AuthProtocol::Sasl.call_id() as i32 as u32,
+            status: RpcStatusProto::Success as i32,
+            ..Default::default()
+        };
+        let mut buf = header.encode_length_delimited_to_vec();
+        let message = RpcSaslProto {
+            state: state.into(),
+            token: Some(token.iter().map(|b| !b).collect()),
+            ..Default::default()
+        };
+        buf.append(&mut message.encode_length_delimited_to_vec());
+        prepend_buf_size(buf)
+    }
+
+    #[test]
+    fn test_wrapped_streams() -> anyhow::Result<()> {
+        let sasl_client: SharedSaslClient = Arc::new(Mutex::new(Box::new(InvertingSaslClient)));
+
+        // the requests are wrapped in packets of the raw send size
+        let output = Arc::new(Mutex::new(vec![]));
+        let mut wrapped_output = WrappedOutputStream::new(
+            Arc::clone(&sasl_client),
+            Box::new(SharedOutput(Arc::clone(&output))),
+        )?;
+        wrapped_output.write_all(b"0123456789")?;
+        let output = output.lock().unwrap().to_owned();
+        let mut output = &output[..];
+        let mut tokens = vec![];
+        while !output.is_empty() {
+            let len = i32::from_be_bytes(output[..4].try_into()?) as usize;
+            let mut packet = &output[4..4 + len];
+            let header = RpcRequestHeaderProto::decode_length_delimited(&mut packet)?;
+            assert_eq!(AuthProtocol::Sasl.call_id() as i32, header.call_id);
+            let message = RpcSaslProto::decode_length_delimited(packet)?;
+            assert_eq!(SaslState::Wrap, message.state());
+            tokens.push(message.token.unwrap_or_default());
+            output = &output[4 + len..];
+        }
+        assert_eq!(
+            vec![b"0123".to_vec(), b"4567".to_vec(), b"89".to_vec()],
+            tokens
+                .iter()
+                .map(|token| token.iter().map(|b| !b).collect::<Vec<_>>())
+                .collect::<Vec<_>>()
+        );
+
+        // the responses are unwrapped, resuming the packets after timeouts
+        let mut input = sasl_response(SaslState::Wrap, b"first");
+        input.append(&mut sasl_response(SaslState::Wrap, b"other"));
+        input.append(&mut sasl_response(SaslState::Success, b"last"));
+        let mut wrapped_input = WrappedInputStream::new(
+            Arc::clone(&sasl_client),
+            Box::new(SlowInput {
+                input: io::Cursor::new(input),
+                timed_out: false,
+            }),
+        );
+        let mut unwrapped = vec![];
+        let err = loop {
+            let mut buf = [0; 4];
+            match wrapped_input.read(&mut buf) {
+                Ok(len) => unwrapped.extend_from_slice(&buf[..len]),
+                Err(e) if e.kind() == io::ErrorKind::TimedOut => {}
+                Err(e) => break e,
+            }
+        };
+        assert_eq!(b"firstother", &unwrapped[..]);
+        assert!(matches!(
+            err.get_ref().and_then(|e| e.downcast_ref::<IOException>()),
+            Some(IOException::Sasl(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_server_principal() -> anyhow::Result<()> {
+        let ugi = UserGroupInformation::create_remote_user("user")?;
+        let addr = "127.0.0.1:8020".parse()?;
+        let mut conf = Configuration::new();
+
+        // the principal of the server must be configured
+        let client = SaslRpcClient::new(&ugi, &KERBEROS_PROTOCOL, &addr, &conf);
+        assert!(client
+            .get_server_principal(&kerberos_auth("nn-host"))
+            .is_err());
+
+        conf.set("test.kerberos.principal", "nn/nn-host@EXAMPLE.COM");
+        let client = SaslRpcClient::new(&ugi, &KERBEROS_PROTOCOL, &addr, &conf);
+        assert_eq!(
+            Some("nn/nn-host".to_owned()),
+            client.get_server_principal(&kerberos_auth("nn-host"))?
+        );
+        let err = client
+            .get_server_principal(&kerberos_auth("other-host"))
+            .unwrap_err();
+        assert_eq!(
+            "Server has invalid Kerberos principal: nn/other-host, expecting: \
+            nn/nn-host@EXAMPLE.COM",
+            err.to_string()
+        );
+
+        // protocols without a server principal don't use kerberos
+        let client = SaslRpcClient::new(&ugi, &SIMPLE_PROTOCOL, &addr, &conf);
+        assert_eq!(
+            None,
+            client.get_server_principal(&kerberos_auth("nn-host"))?
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,145 @@
+use std::fmt;
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// A utility class for dealing with SASL on RPC server
+pub struct SaslRpcServer;
+
+impl SaslRpcServer {
+    pub const SASL_DEFAULT_REALM: &'static str = "default";
+
+    /// The user name a token is presented with: its base64 encoded
+    /// identifier.
+    pub fn encode_identifier(identifier: &[u8]) -> String {
+        encode_base64(identifier)
+    }
+
+    /// The password a token is presented with: its base64 encoded password.
+    pub fn encode_password(password: &[u8]) -> String {
+        encode_base64(password)
+    }
+}
+
+/// The quality of protection of the RPC traffic.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QualityOfProtection {
+    Authentication,
+    Integrity,
+    Privacy,
+}
+
+impl QualityOfProtection {
+    /// The SASL QOP value of the protection, e.g. `auth-int`.
+    pub fn get_sasl_qop(&self) -> &'static str {
+        match self {
+            Self::Authentication => "auth",
+            Self::Integrity => "auth-int",
+            Self::Privacy => "auth-conf",
+        }
+    }
+
+    /// The protection of the given name, ignoring case, e.g. `privacy`.
+    pub fn from_str_name(name: &str) -> Option<Self> {
+        match name.to_ascii_uppercase().as_str() {
+            "AUTHENTICATION" => Some(Self::Authentication),
+            "INTEGRITY" => Some(Self::Integrity),
+            "PRIVACY" => Some(Self::Privacy),
+            _ => None,
+        }
+    }
+}
+
+/// Authentication method
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AuthMethod {
+    Simple,
+    Kerberos,
+    Token,
+    Plain,
+}
+
+impl AuthMethod {
+    /// The code for this method.
+    pub fn code(&self) -> u8 {
+        match self {
+            Self::Simple => 80,
+            Self::Kerberos => 81,
+            Self::Token => 82,
+            Self::Plain => 83,
+        }
+    }
+
+    /// Return the SASL mechanism name
+    pub fn get_mechanism_name(&self) -> &'static str {
+        match self {
+            Self::Simple => "",
+            Self::Kerberos => "GSSAPI",
+            Self::Token => "DIGEST-MD5",
+            Self::Plain => "PLAIN",
+        }
+    }
+
+    /// The name of the method in the SASL negotiation, e.g. `TOKEN`.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            Self::Simple => "SIMPLE",
+            Self::Kerberos => "KERBEROS",
+            Self::Token => "TOKEN",
+            Self::Plain => "PLAIN",
+        }
+    }
+
+    /// The method of the given name in the SASL negotiation.
+    pub fn from_str_name(name: &str) -> Option<Self> {
+        match name {
+            "SIMPLE" => Some(Self::Simple),
+            "KERBEROS" => Some(Self::Kerberos),
+            "TOKEN" => Some(Self::Token),
+            "PLAIN" => Some(Self::Plain),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for AuthMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str_name())
+    }
+}
+
+/// Encode the bytes in base64 with padding.
+fn encode_base64(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(BASE64_ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_base64() {
+        assert_eq!("", encode_base64(b""));
+        assert_eq!("Zg==", encode_base64(b"f"));
+        assert_eq!("Zm8=", encode_base64(b"fo"));
+        assert_eq!("Zm9v", encode_base64(b"foo"));
+        assert_eq!("Zm9vYmFy", encode_base64(b"foobar"));
+        assert_eq!("AP/+", encode_base64(&[0x00, 0xff, 0xfe]));
+    }
+}
@@ -1,14 +1,122 @@
-use crate::common::io::Text;
-use std::net::SocketAddr;
+use super::AuthenticationMethod;
+use crate::common::{
+    conf::Configuration, fs::common_configuration_keys_public::HADOOP_SECURITY_AUTHENTICATION,
+    io::Text, net::NetUtils,
+};
+use anyhow::Error;
+use std::net::{IpAddr, SocketAddr};
+
+/// The pattern of the host name in principal names, replaced with the
+/// actual host name.
+pub const HOSTNAME_PATTERN: &str = "_HOST";
 
 /// Security Utils.
 pub struct SecurityUtil;
 
 impl SecurityUtil {
+    /// Convert Kerberos principal name pattern to valid Kerberos principal
+    /// names. It replaces hostname pattern with hostname, which should be
+    /// fully-qualified domain name. If hostname is null or "0.0.0.0", it uses
+    /// dynamically looked-up fqdn of the current host instead.
+    pub fn get_server_principal(principal_config: &str, hostname: &str) -> String {
+        match Self::get_components(principal_config) {
+            Some(components) => Self::replace_pattern(&components, hostname),
+            None => principal_config.to_owned(),
+        }
+    }
+
+    /// Convert Kerberos principal name pattern to valid Kerberos principal
+    /// names. This method is similar to [`Self::get_server_principal`],
+    /// except 1) the reverse DNS lookup from addr to hostname is done only
+    /// when necessary, 2) param addr can't be null (no default behavior of
+    /// using local hostname when addr is null).
+    pub fn get_server_principal_for_addr(principal_config: &str, addr: &IpAddr) -> String {
+        match Self::get_components(principal_config) {
+            Some(components) => {
+                Self::replace_pattern(&components, &NetUtils::get_canonical_host_name(addr))
+            }
+            None => principal_config.to_owned(),
+        }
+    }
+
+    /// The components of a `<service>/_HOST@REALM` principal name, `None` if
+    /// the principal has no host name pattern.
+    fn get_components(principal_config: &str) -> Option<Vec<&str>> {
+        let components = principal_config.split(['/', '@']).collect::<Vec<_>>();
+        if components.len() == 3 && components[1] == HOSTNAME_PATTERN {
+            Some(components)
+        } else {
+            None
+        }
+    }
+
+    fn replace_pattern(components: &[&str], hostname: &str) -> String {
+        let fqdn = if hostname.is_empty() || hostname == "0.0.0.0" {
+            whoami::hostname()
+        } else {
+            hostname.to_owned()
+        };
+        format!(
+            "{}/{}@{}",
+            components[0],
+            fqdn.to_lowercase(),
+            components[2]
+        )
+    }
+
     /// Construct the service key for a token
     pub fn build_token_service(addr: &SocketAddr) -> Text {
         // TODO: support flag `use_ip_for_token_service`
 
         format!("{}:{}", addr.ip(), addr.port()).into()
     }
+
+    /// Get the host name from the principal name of format `<service>/host@REALM`.
+    pub fn get_host_from_principal(principal_name: &str) -> Option<String> {
+        let (_, rest) = principal_name.split_once('/')?;
+        let host = rest.split('@').next().unwrap_or(rest);
+        Some(host.to_owned())
+    }
+
+    /// The authentication method configured by `hadoop.security.authentication`.
+    pub fn get_authentication_method(conf: &Configuration) -> anyhow::Result<AuthenticationMethod> {
+        let value = conf
+            .get(HADOOP_SECURITY_AUTHENTICATION, Some("simple"))
+            .unwrap_or_default();
+        AuthenticationMethod::value_of(&value.to_uppercase()).ok_or_else(|| {
+            Error::msg(format!(
+                "Invalid attribute value for {} of {}",
+                HADOOP_SECURITY_AUTHENTICATION, value
+            ))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_server_principal() {
+        let service = "hdfs/";
+        let realm = "@REALM";
+        let hostname = "FOOHOST";
+        let principal = format!("{}{}{}", service, HOSTNAME_PATTERN, realm);
+        assert_eq!(
+            "hdfs/foohost@REALM",
+            SecurityUtil::get_server_principal(&principal, hostname)
+        );
+        // principals without the host name pattern are kept as is
+        for principal in ["hdfs/host@REALM", "hdfs@REALM", "hdfs/_HOST/x@REALM"] {
+            assert_eq!(
+                principal,
+                SecurityUtil::get_server_principal(principal, hostname)
+            );
+        }
+        // the local host name replaces the pattern of unspecified hosts
+        assert_eq!(
+            format!("hdfs/{}@REALM", whoami::hostname().to_lowercase()),
+            SecurityUtil::get_server_principal(&principal, "0.0.0.0")
+        );
+    }
 }
@@ -0,0 +1,91 @@
+use crate::common::{io::WritableUtils, TokenProto};
+use std::io::Read;
+
+/// The client-side form of the token.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Token {
+    identifier: Vec<u8>,
+    password: Vec<u8>,
+    kind: String,
+    service: String,
+}
+
+impl Token {
+    /// Construct a token from the components.
+    pub fn new(identifier: &[u8], password: &[u8], kind: &str, service: &str) -> Self {
+        Self {
+            identifier: identifier.to_vec(),
+            password: password.to_vec(),
+            kind: kind.to_owned(),
+            service: service.to_owned(),
+        }
+    }
+
+    /// Get the token identifier's byte representation.
+    pub fn get_identifier(&self) -> &[u8] {
+        &self.identifier
+    }
+
+    /// Get the token password/secret.
+    pub fn get_password(&self) -> &[u8] {
+        &self.password
+    }
+
+    /// Get the token kind.
+    pub fn get_kind(&self) -> &str {
+        &self.kind
+    }
+
+    /// Get the service on which the token is supposed to be used.
+    pub fn get_service(&self) -> &str {
+        &self.service
+    }
+
+    /// Set the service on which the token is supposed to be used.
+    pub fn set_service(&mut self, new_service: &str) {
+        self.service = new_service.to_owned();
+    }
+
+    /// Deserialize the fields of this object from `stream`.
+    pub fn read_fields(stream: &mut impl Read) -> anyhow::Result<Self> {
+        let identifier = Self::read_bytes(stream)?;
+        let password = Self::read_bytes(stream)?;
+        let kind = WritableUtils::read_string(stream)?.unwrap_or_default();
+        let service = WritableUtils::read_string(stream)?.unwrap_or_default();
+        Ok(Self {
+            identifier,
+            password,
+            kind,
+            service,
+        })
+    }
+
+    fn read_bytes(stream: &mut impl Read) -> anyhow::Result<Vec<u8>> {
+        let len = WritableUtils::read_vint(stream)?;
+        let mut bytes = vec![0; len.max(0) as usize];
+        stream.read_exact(&mut bytes)?;
+        Ok(bytes)
+    }
+}
+
+impl From<&TokenProto> for Token {
+    fn from(value: &TokenProto) -> Self {
+        Self {
+            identifier: value.identifier.to_owned(),
+            password: value.password.to_owned(),
+            kind: value.kind.to_owned(),
+            service: value.service.to_owned(),
+        }
+    }
+}
+
+impl From<&Token> for TokenProto {
+    fn from(value: &Token) -> Self {
+        Self {
+            identifier: value.identifier.to_owned(),
+            password: value.password.to_owned(),
+            kind: value.kind.to_owned(),
+            service: value.service.to_owned(),
+        }
+    }
+}
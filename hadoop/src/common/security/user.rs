@@ -1,20 +1,28 @@
 /// Save the full and short name of the user as a principal. This allows us to
 /// have a single type that we always look for when picking up user names.
-#[derive(Clone, Copy)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub(crate) struct User {
-    // TODO
+    full_name: String,
+    short_name: String,
 }
 
 impl User {
+    pub fn new(name: &str) -> Self {
+        // TODO: apply the auth_to_local rules
+        let short_name = name.split(['/', '@']).next().unwrap_or(name);
+        Self {
+            full_name: name.to_owned(),
+            short_name: short_name.to_owned(),
+        }
+    }
+
     /// Get the full name of the user.
     pub fn get_name(&self) -> String {
-        // TODO
-        whoami::username()
+        self.full_name.to_owned()
     }
 
     /// Get the user name up to the first '/' or '@'
     pub fn get_short_name(&self) -> String {
-        // TODO
-        whoami::username()
+        self.short_name.to_owned()
     }
 }
@@ -1,30 +1,100 @@
-use super::User;
-use std::{collections::HashMap, env, sync::Mutex};
+#[cfg(feature = "gssapi")]
+use super::sasl::Credential;
+use super::{sasl_rpc_server::AuthMethod, token::Token, Credentials, SecurityUtil, User};
+use crate::common::conf::Configuration;
+use anyhow::Error;
+use std::{
+    collections::HashMap,
+    env,
+    ffi::CString,
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
 
 const HADOOP_PROXY_USER: &str = "HADOOP_PROXY_USER";
 
+/// Environment variable pointing to the token cache file
+pub const HADOOP_TOKEN_FILE_LOCATION: &str = "HADOOP_TOKEN_FILE_LOCATION";
+
 /// Information about the logged in user.
 static LOGIN_USER_REF: Mutex<Option<UserGroupInformation>> = Mutex::new(None);
 
+/// The authentication method of the cluster, set when the class is
+/// initialized.
+static AUTHENTICATION_METHOD: Mutex<Option<AuthenticationMethod>> = Mutex::new(None);
+
 /// User and group information for Hadoop.
 /// This class provides methods to determine the
 /// user's username and groups. It supports both the Windows, Unix and Kerberos
 /// login modules.
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 pub struct UserGroupInformation {
     user: User,
+    // the credentials of the subject, shared by the copies of this object
+    credentials: Arc<Mutex<Credentials>>,
+    // how the user was authenticated at login, if it logged in
+    authentication_method: Option<AuthenticationMethod>,
+    // the keytab the user logged in from, if any
+    keytab: Option<PathBuf>,
 }
 
 impl UserGroupInformation {
+    fn new(user: User) -> Self {
+        Self {
+            user,
+            credentials: Arc::new(Mutex::new(Credentials::new())),
+            authentication_method: None,
+            keytab: None,
+        }
+    }
+
+    /// Create a user logged in with Kerberos credentials, from the keytab if
+    /// given or else from the ticket cache.
+    fn new_kerberos_user(principal: &str, keytab: Option<&Path>) -> Self {
+        let mut ugi = Self::new(User::new(principal));
+        ugi.authentication_method = Some(AuthenticationMethod::Kerberos);
+        ugi.keytab = keytab.map(Path::to_path_buf);
+        ugi
+    }
+
     /// A method to initialize the fields that depend on a configuration.
     /// Must be called before useKerberos or groups is used.
-    fn ensure_initialized() {
-        // TODO
+    fn ensure_initialized() -> anyhow::Result<()> {
+        let mut authentication_method = AUTHENTICATION_METHOD.lock().unwrap();
+        if authentication_method.is_none() {
+            *authentication_method = Some(SecurityUtil::get_authentication_method(
+                &Configuration::new(),
+            )?);
+        }
+        Ok(())
+    }
+
+    /// Set the static configuration for UGI.
+    /// In particular, set the security authentication mechanism and the
+    /// group look up service.
+    pub fn set_configuration(conf: &Configuration) -> anyhow::Result<()> {
+        *AUTHENTICATION_METHOD.lock().unwrap() =
+            Some(SecurityUtil::get_authentication_method(conf)?);
+        Ok(())
+    }
+
+    /// Determine if UserGroupInformation is using Kerberos to determine
+    /// user identities or is relying on simple authentication
+    pub fn is_security_enabled() -> anyhow::Result<bool> {
+        Ok(!Self::is_authentication_method_enabled(
+            AuthenticationMethod::Simple,
+        )?)
+    }
+
+    fn is_authentication_method_enabled(method: AuthenticationMethod) -> anyhow::Result<bool> {
+        Self::ensure_initialized()?;
+        Ok(*AUTHENTICATION_METHOD.lock().unwrap() == Some(method))
     }
 
     /// Return the current user, including any doAs in the current stack.
     pub fn get_current_user() -> anyhow::Result<Self> {
-        Self::ensure_initialized();
+        Self::ensure_initialized()?;
         // TODO: use context user if any
         Self::get_login_user()
     }
@@ -33,16 +103,20 @@ impl UserGroupInformation {
     /// the user will automatically be logged in with either kerberos credentials
     /// if available, or as the local OS user, based on security settings.
     pub fn get_login_user() -> anyhow::Result<Self> {
-        Self::ensure_initialized();
+        Self::ensure_initialized()?;
         // TODO: confirm whether to use Atomic or Mutex for LOGIN_USER_REF
-        if let Some(login_user) = *LOGIN_USER_REF.lock().unwrap() {
-            return Ok(login_user);
+        if let Some(login_user) = LOGIN_USER_REF.lock().unwrap().as_ref() {
+            return Ok(login_user.to_owned());
         }
         let new_login_user = Self::create_login_user(None)?;
-        let login_user = *LOGIN_USER_REF.lock().unwrap().get_or_insert_with(|| {
-            new_login_user.spawn_auto_renewal_thread_for_user_creds(false);
-            new_login_user
-        });
+        let login_user = LOGIN_USER_REF
+            .lock()
+            .unwrap()
+            .get_or_insert_with(|| {
+                new_login_user.spawn_auto_renewal_thread_for_user_creds(false);
+                new_login_user
+            })
+            .to_owned();
         Ok(login_user)
     }
 
@@ -52,9 +126,25 @@ impl UserGroupInformation {
         // If the HADOOP_PROXY_USER environment variable
         // is specified, create a proxy user as the logged in user.
         let proxy_user = env::var(HADOOP_PROXY_USER).ok().filter(|p| !p.is_empty());
-        let login_user = proxy_user.map_or(real_user, |p| Self::create_proxy_user(&p, &real_user));
+        let login_user = proxy_user.map_or(real_user.to_owned(), |p| {
+            Self::create_proxy_user(&p, &real_user)
+        });
 
-        // TODO: load tokens from files and base64 encoding
+        // Load the token storage file and put all of the tokens into the
+        // user.
+        if let Some(file_location) = env::var_os(HADOOP_TOKEN_FILE_LOCATION) {
+            let source = PathBuf::from(file_location);
+            log::debug!("Reading credentials from location {}", source.display());
+            let cred = Credentials::read_token_storage_file(&source)?;
+            log::debug!(
+                "Loaded {} tokens from {}",
+                cred.number_of_tokens(),
+                source.display()
+            );
+            login_user.add_credentials(&cred);
+        }
+
+        // TODO: load tokens from base64 encoding
 
         Ok(login_user)
     }
@@ -78,18 +168,219 @@ impl UserGroupInformation {
         self.user.get_short_name()
     }
 
+    /// Create a user from a login name. It is intended to be used for remote
+    /// users in RPC, since it won't have any credentials.
+    pub fn create_remote_user(user: &str) -> anyhow::Result<Self> {
+        if user.is_empty() {
+            return Err(Error::msg("Null user"));
+        }
+        Ok(Self::new(User::new(user)))
+    }
+
     /// Get the user's full principal name.
     pub fn get_user_name(&self) -> String {
         self.user.get_name()
     }
 
+    /// Log a user in from a keytab file. Loads a user identity from a keytab
+    /// file and logs them in. They become the currently logged-in user.
+    pub fn login_user_from_keytab(user: &str, path: &str) -> anyhow::Result<()> {
+        if !Self::is_security_enabled()? {
+            return Ok(());
+        }
+        let login_user = Self::login_user_from_keytab_and_return_ugi(user, path)?;
+        *LOGIN_USER_REF.lock().unwrap() = Some(login_user);
+        log::info!(
+            "Login successful for user {} using keytab file {}",
+            user,
+            path
+        );
+        Ok(())
+    }
+
+    /// Log a user in from a keytab file. Loads a user identity from a keytab
+    /// file and login them in. This new user does not affect the currently
+    /// logged-in user.
+    pub fn login_user_from_keytab_and_return_ugi(user: &str, path: &str) -> anyhow::Result<Self> {
+        if !Self::is_security_enabled()? {
+            return Self::get_current_user();
+        }
+        let keytab = Path::new(path);
+        Self::kerberos_login(Some(user), Some(keytab)).map_err(|e| {
+            e.context(format!(
+                "Login failure for user: {} from keytab {}",
+                user, path
+            ))
+        })?;
+        Ok(Self::new_kerberos_user(user, Some(keytab)))
+    }
+
+    /// Re-login a user from keytab if TGT is expired or is close to expiry.
+    pub fn relogin_from_keytab(&self) -> anyhow::Result<()> {
+        let (keytab, principal) = match (&self.keytab, self.has_kerberos_credentials()) {
+            (Some(keytab), true) => (keytab, self.get_user_name()),
+            _ => return Ok(()),
+        };
+        log::debug!("Initiating re-login for {}", principal);
+        Self::kerberos_login(Some(&principal), Some(keytab)).map_err(|e| {
+            e.context(format!(
+                "Login failure for user: {} from keytab {}",
+                principal,
+                keytab.display()
+            ))
+        })?;
+        Ok(())
+    }
+
+    /// Acquire the Kerberos credentials of the principal, from the keytab if
+    /// given or else from the ticket cache, and return the name of the
+    /// principal they belong to.
+    #[cfg(feature = "gssapi")]
+    fn kerberos_login(principal: Option<&str>, keytab: Option<&Path>) -> anyhow::Result<String> {
+        Credential::acquire(principal, keytab)?.get_name()
+    }
+
+    #[cfg(not(feature = "gssapi"))]
+    fn kerberos_login(_principal: Option<&str>, _keytab: Option<&Path>) -> anyhow::Result<String> {
+        Err(Error::msg(
+            "Kerberos login requires the gssapi feature to be enabled",
+        ))
+    }
+
+    /// Whether the user has logged in with Kerberos credentials.
+    pub fn has_kerberos_credentials(&self) -> bool {
+        self.authentication_method == Some(AuthenticationMethod::Kerberos)
+    }
+
+    /// Is this user logged in from a keytab file managed by the UGI?
+    pub fn is_from_keytab(&self) -> bool {
+        self.keytab.is_some() && self.has_kerberos_credentials()
+    }
+
+    /// The keytab file the user logged in from, if any.
+    pub fn get_keytab(&self) -> Option<&Path> {
+        self.keytab.as_deref()
+    }
+
+    /// Get the authentication method from the subject.
+    pub fn get_authentication_method(&self) -> Option<AuthenticationMethod> {
+        self.authentication_method
+    }
+
+    /// Add a token to this UGI
+    pub fn add_token(&self, token: Token) {
+        let alias = token.get_service().to_owned();
+        self.credentials.lock().unwrap().add_token(&alias, token);
+    }
+
+    /// Obtain the tokens in credentials form associated with this user.
+    pub fn get_tokens(&self) -> Vec<Token> {
+        self.credentials
+            .lock()
+            .unwrap()
+            .get_all_tokens()
+            .into_iter()
+            .cloned()
+            .collect()
+    }
+
+    /// Obtain the tokens in credentials form associated with this user.
+    pub fn get_credentials(&self) -> Credentials {
+        self.credentials.lock().unwrap().to_owned()
+    }
+
+    /// Add the given Credentials to this user.
+    pub fn add_credentials(&self, credentials: &Credentials) {
+        self.credentials.lock().unwrap().add_all(credentials);
+    }
+
+    /// Get the group names for this user, as resolved by the local OS.
+    /// An empty vector is returned if the user is unknown to the OS.
+    pub fn get_group_names(&self) -> Vec<String> {
+        let user_name = self.get_short_user_name();
+        let user = match nix::unistd::User::from_name(&user_name) {
+            Ok(Some(user)) => user,
+            _ => return vec![],
+        };
+        let name = match CString::new(user_name) {
+            Ok(name) => name,
+            Err(_) => return vec![],
+        };
+        nix::unistd::getgrouplist(&name, user.gid)
+            .unwrap_or_else(|_| vec![user.gid])
+            .into_iter()
+            .filter_map(|gid| nix::unistd::Group::from_gid(gid).ok().flatten())
+            .map(|group| group.name)
+            .collect()
+    }
+
     /// Login a subject with the given parameters.  If the subject is null,
     /// the login context used to create the subject will be attached.
     fn do_subject_login(
         _subject: Option<&str>,
         _params: Option<HashMap<String, String>>,
     ) -> anyhow::Result<Self> {
-        // TODO
-        Ok(Self { user: User {} })
+        if Self::is_authentication_method_enabled(AuthenticationMethod::Kerberos)? {
+            match Self::kerberos_login(None, None) {
+                Ok(principal) => return Ok(Self::new_kerberos_user(&principal, None)),
+                // the user may still authenticate with tokens
+                Err(e) => log::debug!("Failed to login from the ticket cache: {:#}", e),
+            }
+        }
+        let mut ugi = Self::new(User::new(&whoami::username()));
+        ugi.authentication_method = Some(AuthenticationMethod::Simple);
+        Ok(ugi)
+    }
+}
+
+impl PartialEq for UserGroupInformation {
+    /// Compare the subjects to see if they are equal to each other.
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.credentials, &other.credentials) && self.user == other.user
+    }
+}
+
+impl Eq for UserGroupInformation {}
+
+impl Hash for UserGroupInformation {
+    /// Return the hash of the subject.
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Arc::as_ptr(&self.credentials).hash(state);
+    }
+}
+
+/// Determines the authentication method of the user.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AuthenticationMethod {
+    Simple,
+    Kerberos,
+    Token,
+    Certificate,
+    KerberosSsl,
+    Proxy,
+}
+
+impl AuthenticationMethod {
+    /// The authentication method of the SASL negotiation, if any.
+    pub fn get_auth_method(&self) -> Option<AuthMethod> {
+        match self {
+            Self::Simple => Some(AuthMethod::Simple),
+            Self::Kerberos => Some(AuthMethod::Kerberos),
+            Self::Token => Some(AuthMethod::Token),
+            _ => None,
+        }
+    }
+
+    /// The method of the given name, e.g. `KERBEROS`.
+    pub fn value_of(name: &str) -> Option<Self> {
+        match name {
+            "SIMPLE" => Some(Self::Simple),
+            "KERBEROS" => Some(Self::Kerberos),
+            "TOKEN" => Some(Self::Token),
+            "CERTIFICATE" => Some(Self::Certificate),
+            "KERBEROS_SSL" => Some(Self::KerberosSsl),
+            "PROXY" => Some(Self::Proxy),
+            _ => None,
+        }
     }
 }
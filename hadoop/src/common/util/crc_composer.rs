@@ -0,0 +1,140 @@
+use super::{crc_util, DataChecksumType};
+use anyhow::Error;
+
+/// Encapsulates logic for composing multiple CRCs into one or more combined
+/// CRCs corresponding to concatenated underlying data ranges. Optimized for
+/// composing a large number of CRCs that correspond to underlying chunks of
+/// data all of same size.
+pub struct CrcComposer {
+    crc_polynomial: u32,
+    precomputed_monomial_for_hint: u32,
+    bytes_per_crc_hint: i64,
+    stripe_length: i64,
+    cur_composite_crc: u32,
+    cur_position_in_stripe: i64,
+    digest_out: Vec<u8>,
+}
+
+impl CrcComposer {
+    /// Returns a CrcComposer which will collapse all ingested CRCs into a
+    /// single value.
+    pub fn new_crc_composer(
+        checksum_type: DataChecksumType,
+        bytes_per_crc_hint: i64,
+    ) -> anyhow::Result<Self> {
+        Self::new_striped_crc_composer(checksum_type, bytes_per_crc_hint, i64::MAX)
+    }
+
+    /// Returns a CrcComposer which will collapse CRCs for every combined
+    /// underlying data size which aligns with the specified stripe boundary.
+    /// For example, if "update" is called with 20 CRCs and bytesPerCrc == 5,
+    /// and stripeLength == 10, then every two (10 / 5) consecutive CRCs will
+    /// be combined with each other, yielding a list of 10 CRC "stripes" in the
+    /// final digest, each corresponding to 10 underlying data bytes. Using a
+    /// stripeLength greater than the total underlying data size is equivalent
+    /// to using a non-striped CrcComposer.
+    pub fn new_striped_crc_composer(
+        checksum_type: DataChecksumType,
+        bytes_per_crc_hint: i64,
+        stripe_length: i64,
+    ) -> anyhow::Result<Self> {
+        let polynomial = checksum_type.get_crc_polynomial()?;
+        Ok(Self {
+            crc_polynomial: polynomial,
+            precomputed_monomial_for_hint: crc_util::get_monomial(bytes_per_crc_hint, polynomial),
+            bytes_per_crc_hint,
+            stripe_length,
+            cur_composite_crc: 0,
+            cur_position_in_stripe: 0,
+            digest_out: vec![],
+        })
+    }
+
+    /// Updates with a single additional CRC which corresponds to an underlying
+    /// data size of `bytes_per_crc`.
+    pub fn update(&mut self, crc_b: u32, bytes_per_crc: i64) -> anyhow::Result<()> {
+        if self.cur_composite_crc == 0 {
+            self.cur_composite_crc = crc_b;
+        } else if bytes_per_crc == self.bytes_per_crc_hint {
+            self.cur_composite_crc = crc_util::compose_with_monomial(
+                self.cur_composite_crc,
+                crc_b,
+                self.precomputed_monomial_for_hint,
+                self.crc_polynomial,
+            );
+        } else {
+            self.cur_composite_crc = crc_util::compose(
+                self.cur_composite_crc,
+                crc_b,
+                bytes_per_crc,
+                self.crc_polynomial,
+            );
+        }
+
+        self.cur_position_in_stripe += bytes_per_crc;
+
+        if self.cur_position_in_stripe > self.stripe_length {
+            return Err(Error::msg(format!(
+                "Current position in stripe '{}' after advancing by bytesPerCrc '{}' \
+                exceeds stripeLength '{}' without stripe alignment.",
+                self.cur_position_in_stripe, bytes_per_crc, self.stripe_length
+            )));
+        } else if self.cur_position_in_stripe == self.stripe_length {
+            // Hit a stripe boundary; flush the curCompositeCrc and reset for
+            // next stripe.
+            self.digest_out
+                .extend_from_slice(&self.cur_composite_crc.to_be_bytes());
+            self.cur_composite_crc = 0;
+            self.cur_position_in_stripe = 0;
+        }
+        Ok(())
+    }
+
+    /// Returns byte representation of composed CRCs; if no stripeLength was
+    /// specified, the digest should be of length equal to exactly one CRC.
+    /// Otherwise, the number of CRCs in the returned array is equal to the
+    /// total sum bytesPerCrc divided by stripeLength. If the sum of
+    /// bytesPerCrc is not a multiple of stripeLength, then the last CRC in
+    /// the array corresponds to totalLength % stripeLength underlying data
+    /// bytes.
+    pub fn digest(&mut self) -> Vec<u8> {
+        if self.cur_position_in_stripe > 0 {
+            self.digest_out
+                .extend_from_slice(&self.cur_composite_crc.to_be_bytes());
+            self.cur_composite_crc = 0;
+            self.cur_position_in_stripe = 0;
+        }
+        std::mem::take(&mut self.digest_out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_striped_digest() -> anyhow::Result<()> {
+        let data = b"0123456789abcdefghij";
+        let mut composer = CrcComposer::new_striped_crc_composer(DataChecksumType::Crc32c, 5, 10)?;
+        for chunk in data.chunks(5) {
+            composer.update(crc32c::crc32c(chunk), 5)?;
+        }
+        let expected = [
+            crc32c::crc32c(&data[..10]).to_be_bytes(),
+            crc32c::crc32c(&data[10..]).to_be_bytes(),
+        ]
+        .concat();
+        assert_eq!(expected, composer.digest());
+
+        let mut composer = CrcComposer::new_crc_composer(DataChecksumType::Crc32, 5)?;
+        for chunk in data.chunks(7) {
+            composer.update(crc32fast::hash(chunk), chunk.len() as i64)?;
+        }
+        assert_eq!(
+            crc32fast::hash(data).to_be_bytes().to_vec(),
+            composer.digest()
+        );
+        assert!(CrcComposer::new_crc_composer(DataChecksumType::Null, 5).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,131 @@
+//! This module provides utilities for working with CRCs.
+
+/// The multiplicative identity of the CRC field, the polynomial "1".
+pub const MULTIPLICATIVE_IDENTITY: u32 = 0x80000000;
+/// The reversed representation of the CRC32 (gzip) polynomial.
+pub const GZIP_POLYNOMIAL: u32 = 0xEDB88320;
+/// The reversed representation of the CRC32C (Castagnoli) polynomial.
+pub const CASTAGNOLI_POLYNOMIAL: u32 = 0x82F63B78;
+
+/// Compute x^({`length_bytes`} * 8) mod {`m`}.
+pub fn get_monomial(length_bytes: i64, m: u32) -> u32 {
+    if length_bytes <= 0 {
+        return MULTIPLICATIVE_IDENTITY;
+    }
+
+    // Decompose into
+    // x^degree == x ^ SUM(bit[i] * 2^i) == PRODUCT(x ^ (bit[i] * 2^i))
+    // Generate each x^(2^i) by squaring.
+    // Since 'degree' is in 'bits', but we only need to support byte
+    // granularity we can begin with x^8.
+    let mut multiplier = MULTIPLICATIVE_IDENTITY >> 8;
+    let mut product = MULTIPLICATIVE_IDENTITY;
+    let mut degree = length_bytes;
+    while degree > 0 {
+        if degree & 1 != 0 {
+            product = if product == MULTIPLICATIVE_IDENTITY {
+                multiplier
+            } else {
+                galois_field_multiply(product, multiplier, m)
+            };
+        }
+        multiplier = galois_field_multiply(multiplier, multiplier, m);
+        degree >>= 1;
+    }
+    product
+}
+
+/// Compose the CRC of A and B, given the monomial x^(8 * length of B)
+/// returned by [`get_monomial`].
+pub fn compose_with_monomial(crc_a: u32, crc_b: u32, monomial: u32, m: u32) -> u32 {
+    galois_field_multiply(crc_a, monomial, m) ^ crc_b
+}
+
+/// Compute the CRC of the concatenation of A and B, given `crc_a`, `crc_b`
+/// and the length of B in bytes.
+pub fn compose(crc_a: u32, crc_b: u32, length_b: i64, m: u32) -> u32 {
+    let monomial = get_monomial(length_b, m);
+    compose_with_monomial(crc_a, crc_b, monomial, m)
+}
+
+/// Galois field multiplication of `p` and `q` with the generator
+/// polynomial `m` as the modulus.
+///
+/// The polynomials are represented in the "reversed" form, the top bit
+/// being the x^0 coefficient.
+pub fn galois_field_multiply(p: u32, q: u32, m: u32) -> u32 {
+    let mut summation = 0;
+
+    // Top bit is the x^0 place; each right-shift increments the degree of the
+    // current term.
+    let mut cur_term = MULTIPLICATIVE_IDENTITY;
+
+    // Iteratively multiply p by x mod m as we go to represent the q[i] term
+    // (of degree x^i) times p.
+    let mut px = p;
+
+    while cur_term != 0 {
+        if q & cur_term != 0 {
+            summation ^= px;
+        }
+
+        // Bottom bit represents highest degree since we're little-endian;
+        // before we multiply by "x" for the next term, check bottom bit to
+        // know whether the resulting px will thus have a term matching the
+        // implied "x^32" term represented by the mod.
+        let has_max_degree = px & 1 != 0;
+        px >>= 1;
+        // If px was already degree-31 (would become degree-32 after
+        // multiplying by x), then subtract the mod.
+        if has_max_degree {
+            px ^= m;
+        }
+        cur_term >>= 1;
+    }
+    summation
+}
+
+/// Read the big-endian CRC at `offset` of `buf`.
+pub fn read_int(buf: &[u8], offset: usize) -> anyhow::Result<u32> {
+    let bytes = buf.get(offset..offset + 4).ok_or_else(|| {
+        anyhow::Error::msg(format!(
+            "offset {} too large for buf.length {}",
+            offset,
+            buf.len()
+        ))
+    })?;
+    Ok(u32::from_be_bytes(bytes.try_into()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compose() {
+        let data = b"123456789";
+        for split in 0..=data.len() {
+            let (a, b) = data.split_at(split);
+            let crc = compose(
+                crc32fast::hash(a),
+                crc32fast::hash(b),
+                b.len() as i64,
+                GZIP_POLYNOMIAL,
+            );
+            assert_eq!(0xCBF43926, crc);
+            let crc = compose(
+                crc32c::crc32c(a),
+                crc32c::crc32c(b),
+                b.len() as i64,
+                CASTAGNOLI_POLYNOMIAL,
+            );
+            assert_eq!(0xE3069283, crc);
+        }
+        assert_eq!(
+            MULTIPLICATIVE_IDENTITY,
+            get_monomial(0, CASTAGNOLI_POLYNOMIAL)
+        );
+        assert!(read_int(&[1, 2, 3], 0).is_err());
+        assert_eq!(0x01020304, read_int(&[0, 1, 2, 3, 4], 1).unwrap());
+    }
+}
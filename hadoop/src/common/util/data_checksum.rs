@@ -0,0 +1,254 @@
+use super::crc_util;
+use crate::{common::io::IOException, hdfs::ChecksumTypeProto};
+use anyhow::Error;
+use std::{fmt, str::FromStr};
+
+/// The checksum types
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DataChecksumType {
+    Null,
+    Crc32,
+    Crc32c,
+}
+
+impl DataChecksumType {
+    /// The size of a checksum of this type in bytes.
+    pub fn size(&self) -> usize {
+        match self {
+            Self::Null => 0,
+            Self::Crc32 | Self::Crc32c => 4,
+        }
+    }
+
+    /// The polynomial of the CRC of this type, in the reversed form used by
+    /// [`crc_util`].
+    pub fn get_crc_polynomial(&self) -> anyhow::Result<u32> {
+        match self {
+            Self::Crc32 => Ok(crc_util::GZIP_POLYNOMIAL),
+            Self::Crc32c => Ok(crc_util::CASTAGNOLI_POLYNOMIAL),
+            _ => Err(Error::msg(format!(
+                "No CRC polynomial could be associated with type: {}",
+                self
+            ))),
+        }
+    }
+}
+
+impl From<ChecksumTypeProto> for DataChecksumType {
+    fn from(value: ChecksumTypeProto) -> Self {
+        match value {
+            ChecksumTypeProto::ChecksumNull => Self::Null,
+            ChecksumTypeProto::ChecksumCrc32 => Self::Crc32,
+            ChecksumTypeProto::ChecksumCrc32c => Self::Crc32c,
+        }
+    }
+}
+
+impl FromStr for DataChecksumType {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "NULL" => Ok(Self::Null),
+            "CRC32" => Ok(Self::Crc32),
+            "CRC32C" => Ok(Self::Crc32c),
+            _ => Err(Error::msg(format!("Unknown checksum type: {}", s))),
+        }
+    }
+}
+
+impl fmt::Display for DataChecksumType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Null => "NULL",
+            Self::Crc32 => "CRC32",
+            Self::Crc32c => "CRC32C",
+        };
+        f.write_str(name)
+    }
+}
+
+/// This class provides interface and utilities for processing checksums for
+/// DFS data transfers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DataChecksum {
+    checksum_type: DataChecksumType,
+    bytes_per_checksum: usize,
+}
+
+impl DataChecksum {
+    /// Create a DataChecksum of the given type, or `None` if
+    /// `bytes_per_checksum` is not positive.
+    pub fn new_data_checksum(
+        checksum_type: DataChecksumType,
+        bytes_per_checksum: i32,
+    ) -> Option<Self> {
+        if bytes_per_checksum <= 0 {
+            return None;
+        }
+        Some(Self {
+            checksum_type,
+            bytes_per_checksum: bytes_per_checksum as usize,
+        })
+    }
+
+    pub fn get_checksum_type(&self) -> DataChecksumType {
+        self.checksum_type
+    }
+
+    pub fn get_checksum_size(&self) -> usize {
+        self.checksum_type.size()
+    }
+
+    pub fn get_bytes_per_checksum(&self) -> usize {
+        self.bytes_per_checksum
+    }
+
+    /// Compute the checksum of a chunk. Both CRC variants use the hardware
+    /// instructions (SSE 4.2 / PCLMULQDQ or the ARM CRC extension) when the
+    /// CPU supports them, falling back to table driven implementations.
+    fn compute(&self, chunk: &[u8]) -> u32 {
+        match self.checksum_type {
+            DataChecksumType::Null => 0,
+            DataChecksumType::Crc32 => crc32fast::hash(chunk),
+            DataChecksumType::Crc32c => crc32c::crc32c(chunk),
+        }
+    }
+
+    /// Calculate the checksum of every chunk of `data`, appending them to
+    /// `checksums`. The last chunk may be shorter than bytes per checksum.
+    pub fn calculate_chunked_sums(&self, data: &[u8], checksums: &mut Vec<u8>) {
+        if self.checksum_type == DataChecksumType::Null {
+            return;
+        }
+        for chunk in data.chunks(self.bytes_per_checksum) {
+            checksums.extend_from_slice(&self.compute(chunk).to_be_bytes());
+        }
+    }
+
+    /// Verify that the given checksums match the given data.
+    ///
+    /// `data` is cut into chunks of bytes per checksum, the last one may be
+    /// shorter, and `checksums` holds one checksum per chunk. `file_name` and
+    /// `base_pos`, the position of `data` in the file or block, are used in
+    /// the error reporting the first chunk that does not match.
+    pub fn verify_chunked_sums(
+        &self,
+        data: &[u8],
+        checksums: &[u8],
+        file_name: &str,
+        base_pos: i64,
+    ) -> anyhow::Result<()> {
+        if self.checksum_type == DataChecksumType::Null {
+            return Ok(());
+        }
+        let checksum_size = self.get_checksum_size();
+        let num_chunks = data.len().div_ceil(self.bytes_per_checksum);
+        if checksums.len() < num_chunks * checksum_size {
+            return Err(Error::new(IOException::Checksum(format!(
+                "Checksum {} missing for file {} at position {}: expected {} checksum bytes \
+                but got {}",
+                self.checksum_type,
+                file_name,
+                base_pos,
+                num_chunks * checksum_size,
+                checksums.len()
+            ))));
+        }
+        let sums = checksums.chunks_exact(checksum_size);
+        for (i, (chunk, sum)) in data.chunks(self.bytes_per_checksum).zip(sums).enumerate() {
+            let expected = u32::from_be_bytes(sum.try_into()?);
+            let computed = self.compute(chunk);
+            if computed != expected {
+                let err_pos = base_pos + (i * self.bytes_per_checksum) as i64;
+                return Err(Error::new(IOException::Checksum(format!(
+                    "Checksum {} not matched for file {} at position {}: expected={:X} but \
+                    computed={:X}",
+                    self.checksum_type, file_name, err_pos, expected, computed
+                ))));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for DataChecksum {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "DataChecksum(type={}, chunkSize={})",
+            self.checksum_type, self.bytes_per_checksum
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_calculate_chunked_sums() {
+        let checksum = DataChecksum::new_data_checksum(DataChecksumType::Crc32c, 5).unwrap();
+        let mut checksums = vec![];
+        checksum.calculate_chunked_sums(b"123456789", &mut checksums);
+        assert_eq!(
+            checksums,
+            [
+                crc32c::crc32c(b"12345").to_be_bytes(),
+                crc32c::crc32c(b"6789").to_be_bytes()
+            ]
+            .concat()
+        );
+        checksums.clear();
+        let checksum = DataChecksum::new_data_checksum(DataChecksumType::Crc32c, 9).unwrap();
+        checksum.calculate_chunked_sums(b"123456789", &mut checksums);
+        assert_eq!(checksums, 0xE3069283_u32.to_be_bytes());
+
+        checksums.clear();
+        let checksum = DataChecksum::new_data_checksum(DataChecksumType::Crc32, 9).unwrap();
+        checksum.calculate_chunked_sums(b"123456789", &mut checksums);
+        assert_eq!(checksums, 0xCBF43926_u32.to_be_bytes());
+
+        checksums.clear();
+        let checksum = DataChecksum::new_data_checksum(DataChecksumType::Null, 5).unwrap();
+        checksum.calculate_chunked_sums(b"123456789", &mut checksums);
+        assert!(checksums.is_empty());
+        assert!(DataChecksum::new_data_checksum(DataChecksumType::Null, 0).is_none());
+        assert_eq!(
+            DataChecksumType::Crc32c,
+            "CRC32C".parse::<DataChecksumType>().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_verify_chunked_sums() {
+        for checksum_type in [DataChecksumType::Crc32, DataChecksumType::Crc32c] {
+            let checksum = DataChecksum::new_data_checksum(checksum_type, 4).unwrap();
+            let data = b"0123456789";
+            let mut checksums = vec![];
+            checksum.calculate_chunked_sums(data, &mut checksums);
+            assert_eq!(12, checksums.len());
+            assert!(checksum
+                .verify_chunked_sums(data, &checksums, "file", 0)
+                .is_ok());
+
+            // corrupt the second chunk
+            let mut corrupted = data.to_vec();
+            corrupted[5] ^= 1;
+            let e = checksum
+                .verify_chunked_sums(&corrupted, &checksums, "file", 100)
+                .unwrap_err();
+            let e = e.downcast_ref::<IOException>().unwrap();
+            assert!(matches!(e, IOException::Checksum(_)));
+            assert!(e.get_message().contains("at position 104"));
+
+            assert!(checksum
+                .verify_chunked_sums(data, &checksums[..8], "file", 0)
+                .is_err());
+        }
+        let checksum = DataChecksum::new_data_checksum(DataChecksumType::Null, 4).unwrap();
+        assert!(checksum
+            .verify_chunked_sums(b"data", &[], "file", 0)
+            .is_ok());
+    }
+}
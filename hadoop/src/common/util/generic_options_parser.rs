@@ -0,0 +1,162 @@
+use crate::common::{conf::Configuration, fs::file_system};
+use anyhow::Error;
+use std::io::Write;
+
+/// `GenericOptionsParser` is a utility to parse command line
+/// arguments generic to the Hadoop framework.
+///
+/// `GenericOptionsParser` recognizes several standard command
+/// line arguments, enabling applications to easily specify a namenode, a
+/// ResourceManager, additional configuration resources etc.
+///
+/// The supported generic options are:
+/// ```text
+/// -conf <configuration file>     specify a configuration file
+/// -D <property=value>            use value for given property
+/// -fs <local|namenode:port>      specify a namenode
+/// -jt <local|resourcemanager:port>    specify a ResourceManager
+/// ```
+///
+/// Generic options are only recognized at the start of the arguments, parsing
+/// stops at the first argument which is not a generic option. The options
+/// are applied to the configuration in the order `-fs`, `-jt`, `-conf`, `-D`,
+/// so properties given with `-D` take precedence.
+pub struct GenericOptionsParser {
+    remaining_args: Vec<String>,
+}
+
+impl GenericOptionsParser {
+    /// Create a `GenericOptionsParser` to parse given options as well
+    /// as generic Hadoop options, which are applied to `conf`.
+    ///
+    /// The resulting remaining arguments can be obtained from
+    /// [`GenericOptionsParser::get_remaining_args`].
+    pub fn new(conf: &mut Configuration, args: &[impl AsRef<str>]) -> anyhow::Result<Self> {
+        let mut fs = None;
+        let mut jt = None;
+        let mut conf_files = vec![];
+        let mut properties = vec![];
+
+        let mut args = args.iter().map(AsRef::as_ref);
+        let mut remaining_args = vec![];
+        while let Some(arg) = args.next() {
+            let mut value = |option: &str| {
+                args.next().map(str::to_owned).ok_or(Error::msg(format!(
+                    "Missing argument for option: {}",
+                    option
+                )))
+            };
+            match arg {
+                "-fs" => fs = Some(value("fs")?),
+                "-jt" => jt = Some(value("jt")?),
+                "-conf" => conf_files.push(value("conf")?),
+                "-D" => properties.push(value("D")?),
+                _ if arg.starts_with("-D") && arg.len() > 2 => properties.push(arg[2..].to_owned()),
+                "--" => {
+                    remaining_args.extend(args.by_ref().map(str::to_owned));
+                }
+                _ => {
+                    remaining_args.push(arg.to_owned());
+                    remaining_args.extend(args.by_ref().map(str::to_owned));
+                }
+            }
+        }
+
+        if let Some(fs) = fs {
+            file_system::set_default_uri(conf, &fs);
+        }
+        if let Some(jt) = jt {
+            if jt.eq_ignore_ascii_case("local") {
+                conf.set("mapreduce.framework.name", &jt);
+            }
+            conf.set("yarn.resourcemanager.address", &jt);
+        }
+        for conf_file in conf_files {
+            conf.add_resource_path(conf_file)?;
+        }
+        for property in properties {
+            match property.split_once('=') {
+                Some((key, value)) => conf.set(key, value),
+                None => log::warn!("Ignoring -D option without a value: {}", property),
+            }
+        }
+
+        Ok(Self { remaining_args })
+    }
+
+    /// Returns an array of Strings containing only application-specific
+    /// arguments.
+    pub fn get_remaining_args(&self) -> &[String] {
+        &self.remaining_args
+    }
+
+    /// Print the usage message for generic command-line options supported.
+    pub fn print_generic_command_usage(out: &mut impl Write) -> std::io::Result<()> {
+        writeln!(out, "Generic options supported are:")?;
+        writeln!(
+            out,
+            "-conf <configuration file>        specify an application configuration file"
+        )?;
+        writeln!(
+            out,
+            "-D <property=value>               define a value for a given property"
+        )?;
+        writeln!(
+            out,
+            "-fs <file:///|hdfs://namenode:port> specify default filesystem URL to use, overrides 'fs.defaultFS' property from configurations."
+        )?;
+        writeln!(
+            out,
+            "-jt <local|resourcemanager:port>  specify a ResourceManager"
+        )?;
+        writeln!(out)?;
+        writeln!(out, "The general command line syntax is:")?;
+        writeln!(out, "command [genericOptions] [commandOptions]")?;
+        writeln!(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{env, fs};
+    use uuid::Uuid;
+
+    #[test]
+    fn test_generic_options() {
+        let conf_file = env::temp_dir().join(format!("{}.xml", Uuid::new_v4()));
+        fs::write(
+            &conf_file,
+            "<configuration>
+              <property><name>a</name><value>from-file</value></property>
+              <property><name>b</name><value>from-file</value></property>
+            </configuration>",
+        )
+        .unwrap();
+
+        let mut conf = Configuration::with_load_defaults(false);
+        let args = [
+            "-fs",
+            "nn:8020",
+            "-conf",
+            conf_file.to_str().unwrap(),
+            "-D",
+            "a=x=y",
+            "-Db=2",
+            "-ls",
+            "-D",
+            "c=3",
+        ];
+        let parser = GenericOptionsParser::new(&mut conf, &args).unwrap();
+        fs::remove_file(&conf_file).unwrap();
+
+        assert_eq!(parser.get_remaining_args(), ["-ls", "-D", "c=3"]);
+        assert_eq!(conf.get("fs.defaultFS", None).unwrap(), "hdfs://nn:8020");
+        assert_eq!(conf.get("a", None).unwrap(), "x=y");
+        assert_eq!(conf.get("b", None).unwrap(), "2");
+        assert_eq!(conf.get("c", None), None);
+
+        assert!(GenericOptionsParser::new(&mut conf, &["-conf"]).is_err());
+        assert!(GenericOptionsParser::new(&mut conf, &["-conf", "typo.xml"]).is_err());
+    }
+}
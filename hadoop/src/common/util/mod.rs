@@ -1,3 +1,11 @@
+mod crc_composer;
+pub mod crc_util;
+mod data_checksum;
+mod generic_options_parser;
 mod proto_util;
+pub mod string_utils;
 
+pub use crc_composer::CrcComposer;
+pub use data_checksum::{DataChecksum, DataChecksumType};
+pub use generic_options_parser::GenericOptionsParser;
 pub use proto_util::ProtoUtil;
@@ -1,10 +1,10 @@
 use crate::common::{
-    ipc::{client_id::BYTE_LENGTH, AlignmentContext, RpcKind},
+    ipc::{AlignmentContext, RpcKind},
     rpc_request_header_proto::OperationProto,
-    security::UserGroupInformation,
+    security::{AuthMethod, UserGroupInformation},
     IpcConnectionContextProto, RpcKindProto, RpcRequestHeaderProto, UserInformationProto,
 };
-use std::rc::Rc;
+use std::sync::Arc;
 
 pub struct ProtoUtil;
 
@@ -12,18 +12,18 @@ impl ProtoUtil {
     pub fn make_ipc_connection_context(
         protocol: Option<&str>,
         ugi: Option<&UserGroupInformation>,
-        auth_method: &str,
+        auth_method: &AuthMethod,
     ) -> IpcConnectionContextProto {
         let mut ugi_proto = UserInformationProto {
             effective_user: None,
             real_user: None,
         };
         if let Some(ugi) = ugi {
-            if auth_method == "kerberos" {
+            if *auth_method == AuthMethod::Kerberos {
                 // Real user was established as part of the connection.
                 // Send effective user only.
                 ugi_proto.effective_user = Some(ugi.get_user_name());
-            } else if auth_method == "token" {
+            } else if *auth_method == AuthMethod::Token {
                 // With token, the connection itself establishes
                 // both real and effective user. Hence send none in header.
             } else {
@@ -48,8 +48,8 @@ impl ProtoUtil {
         operation: OperationProto,
         call_id: i32,
         retry_count: i32,
-        uuid: &[u8; BYTE_LENGTH],
-        alignment_context: Option<Rc<dyn AlignmentContext>>,
+        uuid: &[u8],
+        alignment_context: Option<Arc<dyn AlignmentContext>>,
     ) -> RpcRequestHeaderProto {
         let result = RpcRequestHeaderProto {
             rpc_kind: Some(RpcKindProto::from(rpc_kind).into()),
@@ -0,0 +1,168 @@
+use anyhow::Error;
+
+/// The traditional binary prefixes, kilo, mega, ..., exa,
+/// which can be represented by a 64-bit integer.
+/// TraditionalBinaryPrefix symbol are case insensitive.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum TraditionalBinaryPrefix {
+    Kilo,
+    Mega,
+    Giga,
+    Tera,
+    Peta,
+    Exa,
+}
+
+impl TraditionalBinaryPrefix {
+    pub fn values() -> [Self; 6] {
+        [
+            Self::Kilo,
+            Self::Mega,
+            Self::Giga,
+            Self::Tera,
+            Self::Peta,
+            Self::Exa,
+        ]
+    }
+
+    pub fn bit_shift(&self) -> u32 {
+        10 * (*self as u32 + 1)
+    }
+
+    pub fn value(&self) -> i64 {
+        1 << self.bit_shift()
+    }
+
+    pub fn symbol(&self) -> char {
+        match self {
+            Self::Kilo => 'K',
+            Self::Mega => 'M',
+            Self::Giga => 'G',
+            Self::Tera => 'T',
+            Self::Peta => 'P',
+            Self::Exa => 'E',
+        }
+    }
+
+    /// Returns the TraditionalBinaryPrefix object corresponding to the symbol.
+    pub fn value_of(symbol: char) -> Option<Self> {
+        let symbol = symbol.to_ascii_uppercase();
+        Self::values().into_iter().find(|p| p.symbol() == symbol)
+    }
+
+    /// Convert a string to long.
+    /// The input string is first be trimmed
+    /// and then it is parsed with traditional binary prefix.
+    ///
+    /// For example,
+    /// "-1230k" will be converted to -1230 * 1024 = -1259520;
+    /// "891g" will be converted to 891 * 1024^3 = 956703965184;
+    pub fn string2long(s: &str) -> anyhow::Result<i64> {
+        let s = s.trim();
+        let last_char = s
+            .chars()
+            .last()
+            .ok_or(Error::msg("Cannot parse an empty string as a long"))?;
+        if last_char.is_ascii_digit() {
+            return Ok(s.parse()?);
+        }
+        let prefix = Self::value_of(last_char).ok_or(Error::msg(format!(
+            "Invalid size prefix '{}' in '{}'. Allowed prefixes are k, m, g, t, p, e(case insensitive)",
+            last_char, s
+        )))?;
+        let num: i64 = s[..s.len() - last_char.len_utf8()].parse()?;
+        num.checked_mul(prefix.value())
+            .ok_or(Error::msg(format!("{} does not fit in a Long", s)))
+    }
+
+    /// Convert a long integer to a string with traditional binary prefix.
+    pub fn long2string(n: i64, unit: &str, decimal_places: usize) -> String {
+        // take care a special case
+        if n == i64::MIN {
+            return format!("-8 {}{}", Self::Exa.symbol(), unit);
+        }
+
+        let mut b = String::new();
+        // take care negative numbers
+        let n = if n < 0 {
+            b.push('-');
+            -n
+        } else {
+            n
+        };
+        if n < Self::Kilo.value() {
+            // no prefix
+            b.push_str(&n.to_string());
+            if !unit.is_empty() {
+                b.push(' ');
+                b.push_str(unit);
+            }
+            return b;
+        }
+
+        // find traditional binary prefix
+        let values = Self::values();
+        let i = values.iter().take_while(|p| n >= p.value()).count();
+        let mut prefix = values[i - 1];
+        if n & (prefix.value() - 1) == 0 {
+            // exact division
+            b.push_str(&(n >> prefix.bit_shift()).to_string());
+        } else {
+            let mut s = format!("{:.*}", decimal_places, n as f64 / prefix.value() as f64);
+            // check a special rounding up case
+            if s.starts_with("1024") && i < values.len() {
+                prefix = values[i];
+                s = format!("{:.*}", decimal_places, n as f64 / prefix.value() as f64);
+            }
+            b.push_str(&s);
+        }
+        format!("{} {}{}", b, prefix.symbol(), unit)
+    }
+}
+
+/// Return an abbreviated English-language desc of the byte length
+pub fn byte_desc(len: i64) -> String {
+    TraditionalBinaryPrefix::long2string(len, "B", 2)
+}
+
+/// Format a percentage for presentation to the user.
+pub fn format_percent(fraction: f64, decimal_places: usize) -> String {
+    format!("{:.*}%", decimal_places, fraction * 100.0)
+}
+
+/// Given an array of bytes it will convert the bytes to a hex string
+/// representation of the bytes
+pub fn byte_to_hex_string(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_traditional_binary_prefix() {
+        assert_eq!(
+            TraditionalBinaryPrefix::string2long("-1230k").unwrap(),
+            -1259520
+        );
+        assert_eq!(
+            TraditionalBinaryPrefix::string2long("891g").unwrap(),
+            956703965184
+        );
+        assert!(TraditionalBinaryPrefix::string2long("10x").is_err());
+
+        assert_eq!(TraditionalBinaryPrefix::long2string(0, "", 1), "0");
+        assert_eq!(TraditionalBinaryPrefix::long2string(1023, "B", 1), "1023 B");
+        assert_eq!(TraditionalBinaryPrefix::long2string(1024, "", 1), "1 K");
+        assert_eq!(TraditionalBinaryPrefix::long2string(1536, "", 1), "1.5 K");
+        assert_eq!(
+            TraditionalBinaryPrefix::long2string((1 << 20) - 1, "", 1),
+            "1.0 M"
+        );
+        assert_eq!(TraditionalBinaryPrefix::long2string(-2048, "B", 2), "-2 KB");
+        assert_eq!(byte_desc(3 << 30), "3 GB");
+        assert_eq!(format_percent(0.6, 0), "60%");
+        assert_eq!(byte_to_hex_string(&[0, 0xab, 0x10]), "00ab10");
+    }
+}
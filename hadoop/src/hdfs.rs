@@ -1,4 +1,8 @@
+// the docs of the generated protobuf messages are not markdown
+#![allow(clippy::doc_lazy_continuation)]
+
 pub mod fs;
+#[allow(clippy::module_inception)]
 pub mod hdfs;
 
 include!(concat!(env!("OUT_DIR"), "/hadoop.hdfs.rs"));
@@ -1,12 +1,17 @@
+#[cfg(feature = "async")]
+use crate::hdfs::hdfs::AsyncDistributedFileSystem;
 use crate::{
-    common::{conf::Configuration, fs::FileSystem},
-    hdfs::hdfs::DistributedFileSystem,
+    common::{
+        conf::Configuration,
+        fs::{FileSystem, RawLocalFileSystem, LOCAL_FS_SCHEME},
+    },
+    hdfs::hdfs::{protocol::hdfs_constants, DistributedFileSystem},
 };
 use anyhow::Error;
 use iref::Iri;
 
 /// Get a FileSystem for this URI's scheme and authority.
-pub fn get(uri: &Iri, conf: &Configuration) -> anyhow::Result<impl FileSystem> {
+pub fn get(uri: &Iri, conf: &Configuration) -> anyhow::Result<Box<dyn FileSystem>> {
     // TODO: fallback to default fs, scheme, authority
 
     // TODO: CACHE
@@ -14,10 +19,24 @@ pub fn get(uri: &Iri, conf: &Configuration) -> anyhow::Result<impl FileSystem> {
     create_file_system(uri, conf)
 }
 
+/// Get an [`AsyncDistributedFileSystem`] for this HDFS URI, whose calls to
+/// the NameNode are made asynchronously.
+#[cfg(feature = "async")]
+pub fn get_async(uri: &Iri, conf: &Configuration) -> anyhow::Result<AsyncDistributedFileSystem> {
+    match uri.scheme().as_str() {
+        hdfs_constants::HDFS_URI_SCHEME => AsyncDistributedFileSystem::new(uri, conf),
+        _ => Err(Error::msg(format!(
+            "Failed to initialize asynchronous fileystem {}",
+            uri
+        ))),
+    }
+}
+
 /// Create and initialize a new instance of a FileSystem.
-fn create_file_system(uri: &Iri, conf: &Configuration) -> anyhow::Result<impl FileSystem> {
+fn create_file_system(uri: &Iri, conf: &Configuration) -> anyhow::Result<Box<dyn FileSystem>> {
     match uri.scheme().as_str() {
-        "hdfs" => Ok(DistributedFileSystem::new(uri, conf)?),
+        hdfs_constants::HDFS_URI_SCHEME => Ok(Box::new(DistributedFileSystem::new(uri, conf)?)),
+        LOCAL_FS_SCHEME => Ok(Box::new(RawLocalFileSystem::new(uri, conf)?)),
         _ => Err(Error::msg(format!(
             "Failed to initialize fileystem {}",
             uri
@@ -0,0 +1,421 @@
+use super::{
+    distributed_file_system::{file_not_found, get_path_name},
+    protocol::{AsyncClientProtocol, EMPTY_NAME},
+    DFSUtilClient, HdfsConfiguration, NameNodeProxiesClient,
+};
+use crate::common::{
+    conf::Configuration,
+    fs::{
+        options::Rename,
+        permission::{fs_permission::DIR_DEFAULT_PERM, FsCreateModes, FsPermission},
+        ContentSummary, FileStatus, Path,
+    },
+    ipc::remote_exception::unwrap_remote_exception,
+    security::UserGroupInformation,
+};
+use anyhow::Error;
+use iref::{Iri, IriBuf};
+
+/// Implementation of the asynchronous distributed file system.
+/// The calls are made asynchronously over the IPC connection to the
+/// NameNode, so that many of them may be outstanding at the same time,
+/// bounded by `ipc.client.async.calls.max`. Failed calls are retried as
+/// `dfs.client.retry.policy.*` sets, like the calls of the synchronous
+/// client.
+pub struct AsyncDistributedFileSystem {
+    working_dir: Path,
+    uri: IriBuf,
+    namenode: Box<dyn AsyncClientProtocol>,
+    umask: FsPermission,
+}
+
+impl AsyncDistributedFileSystem {
+    pub fn new(uri: &Iri, conf: &Configuration) -> anyhow::Result<Self> {
+        HdfsConfiguration::init();
+        let proxy_info = NameNodeProxiesClient::create_async_proxy_with_client_protocol(conf, uri)?;
+        Self::with_namenode(uri, conf, proxy_info.proxy)
+    }
+
+    fn with_namenode(
+        uri: &Iri,
+        conf: &Configuration,
+        namenode: Box<dyn AsyncClientProtocol>,
+    ) -> anyhow::Result<Self> {
+        let mut base_uri: IriBuf = IriBuf::from_scheme(uri.scheme().to_owned());
+        base_uri.set_authority(Some(
+            uri.authority()
+                .ok_or(Error::msg(format!("Incomplete HDFS URI, no host: {}", uri)))?,
+        ));
+        let ugi = UserGroupInformation::get_current_user()?;
+        let working_dir = Path::new(&DFSUtilClient::get_home_directory(Some(conf), &ugi))?
+            .make_qualified(base_uri.as_iri(), &Path::new("/")?)?;
+        Ok(Self {
+            working_dir,
+            uri: base_uri,
+            namenode,
+            umask: FsPermission::get_umask(Some(conf))?,
+        })
+    }
+
+    pub fn get_uri(&self) -> &Iri {
+        self.uri.as_iri()
+    }
+
+    pub fn get_working_directory(&self) -> &Path {
+        &self.working_dir
+    }
+
+    /// Returns the stat information about the file.
+    pub async fn get_file_status(&self, f: &Path) -> anyhow::Result<FileStatus> {
+        let abs_f = self.fix_relative_part(f)?;
+        match self
+            .namenode
+            .get_file_info_async(&self.get_path_name(&abs_f)?)
+            .await
+            .map_err(unwrap_remote_exception)?
+        {
+            Some(fi) => fi.make_qualified(self.get_uri(), &abs_f),
+            None => Err(file_not_found(format!("File does not exist: {}", abs_f))),
+        }
+    }
+
+    /// List all the entries of a directory.
+    ///
+    /// Will not return "." and ".." entries. The entries are fetched from
+    /// the NameNode in batches until the whole directory has been listed.
+    pub async fn list_status(&self, p: &Path) -> anyhow::Result<Vec<FileStatus>> {
+        let abs_f = self.fix_relative_part(p)?;
+        let src = self.get_path_name(&abs_f)?;
+        let mut start_after = EMPTY_NAME.to_vec();
+        let mut listing = vec![];
+        loop {
+            let this_listing = self
+                .namenode
+                .get_listing_async(&src, &start_after, false)
+                .await
+                .map_err(unwrap_remote_exception)?
+                .ok_or_else(|| file_not_found(format!("File {} does not exist.", abs_f)))?;
+            for file_status in this_listing.get_partial_listing() {
+                listing.push(file_status.make_qualified(self.get_uri(), &abs_f)?);
+            }
+            if !this_listing.has_more() {
+                // got all entries of the directory
+                return Ok(listing);
+            }
+            start_after = this_listing.get_last_name().to_vec();
+        }
+    }
+
+    /// Make the given file and all non-existent parents into directories.
+    pub async fn mkdirs(
+        &self,
+        f: &Path,
+        permission: Option<&FsPermission>,
+    ) -> anyhow::Result<bool> {
+        let abs_f = self.fix_relative_part(f)?;
+        let masked = self.apply_umask(permission.unwrap_or(&DIR_DEFAULT_PERM));
+        self.namenode
+            .mkdirs_async(&self.get_path_name(&abs_f)?, &masked, true)
+            .await
+            .map_err(unwrap_remote_exception)
+    }
+
+    /// Delete a file.
+    pub async fn delete(&self, f: &Path, recursive: bool) -> anyhow::Result<bool> {
+        let abs_f = self.fix_relative_part(f)?;
+        self.namenode
+            .delete_async(&self.get_path_name(&abs_f)?, recursive)
+            .await
+            .map_err(unwrap_remote_exception)
+    }
+
+    /// Renames Path src to Path dst. This rename operation is guaranteed to
+    /// be atomic.
+    pub async fn rename(&self, src: &Path, dst: &Path, options: &[Rename]) -> anyhow::Result<()> {
+        let abs_src = self.fix_relative_part(src)?;
+        let abs_dst = self.fix_relative_part(dst)?;
+        self.namenode
+            .rename2_async(
+                &self.get_path_name(&abs_src)?,
+                &self.get_path_name(&abs_dst)?,
+                options,
+            )
+            .await
+            .map_err(unwrap_remote_exception)
+    }
+
+    /// Set permission of a path.
+    pub async fn set_permission(&self, p: &Path, permission: &FsPermission) -> anyhow::Result<()> {
+        let abs_f = self.fix_relative_part(p)?;
+        self.namenode
+            .set_permission_async(&self.get_path_name(&abs_f)?, permission)
+            .await
+            .map_err(unwrap_remote_exception)
+    }
+
+    /// Set owner of a path (i.e. a file or a directory).
+    /// The parameters username and groupname cannot both be null.
+    pub async fn set_owner(
+        &self,
+        p: &Path,
+        username: Option<&str>,
+        groupname: Option<&str>,
+    ) -> anyhow::Result<()> {
+        if username.is_none() && groupname.is_none() {
+            return Err(Error::msg("username == null && groupname == null"));
+        }
+        let abs_f = self.fix_relative_part(p)?;
+        self.namenode
+            .set_owner_async(&self.get_path_name(&abs_f)?, username, groupname)
+            .await
+            .map_err(unwrap_remote_exception)
+    }
+
+    /// Set the replication for an existing file.
+    pub async fn set_replication(&self, src: &Path, replication: i16) -> anyhow::Result<bool> {
+        let abs_f = self.fix_relative_part(src)?;
+        self.namenode
+            .set_replication_async(&self.get_path_name(&abs_f)?, replication)
+            .await
+            .map_err(unwrap_remote_exception)
+    }
+
+    /// Set access time of a file.
+    pub async fn set_times(&self, p: &Path, mtime: i64, atime: i64) -> anyhow::Result<()> {
+        let abs_f = self.fix_relative_part(p)?;
+        self.namenode
+            .set_times_async(&self.get_path_name(&abs_f)?, mtime, atime)
+            .await
+            .map_err(unwrap_remote_exception)
+    }
+
+    /// Return the [`ContentSummary`] of a given path.
+    pub async fn get_content_summary(&self, f: &Path) -> anyhow::Result<ContentSummary> {
+        let abs_f = self.fix_relative_part(f)?;
+        self.namenode
+            .get_content_summary_async(&self.get_path_name(&abs_f)?)
+            .await
+            .map_err(unwrap_remote_exception)
+    }
+
+    fn apply_umask(&self, permission: &FsPermission) -> FsCreateModes {
+        FsCreateModes::apply_umask(permission, &self.umask)
+    }
+
+    fn get_path_name(&self, file: &Path) -> anyhow::Result<String> {
+        get_path_name(self.get_uri(), file)
+    }
+
+    fn fix_relative_part(&self, p: &Path) -> anyhow::Result<Path> {
+        if p.is_uri_path_absolute() {
+            Ok(p.clone())
+        } else {
+            Path::from_parent(&self.working_dir, p)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        common::io::{
+            retry::{RetryInvocationHandler, RetryPolicies},
+            IOException,
+        },
+        hdfs::{
+            hdfs::{
+                distributed_file_system::tests::{file_status, paged_namenode, remote_exception},
+                protocol::{HdfsFileStatus, MockClientProtocol},
+            },
+            hdfs_file_status_proto::FileType,
+            HdfsFileStatusProto,
+        },
+    };
+    use std::{
+        collections::HashMap,
+        sync::{
+            atomic::{AtomicI32, Ordering},
+            Arc, Mutex,
+        },
+    };
+
+    /// Create an AsyncDistributedFileSystem for `hdfs://nn:8020` whose
+    /// NameNode is `namenode`.
+    fn create_async_dfs(
+        namenode: impl AsyncClientProtocol + 'static,
+    ) -> anyhow::Result<AsyncDistributedFileSystem> {
+        let uri = IriBuf::new("hdfs://nn:8020".to_owned())?;
+        AsyncDistributedFileSystem::with_namenode(
+            uri.as_iri(),
+            &Configuration::new(),
+            Box::new(namenode),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_get_file_status() -> anyhow::Result<()> {
+        let fs = create_async_dfs(MockClientProtocol {
+            get_file_info: Some(Box::new(|src| {
+                Ok((src == "/d/f").then(|| HdfsFileStatus::from(&file_status("", 300, false))))
+            })),
+            ..Default::default()
+        })?;
+
+        let status = fs.get_file_status(&Path::new("/d/f")?).await?;
+        assert_eq!(status.get_path().to_string(), "hdfs://nn:8020/d/f");
+        assert_eq!(status.get_len(), 300);
+        let err = fs
+            .get_file_status(&Path::new("/d/missing")?)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<IOException>(),
+            Some(IOException::FileNotFound(msg)) if msg == "File does not exist: /d/missing"
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_list_status_pages() -> anyhow::Result<()> {
+        let start_afters = Arc::new(Mutex::new(vec![]));
+        let fs = create_async_dfs(paged_namenode(Arc::clone(&start_afters)))?;
+
+        let statuses = fs.list_status(&Path::new("/d")?).await?;
+        let names = statuses
+            .iter()
+            .map(|status| status.get_path().get_name())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["f0", "f1", "f2", "f3", "f4"]);
+        assert_eq!(*start_afters.lock().unwrap(), ["", "f1", "f3"]);
+
+        let err = fs.list_status(&Path::new("/missing")?).await.unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<IOException>(),
+            Some(IOException::FileNotFound(_))
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_mkdirs_applies_umask() -> anyhow::Result<()> {
+        let fs = create_async_dfs(MockClientProtocol {
+            mkdirs: Some(Box::new(|src, create_modes, create_parent| {
+                assert_eq!(src, "/d/e");
+                assert!(create_parent);
+                assert_eq!(create_modes.get_masked().to_short(), 0o755);
+                assert_eq!(create_modes.get_unmasked().to_short(), 0o777);
+                Ok(true)
+            })),
+            ..Default::default()
+        })?;
+
+        assert!(fs.mkdirs(&Path::new("/d/e")?, None).await?);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_delete_remote_exceptions() -> anyhow::Result<()> {
+        let fs = create_async_dfs(MockClientProtocol {
+            delete: Some(Box::new(|src, recursive| match src {
+                "/d/f" => Ok(!recursive),
+                "/d/missing" => Err(remote_exception(
+                    "java.io.FileNotFoundException",
+                    "File does not exist: /d/missing",
+                )),
+                _ => Err(remote_exception(
+                    "org.apache.hadoop.fs.PathIsNotEmptyDirectoryException",
+                    "`/d is non empty': Directory is not empty",
+                )),
+            })),
+            ..Default::default()
+        })?;
+
+        assert!(fs.delete(&Path::new("/d/f")?, false).await?);
+        let err = fs
+            .delete(&Path::new("/d/missing")?, false)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<IOException>(),
+            Some(IOException::FileNotFound(msg)) if msg == "File does not exist: /d/missing"
+        ));
+        let err = fs.delete(&Path::new("/d")?, false).await.unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<IOException>(),
+            Some(IOException::PathIsNotEmptyDirectory(_))
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rename_remote_exceptions() -> anyhow::Result<()> {
+        let fs = create_async_dfs(MockClientProtocol {
+            rename2: Some(Box::new(|src, dst, options| match (src, options) {
+                ("/d/f", [Rename::Overwrite]) => Ok(()),
+                ("/d/missing", _) => Err(remote_exception(
+                    "java.io.FileNotFoundException",
+                    "rename source /d/missing is not found.",
+                )),
+                _ => Err(remote_exception(
+                    "org.apache.hadoop.fs.FileAlreadyExistsException",
+                    &format!("rename destination {} already exists", dst),
+                )),
+            })),
+            ..Default::default()
+        })?;
+
+        let (src, dst) = (Path::new("/d/f")?, Path::new("/d/g")?);
+        fs.rename(&src, &dst, &[Rename::Overwrite]).await?;
+        let err = fs.rename(&src, &dst, &[Rename::None]).await.unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<IOException>(),
+            Some(IOException::FileAlreadyExists(msg))
+                if msg == "rename destination /d/g already exists"
+        ));
+        let err = fs
+            .rename(&Path::new("/d/missing")?, &dst, &[])
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<IOException>(),
+            Some(IOException::FileNotFound(_))
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_retry_policy() -> anyhow::Result<()> {
+        // the NameNode asks to retry the first two calls
+        let calls = Arc::new(AtomicI32::new(0));
+        let namenode = MockClientProtocol {
+            get_file_info: Some(Box::new({
+                let calls = Arc::clone(&calls);
+                move |_| {
+                    if calls.fetch_add(1, Ordering::SeqCst) < 2 {
+                        return Err(Error::new(IOException::Retriable("try again".to_owned())));
+                    }
+                    Ok(Some(HdfsFileStatus::from(&HdfsFileStatusProto {
+                        file_type: FileType::IsDir as i32,
+                        ..Default::default()
+                    })))
+                }
+            })),
+            ..Default::default()
+        };
+        let fs = create_async_dfs(RetryInvocationHandler::new(
+            namenode,
+            RetryPolicies::retry_up_to_maximum_count_with_fixed_sleep(2, 10),
+            HashMap::new(),
+        ))?;
+
+        assert!(fs.get_file_status(&Path::new("/d")?).await?.is_directory());
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+        // and gives up after the maximum count of retries
+        calls.store(-1, Ordering::SeqCst);
+        assert!(fs.get_file_status(&Path::new("/d")?).await.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        Ok(())
+    }
+}
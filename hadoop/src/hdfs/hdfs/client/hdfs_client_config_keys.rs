@@ -1,3 +1,65 @@
+use crate::hdfs::hdfs::protocol::hdfs_constants;
+
 pub const DFS_NAMENODE_RPC_PORT_DEFAULT: i32 = 8020;
+pub const DFS_NAMENODE_KERBEROS_PRINCIPAL_KEY: &str = "dfs.namenode.kerberos.principal";
+pub const DFS_DATANODE_KERBEROS_PRINCIPAL_KEY: &str = "dfs.datanode.kerberos.principal";
 pub const DFS_USER_HOME_DIR_PREFIX_KEY: &str = "dfs.user.home.dir.prefix";
 pub const DFS_USER_HOME_DIR_PREFIX_DEFAULT: &str = "/user";
+pub const DFS_BLOCK_SIZE_KEY: &str = "dfs.blocksize";
+pub const DFS_BLOCK_SIZE_DEFAULT: i64 = 128 * 1024 * 1024;
+pub const DFS_REPLICATION_KEY: &str = "dfs.replication";
+pub const DFS_REPLICATION_DEFAULT: i16 = 3;
+pub const DFS_BYTES_PER_CHECKSUM_KEY: &str = "dfs.bytes-per-checksum";
+pub const DFS_BYTES_PER_CHECKSUM_DEFAULT: i32 = 512;
+pub const DFS_CHECKSUM_TYPE_KEY: &str = "dfs.checksum.type";
+pub const DFS_CHECKSUM_TYPE_DEFAULT: &str = "CRC32C";
+pub const DFS_CHECKSUM_COMBINE_MODE_KEY: &str = "dfs.checksum.combine.mode";
+pub const DFS_CHECKSUM_COMBINE_MODE_DEFAULT: &str = "MD5MD5CRC";
+pub const DFS_CLIENT_WRITE_PACKET_SIZE_KEY: &str = "dfs.client-write-packet-size";
+pub const DFS_CLIENT_WRITE_PACKET_SIZE_DEFAULT: i32 = 64 * 1024;
+pub const DFS_CLIENT_WRITE_MAX_PACKETS_IN_FLIGHT_KEY: &str =
+    "dfs.client.write.max-packets-in-flight";
+pub const DFS_CLIENT_WRITE_MAX_PACKETS_IN_FLIGHT_DEFAULT: i32 = 80;
+pub const DFS_CLIENT_LEASE_RENEWAL_KEY: &str = "dfs.client.lease.renewal";
+pub const DFS_CLIENT_LEASE_RENEWAL_DEFAULT: i64 = hdfs_constants::LEASE_SOFTLIMIT_PERIOD / 2;
+pub const DFS_LEASE_HARDLIMIT_KEY: &str = "dfs.namenode.lease-hard-limit-sec";
+pub const DFS_LEASE_HARDLIMIT_DEFAULT: i64 = hdfs_constants::LEASE_HARDLIMIT_PERIOD / 1000;
+pub const DFS_CLIENT_SOCKET_TIMEOUT_KEY: &str = "dfs.client.socket-timeout";
+pub const DFS_CLIENT_BLOCK_WRITE_RETRIES_KEY: &str = "dfs.client.block.write.retries";
+pub const DFS_CLIENT_BLOCK_WRITE_RETRIES_DEFAULT: i32 = 3;
+pub const DFS_CLIENT_BLOCK_WRITE_LOCATEFOLLOWINGBLOCK_RETRIES_KEY: &str =
+    "dfs.client.block.write.locateFollowingBlock.retries";
+pub const DFS_CLIENT_BLOCK_WRITE_LOCATEFOLLOWINGBLOCK_RETRIES_DEFAULT: i32 = 5;
+pub const DFS_CLIENT_BLOCK_WRITE_LOCATEFOLLOWINGBLOCK_INITIAL_DELAY_MS_KEY: &str =
+    "dfs.client.block.write.locateFollowingBlock.initial.delay.ms";
+pub const DFS_CLIENT_BLOCK_WRITE_LOCATEFOLLOWINGBLOCK_INITIAL_DELAY_MS_DEFAULT: i32 = 400;
+pub const DFS_CLIENT_BLOCK_WRITE_LOCATEFOLLOWINGBLOCK_MAX_DELAY_MS_KEY: &str =
+    "dfs.client.block.write.locateFollowingBlock.max.delay.ms";
+pub const DFS_CLIENT_BLOCK_WRITE_LOCATEFOLLOWINGBLOCK_MAX_DELAY_MS_DEFAULT: i32 = 60000;
+pub const DFS_CLIENT_BLOCK_WRITE_REPLACE_DATANODE_ON_FAILURE_ENABLE_KEY: &str =
+    "dfs.client.block.write.replace-datanode-on-failure.enable";
+pub const DFS_CLIENT_BLOCK_WRITE_REPLACE_DATANODE_ON_FAILURE_ENABLE_DEFAULT: bool = true;
+pub const DFS_CLIENT_BLOCK_WRITE_REPLACE_DATANODE_ON_FAILURE_POLICY_KEY: &str =
+    "dfs.client.block.write.replace-datanode-on-failure.policy";
+pub const DFS_CLIENT_BLOCK_WRITE_REPLACE_DATANODE_ON_FAILURE_POLICY_DEFAULT: &str = "DEFAULT";
+pub const DFS_CLIENT_BLOCK_WRITE_REPLACE_DATANODE_ON_FAILURE_BEST_EFFORT_KEY: &str =
+    "dfs.client.block.write.replace-datanode-on-failure.best-effort";
+pub const DFS_CLIENT_BLOCK_WRITE_REPLACE_DATANODE_ON_FAILURE_BEST_EFFORT_DEFAULT: bool = false;
+pub const DFS_CLIENT_BLOCK_WRITE_REPLACE_DATANODE_ON_FAILURE_MIN_REPLICATION_KEY: &str =
+    "dfs.client.block.write.replace-datanode-on-failure.min-replication";
+pub const DFS_CLIENT_BLOCK_WRITE_REPLACE_DATANODE_ON_FAILURE_MIN_REPLICATION_DEFAULT: i16 = 0;
+pub const DFS_CLIENT_READ_PREFETCH_SIZE_KEY: &str = "dfs.client.read.prefetch.size";
+pub const DFS_CLIENT_MAX_BLOCK_ACQUIRE_FAILURES_KEY: &str = "dfs.client.max.block.acquire.failures";
+pub const DFS_CLIENT_MAX_BLOCK_ACQUIRE_FAILURES_DEFAULT: i32 = 3;
+pub const DFS_CLIENT_RETRY_WINDOW_BASE_KEY: &str = "dfs.client.retry.window.base";
+pub const DFS_CLIENT_RETRY_WINDOW_BASE_DEFAULT: i32 = 3000;
+pub const DFS_CLIENT_RETRY_TIMES_GET_LAST_BLOCK_LENGTH_KEY: &str =
+    "dfs.client.retry.times.get-last-block-length";
+pub const DFS_CLIENT_RETRY_TIMES_GET_LAST_BLOCK_LENGTH_DEFAULT: i32 = 3;
+pub const DFS_CLIENT_RETRY_INTERVAL_GET_LAST_BLOCK_LENGTH_KEY: &str =
+    "dfs.client.retry.interval-ms.get-last-block-length";
+pub const DFS_CLIENT_RETRY_INTERVAL_GET_LAST_BLOCK_LENGTH_DEFAULT: i32 = 4000;
+pub const DFS_CLIENT_RETRY_POLICY_ENABLED_KEY: &str = "dfs.client.retry.policy.enabled";
+pub const DFS_CLIENT_RETRY_POLICY_ENABLED_DEFAULT: bool = false;
+pub const DFS_CLIENT_RETRY_POLICY_SPEC_KEY: &str = "dfs.client.retry.policy.spec";
+pub const DFS_CLIENT_RETRY_POLICY_SPEC_DEFAULT: &str = "10000,6,60000,10";
@@ -0,0 +1,10 @@
+/// Flags for hsync of an HDFS output stream.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SyncFlag {
+    /// When doing sync to DataNodes, also update the metadata (block length)
+    /// in the NameNode.
+    UpdateLength,
+    /// Sync the data to DataNode, close the current block, and allocate a new
+    /// block
+    EndBlock,
+}
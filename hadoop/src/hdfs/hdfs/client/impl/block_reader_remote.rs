@@ -0,0 +1,318 @@
+use crate::{
+    common::{io::IOException, util::DataChecksum, TokenProto},
+    hdfs::{
+        hdfs::{
+            protocol::{
+                datatransfer::{
+                    DataTransferProtoUtil, DataTransferProtocol, PacketReceiver, Sender,
+                },
+                ExtendedBlock,
+            },
+            protocolpb::PBHelperClient,
+        },
+        BlockOpResponseProto, ClientReadStatusProto, Status,
+    },
+};
+use anyhow::Error;
+use prost::Message;
+use std::{
+    io::{self, Read, Write},
+    net::TcpStream,
+};
+
+/// This is a wrapper around connection to datanode
+/// and understands checksum, offset etc.
+///
+/// Terminology:
+/// - block: The hdfs block, typically large (~64MB).
+/// - chunk: A block is divided into chunks, each comes with a checksum.
+///   We want transfers to be chunk-aligned, to be able to verify checksums.
+/// - packet: A grouping of chunks used for transport. It contains a
+///   header, followed by checksum data, followed by real data.
+///
+/// Please see DataNode for the RPC specification.
+pub(crate) struct BlockReaderRemote {
+    peer: TcpStream,
+    checksum: DataChecksum,
+    packet_receiver: PacketReceiver,
+    // position of the next byte to return in the current packet
+    cur_data_pos: usize,
+    // offset in block of the last chunk received
+    last_seq_no: i64,
+    // The offset within the block to start reading at.
+    start_offset: i64,
+    // the file and the name of the block, to report errors
+    filename: String,
+    verify_checksum: bool,
+    bytes_needed_to_finish: i64,
+    // Whether the read status has been sent back to the datanode
+    sent_status_code: bool,
+}
+
+impl BlockReaderRemote {
+    /// Create a new BlockReader specifically to satisfy a read.
+    /// This method also sends the OP_READ_BLOCK request.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_block_reader(
+        file: &str,
+        block: &ExtendedBlock,
+        block_token: &TokenProto,
+        start_offset: i64,
+        len: i64,
+        verify_checksum: bool,
+        client_name: &str,
+        mut peer: TcpStream,
+    ) -> anyhow::Result<Self> {
+        // in and out will be closed when sock is closed (by the caller)
+        Sender::new(&mut peer).read_block(
+            block,
+            block_token,
+            client_name,
+            start_offset,
+            len,
+            verify_checksum,
+        )?;
+
+        let status = BlockOpResponseProto::decode(&PBHelperClient::vint_prefixed(&mut peer)?[..])?;
+        Self::check_success(&status, &peer, block, file)?;
+        let checksum_info = status.read_op_checksum_info.unwrap_or_default();
+        let checksum =
+            DataTransferProtoUtil::from_proto(&checksum_info.checksum).ok_or_else(|| {
+                Error::msg(format!(
+                    "BlockReader: invalid checksum {:?} for file {}",
+                    checksum_info.checksum, file
+                ))
+            })?;
+        let bytes_per_checksum = checksum.get_bytes_per_checksum() as i64;
+
+        // Read the first chunk offset.
+        let first_chunk_offset = checksum_info.chunk_offset as i64;
+        if first_chunk_offset < 0
+            || first_chunk_offset > start_offset
+            || first_chunk_offset <= start_offset - bytes_per_checksum
+        {
+            return Err(Error::msg(format!(
+                "BlockReader: error in first chunk offset ({}) startOffset is {} for file {}",
+                first_chunk_offset, start_offset, file
+            )));
+        }
+
+        Ok(Self {
+            peer,
+            checksum,
+            packet_receiver: PacketReceiver::default(),
+            cur_data_pos: 0,
+            last_seq_no: -1,
+            start_offset,
+            filename: format!("{}:{}", file, block.get_block_name()),
+            verify_checksum,
+            // The total number of bytes we need to transfer from the DN.
+            // This is the amount that the user has requested plus some padding
+            // at the beginning so that the read can begin on a chunk boundary.
+            bytes_needed_to_finish: len + (start_offset - first_chunk_offset),
+            sent_status_code: false,
+        })
+    }
+
+    fn check_success(
+        status: &BlockOpResponseProto,
+        peer: &TcpStream,
+        block: &ExtendedBlock,
+        file: &str,
+    ) -> anyhow::Result<()> {
+        let log_info = format!(
+            "for OP_READ_BLOCK, self={}, remote={}, for file {}, for pool {} block {}_{}",
+            peer.local_addr()?,
+            peer.peer_addr()?,
+            file,
+            block.get_block_pool_id(),
+            block.get_block_id(),
+            block.get_generation_stamp()
+        );
+        DataTransferProtoUtil::check_block_op_status(status, &log_info)
+    }
+
+    fn cur_data_remaining(&self) -> usize {
+        self.packet_receiver.get_data_slice().len() - self.cur_data_pos
+    }
+
+    fn read_next_packet(&mut self) -> anyhow::Result<()> {
+        //Read packet headers.
+        self.packet_receiver.receive_next_packet(&mut self.peer)?;
+        self.cur_data_pos = 0;
+        let cur_header = self
+            .packet_receiver
+            .get_header()
+            .ok_or_else(|| Error::msg("BlockReader: missing packet header"))?;
+        log::trace!("DFSClient readNextPacket got header {}", cur_header);
+
+        // Sanity check the lengths
+        cur_header.sanity_check(self.last_seq_no)?;
+
+        let data_len = cur_header.get_data_len() as i64;
+        let offset_in_block = cur_header.get_offset_in_block();
+        if data_len > 0 {
+            self.last_seq_no = cur_header.get_seqno();
+            if self.verify_checksum {
+                // N.B.: the checksum error offset reported here is actually
+                // relative to the start of the block, not the start of the
+                // file.
+                self.checksum.verify_chunked_sums(
+                    self.packet_receiver.get_data_slice(),
+                    self.packet_receiver.get_checksum_slice(),
+                    &self.filename,
+                    offset_in_block,
+                )?;
+            }
+            self.bytes_needed_to_finish -= data_len;
+        }
+
+        // First packet will include some data prior to the first byte
+        // the user requested. Skip it.
+        if offset_in_block < self.start_offset {
+            self.cur_data_pos = (self.start_offset - offset_in_block) as usize;
+        }
+
+        // If we've now satisfied the whole client read, read one last packet
+        // header, which should be empty
+        if self.bytes_needed_to_finish <= 0 {
+            self.read_trailing_empty_packet()?;
+            if self.verify_checksum {
+                self.send_read_result(Status::ChecksumOk);
+            } else {
+                self.send_read_result(Status::Success);
+            }
+        }
+        Ok(())
+    }
+
+    fn read_trailing_empty_packet(&mut self) -> anyhow::Result<()> {
+        log::trace!("Reading empty packet at end of read");
+        let mut trailer = PacketReceiver::default();
+        trailer.receive_next_packet(&mut self.peer)?;
+        match trailer.get_header() {
+            Some(header) if header.is_last_packet_in_block() && header.get_data_len() == 0 => {
+                Ok(())
+            }
+            header => Err(Error::msg(format!(
+                "Expected empty end-of-read packet! Header: {}",
+                header.map(|h| h.to_string()).unwrap_or_default()
+            ))),
+        }
+    }
+
+    /// When the reader reaches end of the read, it sends a status response
+    /// (e.g. CHECKSUM_OK) to the DN. Failure to do so could lead to the DN
+    /// closing our connection (which we will re-open), but won't affect
+    /// data correctness.
+    fn send_read_result(&mut self, status_code: Status) {
+        assert!(
+            !self.sent_status_code,
+            "already sent status code to {:?}",
+            self.peer.peer_addr()
+        );
+        let proto = ClientReadStatusProto {
+            status: status_code as i32,
+        };
+        let result = self
+            .peer
+            .write_all(&proto.encode_length_delimited_to_vec())
+            .and_then(|_| self.peer.flush());
+        match result {
+            Ok(_) => self.sent_status_code = true,
+            Err(e) => {
+                // It's ok not to be able to send this. But something is probably wrong.
+                log::info!(
+                    "Could not send read status ({}) to datanode {:?}: {}",
+                    status_code.as_str_name(),
+                    self.peer.peer_addr(),
+                    e
+                );
+            }
+        }
+    }
+}
+
+impl Read for BlockReaderRemote {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.packet_receiver.get_header().is_none()
+            || (self.cur_data_remaining() == 0 && self.bytes_needed_to_finish > 0)
+        {
+            self.read_next_packet().map_err(|e| {
+                let e = match e.downcast::<IOException>() {
+                    // keep checksum errors typed so that the caller can read
+                    // from another replica
+                    Ok(e @ IOException::Checksum(_)) => return io::Error::other(e),
+                    Ok(e) => Error::new(e),
+                    Err(e) => e,
+                };
+                io::Error::other(format!(
+                    "Failed to read block data for file {}: {}",
+                    self.filename, e
+                ))
+            })?;
+        }
+
+        log::trace!(
+            "read len={} remaining={}",
+            buf.len(),
+            self.cur_data_remaining()
+        );
+
+        let n_read = self.cur_data_remaining().min(buf.len());
+        let start = self.cur_data_pos;
+        buf[..n_read]
+            .copy_from_slice(&self.packet_receiver.get_data_slice()[start..start + n_read]);
+        self.cur_data_pos += n_read;
+        Ok(n_read)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hdfs::hdfs::protocol::datatransfer::FakeDataNode;
+
+    #[test]
+    fn test_checksum_error_names_block() -> anyhow::Result<()> {
+        let datanode = FakeDataNode::start()?;
+        let data = (0..2000).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+        datanode.put_block(7, &data);
+        datanode.corrupt_replica(7, 1100);
+        let block = ExtendedBlock::new("pool", 7, data.len() as i64, 1001);
+        let id = datanode.get_datanode_info().id;
+        let read = |offset: i64, verify_checksum| -> anyhow::Result<Vec<u8>> {
+            let peer = TcpStream::connect((id.ip_addr.as_str(), id.xfer_port as u16))?;
+            let mut reader = BlockReaderRemote::new_block_reader(
+                "/f",
+                &block,
+                &TokenProto::default(),
+                offset,
+                data.len() as i64 - offset,
+                verify_checksum,
+                "client",
+                peer,
+            )?;
+            let mut buf = vec![];
+            reader.read_to_end(&mut buf)?;
+            Ok(buf)
+        };
+
+        // the error names the block and the offset of the chunk in it
+        let err = read(600, true).unwrap_err();
+        let e = err.downcast_ref::<io::Error>().unwrap();
+        assert!(matches!(
+            e.get_ref().and_then(|e| e.downcast_ref::<IOException>()),
+            Some(IOException::Checksum(_))
+        ));
+        assert!(e
+            .to_string()
+            .contains("for file /f:blk_7 at position 1024:"));
+
+        // the corruption goes unnoticed without verifying the checksums
+        let read = read(600, false)?;
+        assert_eq!(read.len(), 1400);
+        assert_ne!(read[..], data[600..]);
+        Ok(())
+    }
+}
@@ -1,17 +1,252 @@
-use crate::common::{conf::Configuration, fs::permission::FsPermission};
+use crate::{
+    common::{
+        conf::{Configuration, TimeUnit},
+        fs::{options::ChecksumCombineMode, permission::FsPermission},
+        util::{DataChecksum, DataChecksumType},
+    },
+    hdfs::hdfs::{
+        client::hdfs_client_config_keys,
+        protocol::{datatransfer::ReplaceDatanodeOnFailure, hdfs_constants},
+    },
+};
+use anyhow::Error;
 
 /// DFSClient configuration.
 pub struct DfsClientConf {
+    default_block_size: i64,
+    default_replication: i16,
     umask: FsPermission,
+    bytes_per_checksum: i32,
+    checksum_type: DataChecksumType,
+    checksum_combine_mode: ChecksumCombineMode,
+    write_packet_size: i32,
+    write_max_packets: i32,
+    socket_timeout: i32,
+    n_block_write_retry: i32,
+    n_block_write_locate_following_retry: i32,
+    block_write_locate_following_initial_delay_ms: i32,
+    block_write_locate_following_max_delay_ms: i32,
+    replace_datanode_on_failure: ReplaceDatanodeOnFailure,
+    replace_datanode_on_failure_replication: i16,
+    prefetch_size: i64,
+    max_block_acquire_failures: i32,
+    time_window: i32,
+    retry_times_for_get_last_block_length: i32,
+    retry_interval_for_get_last_block_length: i32,
+    lease_renewal_interval: i64,
+    lease_hard_limit_period: i64,
 }
 
 impl DfsClientConf {
     pub fn new(conf: &Configuration) -> anyhow::Result<Self> {
+        let default_block_size = conf.get_long_bytes(
+            hdfs_client_config_keys::DFS_BLOCK_SIZE_KEY,
+            hdfs_client_config_keys::DFS_BLOCK_SIZE_DEFAULT,
+        )?;
+        let default_replication = conf.get_int(
+            hdfs_client_config_keys::DFS_REPLICATION_KEY,
+            hdfs_client_config_keys::DFS_REPLICATION_DEFAULT as i32,
+        )? as i16;
         let umask = FsPermission::get_umask(Some(conf))?;
-        Ok(Self { umask })
+        let bytes_per_checksum = conf.get_int(
+            hdfs_client_config_keys::DFS_BYTES_PER_CHECKSUM_KEY,
+            hdfs_client_config_keys::DFS_BYTES_PER_CHECKSUM_DEFAULT,
+        )?;
+        let checksum_type = conf.get_enum(
+            hdfs_client_config_keys::DFS_CHECKSUM_TYPE_KEY,
+            hdfs_client_config_keys::DFS_CHECKSUM_TYPE_DEFAULT.parse()?,
+        )?;
+        let checksum_combine_mode = conf.get_enum(
+            hdfs_client_config_keys::DFS_CHECKSUM_COMBINE_MODE_KEY,
+            hdfs_client_config_keys::DFS_CHECKSUM_COMBINE_MODE_DEFAULT.parse()?,
+        )?;
+        let write_packet_size = conf.get_int(
+            hdfs_client_config_keys::DFS_CLIENT_WRITE_PACKET_SIZE_KEY,
+            hdfs_client_config_keys::DFS_CLIENT_WRITE_PACKET_SIZE_DEFAULT,
+        )?;
+        let write_max_packets = conf.get_int(
+            hdfs_client_config_keys::DFS_CLIENT_WRITE_MAX_PACKETS_IN_FLIGHT_KEY,
+            hdfs_client_config_keys::DFS_CLIENT_WRITE_MAX_PACKETS_IN_FLIGHT_DEFAULT,
+        )?;
+        let socket_timeout = conf.get_int(
+            hdfs_client_config_keys::DFS_CLIENT_SOCKET_TIMEOUT_KEY,
+            hdfs_constants::READ_TIMEOUT,
+        )?;
+        let n_block_write_retry = conf.get_int(
+            hdfs_client_config_keys::DFS_CLIENT_BLOCK_WRITE_RETRIES_KEY,
+            hdfs_client_config_keys::DFS_CLIENT_BLOCK_WRITE_RETRIES_DEFAULT,
+        )?;
+        let n_block_write_locate_following_retry = conf.get_int(
+            hdfs_client_config_keys::DFS_CLIENT_BLOCK_WRITE_LOCATEFOLLOWINGBLOCK_RETRIES_KEY,
+            hdfs_client_config_keys::DFS_CLIENT_BLOCK_WRITE_LOCATEFOLLOWINGBLOCK_RETRIES_DEFAULT,
+        )?;
+        let block_write_locate_following_initial_delay_ms = conf.get_int(
+            hdfs_client_config_keys::DFS_CLIENT_BLOCK_WRITE_LOCATEFOLLOWINGBLOCK_INITIAL_DELAY_MS_KEY,
+            hdfs_client_config_keys::DFS_CLIENT_BLOCK_WRITE_LOCATEFOLLOWINGBLOCK_INITIAL_DELAY_MS_DEFAULT,
+        )?;
+        let block_write_locate_following_max_delay_ms = conf.get_int(
+            hdfs_client_config_keys::DFS_CLIENT_BLOCK_WRITE_LOCATEFOLLOWINGBLOCK_MAX_DELAY_MS_KEY,
+            hdfs_client_config_keys::DFS_CLIENT_BLOCK_WRITE_LOCATEFOLLOWINGBLOCK_MAX_DELAY_MS_DEFAULT,
+        )?;
+        let replace_datanode_on_failure = ReplaceDatanodeOnFailure::get(conf)?;
+        let replace_datanode_on_failure_replication = conf.get_int(
+            hdfs_client_config_keys::DFS_CLIENT_BLOCK_WRITE_REPLACE_DATANODE_ON_FAILURE_MIN_REPLICATION_KEY,
+            hdfs_client_config_keys::DFS_CLIENT_BLOCK_WRITE_REPLACE_DATANODE_ON_FAILURE_MIN_REPLICATION_DEFAULT as i32,
+        )? as i16;
+        let prefetch_size = conf.get_long(
+            hdfs_client_config_keys::DFS_CLIENT_READ_PREFETCH_SIZE_KEY,
+            10 * default_block_size,
+        )?;
+        let max_block_acquire_failures = conf.get_int(
+            hdfs_client_config_keys::DFS_CLIENT_MAX_BLOCK_ACQUIRE_FAILURES_KEY,
+            hdfs_client_config_keys::DFS_CLIENT_MAX_BLOCK_ACQUIRE_FAILURES_DEFAULT,
+        )?;
+        let time_window = conf.get_int(
+            hdfs_client_config_keys::DFS_CLIENT_RETRY_WINDOW_BASE_KEY,
+            hdfs_client_config_keys::DFS_CLIENT_RETRY_WINDOW_BASE_DEFAULT,
+        )?;
+        let retry_times_for_get_last_block_length = conf.get_int(
+            hdfs_client_config_keys::DFS_CLIENT_RETRY_TIMES_GET_LAST_BLOCK_LENGTH_KEY,
+            hdfs_client_config_keys::DFS_CLIENT_RETRY_TIMES_GET_LAST_BLOCK_LENGTH_DEFAULT,
+        )?;
+        let retry_interval_for_get_last_block_length = conf.get_int(
+            hdfs_client_config_keys::DFS_CLIENT_RETRY_INTERVAL_GET_LAST_BLOCK_LENGTH_KEY,
+            hdfs_client_config_keys::DFS_CLIENT_RETRY_INTERVAL_GET_LAST_BLOCK_LENGTH_DEFAULT,
+        )?;
+        let lease_renewal_interval = conf.get_time_duration(
+            hdfs_client_config_keys::DFS_CLIENT_LEASE_RENEWAL_KEY,
+            hdfs_client_config_keys::DFS_CLIENT_LEASE_RENEWAL_DEFAULT,
+            TimeUnit::Milliseconds,
+        )?;
+        let lease_hard_limit_period = conf.get_long(
+            hdfs_client_config_keys::DFS_LEASE_HARDLIMIT_KEY,
+            hdfs_client_config_keys::DFS_LEASE_HARDLIMIT_DEFAULT,
+        )? * 1000;
+        Ok(Self {
+            default_block_size,
+            default_replication,
+            umask,
+            bytes_per_checksum,
+            checksum_type,
+            checksum_combine_mode,
+            write_packet_size,
+            write_max_packets,
+            socket_timeout,
+            n_block_write_retry,
+            n_block_write_locate_following_retry,
+            block_write_locate_following_initial_delay_ms,
+            block_write_locate_following_max_delay_ms,
+            replace_datanode_on_failure,
+            replace_datanode_on_failure_replication,
+            prefetch_size,
+            max_block_acquire_failures,
+            time_window,
+            retry_times_for_get_last_block_length,
+            retry_interval_for_get_last_block_length,
+            lease_renewal_interval,
+            lease_hard_limit_period,
+        })
+    }
+
+    pub fn get_default_block_size(&self) -> i64 {
+        self.default_block_size
+    }
+
+    pub fn get_default_replication(&self) -> i16 {
+        self.default_replication
     }
 
     pub fn get_umask(&self) -> &FsPermission {
         &self.umask
     }
+
+    /// Create a DataChecksum with the configured checksum type and
+    /// bytes per checksum.
+    pub fn create_checksum(&self) -> anyhow::Result<DataChecksum> {
+        DataChecksum::new_data_checksum(self.checksum_type, self.bytes_per_checksum).ok_or_else(
+            || {
+                Error::msg(format!(
+                    "Invalid checksum type: type={}, bytesPerChecksum={}",
+                    self.checksum_type, self.bytes_per_checksum
+                ))
+            },
+        )
+    }
+
+    pub fn get_checksum_combine_mode(&self) -> ChecksumCombineMode {
+        self.checksum_combine_mode
+    }
+
+    pub fn get_write_packet_size(&self) -> i32 {
+        self.write_packet_size
+    }
+
+    pub fn get_write_max_packets(&self) -> i32 {
+        self.write_max_packets
+    }
+
+    pub fn get_socket_timeout(&self) -> i32 {
+        self.socket_timeout
+    }
+
+    pub fn get_num_block_write_retry(&self) -> i32 {
+        self.n_block_write_retry
+    }
+
+    pub fn get_num_block_write_locate_following_retry(&self) -> i32 {
+        self.n_block_write_locate_following_retry
+    }
+
+    pub fn get_block_write_locate_following_initial_delay_ms(&self) -> i32 {
+        self.block_write_locate_following_initial_delay_ms
+    }
+
+    pub fn get_block_write_locate_following_max_delay_ms(&self) -> i32 {
+        self.block_write_locate_following_max_delay_ms
+    }
+
+    /// The policy of replacing a failed datanode of a write pipeline.
+    pub fn get_replace_datanode_on_failure(&self) -> &ReplaceDatanodeOnFailure {
+        &self.replace_datanode_on_failure
+    }
+
+    /// The minimum number of datanodes a pipeline may keep writing to when no
+    /// replacement for a failed datanode can be found; 0 disables the check.
+    pub fn get_replace_datanode_on_failure_replication(&self) -> i16 {
+        self.replace_datanode_on_failure_replication
+    }
+
+    pub fn get_prefetch_size(&self) -> i64 {
+        self.prefetch_size
+    }
+
+    pub fn get_max_block_acquire_failures(&self) -> i32 {
+        self.max_block_acquire_failures
+    }
+
+    pub fn get_time_window(&self) -> i32 {
+        self.time_window
+    }
+
+    /// The number of times to retry getting the locations of the last block
+    /// of a file under construction.
+    pub fn get_retry_times_for_get_last_block_length(&self) -> i32 {
+        self.retry_times_for_get_last_block_length
+    }
+
+    /// The interval in milliseconds between two retries of getting the
+    /// locations of the last block of a file under construction.
+    pub fn get_retry_interval_for_get_last_block_length(&self) -> i32 {
+        self.retry_interval_for_get_last_block_length
+    }
+
+    /// The interval in milliseconds between two lease renewals.
+    pub fn get_lease_renewal_interval(&self) -> i64 {
+        self.lease_renewal_interval
+    }
+
+    /// The lease hard limit in milliseconds.
+    pub fn get_lease_hard_limit_period(&self) -> i64 {
+        self.lease_hard_limit_period
+    }
 }
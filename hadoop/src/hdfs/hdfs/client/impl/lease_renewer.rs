@@ -0,0 +1,468 @@
+use super::DfsClientConf;
+use crate::{
+    common::ipc::remote_exception::unwrap_remote_exception, hdfs::hdfs::protocol::ClientProtocol,
+};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Condvar, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// The time the daemon keeps running after no more files are being written.
+const LEASE_RENEWER_GRACE_DEFAULT: Duration = Duration::from_secs(60);
+
+/// The longest time the daemon sleeps between two checks.
+const LEASE_RENEWER_SLEEP_DEFAULT: Duration = Duration::from_secs(1);
+
+/// The shortest time the daemon sleeps between two checks, so that a zero
+/// renewal interval does not make it spin.
+const LEASE_RENEWER_SLEEP_MIN: Duration = Duration::from_millis(10);
+
+/// Creates the connection to the namenode used by the daemon.
+pub(crate) type NamenodeFactory =
+    Arc<dyn Fn() -> anyhow::Result<Box<dyn ClientProtocol>> + Send + Sync>;
+
+/// Aborts the stream of a file being written, with the reason it is given.
+pub(crate) type StreamAborter = Box<dyn Fn(&str) + Send + Sync>;
+
+struct RenewerState {
+    // the files being written, by inode id, with their source path and the
+    // function aborting their stream
+    files_being_written: HashMap<u64, (String, StreamAborter)>,
+    // the files whose leases expired, with the reason they were aborted
+    aborted_files: HashMap<u64, String>,
+    // the time of the last successful renewal while files are being written
+    last_lease_renewal: Option<Instant>,
+    // the time when no more files are being written
+    empty_time: Option<Instant>,
+    // the time the daemon keeps running after no more files are being written
+    grace_period: Duration,
+    // the id of the current daemon, a daemon exits once it is not current
+    current_id: u64,
+    daemon_running: bool,
+    closed: bool,
+}
+
+impl Default for RenewerState {
+    fn default() -> Self {
+        Self {
+            files_being_written: HashMap::new(),
+            aborted_files: HashMap::new(),
+            last_lease_renewal: None,
+            empty_time: None,
+            grace_period: LEASE_RENEWER_GRACE_DEFAULT,
+            current_id: 0,
+            daemon_running: false,
+            closed: false,
+        }
+    }
+}
+
+impl RenewerState {
+    fn is_renewer_expired(&self) -> bool {
+        self.empty_time
+            .is_some_and(|t| t.elapsed() > self.grace_period)
+    }
+}
+
+/// Used by [`DFSClient`](crate::hdfs::hdfs::DFSClient) for renewing
+/// file-being-written leases on the namenode.
+///
+/// When a file is opened for write (create or append), namenode stores a file
+/// lease for recording the identity of the writer. The writer (i.e. the
+/// DFSClient) is required to renew the lease periodically. When the lease is
+/// not renewed before it expires, the namenode considers the writer as failed
+/// and then it may either let another writer to obtain the lease or close the
+/// file.
+///
+/// This class also provides the following functionality:
+/// - It maintains the files being written by the client. A daemon thread
+///   renews the lease on the `dfs.client.lease.renewal` cadence while there
+///   are files being written.
+/// - The streams of the files being written are aborted when the lease cannot
+///   be renewed before the hard limit passes.
+/// - The daemon thread stops after a grace period once no more files are
+///   being written, and is restarted when a file is opened for write again.
+pub(crate) struct LeaseRenewer {
+    client_name: String,
+    renewal: Duration,
+    hard_limit: Duration,
+    namenode_factory: NamenodeFactory,
+    state: Arc<(Mutex<RenewerState>, Condvar)>,
+}
+
+impl LeaseRenewer {
+    pub fn new(client_name: &str, conf: &DfsClientConf, namenode_factory: NamenodeFactory) -> Self {
+        Self {
+            client_name: client_name.to_owned(),
+            renewal: Duration::from_millis(conf.get_lease_renewal_interval().max(0) as u64),
+            hard_limit: Duration::from_millis(conf.get_lease_hard_limit_period().max(0) as u64),
+            namenode_factory,
+            state: Default::default(),
+        }
+    }
+
+    /// Set a file being written, starting the daemon if it is not running.
+    /// The stream of the file is aborted with `aborter` if its lease expires.
+    pub fn put(&self, inode_id: u64, src: &str, aborter: StreamAborter) {
+        let mut state = self.state.0.lock().unwrap();
+        state
+            .files_being_written
+            .insert(inode_id, (src.to_owned(), aborter));
+        state.aborted_files.remove(&inode_id);
+        // update the last lease renewal time only when there was no writes.
+        // once there is one write stream open, the lease renewer thread keeps
+        // it updated well with in anyone's expiration time.
+        if state.last_lease_renewal.is_none() {
+            state.last_lease_renewal = Some(Instant::now());
+        }
+        if !state.daemon_running || state.is_renewer_expired() {
+            // start a new daemon with a new id.
+            state.current_id += 1;
+            state.daemon_running = true;
+            let id = state.current_id;
+            let daemon = Daemon {
+                id,
+                client_name: self.client_name.clone(),
+                renewal: self.renewal,
+                hard_limit: self.hard_limit,
+                state: Arc::clone(&self.state),
+            };
+            let namenode_factory = Arc::clone(&self.namenode_factory);
+            let spawned = thread::Builder::new()
+                .name(format!("LeaseRenewer:{}", self.client_name))
+                .spawn(move || daemon.run(namenode_factory));
+            if let Err(e) = spawned {
+                log::warn!("Failed to start the lease renewer daemon: {}", e);
+                state.daemon_running = false;
+            }
+        }
+        state.empty_time = None;
+    }
+
+    /// Close a file, stopping the daemon after the grace period if no more
+    /// files are being written.
+    pub fn close_file(&self, inode_id: u64) {
+        let mut state = self.state.0.lock().unwrap();
+        state.files_being_written.remove(&inode_id);
+        state.aborted_files.remove(&inode_id);
+        if state.files_being_written.is_empty() {
+            state.last_lease_renewal = None;
+            // discover the first time that the file-being-written map is empty.
+            if state.empty_time.is_none() {
+                state.empty_time = Some(Instant::now());
+            }
+        }
+    }
+
+    /// Get the reason the file was aborted, if its lease expired.
+    pub fn get_abort_reason(&self, inode_id: u64) -> Option<String> {
+        self.state
+            .0
+            .lock()
+            .unwrap()
+            .aborted_files
+            .get(&inode_id)
+            .cloned()
+    }
+
+    /// Set the time the daemon keeps running after no more files are being
+    /// written.
+    #[cfg(test)]
+    pub fn set_grace_period(&self, grace_period: Duration) {
+        self.state.0.lock().unwrap().grace_period = grace_period;
+    }
+
+    /// Check if the daemon is running.
+    #[cfg(test)]
+    pub fn is_running(&self) -> bool {
+        self.state.0.lock().unwrap().daemon_running
+    }
+}
+
+impl Drop for LeaseRenewer {
+    fn drop(&mut self) {
+        let (lock, cvar) = &*self.state;
+        lock.lock().unwrap().closed = true;
+        cvar.notify_all();
+    }
+}
+
+/// The thread renewing the lease.
+struct Daemon {
+    id: u64,
+    client_name: String,
+    renewal: Duration,
+    hard_limit: Duration,
+    state: Arc<(Mutex<RenewerState>, Condvar)>,
+}
+
+impl Daemon {
+    fn run(self, namenode_factory: NamenodeFactory) {
+        let namenode = match namenode_factory() {
+            Ok(namenode) => namenode,
+            Err(e) => {
+                log::warn!("Failed to start the lease renewer daemon: {}", e);
+                let mut state = self.state.0.lock().unwrap();
+                if state.current_id == self.id {
+                    state.daemon_running = false;
+                }
+                return;
+            }
+        };
+        let sleep_period = self
+            .renewal
+            .clamp(LEASE_RENEWER_SLEEP_MIN, LEASE_RENEWER_SLEEP_DEFAULT);
+        let mut last_renewed = Instant::now();
+        loop {
+            let elapsed = last_renewed.elapsed();
+            if elapsed >= self.renewal {
+                match self.renew(&*namenode) {
+                    Ok(()) => {
+                        log::debug!(
+                            "Lease renewer daemon for [{}] with renew id {} executed",
+                            self.client_name,
+                            self.id
+                        );
+                        last_renewed = Instant::now();
+                    }
+                    Err(e) => log::warn!(
+                        "Failed to renew lease for [{}] for {} seconds.  Will retry shortly ...: {}",
+                        self.client_name,
+                        elapsed.as_secs(),
+                        e
+                    ),
+                }
+            }
+
+            let (lock, cvar) = &*self.state;
+            let mut state = lock.lock().unwrap();
+            if self.id != state.current_id || state.is_renewer_expired() || state.closed {
+                if self.id != state.current_id {
+                    log::debug!(
+                        "Lease renewer daemon for [{}] with renew id {} is not current",
+                        self.client_name,
+                        self.id
+                    );
+                } else {
+                    log::debug!(
+                        "Lease renewer daemon for [{}] with renew id {} expired",
+                        self.client_name,
+                        self.id
+                    );
+                    state.daemon_running = false;
+                }
+                // no longer the current daemon or expired
+                return;
+            }
+            // if there is no more files being written, stop the daemon after
+            // the grace period.
+            if state.files_being_written.is_empty() && state.empty_time.is_none() {
+                state.empty_time = Some(Instant::now());
+            }
+            let _ = cvar.wait_timeout(state, sleep_period).unwrap();
+        }
+    }
+
+    /// Renew the lease if files are being written, aborting them if the
+    /// lease has already expired.
+    fn renew(&self, namenode: &dyn ClientProtocol) -> anyhow::Result<()> {
+        let (lock, _) = &*self.state;
+        if lock.lock().unwrap().files_being_written.is_empty() {
+            return Ok(());
+        }
+        let result = namenode
+            .renew_lease(&self.client_name)
+            .map_err(unwrap_remote_exception);
+        let mut state = lock.lock().unwrap();
+        match result {
+            Ok(()) => {
+                if !state.files_being_written.is_empty() {
+                    state.last_lease_renewal = Some(Instant::now());
+                }
+                Ok(())
+            }
+            Err(e) => {
+                // Abort if the lease has already expired.
+                let elapsed = state
+                    .last_lease_renewal
+                    .map(|t| t.elapsed())
+                    .unwrap_or_default();
+                if elapsed <= self.hard_limit {
+                    // Let the lease renewer handle it and retry.
+                    return Err(e);
+                }
+                log::warn!(
+                    "Failed to renew lease for {} for {} seconds (>= hard-limit ={} seconds.) \
+                    Closing all files being written ...: {}",
+                    self.client_name,
+                    elapsed.as_secs(),
+                    self.hard_limit.as_secs(),
+                    e
+                );
+                let reason = format!(
+                    "Lease timeout of {} seconds expired.",
+                    self.hard_limit.as_secs()
+                );
+                let files = std::mem::take(&mut state.files_being_written);
+                for (inode_id, (src, aborter)) in files {
+                    log::warn!("Aborting file {}: {}", src, reason);
+                    aborter(&reason);
+                    state.aborted_files.insert(inode_id, reason.clone());
+                }
+                state.last_lease_renewal = None;
+                state.empty_time = Some(Instant::now());
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        common::conf::Configuration,
+        hdfs::hdfs::{
+            client::hdfs_client_config_keys::{
+                DFS_CLIENT_LEASE_RENEWAL_KEY, DFS_LEASE_HARDLIMIT_KEY,
+            },
+            protocol::MockClientProtocol,
+        },
+    };
+    use anyhow::Error;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    type Renewals = Arc<Mutex<Vec<Instant>>>;
+
+    /// Create a renewer renewing every `renewal` milliseconds on a namenode
+    /// recording the renewals and failing them once `expired` is true.
+    fn create_renewer(
+        renewal: &str,
+        hard_limit_secs: &str,
+        expired: Arc<AtomicBool>,
+    ) -> anyhow::Result<(LeaseRenewer, Renewals)> {
+        let mut conf = Configuration::new();
+        conf.set(DFS_CLIENT_LEASE_RENEWAL_KEY, renewal);
+        conf.set(DFS_LEASE_HARDLIMIT_KEY, hard_limit_secs);
+        let renewals = Renewals::default();
+        let namenode_factory: NamenodeFactory = {
+            let renewals = Arc::clone(&renewals);
+            Arc::new(move || {
+                let renewals = Arc::clone(&renewals);
+                let expired = Arc::clone(&expired);
+                Ok(Box::new(MockClientProtocol {
+                    renew_lease: Some(Box::new(move |client_name| {
+                        assert_eq!(client_name, "client");
+                        renewals.lock().unwrap().push(Instant::now());
+                        if expired.load(Ordering::SeqCst) {
+                            return Err(Error::msg("Connection refused"));
+                        }
+                        Ok(())
+                    })),
+                    ..Default::default()
+                }))
+            })
+        };
+        let renewer = LeaseRenewer::new("client", &DfsClientConf::new(&conf)?, namenode_factory);
+        Ok((renewer, renewals))
+    }
+
+    fn no_abort() -> StreamAborter {
+        Box::new(|reason| panic!("Unexpected abort: {}", reason))
+    }
+
+    /// Wait until `cond` holds, failing after a few seconds.
+    fn wait_until(cond: impl Fn() -> bool) {
+        let start = Instant::now();
+        while !cond() {
+            assert!(start.elapsed() < Duration::from_secs(10), "Timed out");
+            thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    #[test]
+    fn test_renewal_cadence() -> anyhow::Result<()> {
+        let (renewer, renewals) = create_renewer("50", "1200", Default::default())?;
+        renewer.put(1, "/f", no_abort());
+        wait_until(|| renewals.lock().unwrap().len() >= 3);
+        let times = renewals.lock().unwrap().clone();
+        for pair in times.windows(2) {
+            assert!(pair[1] - pair[0] >= Duration::from_millis(50));
+        }
+
+        // no renewal once no more files are being written
+        renewer.close_file(1);
+        let count = renewals.lock().unwrap().len();
+        thread::sleep(Duration::from_millis(200));
+        assert!(renewals.lock().unwrap().len() <= count + 1);
+        assert!(renewer.is_running());
+        Ok(())
+    }
+
+    #[test]
+    fn test_zero_renewal_does_not_spin() -> anyhow::Result<()> {
+        let (renewer, renewals) = create_renewer("0", "1200", Default::default())?;
+        let start = Instant::now();
+        renewer.put(1, "/f", no_abort());
+        thread::sleep(Duration::from_millis(100));
+        let count = renewals.lock().unwrap().len();
+        let max = start.elapsed().as_millis() / LEASE_RENEWER_SLEEP_MIN.as_millis() + 1;
+        assert!(count as u128 <= max, "{} renewals", count);
+        Ok(())
+    }
+
+    #[test]
+    fn test_abort_after_hard_limit() -> anyhow::Result<()> {
+        let expired = Arc::new(AtomicBool::new(true));
+        let (renewer, renewals) = create_renewer("10", "0", Arc::clone(&expired))?;
+        let aborted = Arc::new(Mutex::new(vec![]));
+        for inode_id in [1, 2] {
+            let aborted = Arc::clone(&aborted);
+            renewer.put(
+                inode_id,
+                &format!("/f{}", inode_id),
+                Box::new(move |reason| {
+                    aborted.lock().unwrap().push((inode_id, reason.to_owned()));
+                }),
+            );
+        }
+        wait_until(|| renewer.get_abort_reason(2).is_some());
+
+        let reason = "Lease timeout of 0 seconds expired.";
+        assert_eq!(renewer.get_abort_reason(1).as_deref(), Some(reason));
+        assert_eq!(renewer.get_abort_reason(2).as_deref(), Some(reason));
+        let mut aborted = aborted.lock().unwrap().clone();
+        aborted.sort();
+        assert_eq!(aborted, [(1, reason.to_owned()), (2, reason.to_owned())]);
+        // the aborted files are no longer renewed
+        let count = renewals.lock().unwrap().len();
+        thread::sleep(Duration::from_millis(50));
+        assert_eq!(renewals.lock().unwrap().len(), count);
+
+        // a file written again gets a new lease
+        expired.store(false, Ordering::SeqCst);
+        renewer.put(1, "/f1", no_abort());
+        assert_eq!(renewer.get_abort_reason(1), None);
+        wait_until(|| renewals.lock().unwrap().len() > count);
+        Ok(())
+    }
+
+    #[test]
+    fn test_daemon_exits_after_grace_period() -> anyhow::Result<()> {
+        let (renewer, renewals) = create_renewer("10", "1200", Default::default())?;
+        renewer.set_grace_period(Duration::from_millis(100));
+        renewer.put(1, "/f", no_abort());
+        assert!(renewer.is_running());
+        renewer.close_file(1);
+        wait_until(|| !renewer.is_running());
+
+        // a new daemon starts for the next file
+        let count = renewals.lock().unwrap().len();
+        renewer.put(2, "/g", no_abort());
+        assert!(renewer.is_running());
+        wait_until(|| renewals.lock().unwrap().len() > count);
+        Ok(())
+    }
+}
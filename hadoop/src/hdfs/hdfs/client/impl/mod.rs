@@ -1,3 +1,7 @@
+mod block_reader_remote;
 mod dfs_client_conf;
+mod lease_renewer;
 
+pub(crate) use block_reader_remote::BlockReaderRemote;
 pub use dfs_client_conf::DfsClientConf;
+pub(crate) use lease_renewer::{LeaseRenewer, NamenodeFactory};
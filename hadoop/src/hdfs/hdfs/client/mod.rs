@@ -1,2 +1,5 @@
 pub mod hdfs_client_config_keys;
+mod hdfs_data_output_stream;
 pub mod r#impl;
+
+pub use hdfs_data_output_stream::SyncFlag;
@@ -0,0 +1,1135 @@
+use super::{
+    client::hdfs_client_config_keys,
+    dfs_packet::{DFSPacket, HEART_BEAT_SEQNO},
+    protocol::{
+        datatransfer::{DataTransferProtoUtil, DataTransferProtocol, PipelineAck, Sender},
+        DatanodeInfo, ExtendedBlock, HdfsFileStatus, LocatedBlock,
+    },
+    protocolpb::PBHelperClient,
+    DFSClient, DFSOutputStream,
+};
+use crate::{
+    common::{
+        fs::StorageType, io::IOException, ipc::remote_exception::unwrap_remote_exception,
+        util::DataChecksum, TokenProto,
+    },
+    hdfs::{op_write_block_proto::BlockConstructionStage, BlockOpResponseProto, Status},
+};
+use anyhow::Error;
+use prost::Message;
+use std::{
+    collections::VecDeque,
+    io::{self, Write},
+    net::{Shutdown, TcpStream},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Condvar, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+/// The number of times a pipeline is recovered for the same packet before
+/// the write fails.
+const MAX_PIPELINE_RECOVERY_RETRIES: i32 = 5;
+
+/// The state shared between the DataStreamer, its ResponseProcessor and the
+/// lease renewer.
+#[derive(Default)]
+struct AckState {
+    // packets sent to the pipeline and waiting for their acks
+    ack_queue: VecDeque<DFSPacket>,
+    last_acked_seqno: i64,
+    // number of bytes acked in the current block
+    acked_bytes: i64,
+    pipeline_recovery_count: i32,
+    // the reason the pipeline failed, if it did
+    error: Option<String>,
+    bad_node_index: Option<usize>,
+    // the reason the lease renewer aborted the stream, if it did
+    aborted: Option<String>,
+    // a handle on the socket of the pipeline, to close it on abort
+    pipeline: Option<TcpStream>,
+}
+
+impl AckState {
+    /// Record an error of the pipeline. If no explicit error report was
+    /// received, mark the primary node as failed.
+    fn set_error(&mut self, msg: String) {
+        if self.bad_node_index.is_none() {
+            self.bad_node_index = Some(0);
+        }
+        self.error = Some(msg);
+    }
+}
+
+type SharedAckState = Arc<(Mutex<AckState>, Condvar)>;
+
+/// Processes responses from the datanodes. A packet is removed
+/// from the ackQueue when its response arrives.
+struct ResponseProcessor {
+    reply_stream: TcpStream,
+    responder_closed: Arc<AtomicBool>,
+    handle: JoinHandle<()>,
+}
+
+impl ResponseProcessor {
+    fn start(
+        block_stream: &Arc<Mutex<TcpStream>>,
+        targets: Vec<DatanodeInfo>,
+        block_name: String,
+        socket_timeout: Option<Duration>,
+        ack_state: SharedAckState,
+    ) -> io::Result<Self> {
+        let reply_stream = block_stream.lock().unwrap().try_clone()?;
+        // wake up at half the timeout to keep an idle pipeline alive
+        reply_stream.set_read_timeout(socket_timeout.map(|t| t / 2))?;
+        let input = reply_stream.try_clone()?;
+        let out = Arc::clone(block_stream);
+        let responder_closed = Arc::new(AtomicBool::new(false));
+        let closed = Arc::clone(&responder_closed);
+        let handle = thread::Builder::new()
+            .name(format!("ResponseProcessor for block {}", block_name))
+            .spawn(move || {
+                Self::run(
+                    input,
+                    out,
+                    targets,
+                    block_name,
+                    socket_timeout,
+                    ack_state,
+                    closed,
+                )
+            })?;
+        Ok(Self {
+            reply_stream,
+            responder_closed,
+            handle,
+        })
+    }
+
+    fn run(
+        mut input: TcpStream,
+        out: Arc<Mutex<TcpStream>>,
+        targets: Vec<DatanodeInfo>,
+        block_name: String,
+        socket_timeout: Option<Duration>,
+        ack_state: SharedAckState,
+        responder_closed: Arc<AtomicBool>,
+    ) {
+        let (lock, cvar) = &*ack_state;
+        let fail = |msg: String| {
+            if !responder_closed.load(Ordering::SeqCst) {
+                log::warn!("Exception for {}: {}", block_name, msg);
+                lock.lock().unwrap().set_error(msg);
+                cvar.notify_all();
+            }
+        };
+        let mut last_response = Instant::now();
+        while !responder_closed.load(Ordering::SeqCst) {
+            // read an ack from the pipeline
+            let ack = match PipelineAck::read_fields(&mut input) {
+                Ok(ack) => ack,
+                Err(e) if is_timeout(&e) => {
+                    if socket_timeout.is_some_and(|t| last_response.elapsed() >= t) {
+                        fail(e.to_string());
+                        return;
+                    }
+                    let idle = lock.lock().unwrap().ack_queue.is_empty();
+                    if idle {
+                        // send a heartbeat so the datanodes keep the pipeline open
+                        let heartbeat = DFSPacket::new(0, 0, HEART_BEAT_SEQNO, false);
+                        if let Err(e) = heartbeat.write_to(&mut *out.lock().unwrap()) {
+                            fail(e.to_string());
+                            return;
+                        }
+                    }
+                    continue;
+                }
+                Err(e) => {
+                    fail(e.to_string());
+                    return;
+                }
+            };
+            last_response = Instant::now();
+            log::debug!("DFSClient {}", ack);
+
+            // processes response status from datanodes.
+            let mut state = lock.lock().unwrap();
+            for i in (0..ack.get_num_of_replies()).rev() {
+                let reply = ack.get_reply(i);
+                if reply != Status::Success {
+                    // node i in pipeline failed
+                    state.bad_node_index = Some(i);
+                    drop(state);
+                    fail(format!(
+                        "Bad response {} for {} from datanode {}",
+                        reply.as_str_name(),
+                        block_name,
+                        targets.get(i).map(|n| n.to_string()).unwrap_or_default()
+                    ));
+                    return;
+                }
+            }
+
+            let seqno = ack.get_seqno();
+            if seqno == HEART_BEAT_SEQNO {
+                // a heartbeat ack
+                continue;
+            }
+
+            // a success ack for a data packet
+            let expected = state.ack_queue.front().map(|p| p.get_seqno());
+            if expected != Some(seqno) {
+                drop(state);
+                fail(format!(
+                    "ResponseProcessor: Expecting seqno {} for block {} but received {}",
+                    expected.unwrap_or(-1),
+                    block_name,
+                    seqno
+                ));
+                return;
+            }
+            let one = state.ack_queue.pop_front().unwrap();
+            state.acked_bytes = one.get_last_byte_offset_block();
+            state.last_acked_seqno = seqno;
+            state.pipeline_recovery_count = 0;
+            cvar.notify_all();
+            if one.is_last_packet_in_block() {
+                return;
+            }
+        }
+    }
+
+    /// Stop the responder and wait for it to exit.
+    fn close(self) {
+        self.responder_closed.store(true, Ordering::SeqCst);
+        // unblock the pending read
+        let _ = self.reply_stream.shutdown(Shutdown::Both);
+        let _ = self.handle.join();
+    }
+}
+
+fn is_timeout(e: &Error) -> bool {
+    e.downcast_ref::<io::Error>().is_some_and(|e| {
+        matches!(
+            e.kind(),
+            io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+        )
+    })
+}
+
+/// get the recovery stage of this stage
+fn get_recovery_stage(stage: BlockConstructionStage) -> BlockConstructionStage {
+    match stage {
+        BlockConstructionStage::PipelineSetupAppend
+        | BlockConstructionStage::PipelineSetupAppendRecovery => {
+            BlockConstructionStage::PipelineSetupAppendRecovery
+        }
+        BlockConstructionStage::PipelineClose | BlockConstructionStage::PipelineCloseRecovery => {
+            BlockConstructionStage::PipelineCloseRecovery
+        }
+        _ => BlockConstructionStage::PipelineSetupStreamingRecovery,
+    }
+}
+
+/// The DataStreamer class is responsible for sending data packets to the
+/// datanodes in the pipeline. It retrieves a new blockid and block locations
+/// from the namenode, and starts streaming packets to the pipeline of
+/// Datanodes. Every packet has a sequence number associated with
+/// it. When all the packets for a block are sent out and acks for each
+/// if them are received, the DataStreamer closes the current block.
+///
+/// The DataStreamer sends the packets from the writing thread, while a
+/// ResponseProcessor thread receives the acks. If an error occurs, the
+/// unacknowledged packets are moved back to the data queue, the bad datanode
+/// is removed from the pipeline, and the remaining datanodes are set up with
+/// a new generation stamp before streaming resumes.
+pub(crate) struct DataStreamer {
+    dfs_client: Arc<DFSClient>,
+    src: String,
+    file_id: u64,
+    block_size: i64,
+    // its length is in the ack state
+    block: Option<ExtendedBlock>,
+    access_token: TokenProto,
+    block_stream: Option<Arc<Mutex<TcpStream>>>,
+    response: Option<ResponseProcessor>,
+    nodes: Vec<DatanodeInfo>,
+    storage_types: Vec<StorageType>,
+    storage_ids: Vec<String>,
+    stage: BlockConstructionStage,
+    // bytes written in current block
+    bytes_cur_block: i64,
+    // the number of bytes of the current block sent to the pipeline
+    bytes_sent: i64,
+    current_seqno: i64,
+    last_queued_seqno: i64,
+    data_queue: VecDeque<DFSPacket>,
+    ack_state: SharedAckState,
+    max_packets: usize,
+    excluded_nodes: Vec<DatanodeInfo>,
+    // the datanodes removed from the pipeline of the current block
+    failed: Vec<DatanodeInfo>,
+    replication: i16,
+    is_append: bool,
+    is_hflushed: bool,
+    checksum4_write_block: DataChecksum,
+    bad_node_index: Option<usize>,
+    persist_blocks: bool,
+    last_exception: Option<String>,
+}
+
+impl DataStreamer {
+    /// construction with tracing info
+    pub fn new(
+        stat: &HdfsFileStatus,
+        block: Option<ExtendedBlock>,
+        dfs_client: Arc<DFSClient>,
+        src: &str,
+        checksum: DataChecksum,
+    ) -> Self {
+        let max_packets = dfs_client.get_conf().get_write_max_packets() as usize;
+        let ack_state = AckState {
+            last_acked_seqno: -1,
+            acked_bytes: block
+                .as_ref()
+                .map(|b| b.get_num_bytes())
+                .unwrap_or_default(),
+            ..Default::default()
+        };
+        Self {
+            dfs_client,
+            src: src.to_owned(),
+            file_id: stat.get_file_id() as u64,
+            block_size: stat.get_block_size(),
+            block,
+            access_token: TokenProto::default(),
+            block_stream: None,
+            response: None,
+            nodes: vec![],
+            storage_types: vec![],
+            storage_ids: vec![],
+            stage: BlockConstructionStage::PipelineSetupCreate,
+            bytes_cur_block: 0,
+            bytes_sent: 0,
+            current_seqno: 0,
+            last_queued_seqno: -1,
+            data_queue: VecDeque::new(),
+            ack_state: Arc::new((Mutex::new(ack_state), Condvar::new())),
+            max_packets: max_packets.max(1),
+            excluded_nodes: vec![],
+            failed: vec![],
+            replication: stat.get_replication(),
+            is_append: false,
+            is_hflushed: false,
+            checksum4_write_block: checksum,
+            bad_node_index: None,
+            persist_blocks: false,
+            last_exception: None,
+        }
+    }
+
+    /// Construct a data streamer for appending to the last partial block
+    pub fn new_for_append(
+        last_block: &LocatedBlock,
+        stat: &HdfsFileStatus,
+        dfs_client: Arc<DFSClient>,
+        src: &str,
+        checksum: DataChecksum,
+    ) -> Self {
+        let block = last_block.get_block().to_owned();
+        let bytes_sent = block.get_num_bytes();
+        let mut streamer = Self::new(stat, Some(block), dfs_client, src, checksum);
+        streamer.access_token = last_block.get_block_token().to_owned();
+        streamer.stage = BlockConstructionStage::PipelineSetupAppend;
+        streamer.is_append = true;
+        streamer.bytes_sent = bytes_sent;
+        streamer
+    }
+
+    /// Set the pipeline to append to the last block of the file.
+    pub fn set_pipeline_in_construction(
+        &mut self,
+        last_block: &LocatedBlock,
+    ) -> anyhow::Result<()> {
+        self.set_pipeline(last_block);
+        if self.nodes.is_empty() {
+            return Err(Error::msg(format!(
+                "Unable to retrieve blocks locations  for last block {:?} of file {}",
+                self.block, self.src
+            )));
+        }
+        Ok(())
+    }
+
+    /// Set the pipeline to the datanodes of the given block.
+    fn set_pipeline(&mut self, lb: &LocatedBlock) {
+        self.nodes = lb.get_locations().to_vec();
+        self.storage_types = lb.get_storage_types().to_vec();
+        self.storage_ids = lb.get_storage_ids().to_vec();
+    }
+
+    /// Record that the stream has been hflushed, which makes the default
+    /// replace-datanode-on-failure policy replace failed datanodes.
+    pub fn set_hflush(&mut self) {
+        self.is_hflushed = true;
+    }
+
+    /// get the block this streamer is writing to, with the number of bytes
+    /// acked as its length
+    pub fn get_block(&self) -> Option<ExtendedBlock> {
+        let mut block = self.block.clone()?;
+        block.set_num_bytes(self.ack_state.0.lock().unwrap().acked_bytes);
+        Some(block)
+    }
+
+    /// return the number of bytes written in the current block
+    pub fn get_bytes_cur_block(&self) -> i64 {
+        self.bytes_cur_block
+    }
+
+    /// set number of bytes written in the current block
+    pub fn set_bytes_cur_block(&mut self, bytes_cur_block: i64) {
+        self.bytes_cur_block = bytes_cur_block;
+    }
+
+    /// increase bytes of current block by len.
+    pub fn inc_bytes_cur_block(&mut self, len: i64) {
+        self.bytes_cur_block += len;
+    }
+
+    /// get the current sequence number and increase it by one
+    pub fn get_and_inc_current_seqno(&mut self) -> i64 {
+        let seqno = self.current_seqno;
+        self.current_seqno += 1;
+        seqno
+    }
+
+    /// get the last queued sequence number
+    pub fn get_last_queued_seqno(&self) -> i64 {
+        self.last_queued_seqno
+    }
+
+    /// check if new blocks have been allocated since the last call
+    pub fn get_and_reset_persist_blocks(&mut self) -> bool {
+        std::mem::take(&mut self.persist_blocks)
+    }
+
+    /// Get a function aborting this streamer from another thread. It closes
+    /// the pipeline, and the pending and later writes fail with the reason
+    /// it is given.
+    pub fn get_aborter(&self) -> impl Fn(&str) + Send + Sync + 'static {
+        let ack_state = Arc::clone(&self.ack_state);
+        move |reason| {
+            let (lock, cvar) = &*ack_state;
+            let mut state = lock.lock().unwrap();
+            state.aborted = Some(reason.to_owned());
+            state.error = Some(reason.to_owned());
+            if let Some(pipeline) = state.pipeline.take() {
+                // unblock the writer and the responder
+                let _ = pipeline.shutdown(Shutdown::Both);
+            }
+            cvar.notify_all();
+        }
+    }
+
+    /// Check if the streamer has been aborted.
+    fn check_aborted(&self) -> anyhow::Result<()> {
+        match &self.ack_state.0.lock().unwrap().aborted {
+            Some(reason) => Err(Error::msg(reason.to_owned())),
+            None => Ok(()),
+        }
+    }
+
+    /// Check if the streamer has failed, returning its error.
+    pub fn check_closed(&self) -> anyhow::Result<()> {
+        self.check_aborted()?;
+        match &self.last_exception {
+            Some(e) => Err(Error::msg(e.to_owned())),
+            None => Ok(()),
+        }
+    }
+
+    /// Put a packet to the data queue and stream it to the pipeline, waiting
+    /// while too many packets are in flight.
+    pub fn wait_and_queue_packet(&mut self, packet: DFSPacket) -> anyhow::Result<()> {
+        self.check_closed()?;
+        self.last_queued_seqno = packet.get_seqno();
+        log::debug!("Queued {}", packet);
+        self.data_queue.push_back(packet);
+        self.run()
+    }
+
+    /// wait for the ack of seqno
+    pub fn wait_for_acked_seqno(&mut self, seqno: i64) -> anyhow::Result<()> {
+        log::debug!("Waiting for ack for: {}", seqno);
+        loop {
+            self.check_closed()?;
+            {
+                let (lock, cvar) = &*self.ack_state;
+                let state = cvar
+                    .wait_while(lock.lock().unwrap(), |s| {
+                        s.last_acked_seqno < seqno && s.error.is_none()
+                    })
+                    .unwrap();
+                if state.last_acked_seqno >= seqno {
+                    return Ok(());
+                }
+            }
+            // recover the pipeline and resend the packets not acked yet
+            self.run()?;
+        }
+    }
+
+    /// Stream the packets of the data queue, closing the streamer if it fails.
+    fn run(&mut self) -> anyhow::Result<()> {
+        let result = self.stream_data_queue();
+        if let Err(e) = &result {
+            log::warn!("DataStreamer Exception: {}", e);
+            self.last_exception = Some(e.to_string());
+            self.close_response();
+            self.close_stream();
+        }
+        result
+    }
+
+    fn stream_data_queue(&mut self) -> anyhow::Result<()> {
+        loop {
+            // an aborted stream does not recover its pipeline
+            self.check_aborted()?;
+            if self.has_error() {
+                self.process_datanode_error()?;
+                continue;
+            }
+            let one = match self.data_queue.pop_front() {
+                Some(one) => one,
+                None => return Ok(()),
+            };
+
+            // get new block from namenode.
+            if self.stage == BlockConstructionStage::PipelineSetupCreate {
+                log::debug!("Allocating new block: {}", self.src);
+                self.next_block_output_stream()?;
+                self.init_data_streaming()?;
+            } else if self.stage == BlockConstructionStage::PipelineSetupAppend {
+                log::debug!("Append to block {:?}", self.block);
+                self.setup_pipeline_for_append_or_recovery()?;
+                self.init_data_streaming()?;
+            }
+
+            if one.is_last_packet_in_block() {
+                // wait for all data packets have been successfully acked
+                if !self.wait_for_all_acks() {
+                    self.data_queue.push_front(one);
+                    continue;
+                }
+                self.stage = BlockConstructionStage::PipelineClose;
+            }
+            let last_packet_in_block = one.is_last_packet_in_block();
+            self.send_to_pipeline(one)?;
+
+            if last_packet_in_block {
+                // wait for the close packet has been acked
+                if !self.wait_for_all_acks() {
+                    continue;
+                }
+                self.end_block();
+            }
+
+            // wait while too many packets are in flight
+            let (lock, cvar) = &*self.ack_state;
+            let _state = cvar
+                .wait_while(lock.lock().unwrap(), |s| {
+                    s.ack_queue.len() >= self.max_packets && s.error.is_none()
+                })
+                .unwrap();
+        }
+    }
+
+    /// Move the packet to the ack queue and send it to the pipeline.
+    fn send_to_pipeline(&mut self, one: DFSPacket) -> anyhow::Result<()> {
+        let block_stream = self
+            .block_stream
+            .clone()
+            .ok_or_else(|| Error::msg("No pipeline to send the packet to"))?;
+        log::debug!("{:?} sending {}", self.block, one);
+        let mut buf = vec![];
+        one.write_to(&mut buf)?;
+        let last_byte_offset_block = one.get_last_byte_offset_block();
+        self.ack_state.0.lock().unwrap().ack_queue.push_back(one);
+
+        // write out data to remote datanode
+        let mut out = block_stream.lock().unwrap();
+        if let Err(e) = out.write_all(&buf).and_then(|_| out.flush()) {
+            // treat the primary datanode as down since the client is unable
+            // to write to it
+            let (lock, cvar) = &*self.ack_state;
+            lock.lock().unwrap().set_error(e.to_string());
+            cvar.notify_all();
+            log::warn!("DataStreamer Exception: {}", e);
+            return Ok(());
+        }
+        self.bytes_sent = self.bytes_sent.max(last_byte_offset_block);
+        Ok(())
+    }
+
+    fn has_error(&self) -> bool {
+        self.ack_state.0.lock().unwrap().error.is_some()
+    }
+
+    /// Wait until the ack queue is empty, returning false if the pipeline
+    /// failed meanwhile.
+    fn wait_for_all_acks(&self) -> bool {
+        let (lock, cvar) = &*self.ack_state;
+        let state = cvar
+            .wait_while(lock.lock().unwrap(), |s| {
+                !s.ack_queue.is_empty() && s.error.is_none()
+            })
+            .unwrap();
+        state.error.is_none()
+    }
+
+    fn init_data_streaming(&mut self) -> anyhow::Result<()> {
+        let block_stream = self
+            .block_stream
+            .as_ref()
+            .ok_or_else(|| Error::msg("No pipeline to stream data to"))?;
+        log::debug!(
+            "nodes {:?} storageTypes {:?}",
+            self.nodes,
+            self.storage_types
+        );
+        let block_name = self
+            .block
+            .as_ref()
+            .map(|b| b.to_string())
+            .unwrap_or_default();
+        let response = ResponseProcessor::start(
+            block_stream,
+            self.nodes.clone(),
+            block_name,
+            self.dfs_client.get_datanode_read_timeout(self.nodes.len()),
+            Arc::clone(&self.ack_state),
+        )?;
+        self.response = Some(response);
+        self.stage = BlockConstructionStage::DataStreaming;
+        Ok(())
+    }
+
+    fn end_block(&mut self) {
+        log::debug!("Closing old block {:?}", self.block);
+        self.close_response();
+        self.close_stream();
+        self.nodes.clear();
+        self.storage_types.clear();
+        self.storage_ids.clear();
+        self.stage = BlockConstructionStage::PipelineSetupCreate;
+    }
+
+    fn close_response(&mut self) {
+        if let Some(response) = self.response.take() {
+            response.close();
+        }
+    }
+
+    fn close_stream(&mut self) {
+        self.block_stream = None;
+        self.ack_state.0.lock().unwrap().pipeline = None;
+    }
+
+    /// If this stream has encountered any errors, shutdown threads
+    /// and mark the stream as closed.
+    fn process_datanode_error(&mut self) -> anyhow::Result<()> {
+        self.close_response();
+        self.close_stream();
+
+        let ack_state = Arc::clone(&self.ack_state);
+        let (lock, cvar) = &*ack_state;
+        {
+            let mut state = lock.lock().unwrap();
+            // move packets from ack queue to front of the data queue
+            while let Some(packet) = state.ack_queue.pop_back() {
+                self.data_queue.push_front(packet);
+            }
+
+            // Record the new pipeline failure recovery.
+            state.pipeline_recovery_count += 1;
+            if state.pipeline_recovery_count > MAX_PIPELINE_RECOVERY_RETRIES {
+                log::warn!(
+                    "Error recovering pipeline for writing {:?}. Already retried {} times for \
+                    the same packet.",
+                    self.block,
+                    MAX_PIPELINE_RECOVERY_RETRIES
+                );
+                return Err(Error::msg(format!(
+                    "Failing write. Tried pipeline recovery {} times without success.",
+                    MAX_PIPELINE_RECOVERY_RETRIES
+                )));
+            }
+            self.bad_node_index = state.bad_node_index.take();
+            state.error = None;
+        }
+
+        self.setup_pipeline_for_append_or_recovery()?;
+
+        if self.stage == BlockConstructionStage::PipelineClose {
+            // If we had an error while closing the pipeline, we go through a
+            // fast-path where the BlockReceiver does not run. Instead, the
+            // DataNode just finalizes the block immediately during the
+            // 'connect ack' process. So, we want to pull the end-of-block
+            // packet from the dataQueue, since we don't actually have a true
+            // pipeline to send it over.
+            if let Some(end_of_block_packet) = self.data_queue.pop_front() {
+                let mut state = lock.lock().unwrap();
+                state.last_acked_seqno = end_of_block_packet.get_seqno();
+                state.pipeline_recovery_count = 0;
+                cvar.notify_all();
+            }
+            self.end_block();
+            Ok(())
+        } else {
+            self.init_data_streaming()
+        }
+    }
+
+    /// Open a DataStreamer to a DataNode pipeline so that
+    /// it can be written to.
+    /// This happens when a file is appended or data streaming fails.
+    /// The bad datanode, if any, is removed from the pipeline and the
+    /// remaining datanodes are set up with a new generation stamp.
+    fn setup_pipeline_for_append_or_recovery(&mut self) -> anyhow::Result<()> {
+        let block = self
+            .get_block()
+            .ok_or_else(|| Error::msg("No block to recover"))?;
+        let mut new_gs;
+        loop {
+            let is_recovery = self.bad_node_index.is_some();
+            if let Some(bad_node_index) = self.bad_node_index.take() {
+                if self.nodes.len() <= 1 {
+                    return Err(Error::msg(format!(
+                        "All datanodes {:?} are bad. Aborting...",
+                        self.nodes
+                    )));
+                }
+                log::warn!(
+                    "Error Recovery for {} in pipeline {:?}: datanode {}({}) is bad.",
+                    block,
+                    self.nodes,
+                    bad_node_index,
+                    self.nodes[bad_node_index]
+                );
+                self.failed.push(self.nodes.remove(bad_node_index));
+                if bad_node_index < self.storage_types.len() {
+                    self.storage_types.remove(bad_node_index);
+                }
+                if bad_node_index < self.storage_ids.len() {
+                    self.storage_ids.remove(bad_node_index);
+                }
+            }
+            if self.nodes.is_empty() {
+                let msg = format!(
+                    "Could not get block locations. Source file \"{}\" - Aborting...",
+                    self.src
+                );
+                log::warn!("{}", msg);
+                return Err(Error::msg(msg));
+            }
+            self.handle_datanode_replacement(&block)?;
+
+            // get a new generation stamp and an access token
+            let lb = self
+                .dfs_client
+                .namenode
+                .update_block_for_pipeline(&block, self.dfs_client.get_client_name())
+                .map_err(unwrap_remote_exception)?;
+            new_gs = lb.get_block().get_generation_stamp();
+            self.access_token = lb.get_block_token().to_owned();
+
+            // set up the pipeline again with the remaining nodes
+            if self.create_block_output_stream(new_gs, is_recovery) {
+                break;
+            }
+        }
+
+        // update pipeline at the namenode
+        let mut new_block = block.clone();
+        new_block.set_generation_stamp(new_gs);
+        self.dfs_client
+            .namenode
+            .update_pipeline(
+                self.dfs_client.get_client_name(),
+                &block,
+                &new_block,
+                &self.nodes,
+                &self.storage_ids,
+            )
+            .map_err(unwrap_remote_exception)?;
+        // update the block with the new generation stamp
+        if let Some(block) = &mut self.block {
+            block.set_generation_stamp(new_gs);
+        }
+        Ok(())
+    }
+
+    /// Add a datanode in place of the failed one if the
+    /// replace-datanode-on-failure policy asks for it. With best effort, a
+    /// failed replacement is logged and the write goes on with the remaining
+    /// datanodes.
+    fn handle_datanode_replacement(&mut self, block: &ExtendedBlock) -> anyhow::Result<()> {
+        let policy = *self.dfs_client.get_conf().get_replace_datanode_on_failure();
+        if !policy.satisfy(
+            self.replication,
+            &self.nodes,
+            self.is_append,
+            self.is_hflushed,
+        ) {
+            return Ok(());
+        }
+        if let Err(e) = self.add_datanode2_existing_pipeline(block) {
+            if !policy.is_best_effort() {
+                return Err(e);
+            }
+            log::warn!(
+                "Failed to replace datanode. Continue with the remaining datanodes since {} \
+                is set to true.",
+                hdfs_client_config_keys::DFS_CLIENT_BLOCK_WRITE_REPLACE_DATANODE_ON_FAILURE_BEST_EFFORT_KEY
+            );
+            log::warn!("{}", e);
+        }
+        Ok(())
+    }
+
+    /// Ask the namenode for an additional datanode and copy the replica to it
+    /// from one of the existing datanodes of the pipeline.
+    fn add_datanode2_existing_pipeline(&mut self, block: &ExtendedBlock) -> anyhow::Result<()> {
+        // Data is copied only when the block already has some. When the
+        // pipeline is set up for create, there is no data yet; when it is
+        // closed, the block is finalized and will be replicated by the
+        // namenode.
+        let last_acked_seqno = self.ack_state.0.lock().unwrap().last_acked_seqno;
+        if (!self.is_append
+            && last_acked_seqno < 0
+            && self.stage == BlockConstructionStage::PipelineSetupCreate)
+            || self.stage == BlockConstructionStage::PipelineClose
+            || self.stage == BlockConstructionStage::PipelineCloseRecovery
+        {
+            return Ok(());
+        }
+
+        let mut last_exception = None;
+        let mut exclude = self.failed.clone();
+        let mut tried = 0;
+        while tried < 3 {
+            let original = self.nodes.clone();
+            let original_types = self.storage_types.clone();
+            let original_ids = self.storage_ids.clone();
+            // get a new datanode
+            let lb = self
+                .dfs_client
+                .namenode
+                .get_additional_datanode(
+                    &self.src,
+                    self.file_id,
+                    block,
+                    &self.nodes,
+                    &self.storage_ids,
+                    &exclude,
+                    1,
+                    self.dfs_client.get_client_name(),
+                )
+                .map_err(unwrap_remote_exception)?;
+            // a new node was allocated by the namenode. Update nodes.
+            self.set_pipeline(&lb);
+
+            // find the new datanode
+            let Some(d) = self.find_new_datanode(&original)? else {
+                return Ok(());
+            };
+            // transfer replica. pick a source from the original nodes
+            let src = &original[tried % original.len()];
+            let targets = [self.nodes[d].clone()];
+            let target_storage_types = self.storage_types.get(d).copied().into_iter().collect();
+            let target_storage_ids = self.storage_ids.get(d).cloned().into_iter().collect();
+            tried += 1;
+            match self.transfer(
+                src,
+                &targets,
+                target_storage_types,
+                target_storage_ids,
+                lb.get_block_token(),
+            ) {
+                Ok(()) => return Ok(()),
+                Err(e) => {
+                    log::warn!(
+                        "Error transferring data from {} to {}: {}",
+                        src,
+                        targets[0],
+                        e
+                    );
+                    last_exception = Some(e);
+                    // add the allocated node to the exclude list.
+                    exclude.push(targets[0].clone());
+                    self.nodes = original;
+                    self.storage_types = original_types;
+                    self.storage_ids = original_ids;
+                }
+            }
+        }
+        Err(last_exception.unwrap_or_else(|| Error::msg("Failed to add a node")))
+    }
+
+    /// Find the index of the datanode the namenode added to the pipeline, or
+    /// `None` if no datanode was added but the pipeline still has the
+    /// configured minimum number of datanodes.
+    fn find_new_datanode(&self, original: &[DatanodeInfo]) -> anyhow::Result<Option<usize>> {
+        if self.nodes.len() != original.len() + 1 {
+            let min_replication = self
+                .dfs_client
+                .get_conf()
+                .get_replace_datanode_on_failure_replication();
+            if min_replication > 0 && self.nodes.len() >= min_replication as usize {
+                log::warn!(
+                    "Failed to find a new datanode to add to the write pipeline, continue to \
+                    write to the pipeline with {} nodes since it's no less than minimum \
+                    replication: {} configured by {}.",
+                    self.nodes.len(),
+                    min_replication,
+                    hdfs_client_config_keys::DFS_CLIENT_BLOCK_WRITE_REPLACE_DATANODE_ON_FAILURE_MIN_REPLICATION_KEY
+                );
+                return Ok(None);
+            }
+            return Err(Error::msg(format!(
+                "Failed to replace a bad datanode on the existing pipeline due to no more good \
+                datanodes being available to try. (Nodes: current={:?}, original={:?}). The \
+                current failed datanode replacement policy is {}, and a client may configure \
+                this via '{}' in its configuration.",
+                self.nodes,
+                original,
+                self.dfs_client.get_conf().get_replace_datanode_on_failure(),
+                hdfs_client_config_keys::DFS_CLIENT_BLOCK_WRITE_REPLACE_DATANODE_ON_FAILURE_POLICY_KEY
+            )));
+        }
+        self.nodes
+            .iter()
+            .position(|n| {
+                !original
+                    .iter()
+                    .any(|o| o.get_xfer_addr() == n.get_xfer_addr())
+            })
+            .map(Some)
+            .ok_or_else(|| {
+                Error::msg(format!(
+                    "Failed: new datanode not found: nodes={:?}, original={:?}",
+                    self.nodes, original
+                ))
+            })
+    }
+
+    /// Ask the datanode `src` to copy the replica of the current block to
+    /// the `targets`.
+    fn transfer(
+        &self,
+        src: &DatanodeInfo,
+        targets: &[DatanodeInfo],
+        target_storage_types: Vec<StorageType>,
+        target_storage_ids: Vec<String>,
+        block_token: &TokenProto,
+    ) -> anyhow::Result<()> {
+        let block = self
+            .get_block()
+            .ok_or_else(|| Error::msg("No block to transfer"))?;
+        let mut out = self.dfs_client.new_connected_peer(&src.get_xfer_addr())?;
+        out.set_read_timeout(self.dfs_client.get_datanode_read_timeout(2))?;
+
+        // send the request
+        Sender::new(&mut out).transfer_block(
+            &block,
+            block_token,
+            self.dfs_client.get_client_name(),
+            targets,
+            &target_storage_types,
+            &target_storage_ids,
+        )?;
+
+        // ack
+        let resp = BlockOpResponseProto::decode(&PBHelperClient::vint_prefixed(&mut out)?[..])?;
+        if resp.status() != Status::Success {
+            return Err(Error::msg(format!(
+                "Failed to add a datanode. Response status: {:?}{}",
+                resp.status(),
+                resp.message
+                    .map(|m| format!(", message: {}", m))
+                    .unwrap_or_default()
+            )));
+        }
+        Ok(())
+    }
+
+    /// Open a DataStreamer to a DataNode so that it can be written to.
+    /// This happens when a file is created and each time a new block is
+    /// allocated. Must get block ID and the IDs of the destinations from the
+    /// namenode.
+    fn next_block_output_stream(&mut self) -> anyhow::Result<()> {
+        let mut count = self.dfs_client.get_conf().get_num_block_write_retry();
+        let old_block = self.get_block();
+        loop {
+            self.bad_node_index = None;
+            self.bytes_sent = 0;
+
+            let lb = DFSOutputStream::add_block(
+                &self.excluded_nodes,
+                &self.dfs_client,
+                &self.src,
+                old_block.as_ref(),
+                self.file_id,
+            )?;
+            self.block = Some(lb.get_block().to_owned());
+            self.ack_state.0.lock().unwrap().acked_bytes = lb.get_block().get_num_bytes();
+            self.access_token = lb.get_block_token().to_owned();
+            self.set_pipeline(&lb);
+            self.failed.clear();
+
+            // Connect to first DataNode in the list.
+            if self.create_block_output_stream(0, false) {
+                self.persist_blocks = true;
+                return Ok(());
+            }
+
+            if let Some(block) = self.block.take() {
+                log::warn!("Abandoning {}", block);
+                self.dfs_client
+                    .namenode
+                    .abandon_block(
+                        &block,
+                        self.file_id,
+                        &self.src,
+                        self.dfs_client.get_client_name(),
+                    )
+                    .map_err(unwrap_remote_exception)?;
+            }
+            if let Some(bad_node) = self.bad_node_index.and_then(|i| self.nodes.get(i)) {
+                log::warn!("Excluding datanode {}", bad_node);
+                self.excluded_nodes.push(bad_node.to_owned());
+            }
+
+            count -= 1;
+            if count < 0 {
+                return Err(Error::msg("Unable to create new block."));
+            }
+        }
+    }
+
+    /// Connects to the first datanode in the pipeline.
+    /// Returns true if success, otherwise return failure.
+    fn create_block_output_stream(&mut self, new_gs: i64, recovery: bool) -> bool {
+        if self.nodes.is_empty() {
+            log::info!("nodes are empty for write pipeline of {:?}", self.block);
+            return false;
+        }
+        log::debug!("pipeline = {:?}", self.nodes);
+
+        match self.setup_pipeline(new_gs, recovery) {
+            Ok(()) => true,
+            Err((e, first_bad_link)) => {
+                log::info!(
+                    "Exception in createBlockOutputStream {:?}: {}",
+                    self.block,
+                    e
+                );
+                // find the datanode that matches
+                self.bad_node_index = Some(
+                    first_bad_link
+                        .and_then(|link| self.nodes.iter().position(|n| n.get_xfer_addr() == link))
+                        .unwrap_or(0),
+                );
+                self.block_stream = None;
+                false
+            }
+        }
+    }
+
+    /// Connect to the first datanode and send the write block request,
+    /// returning the first bad link reported by the pipeline on failure.
+    fn setup_pipeline(
+        &mut self,
+        new_gs: i64,
+        recovery: bool,
+    ) -> Result<(), (Error, Option<String>)> {
+        // We cannot change the block length in 'block' as it counts the number
+        // of bytes ack'ed.
+        let mut block_copy = self
+            .block
+            .clone()
+            .ok_or_else(|| (Error::msg("No block to write"), None))?;
+        block_copy.set_num_bytes(self.block_size);
+        let acked_bytes = self.ack_state.0.lock().unwrap().acked_bytes;
+        let stage = if recovery {
+            get_recovery_stage(self.stage)
+        } else {
+            self.stage
+        };
+
+        let first_node = &self.nodes[0];
+        let mut out = self
+            .dfs_client
+            .new_connected_peer(&first_node.get_xfer_addr())
+            .map_err(|e| (e, None))?;
+        let read_timeout = self.dfs_client.get_datanode_read_timeout(self.nodes.len());
+        out.set_read_timeout(read_timeout)
+            .map_err(|e| (e.into(), None))?;
+
+        // send the request
+        Sender::new(&mut out)
+            .write_block(
+                &block_copy,
+                self.storage_types.first().copied().unwrap_or_default(),
+                &self.access_token,
+                self.dfs_client.get_client_name(),
+                &self.nodes,
+                &self.storage_types,
+                stage,
+                self.nodes.len() as i32,
+                acked_bytes,
+                self.bytes_sent,
+                new_gs,
+                &DataTransferProtoUtil::to_proto(&self.checksum4_write_block),
+                self.storage_ids.first().map(String::as_str),
+                &self.storage_ids,
+            )
+            .map_err(|e| (e, None))?;
+
+        // receive ack for connect
+        let resp = PBHelperClient::vint_prefixed(&mut out)
+            .and_then(|buf| Ok(BlockOpResponseProto::decode(&buf[..])?))
+            .map_err(|e| (e, None))?;
+        let pipeline_status = resp.status();
+        let first_bad_link = resp.first_bad_link.unwrap_or_default();
+
+        if pipeline_status != Status::Success {
+            let e = if pipeline_status == Status::ErrorAccessToken {
+                Error::new(IOException::InvalidBlockToken(format!(
+                    "Got access token error for connect ack with firstBadLink as {}",
+                    first_bad_link
+                )))
+            } else {
+                Error::msg(format!(
+                    "Bad connect ack with firstBadLink as {}",
+                    first_bad_link
+                ))
+            };
+            let first_bad_link = (!first_bad_link.is_empty()).then_some(first_bad_link);
+            return Err((e, first_bad_link));
+        }
+        let pipeline = out.try_clone().map_err(|e| (e.into(), None))?;
+        self.ack_state.0.lock().unwrap().pipeline = Some(pipeline);
+        self.block_stream = Some(Arc::new(Mutex::new(out)));
+        Ok(())
+    }
+}
+
+impl Drop for DataStreamer {
+    fn drop(&mut self) {
+        self.close_response();
+    }
+}
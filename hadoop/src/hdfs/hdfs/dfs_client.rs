@@ -1,13 +1,35 @@
-use super::{client::r#impl::DfsClientConf, protocol::ClientProtocol, NameNodeProxiesClient};
+use super::{
+    client::r#impl::{DfsClientConf, LeaseRenewer, NamenodeFactory},
+    dfs_input_stream::DFSInputStream,
+    dfs_output_stream::DFSOutputStream,
+    file_checksum_helper::ReplicatedFileChecksumComputer,
+    protocol::{
+        hdfs_constants::READ_TIMEOUT_EXTENSION, BatchedDirectoryListing, ClientProtocol,
+        DirectoryListing, HdfsFileStatus, LocatedBlocks, GET_STATS_CAPACITY_IDX,
+        GET_STATS_REMAINING_IDX, GET_STATS_USED_IDX,
+    },
+    DFSUtilClient, NameNodeProxiesClient,
+};
 use crate::common::{
     conf::Configuration,
-    fs::permission::{fs_permission::DIR_DEFAULT_PERM, FsCreateModes, FsPermission},
-    io::Text,
+    fs::{
+        options::Rename,
+        permission::{
+            fs_permission::{DIR_DEFAULT_PERM, FILE_DEFAULT_PERM},
+            FsCreateModes, FsPermission,
+        },
+        BlockLocation, ContentSummary, CreateFlag, FileChecksum, FsStatus,
+    },
+    io::{IOException, Text},
+    ipc::remote_exception::unwrap_remote_exception,
+    net::NetUtils,
     security::UserGroupInformation,
 };
+use anyhow::Error;
 use atomic::Atomic;
 use iref::Iri;
-use std::sync::Arc;
+use std::{net::TcpStream, process, sync::Arc, time::Duration};
+use uuid::Uuid;
 
 /// DFSClient can connect to a Hadoop Filesystem and
 /// perform basic file tasks.  It uses the ClientProtocol
@@ -18,12 +40,14 @@ use std::sync::Arc;
 /// DistributedFileSystem, which uses DFSClient to handle
 /// filesystem tasks.
 pub(crate) struct DFSClient {
-    _conf: Configuration,
+    conf: Configuration,
     dfs_client_conf: DfsClientConf,
-    namenode: Box<dyn ClientProtocol>,
+    pub namenode: Box<dyn ClientProtocol>,
     // The service used for delegation tokens
     _dt_service: Text,
     pub ugi: UserGroupInformation,
+    client_name: String,
+    lease_renewer: LeaseRenewer,
 }
 
 impl DFSClient {
@@ -35,20 +59,302 @@ impl DFSClient {
             name_node_uri,
             Some(nn_fallback_to_simple_auth),
         )?;
+        // the lease renewer daemon talks to the namenode over its own
+        // connection
+        let namenode_factory: NamenodeFactory = {
+            let conf = conf.to_owned();
+            let name_node_uri = name_node_uri.to_owned();
+            Arc::new(move || {
+                let proxy_info = NameNodeProxiesClient::create_proxy_with_client_protocol(
+                    &conf,
+                    &name_node_uri,
+                    None,
+                )?;
+                Ok(proxy_info.proxy)
+            })
+        };
+        Self::new_internal(
+            conf,
+            proxy_info.proxy,
+            proxy_info.dt_service,
+            namenode_factory,
+        )
+    }
+
+    /// Create a new DFSClient talking to the given rpcNamenode, whose lease
+    /// renewer gets its namenode from `namenode_factory`.
+    #[cfg(test)]
+    pub(crate) fn with_rpc_namenode(
+        conf: &Configuration,
+        rpc_namenode: Box<dyn ClientProtocol>,
+        namenode_factory: NamenodeFactory,
+    ) -> anyhow::Result<Self> {
+        Self::new_internal(
+            conf,
+            rpc_namenode,
+            Text::from(String::new()),
+            namenode_factory,
+        )
+    }
+
+    fn new_internal(
+        conf: &Configuration,
+        namenode: Box<dyn ClientProtocol>,
+        dt_service: Text,
+        namenode_factory: NamenodeFactory,
+    ) -> anyhow::Result<Self> {
+        let dfs_client_conf = DfsClientConf::new(conf)?;
+        let client_name = format!(
+            "DFSClient_NONMAPREDUCE_{}_{}",
+            Uuid::new_v4().as_u128() as i32,
+            process::id()
+        );
+        let lease_renewer = LeaseRenewer::new(&client_name, &dfs_client_conf, namenode_factory);
         Ok(Self {
-            _conf: conf.to_owned(),
-            dfs_client_conf: DfsClientConf::new(&conf)?,
-            namenode: Box::new(proxy_info.proxy),
-            _dt_service: proxy_info.dt_service,
+            conf: conf.to_owned(),
+            dfs_client_conf,
+            namenode,
+            _dt_service: dt_service,
             ugi: UserGroupInformation::get_current_user()?,
+            client_name,
+            lease_renewer,
+        })
+    }
+
+    pub fn get_conf(&self) -> &DfsClientConf {
+        &self.dfs_client_conf
+    }
+
+    pub fn get_configuration(&self) -> &Configuration {
+        &self.conf
+    }
+
+    pub fn get_client_name(&self) -> &str {
+        &self.client_name
+    }
+
+    /// Get the lease renewer of the files being written by this client.
+    pub fn get_lease_renewer(&self) -> &LeaseRenewer {
+        &self.lease_renewer
+    }
+
+    /// Get a lease and start automatic renewal
+    fn begin_file_lease(&self, inode_id: u64, src: &str, out: &DFSOutputStream) {
+        self.lease_renewer
+            .put(inode_id, src, Box::new(out.get_aborter()));
+    }
+
+    /// Stop renewal of lease for the file.
+    pub fn end_file_lease(&self, inode_id: u64) {
+        self.lease_renewer.close_file(inode_id);
+    }
+
+    /// Return the timeout that clients should use when writing to datanodes.
+    pub fn get_datanode_read_timeout(&self, num_nodes: usize) -> Option<Duration> {
+        let dfsv2_timeout = self.dfs_client_conf.get_socket_timeout();
+        (dfsv2_timeout > 0).then(|| {
+            Duration::from_millis(
+                (dfsv2_timeout as u64) + (READ_TIMEOUT_EXTENSION as u64) * num_nodes as u64,
+            )
         })
     }
 
+    /// Get locations of the blocks of the specified file within the
+    /// prefetch range, starting at `start`.
+    pub fn get_located_blocks(
+        &self,
+        src: &str,
+        start: i64,
+    ) -> anyhow::Result<Option<LocatedBlocks>> {
+        self.namenode
+            .get_block_locations(src, start, self.dfs_client_conf.get_prefetch_size())
+            .map_err(unwrap_remote_exception)
+    }
+
+    /// Get block location info about a file.
+    ///
+    /// Returns the locations of the blocks of `src` in the range of `length`
+    /// bytes starting at `start`, or an empty vector if `src` does not exist.
+    pub fn get_file_block_locations(
+        &self,
+        src: &str,
+        start: i64,
+        length: i64,
+    ) -> anyhow::Result<Vec<BlockLocation>> {
+        let blocks = self
+            .namenode
+            .get_block_locations(src, start, length)
+            .map_err(unwrap_remote_exception)?;
+        Ok(DFSUtilClient::located_blocks2_locations(blocks.as_ref()))
+    }
+
+    /// Create an input stream that obtains a nodelist from the
+    /// namenode, and then reads from all the right places.  Creates
+    /// inner subclass of InputStream that does the right out-of-band
+    /// work.
+    pub fn open(
+        self: &Arc<Self>,
+        src: &str,
+        verify_checksum: bool,
+    ) -> anyhow::Result<DFSInputStream> {
+        // Get block info from namenode
+        let located_blocks = self.get_located_blocks(src, 0)?.ok_or_else(|| {
+            Error::new(IOException::FileNotFound(format!(
+                "File does not exist: {}",
+                src
+            )))
+        })?;
+        DFSInputStream::new(Arc::clone(self), src, verify_checksum, located_blocks)
+    }
+
+    /// Create a new dfs file with the specified block replication
+    /// with write-progress reporting and return an output stream for writing
+    /// into the file.
+    ///
+    /// `permission` defaults to the default file permission when `None`,
+    /// and is masked against the configured umask.
+    #[allow(clippy::too_many_arguments)]
+    pub fn create(
+        self: &Arc<Self>,
+        src: &str,
+        permission: Option<&FsPermission>,
+        flag: &[CreateFlag],
+        create_parent: bool,
+        replication: i16,
+        block_size: i64,
+    ) -> anyhow::Result<DFSOutputStream> {
+        CreateFlag::validate(flag)?;
+        let masked = self.apply_umask(permission);
+        log::debug!("{}: masked={}", src, masked.get_masked());
+        let result = DFSOutputStream::new_stream_for_create(
+            Arc::clone(self),
+            src,
+            &masked,
+            flag,
+            create_parent,
+            replication,
+            block_size,
+        )?;
+        self.begin_file_lease(result.get_file_id(), src, &result);
+        Ok(result)
+    }
+
+    /// Append to an existing HDFS file.
+    ///
+    /// `flag` must contain [`CreateFlag::Append`], and
+    /// [`CreateFlag::NewBlock`] to append data to a new block instead of the
+    /// last partial block.
+    pub fn append(
+        self: &Arc<Self>,
+        src: &str,
+        flag: &[CreateFlag],
+    ) -> anyhow::Result<DFSOutputStream> {
+        CreateFlag::validate_for_append(flag)?;
+        let blk_with_status = self
+            .namenode
+            .append(src, &self.client_name, flag)
+            .map_err(unwrap_remote_exception)?;
+        let status = match blk_with_status.get_file_status() {
+            Some(status) => status.to_owned(),
+            None => {
+                log::debug!(
+                    "NameNode is on an older version, request file info with additional RPC \
+                    call for file: {}",
+                    src
+                );
+                self.get_file_info(src)?.ok_or_else(|| {
+                    Error::new(IOException::FileNotFound(format!(
+                        "File does not exist: {}",
+                        src
+                    )))
+                })?
+            }
+        };
+        let result = DFSOutputStream::new_stream_for_append(
+            Arc::clone(self),
+            src,
+            flag,
+            blk_with_status.get_last_block(),
+            &status,
+        )?;
+        self.begin_file_lease(result.get_file_id(), src, &result);
+        Ok(result)
+    }
+
+    /// Connect to the datanode at `addr`, applying the configured socket
+    /// timeout.
+    pub fn new_connected_peer(&self, addr: &str) -> anyhow::Result<TcpStream> {
+        let sock_addr = NetUtils::create_socket_addr(addr, -1, None)?;
+        let socket_timeout = self.dfs_client_conf.get_socket_timeout();
+        let peer = if socket_timeout > 0 {
+            let timeout = Duration::from_millis(socket_timeout as u64);
+            let peer = TcpStream::connect_timeout(&sock_addr, timeout)?;
+            peer.set_read_timeout(Some(timeout))?;
+            peer
+        } else {
+            TcpStream::connect(sock_addr)?
+        };
+        peer.set_nodelay(true)?;
+        Ok(peer)
+    }
+
+    fn apply_umask(&self, permission: Option<&FsPermission>) -> FsCreateModes {
+        let permission = permission.unwrap_or_else(|| &FILE_DEFAULT_PERM);
+        FsCreateModes::apply_umask(permission, self.dfs_client_conf.get_umask())
+    }
+
     fn apply_umask_dir(&self, permission: Option<&FsPermission>) -> FsCreateModes {
         let permission = permission.unwrap_or_else(|| &DIR_DEFAULT_PERM);
         FsCreateModes::apply_umask(permission, self.dfs_client_conf.get_umask())
     }
 
+    /// Rename file or directory.
+    /// See [`ClientProtocol::rename`].
+    pub fn rename(&self, src: &str, dst: &str) -> anyhow::Result<bool> {
+        self.namenode
+            .rename(src, dst)
+            .map_err(unwrap_remote_exception)
+    }
+
+    /// Move blocks from src to trg and delete src.
+    /// See [`ClientProtocol::concat`].
+    pub fn concat(&self, trg: &str, srcs: &[String]) -> anyhow::Result<()> {
+        self.namenode
+            .concat(trg, srcs)
+            .map_err(unwrap_remote_exception)
+    }
+
+    /// Rename file or directory.
+    /// See [`ClientProtocol::rename2`].
+    pub fn rename2(&self, src: &str, dst: &str, options: &[Rename]) -> anyhow::Result<()> {
+        self.namenode
+            .rename2(src, dst, options)
+            .map_err(unwrap_remote_exception)
+    }
+
+    /// Truncate a file to an indicated size.
+    /// See [`ClientProtocol::truncate`].
+    pub fn truncate(&self, src: &str, new_length: i64) -> anyhow::Result<bool> {
+        if new_length < 0 {
+            return Err(Error::msg(format!(
+                "Cannot truncate to a negative file size: {}.",
+                new_length
+            )));
+        }
+        self.namenode
+            .truncate(src, new_length, &self.client_name)
+            .map_err(unwrap_remote_exception)
+    }
+
+    /// Delete file or directory.
+    /// delete contents of the directory if non empty and recursive
+    /// set to true
+    pub fn delete(&self, src: &str, recursive: bool) -> anyhow::Result<bool> {
+        self.namenode
+            .delete(src, recursive)
+            .map_err(unwrap_remote_exception)
+    }
+
     /// Create a directory (or hierarchy of directories) with the given
     /// name and permission.
     pub fn mkdirs(
@@ -69,6 +375,178 @@ impl DFSClient {
         create_modes: &FsCreateModes,
         create_parent: bool,
     ) -> anyhow::Result<bool> {
-        self.namenode.mkdirs(src, create_modes, create_parent)
+        self.namenode
+            .mkdirs(src, create_modes, create_parent)
+            .map_err(unwrap_remote_exception)
+    }
+
+    /// Set replication for an existing file.
+    pub fn set_replication(&self, src: &str, replication: i16) -> anyhow::Result<bool> {
+        self.namenode
+            .set_replication(src, replication)
+            .map_err(unwrap_remote_exception)
+    }
+
+    /// Set permissions to a file or directory.
+    pub fn set_permission(&self, src: &str, permission: &FsPermission) -> anyhow::Result<()> {
+        self.namenode
+            .set_permission(src, permission)
+            .map_err(unwrap_remote_exception)
+    }
+
+    /// Set file or directory owner.
+    pub fn set_owner(
+        &self,
+        src: &str,
+        username: Option<&str>,
+        groupname: Option<&str>,
+    ) -> anyhow::Result<()> {
+        self.namenode
+            .set_owner(src, username, groupname)
+            .map_err(unwrap_remote_exception)
+    }
+
+    /// Get a partial listing of the indicated directory.
+    ///
+    /// Recommend to use [`EMPTY_NAME`](super::protocol::EMPTY_NAME) as `start_after`
+    /// if the application wants to fetch a listing starting from
+    /// the first entry in the directory.
+    pub fn list_paths(
+        &self,
+        src: &str,
+        start_after: &[u8],
+        need_location: bool,
+    ) -> anyhow::Result<Option<DirectoryListing>> {
+        self.namenode
+            .get_listing(src, start_after, need_location)
+            .map_err(unwrap_remote_exception)
+    }
+
+    /// Get a batched listing for the indicated directories.
+    pub fn batched_list_paths(
+        &self,
+        srcs: &[String],
+        start_after: &[u8],
+        need_location: bool,
+    ) -> anyhow::Result<BatchedDirectoryListing> {
+        self.namenode
+            .get_batched_listing(srcs, start_after, need_location)
+            .map_err(unwrap_remote_exception)
+    }
+
+    /// Get the file info for a specific file or directory.
+    ///
+    /// Returns `None` if the file is not found.
+    pub fn get_file_info(&self, src: &str) -> anyhow::Result<Option<HdfsFileStatus>> {
+        self.namenode
+            .get_file_info(src)
+            .map_err(unwrap_remote_exception)
+    }
+
+    /// Get the file info for a specific file or directory, together with the
+    /// locations of its blocks.
+    ///
+    /// Returns `None` if the file is not found.
+    pub fn get_located_file_info(
+        &self,
+        src: &str,
+        need_block_token: bool,
+    ) -> anyhow::Result<Option<HdfsFileStatus>> {
+        self.namenode
+            .get_located_file_info(src, need_block_token)
+            .map_err(unwrap_remote_exception)
+    }
+
+    /// Get the checksum of the whole file or a range of the file. Note that
+    /// the range always starts from the beginning of the file. The file can
+    /// be in replicated form, the checksum of every block is combined
+    /// according to the configured [`ChecksumCombineMode`].
+    ///
+    /// [`ChecksumCombineMode`]: crate::common::fs::options::ChecksumCombineMode
+    pub fn get_file_checksum_with_combine_mode(
+        &self,
+        src: &str,
+        length: i64,
+    ) -> anyhow::Result<Option<Box<dyn FileChecksum>>> {
+        if length < 0 {
+            return Err(Error::msg(format!(
+                "Unexpected negative length: {}",
+                length
+            )));
+        }
+        self.get_file_checksum_internal(src, length)
+    }
+
+    fn get_file_checksum_internal(
+        &self,
+        src: &str,
+        length: i64,
+    ) -> anyhow::Result<Option<Box<dyn FileChecksum>>> {
+        let mut block_locations = None;
+        if length > 0 {
+            let locations = self.get_block_locations(src, length)?;
+            if locations.get_erasure_coding_policy().is_some() {
+                return Err(Error::new(IOException::UnsupportedOperation(format!(
+                    "Checksum of striped file {} is not supported",
+                    src
+                ))));
+            }
+            block_locations = Some(locations);
+        }
+        let mut maker = ReplicatedFileChecksumComputer::new(
+            src,
+            length,
+            block_locations,
+            self,
+            self.dfs_client_conf.get_checksum_combine_mode(),
+        );
+        maker.compute()?;
+        Ok(maker.get_file_checksum())
+    }
+
+    /// Get block locations of the file range `[0, length)`, failing if the
+    /// file does not exist or is under construction.
+    pub fn get_block_locations(&self, src: &str, length: i64) -> anyhow::Result<LocatedBlocks> {
+        // get block locations for the file range
+        let block_locations = self
+            .namenode
+            .get_block_locations(src, 0, length)
+            .map_err(unwrap_remote_exception)?
+            .ok_or_else(|| {
+                Error::new(IOException::FileNotFound(format!(
+                    "File does not exist: {}",
+                    src
+                )))
+            })?;
+        if block_locations.is_under_construction() {
+            return Err(Error::msg(format!(
+                "Fail to get checksum, since file {} is under construction.",
+                src
+            )));
+        }
+        Ok(block_locations)
+    }
+
+    pub fn get_disk_status(&self) -> anyhow::Result<FsStatus> {
+        let stats = self.namenode.get_stats().map_err(unwrap_remote_exception)?;
+        Ok(FsStatus::new(
+            stats[GET_STATS_CAPACITY_IDX],
+            stats[GET_STATS_USED_IDX],
+            stats[GET_STATS_REMAINING_IDX],
+        ))
+    }
+
+    /// Get the [`ContentSummary`] rooted at the specified directory.
+    pub fn get_content_summary(&self, src: &str) -> anyhow::Result<ContentSummary> {
+        self.namenode
+            .get_content_summary(src)
+            .map_err(unwrap_remote_exception)
+    }
+
+    /// Set the modification and access time of a file.
+    pub fn set_times(&self, src: &str, mtime: i64, atime: i64) -> anyhow::Result<()> {
+        self.namenode
+            .set_times(src, mtime, atime)
+            .map_err(unwrap_remote_exception)
     }
 }
@@ -0,0 +1,1069 @@
+use super::{
+    client::r#impl::BlockReaderRemote,
+    protocol::{DatanodeID, DatanodeInfo, LocatedBlock, LocatedBlocks},
+    protocolpb::ClientDatanodeProtocolTranslatorPB,
+    DFSClient,
+};
+use crate::common::{
+    fs::FSInputStream, io::IOException, ipc::remote_exception::unwrap_remote_exception,
+};
+use anyhow::Error;
+use std::{
+    collections::HashSet,
+    io::{self, Read, Seek, SeekFrom},
+    sync::{
+        atomic::{AtomicI64, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+/// DFSInputStream provides bytes from a named file.  It handles
+/// negotiation of the namenode and various datanodes as necessary.
+///
+/// Positional reads only share the cached block locations and the dead
+/// datanodes with the other reads, so they may run concurrently on one
+/// stream.
+pub(crate) struct DFSInputStream {
+    dfs_client: Arc<DFSClient>,
+    src: String,
+    verify_checksum: bool,
+    located_blocks: Mutex<LocatedBlocks>,
+    // the visible length of the last block if it is under construction,
+    // which is not part of the file length the namenode reports
+    last_block_being_written_length: AtomicI64,
+    block_reader: Option<BlockReaderRemote>,
+    current_node: Option<DatanodeInfo>,
+    pos: i64,
+    block_end: i64,
+    // the datanodes which failed to serve blocks of this file
+    dead_nodes: Mutex<HashSet<DatanodeID>>,
+    // number of times the block locations were refetched for the current
+    // sequential read
+    failures: i32,
+}
+
+impl DFSInputStream {
+    pub fn new(
+        dfs_client: Arc<DFSClient>,
+        src: &str,
+        verify_checksum: bool,
+        located_blocks: LocatedBlocks,
+    ) -> anyhow::Result<Self> {
+        let stream = Self {
+            dfs_client,
+            src: src.to_owned(),
+            verify_checksum,
+            located_blocks: Mutex::new(LocatedBlocks::default()),
+            last_block_being_written_length: AtomicI64::new(0),
+            block_reader: None,
+            current_node: None,
+            pos: 0,
+            block_end: -1,
+            dead_nodes: Mutex::new(HashSet::new()),
+            failures: 0,
+        };
+        if !stream.set_located_blocks(located_blocks)? {
+            stream.open_info()?;
+        }
+        Ok(stream)
+    }
+
+    /// Grab the open-file info from namenode, waiting for the datanodes to
+    /// report the last block if it is under construction.
+    fn open_info(&self) -> anyhow::Result<()> {
+        let conf = self.dfs_client.get_conf();
+        let mut retries_for_last_block_length = conf.get_retry_times_for_get_last_block_length();
+        loop {
+            let located_blocks = self
+                .dfs_client
+                .get_located_blocks(&self.src, 0)?
+                .ok_or_else(|| {
+                    Error::new(IOException::FileNotFound(format!(
+                        "File does not exist: {}",
+                        self.src
+                    )))
+                })?;
+            if self.set_located_blocks(located_blocks)? {
+                return Ok(());
+            }
+            if retries_for_last_block_length == 0 {
+                return Err(Error::msg("Could not obtain the last block locations."));
+            }
+            // Getting last block length as -1 is a special case. When cluster
+            // restarts, DNs may not report immediately. At this time
+            // partial block locations will not be available with NN for
+            // getting the length. Lets retry for 3 times to get the length.
+            log::warn!(
+                "Last block locations not available. Datanodes might not have reported \
+                blocks completely. Will retry for {} times",
+                retries_for_last_block_length
+            );
+            thread::sleep(Duration::from_millis(
+                conf.get_retry_interval_for_get_last_block_length() as u64,
+            ));
+            retries_for_last_block_length -= 1;
+        }
+    }
+
+    /// Cache `located_blocks`, reading the visible length of their last block
+    /// from the datanodes if it is under construction. Returns `false`,
+    /// caching nothing, when the datanodes holding the last block are not
+    /// known yet.
+    fn set_located_blocks(&self, mut located_blocks: LocatedBlocks) -> anyhow::Result<bool> {
+        log::debug!("newInfo = {}", located_blocks);
+        let mut last_block_being_written_length = 0;
+        if !located_blocks.is_last_block_complete() {
+            if let Some(last) = located_blocks.get_last_located_block() {
+                if last.get_locations().is_empty() {
+                    // if the length is zero, then no data has been written to
+                    // the datanodes, so there is no need to wait for the
+                    // locations
+                    if last.get_block_size() != 0 {
+                        return Ok(false);
+                    }
+                } else {
+                    last_block_being_written_length = self.read_block_length(last)?;
+                    located_blocks.set_last_block_num_bytes(last_block_being_written_length);
+                }
+            }
+        }
+        let mut cached = self.located_blocks.lock().unwrap();
+        *cached = located_blocks;
+        self.last_block_being_written_length
+            .store(last_block_being_written_length, Ordering::SeqCst);
+        Ok(true)
+    }
+
+    /// Read the visible length of a block under construction from one of the
+    /// datanodes writing it.
+    fn read_block_length(&self, located_block: &LocatedBlock) -> anyhow::Result<i64> {
+        let locs = located_block.get_locations();
+        let mut replica_not_found_count = locs.len();
+        for datanode in locs {
+            let length = ClientDatanodeProtocolTranslatorPB::new(
+                datanode,
+                self.dfs_client.get_configuration(),
+                self.dfs_client.get_conf().get_socket_timeout(),
+                located_block,
+            )
+            .and_then(|cdp| cdp.get_replica_visible_length(located_block.get_block()))
+            .map_err(unwrap_remote_exception);
+            match length {
+                Ok(n) if n >= 0 => return Ok(n),
+                Ok(_) => {}
+                Err(e) => {
+                    if let Some(IOException::ReplicaNotFound(_)) = e.downcast_ref() {
+                        // special case : replica might not be on the DN,
+                        // treat as 0 length
+                        replica_not_found_count -= 1;
+                    }
+                    log::debug!(
+                        "Failed to getReplicaVisibleLength from datanode {} for block {}: {}",
+                        datanode,
+                        located_block.get_block(),
+                        e
+                    );
+                }
+            }
+        }
+
+        // Namenode told us about these locations, but none know about the
+        // replica means that we hit the race between pipeline creation start
+        // and end. We require all of them because some other exception could
+        // have happened on a DN that has it, and we want to report that error.
+        if replica_not_found_count == 0 {
+            return Ok(0);
+        }
+        Err(Error::new(IOException::CannotObtainBlockLength(format!(
+            "Cannot obtain block length for {} of {}",
+            located_block, self.src
+        ))))
+    }
+
+    /// The length of the file, including the visible length of the last
+    /// block if it is under construction.
+    pub fn get_file_length(&self) -> i64 {
+        let located_blocks = self.located_blocks.lock().unwrap();
+        located_blocks.get_file_length()
+            + self.last_block_being_written_length.load(Ordering::SeqCst)
+    }
+
+    /// Get block at the specified position.
+    /// Fetch it from the namenode if not cached.
+    fn get_block_at(&self, offset: i64) -> anyhow::Result<LocatedBlock> {
+        let file_length = self.get_file_length();
+        let located_blocks = self.located_blocks.lock().unwrap();
+        if offset < 0 || offset >= file_length {
+            return Err(Error::msg(format!(
+                "offset < 0 || offset >= getFileLength(), offset={}, locatedBlocks={}",
+                offset, located_blocks
+            )));
+        }
+        if offset >= located_blocks.get_file_length() {
+            // offset to the portion of the last block, which is not known to
+            // the namenode yet
+            return located_blocks
+                .get_last_located_block()
+                .cloned()
+                .ok_or_else(|| Error::msg(format!("Could not find target position {}", offset)));
+        }
+        match located_blocks.find_block(offset) {
+            Ok(idx) => located_blocks
+                .get(idx)
+                .cloned()
+                .ok_or_else(|| Error::msg(format!("Could not find target position {}", offset))),
+            // block is not cached
+            Err(_) => {
+                drop(located_blocks);
+                self.fetch_block_at(offset)
+            }
+        }
+    }
+
+    /// Fetch a block from namenode and cache it, replacing the cached block
+    /// starting at the same offset if there is one.
+    fn fetch_block_at(&self, offset: i64) -> anyhow::Result<LocatedBlock> {
+        let new_blocks = self
+            .dfs_client
+            .get_located_blocks(&self.src, offset)?
+            .ok_or_else(|| Error::msg(format!("Could not find target position {}", offset)))?;
+        let not_found = || Error::msg(format!("Could not find target position {}", offset));
+        if offset >= self.located_blocks.lock().unwrap().get_file_length() {
+            // update the last located block, which holds the offset
+            if !self.set_located_blocks(new_blocks)? {
+                return Err(Error::msg("Could not obtain the last block locations."));
+            }
+            let located_blocks = self.located_blocks.lock().unwrap();
+            return match located_blocks.find_block(offset) {
+                Ok(idx) => located_blocks.get(idx),
+                Err(_) => located_blocks.get_last_located_block(),
+            }
+            .cloned()
+            .ok_or_else(not_found);
+        }
+        let mut located_blocks = self.located_blocks.lock().unwrap();
+        let target_block_idx = match located_blocks.find_block(offset) {
+            Ok(idx) | Err(idx) => idx,
+        };
+        located_blocks.insert_range(target_block_idx, new_blocks.get_located_blocks());
+        located_blocks
+            .get(target_block_idx)
+            .cloned()
+            .ok_or_else(not_found)
+    }
+
+    /// Get blocks in the specified range.
+    /// Fetch them from the namenode if not cached. This function
+    /// will not get a read request beyond the EOF.
+    fn get_block_range(&self, offset: i64, length: i64) -> anyhow::Result<Vec<LocatedBlock>> {
+        let mut block_range = vec![];
+        let mut cur_off = offset;
+        let mut remaining = length;
+        while remaining > 0 {
+            let blk = self.get_block_at(cur_off)?;
+            let bytes_read = remaining.min(blk.get_start_offset() + blk.get_block_size() - cur_off);
+            block_range.push(blk);
+            remaining -= bytes_read;
+            cur_off += bytes_read;
+        }
+        Ok(block_range)
+    }
+
+    /// Open a BlockReader to the block which contains `target`, returning the
+    /// datanode the block is read from.
+    fn block_seek_to(&mut self, target: i64) -> anyhow::Result<DatanodeInfo> {
+        if target >= self.get_file_length() {
+            return Err(Error::msg("Attempted to read past end of file"));
+        }
+
+        // Will be getting a new BlockReader.
+        self.block_reader = None;
+
+        // Connect to best DataNode for desired Block, with potential offset
+        let mut refetch_token = 1;
+        loop {
+            // Compute desired block
+            let target_block = self.get_block_at(target)?;
+
+            // update current position
+            self.pos = target;
+            self.block_end = target_block.get_start_offset() + target_block.get_block_size() - 1;
+            let offset_into_block = target - target_block.get_start_offset();
+
+            let mut failures = self.failures;
+            let chosen = self.choose_data_node(target_block, &mut failures);
+            self.failures = failures;
+            let (target_block, chosen_node) = chosen?;
+            let target_addr = chosen_node.get_xfer_addr();
+            log::debug!(
+                "Connecting to datanode {} for block {}",
+                target_addr,
+                target_block.get_block()
+            );
+            let block_reader = self
+                .dfs_client
+                .new_connected_peer(&target_addr)
+                .and_then(|peer| {
+                    BlockReaderRemote::new_block_reader(
+                        &self.src,
+                        target_block.get_block(),
+                        target_block.get_block_token(),
+                        offset_into_block,
+                        target_block.get_block_size() - offset_into_block,
+                        self.verify_checksum,
+                        self.dfs_client.get_client_name(),
+                        peer,
+                    )
+                });
+            match block_reader {
+                Ok(block_reader) => {
+                    self.block_reader = Some(block_reader);
+                    self.current_node = Some(chosen_node.clone());
+                    return Ok(chosen_node);
+                }
+                Err(e) if refetch_token > 0 && is_invalid_block_token(&e) => {
+                    log::info!(
+                        "Will fetch a new access token and retry, access token was \
+                        invalid when connecting to {} : {}",
+                        target_addr,
+                        e
+                    );
+                    refetch_token -= 1;
+                    self.fetch_block_at(target)?;
+                }
+                Err(e) => {
+                    log::warn!(
+                        "Failed to connect to {} for file {} for block {}, \
+                        add to deadNodes and continue. {}",
+                        target_addr,
+                        self.src,
+                        target_block.get_block(),
+                        e
+                    );
+                    // Put chosen node into dead list, continue
+                    self.add_to_dead_nodes(&chosen_node);
+                }
+            }
+        }
+    }
+
+    /// Read the data buffer from the current block reader, retrying on the
+    /// current node first and then on another datanode on failure.
+    fn read_buffer(&mut self, buf: &mut [u8]) -> anyhow::Result<usize> {
+        let mut retry_current_node = true;
+        loop {
+            let block_reader = self
+                .block_reader
+                .as_mut()
+                .ok_or_else(|| Error::msg("No block reader"))?;
+            let e = match block_reader.read(buf) {
+                Ok(n) => return Ok(n),
+                Err(e) => e,
+            };
+            if is_checksum_exception(&e) {
+                log::warn!(
+                    "Found Checksum error for {} from {} at {}: {}",
+                    self.src,
+                    self.current_node
+                        .as_ref()
+                        .map(|n| n.to_string())
+                        .unwrap_or_default(),
+                    self.pos,
+                    e
+                );
+                retry_current_node = false;
+            } else if !retry_current_node {
+                log::warn!(
+                    "Exception while reading from file {} at {} from {}: {}",
+                    self.src,
+                    self.pos,
+                    self.current_node
+                        .as_ref()
+                        .map(|n| n.to_string())
+                        .unwrap_or_default(),
+                    e
+                );
+            }
+            let source_found = if retry_current_node {
+                // open a new block reader, possibly on the same datanode
+                self.block_seek_to(self.pos)?;
+                true
+            } else {
+                self.seek_to_new_source(self.pos)?
+            };
+            if !source_found {
+                return Err(e.into());
+            }
+            retry_current_node = false;
+        }
+    }
+
+    fn read_with_strategy(&mut self, buf: &mut [u8]) -> anyhow::Result<usize> {
+        self.failures = 0;
+        if buf.is_empty() || self.pos >= self.get_file_length() {
+            return Ok(0);
+        }
+        let mut retries = 2;
+        loop {
+            match self.read_from_current_block(buf) {
+                Ok(result) => {
+                    self.pos += result as i64;
+                    return Ok(result);
+                }
+                Err(e) => {
+                    if retries == 1 {
+                        log::warn!("DFS Read {}", e);
+                    }
+                    self.block_end = -1;
+                    if let Some(node) = self.current_node.take() {
+                        self.add_to_dead_nodes(&node);
+                    }
+                    retries -= 1;
+                    if retries == 0 {
+                        return Err(e);
+                    }
+                }
+            }
+        }
+    }
+
+    fn read_from_current_block(&mut self, buf: &mut [u8]) -> anyhow::Result<usize> {
+        if self.pos > self.block_end || self.current_node.is_none() {
+            self.block_seek_to(self.pos)?;
+        }
+        let real_len = (buf.len() as i64).min(self.block_end - self.pos + 1) as usize;
+        let result = self.read_buffer(&mut buf[..real_len])?;
+        if result == 0 {
+            return Err(Error::msg("Unexpected EOS from the reader"));
+        }
+        Ok(result)
+    }
+
+    /// Choose the datanode to read `block` from, refetching the block
+    /// locations from the namenode if none of them is usable.  `failures`
+    /// counts the refetches of the read.
+    fn choose_data_node(
+        &self,
+        block: LocatedBlock,
+        failures: &mut i32,
+    ) -> anyhow::Result<(LocatedBlock, DatanodeInfo)> {
+        let mut block = block;
+        loop {
+            if let Some(chosen_node) = self.get_best_node(&block) {
+                return Ok((block, chosen_node));
+            }
+            block = self.refetch_locations(&block, failures)?;
+        }
+    }
+
+    fn refetch_locations(
+        &self,
+        block: &LocatedBlock,
+        failures: &mut i32,
+    ) -> anyhow::Result<LocatedBlock> {
+        let err_msg = self.get_best_node_error_string(block.get_locations());
+        if *failures >= self.dfs_client.get_conf().get_max_block_acquire_failures() {
+            let description = format!(
+                "Could not obtain block: {} file={}",
+                block.get_block(),
+                self.src
+            );
+            log::warn!(
+                "{} {}. Throwing a BlockMissingException",
+                description,
+                err_msg
+            );
+            return Err(Error::new(IOException::BlockMissing(description)));
+        }
+
+        log::info!(
+            "Could not obtain {} from any node: {}. \
+            Will get new block locations from namenode and retry...",
+            block.get_block(),
+            err_msg
+        );
+        // Introducing a random factor to the wait time before another retry.
+        // The wait time is dependent on # of failures and a random factor.
+        // At the first time of getting a BlockMissingException, the wait time
+        // is a random number between 0..3000 ms. If the first retry
+        // still fails, we will wait 3000 ms grace period before the 2nd retry.
+        // Also at the second retry, the waiting window is expanded to 6000 ms
+        // alleviating the request rate from the server. Similarly the 3rd retry
+        // will wait 6000ms grace period before retry and the waiting window is
+        // expanded to 9000ms.
+        let time_window = self.dfs_client.get_conf().get_time_window() as f64;
+        // grace period for the last round of attempt
+        let wait_time = time_window * *failures as f64
+            // expanding time window for each failure
+            + time_window * (*failures + 1) as f64 * rand::random::<f64>();
+        log::warn!(
+            "DFS chooseDataNode: got # {} IOException, will wait for {} msec.",
+            *failures + 1,
+            wait_time
+        );
+        thread::sleep(Duration::from_millis(wait_time as u64));
+        self.dead_nodes.lock().unwrap().clear();
+        self.open_info()?;
+        *failures += 1;
+        self.get_block_at(block.get_start_offset())
+    }
+
+    /// Get the best node from which to stream the data.
+    fn get_best_node(&self, block: &LocatedBlock) -> Option<DatanodeInfo> {
+        let dead_nodes = self.dead_nodes.lock().unwrap();
+        block
+            .get_locations()
+            .iter()
+            .find(|node| !dead_nodes.contains(node))
+            .cloned()
+    }
+
+    fn get_best_node_error_string(&self, nodes: &[DatanodeInfo]) -> String {
+        format!(
+            "No live nodes contain current block Block locations: [{}] Dead nodes: [{}]",
+            nodes
+                .iter()
+                .map(|n| n.to_string())
+                .collect::<Vec<_>>()
+                .join(", "),
+            self.dead_nodes
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|n| n.get_xfer_addr())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    }
+
+    fn add_to_dead_nodes(&self, dn_info: &DatanodeInfo) {
+        self.dead_nodes
+            .lock()
+            .unwrap()
+            .insert((**dn_info).to_owned());
+    }
+
+    /// Seek to given position on a node other than the current node.  If
+    /// a node other than the current node is found, then returns true.
+    /// If another node could not be found, then returns false.
+    fn seek_to_new_source(&mut self, target_pos: i64) -> anyhow::Result<bool> {
+        let old_node = match self.current_node.clone() {
+            Some(node) => node,
+            None => {
+                self.block_seek_to(target_pos)?;
+                return Ok(true);
+            }
+        };
+        let marked_dead = self.dead_nodes.lock().unwrap().contains(&*old_node);
+        self.add_to_dead_nodes(&old_node);
+        let new_node = self.block_seek_to(target_pos)?;
+        if !marked_dead {
+            // remove it from deadNodes. blockSeekTo could have cleared
+            // deadNodes and added currentNode again. Thats ok.
+            self.dead_nodes.lock().unwrap().remove(&*old_node);
+        }
+        Ok(old_node.get_datanode_uuid() != new_node.get_datanode_uuid())
+    }
+
+    /// Read bytes starting from the specified position, without moving the
+    /// current position of the stream.
+    fn pread(&self, position: i64, buf: &mut [u8]) -> anyhow::Result<usize> {
+        let mut failures = 0;
+        let filelen = self.get_file_length();
+        if buf.is_empty() || position < 0 || position >= filelen {
+            return Ok(0);
+        }
+        let real_len = (buf.len() as i64).min(filelen - position);
+
+        // determine the block and byte range within the block
+        // corresponding to position and realLen
+        let block_range = self.get_block_range(position, real_len)?;
+        let mut remaining = real_len;
+        let mut position = position;
+        let mut off = 0;
+        for blk in block_range {
+            let target_start = position - blk.get_start_offset();
+            let bytes_to_read = remaining.min(blk.get_block_size() - target_start);
+            let target_end = target_start + bytes_to_read - 1;
+            self.fetch_block_byte_range(
+                blk,
+                target_start,
+                target_end,
+                &mut buf[off..off + bytes_to_read as usize],
+                &mut failures,
+            )?;
+            remaining -= bytes_to_read;
+            position += bytes_to_read;
+            off += bytes_to_read as usize;
+        }
+        Ok(real_len as usize)
+    }
+
+    fn fetch_block_byte_range(
+        &self,
+        block: LocatedBlock,
+        start: i64,
+        end: i64,
+        buf: &mut [u8],
+        failures: &mut i32,
+    ) -> anyhow::Result<()> {
+        let mut block = block;
+        loop {
+            let (chosen_block, chosen_node) = self.choose_data_node(block, failures)?;
+            block = chosen_block;
+            let result = self.actual_get_from_one_data_node(
+                &chosen_node,
+                block.get_start_offset(),
+                start,
+                end,
+                buf,
+            );
+            // the failed datanode is now dead, try another one
+            if result.is_ok() {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Read `start..=end` of the block starting at `block_start_offset` from
+    /// the given datanode into `buf`.
+    fn actual_get_from_one_data_node(
+        &self,
+        datanode: &DatanodeInfo,
+        block_start_offset: i64,
+        start: i64,
+        end: i64,
+        buf: &mut [u8],
+    ) -> anyhow::Result<()> {
+        let len = (end - start + 1) as usize;
+        let target_addr = datanode.get_xfer_addr();
+        let mut refetch_token = 1;
+        loop {
+            // cached block locations may have been updated by chooseDataNode()
+            // or fetchBlockAt(). Always get the latest list of locations at the
+            // start of the loop.
+            let block = self.get_block_at(block_start_offset)?;
+            let result = self
+                .dfs_client
+                .new_connected_peer(&target_addr)
+                .and_then(|peer| {
+                    let mut reader = BlockReaderRemote::new_block_reader(
+                        &self.src,
+                        block.get_block(),
+                        block.get_block_token(),
+                        start,
+                        len as i64,
+                        self.verify_checksum,
+                        self.dfs_client.get_client_name(),
+                        peer,
+                    )?;
+                    Ok(reader.read_exact(&mut buf[..len])?)
+                });
+            match result {
+                Ok(()) => return Ok(()),
+                Err(e) if refetch_token > 0 && is_invalid_block_token(&e) => {
+                    log::info!(
+                        "Will fetch a new access token and retry, access token was \
+                        invalid when connecting to {} : {}",
+                        target_addr,
+                        e
+                    );
+                    refetch_token -= 1;
+                    self.fetch_block_at(block_start_offset)?;
+                }
+                Err(e) => {
+                    let msg = format!(
+                        "Failed to connect to {} for file {} for block {}:{}",
+                        target_addr,
+                        self.src,
+                        block.get_block(),
+                        e
+                    );
+                    log::warn!("Connection failure: {}", msg);
+                    self.add_to_dead_nodes(datanode);
+                    return Err(Error::msg(msg));
+                }
+            }
+        }
+    }
+}
+
+fn is_invalid_block_token(e: &Error) -> bool {
+    matches!(
+        e.downcast_ref::<IOException>(),
+        Some(IOException::InvalidBlockToken(_))
+    )
+}
+
+fn is_checksum_exception(e: &io::Error) -> bool {
+    matches!(
+        e.get_ref().and_then(|e| e.downcast_ref::<IOException>()),
+        Some(IOException::Checksum(_))
+    )
+}
+
+impl Read for DFSInputStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.read_with_strategy(buf).map_err(io::Error::other)
+    }
+}
+
+impl Seek for DFSInputStream {
+    /// Seek to a new arbitrary location.
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let target_pos = match pos {
+            SeekFrom::Start(offset) => offset as i64,
+            SeekFrom::Current(offset) => self.pos + offset,
+            SeekFrom::End(offset) => self.get_file_length() + offset,
+        };
+        if target_pos > self.get_file_length() {
+            return Err(io::Error::other("Cannot seek after EOF"));
+        }
+        if target_pos < 0 {
+            return Err(io::Error::other("Cannot seek to negative offset"));
+        }
+        if target_pos != self.pos {
+            // the next read opens a new block reader at the new position
+            self.block_reader = None;
+            self.block_end = -1;
+            self.pos = target_pos;
+        }
+        Ok(self.pos as u64)
+    }
+}
+
+impl FSInputStream for DFSInputStream {
+    fn read_at(&self, position: u64, buf: &mut [u8]) -> io::Result<usize> {
+        self.pread(position as i64, buf).map_err(io::Error::other)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        common::{
+            conf::Configuration,
+            fs::common_configuration_keys_public::IPC_CLIENT_CONNECT_MAX_RETRIES_KEY,
+        },
+        hdfs::{
+            hdfs::{
+                client::hdfs_client_config_keys::{
+                    DFS_CLIENT_RETRY_INTERVAL_GET_LAST_BLOCK_LENGTH_KEY,
+                    DFS_CLIENT_RETRY_TIMES_GET_LAST_BLOCK_LENGTH_KEY,
+                    DFS_CLIENT_RETRY_WINDOW_BASE_KEY,
+                },
+                distributed_file_system::tests::located_blocks,
+                protocol::{datatransfer::FakeDataNode, MockClientProtocol},
+            },
+            DatanodeInfoProto,
+        },
+    };
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    const BLOCK_SIZE: u64 = 1024;
+
+    /// The contents of a file of three blocks, the last one partial.
+    fn file_data() -> Vec<u8> {
+        (0..2500).map(|i| (i % 251) as u8).collect()
+    }
+
+    /// Store the blocks of `data` on `datanode`.
+    fn put_blocks(datanode: &FakeDataNode, data: &[u8]) {
+        for block in located_blocks(data.len() as u64, BLOCK_SIZE).blocks {
+            let offset = block.offset as usize;
+            let len = block.b.num_bytes.unwrap_or_default() as usize;
+            datanode.put_block(block.b.block_id, &data[offset..offset + len]);
+        }
+    }
+
+    /// Open `/f` of `len` bytes on a NameNode locating its blocks on the
+    /// DataNodes `locations` returns for the number of calls made so far.
+    fn open(
+        len: u64,
+        locations: impl Fn(usize) -> Vec<DatanodeInfoProto> + Send + Sync + 'static,
+    ) -> anyhow::Result<(DFSInputStream, Arc<AtomicUsize>)> {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let namenode = MockClientProtocol {
+            get_block_locations: Some(Box::new({
+                let calls = Arc::clone(&calls);
+                move |src, _, _| {
+                    assert_eq!(src, "/f");
+                    let locs = locations(calls.fetch_add(1, Ordering::SeqCst));
+                    let mut blocks = located_blocks(len, BLOCK_SIZE);
+                    for block in &mut blocks.blocks {
+                        block.locs = locs.clone();
+                    }
+                    Ok(Some(LocatedBlocks::from(&blocks)))
+                }
+            })),
+            ..Default::default()
+        };
+        let mut conf = Configuration::new();
+        // retry at once when no datanode has a block
+        conf.set(DFS_CLIENT_RETRY_WINDOW_BASE_KEY, "1");
+        let dfs_client = Arc::new(DFSClient::with_rpc_namenode(
+            &conf,
+            Box::new(namenode),
+            Arc::new(|| Ok(Box::<MockClientProtocol>::default())),
+        )?);
+        Ok((dfs_client.open("/f", true)?, calls))
+    }
+
+    /// Open `/f` of `len` bytes stored on `locs` whose last block is still
+    /// being written, the NameNode knowing `last_block_len` bytes of it.
+    fn open_under_construction(
+        len: u64,
+        last_block_len: u64,
+        locs: Vec<DatanodeInfoProto>,
+    ) -> anyhow::Result<DFSInputStream> {
+        let namenode = MockClientProtocol {
+            get_block_locations: Some(Box::new(move |_, _, _| {
+                let mut blocks = located_blocks(len, BLOCK_SIZE);
+                for block in &mut blocks.blocks {
+                    block.locs = locs.clone();
+                }
+                let last = blocks.blocks.last_mut().unwrap();
+                blocks.file_length = last.offset;
+                last.b.num_bytes = Some(last_block_len);
+                blocks.last_block = Some(last.clone());
+                blocks.is_last_block_complete = false;
+                blocks.under_construction = true;
+                Ok(Some(LocatedBlocks::from(&blocks)))
+            })),
+            ..Default::default()
+        };
+        let mut conf = Configuration::new();
+        conf.set(DFS_CLIENT_RETRY_TIMES_GET_LAST_BLOCK_LENGTH_KEY, "1");
+        conf.set(DFS_CLIENT_RETRY_INTERVAL_GET_LAST_BLOCK_LENGTH_KEY, "1");
+        conf.set(IPC_CLIENT_CONNECT_MAX_RETRIES_KEY, "0");
+        let dfs_client = Arc::new(DFSClient::with_rpc_namenode(
+            &conf,
+            Box::new(namenode),
+            Arc::new(|| Ok(Box::<MockClientProtocol>::default())),
+        )?);
+        dfs_client.open("/f", true)
+    }
+
+    #[test]
+    fn test_send_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<DFSInputStream>();
+    }
+
+    #[test]
+    fn test_read_switches_blocks_at_boundaries() -> anyhow::Result<()> {
+        let datanode = FakeDataNode::start()?;
+        let data = file_data();
+        put_blocks(&datanode, &data);
+        let dn = datanode.get_datanode_info();
+        let (mut input, _) = open(data.len() as u64, move |_| vec![dn.clone()])?;
+
+        // a read never crosses the end of a block
+        let mut buf = vec![0; 1500];
+        assert_eq!(input.read(&mut buf)?, 1024);
+        assert_eq!(buf[..1024], data[..1024]);
+        assert_eq!(input.read(&mut buf)?, 1024);
+        assert_eq!(buf[..1024], data[1024..2048]);
+        assert_eq!(input.read(&mut buf)?, 452);
+        assert_eq!(buf[..452], data[2048..]);
+        assert_eq!(input.read(&mut buf)?, 0);
+
+        input.seek(SeekFrom::Start(1020))?;
+        assert_eq!(input.read(&mut buf[..10])?, 4);
+        assert_eq!(input.read(&mut buf[..10])?, 10);
+        assert_eq!(buf[..10], data[1024..1034]);
+        assert_eq!(datanode.get_reads(), 5);
+        Ok(())
+    }
+
+    #[test]
+    fn test_pread() -> anyhow::Result<()> {
+        let datanode = FakeDataNode::start()?;
+        let data = file_data();
+        put_blocks(&datanode, &data);
+        let dn = datanode.get_datanode_info();
+        let (mut input, _) = open(data.len() as u64, move |_| vec![dn.clone()])?;
+
+        // a positional read spans blocks
+        let mut buf = vec![0; 1200];
+        assert_eq!(input.read_at(1000, &mut buf)?, 1200);
+        assert_eq!(buf, data[1000..2200]);
+        // and stops at the end of the file
+        assert_eq!(input.read_at(2400, &mut buf)?, 100);
+        assert_eq!(buf[..100], data[2400..]);
+        assert_eq!(input.read_at(2500, &mut buf)?, 0);
+
+        // without moving the position of the stream
+        assert_eq!(input.read(&mut buf[..10])?, 10);
+        assert_eq!(buf[..10], data[..10]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_concurrent_preads() -> anyhow::Result<()> {
+        let datanode = FakeDataNode::start()?;
+        let data = file_data();
+        put_blocks(&datanode, &data);
+        let dn = datanode.get_datanode_info();
+        let (input, calls) = open(data.len() as u64, move |_| vec![dn.clone()])?;
+
+        // the positional reads share the stream
+        thread::scope(|scope| {
+            let handles: Vec<_> = (0..4)
+                .map(|i| {
+                    let input = &input;
+                    scope.spawn(move || -> io::Result<Vec<u8>> {
+                        let mut buf = vec![0; 700];
+                        input.read_fully(i * 600, &mut buf)?;
+                        Ok(buf)
+                    })
+                })
+                .collect();
+            for (i, handle) in handles.into_iter().enumerate() {
+                assert_eq!(handle.join().unwrap()?, data[i * 600..i * 600 + 700]);
+            }
+            Ok::<_, io::Error>(())
+        })?;
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_last_block_under_construction() -> anyhow::Result<()> {
+        let datanode = FakeDataNode::start()?;
+        let data = file_data();
+        put_blocks(&datanode, &data);
+        let dn = datanode.get_datanode_info();
+
+        // the visible length of the last block is read from the datanode
+        let mut input = open_under_construction(data.len() as u64, 0, vec![dn.clone()])?;
+        assert_eq!(input.get_file_length(), 2500);
+        let mut buf = vec![];
+        input.read_to_end(&mut buf)?;
+        assert_eq!(buf, data);
+        let mut buf = vec![0; 200];
+        assert_eq!(input.read_at(2400, &mut buf)?, 100);
+        assert_eq!(buf[..100], data[2400..]);
+
+        // a replica the datanodes do not know yet is empty
+        let empty = FakeDataNode::start()?;
+        let input = open_under_construction(2500, 0, vec![empty.get_datanode_info()])?;
+        assert_eq!(input.get_file_length(), 2048);
+
+        // as is a last block without locations nor data
+        let input = open_under_construction(2500, 0, vec![])?;
+        assert_eq!(input.get_file_length(), 2048);
+
+        // the datanodes of a last block with data must be known
+        let e = open_under_construction(2500, 100, vec![]).err().unwrap();
+        assert_eq!(e.to_string(), "Could not obtain the last block locations.");
+
+        // and must answer
+        let mut down = dn;
+        down.id.ipc_port = 1;
+        let e = open_under_construction(2500, 0, vec![down]).err().unwrap();
+        assert!(matches!(
+            e.downcast_ref::<IOException>(),
+            Some(IOException::CannotObtainBlockLength(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_dead_node_failover() -> anyhow::Result<()> {
+        let datanode = FakeDataNode::start()?;
+        let data = file_data();
+        put_blocks(&datanode, &data);
+        // a datanode which is down
+        let dead = FakeDataNode::start()?.get_datanode_info();
+        // and one which lost its replicas
+        let empty = FakeDataNode::start()?;
+        let nodes = vec![
+            dead,
+            empty.get_datanode_info(),
+            datanode.get_datanode_info(),
+        ];
+        let (mut input, calls) = open(data.len() as u64, move |_| nodes.clone())?;
+
+        let mut read = vec![];
+        input.read_to_end(&mut read)?;
+        assert_eq!(read, data);
+        let mut buf = vec![0; 100];
+        assert_eq!(input.read_at(2100, &mut buf)?, 100);
+        assert_eq!(buf, data[2100..2200]);
+
+        // the failed datanodes are not tried again for the later blocks
+        assert_eq!(empty.get_reads(), 1);
+        assert_eq!(datanode.get_reads(), 4);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_checksum_error_failover() -> anyhow::Result<()> {
+        let corrupt = FakeDataNode::start()?;
+        let datanode = FakeDataNode::start()?;
+        let data = file_data();
+        put_blocks(&corrupt, &data);
+        put_blocks(&datanode, &data);
+        for block in located_blocks(data.len() as u64, BLOCK_SIZE).blocks {
+            corrupt.corrupt_replica(block.b.block_id, 10);
+        }
+        let nodes = vec![corrupt.get_datanode_info(), datanode.get_datanode_info()];
+        let (mut input, _) = open(data.len() as u64, move |_| nodes.clone())?;
+
+        // each block is read from the other replica once its corrupt
+        // replica fails the checksum
+        let mut read = vec![];
+        input.read_to_end(&mut read)?;
+        assert_eq!(read, data);
+        assert_eq!(corrupt.get_reads(), 3);
+        assert_eq!(datanode.get_reads(), 3);
+        Ok(())
+    }
+
+    #[test]
+    fn test_refetch_locations_after_block_moves() -> anyhow::Result<()> {
+        let old = FakeDataNode::start()?;
+        let new = FakeDataNode::start()?;
+        let data = file_data();
+        put_blocks(&new, &data);
+        // the blocks move from the old datanode to the new one once the
+        // file is opened
+        let (old_dn, new_dn) = (old.get_datanode_info(), new.get_datanode_info());
+        let (mut input, calls) = open(data.len() as u64, move |call| match call {
+            0 => vec![old_dn.clone()],
+            _ => vec![new_dn.clone()],
+        })?;
+
+        let mut buf = vec![0; 100];
+        assert_eq!(input.read_at(100, &mut buf)?, 100);
+        assert_eq!(buf, data[100..200]);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        let mut read = vec![];
+        input.read_to_end(&mut read)?;
+        assert_eq!(read, data);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert_eq!(old.get_reads(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_block_missing() -> anyhow::Result<()> {
+        let datanode = FakeDataNode::start()?;
+        let dn = datanode.get_datanode_info();
+        let (mut input, calls) = open(2500, move |_| vec![dn.clone()])?;
+
+        let err = input.read_with_strategy(&mut [0; 100]).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<IOException>(),
+            Some(IOException::BlockMissing(_))
+        ));
+        // the locations are fetched on open and then once per failure
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+        Ok(())
+    }
+}
@@ -0,0 +1,1102 @@
+use super::{
+    data_streamer::DataStreamer,
+    dfs_packet::DFSPacket,
+    protocol::{
+        datatransfer::PKT_MAX_HEADER_LEN, DatanodeInfo, ExtendedBlock, HdfsFileStatus, LocatedBlock,
+    },
+    DFSClient, SyncFlag,
+};
+use crate::common::{
+    fs::{permission::FsCreateModes, CreateFlag, OutputStream},
+    io::IOException,
+    ipc::remote_exception::unwrap_remote_exception,
+    util::DataChecksum,
+};
+use anyhow::Error;
+use std::{
+    io::{self, Write},
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+
+/// The number of chunks buffered before their checksums are computed.
+const BUFFER_NUM_CHUNKS: usize = 9;
+
+/// DFSOutputStream creates files from a stream of bytes.
+///
+/// The client application writes data that is cached internally by
+/// this stream. Data is broken up into packets, each packet is
+/// typically 64K in size. A packet comprises of chunks. Each chunk
+/// is typically 512 bytes and has an associated checksum with it.
+///
+/// When a client application fills up the currentPacket, it is
+/// handed to the DataStreamer, which sends it to the first datanode in
+/// the pipeline. The DataStreamer keeps the packets in the ackQueue until
+/// the datanodes acknowledge them.
+pub(crate) struct DFSOutputStream {
+    dfs_client: Arc<DFSClient>,
+    src: String,
+    file_id: u64,
+    block_size: i64,
+    checksum: DataChecksum,
+    // data not yet cut into chunks
+    buf: Vec<u8>,
+    // the number of bytes buffered before their checksums are computed
+    buf_capacity: usize,
+    // appending to the last partial chunk of the block
+    append_chunk: bool,
+    current_packet: Option<DFSPacket>,
+    write_packet_size: i32,
+    chunks_per_packet: usize,
+    streamer: DataStreamer,
+    closed: bool,
+    last_flush_offset: i64,
+    should_sync_block: bool,
+    // at time of file open
+    initial_file_size: i64,
+}
+
+impl DFSOutputStream {
+    fn new(
+        dfs_client: Arc<DFSClient>,
+        src: &str,
+        flag: &[CreateFlag],
+        stat: &HdfsFileStatus,
+        checksum: DataChecksum,
+        streamer: DataStreamer,
+    ) -> Self {
+        let bytes_per_checksum = checksum.get_bytes_per_checksum();
+        let buf_capacity = bytes_per_checksum * BUFFER_NUM_CHUNKS;
+        Self {
+            src: src.to_owned(),
+            file_id: stat.get_file_id() as u64,
+            block_size: stat.get_block_size(),
+            checksum,
+            buf: Vec::with_capacity(buf_capacity),
+            buf_capacity,
+            append_chunk: false,
+            current_packet: None,
+            write_packet_size: dfs_client.get_conf().get_write_packet_size(),
+            chunks_per_packet: 0,
+            streamer,
+            closed: false,
+            last_flush_offset: 0,
+            should_sync_block: flag.contains(&CreateFlag::SyncBlock),
+            initial_file_size: 0,
+            dfs_client,
+        }
+    }
+
+    /// Create the checksum, which must divide the block size.
+    fn create_checksum(
+        dfs_client: &DFSClient,
+        stat: &HdfsFileStatus,
+    ) -> anyhow::Result<DataChecksum> {
+        let checksum = dfs_client.get_conf().create_checksum()?;
+        let bytes_per_checksum = checksum.get_bytes_per_checksum();
+        let block_size = stat.get_block_size();
+        if block_size % bytes_per_checksum as i64 != 0 {
+            return Err(Error::msg(format!(
+                "Invalid values: dfs.bytes-per-checksum (={}) must divide block size (={}).",
+                bytes_per_checksum, block_size
+            )));
+        }
+        Ok(checksum)
+    }
+
+    pub fn new_stream_for_create(
+        dfs_client: Arc<DFSClient>,
+        src: &str,
+        masked: &FsCreateModes,
+        flag: &[CreateFlag],
+        create_parent: bool,
+        replication: i16,
+        block_size: i64,
+    ) -> anyhow::Result<Self> {
+        let stat = dfs_client
+            .namenode
+            .create(
+                src,
+                masked,
+                dfs_client.get_client_name(),
+                flag,
+                create_parent,
+                replication,
+                block_size,
+            )
+            .map_err(unwrap_remote_exception)?
+            .ok_or_else(|| Error::msg("HdfsFileStatus should not be null!"))?;
+        let checksum = Self::create_checksum(&dfs_client, &stat)?;
+        let streamer = DataStreamer::new(&stat, None, Arc::clone(&dfs_client), src, checksum);
+        let mut out = Self::new(dfs_client, src, flag, &stat, checksum, streamer);
+        out.compute_packet_chunk_size(out.write_packet_size, checksum.get_bytes_per_checksum());
+        Ok(out)
+    }
+
+    /// Construct a new output stream for append.
+    pub fn new_stream_for_append(
+        dfs_client: Arc<DFSClient>,
+        src: &str,
+        flag: &[CreateFlag],
+        last_block: Option<&LocatedBlock>,
+        stat: &HdfsFileStatus,
+    ) -> anyhow::Result<Self> {
+        let checksum = Self::create_checksum(&dfs_client, stat)?;
+        let to_new_block = flag.contains(&CreateFlag::NewBlock);
+
+        // The last partial block of the file has to be filled.
+        let mut out = match last_block {
+            Some(last_block) if !to_new_block => {
+                // indicate that we are appending to an existing block
+                let streamer = DataStreamer::new_for_append(
+                    last_block,
+                    stat,
+                    Arc::clone(&dfs_client),
+                    src,
+                    checksum,
+                );
+                let mut out = Self::new(dfs_client, src, flag, stat, checksum, streamer);
+                out.streamer
+                    .set_bytes_cur_block(last_block.get_block_size());
+                out.adjust_packet_chunk_size(stat)?;
+                out.streamer.set_pipeline_in_construction(last_block)?;
+                out
+            }
+            _ => {
+                let streamer = DataStreamer::new(
+                    stat,
+                    last_block.map(|b| b.get_block().to_owned()),
+                    Arc::clone(&dfs_client),
+                    src,
+                    checksum,
+                );
+                let mut out = Self::new(dfs_client, src, flag, stat, checksum, streamer);
+                out.compute_packet_chunk_size(
+                    out.write_packet_size,
+                    checksum.get_bytes_per_checksum(),
+                );
+                out
+            }
+        };
+        // length of file when opened
+        out.initial_file_size = stat.get_len();
+        Ok(out)
+    }
+
+    fn adjust_packet_chunk_size(&mut self, stat: &HdfsFileStatus) -> anyhow::Result<()> {
+        let bytes_per_checksum = self.checksum.get_bytes_per_checksum() as i64;
+        let used_in_last_block = stat.get_len() % self.block_size;
+        let free_in_last_block = self.block_size - used_in_last_block;
+
+        // calculate the amount of free space in the pre-existing
+        // last crc chunk
+        let used_in_cksum = stat.get_len() % bytes_per_checksum;
+        let free_in_cksum = bytes_per_checksum - used_in_cksum;
+
+        // if there is space in the last block, then we have to
+        // append to that block
+        if free_in_last_block == self.block_size {
+            return Err(Error::msg(format!(
+                "The last block for file {} is full.",
+                self.src
+            )));
+        }
+
+        if used_in_cksum > 0 && free_in_cksum > 0 {
+            // if there is space in the last partial chunk, then
+            // setup in such a way that the next packet will have only
+            // one chunk that fills up the partial chunk.
+            self.compute_packet_chunk_size(0, free_in_cksum as usize);
+            self.set_checksum_buf_size(free_in_cksum as usize);
+            self.append_chunk = true;
+        } else {
+            // if the remaining space in the block is smaller than
+            // that expected size of of a packet, then create
+            // smaller size packet.
+            let psize = (self.write_packet_size as i64).min(free_in_last_block) as i32;
+            self.compute_packet_chunk_size(psize, bytes_per_checksum as usize);
+        }
+        Ok(())
+    }
+
+    /// Resize the buffer of data not yet cut into chunks.
+    fn set_checksum_buf_size(&mut self, size: usize) {
+        self.buf = Vec::with_capacity(size);
+        self.buf_capacity = size;
+    }
+
+    fn compute_packet_chunk_size(&mut self, psize: i32, csize: usize) {
+        let body_size = psize - PKT_MAX_HEADER_LEN as i32;
+        let chunk_size = (csize + self.checksum.get_checksum_size()) as i32;
+        self.chunks_per_packet = (body_size / chunk_size).max(1) as usize;
+        log::debug!(
+            "computePacketChunkSize: src={}, chunkSize={}, chunksPerPacket={}, packetSize={}",
+            self.src,
+            chunk_size,
+            self.chunks_per_packet,
+            chunk_size * self.chunks_per_packet as i32
+        );
+    }
+
+    /// Returns the size of a file as it was when this stream was opened
+    pub fn get_initial_len(&self) -> i64 {
+        self.initial_file_size
+    }
+
+    /// Get the inode id of the file.
+    pub fn get_file_id(&self) -> u64 {
+        self.file_id
+    }
+
+    /// Get a function aborting this stream, which the lease renewer calls
+    /// when the lease of the file expires.
+    pub fn get_aborter(&self) -> impl Fn(&str) + Send + Sync + 'static {
+        self.streamer.get_aborter()
+    }
+
+    fn check_closed(&self) -> anyhow::Result<()> {
+        if self.closed {
+            return Err(Error::msg(format!("Stream closed: {}", self.src)));
+        }
+        // the lease renewer aborts the file when its lease expires
+        let lease_renewer = self.dfs_client.get_lease_renewer();
+        if let Some(reason) = lease_renewer.get_abort_reason(self.file_id) {
+            return Err(Error::msg(reason));
+        }
+        self.streamer.check_closed()
+    }
+
+    fn create_packet(&mut self, chunks_per_pkt: usize, last_packet_in_block: bool) -> DFSPacket {
+        let seqno = self.streamer.get_and_inc_current_seqno();
+        DFSPacket::new(
+            chunks_per_pkt,
+            self.streamer.get_bytes_cur_block(),
+            seqno,
+            last_packet_in_block,
+        )
+    }
+
+    fn write_internal(&mut self, b: &[u8]) -> anyhow::Result<usize> {
+        self.check_closed()?;
+        let mut n = 0;
+        while n < b.len() {
+            let bytes_to_copy = (self.buf_capacity - self.buf.len()).min(b.len() - n);
+            self.buf.extend_from_slice(&b[n..n + bytes_to_copy]);
+            n += bytes_to_copy;
+            if self.buf.len() == self.buf_capacity {
+                // buf is full
+                self.flush_buffer(false, true)?;
+            }
+        }
+        Ok(n)
+    }
+
+    /// Forces any buffered output bytes to be checksumed and written out to
+    /// the underlying output stream. If there is a trailing partial chunk in
+    /// the buffer,
+    /// 1) flushPartial tells us whether to flush that chunk
+    /// 2) if flushPartial is true, keep tells us whether to keep that chunk
+    ///    in the buffer (if flushPartial is false, it is always kept in the
+    ///    buffer)
+    ///
+    /// Returns the number of bytes in the partial chunk kept in the buffer.
+    fn flush_buffer(&mut self, keep: bool, flush_partial: bool) -> anyhow::Result<usize> {
+        let buf_len = self.buf.len();
+        let partial_len = buf_len % self.checksum.get_bytes_per_checksum();
+        let len_to_flush = if flush_partial {
+            buf_len
+        } else {
+            buf_len - partial_len
+        };
+        if len_to_flush != 0 {
+            let buf = std::mem::take(&mut self.buf);
+            let result = self.write_checksum_chunks(&buf[..len_to_flush]);
+            self.buf = buf;
+            result?;
+        }
+        if !flush_partial || keep {
+            self.buf.drain(..buf_len - partial_len);
+            Ok(if flush_partial { partial_len } else { 0 })
+        } else {
+            self.buf.clear();
+            Ok(0)
+        }
+    }
+
+    /// Generate checksums for the given data chunks and output chunks &
+    /// checksums to the underlying output stream.
+    fn write_checksum_chunks(&mut self, b: &[u8]) -> anyhow::Result<()> {
+        let mut checksums = vec![];
+        self.checksum.calculate_chunked_sums(b, &mut checksums);
+        let checksum_size = self.checksum.get_checksum_size();
+        let chunks = b.chunks(self.checksum.get_bytes_per_checksum());
+        for (i, chunk) in chunks.enumerate() {
+            let checksum = &checksums[i * checksum_size..(i + 1) * checksum_size];
+            self.write_chunk(chunk, checksum)?;
+        }
+        Ok(())
+    }
+
+    fn write_chunk(&mut self, b: &[u8], checksum: &[u8]) -> anyhow::Result<()> {
+        if self.current_packet.is_none() {
+            let packet = self.create_packet(self.chunks_per_packet, false);
+            log::debug!(
+                "WriteChunk allocating new packet seqno={}, src={}, chunksPerPacket={}, \
+                bytesCurBlock={}",
+                packet.get_seqno(),
+                self.src,
+                self.chunks_per_packet,
+                self.streamer.get_bytes_cur_block()
+            );
+            self.current_packet = Some(packet);
+        }
+        let packet = self.current_packet.as_mut().unwrap();
+        packet.write_checksum(checksum);
+        packet.write_data(b);
+        packet.inc_num_chunks();
+        let packet_full = packet.get_num_chunks() == packet.get_max_chunks();
+        self.streamer.inc_bytes_cur_block(b.len() as i64);
+
+        // If packet is full, enqueue it for transmission
+        if packet_full || self.streamer.get_bytes_cur_block() == self.block_size {
+            self.enqueue_current_packet_full()?;
+        }
+        Ok(())
+    }
+
+    fn enqueue_current_packet(&mut self) -> anyhow::Result<()> {
+        match self.current_packet.take() {
+            Some(packet) => self.streamer.wait_and_queue_packet(packet),
+            None => Ok(()),
+        }
+    }
+
+    fn enqueue_current_packet_full(&mut self) -> anyhow::Result<()> {
+        log::debug!(
+            "enqueue full {:?}, src={}, bytesCurBlock={}, blockSize={}",
+            self.current_packet.as_ref().map(|p| p.to_string()),
+            self.src,
+            self.streamer.get_bytes_cur_block(),
+            self.block_size
+        );
+        self.enqueue_current_packet()?;
+        self.adjust_chunk_boundary();
+        self.end_block()
+    }
+
+    /// create an empty packet to mark the end of the block.
+    fn set_current_packet_to_empty(&mut self) {
+        let mut packet = self.create_packet(0, true);
+        packet.set_sync_block(self.should_sync_block);
+        self.current_packet = Some(packet);
+    }
+
+    /// Limit the next packet to the space left in the block.
+    fn adjust_chunk_boundary(&mut self) {
+        let bytes_per_checksum = self.checksum.get_bytes_per_checksum();
+        if self.append_chunk && self.streamer.get_bytes_cur_block() % bytes_per_checksum as i64 == 0
+        {
+            self.append_chunk = false;
+            // the partial chunk has been filled, buffer whole chunks again
+            self.set_checksum_buf_size(bytes_per_checksum * BUFFER_NUM_CHUNKS);
+        }
+
+        if !self.append_chunk {
+            let psize = (self.block_size - self.streamer.get_bytes_cur_block())
+                .min(self.write_packet_size as i64) as i32;
+            self.compute_packet_chunk_size(psize, bytes_per_checksum);
+        }
+    }
+
+    /// if encountering a block boundary, send an empty packet to
+    /// indicate the end of block and reset bytesCurBlock.
+    fn end_block(&mut self) -> anyhow::Result<()> {
+        if self.streamer.get_bytes_cur_block() == self.block_size {
+            self.set_current_packet_to_empty();
+            self.enqueue_current_packet()?;
+            self.streamer.set_bytes_cur_block(0);
+            self.last_flush_offset = 0;
+        }
+        Ok(())
+    }
+
+    /// Flushes out to all replicas of the block. The data is in the buffers
+    /// of the DNs but not necessarily in the DN's OS buffers.
+    ///
+    /// It is a synchronous operation. When it returns,
+    /// it guarantees that flushed data become visible to new readers.
+    /// It is not guaranteed that data has been flushed to
+    /// persistent store on the datanode.
+    /// Block allocations are persisted on namenode.
+    fn hflush_impl(&mut self) -> anyhow::Result<()> {
+        self.flush_or_sync(false, &[])
+    }
+
+    /// The expected semantics is all data have flushed out to all replicas
+    /// and all replicas have done posix fsync equivalent - ie the OS has
+    /// flushed it to the disk device (but the disk may have it in its cache).
+    ///
+    /// With [`SyncFlag::UpdateLength`], the NameNode also updates the file
+    /// length to the length of the synced data. With [`SyncFlag::EndBlock`],
+    /// the current block is closed and the next write allocates a new block.
+    fn hsync_impl(&mut self, sync_flags: &[SyncFlag]) -> anyhow::Result<()> {
+        self.flush_or_sync(true, sync_flags)
+    }
+
+    /// Flush/Sync buffered data to DataNodes.
+    fn flush_or_sync(&mut self, is_sync: bool, sync_flags: &[SyncFlag]) -> anyhow::Result<()> {
+        self.check_closed()?;
+        let update_length = sync_flags.contains(&SyncFlag::UpdateLength);
+        let end_block = sync_flags.contains(&SyncFlag::EndBlock);
+
+        // flush checksum buffer, but keep checksum buffer intact if we do not
+        // need to end the current block
+        let num_kept = self.flush_buffer(!end_block, true)?;
+        // bytesCurBlock potentially incremented if there was buffered data
+
+        log::debug!(
+            "DFSClient flush(): bytesCurBlock={}, lastFlushOffset={}, createNewBlock={}",
+            self.streamer.get_bytes_cur_block(),
+            self.last_flush_offset,
+            end_block
+        );
+        // Flush only if we haven't already flushed till this offset.
+        if self.last_flush_offset != self.streamer.get_bytes_cur_block() {
+            // record the valid offset of this flush
+            self.last_flush_offset = self.streamer.get_bytes_cur_block();
+            if is_sync && self.current_packet.is_none() && !end_block {
+                // Nothing to send right now,
+                // but sync was requested.
+                // Send an empty packet if we do not end the block right now
+                self.current_packet = Some(self.create_packet(self.chunks_per_packet, false));
+            }
+        } else if is_sync && self.streamer.get_bytes_cur_block() > 0 && !end_block {
+            // Nothing to send right now,
+            // and the block was partially written,
+            // and sync was requested.
+            // So send an empty sync packet if we do not end the block right
+            // now
+            self.current_packet = Some(self.create_packet(self.chunks_per_packet, false));
+        } else {
+            // just discard the current packet since it is already been sent.
+            self.current_packet = None;
+        }
+        if let Some(packet) = &mut self.current_packet {
+            packet.set_sync_block(is_sync);
+        }
+        self.enqueue_current_packet()?;
+        if end_block && self.streamer.get_bytes_cur_block() > 0 {
+            // Need to end the current block, thus send an empty packet to
+            // indicate this is the end of the block and reset bytesCurBlock
+            let mut packet = self.create_packet(0, true);
+            packet.set_sync_block(self.should_sync_block || is_sync);
+            self.current_packet = Some(packet);
+            self.enqueue_current_packet()?;
+            self.streamer.set_bytes_cur_block(0);
+            self.last_flush_offset = 0;
+        } else {
+            // Restore state of stream. Record the last flush offset
+            // of the last full chunk that was flushed.
+            let bytes_cur_block = self.streamer.get_bytes_cur_block();
+            self.streamer
+                .set_bytes_cur_block(bytes_cur_block - num_kept as i64);
+        }
+
+        let to_wait_for = self.streamer.get_last_queued_seqno();
+        self.streamer.wait_for_acked_seqno(to_wait_for)?;
+
+        // If 1) any new blocks were allocated since the last flush, or 2) to
+        // update length in NN is required, then persist block locations on
+        // namenode.
+        if self.streamer.get_and_reset_persist_blocks() || update_length {
+            // update the block length first time irrespective of flag
+            let last_block_length = self
+                .streamer
+                .get_block()
+                .map(|b| b.get_num_bytes())
+                .unwrap_or(-1);
+            if let Err(e) = self
+                .dfs_client
+                .namenode
+                .fsync(
+                    &self.src,
+                    self.file_id,
+                    self.dfs_client.get_client_name(),
+                    last_block_length,
+                )
+                .map_err(unwrap_remote_exception)
+            {
+                log::warn!("Unable to persist blocks in hflush for {}: {}", self.src, e);
+                return Err(e);
+            }
+        }
+        self.streamer.set_hflush();
+        Ok(())
+    }
+
+    /// Closes this output stream and releases any system
+    /// resources associated with this stream.
+    fn close_impl(&mut self) -> anyhow::Result<()> {
+        if self.closed {
+            return Ok(());
+        }
+        let result = self.close_internal();
+        // the stream is unusable after a failed close
+        self.closed = true;
+        self.dfs_client.end_file_lease(self.file_id);
+        result
+    }
+
+    fn close_internal(&mut self) -> anyhow::Result<()> {
+        self.check_closed()?;
+
+        // flush from all upper layers
+        self.flush_buffer(false, true)?;
+        self.enqueue_current_packet()?;
+        if self.streamer.get_bytes_cur_block() != 0 {
+            self.set_current_packet_to_empty();
+        }
+
+        // flush all data to Datanodes
+        self.enqueue_current_packet()?;
+        let to_wait_for = self.streamer.get_last_queued_seqno();
+        self.streamer.wait_for_acked_seqno(to_wait_for)?;
+
+        let last_block = self.streamer.get_block();
+        self.complete_file(last_block.as_ref())
+    }
+
+    fn complete_file(&self, last: Option<&ExtendedBlock>) -> anyhow::Result<()> {
+        let local_start = Instant::now();
+        let conf = self.dfs_client.get_conf();
+        let mut sleep_time = conf.get_block_write_locate_following_initial_delay_ms() as u64;
+        let max_sleep_time = conf.get_block_write_locate_following_max_delay_ms() as u64;
+        let mut retries = conf.get_num_block_write_locate_following_retry();
+        loop {
+            let file_complete = self
+                .dfs_client
+                .namenode
+                .complete(
+                    &self.src,
+                    self.dfs_client.get_client_name(),
+                    last,
+                    self.file_id,
+                )
+                .map_err(unwrap_remote_exception)?;
+            if file_complete {
+                return Ok(());
+            }
+            if retries == 0 {
+                return Err(Error::msg(format!(
+                    "Unable to close file because the last block {} does not have enough \
+                    number of replicas.",
+                    last.map(|b| b.to_string()).unwrap_or_default()
+                )));
+            }
+            retries -= 1;
+            thread::sleep(Duration::from_millis(sleep_time));
+            sleep_time = (sleep_time * 2).min(max_sleep_time);
+            if local_start.elapsed() > Duration::from_secs(5) {
+                log::info!("Could not complete {} retrying...", self.src);
+            }
+        }
+    }
+
+    /// Allocate a new block for the file, retrying while the previous block
+    /// has not been replicated yet.
+    pub(crate) fn add_block(
+        excluded_nodes: &[DatanodeInfo],
+        dfs_client: &DFSClient,
+        src: &str,
+        prev_block: Option<&ExtendedBlock>,
+        file_id: u64,
+    ) -> anyhow::Result<LocatedBlock> {
+        let conf = dfs_client.get_conf();
+        let mut retries = conf.get_num_block_write_locate_following_retry();
+        let mut sleep_time = conf.get_block_write_locate_following_initial_delay_ms() as u64;
+        let max_sleep_time = conf.get_block_write_locate_following_max_delay_ms() as u64;
+        let local_start = Instant::now();
+        loop {
+            let result = dfs_client
+                .namenode
+                .add_block(
+                    src,
+                    dfs_client.get_client_name(),
+                    prev_block,
+                    excluded_nodes,
+                    file_id,
+                )
+                .map_err(unwrap_remote_exception);
+            match result {
+                Err(e)
+                    if retries > 0
+                        && matches!(
+                            e.downcast_ref::<IOException>(),
+                            Some(IOException::NotReplicatedYet(_))
+                        ) =>
+                {
+                    retries -= 1;
+                    log::info!("Exception while adding a block: {}", e);
+                    let elapsed = local_start.elapsed();
+                    if elapsed > Duration::from_secs(5) {
+                        log::info!("Waiting for replication for {} seconds", elapsed.as_secs());
+                    }
+                    log::warn!(
+                        "NotReplicatedYetException sleeping {} retries left {}",
+                        src,
+                        retries
+                    );
+                    thread::sleep(Duration::from_millis(sleep_time));
+                    sleep_time = (sleep_time * 2).min(max_sleep_time);
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+impl Write for DFSOutputStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.write_internal(buf).map_err(io::Error::other)
+    }
+
+    /// Data is only sent to the datanodes by [`OutputStream::hflush`] and
+    /// [`OutputStream::hsync`] before the stream is closed.
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl OutputStream for DFSOutputStream {
+    fn close(&mut self) -> io::Result<()> {
+        self.close_impl().map_err(io::Error::other)
+    }
+
+    fn hflush(&mut self) -> io::Result<()> {
+        self.hflush_impl().map_err(io::Error::other)
+    }
+
+    fn hsync(&mut self) -> io::Result<()> {
+        self.hsync_impl(&[]).map_err(io::Error::other)
+    }
+
+    fn hsync_with_flags(&mut self, sync_flags: &[SyncFlag]) -> io::Result<()> {
+        self.hsync_impl(sync_flags).map_err(io::Error::other)
+    }
+}
+
+impl Drop for DFSOutputStream {
+    fn drop(&mut self) {
+        if let Err(e) = self.close_impl() {
+            log::warn!("Failed to close file {}: {}", self.src, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        common::conf::Configuration,
+        hdfs::{
+            hdfs::{
+                client::{
+                    hdfs_client_config_keys::{
+                        DFS_CLIENT_BLOCK_WRITE_LOCATEFOLLOWINGBLOCK_INITIAL_DELAY_MS_KEY,
+                        DFS_CLIENT_BLOCK_WRITE_LOCATEFOLLOWINGBLOCK_RETRIES_KEY,
+                        DFS_CLIENT_BLOCK_WRITE_REPLACE_DATANODE_ON_FAILURE_BEST_EFFORT_KEY,
+                        DFS_CLIENT_LEASE_RENEWAL_KEY, DFS_CLIENT_WRITE_PACKET_SIZE_KEY,
+                        DFS_LEASE_HARDLIMIT_KEY,
+                    },
+                    r#impl::NamenodeFactory,
+                },
+                distributed_file_system::tests::{fake_namenode, FakeNamespace},
+                protocol::{datatransfer::FakeDataNode, MockClientProtocol},
+            },
+            op_write_block_proto::BlockConstructionStage,
+            DatanodeInfoProto, LocatedBlockProto,
+        },
+    };
+    use std::sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Mutex,
+    };
+
+    /// Create `/f` on `namenode` with blocks of `block_size` bytes.
+    fn create(
+        conf: &Configuration,
+        namenode: MockClientProtocol,
+        block_size: i64,
+    ) -> anyhow::Result<DFSOutputStream> {
+        create_with_renewer(
+            conf,
+            namenode,
+            Arc::new(|| Ok(Box::<MockClientProtocol>::default())),
+            1,
+            block_size,
+        )
+    }
+
+    /// Create `/f` on `namenode` with `replication` replicas, renewing its
+    /// lease on the namenodes of `namenode_factory`.
+    fn create_with_renewer(
+        conf: &Configuration,
+        namenode: MockClientProtocol,
+        namenode_factory: NamenodeFactory,
+        replication: i16,
+        block_size: i64,
+    ) -> anyhow::Result<DFSOutputStream> {
+        let dfs_client = Arc::new(DFSClient::with_rpc_namenode(
+            conf,
+            Box::new(namenode),
+            namenode_factory,
+        )?);
+        dfs_client.create(
+            "/f",
+            None,
+            &[CreateFlag::Create],
+            true,
+            replication,
+            block_size,
+        )
+    }
+
+    /// Wait until `cond` holds, failing after a few seconds.
+    fn wait_until(cond: impl Fn() -> bool) {
+        let start = Instant::now();
+        while !cond() {
+            assert!(start.elapsed() < Duration::from_secs(10), "Timed out");
+            thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    fn file_data(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i % 251) as u8).collect()
+    }
+
+    #[test]
+    fn test_pipeline_recovery_after_failed_ack() -> anyhow::Result<()> {
+        let dn1 = FakeDataNode::start()?;
+        let dn2 = FakeDataNode::start()?;
+        let namespace = FakeNamespace::default();
+        let namenode = fake_namenode(
+            Arc::clone(&namespace),
+            vec![dn1.get_datanode_info(), dn2.get_datanode_info()],
+        );
+        let mut conf = Configuration::new();
+        // two chunks per packet
+        conf.set(DFS_CLIENT_WRITE_PACKET_SIZE_KEY, "1100");
+        // the second packet is acked with the last datanode failed
+        dn1.fail_ack(1);
+        let data = file_data(3000);
+
+        let mut out = create(&conf, namenode, 4096)?;
+        out.write_all(&data)?;
+        out.close_impl()?;
+
+        // the unacked packets are resent to the remaining datanode
+        assert_eq!(
+            dn1.get_write_stages(),
+            [
+                BlockConstructionStage::PipelineSetupCreate,
+                BlockConstructionStage::PipelineSetupStreamingRecovery
+            ]
+        );
+        let blocks = namespace.lock().unwrap()["/f"].clone();
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(dn1.get_block(blocks[0].b.block_id), Some(data));
+        assert_eq!(blocks[0].b.num_bytes, Some(3000));
+        assert_eq!(blocks[0].b.generation_stamp, 1002);
+        let locs = blocks[0].locs.iter().map(|dn| &dn.id.datanode_uuid);
+        assert_eq!(
+            locs.collect::<Vec<_>>(),
+            [&dn1.get_datanode_info().id.datanode_uuid]
+        );
+        Ok(())
+    }
+
+    /// A NameNode placing the blocks on `pipeline` and adding `spare` to the
+    /// pipelines which lost a datanode.
+    fn fake_namenode_with_spare(
+        namespace: FakeNamespace,
+        pipeline: Vec<DatanodeInfoProto>,
+        spare: DatanodeInfoProto,
+    ) -> MockClientProtocol {
+        let mut namenode = fake_namenode(namespace, pipeline);
+        namenode.get_additional_datanode =
+            Some(Box::new(move |_, _, blk, existings, _, excludes, _, _| {
+                let spare = Some(spare.clone()).filter(|s| {
+                    !excludes
+                        .iter()
+                        .any(|e| e.get_xfer_port() as u32 == s.id.xfer_port)
+                });
+                Ok(LocatedBlock::from(&LocatedBlockProto {
+                    b: blk.into(),
+                    locs: existings.iter().map(|dn| dn.into()).chain(spare).collect(),
+                    ..Default::default()
+                }))
+            }));
+        namenode
+    }
+
+    #[test]
+    fn test_replace_datanode_after_hflush() -> anyhow::Result<()> {
+        let dns = (0..4)
+            .map(|_| FakeDataNode::start())
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let namespace = FakeNamespace::default();
+        let namenode = fake_namenode_with_spare(
+            Arc::clone(&namespace),
+            dns[..3].iter().map(|dn| dn.get_datanode_info()).collect(),
+            dns[3].get_datanode_info(),
+        );
+        let mut conf = Configuration::new();
+        // two chunks per packet
+        conf.set(DFS_CLIENT_WRITE_PACKET_SIZE_KEY, "1100");
+        let data = file_data(3000);
+
+        let mut out = create_with_renewer(
+            &conf,
+            namenode,
+            Arc::new(|| Ok(Box::<MockClientProtocol>::default())),
+            3,
+            4096,
+        )?;
+        out.write_all(&data[..1024])?;
+        out.hflush_impl()?;
+        // the packet after the hflush is acked with the last datanode failed
+        dns[0].fail_ack(1);
+        out.write_all(&data[1024..])?;
+        out.close_impl()?;
+
+        // the replica is copied from the first datanode to the spare one,
+        // which replaces the failed datanode in the pipeline
+        let blocks = namespace.lock().unwrap()["/f"].clone();
+        assert_eq!(blocks.len(), 1);
+        let spare = dns[3].get_datanode_info();
+        assert_eq!(
+            dns[0].get_transfers(),
+            [(
+                blocks[0].b.block_id,
+                vec![format!("{}:{}", spare.id.ip_addr, spare.id.xfer_port)]
+            )]
+        );
+        assert_eq!(dns[0].get_block(blocks[0].b.block_id), Some(data));
+        assert_eq!(blocks[0].b.generation_stamp, 1002);
+        let locs = blocks[0].locs.iter().map(|dn| &dn.id.datanode_uuid);
+        assert_eq!(
+            locs.collect::<Vec<_>>(),
+            [&dns[0], &dns[1], &dns[3]]
+                .map(|dn| dn.get_datanode_info().id.datanode_uuid)
+                .iter()
+                .collect::<Vec<_>>()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_replace_datanode_without_spare() -> anyhow::Result<()> {
+        let write = |best_effort: &str| -> anyhow::Result<Vec<LocatedBlockProto>> {
+            let dns = (0..3)
+                .map(|_| FakeDataNode::start())
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            let namespace = FakeNamespace::default();
+            // the only spare datanode is the failed one
+            let namenode = fake_namenode_with_spare(
+                Arc::clone(&namespace),
+                dns.iter().map(|dn| dn.get_datanode_info()).collect(),
+                dns[2].get_datanode_info(),
+            );
+            let mut conf = Configuration::new();
+            conf.set(DFS_CLIENT_WRITE_PACKET_SIZE_KEY, "1100");
+            conf.set(
+                DFS_CLIENT_BLOCK_WRITE_REPLACE_DATANODE_ON_FAILURE_BEST_EFFORT_KEY,
+                best_effort,
+            );
+            let data = file_data(3000);
+
+            let mut out = create_with_renewer(
+                &conf,
+                namenode,
+                Arc::new(|| Ok(Box::<MockClientProtocol>::default())),
+                3,
+                4096,
+            )?;
+            out.write_all(&data[..1024])?;
+            out.hflush_impl()?;
+            dns[0].fail_ack(1);
+            out.write_all(&data[1024..])?;
+            out.close_impl()?;
+            assert_eq!(dns[0].get_block(1073741825), Some(data));
+            let blocks = namespace.lock().unwrap()["/f"].clone();
+            Ok(blocks)
+        };
+
+        // the write fails as no datanode can replace the failed one
+        let e = write("false").unwrap_err();
+        assert!(
+            e.to_string()
+                .contains("Failed to replace a bad datanode on the existing pipeline"),
+            "{}",
+            e
+        );
+
+        // unless the replacement is best effort
+        let blocks = write("true")?;
+        assert_eq!(blocks[0].locs.len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_hflush_and_hsync() -> anyhow::Result<()> {
+        let datanode = FakeDataNode::start()?;
+        let namespace = FakeNamespace::default();
+        let mut namenode =
+            fake_namenode(Arc::clone(&namespace), vec![datanode.get_datanode_info()]);
+        let fsync_lengths = Arc::new(Mutex::new(vec![]));
+        let fsync = namenode.fsync.take().unwrap();
+        namenode.fsync = Some(Box::new({
+            let fsync_lengths = Arc::clone(&fsync_lengths);
+            move |src, file_id, client, last_block_length| {
+                fsync_lengths.lock().unwrap().push(last_block_length);
+                fsync(src, file_id, client, last_block_length)
+            }
+        }));
+        let data = file_data(950);
+
+        let mut out = create(&Configuration::new(), namenode, 1024)?;
+        // the first flush of a block persists it on the namenode
+        out.write_all(&data[..600])?;
+        out.hflush_impl()?;
+        assert_eq!(*fsync_lengths.lock().unwrap(), [600]);
+        assert!(datanode.get_sync_lengths().is_empty());
+
+        out.write_all(&data[600..700])?;
+        out.hsync_impl(&[SyncFlag::UpdateLength])?;
+        assert_eq!(*fsync_lengths.lock().unwrap(), [600, 700]);
+        assert_eq!(datanode.get_sync_lengths(), [700]);
+        assert_eq!(namespace.lock().unwrap()["/f"][0].b.num_bytes, Some(700));
+
+        // ending the block syncs the data and the end of the block, but does
+        // not update the length on the namenode
+        out.write_all(&data[700..750])?;
+        out.hsync_impl(&[SyncFlag::EndBlock])?;
+        assert_eq!(*fsync_lengths.lock().unwrap(), [600, 700]);
+        assert_eq!(datanode.get_sync_lengths(), [700, 750, 750]);
+
+        // the next write starts a new block
+        out.write_all(&data[750..])?;
+        out.close_impl()?;
+        let blocks = namespace.lock().unwrap()["/f"].clone();
+        let lengths = blocks.iter().map(|b| b.b.num_bytes).collect::<Vec<_>>();
+        assert_eq!(lengths, [Some(750), Some(200)]);
+        assert_eq!(
+            datanode.get_block(blocks[0].b.block_id).as_deref(),
+            Some(&data[..750])
+        );
+        assert_eq!(
+            datanode.get_block(blocks[1].b.block_id).as_deref(),
+            Some(&data[750..])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_complete_retries() -> anyhow::Result<()> {
+        let datanode = FakeDataNode::start()?;
+        let mut conf = Configuration::new();
+        conf.set(
+            DFS_CLIENT_BLOCK_WRITE_LOCATEFOLLOWINGBLOCK_INITIAL_DELAY_MS_KEY,
+            "1",
+        );
+        // complete returns false until the last block is replicated
+        let complete_namenode = |replicated_after: usize| {
+            let mut namenode =
+                fake_namenode(FakeNamespace::default(), vec![datanode.get_datanode_info()]);
+            let calls = Arc::new(AtomicUsize::new(0));
+            let complete = namenode.complete.take().unwrap();
+            namenode.complete = Some(Box::new({
+                let calls = Arc::clone(&calls);
+                move |src, client, last, file_id| {
+                    if calls.fetch_add(1, Ordering::SeqCst) < replicated_after {
+                        return Ok(false);
+                    }
+                    complete(src, client, last, file_id)
+                }
+            }));
+            (namenode, calls)
+        };
+
+        let (namenode, calls) = complete_namenode(2);
+        let mut out = create(&conf, namenode, 1024)?;
+        out.write_all(&file_data(100))?;
+        out.close_impl()?;
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+        conf.set(DFS_CLIENT_BLOCK_WRITE_LOCATEFOLLOWINGBLOCK_RETRIES_KEY, "1");
+        let (namenode, calls) = complete_namenode(usize::MAX);
+        let mut out = create(&conf, namenode, 1024)?;
+        out.write_all(&file_data(100))?;
+        let err = out.close_impl().unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("does not have enough number of replicas"),
+            "{}",
+            err
+        );
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        // the stream stays closed after a failed close
+        assert!(out.close_impl().is_ok());
+        assert!(out.write_all(&[0]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_abort_after_lease_expires() -> anyhow::Result<()> {
+        let datanode = FakeDataNode::start()?;
+        let namenode = fake_namenode(FakeNamespace::default(), vec![datanode.get_datanode_info()]);
+        let mut conf = Configuration::new();
+        conf.set(DFS_CLIENT_LEASE_RENEWAL_KEY, "10");
+        conf.set(DFS_LEASE_HARDLIMIT_KEY, "0");
+        // the lease renewals fail once the lease has expired
+        let expired = Arc::new(AtomicBool::new(false));
+        let namenode_factory: NamenodeFactory = {
+            let expired = Arc::clone(&expired);
+            Arc::new(move || {
+                let expired = Arc::clone(&expired);
+                Ok(Box::new(MockClientProtocol {
+                    renew_lease: Some(Box::new(move |_| {
+                        if expired.load(Ordering::SeqCst) {
+                            return Err(Error::msg("Connection refused"));
+                        }
+                        Ok(())
+                    })),
+                    ..Default::default()
+                }))
+            })
+        };
+
+        let mut out = create_with_renewer(&conf, namenode, namenode_factory, 1, 1024)?;
+        out.write_all(&file_data(100))?;
+        out.hflush_impl()?;
+        assert_eq!(datanode.get_open_writes(), 1);
+        expired.store(true, Ordering::SeqCst);
+
+        let lease_renewer = out.dfs_client.get_lease_renewer();
+        wait_until(|| lease_renewer.get_abort_reason(out.file_id).is_some());
+        let reason = "Lease timeout of 0 seconds expired.";
+        assert_eq!(
+            lease_renewer.get_abort_reason(out.file_id).as_deref(),
+            Some(reason)
+        );
+        assert_eq!(out.check_closed().unwrap_err().to_string(), reason);
+        // the pipeline is closed without waiting for the next write
+        assert_eq!(out.streamer.check_closed().unwrap_err().to_string(), reason);
+        wait_until(|| datanode.get_open_writes() == 0);
+
+        assert!(out.write_all(&file_data(10)).is_err());
+        assert_eq!(out.close_impl().unwrap_err().to_string(), reason);
+        Ok(())
+    }
+}
@@ -0,0 +1,161 @@
+use super::protocol::datatransfer::{PacketHeader, PKT_MAX_HEADER_LEN};
+use std::{
+    fmt,
+    io::{self, Write},
+};
+
+/// The sequence number of a heartbeat packet.
+pub(crate) const HEART_BEAT_SEQNO: i64 = -1;
+
+/// DFSPacket is used by DataStreamer and DFSOutputStream.
+/// DFSOutputStream generates packets and then ask DataStreamer
+/// to send them to datanodes.
+pub(crate) struct DFSPacket {
+    // sequence number of buffer in block
+    seqno: i64,
+    // offset in block
+    offset_in_block: i64,
+    // is this a sync packet?
+    sync_block: bool,
+    // number of chunks currently in packet
+    num_chunks: usize,
+    // max chunks in packet
+    max_chunks: usize,
+    checksum: Vec<u8>,
+    data: Vec<u8>,
+    // is this the last packet in block?
+    last_packet_in_block: bool,
+}
+
+impl DFSPacket {
+    /// Create a new packet.
+    ///
+    /// `chunks_per_pkt` is the maximum number of chunks per packet,
+    /// `offset_in_block` the offset in bytes into the HDFS block and
+    /// `seqno` the sequence number of this packet.
+    pub fn new(
+        chunks_per_pkt: usize,
+        offset_in_block: i64,
+        seqno: i64,
+        last_packet_in_block: bool,
+    ) -> Self {
+        Self {
+            seqno,
+            offset_in_block,
+            sync_block: false,
+            num_chunks: 0,
+            max_chunks: chunks_per_pkt,
+            checksum: vec![],
+            data: vec![],
+            last_packet_in_block,
+        }
+    }
+
+    /// Write data to this packet.
+    pub fn write_data(&mut self, in_array: &[u8]) {
+        self.data.extend_from_slice(in_array);
+    }
+
+    /// Write checksums to this packet
+    pub fn write_checksum(&mut self, in_array: &[u8]) {
+        self.checksum.extend_from_slice(in_array);
+    }
+
+    /// Write the full packet, including the header, to the given output stream.
+    pub fn write_to(&self, out: &mut impl Write) -> io::Result<()> {
+        let data_len = self.data.len();
+        let checksum_len = self.checksum.len();
+        let pkt_len = 4 + checksum_len + data_len;
+        let header = PacketHeader::new(
+            pkt_len as i32,
+            self.offset_in_block,
+            self.seqno,
+            self.last_packet_in_block,
+            data_len as i32,
+            self.sync_block,
+        );
+
+        let mut buf = Vec::with_capacity(PKT_MAX_HEADER_LEN + checksum_len + data_len);
+        header.put_in_buffer(&mut buf);
+        buf.extend_from_slice(&self.checksum);
+        buf.extend_from_slice(&self.data);
+        out.write_all(&buf)?;
+        out.flush()
+    }
+
+    /// Get the packet's last byte's offset in the block
+    pub fn get_last_byte_offset_block(&self) -> i64 {
+        self.offset_in_block + self.data.len() as i64
+    }
+
+    /// Check if this packet is the last packet in block
+    pub fn is_last_packet_in_block(&self) -> bool {
+        self.last_packet_in_block
+    }
+
+    /// get sequence number of this packet
+    pub fn get_seqno(&self) -> i64 {
+        self.seqno
+    }
+
+    /// get the number of chunks this packet contains
+    pub fn get_num_chunks(&self) -> usize {
+        self.num_chunks
+    }
+
+    /// increase the number of chunks by one
+    pub fn inc_num_chunks(&mut self) {
+        self.num_chunks += 1;
+    }
+
+    /// get the maximum number of packets
+    pub fn get_max_chunks(&self) -> usize {
+        self.max_chunks
+    }
+
+    /// set if to sync block
+    pub fn set_sync_block(&mut self, sync_block: bool) {
+        self.sync_block = sync_block;
+    }
+}
+
+impl fmt::Display for DFSPacket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "packet seqno: {} offsetInBlock: {} lastPacketInBlock: {} lastByteOffsetInBlock: {}",
+            self.seqno,
+            self.offset_in_block,
+            self.last_packet_in_block,
+            self.get_last_byte_offset_block()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hdfs::hdfs::protocol::datatransfer::PacketReceiver;
+
+    #[test]
+    fn test_write_to() -> anyhow::Result<()> {
+        let mut packet = DFSPacket::new(2, 512, 7, false);
+        packet.write_checksum(&[1, 2, 3, 4]);
+        packet.write_data(b"data");
+        packet.inc_num_chunks();
+        packet.set_sync_block(true);
+        assert_eq!(1, packet.get_num_chunks());
+        assert_eq!(516, packet.get_last_byte_offset_block());
+
+        let mut buf = vec![];
+        packet.write_to(&mut buf)?;
+        let mut receiver = PacketReceiver::default();
+        receiver.receive_next_packet(&mut &buf[..])?;
+        let header = receiver.get_header().unwrap();
+        assert_eq!(7, header.get_seqno());
+        assert_eq!(512, header.get_offset_in_block());
+        assert_eq!(4, header.get_data_len());
+        assert_eq!(b"data", receiver.get_data_slice());
+        Ok(())
+    }
+}
@@ -1,9 +1,9 @@
-use super::protocol::hdfs_constants;
+use super::protocol::{hdfs_constants, LocatedBlock, LocatedBlocks};
 use crate::{
     common::{
         conf::Configuration,
-        fs::{file_system, path},
-        net::NetUtils,
+        fs::{file_system, path, BlockLocation},
+        net::{NetUtils, NodeBase},
         security::UserGroupInformation,
     },
     hdfs::hdfs::client::hdfs_client_config_keys,
@@ -21,8 +21,7 @@ impl DFSUtilClient {
             file_system::FS_DEFAULT_NAME_KEY,
             filesystem_uri
         )))?;
-        if !hdfs_constants::HDFS_URI_SCHEME.eq_ignore_ascii_case(&filesystem_uri.scheme().as_str())
-        {
+        if !hdfs_constants::HDFS_URI_SCHEME.eq_ignore_ascii_case(filesystem_uri.scheme().as_str()) {
             return Err(Error::msg(format!(
                 "Invalid URI for NameNode address (check {}): {} is not of scheme '{}'.",
                 file_system::FS_DEFAULT_NAME_KEY,
@@ -42,7 +41,8 @@ impl DFSUtilClient {
     /// [`hdfs_client_config_keys::DFS_USER_HOME_DIR_PREFIX_KEY`].
     /// User info is obtained from given [`UserGroupInformation`].
     pub fn get_home_directory(conf: Option<&Configuration>, ugi: &UserGroupInformation) -> String {
-        let mut user_home_prefix = hdfs_client_config_keys::DFS_USER_HOME_DIR_PREFIX_DEFAULT;
+        let mut user_home_prefix =
+            hdfs_client_config_keys::DFS_USER_HOME_DIR_PREFIX_DEFAULT.to_owned();
         if let Some(conf) = conf {
             user_home_prefix = conf
                 .get(
@@ -58,4 +58,53 @@ impl DFSUtilClient {
             ugi.get_short_user_name()
         )
     }
+
+    /// Convert a LocatedBlocks to BlockLocations[]
+    pub fn located_blocks2_locations(blocks: Option<&LocatedBlocks>) -> Vec<BlockLocation> {
+        match blocks {
+            None => vec![],
+            Some(blocks) => Self::located_blocks2_locations_list(blocks.get_located_blocks()),
+        }
+    }
+
+    /// Convert a List<LocatedBlock> to BlockLocation[]
+    pub fn located_blocks2_locations_list(blocks: &[LocatedBlock]) -> Vec<BlockLocation> {
+        blocks
+            .iter()
+            .map(|blk| {
+                let locations = blk.get_locations();
+                let hosts = locations
+                    .iter()
+                    .map(|l| l.get_host_name().to_owned())
+                    .collect();
+                let xfer_addrs = locations
+                    .iter()
+                    .map(|l| l.get_xfer_addr())
+                    .collect::<Vec<_>>();
+                let racks = locations
+                    .iter()
+                    .zip(&xfer_addrs)
+                    .map(|(l, xfer_addr)| {
+                        NodeBase::new(xfer_addr, l.get_network_location()).to_string()
+                    })
+                    .collect();
+                let cached_hosts = blk
+                    .get_cached_locations()
+                    .iter()
+                    .map(|l| l.get_host_name().to_owned())
+                    .collect();
+                BlockLocation::new(
+                    xfer_addrs,
+                    hosts,
+                    cached_hosts,
+                    racks,
+                    blk.get_storage_ids().to_vec(),
+                    blk.get_storage_types().to_vec(),
+                    blk.get_start_offset(),
+                    blk.get_block_size(),
+                    blk.is_corrupt(),
+                )
+            })
+            .collect()
+    }
 }
@@ -1,25 +1,36 @@
-use super::{protocol::hdfs_constants, DFSClient, DFSUtilClient};
+use super::{
+    protocol::{
+        hdfs_constants, BatchedDirectoryListing, DirectoryListing, HdfsFileStatus,
+        HdfsPartialListing, EMPTY_NAME,
+    },
+    DFSClient, DFSUtilClient, HdfsConfiguration,
+};
 use crate::common::{
     conf::Configuration,
-    fs::{permission::FsPermission, FileSystem, Path},
+    fs::{
+        options::Rename, permission::FsPermission, BlockLocation, ContentSummary, CreateFlag,
+        FSDataInputStream, FSDataOutputStream, FileChecksum, FileStatus, FileSystem, FsStatus,
+        LocatedFileStatus, PartialListing, Path, RemoteIterator,
+    },
+    io::IOException,
 };
 use anyhow::Error;
-use iref::{Iri, IriBuf, IriRefBuf};
-use std::str::FromStr;
+use iref::{Iri, IriBuf};
+use std::sync::Arc;
 
 pub(crate) struct DistributedFileSystem {
     conf: Configuration,
     working_dir: Path,
     uri: IriBuf,
-    dfs: DFSClient,
+    dfs: Arc<DFSClient>,
     resolve_symlinks: bool,
 }
 
 impl DistributedFileSystem {
     /// Checks that the passed URI belongs to this filesystem and returns
     /// just the path component. Expects a URI with an absolute path.
-    fn _get_path_name(&self, file: &Path) -> String {
-        file.to_uri().to_string()
+    fn get_path_name(&self, file: &Path) -> anyhow::Result<String> {
+        get_path_name(self.uri.as_iri(), file)
     }
 
     fn mkdirs_internal(
@@ -33,23 +44,53 @@ impl DistributedFileSystem {
         // TODO: FileSystemLinkResolver
 
         self.dfs
-            .mkdirs(abs_f.to_uri().path().as_str(), permission, create_parent)
+            .mkdirs(&self.get_path_name(&abs_f)?, permission, create_parent)
+    }
+
+    fn list_status_internal(&self, p: &Path) -> anyhow::Result<Vec<FileStatus>> {
+        let src = self.get_path_name(p)?;
+
+        // fetch the first batch of entries in the directory
+        let mut this_listing = self
+            .dfs
+            .list_paths(&src, EMPTY_NAME, false)?
+            .ok_or_else(|| file_not_found(format!("File {} does not exist.", p)))?;
+
+        // estimate the total number of entries in the directory
+        let total_num_entries = this_listing.get_partial_listing().len()
+            + this_listing.get_remaining_entries().max(0) as usize;
+        let mut listing = Vec::with_capacity(total_num_entries);
+        loop {
+            for file_status in this_listing.get_partial_listing() {
+                listing.push(file_status.make_qualified(self.get_uri(), p)?);
+            }
+            if !this_listing.has_more() {
+                // got all entries of the directory
+                return Ok(listing);
+            }
+
+            // the directory size is too big that it needs to fetch more
+            this_listing = self
+                .dfs
+                .list_paths(&src, this_listing.get_last_name(), false)?
+                // the directory is deleted
+                .ok_or_else(|| file_not_found(format!("File {} does not exist.", p)))?;
+        }
     }
 }
 
 impl FileSystem for DistributedFileSystem {
-    type FileSystemImpl = Self;
-
     fn new(uri: &Iri, conf: &Configuration) -> anyhow::Result<Self> {
+        HdfsConfiguration::init();
         let mut base_uri: IriBuf = IriBuf::from_scheme(uri.scheme().to_owned());
         base_uri.set_authority(Some(
             uri.authority()
                 .ok_or(Error::msg(format!("Incomplete HDFS URI, no host: {}", uri)))?,
         ));
-        let dfs = DFSClient::new(uri, conf)?;
+        let dfs = Arc::new(DFSClient::new(uri, conf)?);
         Ok(Self {
             conf: conf.to_owned(),
-            working_dir: get_home_directory(conf, &dfs)?,
+            working_dir: get_home_directory(base_uri.as_iri(), conf, &dfs)?,
             uri: base_uri,
             dfs,
             resolve_symlinks: Self::get_resolve_symlinks(conf),
@@ -73,7 +114,7 @@ impl FileSystem for DistributedFileSystem {
     }
 
     fn get_home_directory(&self) -> anyhow::Result<Path> {
-        get_home_directory(&self.conf, &self.dfs)
+        get_home_directory(self.get_uri(), &self.conf, &self.dfs)
     }
 
     /// Create a directory and its parent directories.
@@ -83,11 +124,1269 @@ impl FileSystem for DistributedFileSystem {
     fn mkdirs(&self, f: &Path, permission: Option<&FsPermission>) -> anyhow::Result<bool> {
         self.mkdirs_internal(f, permission, true)
     }
+
+    fn list_status_iterator(&self, p: &Path) -> anyhow::Result<RemoteIterator<'_, FileStatus>> {
+        let abs_f = self.fix_relative_part(p)?;
+        Ok(Box::new(DirListingIterator::new(
+            self,
+            abs_f,
+            false,
+            HdfsFileStatus::make_qualified,
+        )?))
+    }
+
+    /// The returned iterator fetches the entries of the directory in batches,
+    /// together with their block locations.
+    fn list_located_status(
+        &self,
+        p: &Path,
+    ) -> anyhow::Result<RemoteIterator<'_, LocatedFileStatus>> {
+        let abs_f = self.fix_relative_part(p)?;
+        Ok(Box::new(DirListingIterator::new(
+            self,
+            abs_f,
+            true,
+            HdfsFileStatus::make_qualified_located,
+        )?))
+    }
+
+    fn batched_list_status_iterator(
+        &self,
+        paths: &[Path],
+    ) -> anyhow::Result<RemoteIterator<'_, PartialListing<FileStatus>>> {
+        let abs_paths = paths
+            .iter()
+            .map(|p| self.fix_relative_part(p))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        Ok(Box::new(PartialListingIterator::new(
+            self,
+            abs_paths,
+            false,
+            HdfsFileStatus::make_qualified,
+        )?))
+    }
+
+    fn batched_list_located_status_iterator(
+        &self,
+        paths: &[Path],
+    ) -> anyhow::Result<RemoteIterator<'_, PartialListing<LocatedFileStatus>>> {
+        let abs_paths = paths
+            .iter()
+            .map(|p| self.fix_relative_part(p))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        Ok(Box::new(PartialListingIterator::new(
+            self,
+            abs_paths,
+            true,
+            HdfsFileStatus::make_qualified_located,
+        )?))
+    }
+
+    fn rename(&self, src: &Path, dst: &Path) -> anyhow::Result<bool> {
+        let abs_src = self.fix_relative_part(src)?;
+        let abs_dst = self.fix_relative_part(dst)?;
+
+        // TODO: resolve symlinks in the destination
+
+        self.dfs.rename(
+            &self.get_path_name(&abs_src)?,
+            &self.get_path_name(&abs_dst)?,
+        )
+    }
+
+    /// This rename operation is guaranteed to be atomic.
+    fn rename2(&self, src: &Path, dst: &Path, options: &[Rename]) -> anyhow::Result<()> {
+        let abs_src = self.fix_relative_part(src)?;
+        let abs_dst = self.fix_relative_part(dst)?;
+
+        // TODO: resolve symlinks in the destination
+
+        self.dfs.rename2(
+            &self.get_path_name(&abs_src)?,
+            &self.get_path_name(&abs_dst)?,
+            options,
+        )
+    }
+
+    fn open(&self, f: &Path) -> anyhow::Result<FSDataInputStream> {
+        let abs_f = self.fix_relative_part(f)?;
+
+        // TODO: FileSystemLinkResolver
+
+        let dfsis = self.dfs.open(&self.get_path_name(&abs_f)?, true)?;
+        Ok(FSDataInputStream::new(Box::new(dfsis)))
+    }
+
+    fn create(
+        &self,
+        f: &Path,
+        permission: Option<&FsPermission>,
+        overwrite: bool,
+        replication: Option<i16>,
+        block_size: Option<i64>,
+    ) -> anyhow::Result<FSDataOutputStream> {
+        let abs_f = self.fix_relative_part(f)?;
+        let flag = if overwrite {
+            vec![CreateFlag::Create, CreateFlag::Overwrite]
+        } else {
+            vec![CreateFlag::Create]
+        };
+
+        // TODO: FileSystemLinkResolver
+
+        let out = self.dfs.create(
+            &self.get_path_name(&abs_f)?,
+            permission,
+            &flag,
+            true,
+            replication.unwrap_or_else(|| self.get_default_replication(&abs_f)),
+            block_size.unwrap_or_else(|| self.get_default_block_size(&abs_f)),
+        )?;
+        Ok(FSDataOutputStream::new(Box::new(out), 0))
+    }
+
+    fn append(&self, f: &Path) -> anyhow::Result<FSDataOutputStream> {
+        self.append_with_new_block(f, false)
+    }
+
+    fn append_with_new_block(
+        &self,
+        f: &Path,
+        append_to_new_block: bool,
+    ) -> anyhow::Result<FSDataOutputStream> {
+        let abs_f = self.fix_relative_part(f)?;
+        let mut flag = vec![CreateFlag::Append];
+        if append_to_new_block {
+            flag.push(CreateFlag::NewBlock);
+        }
+
+        // TODO: FileSystemLinkResolver
+
+        let out = self.dfs.append(&self.get_path_name(&abs_f)?, &flag)?;
+        let initial_len = out.get_initial_len() as u64;
+        Ok(FSDataOutputStream::new(Box::new(out), initial_len))
+    }
+
+    /// Move blocks from srcs to trg and delete srcs afterwards.
+    /// The file block sizes must be the same.
+    fn concat(&self, trg: &Path, psrcs: &[Path]) -> anyhow::Result<()> {
+        // Make target absolute
+        let abs_f = self.fix_relative_part(trg)?;
+        // Make all srcs absolute and get their path names
+        let srcs = psrcs
+            .iter()
+            .map(|p| self.get_path_name(&self.fix_relative_part(p)?))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        // TODO: resolve symlinks in the target and sources
+
+        self.dfs.concat(&self.get_path_name(&abs_f)?, &srcs)
+    }
+
+    fn truncate(&self, f: &Path, new_length: i64) -> anyhow::Result<bool> {
+        let abs_f = self.fix_relative_part(f)?;
+
+        // TODO: FileSystemLinkResolver
+
+        self.dfs.truncate(&self.get_path_name(&abs_f)?, new_length)
+    }
+
+    fn delete(&self, f: &Path, recursive: bool) -> anyhow::Result<bool> {
+        let abs_f = self.fix_relative_part(f)?;
+
+        // TODO: FileSystemLinkResolver
+
+        self.dfs.delete(&self.get_path_name(&abs_f)?, recursive)
+    }
+
+    /// Returns the stat information about the file.
+    fn get_file_status(&self, f: &Path) -> anyhow::Result<FileStatus> {
+        let abs_f = self.fix_relative_part(f)?;
+
+        // TODO: FileSystemLinkResolver
+
+        match self.dfs.get_file_info(&self.get_path_name(&abs_f)?)? {
+            Some(fi) => fi.make_qualified(self.get_uri(), &abs_f),
+            None => Err(file_not_found(format!("File does not exist: {}", abs_f))),
+        }
+    }
+
+    /// Returns the stat information about the file, with the locations of its
+    /// blocks fetched from the NameNode in the same call.
+    fn get_located_file_status(&self, f: &Path) -> anyhow::Result<LocatedFileStatus> {
+        let abs_f = self.fix_relative_part(f)?;
+
+        // TODO: FileSystemLinkResolver
+
+        match self
+            .dfs
+            .get_located_file_info(&self.get_path_name(&abs_f)?, false)?
+        {
+            Some(fi) => fi.make_qualified_located(self.get_uri(), &abs_f),
+            None => Err(file_not_found(format!("File does not exist: {}", abs_f))),
+        }
+    }
+
+    /// The locations are those of the blocks of the file at the path of
+    /// `file`, as reported by the NameNode.
+    fn get_file_block_locations(
+        &self,
+        file: &FileStatus,
+        start: i64,
+        len: i64,
+    ) -> anyhow::Result<Vec<BlockLocation>> {
+        let abs_f = self.fix_relative_part(file.get_path())?;
+
+        // TODO: FileSystemLinkResolver
+
+        self.dfs
+            .get_file_block_locations(&self.get_path_name(&abs_f)?, start, len)
+    }
+
+    /// List all the entries of a directory.
+    ///
+    /// Will not return "." and ".." entries. The entries are fetched from
+    /// the NameNode in batches until the whole directory has been listed.
+    fn list_status(&self, p: &Path) -> anyhow::Result<Vec<FileStatus>> {
+        let abs_f = self.fix_relative_part(p)?;
+
+        // TODO: FileSystemLinkResolver
+
+        self.list_status_internal(&abs_f)
+    }
+
+    fn set_permission(&self, p: &Path, permission: &FsPermission) -> anyhow::Result<()> {
+        let abs_f = self.fix_relative_part(p)?;
+        self.dfs
+            .set_permission(&self.get_path_name(&abs_f)?, permission)
+    }
+
+    fn set_owner(
+        &self,
+        p: &Path,
+        username: Option<&str>,
+        groupname: Option<&str>,
+    ) -> anyhow::Result<()> {
+        if username.is_none() && groupname.is_none() {
+            return Err(Error::msg("username == null && groupname == null"));
+        }
+        let abs_f = self.fix_relative_part(p)?;
+        self.dfs
+            .set_owner(&self.get_path_name(&abs_f)?, username, groupname)
+    }
+
+    fn set_replication(&self, src: &Path, replication: i16) -> anyhow::Result<bool> {
+        let abs_f = self.fix_relative_part(src)?;
+        self.dfs
+            .set_replication(&self.get_path_name(&abs_f)?, replication)
+    }
+
+    fn set_times(&self, p: &Path, mtime: i64, atime: i64) -> anyhow::Result<()> {
+        let abs_f = self.fix_relative_part(p)?;
+        self.dfs
+            .set_times(&self.get_path_name(&abs_f)?, mtime, atime)
+    }
+
+    fn get_content_summary(&self, f: &Path) -> anyhow::Result<ContentSummary> {
+        let abs_f = self.fix_relative_part(f)?;
+        self.dfs.get_content_summary(&self.get_path_name(&abs_f)?)
+    }
+
+    fn get_file_checksum_with_length(
+        &self,
+        f: &Path,
+        length: i64,
+    ) -> anyhow::Result<Option<Box<dyn FileChecksum>>> {
+        let abs_f = self.fix_relative_part(f)?;
+
+        // TODO: FileSystemLinkResolver
+
+        self.dfs
+            .get_file_checksum_with_combine_mode(&self.get_path_name(&abs_f)?, length)
+    }
+
+    fn get_status(&self, _p: Option<&Path>) -> anyhow::Result<FsStatus> {
+        self.dfs.get_disk_status()
+    }
+
+    fn get_default_replication(&self, _path: &Path) -> i16 {
+        self.dfs.get_conf().get_default_replication()
+    }
+
+    fn get_default_block_size(&self, _f: &Path) -> i64 {
+        self.dfs.get_conf().get_default_block_size()
+    }
+}
+
+/// Qualifies an [`HdfsFileStatus`] listed under a parent path.
+type Qualifier<T> = fn(&HdfsFileStatus, &Iri, &Path) -> anyhow::Result<T>;
+
+/// This class defines an iterator that returns
+/// the file status of each file/subdirectory of a directory.
+///
+/// If `need_location` is set, the returned statuses carry their block
+/// locations. The directory is fetched from the NameNode in batches, on
+/// demand.
+struct DirListingIterator<'a, T> {
+    fs: &'a DistributedFileSystem,
+    this_listing: Option<DirectoryListing>,
+    i: usize,
+    p: Path,
+    src: String,
+    need_location: bool,
+    qualify: Qualifier<T>,
+}
+
+impl<'a, T> DirListingIterator<'a, T> {
+    fn new(
+        fs: &'a DistributedFileSystem,
+        p: Path,
+        need_location: bool,
+        qualify: Qualifier<T>,
+    ) -> anyhow::Result<Self> {
+        let src = fs.get_path_name(&p)?;
+        // fetch the first batch of entries in the directory
+        let this_listing = fs
+            .dfs
+            .list_paths(&src, EMPTY_NAME, need_location)?
+            // the directory does not exist
+            .ok_or_else(|| file_not_found(format!("File {} does not exist.", p)))?;
+        Ok(Self {
+            fs,
+            this_listing: Some(this_listing),
+            i: 0,
+            p,
+            src,
+            need_location,
+            qualify,
+        })
+    }
+
+    fn has_next_no_filter(&mut self) -> anyhow::Result<bool> {
+        let this_listing = match &self.this_listing {
+            Some(this_listing) => this_listing,
+            None => return Ok(false),
+        };
+        if self.i >= this_listing.get_partial_listing().len() && this_listing.has_more() {
+            // current listing is exhausted & fetch a new listing
+            let next_listing = self
+                .fs
+                .dfs
+                .list_paths(&self.src, this_listing.get_last_name(), self.need_location)?
+                .ok_or_else(|| file_not_found(format!("File {} does not exist.", self.p)))?;
+            self.this_listing = Some(next_listing);
+            self.i = 0;
+        }
+        Ok(self
+            .this_listing
+            .as_ref()
+            .is_some_and(|l| self.i < l.get_partial_listing().len()))
+    }
+}
+
+impl<T> Iterator for DirListingIterator<'_, T> {
+    type Item = anyhow::Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.has_next_no_filter() {
+            Ok(true) => {}
+            Ok(false) => return None,
+            Err(e) => {
+                // stop listing once the directory has gone away
+                self.this_listing = None;
+                return Some(Err(e));
+            }
+        }
+        let file_stat = &self.this_listing.as_ref()?.get_partial_listing()[self.i];
+        self.i += 1;
+        Some((self.qualify)(file_stat, self.fs.get_uri(), &self.p))
+    }
+}
+
+/// An iterator over the [`PartialListing`]s of a batched listing of paths.
+/// Further batches are fetched from the NameNode on demand.
+struct PartialListingIterator<'a, T> {
+    fs: &'a DistributedFileSystem,
+    paths: Vec<Path>,
+    srcs: Vec<String>,
+    need_location: bool,
+    batched_listing: Option<BatchedDirectoryListing>,
+    listing_idx: usize,
+    qualify: Qualifier<T>,
+}
+
+impl<'a, T> PartialListingIterator<'a, T> {
+    fn new(
+        fs: &'a DistributedFileSystem,
+        paths: Vec<Path>,
+        need_location: bool,
+        qualify: Qualifier<T>,
+    ) -> anyhow::Result<Self> {
+        let srcs = paths
+            .iter()
+            .map(|p| fs.get_path_name(p))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        // Do the first listing
+        let batched_listing = if srcs.is_empty() {
+            None
+        } else {
+            Some(
+                fs.dfs
+                    .batched_list_paths(&srcs, EMPTY_NAME, need_location)?,
+            )
+        };
+        Ok(Self {
+            fs,
+            paths,
+            srcs,
+            need_location,
+            batched_listing,
+            listing_idx: 0,
+            qualify,
+        })
+    }
+
+    fn has_next(&mut self) -> anyhow::Result<bool> {
+        let batched_listing = match &self.batched_listing {
+            Some(batched_listing) => batched_listing,
+            None => return Ok(false),
+        };
+        // If we're done with the current batch, try to get the next batch
+        if self.listing_idx >= batched_listing.get_listings().len() {
+            if !batched_listing.has_more() {
+                log::trace!("No more elements");
+                return Ok(false);
+            }
+            let next_listing = self.fs.dfs.batched_list_paths(
+                &self.srcs,
+                batched_listing.get_start_after(),
+                self.need_location,
+            )?;
+            self.batched_listing = Some(next_listing);
+            self.listing_idx = 0;
+        }
+        Ok(self
+            .batched_listing
+            .as_ref()
+            .is_some_and(|l| self.listing_idx < l.get_listings().len()))
+    }
+
+    fn qualify_listing(&self, listing: &HdfsPartialListing) -> anyhow::Result<PartialListing<T>> {
+        let parent = self.paths.get(listing.get_parent_idx()).ok_or_else(|| {
+            Error::msg(format!("Invalid parent index {}", listing.get_parent_idx()))
+        })?;
+        if let Some(exception) = listing.get_exception() {
+            let e = exception.clone().unwrap_remote_exception();
+            return Ok(PartialListing::with_exception(parent.clone(), e));
+        }
+        // Qualify paths for the client.
+        let statuses = listing
+            .get_partial_listing()
+            .iter()
+            .map(|status| (self.qualify)(status, self.fs.get_uri(), parent))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        Ok(PartialListing::new(parent.clone(), statuses))
+    }
+}
+
+impl<T> Iterator for PartialListingIterator<'_, T> {
+    type Item = anyhow::Result<PartialListing<T>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.has_next() {
+            Ok(true) => {}
+            Ok(false) => return None,
+            Err(e) => {
+                self.batched_listing = None;
+                return Some(Err(e));
+            }
+        }
+        let listing = &self.batched_listing.as_ref()?.get_listings()[self.listing_idx];
+        self.listing_idx += 1;
+        Some(self.qualify_listing(listing))
+    }
+}
+
+/// Checks that the passed URI belongs to the filesystem at `uri` and returns
+/// just the path component. Expects a URI with an absolute path.
+pub(super) fn get_path_name(uri: &Iri, file: &Path) -> anyhow::Result<String> {
+    check_path(uri, file)?;
+    let result = file.get_uri_path();
+    if !file.is_uri_path_absolute() {
+        return Err(Error::msg(format!(
+            "Pathname {} from {} is not a valid DFS filename.",
+            result, file
+        )));
+    }
+    Ok(result)
 }
 
-fn get_home_directory(conf: &Configuration, dfs: &DFSClient) -> anyhow::Result<Path> {
-    // TODO: refactor path qualification
-    Ok(Path::from(IriRefBuf::from_str(
-        &DFSUtilClient::get_home_directory(Some(conf), &dfs.ugi),
-    )?))
+/// Check that a Path belongs to the filesystem at `uri`.
+fn check_path(uri: &Iri, path: &Path) -> anyhow::Result<()> {
+    let scheme = match path.get_scheme() {
+        // fs is relative
+        None => return Ok(()),
+        Some(scheme) => scheme,
+    };
+    let this_scheme = uri.scheme().as_str();
+    // schemes match
+    if scheme.eq_ignore_ascii_case(this_scheme) {
+        let this_authority = uri.authority().map(|a| a.as_str());
+        match path.get_authority() {
+            // path's authority is null; fs uses its default
+            None => return Ok(()),
+            // authorities match
+            Some(authority)
+                if this_authority.is_some_and(|a| a.eq_ignore_ascii_case(authority)) =>
+            {
+                return Ok(())
+            }
+            _ => {}
+        }
+    }
+    Err(Error::msg(format!("Wrong FS: {}, expected: {}", path, uri)))
+}
+
+/// The error of a path that does not exist on the NameNode.
+pub(super) fn file_not_found(msg: String) -> Error {
+    Error::new(IOException::FileNotFound(msg))
+}
+
+fn get_home_directory(uri: &Iri, conf: &Configuration, dfs: &DFSClient) -> anyhow::Result<Path> {
+    Path::new(&DFSUtilClient::get_home_directory(Some(conf), &dfs.ugi))?
+        .make_qualified(uri, &Path::new("/")?)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::common::ipc::RemoteException;
+    use crate::hdfs::{
+        hdfs::protocol::{
+            datatransfer::FakeDataNode, LastBlockWithStatus, LocatedBlock, LocatedBlocks,
+            MockClientProtocol,
+        },
+        hdfs_file_status_proto::FileType,
+        op_write_block_proto::BlockConstructionStage,
+        BatchedDirectoryListingProto, DatanodeIdProto, DatanodeInfoProto, DirectoryListingProto,
+        ExtendedBlockProto, GetBatchedListingResponseProto, HdfsFileStatusProto, LocatedBlockProto,
+        LocatedBlocksProto, RemoteExceptionProto,
+    };
+    use std::{
+        collections::HashMap,
+        io::{Read, Write},
+        sync::{Arc, Mutex},
+    };
+
+    const BLOCK_SIZE: u64 = 128;
+
+    /// The blocks of the files written through a [`fake_namenode`] by path.
+    pub(crate) type FakeNamespace = Arc<Mutex<HashMap<String, Vec<LocatedBlockProto>>>>;
+
+    /// Create a DistributedFileSystem for `hdfs://nn:8020` whose NameNode is
+    /// `namenode`.
+    pub(crate) fn create_dfs(
+        namenode: MockClientProtocol,
+    ) -> anyhow::Result<DistributedFileSystem> {
+        let conf = Configuration::new();
+        let dfs = Arc::new(DFSClient::with_rpc_namenode(
+            &conf,
+            Box::new(namenode),
+            Arc::new(|| Ok(Box::<MockClientProtocol>::default())),
+        )?);
+        let uri = IriBuf::new("hdfs://nn:8020".to_owned())?;
+        Ok(DistributedFileSystem {
+            working_dir: get_home_directory(uri.as_iri(), &conf, &dfs)?,
+            resolve_symlinks: DistributedFileSystem::get_resolve_symlinks(&conf),
+            conf,
+            uri,
+            dfs,
+        })
+    }
+
+    /// The blocks of a file of `len` bytes, in blocks of `block_size` bytes
+    /// stored on `dn1`.
+    pub(crate) fn located_blocks(len: u64, block_size: u64) -> LocatedBlocksProto {
+        let blocks = (0..len)
+            .step_by(block_size as usize)
+            .enumerate()
+            .map(|(i, offset)| LocatedBlockProto {
+                b: ExtendedBlockProto {
+                    pool_id: "BP-1".to_owned(),
+                    block_id: 1073741825 + i as u64,
+                    generation_stamp: 1001,
+                    num_bytes: Some(block_size.min(len - offset)),
+                },
+                offset,
+                locs: vec![DatanodeInfoProto {
+                    id: DatanodeIdProto {
+                        ip_addr: "10.0.0.1".to_owned(),
+                        host_name: "dn1".to_owned(),
+                        xfer_port: 9866,
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                }],
+                ..Default::default()
+            })
+            .collect::<Vec<_>>();
+        LocatedBlocksProto {
+            file_length: len,
+            last_block: blocks.last().cloned(),
+            blocks,
+            is_last_block_complete: true,
+            ..Default::default()
+        }
+    }
+
+    /// The status of a file named `name` of `len` bytes, with its block
+    /// locations if `need_location`.
+    pub(crate) fn file_status(name: &str, len: u64, need_location: bool) -> HdfsFileStatusProto {
+        HdfsFileStatusProto {
+            file_type: FileType::IsFile as i32,
+            path: name.as_bytes().to_vec(),
+            length: len,
+            blocksize: Some(BLOCK_SIZE),
+            locations: need_location.then(|| located_blocks(len, BLOCK_SIZE)),
+            ..Default::default()
+        }
+    }
+
+    /// Set the length of the last block of `src` to `len`.
+    fn set_last_block_length(
+        namespace: &mut HashMap<String, Vec<LocatedBlockProto>>,
+        src: &str,
+        len: i64,
+    ) {
+        if let Some(last) = namespace.get_mut(src).and_then(|blocks| blocks.last_mut()) {
+            last.b.num_bytes = Some(len as u64);
+        }
+    }
+
+    /// A NameNode recording the files written through it in `namespace`,
+    /// placing their blocks on the DataNodes of `pipeline`.
+    pub(crate) fn fake_namenode(
+        namespace: FakeNamespace,
+        pipeline: Vec<DatanodeInfoProto>,
+    ) -> MockClientProtocol {
+        MockClientProtocol {
+            create: Some(Box::new({
+                let namespace = Arc::clone(&namespace);
+                move |src, _, _, _, _, replication, block_size| {
+                    namespace.lock().unwrap().insert(src.to_owned(), vec![]);
+                    Ok(Some(HdfsFileStatus::from(&HdfsFileStatusProto {
+                        file_type: FileType::IsFile as i32,
+                        block_replication: Some(replication as u32),
+                        blocksize: Some(block_size as u64),
+                        file_id: Some(16386),
+                        ..Default::default()
+                    })))
+                }
+            })),
+            append: Some(Box::new({
+                let namespace = Arc::clone(&namespace);
+                move |src, _, flag| {
+                    let namespace = namespace.lock().unwrap();
+                    let blocks = namespace.get(src).ok_or_else(|| {
+                        remote_exception(
+                            "java.io.FileNotFoundException",
+                            &format!("File does not exist: {}", src),
+                        )
+                    })?;
+                    let len = blocks.iter().filter_map(|b| b.b.num_bytes).sum();
+                    // the files appended to by the tests have blocks of 1024 bytes
+                    let block_size = 1024;
+                    // the last block is appended to unless it is full or a
+                    // new block is asked for
+                    let last_block = blocks
+                        .last()
+                        .filter(|b| b.b.num_bytes.unwrap_or_default() < block_size)
+                        .filter(|_| !flag.contains(&CreateFlag::NewBlock));
+                    Ok(LastBlockWithStatus::new(
+                        last_block.map(LocatedBlock::from),
+                        Some(HdfsFileStatus::from(&HdfsFileStatusProto {
+                            file_type: FileType::IsFile as i32,
+                            length: len,
+                            blocksize: Some(block_size),
+                            file_id: Some(16386),
+                            ..Default::default()
+                        })),
+                    ))
+                }
+            })),
+            add_block: Some(Box::new({
+                let namespace = Arc::clone(&namespace);
+                move |src, _, previous, exclude_nodes, _| {
+                    let mut namespace = namespace.lock().unwrap();
+                    if let Some(previous) = previous {
+                        set_last_block_length(&mut namespace, src, previous.get_num_bytes());
+                    }
+                    let blocks = namespace
+                        .get_mut(src)
+                        .ok_or_else(|| Error::msg("Lease mismatch"))?;
+                    let block = LocatedBlockProto {
+                        b: ExtendedBlockProto {
+                            pool_id: "BP-1".to_owned(),
+                            block_id: 1073741825 + blocks.len() as u64,
+                            generation_stamp: 1001,
+                            num_bytes: Some(0),
+                        },
+                        offset: blocks.iter().filter_map(|b| b.b.num_bytes).sum(),
+                        locs: pipeline
+                            .iter()
+                            .filter(|dn| {
+                                !exclude_nodes
+                                    .iter()
+                                    .any(|e| e.get_xfer_port() as u32 == dn.id.xfer_port)
+                            })
+                            .cloned()
+                            .collect(),
+                        ..Default::default()
+                    };
+                    blocks.push(block.clone());
+                    Ok(LocatedBlock::from(&block))
+                }
+            })),
+            complete: Some(Box::new({
+                let namespace = Arc::clone(&namespace);
+                move |src, _, last, _| {
+                    if let Some(last) = last {
+                        set_last_block_length(
+                            &mut namespace.lock().unwrap(),
+                            src,
+                            last.get_num_bytes(),
+                        );
+                    }
+                    Ok(true)
+                }
+            })),
+            fsync: Some(Box::new({
+                let namespace = Arc::clone(&namespace);
+                move |src, _, _, last_block_length| {
+                    if last_block_length >= 0 {
+                        set_last_block_length(
+                            &mut namespace.lock().unwrap(),
+                            src,
+                            last_block_length,
+                        );
+                    }
+                    Ok(())
+                }
+            })),
+            get_block_locations: Some(Box::new({
+                let namespace = Arc::clone(&namespace);
+                move |src, offset, length| {
+                    let namespace = namespace.lock().unwrap();
+                    let Some(blocks) = namespace.get(src) else {
+                        return Ok(None);
+                    };
+                    let end = (offset + length) as u64;
+                    Ok(Some(LocatedBlocks::from(&LocatedBlocksProto {
+                        file_length: blocks.iter().filter_map(|b| b.b.num_bytes).sum(),
+                        blocks: blocks
+                            .iter()
+                            .filter(|b| {
+                                b.offset < end
+                                    && b.offset + b.b.num_bytes.unwrap_or_default() > offset as u64
+                            })
+                            .cloned()
+                            .collect(),
+                        is_last_block_complete: true,
+                        ..Default::default()
+                    })))
+                }
+            })),
+            update_block_for_pipeline: Some(Box::new(|block, _| {
+                let mut block = block.to_owned();
+                block.set_generation_stamp(block.get_generation_stamp() + 1);
+                Ok(LocatedBlock::from(&LocatedBlockProto {
+                    b: (&block).into(),
+                    ..Default::default()
+                }))
+            })),
+            update_pipeline: Some(Box::new({
+                let namespace = Arc::clone(&namespace);
+                move |_, old_block, new_block, new_nodes, _| {
+                    let mut namespace = namespace.lock().unwrap();
+                    let block = namespace
+                        .values_mut()
+                        .flatten()
+                        .find(|b| b.b.block_id as i64 == old_block.get_block_id())
+                        .ok_or_else(|| Error::msg(format!("{} does not exist", old_block)))?;
+                    block.b.generation_stamp = new_block.get_generation_stamp() as u64;
+                    block.locs = new_nodes.iter().map(|dn| dn.into()).collect();
+                    Ok(())
+                }
+            })),
+            renew_lease: Some(Box::new(|_| Ok(()))),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_create_and_open_round_trip() -> anyhow::Result<()> {
+        let datanode = FakeDataNode::start()?;
+        let namespace = FakeNamespace::default();
+        let fs = create_dfs(fake_namenode(
+            Arc::clone(&namespace),
+            vec![datanode.get_datanode_info()],
+        ))?;
+        // three blocks, the last one partial
+        let data = (0..2500).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+        let f = Path::new("/d/f")?;
+
+        let mut out = fs.create(&f, None, true, Some(1), Some(1024))?;
+        out.write_all(&data)?;
+        out.close()?;
+
+        let blocks = namespace.lock().unwrap()["/d/f"].clone();
+        let lengths = blocks.iter().map(|b| b.b.num_bytes).collect::<Vec<_>>();
+        assert_eq!(lengths, [Some(1024), Some(1024), Some(452)]);
+        for block in &blocks {
+            let offset = block.offset as usize;
+            let len = block.b.num_bytes.unwrap_or_default() as usize;
+            assert_eq!(
+                datanode.get_block(block.b.block_id).as_deref(),
+                Some(&data[offset..offset + len])
+            );
+        }
+
+        let mut input = fs.open(&f)?;
+        let mut read = vec![];
+        input.read_to_end(&mut read)?;
+        assert_eq!(read, data);
+        // read across the boundary of the first two blocks
+        let mut buf = [0; 100];
+        input.read_fully(1000, &mut buf)?;
+        assert_eq!(buf, data[1000..1100]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_located_file_status() -> anyhow::Result<()> {
+        let fs = create_dfs(MockClientProtocol {
+            get_located_file_info: Some(Box::new(|src, need_block_token| {
+                assert!(!need_block_token);
+                Ok((src == "/d/f").then(|| HdfsFileStatus::from(&file_status("", 300, true))))
+            })),
+            ..Default::default()
+        })?;
+
+        let status = fs.get_located_file_status(&Path::new("/d/f")?)?;
+        assert_eq!(status.get_path().to_string(), "hdfs://nn:8020/d/f");
+        assert_eq!(status.get_len(), 300);
+        let locations = status.get_block_locations();
+        assert_eq!(locations.len(), 3);
+        assert_eq!(locations[2].get_hosts(), ["dn1"]);
+        assert_eq!(
+            (locations[2].get_offset(), locations[2].get_length()),
+            (256, 44)
+        );
+
+        let err = fs
+            .get_located_file_status(&Path::new("/d/missing")?)
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<IOException>(),
+            Some(IOException::FileNotFound(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_file_block_locations() -> anyhow::Result<()> {
+        let fs = create_dfs(MockClientProtocol {
+            get_block_locations: Some(Box::new(|src, offset, length| {
+                assert_eq!((offset, length), (100, 200));
+                Ok((src == "/d/f").then(|| LocatedBlocks::from(&located_blocks(300, BLOCK_SIZE))))
+            })),
+            ..Default::default()
+        })?;
+
+        let status = HdfsFileStatus::from(&file_status("", 300, false))
+            .make_qualified(fs.get_uri(), &Path::new("/d/f")?)?;
+        let locations = fs.get_file_block_locations(&status, 100, 200)?;
+        assert_eq!(locations.len(), 3);
+        assert_eq!(locations[0].get_names(), ["10.0.0.1:9866"]);
+        assert_eq!(locations[0].get_hosts(), ["dn1"]);
+        assert_eq!(
+            (locations[1].get_offset(), locations[1].get_length()),
+            (128, 128)
+        );
+
+        // the file has gone away
+        let status = HdfsFileStatus::from(&file_status("", 300, false))
+            .make_qualified(fs.get_uri(), &Path::new("/d/gone")?)?;
+        assert!(fs.get_file_block_locations(&status, 100, 200)?.is_empty());
+        Ok(())
+    }
+
+    /// A NameNode failure of Java class `class_name`.
+    pub(crate) fn remote_exception(class_name: &str, msg: &str) -> Error {
+        Error::new(RemoteException::new(class_name, msg))
+    }
+
+    #[test]
+    fn test_delete_remote_exceptions() -> anyhow::Result<()> {
+        let fs = create_dfs(MockClientProtocol {
+            delete: Some(Box::new(|src, recursive| match src {
+                "/d/f" => Ok(!recursive),
+                "/d/missing" => Err(remote_exception(
+                    "java.io.FileNotFoundException",
+                    "File does not exist: /d/missing",
+                )),
+                _ => Err(remote_exception(
+                    "org.apache.hadoop.fs.PathIsNotEmptyDirectoryException",
+                    "`/d is non empty': Directory is not empty",
+                )),
+            })),
+            ..Default::default()
+        })?;
+
+        assert!(fs.delete(&Path::new("/d/f")?, false)?);
+        let err = fs.delete(&Path::new("/d/missing")?, false).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<IOException>(),
+            Some(IOException::FileNotFound(msg)) if msg == "File does not exist: /d/missing"
+        ));
+        let err = fs.delete(&Path::new("/d")?, false).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<IOException>(),
+            Some(IOException::PathIsNotEmptyDirectory(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_rename_remote_exceptions() -> anyhow::Result<()> {
+        let fs = create_dfs(MockClientProtocol {
+            rename: Some(Box::new(|src, dst| match (src, dst) {
+                ("/d/f", "/d/g") => Ok(true),
+                ("/d/missing", _) => Err(remote_exception(
+                    "java.io.FileNotFoundException",
+                    "rename source /d/missing is not found.",
+                )),
+                _ => Err(remote_exception(
+                    "org.apache.hadoop.fs.ParentNotDirectoryException",
+                    "rename destination parent /d/f is a file.",
+                )),
+            })),
+            ..Default::default()
+        })?;
+
+        assert!(fs.rename(&Path::new("/d/f")?, &Path::new("/d/g")?)?);
+        let err = fs
+            .rename(&Path::new("/d/missing")?, &Path::new("/d/g")?)
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<IOException>(),
+            Some(IOException::FileNotFound(_))
+        ));
+        let err = fs
+            .rename(&Path::new("/d/g")?, &Path::new("/d/f/g")?)
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<IOException>(),
+            Some(IOException::ParentNotDirectory(msg))
+                if msg == "rename destination parent /d/f is a file."
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_rename2_remote_exceptions() -> anyhow::Result<()> {
+        let fs = create_dfs(MockClientProtocol {
+            rename2: Some(Box::new(|src, dst, options| {
+                assert_eq!(src, "/d/f");
+                match options {
+                    [Rename::Overwrite] => Ok(()),
+                    _ => Err(remote_exception(
+                        "org.apache.hadoop.fs.FileAlreadyExistsException",
+                        &format!("rename destination {} already exists", dst),
+                    )),
+                }
+            })),
+            ..Default::default()
+        })?;
+
+        let (src, dst) = (Path::new("/d/f")?, Path::new("/d/g")?);
+        fs.rename2(&src, &dst, &[Rename::Overwrite])?;
+        let err = fs.rename2(&src, &dst, &[Rename::None]).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<IOException>(),
+            Some(IOException::FileAlreadyExists(msg))
+                if msg == "rename destination /d/g already exists"
+        ));
+        Ok(())
+    }
+
+    /// A NameNode listing the files `f0` to `f4` of `/d` two at a time,
+    /// recording the names the listings start after.
+    pub(crate) fn paged_namenode(start_afters: Arc<Mutex<Vec<String>>>) -> MockClientProtocol {
+        MockClientProtocol {
+            get_listing: Some(Box::new(move |src, start_after, need_location| {
+                if src != "/d" {
+                    return Ok(None);
+                }
+                start_afters
+                    .lock()
+                    .unwrap()
+                    .push(String::from_utf8(start_after.to_vec())?);
+                let names = ["f0", "f1", "f2", "f3", "f4"];
+                let start = names.partition_point(|name| name.as_bytes() <= start_after);
+                let end = (start + 2).min(names.len());
+                Ok(Some(DirectoryListing::from(&DirectoryListingProto {
+                    partial_listing: names[start..end]
+                        .iter()
+                        .map(|name| file_status(name, 300, need_location))
+                        .collect(),
+                    remaining_entries: (names.len() - end) as u32,
+                })))
+            })),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_list_status_pages() -> anyhow::Result<()> {
+        let start_afters = Arc::new(Mutex::new(vec![]));
+        let fs = create_dfs(paged_namenode(Arc::clone(&start_afters)))?;
+        let names = |statuses: Vec<FileStatus>| {
+            statuses
+                .iter()
+                .map(|status| status.get_path().get_name())
+                .collect::<Vec<_>>()
+        };
+
+        let statuses = fs
+            .list_status_iterator(&Path::new("/d")?)?
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        assert_eq!(names(statuses), ["f0", "f1", "f2", "f3", "f4"]);
+        assert_eq!(*start_afters.lock().unwrap(), ["", "f1", "f3"]);
+
+        start_afters.lock().unwrap().clear();
+        let statuses = fs.list_status(&Path::new("/d")?)?;
+        assert_eq!(names(statuses), ["f0", "f1", "f2", "f3", "f4"]);
+        assert_eq!(*start_afters.lock().unwrap(), ["", "f1", "f3"]);
+
+        let statuses = fs
+            .list_located_status(&Path::new("/d")?)?
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        assert_eq!(statuses.len(), 5);
+        assert_eq!(statuses[4].get_path().to_string(), "hdfs://nn:8020/d/f4");
+        assert_eq!(statuses[4].get_block_locations().len(), 3);
+
+        let err = fs.list_status_iterator(&Path::new("/missing")?).err();
+        assert!(matches!(
+            err.as_ref().and_then(|e| e.downcast_ref::<IOException>()),
+            Some(IOException::FileNotFound(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_batched_listing_with_errors() -> anyhow::Result<()> {
+        let fs = create_dfs(MockClientProtocol {
+            get_batched_listing: Some(Box::new(|srcs, start_after, need_location| {
+                assert_eq!(srcs, ["/a", "/missing", "/b"]);
+                let listing = |parent_idx, names: &[&str]| BatchedDirectoryListingProto {
+                    partial_listing: names
+                        .iter()
+                        .map(|name| file_status(name, 300, need_location))
+                        .collect(),
+                    parent_idx,
+                    exception: None,
+                };
+                let res = match start_after {
+                    b"" => GetBatchedListingResponseProto {
+                        listings: vec![
+                            listing(0, &["f0", "f1"]),
+                            BatchedDirectoryListingProto {
+                                exception: Some(RemoteExceptionProto {
+                                    class_name: "java.io.FileNotFoundException".to_owned(),
+                                    message: Some("File /missing does not exist.".to_owned()),
+                                }),
+                                ..listing(1, &[])
+                            },
+                        ],
+                        has_more: true,
+                        start_after: b"/missing".to_vec(),
+                    },
+                    b"/missing" => GetBatchedListingResponseProto {
+                        listings: vec![listing(2, &["g0"])],
+                        has_more: false,
+                        start_after: b"/b/g0".to_vec(),
+                    },
+                    _ => return Err(Error::msg("Unexpected start after")),
+                };
+                Ok(BatchedDirectoryListing::from(&res))
+            })),
+            ..Default::default()
+        })?;
+
+        let paths = [Path::new("/a")?, Path::new("/missing")?, Path::new("/b")?];
+        let mut listings = fs.batched_list_located_status_iterator(&paths)?;
+
+        let listing = listings.next().unwrap()?;
+        assert_eq!(listing.get_listed_path().to_string(), "/a");
+        let statuses = listing.get()?;
+        assert_eq!(statuses.len(), 2);
+        assert_eq!(statuses[1].get_path().to_string(), "hdfs://nn:8020/a/f1");
+        assert_eq!(statuses[1].get_block_locations().len(), 3);
+
+        let listing = listings.next().unwrap()?;
+        assert_eq!(listing.get_listed_path().to_string(), "/missing");
+        let err = listing.get().unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<IOException>(),
+            Some(IOException::FileNotFound(msg)) if msg == "File /missing does not exist."
+        ));
+
+        let listing = listings.next().unwrap()?;
+        assert_eq!(listing.get_listed_path().to_string(), "/b");
+        assert_eq!(
+            listing.get()?[0].get_path().to_string(),
+            "hdfs://nn:8020/b/g0"
+        );
+        assert!(listings.next().is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_truncate() -> anyhow::Result<()> {
+        let fs = create_dfs(MockClientProtocol {
+            truncate: Some(Box::new(|src, new_length, _| {
+                assert_eq!(src, "/d/f");
+                // a block cut short needs to be recovered before the file can
+                // be written again
+                Ok((new_length as u64).is_multiple_of(BLOCK_SIZE))
+            })),
+            ..Default::default()
+        })?;
+
+        let f = Path::new("/d/f")?;
+        assert!(fs.truncate(&f, 256)?);
+        assert!(!fs.truncate(&f, 300)?);
+        let err = fs.truncate(&f, -1).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Cannot truncate to a negative file size: -1."
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_concat() -> anyhow::Result<()> {
+        let calls = Arc::new(Mutex::new(vec![]));
+        let fs = create_dfs(MockClientProtocol {
+            concat: Some(Box::new({
+                let calls = Arc::clone(&calls);
+                move |trg, srcs| {
+                    calls.lock().unwrap().push((trg.to_owned(), srcs.to_vec()));
+                    Ok(())
+                }
+            })),
+            ..Default::default()
+        })?;
+
+        // relative paths are resolved against the working directory
+        fs.concat(
+            &Path::new("/d/f")?,
+            &[Path::new("hdfs://nn:8020/d/g")?, Path::new("h")?],
+        )?;
+        let home = fs.get_working_directory().get_uri_path();
+        assert_eq!(
+            *calls.lock().unwrap(),
+            [(
+                "/d/f".to_owned(),
+                vec!["/d/g".to_owned(), format!("{}/h", home)]
+            )]
+        );
+        // sources of another filesystem are rejected
+        assert!(fs
+            .concat(&Path::new("/d/f")?, &[Path::new("hdfs://nn2:8020/d/g")?])
+            .is_err());
+        assert_eq!(calls.lock().unwrap().len(), 1);
+        Ok(())
+    }
+
+    /// Write a file of 700 bytes through `fs`, returning its contents.
+    fn write_partial_block(fs: &DistributedFileSystem, f: &Path) -> anyhow::Result<Vec<u8>> {
+        let data = (0..700).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+        let mut out = fs.create(f, None, true, Some(1), Some(1024))?;
+        out.write_all(&data)?;
+        out.close()?;
+        Ok(data)
+    }
+
+    #[test]
+    fn test_append_to_last_block() -> anyhow::Result<()> {
+        let datanode = FakeDataNode::start()?;
+        let namespace = FakeNamespace::default();
+        let fs = create_dfs(fake_namenode(
+            Arc::clone(&namespace),
+            vec![datanode.get_datanode_info()],
+        ))?;
+        let f = Path::new("/d/f")?;
+        let mut data = write_partial_block(&fs, &f)?;
+
+        let mut out = fs.append(&f)?;
+        assert_eq!(out.get_pos(), 700);
+        let more = [7; 300];
+        out.write_all(&more)?;
+        out.close()?;
+        data.extend_from_slice(&more);
+
+        // the last block is reopened with a new generation stamp
+        assert_eq!(
+            datanode.get_write_stages(),
+            [
+                BlockConstructionStage::PipelineSetupCreate,
+                BlockConstructionStage::PipelineSetupAppend
+            ]
+        );
+        let blocks = namespace.lock().unwrap()["/d/f"].clone();
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].b.num_bytes, Some(1000));
+        assert_eq!(blocks[0].b.generation_stamp, 1002);
+        assert_eq!(datanode.get_block(blocks[0].b.block_id), Some(data));
+        Ok(())
+    }
+
+    #[test]
+    fn test_append_to_new_block() -> anyhow::Result<()> {
+        let datanode = FakeDataNode::start()?;
+        let namespace = FakeNamespace::default();
+        let fs = create_dfs(fake_namenode(
+            Arc::clone(&namespace),
+            vec![datanode.get_datanode_info()],
+        ))?;
+        let f = Path::new("/d/f")?;
+        let data = write_partial_block(&fs, &f)?;
+
+        let mut out = fs.append_with_new_block(&f, true)?;
+        assert_eq!(out.get_pos(), 700);
+        let more = [7; 300];
+        out.write_all(&more)?;
+        out.close()?;
+
+        // the partial block is left as is
+        assert_eq!(
+            datanode.get_write_stages(),
+            [
+                BlockConstructionStage::PipelineSetupCreate,
+                BlockConstructionStage::PipelineSetupCreate
+            ]
+        );
+        let blocks = namespace.lock().unwrap()["/d/f"].clone();
+        let lengths = blocks.iter().map(|b| b.b.num_bytes).collect::<Vec<_>>();
+        assert_eq!(lengths, [Some(700), Some(300)]);
+        assert_eq!(blocks[1].offset, 700);
+        assert_eq!(datanode.get_block(blocks[0].b.block_id), Some(data));
+        assert_eq!(
+            datanode.get_block(blocks[1].b.block_id).as_deref(),
+            Some(&more[..])
+        );
+        Ok(())
+    }
 }
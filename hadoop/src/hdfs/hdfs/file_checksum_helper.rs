@@ -0,0 +1,548 @@
+use super::{
+    protocol::{
+        datatransfer::{DataTransferProtoUtil, DataTransferProtocol, Sender},
+        hdfs_constants, DatanodeInfo, LocatedBlock, LocatedBlocks,
+    },
+    protocolpb::PBHelperClient,
+    DFSClient,
+};
+use crate::{
+    common::{
+        fs::{
+            options::ChecksumCombineMode, CompositeCrcFileChecksum, FileChecksum,
+            MD5MD5CRC32FileChecksum, PathIOException,
+        },
+        io::IOException,
+        util::{crc_util, CrcComposer, DataChecksumType},
+    },
+    hdfs::{
+        BlockChecksumOptionsProto, BlockChecksumTypeProto, BlockOpResponseProto,
+        OpBlockChecksumResponseProto,
+    },
+};
+use anyhow::Error;
+use prost::Message;
+use std::time::Duration;
+
+/// A file checksum computer for replicated files, it computes the checksum of
+/// every block on one of its datanodes and combines them into the file
+/// checksum.
+pub(crate) struct ReplicatedFileChecksumComputer<'a> {
+    src: String,
+    length: i64,
+    block_locations: Option<LocatedBlocks>,
+    dfs_client: &'a DFSClient,
+    combine_mode: ChecksumCombineMode,
+    block_checksum_type: BlockChecksumTypeProto,
+
+    located_blocks: Vec<LocatedBlock>,
+    remaining: i64,
+
+    bytes_per_crc: i32,
+    crc_type: Option<DataChecksumType>,
+    // whether the blocks have different crc types
+    mixed_crc_type: bool,
+    crc_per_block: i64,
+    is_refetch_blocks: bool,
+    last_retried_index: i64,
+    timeout: Duration,
+
+    block_checksum_buf: Vec<u8>,
+    file_checksum: Option<Box<dyn FileChecksum>>,
+}
+
+impl<'a> ReplicatedFileChecksumComputer<'a> {
+    /// Constructor that accepts all the input parameters for the computing.
+    pub fn new(
+        src: &str,
+        length: i64,
+        block_locations: Option<LocatedBlocks>,
+        dfs_client: &'a DFSClient,
+        combine_mode: ChecksumCombineMode,
+    ) -> Self {
+        let block_checksum_type = match combine_mode {
+            ChecksumCombineMode::Md5md5crc => BlockChecksumTypeProto::Md5crc,
+            ChecksumCombineMode::CompositeCrc => BlockChecksumTypeProto::CompositeCrc,
+        };
+        let mut remaining = length;
+        let mut located_blocks = vec![];
+        if let Some(block_locations) = &block_locations {
+            if src.contains(hdfs_constants::SEPARATOR_DOT_SNAPSHOT_DIR_SEPARATOR) {
+                remaining = length.min(block_locations.get_file_length());
+            }
+            located_blocks = block_locations.get_located_blocks().to_vec();
+        }
+        Self {
+            src: src.to_owned(),
+            length,
+            block_locations,
+            dfs_client,
+            combine_mode,
+            block_checksum_type,
+            located_blocks,
+            remaining,
+            bytes_per_crc: 0,
+            crc_type: None,
+            mixed_crc_type: false,
+            crc_per_block: 0,
+            is_refetch_blocks: false,
+            last_retried_index: -1,
+            timeout: Duration::ZERO,
+            block_checksum_buf: vec![],
+            file_checksum: None,
+        }
+    }
+
+    /// Perform the file checksum computing. The intermediate results are
+    /// stored in the object and will be used later.
+    pub fn compute(&mut self) -> anyhow::Result<()> {
+        // request length is 0 or the file is empty, return one with the
+        // magic entry that matches what previous hdfs versions return.
+        if self.located_blocks.is_empty() {
+            // Explicitly specified here in case the default DataOutputBuffer
+            // buffer length value is changed in future. This matters because
+            // the fixed value 32 has to be used to repeat the magic value for
+            // previous HDFS version.
+            const LEN_OF_ZERO_BYTES: usize = 32;
+            let empty_block_md5 = [0u8; LEN_OF_ZERO_BYTES];
+            let file_md5 = md5::compute(empty_block_md5);
+            self.file_checksum = Some(Box::new(MD5MD5CRC32FileChecksum::new(
+                0,
+                0,
+                file_md5.0,
+                DataChecksumType::Crc32,
+            )));
+        } else {
+            self.checksum_blocks()?;
+            self.file_checksum = self.make_final_result()?;
+        }
+        Ok(())
+    }
+
+    /// Returns the computed file checksum, or `None` when the blocks use
+    /// different or no CRC types.
+    pub fn get_file_checksum(self) -> Option<Box<dyn FileChecksum>> {
+        self.file_checksum
+    }
+
+    /// Compute block checksums block by block and append the raw bytes of
+    /// the block checksums into the block checksum buffer.
+    fn checksum_blocks(&mut self) -> anyhow::Result<()> {
+        // get block checksum for each block
+        let mut bi = 0;
+        while bi < self.located_blocks.len() && self.remaining >= 0 {
+            if self.is_refetch_blocks {
+                // refetch to get fresh tokens
+                self.refetch_blocks()?;
+            }
+            let mut located_block = self.located_blocks[bi].to_owned();
+            let done = self.checksum_block(&mut located_block, bi)?;
+            // the block length may have been adjusted to the requested length
+            self.located_blocks[bi] = located_block;
+            if !done {
+                return Err(PathIOException::with_error(
+                    &self.src,
+                    &format!(
+                        "Fail to get block MD5 for {}",
+                        self.located_blocks[bi].get_block()
+                    ),
+                )
+                .into());
+            }
+            if self.is_refetch_blocks {
+                // repeat at bi-th block
+                continue;
+            }
+            bi += 1;
+        }
+        Ok(())
+    }
+
+    fn refetch_blocks(&mut self) -> anyhow::Result<()> {
+        let block_locations = self
+            .dfs_client
+            .get_block_locations(&self.src, self.length)?;
+        self.located_blocks = block_locations.get_located_blocks().to_vec();
+        self.block_locations = Some(block_locations);
+        self.is_refetch_blocks = false;
+        Ok(())
+    }
+
+    /// Return true when sounds good to continue or retry, false when severe
+    /// condition or totally failed.
+    fn checksum_block(
+        &mut self,
+        located_block: &mut LocatedBlock,
+        block_idx: usize,
+    ) -> anyhow::Result<bool> {
+        let num_bytes = located_block.get_block().get_num_bytes();
+        if self.remaining < num_bytes {
+            located_block.get_block_mut().set_num_bytes(self.remaining);
+        }
+        self.remaining -= located_block.get_block().get_num_bytes();
+
+        let datanodes = located_block.get_locations().to_vec();
+        let tmp_timeout = 3000 * datanodes.len() as u64
+            + self.dfs_client.get_conf().get_socket_timeout().max(0) as u64;
+        self.timeout = Duration::from_millis(tmp_timeout);
+
+        // try each datanode location of the block
+        for (j, datanode) in datanodes.iter().enumerate() {
+            match self.try_datanode(located_block, datanode, block_idx) {
+                Ok(()) => return Ok(true),
+                Err(e)
+                    if matches!(
+                        e.downcast_ref::<IOException>(),
+                        Some(IOException::InvalidBlockToken(_))
+                    ) =>
+                {
+                    if block_idx as i64 > self.last_retried_index {
+                        log::debug!(
+                            "Got access token error in response to OP_BLOCK_CHECKSUM for \
+                            file {} for block {} from datanode {}. Will retry the block once.",
+                            self.src,
+                            located_block.get_block(),
+                            datanode
+                        );
+                        self.last_retried_index = block_idx as i64;
+                        // actually it's not done; but we'll retry
+                        self.is_refetch_blocks = true;
+                        return Ok(true);
+                    }
+                }
+                Err(e) => log::warn!("src={}, datanodes[{}]={}: {}", self.src, j, datanode, e),
+            }
+        }
+        Ok(false)
+    }
+
+    /// Return the block checksum from the specified datanode.
+    fn try_datanode(
+        &mut self,
+        located_block: &LocatedBlock,
+        datanode: &DatanodeInfo,
+        block_idx: usize,
+    ) -> anyhow::Result<()> {
+        let block = located_block.get_block();
+        let mut peer = self
+            .dfs_client
+            .new_connected_peer(&datanode.get_xfer_addr())?;
+        peer.set_read_timeout(Some(self.timeout))?;
+        log::debug!("write to {}: BLOCK_CHECKSUM, block={}", datanode, block);
+
+        // get block checksum
+        let block_checksum_options = BlockChecksumOptionsProto {
+            block_checksum_type: Some(self.block_checksum_type as i32),
+            stripe_length: Some(0),
+        };
+        Sender::new(&mut peer).block_checksum(
+            block,
+            located_block.get_block_token(),
+            &block_checksum_options,
+        )?;
+
+        let reply = BlockOpResponseProto::decode(&PBHelperClient::vint_prefixed(&mut peer)?[..])?;
+        let log_info = format!("for block {} from datanode {}", block, datanode);
+        DataTransferProtoUtil::check_block_op_status(&reply, &log_info)?;
+
+        let checksum_data = reply.checksum_response.unwrap_or_default();
+        self.extract_checksum_properties(&checksum_data, block_idx)?;
+        let block_checksum_for_debug = self.populate_block_checksum_buf(&checksum_data)?;
+        log::debug!(
+            "got reply from {}: blockChecksum={}, blockChecksumType={}",
+            datanode,
+            block_checksum_for_debug,
+            self.block_checksum_type.as_str_name()
+        );
+        Ok(())
+    }
+
+    fn extract_checksum_properties(
+        &mut self,
+        checksum_data: &OpBlockChecksumResponseProto,
+        block_idx: usize,
+    ) -> anyhow::Result<()> {
+        // read byte-per-checksum
+        let bpc = checksum_data.bytes_per_crc as i32;
+        if block_idx == 0 {
+            // first block
+            self.bytes_per_crc = bpc;
+        } else if bpc != self.bytes_per_crc {
+            if self.block_checksum_type == BlockChecksumTypeProto::CompositeCrc {
+                log::warn!(
+                    "Current bytesPerCRC={} doesn't match next bpc={}, but continuing anyway \
+                    because we're using COMPOSITE_CRC. If trying to preserve CHECKSUMTYPE, only \
+                    the current bytesPerCRC will be preserved.",
+                    self.bytes_per_crc,
+                    bpc
+                );
+            } else {
+                return Err(Error::msg(format!(
+                    "Byte-per-checksum not matched: bpc={} but bytesPerCRC={}",
+                    bpc, self.bytes_per_crc
+                )));
+            }
+        }
+
+        // read crc-per-block
+        let cpb = checksum_data.crc_per_block as i64;
+        if self.located_blocks.len() > 1 && block_idx == 0 {
+            self.crc_per_block = cpb;
+        }
+
+        // read crc-type
+        let ct = DataChecksumType::from(checksum_data.crc_type());
+        if block_idx == 0 {
+            // first block
+            self.crc_type = Some(ct);
+        } else if !self.mixed_crc_type && self.crc_type != Some(ct) {
+            if self.block_checksum_type == BlockChecksumTypeProto::CompositeCrc {
+                return Err(Error::msg(
+                    "DataChecksum.Type.MIXED is not supported for COMPOSITE_CRC",
+                ));
+            }
+            // if crc types are mixed in a file
+            self.mixed_crc_type = true;
+        }
+
+        if block_idx == 0 {
+            log::debug!(
+                "set bytesPerCRC={}, crcPerBlock={}",
+                self.bytes_per_crc,
+                self.crc_per_block
+            );
+        }
+        Ok(())
+    }
+
+    /// Parses out the raw blockChecksum bytes from `checksum_data` according
+    /// to the block checksum type and populates the block checksum buffer
+    /// with it.
+    ///
+    /// Returns a debug string representation of the parsed checksum.
+    fn populate_block_checksum_buf(
+        &mut self,
+        checksum_data: &OpBlockChecksumResponseProto,
+    ) -> anyhow::Result<String> {
+        match self.block_checksum_type {
+            BlockChecksumTypeProto::Md5crc => {
+                // read md5
+                if checksum_data.block_checksum.len() != 16 {
+                    return Err(Error::msg(format!(
+                        "Unexpected length of the block MD5: {}",
+                        checksum_data.block_checksum.len()
+                    )));
+                }
+                self.block_checksum_buf
+                    .extend_from_slice(&checksum_data.block_checksum);
+            }
+            BlockChecksumTypeProto::CompositeCrc => {
+                let returned_type = checksum_data
+                    .block_checksum_options
+                    .as_ref()
+                    .map(|o| o.block_checksum_type())
+                    .unwrap_or_default();
+                if returned_type != BlockChecksumTypeProto::CompositeCrc {
+                    return Err(Error::msg(format!(
+                        "Unexpected blockChecksumType '{}', expecting COMPOSITE_CRC",
+                        returned_type.as_str_name()
+                    )));
+                }
+                self.block_checksum_buf
+                    .extend_from_slice(&checksum_data.block_checksum);
+            }
+        }
+        Ok(checksum_data
+            .block_checksum
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect())
+    }
+
+    fn make_final_result(&self) -> anyhow::Result<Option<Box<dyn FileChecksum>>> {
+        match self.combine_mode {
+            ChecksumCombineMode::Md5md5crc => Ok(self.make_md5_crc_result()),
+            ChecksumCombineMode::CompositeCrc => Ok(Some(self.make_composite_crc_result()?)),
+        }
+    }
+
+    fn make_md5_crc_result(&self) -> Option<Box<dyn FileChecksum>> {
+        // compute file MD5
+        let file_md5 = md5::compute(&self.block_checksum_buf);
+        match self.crc_type {
+            Some(crc_type @ (DataChecksumType::Crc32 | DataChecksumType::Crc32c))
+                if !self.mixed_crc_type =>
+            {
+                Some(Box::new(MD5MD5CRC32FileChecksum::new(
+                    self.bytes_per_crc,
+                    self.crc_per_block,
+                    file_md5.0,
+                    crc_type,
+                )))
+            }
+            // we will get here when crcType is "NULL" or "MIXED".
+            _ => None,
+        }
+    }
+
+    fn make_composite_crc_result(&self) -> anyhow::Result<Box<dyn FileChecksum>> {
+        let crc_type = self.crc_type.unwrap_or(DataChecksumType::Null);
+        let block_size_hint = self
+            .located_blocks
+            .first()
+            .map(|b| b.get_block_size())
+            .unwrap_or_default();
+        let mut crc_composer = CrcComposer::new_crc_composer(crc_type, block_size_hint)?;
+        let block_checksum_bytes = &self.block_checksum_buf;
+
+        let mut sum_block_lengths = 0;
+        let last_index = self.located_blocks.len() - 1;
+        for (i, block) in self.located_blocks[..last_index].iter().enumerate() {
+            // For everything except the last LocatedBlock, we expect
+            // getBlockSize() to accurately reflect the number of file bytes
+            // digested in the block checksum.
+            sum_block_lengths += block.get_block_size();
+            let block_crc = crc_util::read_int(block_checksum_bytes, i * 4)?;
+
+            crc_composer.update(block_crc, block.get_block_size())?;
+            log::debug!(
+                "Added blockCrc 0x{:x} for block index {} of size {}",
+                block_crc,
+                i,
+                block.get_block_size()
+            );
+        }
+
+        // NB: In some cases the located blocks have their block size adjusted
+        // explicitly based on the requested length, but not all cases;
+        // these numbers may or may not reflect actual sizes on disk.
+        let reported_last_block_size = self
+            .block_locations
+            .as_ref()
+            .and_then(|b| b.get_last_located_block())
+            .map(|b| b.get_block_size())
+            .unwrap_or_default();
+        let mut consumed_last_block_length = reported_last_block_size;
+        if self.length - sum_block_lengths < reported_last_block_size {
+            log::warn!(
+                "Last block length {} is less than reportedLastBlockSize {}",
+                self.length - sum_block_lengths,
+                reported_last_block_size
+            );
+            consumed_last_block_length = self.length - sum_block_lengths;
+        }
+        let last_block_crc = crc_util::read_int(block_checksum_bytes, 4 * last_index)?;
+        crc_composer.update(last_block_crc, consumed_last_block_length)?;
+        log::debug!(
+            "Added lastBlockCrc 0x{:x} for block index {} of size {}",
+            last_block_crc,
+            last_index,
+            consumed_last_block_length
+        );
+
+        let composite_crc = crc_util::read_int(&crc_composer.digest(), 0)?;
+        Ok(Box::new(CompositeCrcFileChecksum::new(
+            composite_crc,
+            crc_type,
+            self.bytes_per_crc,
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        common::conf::Configuration,
+        hdfs::hdfs::{
+            client::hdfs_client_config_keys::DFS_CHECKSUM_COMBINE_MODE_KEY,
+            distributed_file_system::tests::located_blocks,
+            protocol::{datatransfer::FakeDataNode, MockClientProtocol},
+        },
+    };
+    use std::sync::Arc;
+
+    const BLOCK_SIZE: u64 = 1024;
+    const FILE_LEN: u64 = 2500;
+
+    /// Get the checksum of the first `length` bytes of a file of three
+    /// blocks, the last one partial, combining the block checksums with
+    /// `combine_mode`.
+    fn get_file_checksum(combine_mode: &str, length: i64) -> anyhow::Result<String> {
+        let datanode = FakeDataNode::start()?;
+        let data = (0..FILE_LEN).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+        for block in located_blocks(FILE_LEN, BLOCK_SIZE).blocks {
+            let offset = block.offset as usize;
+            let len = block.b.num_bytes.unwrap_or_default() as usize;
+            datanode.put_block(block.b.block_id, &data[offset..offset + len]);
+        }
+        let locs = vec![datanode.get_datanode_info()];
+        let namenode = MockClientProtocol {
+            get_block_locations: Some(Box::new(move |src, offset, length| {
+                assert_eq!((src, offset), ("/f", 0));
+                let mut blocks = located_blocks(FILE_LEN, BLOCK_SIZE);
+                blocks.blocks.retain(|b| b.offset < length as u64);
+                for block in &mut blocks.blocks {
+                    block.locs = locs.clone();
+                }
+                Ok(Some(LocatedBlocks::from(&blocks)))
+            })),
+            ..Default::default()
+        };
+        let mut conf = Configuration::new();
+        conf.set(DFS_CHECKSUM_COMBINE_MODE_KEY, combine_mode);
+        let dfs_client = DFSClient::with_rpc_namenode(
+            &conf,
+            Box::new(namenode),
+            Arc::new(|| Ok(Box::<MockClientProtocol>::default())),
+        )?;
+        let checksum = dfs_client
+            .get_file_checksum_with_combine_mode("/f", length)?
+            .ok_or_else(|| Error::msg("No checksum"))?;
+        Ok(checksum.to_string())
+    }
+
+    // The expected checksums are the ones `hadoop fs -checksum` prints for
+    // the file, worked out from the CRC32C of its 512 byte chunks.
+
+    #[test]
+    fn test_md5md5crc32_checksum() -> anyhow::Result<()> {
+        assert_eq!(
+            get_file_checksum("MD5MD5CRC", FILE_LEN as i64)?,
+            "MD5-of-2MD5-of-512CRC32C:\
+            0000020000000000000000027cc5e9b243f64bf35def98ae0bda8eee"
+        );
+        // the checksum of the last block stops in the middle of its chunk
+        assert_eq!(
+            get_file_checksum("MD5MD5CRC", 2100)?,
+            "MD5-of-2MD5-of-512CRC32C:\
+            000002000000000000000002ef936a2af8be8eb7a55a2fb6b9e20cb8"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_composite_crc_checksum() -> anyhow::Result<()> {
+        // the composite crc is the crc of the whole range
+        assert_eq!(
+            get_file_checksum("COMPOSITE_CRC", FILE_LEN as i64)?,
+            "COMPOSITE-CRC32C:ee8bd9af"
+        );
+        assert_eq!(
+            get_file_checksum("COMPOSITE_CRC", 2100)?,
+            "COMPOSITE-CRC32C:7938f8a8"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_empty_range_checksum() -> anyhow::Result<()> {
+        // the magic value of older versions, the MD5 of 32 zero bytes
+        assert_eq!(
+            get_file_checksum("MD5MD5CRC", 0)?,
+            "MD5-of-0MD5-of-0CRC32:\
+            00000000000000000000000070bc8f4b72a86921468bf8e8441dce51"
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,123 @@
+use crate::common::conf::{Configuration, DeprecationDelta};
+use std::sync::Once;
+
+static INIT: Once = Once::new();
+
+/// Keys of the HDFS configuration which have been renamed, along with their
+/// replacements.
+const DEPRECATED_KEYS: &[(&str, &str)] = &[
+    ("dfs.backup.address", "dfs.namenode.backup.address"),
+    (
+        "dfs.backup.http.address",
+        "dfs.namenode.backup.http-address",
+    ),
+    (
+        "dfs.balance.bandwidthPerSec",
+        "dfs.datanode.balance.bandwidthPerSec",
+    ),
+    ("dfs.data.dir", "dfs.datanode.data.dir"),
+    ("dfs.http.address", "dfs.namenode.http-address"),
+    ("dfs.https.address", "dfs.namenode.https-address"),
+    ("dfs.max.objects", "dfs.namenode.max.objects"),
+    ("dfs.name.dir", "dfs.namenode.name.dir"),
+    ("dfs.name.dir.restore", "dfs.namenode.name.dir.restore"),
+    ("dfs.name.edits.dir", "dfs.namenode.edits.dir"),
+    ("dfs.read.prefetch.size", "dfs.client.read.prefetch.size"),
+    ("dfs.safemode.extension", "dfs.namenode.safemode.extension"),
+    (
+        "dfs.safemode.threshold.pct",
+        "dfs.namenode.safemode.threshold-pct",
+    ),
+    (
+        "dfs.secondary.http.address",
+        "dfs.namenode.secondary.http-address",
+    ),
+    ("dfs.socket.timeout", "dfs.client.socket-timeout"),
+    ("fs.checkpoint.dir", "dfs.namenode.checkpoint.dir"),
+    (
+        "fs.checkpoint.edits.dir",
+        "dfs.namenode.checkpoint.edits.dir",
+    ),
+    ("fs.checkpoint.period", "dfs.namenode.checkpoint.period"),
+    (
+        "heartbeat.recheck.interval",
+        "dfs.namenode.heartbeat.recheck-interval",
+    ),
+    (
+        "dfs.https.client.keystore.resource",
+        "dfs.client.https.keystore.resource",
+    ),
+    ("dfs.https.need.client.auth", "dfs.client.https.need-auth"),
+    ("slave.host.name", "dfs.datanode.hostname"),
+    ("session.id", "dfs.metrics.session-id"),
+    (
+        "dfs.access.time.precision",
+        "dfs.namenode.accesstime.precision",
+    ),
+    (
+        "dfs.replication.considerLoad",
+        "dfs.namenode.redundancy.considerLoad",
+    ),
+    (
+        "dfs.replication.interval",
+        "dfs.namenode.redundancy.interval",
+    ),
+    ("dfs.replication.min", "dfs.namenode.replication.min"),
+    (
+        "dfs.replication.pending.timeout.sec",
+        "dfs.namenode.reconstruction.pending.timeout-sec",
+    ),
+    (
+        "dfs.max-repl-streams",
+        "dfs.namenode.replication.max-streams",
+    ),
+    ("dfs.permissions", "dfs.permissions.enabled"),
+    (
+        "dfs.permissions.supergroup",
+        "dfs.permissions.superusergroup",
+    ),
+    ("dfs.write.packet.size", "dfs.client-write-packet-size"),
+    ("dfs.block.size", "dfs.blocksize"),
+    (
+        "dfs.datanode.max.xcievers",
+        "dfs.datanode.max.transfer.threads",
+    ),
+    ("io.bytes.per.checksum", "dfs.bytes-per-checksum"),
+    ("dfs.federation.nameservices", "dfs.nameservices"),
+    ("dfs.federation.nameservice.id", "dfs.nameservice.id"),
+    (
+        "dfs.encryption.key.provider.uri",
+        "hadoop.security.key.provider.path",
+    ),
+];
+
+/// Adds deprecated keys into the configuration.
+pub struct HdfsConfiguration;
+
+impl HdfsConfiguration {
+    /// Register `hdfs-default.xml` and `hdfs-site.xml` as default resources
+    /// and add the HDFS deprecated keys. It is safe for this method to be
+    /// called multiple times as the registration only happens once.
+    pub fn init() {
+        INIT.call_once(|| {
+            Self::add_deprecated_keys();
+            Configuration::add_default_resource("hdfs-default.xml");
+            Configuration::add_default_resource("hdfs-site.xml");
+        });
+    }
+
+    /// A new configuration which also loads the HDFS default resources.
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new() -> Configuration {
+        Self::init();
+        Configuration::new()
+    }
+
+    fn add_deprecated_keys() {
+        let deltas: Vec<DeprecationDelta> = DEPRECATED_KEYS
+            .iter()
+            .map(|(key, new_key)| DeprecationDelta::new(key, new_key))
+            .collect();
+        Configuration::add_deprecations(&deltas);
+    }
+}
@@ -1,12 +1,25 @@
+#[cfg(feature = "async")]
+mod async_distributed_file_system;
 mod client;
+mod data_streamer;
 mod dfs_client;
+mod dfs_input_stream;
+mod dfs_output_stream;
+mod dfs_packet;
 mod dfs_util_client;
 mod distributed_file_system;
+mod file_checksum_helper;
+mod hdfs_configuration;
 mod name_node_proxies_client;
 pub mod protocol;
 mod protocolpb;
 
+#[cfg(feature = "async")]
+pub use async_distributed_file_system::AsyncDistributedFileSystem;
+pub use client::SyncFlag;
 pub(crate) use dfs_client::DFSClient;
+pub(crate) use dfs_output_stream::DFSOutputStream;
 pub(crate) use dfs_util_client::DFSUtilClient;
 pub(crate) use distributed_file_system::DistributedFileSystem;
+pub use hdfs_configuration::HdfsConfiguration;
 pub(crate) use name_node_proxies_client::NameNodeProxiesClient;
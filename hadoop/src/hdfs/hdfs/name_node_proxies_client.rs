@@ -1,17 +1,23 @@
+#[cfg(feature = "async")]
+use super::protocol::AsyncClientProtocol;
 use super::{
+    client::hdfs_client_config_keys,
     protocol::ClientProtocol,
     protocolpb::{ClientNamenodeProtocolPB, ClientNamenodeProtocolTranslatorPB},
     DFSUtilClient,
 };
 use crate::common::{
     conf::Configuration,
-    io::Text,
+    io::{
+        retry::{RetryInvocationHandler, RetryPolicy, RetryUtils},
+        IOException, Text,
+    },
     ipc::{AlignmentContext, Client, RPC},
     security::{SecurityUtil, UserGroupInformation},
 };
 use atomic::Atomic;
 use iref::Iri;
-use std::{net::SocketAddr, rc::Rc, sync::Arc};
+use std::{collections::HashMap, net::SocketAddr, sync::Arc};
 
 /// Wrapper for a client proxy as well as its associated service ID.
 /// This is simply used as a tuple-like return type for created NN proxy.
@@ -36,7 +42,7 @@ impl NameNodeProxiesClient {
         conf: &Configuration,
         name_node_uri: &Iri,
         fallback_to_simple_auth: Option<Arc<Atomic<bool>>>,
-    ) -> anyhow::Result<ProxyAndInfo<impl ClientProtocol>> {
+    ) -> anyhow::Result<ProxyAndInfo<Box<dyn ClientProtocol>>> {
         // TODO: support HA proxy
 
         let nn_addr = DFSUtilClient::get_nnaddress(name_node_uri)?;
@@ -55,13 +61,44 @@ impl NameNodeProxiesClient {
         })
     }
 
+    /// Creates the namenode proxy with the ClientProtocol whose calls may be
+    /// made asynchronously, see [`AsyncClientProtocol`]. The failed calls are
+    /// retried with the retry policy of the synchronous proxy.
+    #[cfg(feature = "async")]
+    pub fn create_async_proxy_with_client_protocol(
+        conf: &Configuration,
+        name_node_uri: &Iri,
+    ) -> anyhow::Result<ProxyAndInfo<Box<dyn AsyncClientProtocol>>> {
+        let nn_addr = DFSUtilClient::get_nnaddress(name_node_uri)?;
+        let dt_service = SecurityUtil::build_token_service(&nn_addr);
+        let default_policy = Self::get_default_retry_policy(conf);
+        let proxy: ClientNamenodeProtocolPB = RPC::get_protocol_proxy(
+            &nn_addr,
+            &UserGroupInformation::get_current_user()?,
+            conf,
+            Client::get_timeout(conf)?,
+            Some(Arc::clone(&default_policy)),
+            None,
+            None,
+        )?;
+        Ok(ProxyAndInfo {
+            proxy: Box::new(RetryInvocationHandler::new(
+                ClientNamenodeProtocolTranslatorPB::from(proxy),
+                default_policy,
+                HashMap::new(),
+            )),
+            dt_service,
+            _address: nn_addr,
+        })
+    }
+
     pub fn create_non_ha_proxy_with_client_protocol(
         address: &SocketAddr,
         conf: &Configuration,
         ugi: &UserGroupInformation,
         with_retries: bool,
         fallback_to_simple_auth: Option<Arc<Atomic<bool>>>,
-    ) -> anyhow::Result<impl ClientProtocol> {
+    ) -> anyhow::Result<Box<dyn ClientProtocol>> {
         Self::create_proxy_with_alignment_context(
             address,
             conf,
@@ -78,26 +115,44 @@ impl NameNodeProxiesClient {
         ugi: &UserGroupInformation,
         with_retries: bool,
         fallback_to_simple_auth: Option<Arc<Atomic<bool>>>,
-        alignment_context: Option<Rc<dyn AlignmentContext>>,
-    ) -> anyhow::Result<impl ClientProtocol> {
+        alignment_context: Option<Arc<dyn AlignmentContext>>,
+    ) -> anyhow::Result<Box<dyn ClientProtocol>> {
         // TODO: set protocol engine for ClientNamenodeProtocolPB
 
-        // TODO: get default policy
+        let default_policy = Self::get_default_retry_policy(conf);
 
         let proxy: ClientNamenodeProtocolPB = RPC::get_protocol_proxy(
             address,
             ugi,
             conf,
-            Client::get_timeout(conf),
-            None,
+            Client::get_timeout(conf)?,
+            Some(Arc::clone(&default_policy)),
             fallback_to_simple_auth,
             alignment_context,
         )?;
+        let translator_proxy = ClientNamenodeProtocolTranslatorPB::from(proxy);
 
         if with_retries {
-            // TODO: support retry proxy
+            // create the proxy with retries
+            Ok(Box::new(RetryInvocationHandler::new(
+                translator_proxy,
+                default_policy,
+                HashMap::new(),
+            )))
+        } else {
+            Ok(Box::new(translator_proxy))
         }
+    }
 
-        Ok(ClientNamenodeProtocolTranslatorPB::from(proxy))
+    /// The retry policy of the NN proxies, as set by `dfs.client.retry.policy.*`.
+    fn get_default_retry_policy(conf: &Configuration) -> Arc<dyn RetryPolicy> {
+        RetryUtils::get_default_retry_policy(
+            conf,
+            hdfs_client_config_keys::DFS_CLIENT_RETRY_POLICY_ENABLED_KEY,
+            hdfs_client_config_keys::DFS_CLIENT_RETRY_POLICY_ENABLED_DEFAULT,
+            hdfs_client_config_keys::DFS_CLIENT_RETRY_POLICY_SPEC_KEY,
+            hdfs_client_config_keys::DFS_CLIENT_RETRY_POLICY_SPEC_DEFAULT,
+            IOException::SafeMode(String::new()).get_class_name(),
+        )
     }
 }
@@ -0,0 +1,81 @@
+use super::HdfsFileStatus;
+use crate::{
+    common::ipc::RemoteException,
+    hdfs::{BatchedDirectoryListingProto, GetBatchedListingResponseProto},
+};
+
+/// A partial listing of a directory, as returned by a batched listing.
+/// The listing either holds the statuses of the children of the path at
+/// `parent_idx` or the exception raised while listing it.
+#[derive(Clone, Debug)]
+pub struct HdfsPartialListing {
+    partial_listing: Vec<HdfsFileStatus>,
+    parent_idx: usize,
+    exception: Option<RemoteException>,
+}
+
+impl HdfsPartialListing {
+    pub fn get_partial_listing(&self) -> &[HdfsFileStatus] {
+        &self.partial_listing
+    }
+
+    pub fn get_parent_idx(&self) -> usize {
+        self.parent_idx
+    }
+
+    /// The exception raised by the NameNode while listing the parent, if any.
+    pub fn get_exception(&self) -> Option<&RemoteException> {
+        self.exception.as_ref()
+    }
+}
+
+impl From<&BatchedDirectoryListingProto> for HdfsPartialListing {
+    fn from(listing: &BatchedDirectoryListingProto) -> Self {
+        Self {
+            partial_listing: listing
+                .partial_listing
+                .iter()
+                .map(HdfsFileStatus::from)
+                .collect(),
+            parent_idx: listing.parent_idx as usize,
+            exception: listing
+                .exception
+                .as_ref()
+                .map(|e| RemoteException::new(&e.class_name, e.message.as_deref().unwrap_or(""))),
+        }
+    }
+}
+
+/// A struct-like class for holding partial listings returned by the batched
+/// listing API. This class is used internally by the HDFS client and
+/// namenode and is not meant for public consumption.
+#[derive(Clone, Debug)]
+pub struct BatchedDirectoryListing {
+    listings: Vec<HdfsPartialListing>,
+    has_more: bool,
+    start_after: Vec<u8>,
+}
+
+impl BatchedDirectoryListing {
+    pub fn get_listings(&self) -> &[HdfsPartialListing] {
+        &self.listings
+    }
+
+    pub fn has_more(&self) -> bool {
+        self.has_more
+    }
+
+    pub fn get_start_after(&self) -> &[u8] {
+        &self.start_after
+    }
+}
+
+impl From<&GetBatchedListingResponseProto> for BatchedDirectoryListing {
+    fn from(res: &GetBatchedListingResponseProto) -> Self {
+        Self {
+            listings: res.listings.iter().map(HdfsPartialListing::from).collect(),
+            has_more: res.has_more,
+            start_after: res.start_after.to_owned(),
+        }
+    }
+}
@@ -1,4 +1,17 @@
-use crate::common::fs::permission::FsCreateModes;
+use super::{
+    BatchedDirectoryListing, DatanodeInfo, DirectoryListing, ExtendedBlock, HdfsFileStatus,
+    LastBlockWithStatus, LocatedBlock, LocatedBlocks,
+};
+use crate::common::{
+    fs::{
+        options::Rename,
+        permission::{FsCreateModes, FsPermission},
+        ContentSummary, CreateFlag,
+    },
+    io::retry::RetryInvocationHandler,
+};
+#[cfg(feature = "async")]
+use std::{future::Future, pin::Pin};
 
 /// Until version 69, this class ClientProtocol served as both
 /// the client interface to the NN AND the RPC protocol used to
@@ -21,10 +34,192 @@ use crate::common::fs::permission::FsCreateModes;
 ///  serialization. DO not update this version any further.
 pub(crate) const _VERSION_ID: u64 = 69;
 
+pub(crate) const GET_STATS_CAPACITY_IDX: usize = 0;
+pub(crate) const GET_STATS_USED_IDX: usize = 1;
+pub(crate) const GET_STATS_REMAINING_IDX: usize = 2;
+
 /// ClientProtocol is used by user code via the DistributedFileSystem class to
 /// communicate with the NameNode.  User code can manipulate the directory
 /// namespace, as well as open/close file streams, etc.
-pub(crate) trait ClientProtocol {
+///
+/// A NameNode proxy is shared by the file streams of a client, which may be
+/// sent to other threads.
+pub(crate) trait ClientProtocol: Send + Sync {
+    /// Get locations of the blocks of the specified file
+    /// within the specified range.
+    /// DataNode locations for each block are sorted by
+    /// the proximity to the client.
+    ///
+    /// Return [`LocatedBlocks`] which contains
+    /// file length, blocks and their locations, or `None` if `src` does not
+    /// exist.
+    fn get_block_locations(
+        &self,
+        src: &str,
+        offset: i64,
+        length: i64,
+    ) -> anyhow::Result<Option<LocatedBlocks>>;
+
+    /// Create a new file entry in the namespace.
+    ///
+    /// This will create an empty file specified by the source path.
+    /// The path should reflect a full path originated at the root.
+    /// The name-node does not have a notion of "current" directory for a client.
+    ///
+    /// Once created, the file is visible and available for read to other clients.
+    /// Although, other clients cannot [`delete`](Self::delete), re-create or
+    /// [`rename`](Self::rename) it until the file is completed
+    /// or explicitly as a result of lease expiration.
+    ///
+    /// Blocks have a maximum size.  Clients that intend to create
+    /// multi-block files must also use [`add_block`](Self::add_block).
+    ///
+    /// Returns the status of the created file, it could be `None` if the server
+    /// doesn't support returning the file status.
+    #[allow(clippy::too_many_arguments)]
+    fn create(
+        &self,
+        src: &str,
+        create_modes: &FsCreateModes,
+        client_name: &str,
+        flag: &[CreateFlag],
+        create_parent: bool,
+        replication: i16,
+        block_size: i64,
+    ) -> anyhow::Result<Option<HdfsFileStatus>>;
+
+    /// Append to the end of the file.
+    ///
+    /// `flag` indicates whether the data is appended to a new block.
+    ///
+    /// Returns wrapper with information about the last partial block and file
+    /// status if any.
+    fn append(
+        &self,
+        src: &str,
+        client_name: &str,
+        flag: &[CreateFlag],
+    ) -> anyhow::Result<LastBlockWithStatus>;
+
+    /// The client can give up on a block by calling abandonBlock().
+    /// The client can then either obtain a new block, or complete or abandon the
+    /// file.
+    /// Any partial writes to the block will be discarded.
+    fn abandon_block(
+        &self,
+        b: &ExtendedBlock,
+        file_id: u64,
+        src: &str,
+        holder: &str,
+    ) -> anyhow::Result<()>;
+
+    /// A client that wants to write an additional block to the
+    /// indicated filename (which must currently be open for writing)
+    /// should call addBlock().
+    ///
+    /// addBlock() allocates a new block and datanodes the block data
+    /// should be replicated to.
+    ///
+    /// addBlock() also commits the previous block by reporting
+    /// to the name-node the actual generation stamp and the length
+    /// of the block that the client has transmitted to data-nodes.
+    fn add_block(
+        &self,
+        src: &str,
+        client_name: &str,
+        previous: Option<&ExtendedBlock>,
+        exclude_nodes: &[DatanodeInfo],
+        file_id: u64,
+    ) -> anyhow::Result<LocatedBlock>;
+
+    /// Get a datanode for an existing pipeline.
+    ///
+    /// `existings` are the datanodes of the pipeline, with the storages
+    /// `existing_storage_ids`, and `excludes` the datanodes which should not
+    /// be picked. The returned block holds the datanodes of the pipeline with
+    /// the `num_additional_nodes` additional datanodes.
+    #[allow(clippy::too_many_arguments)]
+    fn get_additional_datanode(
+        &self,
+        src: &str,
+        file_id: u64,
+        blk: &ExtendedBlock,
+        existings: &[DatanodeInfo],
+        existing_storage_ids: &[String],
+        excludes: &[DatanodeInfo],
+        num_additional_nodes: i32,
+        client_name: &str,
+    ) -> anyhow::Result<LocatedBlock>;
+
+    /// The client is done writing data to the given filename, and would
+    /// like to complete it.
+    ///
+    /// The function returns whether the file has been closed successfully.
+    /// If the function returns false, the caller should try again.
+    ///
+    /// close() also commits the last block of file by reporting
+    /// to the name-node the actual generation stamp and the length
+    /// of the block that the client has transmitted to data-nodes.
+    ///
+    /// A call to complete() will not return true until all the file's
+    /// blocks have been replicated the minimum number of times.  Thus,
+    /// DataNode failures may cause a client to call complete() several
+    /// times before succeeding.
+    fn complete(
+        &self,
+        src: &str,
+        client_name: &str,
+        last: Option<&ExtendedBlock>,
+        file_id: u64,
+    ) -> anyhow::Result<bool>;
+
+    /// Rename an item in the file system namespace.
+    ///
+    /// Returns true if successful, or false if the old name does not exist
+    /// or if the new name already belongs to the namespace.
+    fn rename(&self, src: &str, dst: &str) -> anyhow::Result<bool>;
+
+    /// Moves blocks from srcs to trg and delete srcs.
+    ///
+    /// `trg` is an existing file, `srcs` a list of existing files (same block
+    /// size, same replication).
+    fn concat(&self, trg: &str, srcs: &[String]) -> anyhow::Result<()>;
+
+    /// Rename src to dst.
+    /// - Fails if src is a file and dst is a directory.
+    /// - Fails if src is a directory and dst is a file.
+    /// - Fails if the parent of dst does not exist or is a file.
+    ///
+    /// Without OVERWRITE option, rename fails if the dst already exists.
+    /// With OVERWRITE option, rename overwrites the dst, if it is a file
+    /// or an empty directory. Rename fails if dst is a non-empty directory.
+    ///
+    /// This implementation of rename is atomic.
+    fn rename2(&self, src: &str, dst: &str, options: &[Rename]) -> anyhow::Result<()>;
+
+    /// Truncate file src to new size.
+    /// - Fails if src is a directory.
+    /// - Fails if src does not exist.
+    /// - Fails if src is not closed.
+    /// - Fails if new size is greater than current size.
+    ///
+    /// This implementation of truncate is purely a namespace operation if
+    /// truncate occurs at a block boundary. Requires DataNode block recovery
+    /// otherwise.
+    ///
+    /// Returns true if client does not need to wait for block recovery,
+    /// false if client needs to wait for block recovery.
+    fn truncate(&self, src: &str, new_length: i64, client_name: &str) -> anyhow::Result<bool>;
+
+    /// Delete the given file or directory from the file system.
+    ///
+    /// same as delete but provides a way to avoid accidentally
+    /// deleting non empty directories programmatically.
+    ///
+    /// Returns true only if the existing file or directory was actually removed
+    /// from the file system.
+    fn delete(&self, src: &str, recursive: bool) -> anyhow::Result<bool>;
+
     /// Create a directory (or hierarchy of directories) with the given
     /// name and permission.
     fn mkdirs(
@@ -33,4 +228,754 @@ pub(crate) trait ClientProtocol {
         create_modes: &FsCreateModes,
         create_parent: bool,
     ) -> anyhow::Result<bool>;
+
+    /// Set replication for an existing file.
+    ///
+    /// The NameNode sets replication to the new value and returns.
+    /// The actual block replication is not expected to be performed during
+    /// this method call. The blocks will be populated or removed in the
+    /// background as the result of the routine block maintenance procedures.
+    fn set_replication(&self, src: &str, replication: i16) -> anyhow::Result<bool>;
+
+    /// Set permissions for an existing file/directory.
+    fn set_permission(&self, src: &str, permission: &FsPermission) -> anyhow::Result<()>;
+
+    /// Set Owner of a path (i.e. a file or a directory).
+    /// The parameters username and groupname cannot both be `None`.
+    fn set_owner(
+        &self,
+        src: &str,
+        username: Option<&str>,
+        groupname: Option<&str>,
+    ) -> anyhow::Result<()>;
+
+    /// Get a partial listing of the indicated directory.
+    ///
+    /// Returns `None` if `src` does not exist. `start_after` is the name to
+    /// start listing after, encoded in java UTF8, and `need_location` tells
+    /// whether block locations are returned for each file.
+    fn get_listing(
+        &self,
+        src: &str,
+        start_after: &[u8],
+        need_location: bool,
+    ) -> anyhow::Result<Option<DirectoryListing>>;
+
+    /// Get a partial listing of the input directories.
+    ///
+    /// `srcs` are the input directories, `start_after` is the name to start
+    /// listing after, encoded in Java UTF8, and `need_location` tells whether
+    /// block locations need to be fetched.
+    fn get_batched_listing(
+        &self,
+        srcs: &[String],
+        start_after: &[u8],
+        need_location: bool,
+    ) -> anyhow::Result<BatchedDirectoryListing>;
+
+    /// Client programs can cause stateful changes in the NameNode that affect
+    /// other clients. A client may obtain a file and neither abandon nor
+    /// complete it. A client might hold a series of locks that prevent other
+    /// clients from proceeding. Clearly, it would be bad if a client held a
+    /// bunch of locks that it never gave up. This can happen easily if the
+    /// client dies unexpectedly.
+    ///
+    /// So, the NameNode will revoke the locks and live file-creates for
+    /// clients that it thinks have died. A client tells the NameNode that it
+    /// is still alive by periodically calling renewLease(). If a certain
+    /// amount of time passes since the last call to renewLease(), the NameNode
+    /// assumes the client has died.
+    fn renew_lease(&self, client_name: &str) -> anyhow::Result<()>;
+
+    /// Get the file info for a specific file or directory.
+    ///
+    /// Returns `None` if `src` is not found.
+    fn get_file_info(&self, src: &str) -> anyhow::Result<Option<HdfsFileStatus>>;
+
+    /// Get the file info for a specific file or directory with
+    /// [`LocatedBlocks`].
+    ///
+    /// `need_block_token` is whether block access tokens should be returned
+    /// with the block locations.
+    ///
+    /// Returns `None` if `src` is not found.
+    fn get_located_file_info(
+        &self,
+        src: &str,
+        need_block_token: bool,
+    ) -> anyhow::Result<Option<HdfsFileStatus>>;
+
+    /// Get an array of aggregated statistics combining blocks of both type
+    /// replicated and erasure coded. Use public constants like
+    /// [`GET_STATS_CAPACITY_IDX`] in place of actual numbers to index into the
+    /// array.
+    fn get_stats(&self) -> anyhow::Result<Vec<i64>>;
+
+    /// Get [`ContentSummary`] rooted at the specified directory.
+    fn get_content_summary(&self, path: &str) -> anyhow::Result<ContentSummary>;
+
+    /// Write all metadata for this file into persistent storage.
+    /// The file must be currently open for writing.
+    ///
+    /// `last_block_length` is the length of the last block under construction
+    /// to be reported to NameNode, or -1 if it should not be updated.
+    fn fsync(
+        &self,
+        src: &str,
+        file_id: u64,
+        client: &str,
+        last_block_length: i64,
+    ) -> anyhow::Result<()>;
+
+    /// Sets the modification and access time of the file to the specified time.
+    fn set_times(&self, src: &str, mtime: i64, atime: i64) -> anyhow::Result<()>;
+
+    /// Get a new generation stamp together with an access token for
+    /// a block under construction
+    ///
+    /// This method is called only when a client needs to recover a failed
+    /// pipeline or set up a pipeline for appending to a block.
+    fn update_block_for_pipeline(
+        &self,
+        block: &ExtendedBlock,
+        client_name: &str,
+    ) -> anyhow::Result<LocatedBlock>;
+
+    /// Update a pipeline for a block under construction.
+    fn update_pipeline(
+        &self,
+        client_name: &str,
+        old_block: &ExtendedBlock,
+        new_block: &ExtendedBlock,
+        new_nodes: &[DatanodeInfo],
+        new_storage_ids: &[String],
+    ) -> anyhow::Result<()>;
+}
+
+/// A future resolving to the response of an [`AsyncClientProtocol`] call.
+#[cfg(feature = "async")]
+pub(crate) type ProtocolFuture<'a, T> =
+    Pin<Box<dyn Future<Output = anyhow::Result<T>> + Send + 'a>>;
+
+/// The asynchronous counterparts of the [`ClientProtocol`] calls, used by
+/// the asynchronous HDFS client. See [`ClientProtocol`] for the semantics of
+/// each call.
+#[cfg(feature = "async")]
+pub(crate) trait AsyncClientProtocol: Send + Sync {
+    fn rename2_async<'a>(
+        &'a self,
+        src: &'a str,
+        dst: &'a str,
+        options: &'a [Rename],
+    ) -> ProtocolFuture<'a, ()>;
+
+    fn delete_async<'a>(&'a self, src: &'a str, recursive: bool) -> ProtocolFuture<'a, bool>;
+
+    fn mkdirs_async<'a>(
+        &'a self,
+        src: &'a str,
+        create_modes: &'a FsCreateModes,
+        create_parent: bool,
+    ) -> ProtocolFuture<'a, bool>;
+
+    fn set_replication_async<'a>(
+        &'a self,
+        src: &'a str,
+        replication: i16,
+    ) -> ProtocolFuture<'a, bool>;
+
+    fn set_permission_async<'a>(
+        &'a self,
+        src: &'a str,
+        permission: &'a FsPermission,
+    ) -> ProtocolFuture<'a, ()>;
+
+    fn set_owner_async<'a>(
+        &'a self,
+        src: &'a str,
+        username: Option<&'a str>,
+        groupname: Option<&'a str>,
+    ) -> ProtocolFuture<'a, ()>;
+
+    fn get_listing_async<'a>(
+        &'a self,
+        src: &'a str,
+        start_after: &'a [u8],
+        need_location: bool,
+    ) -> ProtocolFuture<'a, Option<DirectoryListing>>;
+
+    fn get_file_info_async<'a>(
+        &'a self,
+        src: &'a str,
+    ) -> ProtocolFuture<'a, Option<HdfsFileStatus>>;
+
+    fn get_content_summary_async<'a>(&'a self, path: &'a str)
+        -> ProtocolFuture<'a, ContentSummary>;
+
+    fn set_times_async<'a>(
+        &'a self,
+        src: &'a str,
+        mtime: i64,
+        atime: i64,
+    ) -> ProtocolFuture<'a, ()>;
+}
+
+/// Whether a method may be retried even if the server may have applied it,
+/// as the `@Idempotent` and `@AtMostOnce` annotations of the methods tell.
+const IDEMPOTENT: bool = true;
+const AT_MOST_ONCE: bool = true;
+
+/// The NN proxy retrying the failed calls of `T`.
+impl<T: ClientProtocol> ClientProtocol for RetryInvocationHandler<T> {
+    fn get_block_locations(
+        &self,
+        src: &str,
+        offset: i64,
+        length: i64,
+    ) -> anyhow::Result<Option<LocatedBlocks>> {
+        self.invoke("getBlockLocations", IDEMPOTENT, |p| {
+            p.get_block_locations(src, offset, length)
+        })
+    }
+
+    fn create(
+        &self,
+        src: &str,
+        create_modes: &FsCreateModes,
+        client_name: &str,
+        flag: &[CreateFlag],
+        create_parent: bool,
+        replication: i16,
+        block_size: i64,
+    ) -> anyhow::Result<Option<HdfsFileStatus>> {
+        self.invoke("create", AT_MOST_ONCE, |p| {
+            p.create(
+                src,
+                create_modes,
+                client_name,
+                flag,
+                create_parent,
+                replication,
+                block_size,
+            )
+        })
+    }
+
+    fn append(
+        &self,
+        src: &str,
+        client_name: &str,
+        flag: &[CreateFlag],
+    ) -> anyhow::Result<LastBlockWithStatus> {
+        self.invoke("append", AT_MOST_ONCE, |p| p.append(src, client_name, flag))
+    }
+
+    fn abandon_block(
+        &self,
+        b: &ExtendedBlock,
+        file_id: u64,
+        src: &str,
+        holder: &str,
+    ) -> anyhow::Result<()> {
+        self.invoke("abandonBlock", IDEMPOTENT, |p| {
+            p.abandon_block(b, file_id, src, holder)
+        })
+    }
+
+    fn add_block(
+        &self,
+        src: &str,
+        client_name: &str,
+        previous: Option<&ExtendedBlock>,
+        exclude_nodes: &[DatanodeInfo],
+        file_id: u64,
+    ) -> anyhow::Result<LocatedBlock> {
+        self.invoke("addBlock", IDEMPOTENT, |p| {
+            p.add_block(src, client_name, previous, exclude_nodes, file_id)
+        })
+    }
+
+    fn get_additional_datanode(
+        &self,
+        src: &str,
+        file_id: u64,
+        blk: &ExtendedBlock,
+        existings: &[DatanodeInfo],
+        existing_storage_ids: &[String],
+        excludes: &[DatanodeInfo],
+        num_additional_nodes: i32,
+        client_name: &str,
+    ) -> anyhow::Result<LocatedBlock> {
+        self.invoke("getAdditionalDatanode", IDEMPOTENT, |p| {
+            p.get_additional_datanode(
+                src,
+                file_id,
+                blk,
+                existings,
+                existing_storage_ids,
+                excludes,
+                num_additional_nodes,
+                client_name,
+            )
+        })
+    }
+
+    fn complete(
+        &self,
+        src: &str,
+        client_name: &str,
+        last: Option<&ExtendedBlock>,
+        file_id: u64,
+    ) -> anyhow::Result<bool> {
+        self.invoke("complete", IDEMPOTENT, |p| {
+            p.complete(src, client_name, last, file_id)
+        })
+    }
+
+    fn rename(&self, src: &str, dst: &str) -> anyhow::Result<bool> {
+        self.invoke("rename", AT_MOST_ONCE, |p| p.rename(src, dst))
+    }
+
+    fn concat(&self, trg: &str, srcs: &[String]) -> anyhow::Result<()> {
+        self.invoke("concat", AT_MOST_ONCE, |p| p.concat(trg, srcs))
+    }
+
+    fn rename2(&self, src: &str, dst: &str, options: &[Rename]) -> anyhow::Result<()> {
+        self.invoke("rename2", AT_MOST_ONCE, |p| p.rename2(src, dst, options))
+    }
+
+    fn truncate(&self, src: &str, new_length: i64, client_name: &str) -> anyhow::Result<bool> {
+        self.invoke("truncate", IDEMPOTENT, |p| {
+            p.truncate(src, new_length, client_name)
+        })
+    }
+
+    fn delete(&self, src: &str, recursive: bool) -> anyhow::Result<bool> {
+        self.invoke("delete", AT_MOST_ONCE, |p| p.delete(src, recursive))
+    }
+
+    fn mkdirs(
+        &self,
+        src: &str,
+        create_modes: &FsCreateModes,
+        create_parent: bool,
+    ) -> anyhow::Result<bool> {
+        self.invoke("mkdirs", IDEMPOTENT, |p| {
+            p.mkdirs(src, create_modes, create_parent)
+        })
+    }
+
+    fn set_replication(&self, src: &str, replication: i16) -> anyhow::Result<bool> {
+        self.invoke("setReplication", IDEMPOTENT, |p| {
+            p.set_replication(src, replication)
+        })
+    }
+
+    fn set_permission(&self, src: &str, permission: &FsPermission) -> anyhow::Result<()> {
+        self.invoke("setPermission", IDEMPOTENT, |p| {
+            p.set_permission(src, permission)
+        })
+    }
+
+    fn set_owner(
+        &self,
+        src: &str,
+        username: Option<&str>,
+        groupname: Option<&str>,
+    ) -> anyhow::Result<()> {
+        self.invoke("setOwner", IDEMPOTENT, |p| {
+            p.set_owner(src, username, groupname)
+        })
+    }
+
+    fn get_listing(
+        &self,
+        src: &str,
+        start_after: &[u8],
+        need_location: bool,
+    ) -> anyhow::Result<Option<DirectoryListing>> {
+        self.invoke("getListing", IDEMPOTENT, |p| {
+            p.get_listing(src, start_after, need_location)
+        })
+    }
+
+    fn get_batched_listing(
+        &self,
+        srcs: &[String],
+        start_after: &[u8],
+        need_location: bool,
+    ) -> anyhow::Result<BatchedDirectoryListing> {
+        self.invoke("getBatchedListing", IDEMPOTENT, |p| {
+            p.get_batched_listing(srcs, start_after, need_location)
+        })
+    }
+
+    fn renew_lease(&self, client_name: &str) -> anyhow::Result<()> {
+        self.invoke("renewLease", IDEMPOTENT, |p| p.renew_lease(client_name))
+    }
+
+    fn get_file_info(&self, src: &str) -> anyhow::Result<Option<HdfsFileStatus>> {
+        self.invoke("getFileInfo", IDEMPOTENT, |p| p.get_file_info(src))
+    }
+
+    fn get_located_file_info(
+        &self,
+        src: &str,
+        need_block_token: bool,
+    ) -> anyhow::Result<Option<HdfsFileStatus>> {
+        self.invoke("getLocatedFileInfo", IDEMPOTENT, |p| {
+            p.get_located_file_info(src, need_block_token)
+        })
+    }
+
+    fn get_stats(&self) -> anyhow::Result<Vec<i64>> {
+        self.invoke("getStats", IDEMPOTENT, |p| p.get_stats())
+    }
+
+    fn get_content_summary(&self, path: &str) -> anyhow::Result<ContentSummary> {
+        self.invoke("getContentSummary", IDEMPOTENT, |p| {
+            p.get_content_summary(path)
+        })
+    }
+
+    fn fsync(
+        &self,
+        src: &str,
+        file_id: u64,
+        client: &str,
+        last_block_length: i64,
+    ) -> anyhow::Result<()> {
+        self.invoke("fsync", IDEMPOTENT, |p| {
+            p.fsync(src, file_id, client, last_block_length)
+        })
+    }
+
+    fn set_times(&self, src: &str, mtime: i64, atime: i64) -> anyhow::Result<()> {
+        self.invoke("setTimes", IDEMPOTENT, |p| p.set_times(src, mtime, atime))
+    }
+
+    fn update_block_for_pipeline(
+        &self,
+        block: &ExtendedBlock,
+        client_name: &str,
+    ) -> anyhow::Result<LocatedBlock> {
+        self.invoke("updateBlockForPipeline", IDEMPOTENT, |p| {
+            p.update_block_for_pipeline(block, client_name)
+        })
+    }
+
+    fn update_pipeline(
+        &self,
+        client_name: &str,
+        old_block: &ExtendedBlock,
+        new_block: &ExtendedBlock,
+        new_nodes: &[DatanodeInfo],
+        new_storage_ids: &[String],
+    ) -> anyhow::Result<()> {
+        self.invoke("updatePipeline", AT_MOST_ONCE, |p| {
+            p.update_pipeline(
+                client_name,
+                old_block,
+                new_block,
+                new_nodes,
+                new_storage_ids,
+            )
+        })
+    }
+}
+
+/// The asynchronous NN proxy retrying the failed calls of `T`, with the
+/// same retry policies as the synchronous calls.
+#[cfg(feature = "async")]
+impl<T: AsyncClientProtocol> AsyncClientProtocol for RetryInvocationHandler<T> {
+    fn rename2_async<'a>(
+        &'a self,
+        src: &'a str,
+        dst: &'a str,
+        options: &'a [Rename],
+    ) -> ProtocolFuture<'a, ()> {
+        Box::pin(self.invoke_async("rename2", AT_MOST_ONCE, move |p| {
+            p.rename2_async(src, dst, options)
+        }))
+    }
+
+    fn delete_async<'a>(&'a self, src: &'a str, recursive: bool) -> ProtocolFuture<'a, bool> {
+        Box::pin(self.invoke_async("delete", AT_MOST_ONCE, move |p| {
+            p.delete_async(src, recursive)
+        }))
+    }
+
+    fn mkdirs_async<'a>(
+        &'a self,
+        src: &'a str,
+        create_modes: &'a FsCreateModes,
+        create_parent: bool,
+    ) -> ProtocolFuture<'a, bool> {
+        Box::pin(self.invoke_async("mkdirs", IDEMPOTENT, move |p| {
+            p.mkdirs_async(src, create_modes, create_parent)
+        }))
+    }
+
+    fn set_replication_async<'a>(
+        &'a self,
+        src: &'a str,
+        replication: i16,
+    ) -> ProtocolFuture<'a, bool> {
+        Box::pin(self.invoke_async("setReplication", IDEMPOTENT, move |p| {
+            p.set_replication_async(src, replication)
+        }))
+    }
+
+    fn set_permission_async<'a>(
+        &'a self,
+        src: &'a str,
+        permission: &'a FsPermission,
+    ) -> ProtocolFuture<'a, ()> {
+        Box::pin(self.invoke_async("setPermission", IDEMPOTENT, move |p| {
+            p.set_permission_async(src, permission)
+        }))
+    }
+
+    fn set_owner_async<'a>(
+        &'a self,
+        src: &'a str,
+        username: Option<&'a str>,
+        groupname: Option<&'a str>,
+    ) -> ProtocolFuture<'a, ()> {
+        Box::pin(self.invoke_async("setOwner", IDEMPOTENT, move |p| {
+            p.set_owner_async(src, username, groupname)
+        }))
+    }
+
+    fn get_listing_async<'a>(
+        &'a self,
+        src: &'a str,
+        start_after: &'a [u8],
+        need_location: bool,
+    ) -> ProtocolFuture<'a, Option<DirectoryListing>> {
+        Box::pin(self.invoke_async("getListing", IDEMPOTENT, move |p| {
+            p.get_listing_async(src, start_after, need_location)
+        }))
+    }
+
+    fn get_file_info_async<'a>(
+        &'a self,
+        src: &'a str,
+    ) -> ProtocolFuture<'a, Option<HdfsFileStatus>> {
+        Box::pin(self.invoke_async("getFileInfo", IDEMPOTENT, move |p| {
+            p.get_file_info_async(src)
+        }))
+    }
+
+    fn get_content_summary_async<'a>(
+        &'a self,
+        path: &'a str,
+    ) -> ProtocolFuture<'a, ContentSummary> {
+        Box::pin(
+            self.invoke_async("getContentSummary", IDEMPOTENT, move |p| {
+                p.get_content_summary_async(path)
+            }),
+        )
+    }
+
+    fn set_times_async<'a>(
+        &'a self,
+        src: &'a str,
+        mtime: i64,
+        atime: i64,
+    ) -> ProtocolFuture<'a, ()> {
+        Box::pin(self.invoke_async("setTimes", IDEMPOTENT, move |p| {
+            p.set_times_async(src, mtime, atime)
+        }))
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use anyhow::Error;
+
+    macro_rules! mock_client_protocol {
+        ($(fn $name:ident(&self $(, $arg:ident: $ty:ty)*) -> $ret:ty;)*) => {
+            /// A NameNode answering the calls of a test with the handlers it
+            /// sets, failing the calls of the methods without a handler.
+            #[allow(clippy::type_complexity)]
+            #[derive(Default)]
+            pub(crate) struct MockClientProtocol {
+                $(pub $name: Option<Box<dyn Fn($($ty),*) -> anyhow::Result<$ret> + Send + Sync>>,)*
+            }
+
+            impl ClientProtocol for MockClientProtocol {
+                $(fn $name(&self $(, $arg: $ty)*) -> anyhow::Result<$ret> {
+                    match &self.$name {
+                        Some(handler) => handler($($arg),*),
+                        None => Err(Error::msg(concat!("Unexpected call: ", stringify!($name)))),
+                    }
+                })*
+            }
+        };
+    }
+
+    mock_client_protocol! {
+        fn get_block_locations(&self, src: &str, offset: i64, length: i64) -> Option<LocatedBlocks>;
+        fn create(
+            &self,
+            src: &str,
+            create_modes: &FsCreateModes,
+            client_name: &str,
+            flag: &[CreateFlag],
+            create_parent: bool,
+            replication: i16,
+            block_size: i64
+        ) -> Option<HdfsFileStatus>;
+        fn append(&self, src: &str, client_name: &str, flag: &[CreateFlag]) -> LastBlockWithStatus;
+        fn abandon_block(&self, b: &ExtendedBlock, file_id: u64, src: &str, holder: &str) -> ();
+        fn add_block(
+            &self,
+            src: &str,
+            client_name: &str,
+            previous: Option<&ExtendedBlock>,
+            exclude_nodes: &[DatanodeInfo],
+            file_id: u64
+        ) -> LocatedBlock;
+        fn get_additional_datanode(
+            &self,
+            src: &str,
+            file_id: u64,
+            blk: &ExtendedBlock,
+            existings: &[DatanodeInfo],
+            existing_storage_ids: &[String],
+            excludes: &[DatanodeInfo],
+            num_additional_nodes: i32,
+            client_name: &str
+        ) -> LocatedBlock;
+        fn complete(
+            &self,
+            src: &str,
+            client_name: &str,
+            last: Option<&ExtendedBlock>,
+            file_id: u64
+        ) -> bool;
+        fn rename(&self, src: &str, dst: &str) -> bool;
+        fn concat(&self, trg: &str, srcs: &[String]) -> ();
+        fn rename2(&self, src: &str, dst: &str, options: &[Rename]) -> ();
+        fn truncate(&self, src: &str, new_length: i64, client_name: &str) -> bool;
+        fn delete(&self, src: &str, recursive: bool) -> bool;
+        fn mkdirs(&self, src: &str, create_modes: &FsCreateModes, create_parent: bool) -> bool;
+        fn set_replication(&self, src: &str, replication: i16) -> bool;
+        fn set_permission(&self, src: &str, permission: &FsPermission) -> ();
+        fn set_owner(&self, src: &str, username: Option<&str>, groupname: Option<&str>) -> ();
+        fn get_listing(
+            &self,
+            src: &str,
+            start_after: &[u8],
+            need_location: bool
+        ) -> Option<DirectoryListing>;
+        fn get_batched_listing(
+            &self,
+            srcs: &[String],
+            start_after: &[u8],
+            need_location: bool
+        ) -> BatchedDirectoryListing;
+        fn renew_lease(&self, client_name: &str) -> ();
+        fn get_file_info(&self, src: &str) -> Option<HdfsFileStatus>;
+        fn get_located_file_info(&self, src: &str, need_block_token: bool) -> Option<HdfsFileStatus>;
+        fn get_stats(&self) -> Vec<i64>;
+        fn get_content_summary(&self, path: &str) -> ContentSummary;
+        fn fsync(&self, src: &str, file_id: u64, client: &str, last_block_length: i64) -> ();
+        fn set_times(&self, src: &str, mtime: i64, atime: i64) -> ();
+        fn update_block_for_pipeline(&self, block: &ExtendedBlock, client_name: &str) -> LocatedBlock;
+        fn update_pipeline(
+            &self,
+            client_name: &str,
+            old_block: &ExtendedBlock,
+            new_block: &ExtendedBlock,
+            new_nodes: &[DatanodeInfo],
+            new_storage_ids: &[String]
+        ) -> ();
+    }
+
+    /// The asynchronous calls of the mock are answered by the handlers of
+    /// the synchronous ones.
+    #[cfg(feature = "async")]
+    impl AsyncClientProtocol for MockClientProtocol {
+        fn rename2_async<'a>(
+            &'a self,
+            src: &'a str,
+            dst: &'a str,
+            options: &'a [Rename],
+        ) -> ProtocolFuture<'a, ()> {
+            Box::pin(async move { self.rename2(src, dst, options) })
+        }
+
+        fn delete_async<'a>(&'a self, src: &'a str, recursive: bool) -> ProtocolFuture<'a, bool> {
+            Box::pin(async move { self.delete(src, recursive) })
+        }
+
+        fn mkdirs_async<'a>(
+            &'a self,
+            src: &'a str,
+            create_modes: &'a FsCreateModes,
+            create_parent: bool,
+        ) -> ProtocolFuture<'a, bool> {
+            Box::pin(async move { self.mkdirs(src, create_modes, create_parent) })
+        }
+
+        fn set_replication_async<'a>(
+            &'a self,
+            src: &'a str,
+            replication: i16,
+        ) -> ProtocolFuture<'a, bool> {
+            Box::pin(async move { self.set_replication(src, replication) })
+        }
+
+        fn set_permission_async<'a>(
+            &'a self,
+            src: &'a str,
+            permission: &'a FsPermission,
+        ) -> ProtocolFuture<'a, ()> {
+            Box::pin(async move { self.set_permission(src, permission) })
+        }
+
+        fn set_owner_async<'a>(
+            &'a self,
+            src: &'a str,
+            username: Option<&'a str>,
+            groupname: Option<&'a str>,
+        ) -> ProtocolFuture<'a, ()> {
+            Box::pin(async move { self.set_owner(src, username, groupname) })
+        }
+
+        fn get_listing_async<'a>(
+            &'a self,
+            src: &'a str,
+            start_after: &'a [u8],
+            need_location: bool,
+        ) -> ProtocolFuture<'a, Option<DirectoryListing>> {
+            Box::pin(async move { self.get_listing(src, start_after, need_location) })
+        }
+
+        fn get_file_info_async<'a>(
+            &'a self,
+            src: &'a str,
+        ) -> ProtocolFuture<'a, Option<HdfsFileStatus>> {
+            Box::pin(async move { self.get_file_info(src) })
+        }
+
+        fn get_content_summary_async<'a>(
+            &'a self,
+            path: &'a str,
+        ) -> ProtocolFuture<'a, ContentSummary> {
+            Box::pin(async move { self.get_content_summary(path) })
+        }
+
+        fn set_times_async<'a>(
+            &'a self,
+            src: &'a str,
+            mtime: i64,
+            atime: i64,
+        ) -> ProtocolFuture<'a, ()> {
+            Box::pin(async move { self.set_times(src, mtime, atime) })
+        }
+    }
 }
@@ -0,0 +1,107 @@
+use crate::hdfs::DatanodeIdProto;
+use std::fmt;
+
+/// This class represents the primary identifier for a Datanode.
+/// Datanodes are identified by how they can be contacted (hostname
+/// and ports) and their storage ID, a unique number that associates
+/// the Datanodes blocks with a particular Datanode.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct DatanodeID {
+    // IP address
+    ip_addr: String,
+    // hostname claimed by datanode
+    host_name: String,
+    // UUID identifying a given datanode
+    datanode_uuid: String,
+    // data streaming port
+    xfer_port: i32,
+    // info server port
+    info_port: i32,
+    // info server port
+    info_secure_port: i32,
+    // IPC server port
+    ipc_port: i32,
+}
+
+impl DatanodeID {
+    pub fn get_ip_addr(&self) -> &str {
+        &self.ip_addr
+    }
+
+    pub fn get_host_name(&self) -> &str {
+        &self.host_name
+    }
+
+    pub fn get_datanode_uuid(&self) -> &str {
+        &self.datanode_uuid
+    }
+
+    /// Returns IP:xferPort string
+    pub fn get_xfer_addr(&self) -> String {
+        format!("{}:{}", self.ip_addr, self.xfer_port)
+    }
+
+    /// Returns IP:ipcPort string
+    pub fn get_ipc_addr(&self) -> String {
+        format!("{}:{}", self.ip_addr, self.ipc_port)
+    }
+
+    /// Returns hostname:xferPort
+    pub fn get_xfer_addr_with_hostname(&self) -> String {
+        format!("{}:{}", self.host_name, self.xfer_port)
+    }
+
+    /// Returns data streaming port
+    pub fn get_xfer_port(&self) -> i32 {
+        self.xfer_port
+    }
+
+    /// Returns infoPort (the port at which the HTTP server bound to)
+    pub fn get_info_port(&self) -> i32 {
+        self.info_port
+    }
+
+    /// Returns infoSecurePort (the port at which the HTTPS server bound to)
+    pub fn get_info_secure_port(&self) -> i32 {
+        self.info_secure_port
+    }
+
+    /// Returns ipcPort (the port at which the IPC server bound to)
+    pub fn get_ipc_port(&self) -> i32 {
+        self.ipc_port
+    }
+}
+
+impl fmt::Display for DatanodeID {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.get_xfer_addr())
+    }
+}
+
+impl From<&DatanodeIdProto> for DatanodeID {
+    fn from(dn: &DatanodeIdProto) -> Self {
+        Self {
+            ip_addr: dn.ip_addr.to_owned(),
+            host_name: dn.host_name.to_owned(),
+            datanode_uuid: dn.datanode_uuid.to_owned(),
+            xfer_port: dn.xfer_port as i32,
+            info_port: dn.info_port as i32,
+            info_secure_port: dn.info_secure_port.unwrap_or_default() as i32,
+            ipc_port: dn.ipc_port as i32,
+        }
+    }
+}
+
+impl From<&DatanodeID> for DatanodeIdProto {
+    fn from(dn: &DatanodeID) -> Self {
+        DatanodeIdProto {
+            ip_addr: dn.ip_addr.to_owned(),
+            host_name: dn.host_name.to_owned(),
+            datanode_uuid: dn.datanode_uuid.to_owned(),
+            xfer_port: dn.xfer_port as u32,
+            info_port: dn.info_port as u32,
+            ipc_port: dn.ipc_port as u32,
+            info_secure_port: Some(dn.info_secure_port as u32),
+        }
+    }
+}
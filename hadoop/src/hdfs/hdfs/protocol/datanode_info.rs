@@ -0,0 +1,198 @@
+use super::DatanodeID;
+use crate::{
+    common::net::NetworkTopology,
+    hdfs::{datanode_info_proto::AdminState, DatanodeInfoProto},
+};
+use std::{fmt, ops::Deref};
+
+/// The administrative states of a datanode.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum AdminStates {
+    #[default]
+    Normal,
+    DecommissionInprogress,
+    Decommissioned,
+    EnteringMaintenance,
+    InMaintenance,
+}
+
+impl fmt::Display for AdminStates {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = match self {
+            Self::Normal => "In Service",
+            Self::DecommissionInprogress => "Decommission In Progress",
+            Self::Decommissioned => "Decommissioned",
+            Self::EnteringMaintenance => "Entering Maintenance",
+            Self::InMaintenance => "In Maintenance",
+        };
+        f.write_str(value)
+    }
+}
+
+impl From<AdminState> for AdminStates {
+    fn from(state: AdminState) -> Self {
+        match state {
+            AdminState::Normal => Self::Normal,
+            AdminState::DecommissionInprogress => Self::DecommissionInprogress,
+            AdminState::Decommissioned => Self::Decommissioned,
+            AdminState::EnteringMaintenance => Self::EnteringMaintenance,
+            AdminState::InMaintenance => Self::InMaintenance,
+        }
+    }
+}
+
+/// This class extends the primary identifier of a Datanode with ephemeral
+/// state, eg usage information, current administrative state, and the
+/// network location that is communicated to clients.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DatanodeInfo {
+    id: DatanodeID,
+    capacity: i64,
+    dfs_used: i64,
+    non_dfs_used: i64,
+    remaining: i64,
+    block_pool_used: i64,
+    cache_capacity: i64,
+    cache_used: i64,
+    last_update: i64,
+    xceiver_count: i32,
+    location: String,
+    upgrade_domain: Option<String>,
+    admin_state: AdminStates,
+}
+
+impl DatanodeInfo {
+    /// The raw capacity.
+    pub fn get_capacity(&self) -> i64 {
+        self.capacity
+    }
+
+    /// The used space by the data node.
+    pub fn get_dfs_used(&self) -> i64 {
+        self.dfs_used
+    }
+
+    /// The used space by the data node.
+    pub fn get_non_dfs_used(&self) -> i64 {
+        self.non_dfs_used
+    }
+
+    /// The raw free space.
+    pub fn get_remaining(&self) -> i64 {
+        self.remaining
+    }
+
+    /// Used space by the block pool.
+    pub fn get_block_pool_used(&self) -> i64 {
+        self.block_pool_used
+    }
+
+    /// The cache capacity of the datanode.
+    pub fn get_cache_capacity(&self) -> i64 {
+        self.cache_capacity
+    }
+
+    /// The amount of cache used by the datanode.
+    pub fn get_cache_used(&self) -> i64 {
+        self.cache_used
+    }
+
+    /// The time when this information was accurate.
+    pub fn get_last_update(&self) -> i64 {
+        self.last_update
+    }
+
+    /// Number of active connections.
+    pub fn get_xceiver_count(&self) -> i32 {
+        self.xceiver_count
+    }
+
+    /// Network location of the datanode.
+    pub fn get_network_location(&self) -> &str {
+        &self.location
+    }
+
+    /// The upgrade domain of the datanode.
+    pub fn get_upgrade_domain(&self) -> Option<&str> {
+        self.upgrade_domain.as_deref()
+    }
+
+    /// Retrieves the admin state of this node.
+    pub fn get_admin_state(&self) -> AdminStates {
+        self.admin_state
+    }
+
+    /// Returns true if the node is in the process of being decommissioned
+    pub fn is_decommission_in_progress(&self) -> bool {
+        self.admin_state == AdminStates::DecommissionInprogress
+    }
+
+    /// Returns true if the node has been decommissioned.
+    pub fn is_decommissioned(&self) -> bool {
+        self.admin_state == AdminStates::Decommissioned
+    }
+}
+
+impl Deref for DatanodeInfo {
+    type Target = DatanodeID;
+
+    fn deref(&self) -> &Self::Target {
+        &self.id
+    }
+}
+
+impl fmt::Display for DatanodeInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.id.fmt(f)
+    }
+}
+
+impl From<&DatanodeInfoProto> for DatanodeInfo {
+    fn from(di: &DatanodeInfoProto) -> Self {
+        Self {
+            id: DatanodeID::from(&di.id),
+            capacity: di.capacity.unwrap_or_default() as i64,
+            dfs_used: di.dfs_used.unwrap_or_default() as i64,
+            non_dfs_used: di.non_dfs_used.unwrap_or_default() as i64,
+            remaining: di.remaining.unwrap_or_default() as i64,
+            block_pool_used: di.block_pool_used.unwrap_or_default() as i64,
+            cache_capacity: di.cache_capacity.unwrap_or_default() as i64,
+            cache_used: di.cache_used.unwrap_or_default() as i64,
+            last_update: di.last_update.unwrap_or_default() as i64,
+            xceiver_count: di.xceiver_count.unwrap_or_default() as i32,
+            location: di
+                .location
+                .to_owned()
+                .unwrap_or_else(|| NetworkTopology::DEFAULT_RACK.to_owned()),
+            upgrade_domain: di.upgrade_domain.to_owned(),
+            admin_state: AdminStates::from(di.admin_state()),
+        }
+    }
+}
+
+impl From<&DatanodeInfo> for DatanodeInfoProto {
+    fn from(di: &DatanodeInfo) -> Self {
+        DatanodeInfoProto {
+            id: (&di.id).into(),
+            capacity: Some(di.capacity as u64),
+            dfs_used: Some(di.dfs_used as u64),
+            remaining: Some(di.remaining as u64),
+            block_pool_used: Some(di.block_pool_used as u64),
+            last_update: Some(di.last_update as u64),
+            xceiver_count: Some(di.xceiver_count as u32),
+            location: Some(di.location.to_owned()),
+            non_dfs_used: Some(di.non_dfs_used as u64),
+            admin_state: Some(match di.admin_state {
+                AdminStates::Normal => AdminState::Normal,
+                AdminStates::DecommissionInprogress => AdminState::DecommissionInprogress,
+                AdminStates::Decommissioned => AdminState::Decommissioned,
+                AdminStates::EnteringMaintenance => AdminState::EnteringMaintenance,
+                AdminStates::InMaintenance => AdminState::InMaintenance,
+            } as i32),
+            cache_capacity: Some(di.cache_capacity as u64),
+            cache_used: Some(di.cache_used as u64),
+            upgrade_domain: di.upgrade_domain.to_owned(),
+            ..Default::default()
+        }
+    }
+}
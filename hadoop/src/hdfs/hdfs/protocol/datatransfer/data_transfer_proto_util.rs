@@ -0,0 +1,78 @@
+use crate::{
+    common::{
+        io::IOException,
+        util::{DataChecksum, DataChecksumType},
+        TokenProto,
+    },
+    hdfs::{
+        hdfs::protocol::ExtendedBlock, BaseHeaderProto, BlockOpResponseProto, ChecksumProto,
+        ChecksumTypeProto, ClientOperationHeaderProto, Status,
+    },
+};
+use anyhow::Error;
+
+/// Static utilities for dealing with the protocol buffers used by the
+/// Data Transfer Protocol.
+pub(crate) struct DataTransferProtoUtil;
+
+impl DataTransferProtoUtil {
+    pub fn to_proto(checksum: &DataChecksum) -> ChecksumProto {
+        let checksum_type = match checksum.get_checksum_type() {
+            DataChecksumType::Null => ChecksumTypeProto::ChecksumNull,
+            DataChecksumType::Crc32 => ChecksumTypeProto::ChecksumCrc32,
+            DataChecksumType::Crc32c => ChecksumTypeProto::ChecksumCrc32c,
+        };
+        ChecksumProto {
+            r#type: checksum_type as i32,
+            bytes_per_checksum: checksum.get_bytes_per_checksum() as u32,
+        }
+    }
+
+    pub fn from_proto(proto: &ChecksumProto) -> Option<DataChecksum> {
+        DataChecksum::new_data_checksum(proto.r#type().into(), proto.bytes_per_checksum as i32)
+    }
+
+    pub fn build_client_header(
+        blk: &ExtendedBlock,
+        client: &str,
+        block_token: &TokenProto,
+    ) -> ClientOperationHeaderProto {
+        ClientOperationHeaderProto {
+            base_header: Self::build_base_header(blk, block_token),
+            client_name: client.to_owned(),
+        }
+    }
+
+    pub fn build_base_header(blk: &ExtendedBlock, block_token: &TokenProto) -> BaseHeaderProto {
+        // TODO: add the trace info of the current span
+        BaseHeaderProto {
+            block: blk.into(),
+            token: Some(block_token.to_owned()),
+            trace_info: None,
+        }
+    }
+
+    pub fn check_block_op_status(
+        response: &BlockOpResponseProto,
+        log_info: &str,
+    ) -> anyhow::Result<()> {
+        let status = response.status();
+        if status == Status::Success {
+            return Ok(());
+        }
+        let message = response.message.as_deref().unwrap_or_default();
+        if status == Status::ErrorAccessToken {
+            Err(Error::new(IOException::InvalidBlockToken(format!(
+                "Got access token error, status message {}, {}",
+                message, log_info
+            ))))
+        } else {
+            Err(Error::msg(format!(
+                "Got error, status={}, status message {}, {}",
+                status.as_str_name(),
+                message,
+                log_info
+            )))
+        }
+    }
+}
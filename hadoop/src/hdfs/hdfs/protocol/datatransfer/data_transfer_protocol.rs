@@ -0,0 +1,624 @@
+use crate::{
+    common::{fs::StorageType, TokenProto},
+    hdfs::{
+        hdfs::protocol::{DatanodeInfo, ExtendedBlock},
+        op_write_block_proto::BlockConstructionStage,
+        BlockChecksumOptionsProto, ChecksumProto,
+    },
+};
+
+/// Version for data transfers between clients and datanodes
+/// This should change when serialization of DatanodeInfo, not just
+/// when protocol changes. It is not very obvious.
+///
+/// Version 28:
+///    Declare methods in DataTransferProtocol interface.
+pub(crate) const DATA_TRANSFER_VERSION: i16 = 28;
+
+/// Transfer data to/from datanode using a streaming protocol.
+pub(crate) trait DataTransferProtocol {
+    /// Read a block.
+    ///
+    /// `block_offset` is the offset of the data to read within the block and
+    /// `length` the maximum number of bytes to read. `send_checksum` tells
+    /// whether the datanode should send checksums.
+    fn read_block(
+        &mut self,
+        blk: &ExtendedBlock,
+        block_token: &TokenProto,
+        client_name: &str,
+        block_offset: i64,
+        length: i64,
+        send_checksum: bool,
+    ) -> anyhow::Result<()>;
+
+    /// Write a block to a datanode pipeline.
+    /// The receiver datanode of this call is the next datanode in the pipeline.
+    /// The other downstream datanodes are specified by the targets parameter.
+    /// Note that the receiver [`DatanodeInfo`] is not required in the
+    /// parameter list since the receiver datanode knows its info.  However, the
+    /// [`StorageType`] for storing the replica in the receiver datanode is a
+    /// parameter since the receiver datanode may support multiple storage types.
+    #[allow(clippy::too_many_arguments)]
+    fn write_block(
+        &mut self,
+        blk: &ExtendedBlock,
+        storage_type: StorageType,
+        block_token: &TokenProto,
+        client_name: &str,
+        targets: &[DatanodeInfo],
+        target_storage_types: &[StorageType],
+        stage: BlockConstructionStage,
+        pipeline_size: i32,
+        min_bytes_rcvd: i64,
+        max_bytes_rcvd: i64,
+        latest_generation_stamp: i64,
+        requested_checksum: &ChecksumProto,
+        storage_id: Option<&str>,
+        target_storage_ids: &[String],
+    ) -> anyhow::Result<()>;
+
+    /// Transfer a block to another datanode.
+    /// The block stage must be either `TransferRbw` or `TransferFinalized`.
+    fn transfer_block(
+        &mut self,
+        blk: &ExtendedBlock,
+        block_token: &TokenProto,
+        client_name: &str,
+        targets: &[DatanodeInfo],
+        target_storage_types: &[StorageType],
+        target_storage_ids: &[String],
+    ) -> anyhow::Result<()>;
+
+    /// Get block checksum (MD5 of CRC32).
+    ///
+    /// `block_checksum_options` determines how the block-level checksum is
+    /// computed from underlying block metadata.
+    fn block_checksum(
+        &mut self,
+        blk: &ExtendedBlock,
+        block_token: &TokenProto,
+        block_checksum_options: &BlockChecksumOptionsProto,
+    ) -> anyhow::Result<()>;
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::{
+        common::{
+            ipc::AuthProtocol,
+            rpc_response_header_proto::{RpcErrorCodeProto, RpcStatusProto},
+            rpc_sasl_proto::SaslState,
+            util::{DataChecksum, DataChecksumType},
+            RequestHeaderProto, RpcRequestHeaderProto, RpcResponseHeaderProto, RpcSaslProto,
+        },
+        hdfs::{
+            hdfs::{
+                protocol::datatransfer::{DataTransferProtoUtil, Op, PacketHeader, PacketReceiver},
+                protocolpb::PBHelperClient,
+            },
+            BlockChecksumTypeProto, BlockOpResponseProto, ChecksumTypeProto, DatanodeIdProto,
+            DatanodeInfoProto, GetReplicaVisibleLengthRequestProto,
+            GetReplicaVisibleLengthResponseProto, OpBlockChecksumProto,
+            OpBlockChecksumResponseProto, OpReadBlockProto, OpTransferBlockProto,
+            OpWriteBlockProto, PipelineAckProto, ReadOpChecksumInfoProto, Status,
+        },
+    };
+    use anyhow::Error;
+    use prost::Message;
+    use std::{
+        collections::HashMap,
+        io::{Read, Write},
+        net::{SocketAddr, TcpListener, TcpStream},
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc, Mutex,
+        },
+        thread::{self, JoinHandle},
+    };
+    use uuid::Uuid;
+
+    /// The number of chunks in the packets of a block read.
+    const READ_PACKET_CHUNKS: usize = 4;
+
+    #[derive(Default)]
+    struct FakeDataNodeState {
+        // the replicas by block id
+        blocks: HashMap<u64, Vec<u8>>,
+        // the offset of a byte of a replica sent altered, by block id
+        corrupt_bytes: HashMap<u64, usize>,
+        // the number of read block requests received
+        reads: usize,
+        // the number of write block connections still open
+        open_writes: usize,
+        // the stages of the write block requests received
+        write_stages: Vec<BlockConstructionStage>,
+        // the replica lengths after each packet asking for a sync
+        sync_lengths: Vec<usize>,
+        // the block ids and target addresses of the transfer block requests
+        // received
+        transfers: Vec<(u64, Vec<String>)>,
+        // the seqno of a packet whose ack reports the last datanode of the
+        // pipeline as failed
+        fail_ack: Option<i64>,
+    }
+
+    /// A DataNode storing the replicas of a test in memory and serving them
+    /// over the data transfer protocol on a local port. The DataNode does
+    /// not forward packets, it acks them on behalf of the whole pipeline.
+    /// The same port answers the ClientDatanodeProtocol calls over IPC.
+    pub(crate) struct FakeDataNode {
+        uuid: String,
+        addr: SocketAddr,
+        state: Arc<Mutex<FakeDataNodeState>>,
+        closed: Arc<AtomicBool>,
+        handle: Option<JoinHandle<()>>,
+    }
+
+    impl FakeDataNode {
+        /// Start a DataNode listening on a free local port.
+        pub fn start() -> anyhow::Result<Self> {
+            let listener = TcpListener::bind("127.0.0.1:0")?;
+            let addr = listener.local_addr()?;
+            let state = Arc::new(Mutex::new(FakeDataNodeState::default()));
+            let closed = Arc::new(AtomicBool::new(false));
+            let handle = {
+                let state = Arc::clone(&state);
+                let closed = Arc::clone(&closed);
+                thread::spawn(move || {
+                    for peer in listener.incoming() {
+                        if closed.load(Ordering::SeqCst) {
+                            return;
+                        }
+                        let Ok(peer) = peer else { continue };
+                        let state = Arc::clone(&state);
+                        thread::spawn(move || {
+                            if let Err(e) = Self::serve(peer, &state) {
+                                log::debug!("FakeDataNode: {}", e);
+                            }
+                        });
+                    }
+                })
+            };
+            Ok(Self {
+                uuid: Uuid::new_v4().to_string(),
+                addr,
+                state,
+                closed,
+                handle: Some(handle),
+            })
+        }
+
+        /// The info of this DataNode as the NameNode reports it.
+        pub fn get_datanode_info(&self) -> DatanodeInfoProto {
+            DatanodeInfoProto {
+                id: DatanodeIdProto {
+                    ip_addr: self.addr.ip().to_string(),
+                    host_name: "localhost".to_owned(),
+                    datanode_uuid: self.uuid.to_owned(),
+                    xfer_port: self.addr.port() as u32,
+                    ipc_port: self.addr.port() as u32,
+                    ..Default::default()
+                },
+                ..Default::default()
+            }
+        }
+
+        /// Get the replica of the block, if this DataNode has one.
+        pub fn get_block(&self, block_id: u64) -> Option<Vec<u8>> {
+            self.state.lock().unwrap().blocks.get(&block_id).cloned()
+        }
+
+        /// Store a replica of the block.
+        pub fn put_block(&self, block_id: u64, data: &[u8]) {
+            self.state
+                .lock()
+                .unwrap()
+                .blocks
+                .insert(block_id, data.to_vec());
+        }
+
+        /// Send the byte at `offset` of the replica of the block altered,
+        /// with the checksum of the original data.
+        pub fn corrupt_replica(&self, block_id: u64, offset: usize) {
+            self.state
+                .lock()
+                .unwrap()
+                .corrupt_bytes
+                .insert(block_id, offset);
+        }
+
+        /// Get the number of read block requests received so far.
+        pub fn get_reads(&self) -> usize {
+            self.state.lock().unwrap().reads
+        }
+
+        /// Get the number of write block connections still open.
+        pub fn get_open_writes(&self) -> usize {
+            self.state.lock().unwrap().open_writes
+        }
+
+        /// Get the stages of the write block requests received so far.
+        pub fn get_write_stages(&self) -> Vec<BlockConstructionStage> {
+            self.state.lock().unwrap().write_stages.clone()
+        }
+
+        /// Get the length of the replica after each packet asking for a sync.
+        pub fn get_sync_lengths(&self) -> Vec<usize> {
+            self.state.lock().unwrap().sync_lengths.clone()
+        }
+
+        /// Get the block ids and target addresses of the transfer block
+        /// requests received so far.
+        pub fn get_transfers(&self) -> Vec<(u64, Vec<String>)> {
+            self.state.lock().unwrap().transfers.clone()
+        }
+
+        /// Report the last datanode of the pipeline as failed in the ack of
+        /// the packet with the given seqno, once.
+        pub fn fail_ack(&self, seqno: i64) {
+            self.state.lock().unwrap().fail_ack = Some(seqno);
+        }
+
+        fn serve(mut peer: TcpStream, state: &Mutex<FakeDataNodeState>) -> anyhow::Result<()> {
+            let mut magic = [0; 4];
+            if peer.peek(&mut magic)? == magic.len() && &magic == b"hrpc" {
+                return Self::serve_ipc(peer, state);
+            }
+            let mut version = [0; 2];
+            peer.read_exact(&mut version)?;
+            if i16::from_be_bytes(version) != DATA_TRANSFER_VERSION {
+                return Err(Error::msg("Version Mismatch"));
+            }
+            let mut op = [0; 1];
+            peer.read_exact(&mut op)?;
+            let proto = PBHelperClient::vint_prefixed(&mut peer)?;
+            match op[0] {
+                code if code == Op::WriteBlock.code() => {
+                    let op = OpWriteBlockProto::decode(&proto[..])?;
+                    state.lock().unwrap().open_writes += 1;
+                    let result = Self::write_block(peer, state, &op);
+                    state.lock().unwrap().open_writes -= 1;
+                    result
+                }
+                code if code == Op::ReadBlock.code() => {
+                    Self::read_block(peer, state, &OpReadBlockProto::decode(&proto[..])?)
+                }
+                code if code == Op::TransferBlock.code() => {
+                    let op = OpTransferBlockProto::decode(&proto[..])?;
+                    let targets = op
+                        .targets
+                        .iter()
+                        .map(|t| format!("{}:{}", t.id.ip_addr, t.id.xfer_port))
+                        .collect();
+                    state
+                        .lock()
+                        .unwrap()
+                        .transfers
+                        .push((op.header.base_header.block.block_id, targets));
+                    Self::respond(
+                        &mut peer,
+                        &BlockOpResponseProto {
+                            status: Status::Success as i32,
+                            ..Default::default()
+                        },
+                    )
+                }
+                code if code == Op::BlockChecksum.code() => {
+                    Self::block_checksum(peer, state, &OpBlockChecksumProto::decode(&proto[..])?)
+                }
+                code => Err(Error::msg(format!("Unknown op {}", code))),
+            }
+        }
+
+        /// Answer the ClientDatanodeProtocol calls of an IPC connection.
+        fn serve_ipc(mut peer: TcpStream, state: &Mutex<FakeDataNodeState>) -> anyhow::Result<()> {
+            // "hrpc", version, service class and auth protocol
+            let mut connection_header = [0; 7];
+            peer.read_exact(&mut connection_header)?;
+            let sasl_call_id = AuthProtocol::Sasl.call_id();
+            if connection_header[6] as i8 == sasl_call_id {
+                // switch the client to simple authentication, as a datanode
+                // with security disabled does
+                Self::read_rpc_frame(&mut peer)?;
+                let header = RpcResponseHeaderProto {
+                    call_id: sasl_call_id as i32 as u32,
+                    status: RpcStatusProto::Success as i32,
+                    ..Default::default()
+                };
+                let response = RpcSaslProto {
+                    state: SaslState::Success as i32,
+                    ..Default::default()
+                };
+                Self::write_rpc_response(
+                    &mut peer,
+                    &header,
+                    &response.encode_length_delimited_to_vec(),
+                )?;
+            }
+            // the connection context
+            Self::read_rpc_frame(&mut peer)?;
+            loop {
+                let frame = Self::read_rpc_frame(&mut peer)?;
+                let mut payload = &frame[..];
+                let header = RpcRequestHeaderProto::decode_length_delimited(&mut payload)?;
+                if header.call_id < 0 {
+                    // a ping
+                    continue;
+                }
+                let request_header = RequestHeaderProto::decode_length_delimited(&mut payload)?;
+                let result = match request_header.method_name.as_str() {
+                    "getReplicaVisibleLength" => {
+                        let req =
+                            GetReplicaVisibleLengthRequestProto::decode_length_delimited(payload)?;
+                        let state = state.lock().unwrap();
+                        match state.blocks.get(&req.block.block_id) {
+                            Some(replica) => Ok(GetReplicaVisibleLengthResponseProto {
+                                length: replica.len() as u64,
+                            }
+                            .encode_length_delimited_to_vec()),
+                            None => Err((
+                                "org.apache.hadoop.hdfs.server.datanode.ReplicaNotFoundException",
+                                format!("Replica not found for blk_{}", req.block.block_id),
+                            )),
+                        }
+                    }
+                    method => Err((
+                        "org.apache.hadoop.ipc.RpcNoSuchMethodException",
+                        format!("Unknown method {}", method),
+                    )),
+                };
+                let mut response_header = RpcResponseHeaderProto {
+                    call_id: header.call_id as u32,
+                    status: RpcStatusProto::Success as i32,
+                    ..Default::default()
+                };
+                let response = match result {
+                    Ok(response) => response,
+                    Err((exception_class_name, error_msg)) => {
+                        response_header.status = RpcStatusProto::Error as i32;
+                        response_header.exception_class_name =
+                            Some(exception_class_name.to_owned());
+                        response_header.error_msg = Some(error_msg);
+                        response_header.error_detail =
+                            Some(RpcErrorCodeProto::ErrorApplication as i32);
+                        vec![]
+                    }
+                };
+                Self::write_rpc_response(&mut peer, &response_header, &response)?;
+            }
+        }
+
+        fn read_rpc_frame(peer: &mut TcpStream) -> anyhow::Result<Vec<u8>> {
+            let mut len = [0; 4];
+            peer.read_exact(&mut len)?;
+            let mut frame = vec![0; i32::from_be_bytes(len) as usize];
+            peer.read_exact(&mut frame)?;
+            Ok(frame)
+        }
+
+        fn write_rpc_response(
+            peer: &mut TcpStream,
+            header: &RpcResponseHeaderProto,
+            response: &[u8],
+        ) -> anyhow::Result<()> {
+            let mut buf = header.encode_length_delimited_to_vec();
+            buf.extend_from_slice(response);
+            peer.write_all(&(buf.len() as i32).to_be_bytes())?;
+            peer.write_all(&buf)?;
+            Ok(())
+        }
+
+        fn respond(peer: &mut TcpStream, response: &impl Message) -> anyhow::Result<()> {
+            peer.write_all(&response.encode_length_delimited_to_vec())?;
+            peer.flush()?;
+            Ok(())
+        }
+
+        fn write_block(
+            mut peer: TcpStream,
+            state: &Mutex<FakeDataNodeState>,
+            op: &OpWriteBlockProto,
+        ) -> anyhow::Result<()> {
+            let block_id = op.header.base_header.block.block_id;
+            let pipeline_size = op.targets.len() + 1;
+            state.lock().unwrap().write_stages.push(op.stage());
+            Self::respond(
+                &mut peer,
+                &BlockOpResponseProto {
+                    status: Status::Success as i32,
+                    ..Default::default()
+                },
+            )?;
+
+            let mut receiver = PacketReceiver::default();
+            loop {
+                receiver.receive_next_packet(&mut peer)?;
+                let header = receiver.get_header().unwrap();
+                let seqno = header.get_seqno();
+                let failed = {
+                    let mut state = state.lock().unwrap();
+                    let replica = state.blocks.entry(block_id).or_default();
+                    if header.get_data_len() > 0 {
+                        // a resent packet overwrites the data after its offset
+                        replica.truncate(header.get_offset_in_block() as usize);
+                        replica.extend_from_slice(receiver.get_data_slice());
+                    }
+                    let len = replica.len();
+                    if header.get_sync_block() {
+                        state.sync_lengths.push(len);
+                    }
+                    state.fail_ack.take_if(|s| *s == seqno).is_some()
+                };
+                let mut reply = vec![Status::Success as i32; pipeline_size];
+                if failed {
+                    reply[pipeline_size - 1] = Status::Error as i32;
+                }
+                Self::respond(
+                    &mut peer,
+                    &PipelineAckProto {
+                        seqno,
+                        reply,
+                        ..Default::default()
+                    },
+                )?;
+                if failed {
+                    // drop the packets in flight until the client gives up on
+                    // this pipeline
+                    while receiver.receive_next_packet(&mut peer).is_ok() {}
+                    return Ok(());
+                }
+                if header.is_last_packet_in_block() {
+                    return Ok(());
+                }
+            }
+        }
+
+        fn read_block(
+            mut peer: TcpStream,
+            state: &Mutex<FakeDataNodeState>,
+            op: &OpReadBlockProto,
+        ) -> anyhow::Result<()> {
+            let block_id = op.header.base_header.block.block_id;
+            let (replica, corrupt_byte) = {
+                let mut state = state.lock().unwrap();
+                state.reads += 1;
+                (
+                    state.blocks.get(&block_id).cloned(),
+                    state.corrupt_bytes.get(&block_id).copied(),
+                )
+            };
+            let Some(replica) = replica else {
+                return Self::respond(
+                    &mut peer,
+                    &BlockOpResponseProto {
+                        status: Status::Error as i32,
+                        message: Some(format!("Replica not found for blk_{}", block_id)),
+                        ..Default::default()
+                    },
+                );
+            };
+
+            // send whole chunks from the chunk holding the offset
+            let checksum = DataChecksum::new_data_checksum(DataChecksumType::Crc32c, 512).unwrap();
+            let bytes_per_checksum = checksum.get_bytes_per_checksum();
+            let offset = op.offset as usize;
+            let chunk_offset = offset - offset % bytes_per_checksum;
+            let end = (offset + op.len as usize)
+                .next_multiple_of(bytes_per_checksum)
+                .min(replica.len());
+            Self::respond(
+                &mut peer,
+                &BlockOpResponseProto {
+                    status: Status::Success as i32,
+                    read_op_checksum_info: Some(ReadOpChecksumInfoProto {
+                        checksum: DataTransferProtoUtil::to_proto(&checksum),
+                        chunk_offset: chunk_offset as u64,
+                    }),
+                    ..Default::default()
+                },
+            )?;
+
+            let mut seqno = 0;
+            let mut packet_offset = chunk_offset;
+            while packet_offset < end {
+                let packet_end = (packet_offset + READ_PACKET_CHUNKS * bytes_per_checksum).min(end);
+                let mut data = replica[packet_offset..packet_end].to_vec();
+                let mut checksums = vec![];
+                if op.send_checksums.unwrap_or(true) {
+                    checksum.calculate_chunked_sums(&data, &mut checksums);
+                }
+                if let Some(offset) = corrupt_byte {
+                    if (packet_offset..packet_end).contains(&offset) {
+                        data[offset - packet_offset] ^= 0xff;
+                    }
+                }
+                let mut buf = vec![];
+                PacketHeader::new(
+                    (4 + checksums.len() + data.len()) as i32,
+                    packet_offset as i64,
+                    seqno,
+                    false,
+                    data.len() as i32,
+                    false,
+                )
+                .put_in_buffer(&mut buf);
+                buf.extend_from_slice(&checksums);
+                buf.extend_from_slice(&data);
+                peer.write_all(&buf)?;
+                seqno += 1;
+                packet_offset = packet_end;
+            }
+            // the empty packet ending the read
+            let mut buf = vec![];
+            PacketHeader::new(4, end as i64, seqno, true, 0, false).put_in_buffer(&mut buf);
+            peer.write_all(&buf)?;
+            peer.flush()?;
+
+            // the client may close the connection without sending its status
+            let _ = PBHelperClient::vint_prefixed(&mut peer);
+            Ok(())
+        }
+
+        fn block_checksum(
+            mut peer: TcpStream,
+            state: &Mutex<FakeDataNodeState>,
+            op: &OpBlockChecksumProto,
+        ) -> anyhow::Result<()> {
+            let block = &op.header.block;
+            let replica = state.lock().unwrap().blocks.get(&block.block_id).cloned();
+            let Some(replica) = replica else {
+                return Self::respond(
+                    &mut peer,
+                    &BlockOpResponseProto {
+                        status: Status::Error as i32,
+                        message: Some(format!("Replica not found for blk_{}", block.block_id)),
+                        ..Default::default()
+                    },
+                );
+            };
+
+            // the block checksum covers the requested length of the replica,
+            // while the crcs per block count the chunks of the whole replica
+            let checksum = DataChecksum::new_data_checksum(DataChecksumType::Crc32c, 512).unwrap();
+            let bytes_per_checksum = checksum.get_bytes_per_checksum();
+            let len = (block.num_bytes.unwrap_or_default() as usize).min(replica.len());
+            let data = &replica[..len];
+            let options = op.block_checksum_options.clone().unwrap_or_default();
+            let block_checksum = match options.block_checksum_type() {
+                BlockChecksumTypeProto::Md5crc => {
+                    let mut crcs = vec![];
+                    checksum.calculate_chunked_sums(data, &mut crcs);
+                    md5::compute(crcs).0.to_vec()
+                }
+                BlockChecksumTypeProto::CompositeCrc => crc32c::crc32c(data).to_be_bytes().to_vec(),
+            };
+            Self::respond(
+                &mut peer,
+                &BlockOpResponseProto {
+                    status: Status::Success as i32,
+                    checksum_response: Some(OpBlockChecksumResponseProto {
+                        bytes_per_crc: bytes_per_checksum as u32,
+                        crc_per_block: replica.len().div_ceil(bytes_per_checksum) as u64,
+                        block_checksum,
+                        crc_type: Some(ChecksumTypeProto::ChecksumCrc32c as i32),
+                        block_checksum_options: Some(options),
+                    }),
+                    ..Default::default()
+                },
+            )
+        }
+    }
+
+    impl Drop for FakeDataNode {
+        fn drop(&mut self) {
+            self.closed.store(true, Ordering::SeqCst);
+            // wake up the listener so that it closes its port
+            let _ = TcpStream::connect(self.addr);
+            if let Some(handle) = self.handle.take() {
+                let _ = handle.join();
+            }
+        }
+    }
+}
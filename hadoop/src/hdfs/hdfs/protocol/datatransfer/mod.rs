@@ -0,0 +1,19 @@
+mod data_transfer_proto_util;
+mod data_transfer_protocol;
+mod op;
+mod packet_header;
+mod packet_receiver;
+mod pipeline_ack;
+mod replace_datanode_on_failure;
+mod sender;
+
+pub(crate) use data_transfer_proto_util::DataTransferProtoUtil;
+#[cfg(test)]
+pub(crate) use data_transfer_protocol::tests::FakeDataNode;
+pub(crate) use data_transfer_protocol::{DataTransferProtocol, DATA_TRANSFER_VERSION};
+pub(crate) use op::Op;
+pub(crate) use packet_header::{PacketHeader, PKT_MAX_HEADER_LEN};
+pub(crate) use packet_receiver::PacketReceiver;
+pub(crate) use pipeline_ack::PipelineAck;
+pub(crate) use replace_datanode_on_failure::ReplaceDatanodeOnFailure;
+pub(crate) use sender::Sender;
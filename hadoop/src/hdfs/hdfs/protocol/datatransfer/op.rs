@@ -0,0 +1,20 @@
+/// Operation
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Op {
+    WriteBlock,
+    ReadBlock,
+    BlockChecksum,
+    TransferBlock,
+}
+
+impl Op {
+    /// The code for this operation.
+    pub fn code(&self) -> u8 {
+        match self {
+            Self::WriteBlock => 80,
+            Self::ReadBlock => 81,
+            Self::BlockChecksum => 85,
+            Self::TransferBlock => 86,
+        }
+    }
+}
@@ -0,0 +1,158 @@
+use crate::hdfs::PacketHeaderProto;
+use anyhow::Error;
+use prost::Message;
+use std::fmt;
+
+/// The serialized size of a header proto with every field set, which is the
+/// largest a header can be as all of its fields are fixed-length.
+const MAX_PROTO_SIZE: usize = 27;
+
+pub(crate) const PKT_LENGTHS_LEN: usize = 4 + 2;
+pub(crate) const PKT_MAX_HEADER_LEN: usize = PKT_LENGTHS_LEN + MAX_PROTO_SIZE;
+
+/// Header data for each packet that goes through the read/write pipelines.
+/// Includes all of the information about the packet, excluding checksums and
+/// actual data.
+///
+/// This data includes:
+///  - the offset in bytes into the HDFS block of the data in this packet
+///  - the sequence number of this packet in the pipeline
+///  - whether or not this is the last packet in the pipeline
+///  - the length of the data in this packet
+///  - whether or not this packet should be synced by the DNs.
+///
+/// When serialized, this header is written out as a protocol buffer, preceded
+/// by a 4-byte integer representing the full packet length, and a 2-byte short
+/// representing the header length.
+#[derive(Clone, Debug)]
+pub(crate) struct PacketHeader {
+    packet_len: i32,
+    proto: PacketHeaderProto,
+}
+
+impl PacketHeader {
+    pub fn new(
+        packet_len: i32,
+        offset_in_block: i64,
+        seqno: i64,
+        last_packet_in_block: bool,
+        data_len: i32,
+        sync_block: bool,
+    ) -> Self {
+        Self {
+            packet_len,
+            proto: PacketHeaderProto {
+                offset_in_block,
+                seqno,
+                last_packet_in_block,
+                data_len,
+                // Only set syncBlock if it is specified.
+                // This is wire-incompatible with Hadoop 2.0.0-alpha due to HDFS-3721
+                // because it changes the length of the packet header, and BlockReceiver
+                // in that version did not support variable-length headers.
+                sync_block: sync_block.then_some(true),
+            },
+        }
+    }
+
+    /// Parse the header from the length of the packet and the serialized
+    /// header proto.
+    pub fn from_data(packet_len: i32, header_data: &[u8]) -> anyhow::Result<Self> {
+        Ok(Self {
+            packet_len,
+            proto: PacketHeaderProto::decode(header_data)?,
+        })
+    }
+
+    pub fn get_data_len(&self) -> i32 {
+        self.proto.data_len
+    }
+
+    pub fn is_last_packet_in_block(&self) -> bool {
+        self.proto.last_packet_in_block
+    }
+
+    pub fn get_seqno(&self) -> i64 {
+        self.proto.seqno
+    }
+
+    pub fn get_offset_in_block(&self) -> i64 {
+        self.proto.offset_in_block
+    }
+
+    #[cfg(test)]
+    pub fn get_sync_block(&self) -> bool {
+        self.proto.sync_block.unwrap_or_default()
+    }
+
+    /// Write the lengths and the header proto of this packet to `buf`.
+    pub fn put_in_buffer(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&self.packet_len.to_be_bytes());
+        buf.extend_from_slice(&(self.proto.encoded_len() as i16).to_be_bytes());
+        self.proto.encode_raw(buf);
+    }
+
+    /// Perform a sanity check on the packet, returning an error
+    /// if it's invalid.
+    pub fn sanity_check(&self, last_seq_no: i64) -> anyhow::Result<()> {
+        let valid = if self.proto.data_len <= 0 && !self.proto.last_packet_in_block {
+            // We should only have a non-positive data length for the last packet
+            false
+        } else if self.proto.last_packet_in_block && self.proto.data_len != 0 {
+            // The last packet should not contain data
+            false
+        } else {
+            // Seqnos should always increase by 1 with each packet received
+            self.proto.seqno == last_seq_no + 1
+        };
+        if valid {
+            Ok(())
+        } else {
+            Err(Error::msg(format!(
+                "BlockReader: error in packet header {}",
+                self
+            )))
+        }
+    }
+}
+
+impl fmt::Display for PacketHeader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "PacketHeader with packetLen={} header data: {:?}",
+            self.packet_len, self.proto
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_max_header_len() {
+        let header = PacketHeader::new(0, i64::MAX, i64::MAX, true, i32::MAX, true);
+        let mut buf = vec![];
+        header.put_in_buffer(&mut buf);
+        assert_eq!(PKT_MAX_HEADER_LEN, buf.len());
+    }
+
+    #[test]
+    fn test_put_in_buffer() {
+        let header = PacketHeader::new(4 + 512, 1024, 3, false, 512, false);
+        let mut buf = vec![];
+        header.put_in_buffer(&mut buf);
+        assert_eq!(516, i32::from_be_bytes([buf[0], buf[1], buf[2], buf[3]]));
+        let header_len = i16::from_be_bytes([buf[4], buf[5]]) as usize;
+        assert_eq!(PKT_LENGTHS_LEN + header_len, buf.len());
+
+        let parsed = PacketHeader::from_data(516, &buf[PKT_LENGTHS_LEN..]).unwrap();
+        assert_eq!(1024, parsed.get_offset_in_block());
+        assert_eq!(3, parsed.get_seqno());
+        assert_eq!(512, parsed.get_data_len());
+        assert!(!parsed.is_last_packet_in_block());
+        assert!(parsed.sanity_check(2).is_ok());
+        assert!(parsed.sanity_check(3).is_err());
+    }
+}
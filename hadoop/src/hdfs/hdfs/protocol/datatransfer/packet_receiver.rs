@@ -0,0 +1,122 @@
+use super::{packet_header::PKT_LENGTHS_LEN, PacketHeader};
+use anyhow::Error;
+use std::io::Read;
+
+/// The max size of any single packet. This prevents OOMEs when
+/// invalid data is sent.
+const MAX_PACKET_SIZE: i32 = 16 * 1024 * 1024;
+
+/// Class to handle reading packets one-at-a-time from the wire.
+/// These packets are used both for reading and writing data to/from
+/// DataNodes.
+#[derive(Default)]
+pub(crate) struct PacketReceiver {
+    cur_header: Option<PacketHeader>,
+    cur_checksum: Vec<u8>,
+    cur_data: Vec<u8>,
+}
+
+impl PacketReceiver {
+    pub fn get_header(&self) -> Option<&PacketHeader> {
+        self.cur_header.as_ref()
+    }
+
+    pub fn get_data_slice(&self) -> &[u8] {
+        &self.cur_data
+    }
+
+    pub fn get_checksum_slice(&self) -> &[u8] {
+        &self.cur_checksum
+    }
+
+    /// Reads all of the data for the next packet from `input`.
+    pub fn receive_next_packet(&mut self, input: &mut impl Read) -> anyhow::Result<()> {
+        // Each packet looks like:
+        //   PLEN    HLEN      HEADER     CHECKSUMS  DATA
+        //   32-bit  16-bit   <protobuf>  <variable length>
+        //
+        // PLEN:      Payload length
+        //            = length(PLEN) + length(CHECKSUMS) + length(DATA)
+        //            This length includes its own encoded length in
+        //            the sum for historical reasons.
+        //
+        // HLEN:      Header length
+        //            = length(HEADER)
+        //
+        // HEADER:    the actual packet header fields, encoded in protobuf
+        // CHECKSUMS: the crcs for the data chunk. May be missing if
+        //            checksums were not requested
+        // DATA       the actual block data
+        let mut lengths = [0; PKT_LENGTHS_LEN];
+        input.read_exact(&mut lengths)?;
+        let payload_len = i32::from_be_bytes([lengths[0], lengths[1], lengths[2], lengths[3]]);
+        if payload_len < 4 {
+            return Err(Error::msg(format!(
+                "Invalid payload length {}",
+                payload_len
+            )));
+        }
+        let data_plus_checksum_len = payload_len - 4;
+        let header_len = i16::from_be_bytes([lengths[4], lengths[5]]);
+        if header_len < 0 {
+            return Err(Error::msg(format!("Invalid header length {}", header_len)));
+        }
+        let total_len = payload_len as i64 + header_len as i64;
+        if total_len > MAX_PACKET_SIZE as i64 {
+            return Err(Error::msg(format!(
+                "Incorrect value for packet payload size: {}",
+                payload_len
+            )));
+        }
+
+        // Make sure we have space for the whole packet, and
+        // read it.
+        let mut header_buf = vec![0; header_len as usize];
+        input.read_exact(&mut header_buf)?;
+        let header = PacketHeader::from_data(payload_len, &header_buf)?;
+
+        // Compute the sub-slices of the packet
+        let checksum_len = data_plus_checksum_len - header.get_data_len();
+        if checksum_len < 0 {
+            return Err(Error::msg(format!(
+                "Invalid packet: data length in packet header exceeds data length received. \
+                dataPlusChecksumLen={} header: {}",
+                data_plus_checksum_len, header
+            )));
+        }
+        self.cur_checksum.resize(checksum_len as usize, 0);
+        input.read_exact(&mut self.cur_checksum)?;
+        self.cur_data.resize(header.get_data_len() as usize, 0);
+        input.read_exact(&mut self.cur_data)?;
+        self.cur_header = Some(header);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_receive_packet() {
+        let data = b"hello world";
+        let checksum = [0u8; 4];
+        let header = PacketHeader::new(
+            (4 + checksum.len() + data.len()) as i32,
+            0,
+            0,
+            false,
+            data.len() as i32,
+            false,
+        );
+        let mut buf = vec![];
+        header.put_in_buffer(&mut buf);
+        buf.extend_from_slice(&checksum);
+        buf.extend_from_slice(data);
+
+        let mut receiver = PacketReceiver::default();
+        receiver.receive_next_packet(&mut &buf[..]).unwrap();
+        assert_eq!(data, receiver.get_data_slice());
+        assert_eq!(0, receiver.get_header().unwrap().get_seqno());
+    }
+}
@@ -0,0 +1,49 @@
+use crate::hdfs::{hdfs::protocolpb::PBHelperClient, PipelineAckProto, Status};
+use prost::Message;
+use std::{fmt, io::Read};
+
+/// The bits of a header flag which hold the status of the reply.
+const STATUS_MASK: u32 = 0x0F;
+
+/// Pipeline Acknowledgment
+pub(crate) struct PipelineAck {
+    proto: PipelineAckProto,
+}
+
+impl PipelineAck {
+    /// Get the sequence number
+    pub fn get_seqno(&self) -> i64 {
+        self.proto.seqno
+    }
+
+    /// Get the number of replies
+    pub fn get_num_of_replies(&self) -> usize {
+        self.proto.reply.len()
+    }
+
+    /// Get the i-th reply
+    pub fn get_reply(&self, i: usize) -> Status {
+        // newer datanodes report the status within the header flags
+        let status = match self.proto.flag.get(i) {
+            Some(flag) => (flag & STATUS_MASK) as i32,
+            None => self.proto.reply[i],
+        };
+        Status::try_from(status).unwrap_or(Status::Error)
+    }
+
+    /// Reads an ack from `input`.
+    pub fn read_fields(input: &mut impl Read) -> anyhow::Result<Self> {
+        let proto = PipelineAckProto::decode(&PBHelperClient::vint_prefixed(input)?[..])?;
+        Ok(Self { proto })
+    }
+}
+
+impl fmt::Display for PipelineAck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let replies = (0..self.get_num_of_replies())
+            .map(|i| self.get_reply(i).as_str_name())
+            .collect::<Vec<_>>()
+            .join(" ");
+        write!(f, "seqno: {} reply: {}", self.get_seqno(), replies)
+    }
+}
@@ -0,0 +1,124 @@
+use crate::{
+    common::conf::Configuration,
+    hdfs::hdfs::{client::hdfs_client_config_keys, protocol::DatanodeInfo},
+};
+use anyhow::Error;
+use std::{fmt::Display, str::FromStr};
+
+/// The policy of replace-datanode-on-failure.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Policy {
+    /// The feature is disabled in the entire site.
+    Disable,
+    /// Never add a new datanode.
+    Never,
+    /// Use the default condition, see
+    /// [`ReplaceDatanodeOnFailure::satisfy`].
+    Default,
+    /// Always add a new datanode when an existing datanode is removed.
+    Always,
+}
+
+impl FromStr for Policy {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // DISABLE is only set by turning the feature off
+        match s.to_ascii_uppercase().as_str() {
+            "NEVER" => Ok(Self::Never),
+            "DEFAULT" => Ok(Self::Default),
+            "ALWAYS" => Ok(Self::Always),
+            _ => Err(Error::msg(format!(
+                "Illegal configuration value for {}: {}",
+                hdfs_client_config_keys::DFS_CLIENT_BLOCK_WRITE_REPLACE_DATANODE_ON_FAILURE_POLICY_KEY,
+                s
+            ))),
+        }
+    }
+}
+
+impl Display for Policy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Disable => "DISABLE",
+            Self::Never => "NEVER",
+            Self::Default => "DEFAULT",
+            Self::Always => "ALWAYS",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// The setting of replace-datanode-on-failure feature.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct ReplaceDatanodeOnFailure {
+    policy: Policy,
+    best_effort: bool,
+}
+
+impl ReplaceDatanodeOnFailure {
+    /// Get the setting from configuration.
+    pub fn get(conf: &Configuration) -> anyhow::Result<Self> {
+        let policy = if conf.get_bool(
+            hdfs_client_config_keys::DFS_CLIENT_BLOCK_WRITE_REPLACE_DATANODE_ON_FAILURE_ENABLE_KEY,
+            hdfs_client_config_keys::DFS_CLIENT_BLOCK_WRITE_REPLACE_DATANODE_ON_FAILURE_ENABLE_DEFAULT,
+        ) {
+            conf.get(
+                hdfs_client_config_keys::DFS_CLIENT_BLOCK_WRITE_REPLACE_DATANODE_ON_FAILURE_POLICY_KEY,
+                Some(hdfs_client_config_keys::DFS_CLIENT_BLOCK_WRITE_REPLACE_DATANODE_ON_FAILURE_POLICY_DEFAULT),
+            )
+            .unwrap_or_default()
+            .trim()
+            .parse()?
+        } else {
+            Policy::Disable
+        };
+        let best_effort = conf.get_bool(
+            hdfs_client_config_keys::DFS_CLIENT_BLOCK_WRITE_REPLACE_DATANODE_ON_FAILURE_BEST_EFFORT_KEY,
+            hdfs_client_config_keys::DFS_CLIENT_BLOCK_WRITE_REPLACE_DATANODE_ON_FAILURE_BEST_EFFORT_DEFAULT,
+        );
+        Ok(Self {
+            policy,
+            best_effort,
+        })
+    }
+
+    /// Best effort means that the client will try to replace the failed datanode
+    /// (provided that the policy is satisfied), however, it will continue the
+    /// write operation in case that the datanode replacement also fails.
+    pub fn is_best_effort(&self) -> bool {
+        self.best_effort
+    }
+
+    /// Does it need a replacement according to the policy?
+    ///
+    /// Let r be the replication number and n the number of existing datanodes.
+    /// The default condition is r >= 3 and either n <= r / 2, or the stream is
+    /// appended or hflushed.
+    pub fn satisfy(
+        &self,
+        replication: i16,
+        existings: &[DatanodeInfo],
+        is_append: bool,
+        is_hflushed: bool,
+    ) -> bool {
+        let n = existings.len() as i16;
+        if n == 0 || n >= replication {
+            // don't need to add datanode for any policy.
+            return false;
+        }
+        match self.policy {
+            Policy::Disable | Policy::Never => false,
+            Policy::Default => {
+                replication >= 3 && (n <= replication / 2 || is_append || is_hflushed)
+            }
+            Policy::Always => true,
+        }
+    }
+}
+
+impl Display for ReplaceDatanodeOnFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.policy)
+    }
+}
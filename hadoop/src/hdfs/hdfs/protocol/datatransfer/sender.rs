@@ -0,0 +1,140 @@
+use super::{DataTransferProtoUtil, DataTransferProtocol, Op, DATA_TRANSFER_VERSION};
+use crate::{
+    common::{fs::StorageType, TokenProto},
+    hdfs::{
+        hdfs::protocol::{DatanodeInfo, ExtendedBlock},
+        op_write_block_proto::BlockConstructionStage,
+        BlockChecksumOptionsProto, ChecksumProto, OpBlockChecksumProto, OpReadBlockProto,
+        OpTransferBlockProto, OpWriteBlockProto, StorageTypeProto,
+    },
+};
+use prost::Message;
+use std::io::Write;
+
+/// Sender
+pub(crate) struct Sender<W: Write> {
+    out: W,
+}
+
+impl<W: Write> Sender<W> {
+    /// Create a sender for DataTransferProtocol with a output stream.
+    pub fn new(out: W) -> Self {
+        Self { out }
+    }
+
+    /// Initialize a operation.
+    fn op(&mut self, op: Op) -> anyhow::Result<()> {
+        self.out.write_all(&DATA_TRANSFER_VERSION.to_be_bytes())?;
+        self.out.write_all(&[op.code()])?;
+        Ok(())
+    }
+
+    fn send(&mut self, opcode: Op, proto: &impl Message) -> anyhow::Result<()> {
+        log::trace!("Sending DataTransferOp {:?}: {:?}", opcode, proto);
+        self.op(opcode)?;
+        self.out
+            .write_all(&proto.encode_length_delimited_to_vec())?;
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+impl<W: Write> DataTransferProtocol for Sender<W> {
+    fn read_block(
+        &mut self,
+        blk: &ExtendedBlock,
+        block_token: &TokenProto,
+        client_name: &str,
+        block_offset: i64,
+        length: i64,
+        send_checksum: bool,
+    ) -> anyhow::Result<()> {
+        let proto = OpReadBlockProto {
+            header: DataTransferProtoUtil::build_client_header(blk, client_name, block_token),
+            offset: block_offset as u64,
+            len: length as u64,
+            send_checksums: Some(send_checksum),
+            caching_strategy: None,
+        };
+        self.send(Op::ReadBlock, &proto)
+    }
+
+    fn write_block(
+        &mut self,
+        blk: &ExtendedBlock,
+        storage_type: StorageType,
+        block_token: &TokenProto,
+        client_name: &str,
+        targets: &[DatanodeInfo],
+        target_storage_types: &[StorageType],
+        stage: BlockConstructionStage,
+        pipeline_size: i32,
+        min_bytes_rcvd: i64,
+        max_bytes_rcvd: i64,
+        latest_generation_stamp: i64,
+        requested_checksum: &ChecksumProto,
+        storage_id: Option<&str>,
+        target_storage_ids: &[String],
+    ) -> anyhow::Result<()> {
+        let header = DataTransferProtoUtil::build_client_header(blk, client_name, block_token);
+        // the first target is the receiver itself
+        let proto = OpWriteBlockProto {
+            header,
+            targets: targets.iter().skip(1).map(|t| t.into()).collect(),
+            source: None,
+            stage: stage as i32,
+            pipeline_size: pipeline_size as u32,
+            min_bytes_rcvd: min_bytes_rcvd as u64,
+            max_bytes_rcvd: max_bytes_rcvd as u64,
+            latest_generation_stamp: latest_generation_stamp as u64,
+            requested_checksum: requested_checksum.to_owned(),
+            caching_strategy: None,
+            storage_type: Some(StorageTypeProto::from(storage_type) as i32),
+            target_storage_types: target_storage_types
+                .iter()
+                .skip(1)
+                .map(|t| StorageTypeProto::from(*t) as i32)
+                .collect(),
+            allow_lazy_persist: None,
+            pinning: None,
+            target_pinnings: vec![],
+            storage_id: storage_id.map(str::to_owned),
+            target_storage_ids: target_storage_ids.iter().skip(1).cloned().collect(),
+        };
+        self.send(Op::WriteBlock, &proto)
+    }
+
+    fn transfer_block(
+        &mut self,
+        blk: &ExtendedBlock,
+        block_token: &TokenProto,
+        client_name: &str,
+        targets: &[DatanodeInfo],
+        target_storage_types: &[StorageType],
+        target_storage_ids: &[String],
+    ) -> anyhow::Result<()> {
+        let proto = OpTransferBlockProto {
+            header: DataTransferProtoUtil::build_client_header(blk, client_name, block_token),
+            targets: targets.iter().map(|t| t.into()).collect(),
+            target_storage_types: target_storage_types
+                .iter()
+                .map(|t| StorageTypeProto::from(*t) as i32)
+                .collect(),
+            target_storage_ids: target_storage_ids.to_vec(),
+        };
+        self.send(Op::TransferBlock, &proto)
+    }
+
+    fn block_checksum(
+        &mut self,
+        blk: &ExtendedBlock,
+        block_token: &TokenProto,
+        block_checksum_options: &BlockChecksumOptionsProto,
+    ) -> anyhow::Result<()> {
+        let proto = OpBlockChecksumProto {
+            header: DataTransferProtoUtil::build_base_header(blk, block_token),
+            block_checksum_options: Some(block_checksum_options.to_owned()),
+        };
+        self.send(Op::BlockChecksum, &proto)
+    }
+}
@@ -0,0 +1,47 @@
+use super::HdfsFileStatus;
+use crate::hdfs::DirectoryListingProto;
+
+/// This class defines a partial listing of a directory to support
+/// iterative directory listing.
+#[derive(Clone, Debug)]
+pub struct DirectoryListing {
+    partial_listing: Vec<HdfsFileStatus>,
+    remaining_entries: i32,
+}
+
+impl DirectoryListing {
+    /// Get the partial listing of file status.
+    pub fn get_partial_listing(&self) -> &[HdfsFileStatus] {
+        &self.partial_listing
+    }
+
+    /// Get the number of remaining entries that are left to be listed.
+    pub fn get_remaining_entries(&self) -> i32 {
+        self.remaining_entries
+    }
+
+    /// Check if there are more entries that are left to be listed.
+    pub fn has_more(&self) -> bool {
+        self.remaining_entries != 0
+    }
+
+    /// Get the last name in this list.
+    pub fn get_last_name(&self) -> &[u8] {
+        self.partial_listing
+            .last()
+            .map_or(&[], |status| status.get_local_name_in_bytes())
+    }
+}
+
+impl From<&DirectoryListingProto> for DirectoryListing {
+    fn from(dl: &DirectoryListingProto) -> Self {
+        Self {
+            partial_listing: dl
+                .partial_listing
+                .iter()
+                .map(HdfsFileStatus::from)
+                .collect(),
+            remaining_entries: dl.remaining_entries as i32,
+        }
+    }
+}
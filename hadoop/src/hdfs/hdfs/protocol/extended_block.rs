@@ -0,0 +1,85 @@
+use crate::hdfs::ExtendedBlockProto;
+use std::fmt;
+
+/// Identifies a Block uniquely across the block pools.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ExtendedBlock {
+    pool_id: String,
+    block_id: i64,
+    num_bytes: i64,
+    generation_stamp: i64,
+}
+
+impl ExtendedBlock {
+    pub fn new(pool_id: &str, block_id: i64, num_bytes: i64, generation_stamp: i64) -> Self {
+        Self {
+            pool_id: pool_id.to_owned(),
+            block_id,
+            num_bytes,
+            generation_stamp,
+        }
+    }
+
+    pub fn get_block_pool_id(&self) -> &str {
+        &self.pool_id
+    }
+
+    /// Returns the block name, e.g. `blk_1073741825`.
+    pub fn get_block_name(&self) -> String {
+        format!("blk_{}", self.block_id)
+    }
+
+    pub fn get_num_bytes(&self) -> i64 {
+        self.num_bytes
+    }
+
+    pub fn get_block_id(&self) -> i64 {
+        self.block_id
+    }
+
+    pub fn get_generation_stamp(&self) -> i64 {
+        self.generation_stamp
+    }
+
+    pub fn set_num_bytes(&mut self, len: i64) {
+        self.num_bytes = len;
+    }
+
+    pub fn set_generation_stamp(&mut self, gen_stamp: i64) {
+        self.generation_stamp = gen_stamp;
+    }
+}
+
+impl fmt::Display for ExtendedBlock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}_{}",
+            self.pool_id,
+            self.get_block_name(),
+            self.generation_stamp
+        )
+    }
+}
+
+impl From<&ExtendedBlockProto> for ExtendedBlock {
+    fn from(eb: &ExtendedBlockProto) -> Self {
+        Self::new(
+            &eb.pool_id,
+            eb.block_id as i64,
+            eb.num_bytes.unwrap_or_default() as i64,
+            eb.generation_stamp as i64,
+        )
+    }
+}
+
+impl From<&ExtendedBlock> for ExtendedBlockProto {
+    fn from(b: &ExtendedBlock) -> Self {
+        ExtendedBlockProto {
+            pool_id: b.pool_id.to_owned(),
+            block_id: b.block_id as u64,
+            generation_stamp: b.generation_stamp as u64,
+            num_bytes: Some(b.num_bytes as u64),
+        }
+    }
+}
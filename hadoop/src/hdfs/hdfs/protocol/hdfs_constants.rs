@@ -1,5 +1,40 @@
 /// URI Scheme for `hdfs://namenode/` URIs.
 pub const HDFS_URI_SCHEME: &str = "hdfs";
 
+pub const DOT_SNAPSHOT_DIR: &str = ".snapshot";
+pub const SEPARATOR_DOT_SNAPSHOT_DIR_SEPARATOR: &str = "/.snapshot/";
+
 /// HDFS Protocol Names:
 pub const CLIENT_NAMENODE_PROTOCOL_NAME: &str = "org.apache.hadoop.hdfs.protocol.ClientProtocol";
+pub const CLIENT_DATANODE_PROTOCOL_NAME: &str =
+    "org.apache.hadoop.hdfs.protocol.ClientDatanodeProtocol";
+
+/// The kind of the delegation tokens issued by the NameNode.
+pub const HDFS_DELEGATION_KIND: &str = "HDFS_DELEGATION_TOKEN";
+
+/// The kind of the block access tokens issued by the NameNode.
+pub const HDFS_BLOCK_TOKEN_KIND: &str = "HDFS_BLOCK_TOKEN";
+
+// Timeouts for communicating with DataNode for streaming writes/reads
+pub const READ_TIMEOUT: i32 = 60 * 1000;
+pub const READ_TIMEOUT_EXTENSION: i32 = 5 * 1000;
+
+/// For a HDFS client to write to a file, a lease is granted; During the lease
+/// period, no other client can write to the file. The writing client can
+/// periodically renew the lease. When the file is closed, the lease is
+/// revoked. The lease duration is bound by this soft limit and a
+/// [`LEASE_HARDLIMIT_PERIOD`]. Until the soft limit expires, the writer has
+/// sole write access to the file. If the soft limit expires and the client
+/// fails to close the file or renew the lease, another client can preempt the
+/// lease.
+pub const LEASE_SOFTLIMIT_PERIOD: i64 = 60 * 1000;
+
+/// For a HDFS client to write to a file, a lease is granted; During the lease
+/// period, no other client can write to the file. The writing client can
+/// periodically renew the lease. When the file is closed, the lease is
+/// revoked. The lease duration is bound by a [`LEASE_SOFTLIMIT_PERIOD`] and
+/// this hard limit. If after the hard limit expires and the client has failed
+/// to renew the lease, HDFS assumes that the client has quit and will
+/// automatically close the file on behalf of the writer, and recover the
+/// lease.
+pub const LEASE_HARDLIMIT_PERIOD: i64 = 20 * LEASE_SOFTLIMIT_PERIOD;
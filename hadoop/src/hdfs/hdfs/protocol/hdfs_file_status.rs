@@ -0,0 +1,375 @@
+use super::LocatedBlocks;
+use crate::{
+    common::fs::{permission::FsPermission, FileStatus, LocatedFileStatus, Path},
+    hdfs::{
+        hdfs::DFSUtilClient,
+        hdfs_file_status_proto::{FileType, Flags},
+        ErasureCodingPolicyProto, FileEncryptionInfoProto, HdfsFileStatusProto,
+    },
+};
+use iref::Iri;
+
+/// The local name of a file status that denotes the path itself.
+pub const EMPTY_NAME: &[u8] = &[];
+
+/// The inode id of files created before inode ids were introduced.
+pub const GRANDFATHER_INODE_ID: i64 = 0;
+
+/// The block storage policy id when no policy is specified.
+pub const BLOCK_STORAGE_POLICY_ID_UNSPECIFIED: u8 = 0;
+
+/// HDFS metadata for an entity in the filesystem.
+#[derive(Clone, Debug)]
+pub struct HdfsFileStatus {
+    length: i64,
+    isdir: bool,
+    block_replication: i16,
+    blocksize: i64,
+    modification_time: i64,
+    access_time: i64,
+    permission: FsPermission,
+    owner: String,
+    group: String,
+    symlink: Option<Vec<u8>>,
+    // local name of the inode that's encoded in java UTF8
+    local_name: Vec<u8>,
+    file_id: i64,
+    children_num: i32,
+    fe_info: Option<FileEncryptionInfoProto>,
+    ec_policy: Option<ErasureCodingPolicyProto>,
+    storage_policy: u8,
+    has_acl: bool,
+    is_encrypted: bool,
+    is_erasure_coded: bool,
+    is_snapshot_enabled: bool,
+    // block locations, only set when requested from the NameNode
+    locations: Option<LocatedBlocks>,
+}
+
+impl HdfsFileStatus {
+    /// Get the length of this file, in bytes.
+    pub fn get_len(&self) -> i64 {
+        self.length
+    }
+
+    /// Is this a directory?
+    pub fn is_dir(&self) -> bool {
+        self.isdir
+    }
+
+    /// Is this a symbolic link?
+    pub fn is_symlink(&self) -> bool {
+        self.symlink.is_some()
+    }
+
+    /// Get the replication factor of a file.
+    pub fn get_replication(&self) -> i16 {
+        self.block_replication
+    }
+
+    /// Get the block size of the file.
+    pub fn get_block_size(&self) -> i64 {
+        self.blocksize
+    }
+
+    /// Get the modification time of the file.
+    pub fn get_modification_time(&self) -> i64 {
+        self.modification_time
+    }
+
+    /// Get the access time of the file.
+    pub fn get_access_time(&self) -> i64 {
+        self.access_time
+    }
+
+    /// Get FsPermission associated with the file.
+    pub fn get_permission(&self) -> &FsPermission {
+        &self.permission
+    }
+
+    /// Get the owner of the file.
+    pub fn get_owner(&self) -> &str {
+        &self.owner
+    }
+
+    /// Get the group associated with the file.
+    pub fn get_group(&self) -> &str {
+        &self.group
+    }
+
+    /// Get the string representation of the symlink.
+    pub fn get_symlink(&self) -> Option<String> {
+        self.symlink
+            .as_ref()
+            .map(|s| String::from_utf8_lossy(s).into_owned())
+    }
+
+    /// Inode ID for this entity, if a file.
+    pub fn get_file_id(&self) -> i64 {
+        self.file_id
+    }
+
+    /// Number of children for this inode.
+    pub fn get_children_num(&self) -> i32 {
+        self.children_num
+    }
+
+    /// Get metadata for encryption, if present.
+    pub fn get_file_encryption_info(&self) -> Option<&FileEncryptionInfoProto> {
+        self.fe_info.as_ref()
+    }
+
+    /// Get the erasure coding policy if it's set.
+    pub fn get_erasure_coding_policy(&self) -> Option<&ErasureCodingPolicyProto> {
+        self.ec_policy.as_ref()
+    }
+
+    /// Storage policy for this entity.
+    pub fn get_storage_policy(&self) -> u8 {
+        self.storage_policy
+    }
+
+    pub fn has_acl(&self) -> bool {
+        self.has_acl
+    }
+
+    pub fn is_encrypted(&self) -> bool {
+        self.is_encrypted
+    }
+
+    pub fn is_erasure_coded(&self) -> bool {
+        self.is_erasure_coded
+    }
+
+    pub fn is_snapshot_enabled(&self) -> bool {
+        self.is_snapshot_enabled
+    }
+
+    /// Get block locations, if they were requested with the status.
+    pub fn get_located_blocks(&self) -> Option<&LocatedBlocks> {
+        self.locations.as_ref()
+    }
+
+    /// Check if the local name is empty.
+    pub fn is_empty_local_name(&self) -> bool {
+        self.local_name.is_empty()
+    }
+
+    /// Get the string representation of the local name.
+    pub fn get_local_name(&self) -> String {
+        String::from_utf8_lossy(&self.local_name).into_owned()
+    }
+
+    /// Get the Java UTF8 representation of the local name.
+    pub fn get_local_name_in_bytes(&self) -> &[u8] {
+        &self.local_name
+    }
+
+    /// Get the string representation of the full path name.
+    pub fn get_full_name(&self, parent: &str) -> String {
+        if self.is_empty_local_name() {
+            return parent.to_owned();
+        }
+        let mut full_name = parent.to_owned();
+        if !parent.ends_with('/') {
+            full_name.push('/');
+        }
+        full_name.push_str(&self.get_local_name());
+        full_name
+    }
+
+    /// Get the full path.
+    pub fn get_full_path(&self, parent: &Path) -> anyhow::Result<Path> {
+        if self.is_empty_local_name() {
+            return Ok(parent.clone());
+        }
+        Path::from_parent_str(parent, &self.get_local_name())
+    }
+
+    /// Resolve the short name of the Path given the URI, parent provided. This
+    /// FileStatus reference will not contain a valid Path until it is resolved
+    /// by this method.
+    pub fn make_qualified(&self, default_uri: &Iri, parent: &Path) -> anyhow::Result<FileStatus> {
+        // fully-qualify path
+        let path = self
+            .get_full_path(parent)?
+            .make_qualified(default_uri, parent)?;
+        let symlink = match self.get_symlink() {
+            Some(symlink) => Some(Path::new(&symlink)?),
+            None => None,
+        };
+        Ok(FileStatus::new(
+            self.length,
+            self.isdir,
+            self.block_replication,
+            self.blocksize,
+            self.modification_time,
+            self.access_time,
+            Some(self.permission),
+            &self.owner,
+            &self.group,
+            symlink,
+            path,
+            FileStatus::attributes(
+                self.has_acl,
+                self.is_encrypted,
+                self.is_erasure_coded,
+                self.is_snapshot_enabled,
+            ),
+        ))
+    }
+
+    /// This function is used to transform the underlying HDFS LocatedBlocks to
+    /// BlockLocations. This method must be invoked before
+    /// [`LocatedFileStatus::get_block_locations`] is called.
+    pub fn make_qualified_located(
+        &self,
+        default_uri: &Iri,
+        path: &Path,
+    ) -> anyhow::Result<LocatedFileStatus> {
+        let status = self.make_qualified(default_uri, path)?;
+        let locations = DFSUtilClient::located_blocks2_locations(self.locations.as_ref());
+        Ok(LocatedFileStatus::new(status, locations))
+    }
+}
+
+impl From<&HdfsFileStatusProto> for HdfsFileStatus {
+    fn from(fs: &HdfsFileStatusProto) -> Self {
+        let flags = fs.flags.unwrap_or_default();
+        let has_flag = |flag: Flags| flags & flag as u32 != 0;
+        Self {
+            length: fs.length as i64,
+            isdir: fs.file_type == FileType::IsDir as i32,
+            block_replication: fs.block_replication.unwrap_or_default() as i16,
+            blocksize: fs.blocksize.unwrap_or_default() as i64,
+            modification_time: fs.modification_time as i64,
+            access_time: fs.access_time as i64,
+            permission: FsPermission::from(&fs.permission),
+            owner: fs.owner.to_owned(),
+            group: fs.group.to_owned(),
+            symlink: (fs.file_type == FileType::IsSymlink as i32)
+                .then(|| fs.symlink.to_owned().unwrap_or_default()),
+            local_name: fs.path.to_owned(),
+            file_id: fs.file_id.map_or(GRANDFATHER_INODE_ID, |id| id as i64),
+            children_num: fs.children_num.unwrap_or(-1),
+            fe_info: fs.file_encryption_info.to_owned(),
+            ec_policy: fs.ec_policy.to_owned(),
+            storage_policy: fs
+                .storage_policy
+                .map_or(BLOCK_STORAGE_POLICY_ID_UNSPECIFIED, |p| p as u8),
+            has_acl: has_flag(Flags::HasAcl),
+            is_encrypted: has_flag(Flags::HasCrypt),
+            is_erasure_coded: has_flag(Flags::HasEc),
+            is_snapshot_enabled: has_flag(Flags::SnapshotEnabled),
+            locations: fs.locations.as_ref().map(LocatedBlocks::from),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hdfs::{
+        DatanodeIdProto, DatanodeInfoProto, ExtendedBlockProto, FsPermissionProto,
+        LocatedBlockProto, LocatedBlocksProto, StorageTypeProto,
+    };
+    use iref::IriBuf;
+
+    #[test]
+    fn test_make_qualified() -> anyhow::Result<()> {
+        let proto = HdfsFileStatusProto {
+            file_type: FileType::IsFile as i32,
+            path: b"part-00000".to_vec(),
+            length: 1024,
+            permission: FsPermissionProto { perm: 0o640 },
+            owner: "hdfs".to_owned(),
+            group: "supergroup".to_owned(),
+            block_replication: Some(3),
+            blocksize: Some(134217728),
+            file_id: Some(16386),
+            flags: Some(Flags::HasCrypt as u32 | Flags::HasEc as u32),
+            ..Default::default()
+        };
+        let status = HdfsFileStatus::from(&proto);
+        assert!(!status.is_dir());
+        assert_eq!(status.get_file_id(), 16386);
+        assert_eq!(status.get_children_num(), -1);
+        assert_eq!(status.get_full_name("/warehouse"), "/warehouse/part-00000");
+
+        let uri = IriBuf::new("hdfs://nn:8020".to_owned())?;
+        let parent = Path::new("/warehouse")?;
+        let qualified = status.make_qualified(uri.as_iri(), &parent)?;
+        assert_eq!(
+            qualified.get_path().to_string(),
+            "hdfs://nn:8020/warehouse/part-00000"
+        );
+        assert_eq!(qualified.get_len(), 1024);
+        assert_eq!(qualified.get_replication(), 3);
+        assert_eq!(qualified.get_permission().to_string(), "rw-r-----");
+        assert!(qualified.is_encrypted());
+        assert!(qualified.is_erasure_coded());
+        assert!(!qualified.has_acl());
+        Ok(())
+    }
+
+    #[test]
+    fn test_make_qualified_located() -> anyhow::Result<()> {
+        let datanode = |ip: &str, host: &str, location: Option<&str>| DatanodeInfoProto {
+            id: DatanodeIdProto {
+                ip_addr: ip.to_owned(),
+                host_name: host.to_owned(),
+                xfer_port: 9866,
+                ..Default::default()
+            },
+            location: location.map(str::to_owned),
+            ..Default::default()
+        };
+        let block = LocatedBlockProto {
+            b: ExtendedBlockProto {
+                pool_id: "BP-1".to_owned(),
+                block_id: 1073741825,
+                generation_stamp: 1001,
+                num_bytes: Some(512),
+            },
+            offset: 0,
+            locs: vec![
+                datanode("10.0.0.1", "dn1", Some("/rack1/")),
+                datanode("10.0.0.2", "dn2", None),
+            ],
+            is_cached: vec![false, true],
+            storage_types: vec![StorageTypeProto::Ssd as i32, StorageTypeProto::Disk as i32],
+            storage_i_ds: vec!["DS-1".to_owned(), "DS-2".to_owned()],
+            ..Default::default()
+        };
+        let proto = HdfsFileStatusProto {
+            file_type: FileType::IsFile as i32,
+            path: b"f".to_vec(),
+            length: 512,
+            locations: Some(LocatedBlocksProto {
+                file_length: 512,
+                blocks: vec![block],
+                is_last_block_complete: true,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let uri = IriBuf::new("hdfs://nn:8020".to_owned())?;
+        let located =
+            HdfsFileStatus::from(&proto).make_qualified_located(uri.as_iri(), &Path::new("/d")?)?;
+        assert_eq!(located.get_path().to_string(), "hdfs://nn:8020/d/f");
+        let locations = located.get_block_locations();
+        assert_eq!(locations.len(), 1);
+        let location = &locations[0];
+        assert_eq!(location.get_names(), ["10.0.0.1:9866", "10.0.0.2:9866"]);
+        assert_eq!(location.get_hosts(), ["dn1", "dn2"]);
+        assert_eq!(location.get_cached_hosts(), ["dn2"]);
+        assert_eq!(
+            location.get_topology_paths(),
+            ["/rack1/10.0.0.1:9866", "/default-rack/10.0.0.2:9866"]
+        );
+        assert_eq!(location.get_storage_types()[0].to_string(), "SSD");
+        assert_eq!((location.get_offset(), location.get_length()), (0, 512));
+        Ok(())
+    }
+}
@@ -0,0 +1,24 @@
+use super::{HdfsFileStatus, LocatedBlock};
+
+/// Class to contain Lastblock and HdfsFileStatus for the Append operation
+pub struct LastBlockWithStatus {
+    last_block: Option<LocatedBlock>,
+    file_status: Option<HdfsFileStatus>,
+}
+
+impl LastBlockWithStatus {
+    pub fn new(last_block: Option<LocatedBlock>, file_status: Option<HdfsFileStatus>) -> Self {
+        Self {
+            last_block,
+            file_status,
+        }
+    }
+
+    pub fn get_last_block(&self) -> Option<&LocatedBlock> {
+        self.last_block.as_ref()
+    }
+
+    pub fn get_file_status(&self) -> Option<&HdfsFileStatus> {
+        self.file_status.as_ref()
+    }
+}
@@ -0,0 +1,146 @@
+use super::{DatanodeInfo, ExtendedBlock};
+use crate::{
+    common::{fs::StorageType, TokenProto},
+    hdfs::{LocatedBlockProto, StorageTypeProto},
+};
+use std::fmt;
+
+/// Associates a block with the Datanodes that contain its replicas
+/// and other block metadata (E.g. the file offset associated with this
+/// block, whether it is corrupt, a location is cached in memory,
+/// security token, etc).
+#[derive(Clone, Debug)]
+pub struct LocatedBlock {
+    b: ExtendedBlock,
+    // offset of the first byte of the block in the file
+    offset: i64,
+    locs: Vec<DatanodeInfo>,
+    // Storage ID for each replica
+    storage_ids: Vec<String>,
+    // Storage type for each replica
+    storage_types: Vec<StorageType>,
+    // corrupt flag is true if all of the replicas of a block are corrupt.
+    // else false. If block has few corrupt replicas, they are filtered and
+    // their locations are not part of this object
+    corrupt: bool,
+    block_token: TokenProto,
+    // List of cached datanode locations
+    cached_locs: Vec<DatanodeInfo>,
+}
+
+impl LocatedBlock {
+    pub fn get_block_token(&self) -> &TokenProto {
+        &self.block_token
+    }
+
+    pub fn set_block_token(&mut self, token: TokenProto) {
+        self.block_token = token;
+    }
+
+    pub fn get_block(&self) -> &ExtendedBlock {
+        &self.b
+    }
+
+    pub fn get_block_mut(&mut self) -> &mut ExtendedBlock {
+        &mut self.b
+    }
+
+    /// Returns the locations associated with this block. The returned array is
+    /// not expected to be modified. If it is, caller must immediately invoke
+    /// `update_cached_storage_info` to update the cached Storage ID/Type
+    /// arrays.
+    pub fn get_locations(&self) -> &[DatanodeInfo] {
+        &self.locs
+    }
+
+    pub fn get_storage_types(&self) -> &[StorageType] {
+        &self.storage_types
+    }
+
+    pub fn get_storage_ids(&self) -> &[String] {
+        &self.storage_ids
+    }
+
+    pub fn get_start_offset(&self) -> i64 {
+        self.offset
+    }
+
+    pub fn get_block_size(&self) -> i64 {
+        self.b.get_num_bytes()
+    }
+
+    pub fn set_start_offset(&mut self, value: i64) {
+        self.offset = value;
+    }
+
+    pub fn set_corrupt(&mut self, corrupt: bool) {
+        self.corrupt = corrupt;
+    }
+
+    pub fn is_corrupt(&self) -> bool {
+        self.corrupt
+    }
+
+    /// Returns cached locations of the block.
+    pub fn get_cached_locations(&self) -> &[DatanodeInfo] {
+        &self.cached_locs
+    }
+}
+
+impl fmt::Display for LocatedBlock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let locs = self
+            .locs
+            .iter()
+            .map(|l| l.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        write!(
+            f,
+            "LocatedBlock{{{}; getBlockSize()={}; corrupt={}; offset={}; locs=[{}]}}",
+            self.b,
+            self.get_block_size(),
+            self.corrupt,
+            self.offset,
+            locs
+        )
+    }
+}
+
+impl From<&LocatedBlockProto> for LocatedBlock {
+    fn from(proto: &LocatedBlockProto) -> Self {
+        let targets = proto
+            .locs
+            .iter()
+            .map(DatanodeInfo::from)
+            .collect::<Vec<_>>();
+        let storage_types = if proto.storage_types.is_empty() {
+            // The storage types are not set by old NameNodes.
+            vec![StorageType::DEFAULT; targets.len()]
+        } else {
+            proto
+                .storage_types
+                .iter()
+                .map(|t| StorageType::from(StorageTypeProto::try_from(*t).unwrap_or_default()))
+                .collect()
+        };
+        // Set values from the isCached list, re-using references from loc
+        let cached_locs = proto
+            .is_cached
+            .iter()
+            .zip(&targets)
+            .filter(|(is_cached, _)| **is_cached)
+            .map(|(_, target)| target.clone())
+            .collect();
+        Self {
+            b: ExtendedBlock::from(&proto.b),
+            offset: proto.offset as i64,
+            locs: targets,
+            storage_ids: proto.storage_i_ds.to_owned(),
+            storage_types,
+            corrupt: proto.corrupt,
+            block_token: proto.block_token.to_owned(),
+            cached_locs,
+        }
+    }
+}
@@ -0,0 +1,227 @@
+use super::LocatedBlock;
+use crate::hdfs::{ErasureCodingPolicyProto, FileEncryptionInfoProto, LocatedBlocksProto};
+use std::fmt;
+
+/// Collection of blocks with their locations and the file length.
+#[derive(Clone, Debug, Default)]
+pub struct LocatedBlocks {
+    file_length: i64,
+    // array of blocks with prioritized locations
+    blocks: Vec<LocatedBlock>,
+    under_construction: bool,
+    last_located_block: Option<LocatedBlock>,
+    is_last_block_complete: bool,
+    file_encryption_info: Option<FileEncryptionInfoProto>,
+    ec_policy: Option<ErasureCodingPolicyProto>,
+}
+
+impl LocatedBlocks {
+    /// Get located blocks.
+    pub fn get_located_blocks(&self) -> &[LocatedBlock] {
+        &self.blocks
+    }
+
+    /// Get the last located block.
+    pub fn get_last_located_block(&self) -> Option<&LocatedBlock> {
+        self.last_located_block.as_ref()
+    }
+
+    /// Set the length of the last block, which is under construction, both
+    /// in the last located block and in the cached block list.
+    pub fn set_last_block_num_bytes(&mut self, num_bytes: i64) {
+        let Some(last) = &mut self.last_located_block else {
+            return;
+        };
+        last.get_block_mut().set_num_bytes(num_bytes);
+        let block_id = last.get_block().get_block_id();
+        for block in &mut self.blocks {
+            if block.get_block().get_block_id() == block_id {
+                block.get_block_mut().set_num_bytes(num_bytes);
+            }
+        }
+    }
+
+    /// Is the last block completed?
+    pub fn is_last_block_complete(&self) -> bool {
+        self.is_last_block_complete
+    }
+
+    /// Get located block.
+    pub fn get(&self, index: usize) -> Option<&LocatedBlock> {
+        self.blocks.get(index)
+    }
+
+    /// Get number of located blocks.
+    pub fn located_block_count(&self) -> usize {
+        self.blocks.len()
+    }
+
+    pub fn get_file_length(&self) -> i64 {
+        self.file_length
+    }
+
+    /// Return true if file was under construction when this LocatedBlocks was
+    /// constructed, false otherwise.
+    pub fn is_under_construction(&self) -> bool {
+        self.under_construction
+    }
+
+    /// Returns the FileEncryptionInfo for the LocatedBlocks
+    pub fn get_file_encryption_info(&self) -> Option<&FileEncryptionInfoProto> {
+        self.file_encryption_info.as_ref()
+    }
+
+    /// Returns the erasure coding policy for the LocatedBlocks.
+    pub fn get_erasure_coding_policy(&self) -> Option<&ErasureCodingPolicyProto> {
+        self.ec_policy.as_ref()
+    }
+
+    /// Find block containing specified offset.
+    ///
+    /// Returns `Ok` with the block index if found, otherwise `Err` with the
+    /// index at which a block starting at `offset` would be inserted.
+    pub fn find_block(&self, offset: i64) -> Result<usize, usize> {
+        self.blocks.binary_search_by(|block| {
+            let start = block.get_start_offset();
+            if offset < start {
+                std::cmp::Ordering::Greater
+            } else if offset >= start + block.get_block_size() {
+                std::cmp::Ordering::Less
+            } else {
+                std::cmp::Ordering::Equal
+            }
+        })
+    }
+
+    /// Insert the `new_blocks` fetched for a range of the file at `block_idx`,
+    /// replacing the cached blocks which start at the same offsets.
+    ///
+    /// The new blocks must be sorted by their start offsets.
+    pub fn insert_range(&mut self, block_idx: usize, new_blocks: &[LocatedBlock]) {
+        let mut old_idx = block_idx;
+        let mut ins_start = 0;
+        let mut ins_end = 0;
+        for (new_idx, new_block) in new_blocks.iter().enumerate() {
+            if old_idx >= self.blocks.len() {
+                break;
+            }
+            let new_off = new_block.get_start_offset();
+            let old_off = self.blocks[old_idx].get_start_offset();
+            if new_off < old_off {
+                ins_end += 1;
+            } else if new_off == old_off {
+                // replace old cached block by the new one
+                self.blocks[old_idx] = new_block.clone();
+                if ins_start < ins_end {
+                    // insert new blocks
+                    self.blocks.splice(
+                        old_idx..old_idx,
+                        new_blocks[ins_start..ins_end].iter().cloned(),
+                    );
+                    old_idx += ins_end - ins_start;
+                }
+                ins_start = new_idx + 1;
+                ins_end = new_idx + 1;
+                old_idx += 1;
+            } else {
+                debug_assert!(false, "List of LocatedBlock must be sorted by startOffset");
+            }
+        }
+        ins_end = new_blocks.len();
+        if ins_start < ins_end {
+            // insert new blocks
+            self.blocks.splice(
+                old_idx..old_idx,
+                new_blocks[ins_start..ins_end].iter().cloned(),
+            );
+        }
+    }
+}
+
+impl fmt::Display for LocatedBlocks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "LocatedBlocks{{\n  fileLength={}\n  underConstruction={}\n  blocks=[{}]",
+            self.file_length,
+            self.under_construction,
+            self.blocks
+                .iter()
+                .map(|b| b.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )?;
+        if let Some(last) = &self.last_located_block {
+            write!(f, "\n  lastLocatedBlock={}", last)?;
+        }
+        write!(
+            f,
+            "\n  isLastBlockComplete={}\n}}",
+            self.is_last_block_complete
+        )
+    }
+}
+
+impl From<&LocatedBlocksProto> for LocatedBlocks {
+    fn from(lb: &LocatedBlocksProto) -> Self {
+        Self {
+            file_length: lb.file_length as i64,
+            blocks: lb.blocks.iter().map(LocatedBlock::from).collect(),
+            under_construction: lb.under_construction,
+            last_located_block: lb.last_block.as_ref().map(LocatedBlock::from),
+            is_last_block_complete: lb.is_last_block_complete,
+            file_encryption_info: lb.file_encryption_info.to_owned(),
+            ec_policy: lb.ec_policy.to_owned(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hdfs::{ExtendedBlockProto, LocatedBlockProto};
+
+    fn located_block(offset: i64, len: i64) -> LocatedBlock {
+        LocatedBlock::from(&LocatedBlockProto {
+            b: ExtendedBlockProto {
+                pool_id: "pool".to_owned(),
+                block_id: offset as u64,
+                generation_stamp: 1,
+                num_bytes: Some(len as u64),
+            },
+            offset: offset as u64,
+            ..Default::default()
+        })
+    }
+
+    fn offsets(blocks: &LocatedBlocks) -> Vec<i64> {
+        blocks
+            .get_located_blocks()
+            .iter()
+            .map(|b| b.get_start_offset())
+            .collect()
+    }
+
+    #[test]
+    fn test_find_block_and_insert_range() {
+        let mut blocks = LocatedBlocks {
+            file_length: 50,
+            blocks: vec![located_block(0, 10), located_block(30, 10)],
+            ..Default::default()
+        };
+        assert_eq!(Ok(0), blocks.find_block(9));
+        assert_eq!(Err(1), blocks.find_block(10));
+        assert_eq!(Ok(1), blocks.find_block(35));
+        assert_eq!(Err(2), blocks.find_block(45));
+
+        blocks.insert_range(1, &[located_block(10, 10), located_block(20, 10)]);
+        assert_eq!(vec![0, 10, 20, 30], offsets(&blocks));
+
+        blocks.insert_range(2, &[located_block(20, 10), located_block(30, 10)]);
+        assert_eq!(vec![0, 10, 20, 30], offsets(&blocks));
+
+        blocks.insert_range(4, &[located_block(40, 10)]);
+        assert_eq!(vec![0, 10, 20, 30, 40], offsets(&blocks));
+        assert_eq!(Ok(4), blocks.find_block(45));
+    }
+}
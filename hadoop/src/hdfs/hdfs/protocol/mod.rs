@@ -1,4 +1,31 @@
+mod batched_directory_listing;
 mod client_protocol;
+mod datanode_id;
+mod datanode_info;
+pub(crate) mod datatransfer;
+mod directory_listing;
+mod extended_block;
 pub mod hdfs_constants;
+mod hdfs_file_status;
+mod last_block_with_status;
+mod located_block;
+mod located_blocks;
 
-pub(crate) use client_protocol::ClientProtocol;
+pub use batched_directory_listing::{BatchedDirectoryListing, HdfsPartialListing};
+#[cfg(test)]
+pub(crate) use client_protocol::tests::MockClientProtocol;
+#[cfg(feature = "async")]
+pub(crate) use client_protocol::{AsyncClientProtocol, ProtocolFuture};
+pub(crate) use client_protocol::{
+    ClientProtocol, GET_STATS_CAPACITY_IDX, GET_STATS_REMAINING_IDX, GET_STATS_USED_IDX,
+};
+pub use datanode_id::DatanodeID;
+pub use datanode_info::{AdminStates, DatanodeInfo};
+pub use directory_listing::DirectoryListing;
+pub use extended_block::ExtendedBlock;
+pub use hdfs_file_status::{
+    HdfsFileStatus, BLOCK_STORAGE_POLICY_ID_UNSPECIFIED, EMPTY_NAME, GRANDFATHER_INODE_ID,
+};
+pub use last_block_with_status::LastBlockWithStatus;
+pub use located_block::LocatedBlock;
+pub use located_blocks::LocatedBlocks;
@@ -0,0 +1,40 @@
+use crate::{
+    common::ipc::{Invoker, ProtocolInfo, RpcProtocol},
+    hdfs::{
+        hdfs::{
+            client::hdfs_client_config_keys::DFS_DATANODE_KERBEROS_PRINCIPAL_KEY,
+            protocol::hdfs_constants::{CLIENT_DATANODE_PROTOCOL_NAME, HDFS_BLOCK_TOKEN_KIND},
+        },
+        *,
+    },
+};
+
+/// Protocol that clients use to communicate with the DataNode.
+pub(crate) struct ClientDatanodeProtocolPB {
+    invoker: Invoker<Self>,
+}
+
+impl RpcProtocol for ClientDatanodeProtocolPB {
+    fn get_protocol_info() -> &'static ProtocolInfo {
+        static PROTOCOL_INFO: ProtocolInfo = ProtocolInfo {
+            protocol_name: CLIENT_DATANODE_PROTOCOL_NAME,
+            protocol_version: 1,
+            token_kind: Some(HDFS_BLOCK_TOKEN_KIND),
+            server_principal: Some(DFS_DATANODE_KERBEROS_PRINCIPAL_KEY),
+        };
+        &PROTOCOL_INFO
+    }
+
+    fn from(invoker: Invoker<Self>) -> Self {
+        Self { invoker }
+    }
+}
+
+impl ClientDatanodeProtocolPB {
+    pub fn get_replica_visible_length(
+        &self,
+        req: &GetReplicaVisibleLengthRequestProto,
+    ) -> anyhow::Result<GetReplicaVisibleLengthResponseProto> {
+        self.invoker.invoke("getReplicaVisibleLength", req)
+    }
+}
@@ -0,0 +1,66 @@
+use super::ClientDatanodeProtocolPB;
+use crate::{
+    common::{
+        conf::Configuration,
+        fs::common_configuration_keys_public::IPC_CLIENT_CONNECTION_MAXIDLETIME_KEY,
+        ipc::RPC,
+        net::NetUtils,
+        security::{token::Token, SecurityUtil, UserGroupInformation},
+    },
+    hdfs::{
+        hdfs::protocol::{DatanodeID, ExtendedBlock, LocatedBlock},
+        GetReplicaVisibleLengthRequestProto,
+    },
+};
+
+/// This class is the client side translator to translate the requests made on
+/// the ClientDatanodeProtocol interfaces to the RPC server implementing
+/// [`ClientDatanodeProtocolPB`].
+pub(crate) struct ClientDatanodeProtocolTranslatorPB {
+    rpc_proxy: ClientDatanodeProtocolPB,
+}
+
+impl ClientDatanodeProtocolTranslatorPB {
+    /// Create a proxy to the datanode `datanodeid` authenticated by the block
+    /// token of `located_block`.
+    pub fn new(
+        datanodeid: &DatanodeID,
+        conf: &Configuration,
+        socket_timeout: i32,
+        located_block: &LocatedBlock,
+    ) -> anyhow::Result<Self> {
+        let dn_addr = datanodeid.get_ipc_addr();
+        let addr = NetUtils::create_socket_addr(&dn_addr, -1, None)?;
+        log::debug!("Connecting to datanode {} addr={}", dn_addr, addr);
+
+        // Since we're creating a new UserGroupInformation here, we know that
+        // no future RPC proxies will be able to re-use the same connection.
+        // And usages of this proxy tend to be one-off calls.
+        let mut conf_with_no_ipc_idle = conf.to_owned();
+        conf_with_no_ipc_idle.set_int(IPC_CLIENT_CONNECTION_MAXIDLETIME_KEY, 0);
+
+        let ticket =
+            UserGroupInformation::create_remote_user(&located_block.get_block().get_block_name())?;
+        // the block token is looked up by the address of the datanode
+        let mut token = Token::from(located_block.get_block_token());
+        token.set_service(&SecurityUtil::build_token_service(&addr).to_string());
+        ticket.add_token(token);
+        let rpc_proxy = RPC::get_protocol_proxy(
+            &addr,
+            &ticket,
+            &conf_with_no_ipc_idle,
+            socket_timeout,
+            None,
+            None,
+            None,
+        )?;
+        Ok(Self { rpc_proxy })
+    }
+
+    /// Return the visible length of a replica.
+    pub fn get_replica_visible_length(&self, b: &ExtendedBlock) -> anyhow::Result<i64> {
+        let req = GetReplicaVisibleLengthRequestProto { block: b.into() };
+        let res = self.rpc_proxy.get_replica_visible_length(&req)?;
+        Ok(res.length as i64)
+    }
+}
@@ -1,6 +1,12 @@
 use crate::{
     common::ipc::{Invoker, ProtocolInfo, RpcProtocol},
-    hdfs::{hdfs::protocol::hdfs_constants::CLIENT_NAMENODE_PROTOCOL_NAME, *},
+    hdfs::{
+        hdfs::{
+            client::hdfs_client_config_keys::DFS_NAMENODE_KERBEROS_PRINCIPAL_KEY,
+            protocol::hdfs_constants::{CLIENT_NAMENODE_PROTOCOL_NAME, HDFS_DELEGATION_KIND},
+        },
+        *,
+    },
 };
 
 /// Protocol that clients use to communicate with the NameNode.
@@ -13,6 +19,8 @@ impl RpcProtocol for ClientNamenodeProtocolPB {
         static PROTOCOL_INFO: ProtocolInfo = ProtocolInfo {
             protocol_name: CLIENT_NAMENODE_PROTOCOL_NAME,
             protocol_version: 1,
+            token_kind: Some(HDFS_DELEGATION_KIND),
+            server_principal: Some(DFS_NAMENODE_KERBEROS_PRINCIPAL_KEY),
         };
         &PROTOCOL_INFO
     }
@@ -24,12 +32,199 @@ impl RpcProtocol for ClientNamenodeProtocolPB {
 
 macro_rules! client_namenode_protocol_method {
     ($method:ident, $req_type:ident, $res_type:ident) => {
+        client_namenode_protocol_method!($method, stringify!($method), $req_type, $res_type);
+    };
+    ($method:ident, $method_name:expr, $req_type:ident, $res_type:ident) => {
         pub fn $method(&self, req: &$req_type) -> anyhow::Result<$res_type> {
-            self.invoker.invoke(stringify!($method), req)
+            self.invoker.invoke($method_name, req)
+        }
+    };
+}
+
+#[cfg(feature = "async")]
+macro_rules! client_namenode_protocol_async_method {
+    ($method:ident, $method_name:expr, $req_type:ident, $res_type:ident) => {
+        pub async fn $method(&self, req: &$req_type) -> anyhow::Result<$res_type> {
+            self.invoker.invoke_async($method_name, req).await
         }
     };
 }
 
 impl ClientNamenodeProtocolPB {
+    client_namenode_protocol_method!(
+        get_block_locations,
+        "getBlockLocations",
+        GetBlockLocationsRequestProto,
+        GetBlockLocationsResponseProto
+    );
+    client_namenode_protocol_method!(create, CreateRequestProto, CreateResponseProto);
+    client_namenode_protocol_method!(append, AppendRequestProto, AppendResponseProto);
+    client_namenode_protocol_method!(
+        abandon_block,
+        "abandonBlock",
+        AbandonBlockRequestProto,
+        AbandonBlockResponseProto
+    );
+    client_namenode_protocol_method!(
+        add_block,
+        "addBlock",
+        AddBlockRequestProto,
+        AddBlockResponseProto
+    );
+    client_namenode_protocol_method!(
+        get_additional_datanode,
+        "getAdditionalDatanode",
+        GetAdditionalDatanodeRequestProto,
+        GetAdditionalDatanodeResponseProto
+    );
+    client_namenode_protocol_method!(complete, CompleteRequestProto, CompleteResponseProto);
+    client_namenode_protocol_method!(rename, RenameRequestProto, RenameResponseProto);
+    client_namenode_protocol_method!(concat, ConcatRequestProto, ConcatResponseProto);
+    client_namenode_protocol_method!(rename2, Rename2RequestProto, Rename2ResponseProto);
+    client_namenode_protocol_method!(truncate, TruncateRequestProto, TruncateResponseProto);
+    client_namenode_protocol_method!(delete, DeleteRequestProto, DeleteResponseProto);
     client_namenode_protocol_method!(mkdirs, MkdirsRequestProto, MkdirsResponseProto);
+    client_namenode_protocol_method!(
+        set_replication,
+        "setReplication",
+        SetReplicationRequestProto,
+        SetReplicationResponseProto
+    );
+    client_namenode_protocol_method!(
+        set_permission,
+        "setPermission",
+        SetPermissionRequestProto,
+        SetPermissionResponseProto
+    );
+    client_namenode_protocol_method!(
+        set_owner,
+        "setOwner",
+        SetOwnerRequestProto,
+        SetOwnerResponseProto
+    );
+    client_namenode_protocol_method!(
+        get_listing,
+        "getListing",
+        GetListingRequestProto,
+        GetListingResponseProto
+    );
+    client_namenode_protocol_method!(
+        get_batched_listing,
+        "getBatchedListing",
+        GetBatchedListingRequestProto,
+        GetBatchedListingResponseProto
+    );
+    client_namenode_protocol_method!(
+        renew_lease,
+        "renewLease",
+        RenewLeaseRequestProto,
+        RenewLeaseResponseProto
+    );
+    client_namenode_protocol_method!(
+        get_file_info,
+        "getFileInfo",
+        GetFileInfoRequestProto,
+        GetFileInfoResponseProto
+    );
+    client_namenode_protocol_method!(
+        get_located_file_info,
+        "getLocatedFileInfo",
+        GetLocatedFileInfoRequestProto,
+        GetLocatedFileInfoResponseProto
+    );
+    client_namenode_protocol_method!(
+        get_fs_stats,
+        "getFsStats",
+        GetFsStatusRequestProto,
+        GetFsStatsResponseProto
+    );
+    client_namenode_protocol_method!(
+        get_content_summary,
+        "getContentSummary",
+        GetContentSummaryRequestProto,
+        GetContentSummaryResponseProto
+    );
+    client_namenode_protocol_method!(fsync, FsyncRequestProto, FsyncResponseProto);
+    client_namenode_protocol_method!(
+        set_times,
+        "setTimes",
+        SetTimesRequestProto,
+        SetTimesResponseProto
+    );
+    client_namenode_protocol_method!(
+        update_block_for_pipeline,
+        "updateBlockForPipeline",
+        UpdateBlockForPipelineRequestProto,
+        UpdateBlockForPipelineResponseProto
+    );
+    client_namenode_protocol_method!(
+        update_pipeline,
+        "updatePipeline",
+        UpdatePipelineRequestProto,
+        UpdatePipelineResponseProto
+    );
+}
+
+#[cfg(feature = "async")]
+impl ClientNamenodeProtocolPB {
+    client_namenode_protocol_async_method!(
+        rename2_async,
+        "rename2",
+        Rename2RequestProto,
+        Rename2ResponseProto
+    );
+    client_namenode_protocol_async_method!(
+        delete_async,
+        "delete",
+        DeleteRequestProto,
+        DeleteResponseProto
+    );
+    client_namenode_protocol_async_method!(
+        mkdirs_async,
+        "mkdirs",
+        MkdirsRequestProto,
+        MkdirsResponseProto
+    );
+    client_namenode_protocol_async_method!(
+        set_replication_async,
+        "setReplication",
+        SetReplicationRequestProto,
+        SetReplicationResponseProto
+    );
+    client_namenode_protocol_async_method!(
+        set_permission_async,
+        "setPermission",
+        SetPermissionRequestProto,
+        SetPermissionResponseProto
+    );
+    client_namenode_protocol_async_method!(
+        set_owner_async,
+        "setOwner",
+        SetOwnerRequestProto,
+        SetOwnerResponseProto
+    );
+    client_namenode_protocol_async_method!(
+        get_listing_async,
+        "getListing",
+        GetListingRequestProto,
+        GetListingResponseProto
+    );
+    client_namenode_protocol_async_method!(
+        get_file_info_async,
+        "getFileInfo",
+        GetFileInfoRequestProto,
+        GetFileInfoResponseProto
+    );
+    client_namenode_protocol_async_method!(
+        get_content_summary_async,
+        "getContentSummary",
+        GetContentSummaryRequestProto,
+        GetContentSummaryResponseProto
+    );
+    client_namenode_protocol_async_method!(
+        set_times_async,
+        "setTimes",
+        SetTimesRequestProto,
+        SetTimesResponseProto
+    );
 }
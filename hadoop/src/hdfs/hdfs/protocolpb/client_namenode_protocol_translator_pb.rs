@@ -1,7 +1,19 @@
 use super::{ClientNamenodeProtocolPB, PBHelperClient};
+#[cfg(feature = "async")]
+use crate::hdfs::hdfs::protocol::{AsyncClientProtocol, ProtocolFuture};
 use crate::{
-    common::fs::permission::FsCreateModes,
-    hdfs::{hdfs::protocol::ClientProtocol, *},
+    common::fs::{
+        options::Rename,
+        permission::{FsCreateModes, FsPermission},
+        ContentSummary, CreateFlag,
+    },
+    hdfs::{
+        hdfs::protocol::{
+            BatchedDirectoryListing, ClientProtocol, DatanodeInfo, DirectoryListing, ExtendedBlock,
+            HdfsFileStatus, LastBlockWithStatus, LocatedBlock, LocatedBlocks,
+        },
+        *,
+    },
 };
 
 /// This class forwards NN's ClientProtocol calls as RPC calls to the NN server
@@ -18,6 +30,195 @@ impl From<ClientNamenodeProtocolPB> for ClientNamenodeProtocolTranslatorPB {
 }
 
 impl ClientProtocol for ClientNamenodeProtocolTranslatorPB {
+    fn get_block_locations(
+        &self,
+        src: &str,
+        offset: i64,
+        length: i64,
+    ) -> anyhow::Result<Option<LocatedBlocks>> {
+        let req = GetBlockLocationsRequestProto {
+            src: src.to_owned(),
+            offset: offset as u64,
+            length: length as u64,
+        };
+        let res = self.rpc_proxy.get_block_locations(&req)?;
+        Ok(res.locations.as_ref().map(PBHelperClient::convert))
+    }
+
+    fn create(
+        &self,
+        src: &str,
+        create_modes: &FsCreateModes,
+        client_name: &str,
+        flag: &[CreateFlag],
+        create_parent: bool,
+        replication: i16,
+        block_size: i64,
+    ) -> anyhow::Result<Option<HdfsFileStatus>> {
+        // TODO: support encryption zones, the NameNode refuses to create files
+        // in them for clients which support no crypto protocol version
+        let req = CreateRequestProto {
+            src: src.to_owned(),
+            masked: PBHelperClient::convert(create_modes.get_masked()),
+            client_name: client_name.to_owned(),
+            create_flag: PBHelperClient::convert_create_flag(flag),
+            create_parent,
+            replication: replication as u32,
+            block_size: block_size as u64,
+            crypto_protocol_version: vec![],
+            unmasked: Some(PBHelperClient::convert(create_modes.get_unmasked())),
+            ec_policy_name: None,
+            storage_policy: None,
+        };
+        let res = self.rpc_proxy.create(&req)?;
+        Ok(res.fs.as_ref().map(PBHelperClient::convert))
+    }
+
+    fn append(
+        &self,
+        src: &str,
+        client_name: &str,
+        flag: &[CreateFlag],
+    ) -> anyhow::Result<LastBlockWithStatus> {
+        let req = AppendRequestProto {
+            src: src.to_owned(),
+            client_name: client_name.to_owned(),
+            flag: Some(PBHelperClient::convert_create_flag(flag)),
+        };
+        let res = self.rpc_proxy.append(&req)?;
+        let last_block = res.block.as_ref().map(PBHelperClient::convert);
+        let stat = res.stat.as_ref().map(PBHelperClient::convert);
+        Ok(LastBlockWithStatus::new(last_block, stat))
+    }
+
+    fn abandon_block(
+        &self,
+        b: &ExtendedBlock,
+        file_id: u64,
+        src: &str,
+        holder: &str,
+    ) -> anyhow::Result<()> {
+        let req = AbandonBlockRequestProto {
+            b: PBHelperClient::convert(b),
+            src: src.to_owned(),
+            holder: holder.to_owned(),
+            file_id: Some(file_id),
+        };
+        self.rpc_proxy.abandon_block(&req)?;
+        Ok(())
+    }
+
+    fn add_block(
+        &self,
+        src: &str,
+        client_name: &str,
+        previous: Option<&ExtendedBlock>,
+        exclude_nodes: &[DatanodeInfo],
+        file_id: u64,
+    ) -> anyhow::Result<LocatedBlock> {
+        let req = AddBlockRequestProto {
+            src: src.to_owned(),
+            client_name: client_name.to_owned(),
+            previous: previous.map(PBHelperClient::convert),
+            exclude_nodes: exclude_nodes.iter().map(PBHelperClient::convert).collect(),
+            file_id: Some(file_id),
+            favored_nodes: vec![],
+            flags: vec![],
+        };
+        Ok(PBHelperClient::convert(
+            &self.rpc_proxy.add_block(&req)?.block,
+        ))
+    }
+
+    fn get_additional_datanode(
+        &self,
+        src: &str,
+        file_id: u64,
+        blk: &ExtendedBlock,
+        existings: &[DatanodeInfo],
+        existing_storage_ids: &[String],
+        excludes: &[DatanodeInfo],
+        num_additional_nodes: i32,
+        client_name: &str,
+    ) -> anyhow::Result<LocatedBlock> {
+        let req = GetAdditionalDatanodeRequestProto {
+            src: src.to_owned(),
+            file_id: Some(file_id),
+            blk: PBHelperClient::convert(blk),
+            existings: existings.iter().map(PBHelperClient::convert).collect(),
+            existing_storage_uuids: existing_storage_ids.to_vec(),
+            excludes: excludes.iter().map(PBHelperClient::convert).collect(),
+            num_additional_nodes: num_additional_nodes as u32,
+            client_name: client_name.to_owned(),
+        };
+        Ok(PBHelperClient::convert(
+            &self.rpc_proxy.get_additional_datanode(&req)?.block,
+        ))
+    }
+
+    fn complete(
+        &self,
+        src: &str,
+        client_name: &str,
+        last: Option<&ExtendedBlock>,
+        file_id: u64,
+    ) -> anyhow::Result<bool> {
+        let req = CompleteRequestProto {
+            src: src.to_owned(),
+            client_name: client_name.to_owned(),
+            last: last.map(PBHelperClient::convert),
+            file_id: Some(file_id),
+        };
+        Ok(self.rpc_proxy.complete(&req)?.result)
+    }
+
+    fn rename(&self, src: &str, dst: &str) -> anyhow::Result<bool> {
+        let req = RenameRequestProto {
+            src: src.to_owned(),
+            dst: dst.to_owned(),
+        };
+        Ok(self.rpc_proxy.rename(&req)?.result)
+    }
+
+    fn concat(&self, trg: &str, srcs: &[String]) -> anyhow::Result<()> {
+        let req = ConcatRequestProto {
+            trg: trg.to_owned(),
+            srcs: srcs.to_vec(),
+        };
+        self.rpc_proxy.concat(&req)?;
+        Ok(())
+    }
+
+    fn rename2(&self, src: &str, dst: &str, options: &[Rename]) -> anyhow::Result<()> {
+        let overwrite = options.contains(&Rename::Overwrite);
+        let to_trash = options.contains(&Rename::ToTrash);
+        let req = Rename2RequestProto {
+            src: src.to_owned(),
+            dst: dst.to_owned(),
+            overwrite_dest: overwrite,
+            move_to_trash: Some(to_trash),
+        };
+        self.rpc_proxy.rename2(&req)?;
+        Ok(())
+    }
+
+    fn truncate(&self, src: &str, new_length: i64, client_name: &str) -> anyhow::Result<bool> {
+        let req = TruncateRequestProto {
+            src: src.to_owned(),
+            new_length: new_length as u64,
+            client_name: client_name.to_owned(),
+        };
+        Ok(self.rpc_proxy.truncate(&req)?.result)
+    }
+
+    fn delete(&self, src: &str, recursive: bool) -> anyhow::Result<bool> {
+        let req = DeleteRequestProto {
+            src: src.to_owned(),
+            recursive,
+        };
+        Ok(self.rpc_proxy.delete(&req)?.result)
+    }
+
     fn mkdirs(
         &self,
         src: &str,
@@ -35,4 +236,473 @@ impl ClientProtocol for ClientNamenodeProtocolTranslatorPB {
 
         Ok(self.rpc_proxy.mkdirs(&req)?.result)
     }
+
+    fn set_replication(&self, src: &str, replication: i16) -> anyhow::Result<bool> {
+        let req = SetReplicationRequestProto {
+            src: src.to_owned(),
+            replication: replication as u32,
+        };
+        Ok(self.rpc_proxy.set_replication(&req)?.result)
+    }
+
+    fn set_permission(&self, src: &str, permission: &FsPermission) -> anyhow::Result<()> {
+        let req = SetPermissionRequestProto {
+            src: src.to_owned(),
+            permission: PBHelperClient::convert(permission),
+        };
+        self.rpc_proxy.set_permission(&req)?;
+        Ok(())
+    }
+
+    fn set_owner(
+        &self,
+        src: &str,
+        username: Option<&str>,
+        groupname: Option<&str>,
+    ) -> anyhow::Result<()> {
+        let req = SetOwnerRequestProto {
+            src: src.to_owned(),
+            username: username.map(str::to_owned),
+            groupname: groupname.map(str::to_owned),
+        };
+        self.rpc_proxy.set_owner(&req)?;
+        Ok(())
+    }
+
+    fn get_listing(
+        &self,
+        src: &str,
+        start_after: &[u8],
+        need_location: bool,
+    ) -> anyhow::Result<Option<DirectoryListing>> {
+        let req = GetListingRequestProto {
+            src: src.to_owned(),
+            start_after: start_after.to_vec(),
+            need_location,
+        };
+        let res = self.rpc_proxy.get_listing(&req)?;
+        Ok(res.dir_list.as_ref().map(PBHelperClient::convert))
+    }
+
+    fn get_batched_listing(
+        &self,
+        srcs: &[String],
+        start_after: &[u8],
+        need_location: bool,
+    ) -> anyhow::Result<BatchedDirectoryListing> {
+        let req = GetBatchedListingRequestProto {
+            paths: srcs.to_vec(),
+            start_after: start_after.to_vec(),
+            need_location,
+        };
+        Ok(PBHelperClient::convert(
+            &self.rpc_proxy.get_batched_listing(&req)?,
+        ))
+    }
+
+    fn renew_lease(&self, client_name: &str) -> anyhow::Result<()> {
+        let req = RenewLeaseRequestProto {
+            client_name: client_name.to_owned(),
+            namespaces: vec![],
+        };
+        self.rpc_proxy.renew_lease(&req)?;
+        Ok(())
+    }
+
+    fn get_file_info(&self, src: &str) -> anyhow::Result<Option<HdfsFileStatus>> {
+        let req = GetFileInfoRequestProto {
+            src: src.to_owned(),
+        };
+        let res = self.rpc_proxy.get_file_info(&req)?;
+        Ok(res.fs.as_ref().map(PBHelperClient::convert))
+    }
+
+    fn get_located_file_info(
+        &self,
+        src: &str,
+        need_block_token: bool,
+    ) -> anyhow::Result<Option<HdfsFileStatus>> {
+        let req = GetLocatedFileInfoRequestProto {
+            src: Some(src.to_owned()),
+            need_block_token: Some(need_block_token),
+        };
+        let res = self.rpc_proxy.get_located_file_info(&req)?;
+        Ok(res.fs.as_ref().map(PBHelperClient::convert))
+    }
+
+    fn get_stats(&self) -> anyhow::Result<Vec<i64>> {
+        let res = self.rpc_proxy.get_fs_stats(&GetFsStatusRequestProto {})?;
+        Ok(vec![
+            res.capacity as i64,
+            res.used as i64,
+            res.remaining as i64,
+            res.under_replicated as i64,
+            res.corrupt_blocks as i64,
+            res.missing_blocks as i64,
+            res.missing_repl_one_blocks.unwrap_or_default() as i64,
+            res.blocks_in_future.unwrap_or_default() as i64,
+            res.pending_deletion_blocks.unwrap_or_default() as i64,
+        ])
+    }
+
+    fn get_content_summary(&self, path: &str) -> anyhow::Result<ContentSummary> {
+        let req = GetContentSummaryRequestProto {
+            path: path.to_owned(),
+        };
+        Ok(PBHelperClient::convert(
+            &self.rpc_proxy.get_content_summary(&req)?.summary,
+        ))
+    }
+
+    fn fsync(
+        &self,
+        src: &str,
+        file_id: u64,
+        client: &str,
+        last_block_length: i64,
+    ) -> anyhow::Result<()> {
+        let req = FsyncRequestProto {
+            src: src.to_owned(),
+            client: client.to_owned(),
+            last_block_length: Some(last_block_length),
+            file_id: Some(file_id),
+        };
+        self.rpc_proxy.fsync(&req)?;
+        Ok(())
+    }
+
+    fn set_times(&self, src: &str, mtime: i64, atime: i64) -> anyhow::Result<()> {
+        let req = SetTimesRequestProto {
+            src: src.to_owned(),
+            mtime: mtime as u64,
+            atime: atime as u64,
+        };
+        self.rpc_proxy.set_times(&req)?;
+        Ok(())
+    }
+
+    fn update_block_for_pipeline(
+        &self,
+        block: &ExtendedBlock,
+        client_name: &str,
+    ) -> anyhow::Result<LocatedBlock> {
+        let req = UpdateBlockForPipelineRequestProto {
+            block: PBHelperClient::convert(block),
+            client_name: client_name.to_owned(),
+        };
+        Ok(PBHelperClient::convert(
+            &self.rpc_proxy.update_block_for_pipeline(&req)?.block,
+        ))
+    }
+
+    fn update_pipeline(
+        &self,
+        client_name: &str,
+        old_block: &ExtendedBlock,
+        new_block: &ExtendedBlock,
+        new_nodes: &[DatanodeInfo],
+        new_storage_ids: &[String],
+    ) -> anyhow::Result<()> {
+        let req = UpdatePipelineRequestProto {
+            client_name: client_name.to_owned(),
+            old_block: PBHelperClient::convert(old_block),
+            new_block: PBHelperClient::convert(new_block),
+            new_nodes: new_nodes
+                .iter()
+                .map(|n| PBHelperClient::convert(&**n))
+                .collect(),
+            storage_i_ds: new_storage_ids.to_vec(),
+        };
+        self.rpc_proxy.update_pipeline(&req)?;
+        Ok(())
+    }
+}
+
+/// The asynchronous calls, the returned futures resolve to the responses of
+/// the NN server.
+#[cfg(feature = "async")]
+impl AsyncClientProtocol for ClientNamenodeProtocolTranslatorPB {
+    fn rename2_async<'a>(
+        &'a self,
+        src: &'a str,
+        dst: &'a str,
+        options: &'a [Rename],
+    ) -> ProtocolFuture<'a, ()> {
+        Box::pin(async move {
+            let overwrite = options.contains(&Rename::Overwrite);
+            let to_trash = options.contains(&Rename::ToTrash);
+            let req = Rename2RequestProto {
+                src: src.to_owned(),
+                dst: dst.to_owned(),
+                overwrite_dest: overwrite,
+                move_to_trash: Some(to_trash),
+            };
+            self.rpc_proxy.rename2_async(&req).await?;
+            Ok(())
+        })
+    }
+
+    fn delete_async<'a>(&'a self, src: &'a str, recursive: bool) -> ProtocolFuture<'a, bool> {
+        Box::pin(async move {
+            let req = DeleteRequestProto {
+                src: src.to_owned(),
+                recursive,
+            };
+            Ok(self.rpc_proxy.delete_async(&req).await?.result)
+        })
+    }
+
+    fn mkdirs_async<'a>(
+        &'a self,
+        src: &'a str,
+        create_modes: &'a FsCreateModes,
+        create_parent: bool,
+    ) -> ProtocolFuture<'a, bool> {
+        Box::pin(async move {
+            let req = MkdirsRequestProto {
+                src: src.to_owned(),
+                masked: PBHelperClient::convert(create_modes.get_masked()),
+                create_parent,
+                unmasked: Some(PBHelperClient::convert(create_modes.get_unmasked())),
+            };
+            Ok(self.rpc_proxy.mkdirs_async(&req).await?.result)
+        })
+    }
+
+    fn set_replication_async<'a>(
+        &'a self,
+        src: &'a str,
+        replication: i16,
+    ) -> ProtocolFuture<'a, bool> {
+        Box::pin(async move {
+            let req = SetReplicationRequestProto {
+                src: src.to_owned(),
+                replication: replication as u32,
+            };
+            Ok(self.rpc_proxy.set_replication_async(&req).await?.result)
+        })
+    }
+
+    fn set_permission_async<'a>(
+        &'a self,
+        src: &'a str,
+        permission: &'a FsPermission,
+    ) -> ProtocolFuture<'a, ()> {
+        Box::pin(async move {
+            let req = SetPermissionRequestProto {
+                src: src.to_owned(),
+                permission: PBHelperClient::convert(permission),
+            };
+            self.rpc_proxy.set_permission_async(&req).await?;
+            Ok(())
+        })
+    }
+
+    fn set_owner_async<'a>(
+        &'a self,
+        src: &'a str,
+        username: Option<&'a str>,
+        groupname: Option<&'a str>,
+    ) -> ProtocolFuture<'a, ()> {
+        Box::pin(async move {
+            let req = SetOwnerRequestProto {
+                src: src.to_owned(),
+                username: username.map(str::to_owned),
+                groupname: groupname.map(str::to_owned),
+            };
+            self.rpc_proxy.set_owner_async(&req).await?;
+            Ok(())
+        })
+    }
+
+    fn get_listing_async<'a>(
+        &'a self,
+        src: &'a str,
+        start_after: &'a [u8],
+        need_location: bool,
+    ) -> ProtocolFuture<'a, Option<DirectoryListing>> {
+        Box::pin(async move {
+            let req = GetListingRequestProto {
+                src: src.to_owned(),
+                start_after: start_after.to_vec(),
+                need_location,
+            };
+            let res = self.rpc_proxy.get_listing_async(&req).await?;
+            Ok(res.dir_list.as_ref().map(PBHelperClient::convert))
+        })
+    }
+
+    fn get_file_info_async<'a>(
+        &'a self,
+        src: &'a str,
+    ) -> ProtocolFuture<'a, Option<HdfsFileStatus>> {
+        Box::pin(async move {
+            let req = GetFileInfoRequestProto {
+                src: src.to_owned(),
+            };
+            let res = self.rpc_proxy.get_file_info_async(&req).await?;
+            Ok(res.fs.as_ref().map(PBHelperClient::convert))
+        })
+    }
+
+    fn get_content_summary_async<'a>(
+        &'a self,
+        path: &'a str,
+    ) -> ProtocolFuture<'a, ContentSummary> {
+        Box::pin(async move {
+            let req = GetContentSummaryRequestProto {
+                path: path.to_owned(),
+            };
+            Ok(PBHelperClient::convert(
+                &self
+                    .rpc_proxy
+                    .get_content_summary_async(&req)
+                    .await?
+                    .summary,
+            ))
+        })
+    }
+
+    fn set_times_async<'a>(
+        &'a self,
+        src: &'a str,
+        mtime: i64,
+        atime: i64,
+    ) -> ProtocolFuture<'a, ()> {
+        Box::pin(async move {
+            let req = SetTimesRequestProto {
+                src: src.to_owned(),
+                mtime: mtime as u64,
+                atime: atime as u64,
+            };
+            self.rpc_proxy.set_times_async(&req).await?;
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::{
+        common::{
+            conf::Configuration, rpc_response_header_proto::RpcStatusProto,
+            security::UserGroupInformation, RequestHeaderProto, RpcRequestHeaderProto,
+            RpcResponseHeaderProto, TokenProto,
+        },
+        hdfs::hdfs::NameNodeProxiesClient,
+    };
+    use prost::Message;
+    use std::{
+        io::{Read, Write},
+        net::{SocketAddr, TcpListener, TcpStream},
+        thread,
+    };
+
+    fn read_frame(stream: &mut TcpStream) -> anyhow::Result<Vec<u8>> {
+        let mut len = [0; 4];
+        stream.read_exact(&mut len)?;
+        let mut frame = vec![0; i32::from_be_bytes(len) as usize];
+        stream.read_exact(&mut frame)?;
+        Ok(frame)
+    }
+
+    /// Start a NameNode serving one connection, answering every call with
+    /// `handler`, which gets the method name and the length-delimited request
+    /// and returns the length-delimited response.
+    pub(crate) fn start_namenode(
+        handler: impl Fn(&str, &[u8]) -> anyhow::Result<Vec<u8>> + Send + 'static,
+    ) -> anyhow::Result<SocketAddr> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let addr = listener.local_addr()?;
+        thread::spawn(move || -> anyhow::Result<()> {
+            let (mut stream, _) = listener.accept()?;
+            // "hrpc", version, service class and auth protocol
+            stream.read_exact(&mut [0; 7])?;
+            // the connection context
+            read_frame(&mut stream)?;
+            loop {
+                let frame = read_frame(&mut stream)?;
+                let mut payload = &frame[..];
+                let rpc_header = RpcRequestHeaderProto::decode_length_delimited(&mut payload)?;
+                let header = RequestHeaderProto::decode_length_delimited(&mut payload)?;
+                let response = RpcResponseHeaderProto {
+                    call_id: rpc_header.call_id as u32,
+                    status: RpcStatusProto::Success as i32,
+                    ..Default::default()
+                };
+                let mut buf = response.encode_length_delimited_to_vec();
+                buf.extend(handler(&header.method_name, payload)?);
+                stream.write_all(&(buf.len() as i32).to_be_bytes())?;
+                stream.write_all(&buf)?;
+            }
+        });
+        Ok(addr)
+    }
+
+    /// Connect to the NameNode at `addr` with a translator without retries.
+    pub(crate) fn create_translator(addr: &SocketAddr) -> anyhow::Result<Box<dyn ClientProtocol>> {
+        NameNodeProxiesClient::create_non_ha_proxy_with_client_protocol(
+            addr,
+            &Configuration::new(),
+            &UserGroupInformation::get_current_user()?,
+            false,
+            None,
+        )
+    }
+
+    #[test]
+    fn test_get_located_file_info() -> anyhow::Result<()> {
+        let addr = start_namenode(|method, mut req| {
+            assert_eq!(method, "getLocatedFileInfo");
+            let req = GetLocatedFileInfoRequestProto::decode_length_delimited(&mut req)?;
+            let fs = match req.src.as_deref() {
+                Some("/f") => Some(HdfsFileStatusProto {
+                    file_type: hdfs_file_status_proto::FileType::IsFile as i32,
+                    length: 512,
+                    locations: Some(LocatedBlocksProto {
+                        file_length: 512,
+                        blocks: vec![LocatedBlockProto {
+                            b: ExtendedBlockProto {
+                                pool_id: "BP-1".to_owned(),
+                                block_id: 1073741825,
+                                generation_stamp: 1001,
+                                num_bytes: Some(512),
+                            },
+                            // the block token is only set when requested
+                            block_token: TokenProto {
+                                kind: if req.need_block_token() {
+                                    "HDFS_BLOCK_TOKEN".to_owned()
+                                } else {
+                                    String::new()
+                                },
+                                ..Default::default()
+                            },
+                            ..Default::default()
+                        }],
+                        is_last_block_complete: true,
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                }),
+                _ => None,
+            };
+            Ok(GetLocatedFileInfoResponseProto { fs }.encode_length_delimited_to_vec())
+        })?;
+        let namenode = create_translator(&addr)?;
+
+        let status = namenode.get_located_file_info("/f", true)?.unwrap();
+        assert_eq!(status.get_len(), 512);
+        let blocks = status.get_located_blocks().unwrap().get_located_blocks();
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].get_block().get_block_id(), 1073741825);
+        assert_eq!(blocks[0].get_block_token().kind, "HDFS_BLOCK_TOKEN");
+
+        let status = namenode.get_located_file_info("/f", false)?.unwrap();
+        let blocks = status.get_located_blocks().unwrap().get_located_blocks();
+        assert!(blocks[0].get_block_token().kind.is_empty());
+
+        assert!(namenode.get_located_file_info("/missing", true)?.is_none());
+        Ok(())
+    }
 }
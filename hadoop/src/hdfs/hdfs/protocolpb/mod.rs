@@ -1,7 +1,11 @@
+mod client_datanode_protocol_pb;
+mod client_datanode_protocol_translator_pb;
 mod client_namenode_protocol_pb;
 mod client_namenode_protocol_translator_pb;
 mod pb_helper_client;
 
+pub(crate) use client_datanode_protocol_pb::ClientDatanodeProtocolPB;
+pub(crate) use client_datanode_protocol_translator_pb::ClientDatanodeProtocolTranslatorPB;
 pub(crate) use client_namenode_protocol_pb::ClientNamenodeProtocolPB;
 pub(crate) use client_namenode_protocol_translator_pb::ClientNamenodeProtocolTranslatorPB;
 pub use pb_helper_client::PBHelperClient;
@@ -1,3 +1,7 @@
+use crate::{common::fs::CreateFlag, hdfs::CreateFlagProto};
+use anyhow::Error;
+use std::io::{self, Read};
+
 /// Utilities for converting protobuf classes to and from hdfs-client side
 /// implementation classes and other helper utilities to help in dealing with
 /// protobuf.
@@ -7,4 +11,81 @@ impl PBHelperClient {
     pub fn convert<S: Into<T>, T>(value: S) -> T {
         value.into()
     }
+
+    pub fn convert_create_flag(flag: &[CreateFlag]) -> u32 {
+        flag.iter()
+            .map(|f| match f {
+                CreateFlag::Create => CreateFlagProto::Create as u32,
+                CreateFlag::Overwrite => CreateFlagProto::Overwrite as u32,
+                CreateFlag::Append => CreateFlagProto::Append as u32,
+                CreateFlag::LazyPersist => CreateFlagProto::LazyPersist as u32,
+                CreateFlag::NewBlock => CreateFlagProto::NewBlock as u32,
+                CreateFlag::ShouldReplicate => CreateFlagProto::ShouldReplicate as u32,
+                // the remaining flags only matter to the client
+                _ => 0,
+            })
+            .fold(0, |value, f| value | f)
+    }
+
+    /// Read a message prefixed by its varint encoded length from `input`,
+    /// returning the bytes of the message.
+    pub fn vint_prefixed(input: &mut impl Read) -> anyhow::Result<Vec<u8>> {
+        let mut size: u64 = 0;
+        let mut shift = 0;
+        loop {
+            let mut byte = [0; 1];
+            if let Err(e) = input.read_exact(&mut byte) {
+                return Err(if e.kind() == io::ErrorKind::UnexpectedEof {
+                    Error::msg("Unexpected EOF while trying to read response from server")
+                } else {
+                    e.into()
+                });
+            }
+            size |= ((byte[0] & 0x7F) as u64) << shift;
+            if byte[0] & 0x80 == 0 {
+                break;
+            }
+            shift += 7;
+            if shift >= 64 {
+                return Err(Error::msg("Malformed varint"));
+            }
+        }
+        let mut buf = vec![0; size as usize];
+        input.read_exact(&mut buf)?;
+        Ok(buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prost::Message;
+
+    #[test]
+    fn test_vint_prefixed() {
+        let proto = crate::hdfs::ClientReadStatusProto {
+            status: crate::hdfs::Status::ChecksumOk as i32,
+        };
+        let buf = proto.encode_length_delimited_to_vec();
+        let mut input = &buf[..];
+        let msg = PBHelperClient::vint_prefixed(&mut input).unwrap();
+        assert_eq!(
+            proto,
+            crate::hdfs::ClientReadStatusProto::decode(&msg[..]).unwrap()
+        );
+        assert!(input.is_empty());
+        assert!(PBHelperClient::vint_prefixed(&mut input).is_err());
+    }
+
+    #[test]
+    fn test_convert_create_flag() {
+        assert_eq!(
+            3,
+            PBHelperClient::convert_create_flag(&[CreateFlag::Create, CreateFlag::Overwrite])
+        );
+        assert_eq!(
+            36,
+            PBHelperClient::convert_create_flag(&[CreateFlag::Append, CreateFlag::NewBlock])
+        );
+    }
 }